@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nfs_mamont::vfs::{access, file, Credentials};
+
+use crate::clock::{Clock, SystemClock};
+use crate::housekeeper::ExpiryQueue;
+
+/// Identity and requested rights an ACCESS result was computed for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AccessKey {
+    uid: u32,
+    gid: u32,
+    gids: Vec<u32>,
+    mask: u32,
+}
+
+/// Cached results for one file: when each was computed, with the attributes and rights.
+type FileResults = HashMap<AccessKey, (Instant, file::Attr, access::Mask)>;
+
+/// Short-lived cache of ACCESS results keyed by handle, caller identity and mask.
+///
+/// Entries expire after the configured TTL; a TTL of zero disables caching.
+/// Callers must invalidate entries of objects whose mode or owner they change.
+pub struct AccessCache {
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+}
+
+/// Cached results by handle.
+type Entries = HashMap<file::Handle, FileResults>;
+
+#[derive(Default)]
+struct State {
+    entries: Entries,
+    expiry: ExpiryQueue<(file::Handle, AccessKey)>,
+}
+
+impl std::fmt::Debug for AccessCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cached = self.state.lock().map_or(0, |state| state.entries.len());
+        f.debug_struct("AccessCache").field("ttl", &self.ttl).field("cached", &cached).finish()
+    }
+}
+
+impl Default for AccessCache {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl AccessCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, clock: Arc::new(SystemClock), state: Mutex::new(State::default()) }
+    }
+
+    /// Ages entries by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the cached attributes of `file` and the rights granted to `cred` for
+    /// `mask`, unless they are missing or expired.
+    pub fn get(
+        &self,
+        file: &file::Handle,
+        cred: &Credentials,
+        mask: access::Mask,
+    ) -> Option<(file::Attr, access::Mask)> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let key = Self::key(cred, mask);
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let results = state.entries.get_mut(file)?;
+        match results.get(&key) {
+            Some((cached_at, attr, granted))
+                if now.saturating_duration_since(*cached_at) < self.ttl =>
+            {
+                Some((attr.clone(), *granted))
+            }
+            Some(_) => {
+                results.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Caches `granted` as the rights of `cred` for `mask` on `file` with attributes `attr`.
+    pub fn insert(
+        &self,
+        file: &file::Handle,
+        cred: &Credentials,
+        mask: access::Mask,
+        attr: &file::Attr,
+        granted: access::Mask,
+    ) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let key = Self::key(cred, mask);
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let State { entries, expiry } = &mut *state;
+        entries.entry(file.clone()).or_default().insert(key.clone(), (now, attr.clone(), granted));
+        let evicted = expiry.push(now, (file.clone(), key), |at, (file, key)| {
+            Self::current(entries, at, file, key)
+        });
+        for (file, key) in &evicted {
+            Self::remove(entries, file, key);
+        }
+    }
+
+    /// Drops all cached results for `file`.
+    pub fn invalidate(&self, file: &file::Handle) {
+        self.state.lock().unwrap().entries.remove(file);
+    }
+
+    /// Drops all cached results.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.expiry.clear();
+    }
+
+    /// Drops up to `budget` expired results, returning how many were dropped.
+    pub fn expire(&self, budget: usize) -> usize {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let State { entries, expiry } = &mut *state;
+        let expired = expiry.pop_expired(now, self.ttl, budget, |at, (file, key)| {
+            Self::current(entries, at, file, key)
+        });
+        for (file, key) in &expired {
+            Self::remove(entries, file, key);
+        }
+        expired.len()
+    }
+
+    /// Returns the number of cached results, expired or not.
+    pub fn entry_count(&self) -> usize {
+        self.state.lock().unwrap().entries.values().map(HashMap::len).sum()
+    }
+
+    /// Returns whether the result for `key` on `file` was cached at `cached_at`.
+    fn current(
+        entries: &Entries,
+        cached_at: &Instant,
+        file: &file::Handle,
+        key: &AccessKey,
+    ) -> bool {
+        let result = entries.get(file).and_then(|results| results.get(key));
+        matches!(result, Some((at, _, _)) if at == cached_at)
+    }
+
+    fn remove(entries: &mut Entries, file: &file::Handle, key: &AccessKey) {
+        if let Some(results) = entries.get_mut(file) {
+            results.remove(key);
+            if results.is_empty() {
+                entries.remove(file);
+            }
+        }
+    }
+
+    fn key(cred: &Credentials, mask: access::Mask) -> AccessKey {
+        AccessKey { uid: cred.uid, gid: cred.gid, gids: cred.gids.clone(), mask: mask.bits() }
+    }
+}
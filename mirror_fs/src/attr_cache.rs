@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nfs_mamont::vfs::file;
+
+use crate::clock::{Clock, SystemClock};
+use crate::housekeeper::ExpiryQueue;
+
+/// Short-lived cache of file attributes keyed by handle.
+///
+/// Entries expire after the configured TTL; a TTL of zero disables caching.
+/// Callers must invalidate entries of objects they modify.
+pub struct AttrCache {
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+}
+
+/// Cached attributes by handle, with the time they were cached.
+type Entries = HashMap<file::Handle, (Instant, file::Attr)>;
+
+#[derive(Default)]
+struct State {
+    entries: Entries,
+    expiry: ExpiryQueue<file::Handle>,
+}
+
+impl std::fmt::Debug for AttrCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cached = self.state.lock().map_or(0, |state| state.entries.len());
+        f.debug_struct("AttrCache").field("ttl", &self.ttl).field("cached", &cached).finish()
+    }
+}
+
+impl Default for AttrCache {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl AttrCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, clock: Arc::new(SystemClock), state: Mutex::new(State::default()) }
+    }
+
+    /// Ages entries by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the cached attributes of `file`, unless they are missing or expired.
+    pub fn get(&self, file: &file::Handle) -> Option<file::Attr> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        match state.entries.get(file) {
+            Some((cached_at, attr)) if now.saturating_duration_since(*cached_at) < self.ttl => {
+                Some(attr.clone())
+            }
+            Some(_) => {
+                state.entries.remove(file);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Caches `attr` as the current attributes of `file`.
+    pub fn insert(&self, file: &file::Handle, attr: &file::Attr) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let State { entries, expiry } = &mut *state;
+        entries.insert(file.clone(), (now, attr.clone()));
+        let evicted = expiry.push(now, file.clone(), |at, file| Self::current(entries, at, file));
+        for file in evicted {
+            entries.remove(&file);
+        }
+    }
+
+    /// Drops the cached attributes of `file`.
+    pub fn invalidate(&self, file: &file::Handle) {
+        self.state.lock().unwrap().entries.remove(file);
+    }
+
+    /// Drops all cached attributes, e.g. after an operation changing link counts
+    /// of objects whose handles are not at hand.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.expiry.clear();
+    }
+
+    /// Drops up to `budget` expired entries, returning how many were dropped.
+    pub fn expire(&self, budget: usize) -> usize {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let State { entries, expiry } = &mut *state;
+        let expired =
+            expiry.pop_expired(now, self.ttl, budget, |at, file| Self::current(entries, at, file));
+        for file in &expired {
+            entries.remove(file);
+        }
+        expired.len()
+    }
+
+    /// Returns the number of cached entries, expired or not.
+    pub fn entry_count(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns whether the entry of `file` was cached at `cached_at`.
+    fn current(entries: &Entries, cached_at: &Instant, file: &file::Handle) -> bool {
+        matches!(entries.get(file), Some((at, _)) if at == cached_at)
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of time for cache expiry and server-assigned timestamps.
+///
+/// [`MirrorFS`](crate::fs::MirrorFS) reads the time only through its clock, so tests
+/// can substitute a [`MockClock`] and move time forward without sleeping.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// Returns the monotonic time, used to age cache entries.
+    fn now(&self) -> Instant;
+
+    /// Returns the wall-clock time, used for timestamps set to the server time.
+    fn system_time(&self) -> SystemTime;
+}
+
+/// Clock reading the time of the operating system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock standing still until [`MockClock::advance`] moves it forward.
+///
+/// Starts at the time of its creation, so the wall-clock times it reports stay plausible.
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    system_start: SystemTime,
+    elapsed: Mutex<Duration>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now(), system_start: SystemTime::now(), elapsed: Mutex::default() }
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock().unwrap() += by;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.system_start + *self.elapsed.lock().unwrap()
+    }
+}
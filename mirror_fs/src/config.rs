@@ -1,21 +1,72 @@
-use std::num::NonZeroUsize;
+use std::collections::HashMap;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
 
 use serde::Deserialize;
 
+use nfs_mamont::vfs::credentials::{ANON_GID, ANON_UID};
+use nfs_mamont::vfs::file;
+use nfs_mamont::vfs::IdMapPolicy;
+use nfs_mamont::{
+    AnonymousAccess, Keepalive, QueueCapacity, RateLimit, SocketConfig,
+    DEFAULT_REPLY_QUEUE_CAPACITY, DEFAULT_REQUEST_QUEUE_CAPACITY,
+};
+
+use crate::fs::{CookieVerifierPolicy, CreateModes, Durability};
+use crate::housekeeper::{Housekeeper, DEFAULT_HOUSEKEEPING_BUDGET, DEFAULT_HOUSEKEEPING_INTERVAL};
+use crate::write_buffer::WriteBufferLimits;
+
 const DEFAULT_VFS_POOL_SIZE: usize = 10;
 const MAX_EXPORTS_COUNT: usize = 256;
 const DEFAULT_READ_BUFFER_SIZE: usize = 64 * 1024;
 const DEFAULT_READ_BUFFER_COUNT: usize = 2048;
 const DEFAULT_WRITE_BUFFER_SIZE: usize = 64 * 1024;
 const DEFAULT_WRITE_BUFFER_COUNT: usize = 2048;
+const DEFAULT_MAX_CONCURRENT_OPS: usize = 64;
+const DEFAULT_WRITE_BUFFER_FLUSH_BYTES: usize = 1024 * 1024;
+const DEFAULT_WRITE_BUFFER_MAX_BYTES: usize = 64 * 1024 * 1024;
+/// Linux defaults of `tcp_keepalive_intvl` and `tcp_keepalive_probes`.
+const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(75);
+const DEFAULT_KEEPALIVE_RETRIES: u32 = 9;
 
 #[derive(Debug)]
 pub struct Config {
     pub allocator: AllocatorConfig,
     pub vfs_pool_size: NonZeroUsize,
+    pub queue_capacity: QueueCapacity,
+    pub rate_limit: RateLimit,
+    /// Log calls refused for security reasons with [`nfs_mamont::audit::TracingAuditSink`].
+    pub audit_log: bool,
+    /// Fail replies carrying malformed attributes instead of sending them, to catch
+    /// backend bugs.
+    pub strict_attrs: bool,
+    /// Time an NFS procedure may take before it is answered with `NFS3ERR_JUKEBOX`.
+    pub proc_timeout: Option<Duration>,
     pub export_root: PathBuf,
     pub exports: Vec<ExportConfig>,
+    pub case_insensitive: bool,
+    pub id_map: IdMapPolicy,
+    /// Identity of AUTH_NONE callers, or their rejection on a `secure` export.
+    pub anonymous_access: AnonymousAccess,
+    pub durability: Durability,
+    pub cookie_verifiers: CookieVerifierPolicy,
+    pub attr_cache_ttl: Duration,
+    pub access_cache_ttl: Duration,
+    pub negative_lookup_ttl: Duration,
+    /// Background expiry of the attribute, ACCESS and negative lookup caches.
+    pub housekeeping: Housekeeper,
+    /// TCP options of the listener and of accepted connections.
+    pub socket: SocketConfig,
+    pub read_dir_plus_max_handles: Option<NonZeroU32>,
+    /// Alignment of READ and WRITE transfers advertised to clients.
+    pub transfer_multiple: Option<NonZeroU32>,
+    pub time_delta: Option<file::Time>,
+    /// Epoch naming the write verifier, shared by servers that clients fail over between.
+    pub write_epoch: Option<u64>,
+    pub write_buffer: Option<WriteBufferLimits>,
+    /// Journal keeping file handles valid across restarts.
+    pub handle_registry: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -24,12 +75,25 @@ pub struct AllocatorConfig {
     pub read_buffer_count: NonZeroUsize,
     pub write_buffer_size: NonZeroUsize,
     pub write_buffer_count: NonZeroUsize,
+    /// Sizes both pools for the negotiated transfer size instead of the buffer settings.
+    pub transfer: Option<TransferConfig>,
+}
+
+/// Largest READ/WRITE transfer the server advertises and how many may be in flight.
+#[derive(Debug, Clone, Copy)]
+pub struct TransferConfig {
+    pub max_transfer: NonZeroU32,
+    pub max_concurrent_ops: NonZeroUsize,
 }
 
 #[derive(Debug)]
 pub struct ExportConfig {
     pub local_path: PathBuf,
     pub mount_path: String,
+    /// File system id reported for files of the export instead of their device id.
+    pub fsid: Option<u64>,
+    /// Modes of objects created in the export without a mode from the client.
+    pub create_modes: CreateModes,
 }
 
 impl Default for Config {
@@ -37,8 +101,29 @@ impl Default for Config {
         Self {
             allocator: AllocatorConfig::default(),
             vfs_pool_size: NonZeroUsize::new(DEFAULT_VFS_POOL_SIZE).unwrap(),
+            queue_capacity: QueueCapacity::default(),
+            rate_limit: RateLimit::default(),
+            audit_log: false,
+            strict_attrs: false,
+            proc_timeout: None,
             export_root: PathBuf::new(),
             exports: Vec::with_capacity(MAX_EXPORTS_COUNT),
+            case_insensitive: false,
+            id_map: IdMapPolicy::NoSquash,
+            anonymous_access: AnonymousAccess::default(),
+            durability: Durability::Full,
+            cookie_verifiers: CookieVerifierPolicy::Listing,
+            attr_cache_ttl: Duration::ZERO,
+            access_cache_ttl: Duration::ZERO,
+            negative_lookup_ttl: Duration::ZERO,
+            housekeeping: Housekeeper::default(),
+            socket: SocketConfig::default(),
+            read_dir_plus_max_handles: None,
+            transfer_multiple: None,
+            time_delta: None,
+            write_epoch: None,
+            write_buffer: None,
+            handle_registry: None,
         }
     }
 }
@@ -50,6 +135,7 @@ impl Default for AllocatorConfig {
             read_buffer_count: NonZeroUsize::new(DEFAULT_READ_BUFFER_COUNT).unwrap(),
             write_buffer_size: NonZeroUsize::new(DEFAULT_WRITE_BUFFER_SIZE).unwrap(),
             write_buffer_count: NonZeroUsize::new(DEFAULT_WRITE_BUFFER_COUNT).unwrap(),
+            transfer: None,
         }
     }
 }
@@ -81,12 +167,104 @@ pub fn load_config(path: &Path) -> std::io::Result<Config> {
                 raw_alloc.write_buffer_count.unwrap_or(DEFAULT_WRITE_BUFFER_COUNT),
                 "write_buffer_count",
             )?,
+            transfer: match raw_alloc.max_transfer {
+                Some(max_transfer) => Some(TransferConfig {
+                    max_transfer: NonZeroU32::new(max_transfer)
+                        .ok_or_else(|| invalid_input("max_transfer must be greater than zero"))?,
+                    max_concurrent_ops: non_zero(
+                        raw_alloc.max_concurrent_ops.unwrap_or(DEFAULT_MAX_CONCURRENT_OPS),
+                        "max_concurrent_ops",
+                    )?,
+                }),
+                None => None,
+            },
         },
         None => AllocatorConfig::default(),
     };
 
+    let transfer_multiple = match raw_config.transfer_multiple {
+        Some(multiple) => {
+            let multiple = NonZeroU32::new(multiple)
+                .ok_or_else(|| invalid_input("transfer_multiple must be greater than zero"))?;
+            let max_transfer = allocator.transfer.as_ref().map(|transfer| transfer.max_transfer);
+            if max_transfer.is_some_and(|max| max.get() % multiple.get() != 0) {
+                return Err(invalid_input("max_transfer must be a multiple of transfer_multiple"));
+            }
+            Some(multiple)
+        }
+        None => None,
+    };
+
     let vfs_pool_size =
         non_zero(raw_config.vfs_pool_size.unwrap_or(DEFAULT_VFS_POOL_SIZE), "vfs_pool_size")?;
+    let queue_capacity = QueueCapacity {
+        requests: non_zero(
+            raw_config.request_queue_capacity.unwrap_or(DEFAULT_REQUEST_QUEUE_CAPACITY),
+            "request_queue_capacity",
+        )?,
+        replies: non_zero(
+            raw_config.reply_queue_capacity.unwrap_or(DEFAULT_REPLY_QUEUE_CAPACITY),
+            "reply_queue_capacity",
+        )?,
+    };
+
+    // Limits left out, or set to zero, are off.
+    let rate_limit = match raw_config.rate_limit {
+        Some(raw) => RateLimit {
+            requests_per_sec: raw.requests_per_sec.and_then(NonZeroU32::new),
+            bytes_per_sec: raw.bytes_per_sec.and_then(NonZeroU64::new),
+        },
+        None => RateLimit::default(),
+    };
+
+    let socket = match raw_config.socket {
+        Some(raw) => SocketConfig {
+            nodelay: raw.nodelay.unwrap_or(true),
+            keepalive: match raw.keepalive_idle_ms {
+                Some(idle) => Some(Keepalive {
+                    idle: positive_millis(idle, "socket.keepalive_idle_ms")?,
+                    interval: match raw.keepalive_interval_ms {
+                        Some(ms) => positive_millis(ms, "socket.keepalive_interval_ms")?,
+                        None => DEFAULT_KEEPALIVE_INTERVAL,
+                    },
+                    retries: match raw.keepalive_retries {
+                        Some(0) => {
+                            return Err(invalid_input(
+                                "socket.keepalive_retries must be greater than zero",
+                            ))
+                        }
+                        Some(retries) => retries,
+                        None => DEFAULT_KEEPALIVE_RETRIES,
+                    },
+                }),
+                None => None,
+            },
+            send_buffer_size: raw
+                .send_buffer_size
+                .map(|size| non_zero(size, "socket.send_buffer_size"))
+                .transpose()?,
+            recv_buffer_size: raw
+                .recv_buffer_size
+                .map(|size| non_zero(size, "socket.recv_buffer_size"))
+                .transpose()?,
+            reuse_address: raw.reuse_address.unwrap_or(true),
+        },
+        None => SocketConfig::default(),
+    };
+
+    let housekeeping = match raw_config.housekeeping {
+        Some(raw) => Housekeeper::new(
+            match raw.interval_ms {
+                Some(0) => {
+                    return Err(invalid_input("housekeeping.interval_ms must be greater than zero"))
+                }
+                Some(ms) => Duration::from_millis(ms),
+                None => DEFAULT_HOUSEKEEPING_INTERVAL,
+            },
+            non_zero(raw.budget.unwrap_or(DEFAULT_HOUSEKEEPING_BUDGET), "housekeeping.budget")?,
+        ),
+        None => Housekeeper::default(),
+    };
 
     let raw_exports = raw_config
         .exports
@@ -103,24 +281,130 @@ pub fn load_config(path: &Path) -> std::io::Result<Config> {
     }
 
     let root = resolve_export_root(&raw_exports.root)?;
+    let mut fsids = by_export(raw_exports.fsid)?;
+    let mut file_modes = by_export(raw_exports.file_mode)?;
+    let mut dir_modes = by_export(raw_exports.dir_mode)?;
+    for (export_path, &mode) in file_modes.iter().chain(&dir_modes) {
+        if mode > 0o7777 {
+            return Err(invalid_input(format!(
+                "mode {mode:#o} of export {} has bits beyond 0o7777",
+                export_path.display()
+            )));
+        }
+    }
     let mut exports = Vec::with_capacity(raw_exports.paths.len());
     for export_path in &raw_exports.paths {
         let relative = normalize_export_path(export_path)?;
         let local_path = resolve_export_root(&root.join(&relative))?;
+        if !local_path.starts_with(&root) {
+            return Err(invalid_input(format!(
+                "export {} resolves to {}, outside the export root",
+                relative.display(),
+                local_path.display()
+            )));
+        }
         let mount_path = mount_path_for_export(&relative);
-        exports.push(ExportConfig { local_path, mount_path });
+        let fsid = fsids.remove(&relative);
+        let create_modes = CreateModes {
+            file: file_modes.remove(&relative),
+            directory: dir_modes.remove(&relative),
+        };
+        exports.push(ExportConfig { local_path, mount_path, fsid, create_modes });
+    }
+    let unknown = [
+        ("fsid", fsids.into_keys().next()),
+        ("file_mode", file_modes.into_keys().next()),
+        ("dir_mode", dir_modes.into_keys().next()),
+    ];
+    if let Some((field, Some(path))) = unknown.into_iter().find(|(_, path)| path.is_some()) {
+        return Err(invalid_input(format!("{field} set for unknown export {}", path.display())));
     }
 
     validate_exports(&exports)?;
 
-    Ok(Config { allocator, vfs_pool_size, export_root: root, exports })
+    let anon_uid = raw_exports.anon_uid.unwrap_or(ANON_UID);
+    let anon_gid = raw_exports.anon_gid.unwrap_or(ANON_GID);
+    let id_map = match raw_exports.squash.unwrap_or(RawSquash::None) {
+        RawSquash::None => IdMapPolicy::NoSquash,
+        RawSquash::Root => IdMapPolicy::RootSquash { anon_uid, anon_gid },
+        RawSquash::All => IdMapPolicy::AllSquash { anon_uid, anon_gid },
+    };
+    let proc_timeout = match raw_config.proc_timeout_ms {
+        Some(ms) => Some(positive_millis(ms, "proc_timeout_ms")?),
+        None => None,
+    };
+    let anonymous_access = if raw_exports.secure.unwrap_or(false) {
+        AnonymousAccess::Deny
+    } else {
+        AnonymousAccess::Allow { uid: anon_uid, gid: anon_gid }
+    };
+
+    Ok(Config {
+        allocator,
+        vfs_pool_size,
+        queue_capacity,
+        rate_limit,
+        audit_log: raw_config.audit_log.unwrap_or(false),
+        strict_attrs: raw_config.strict_attrs.unwrap_or(false),
+        proc_timeout,
+        export_root: root,
+        exports,
+        case_insensitive: raw_exports.case_insensitive.unwrap_or(false),
+        id_map,
+        anonymous_access,
+        durability: match raw_config.durability.unwrap_or(RawDurability::Full) {
+            RawDurability::Full => Durability::Full,
+            RawDurability::Data => Durability::DataOnly,
+            RawDurability::None => Durability::None,
+        },
+        cookie_verifiers: match raw_config.cookie_verifier.unwrap_or(RawCookieVerifier::Listing) {
+            RawCookieVerifier::Listing => CookieVerifierPolicy::Listing,
+            RawCookieVerifier::Disabled => CookieVerifierPolicy::Disabled,
+        },
+        attr_cache_ttl: Duration::from_millis(raw_config.attr_cache_ttl_ms.unwrap_or(0)),
+        access_cache_ttl: Duration::from_millis(raw_config.access_cache_ttl_ms.unwrap_or(0)),
+        negative_lookup_ttl: Duration::from_millis(raw_config.negative_lookup_ttl_ms.unwrap_or(0)),
+        housekeeping,
+        socket,
+        read_dir_plus_max_handles: raw_config.read_dir_plus_max_handles.and_then(NonZeroU32::new),
+        transfer_multiple,
+        time_delta: raw_config.time_delta_ns.map(|nanos| file::Time {
+            seconds: u32::try_from(nanos / 1_000_000_000).unwrap_or(u32::MAX),
+            nanos: (nanos % 1_000_000_000) as u32,
+        }),
+        write_epoch: raw_config.write_epoch,
+        write_buffer: raw_config.write_buffer.map(|raw| WriteBufferLimits {
+            flush_bytes: raw.flush_bytes.unwrap_or(DEFAULT_WRITE_BUFFER_FLUSH_BYTES),
+            max_bytes: raw.max_bytes.unwrap_or(DEFAULT_WRITE_BUFFER_MAX_BYTES),
+        }),
+        handle_registry: raw_config.handle_registry,
+    })
 }
 
 #[derive(Deserialize)]
 struct RawConfig {
     allocator: Option<RawAllocatorConfig>,
     vfs_pool_size: Option<usize>,
+    request_queue_capacity: Option<usize>,
+    reply_queue_capacity: Option<usize>,
+    rate_limit: Option<RawRateLimitConfig>,
+    audit_log: Option<bool>,
+    strict_attrs: Option<bool>,
+    proc_timeout_ms: Option<u64>,
     exports: Option<RawExportsConfig>,
+    durability: Option<RawDurability>,
+    cookie_verifier: Option<RawCookieVerifier>,
+    attr_cache_ttl_ms: Option<u64>,
+    access_cache_ttl_ms: Option<u64>,
+    negative_lookup_ttl_ms: Option<u64>,
+    housekeeping: Option<RawHousekeepingConfig>,
+    socket: Option<RawSocketConfig>,
+    read_dir_plus_max_handles: Option<u32>,
+    transfer_multiple: Option<u32>,
+    time_delta_ns: Option<u64>,
+    write_epoch: Option<u64>,
+    write_buffer: Option<RawWriteBufferConfig>,
+    handle_registry: Option<PathBuf>,
 }
 
 #[derive(Deserialize)]
@@ -129,12 +413,78 @@ struct RawAllocatorConfig {
     read_buffer_count: Option<usize>,
     write_buffer_size: Option<usize>,
     write_buffer_count: Option<usize>,
+    max_transfer: Option<u32>,
+    max_concurrent_ops: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct RawRateLimitConfig {
+    requests_per_sec: Option<u32>,
+    bytes_per_sec: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RawHousekeepingConfig {
+    interval_ms: Option<u64>,
+    budget: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct RawSocketConfig {
+    nodelay: Option<bool>,
+    reuse_address: Option<bool>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    keepalive_idle_ms: Option<u64>,
+    keepalive_interval_ms: Option<u64>,
+    keepalive_retries: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RawWriteBufferConfig {
+    flush_bytes: Option<usize>,
+    max_bytes: Option<usize>,
 }
 
 #[derive(Deserialize)]
 struct RawExportsConfig {
     root: PathBuf,
     paths: Vec<PathBuf>,
+    case_insensitive: Option<bool>,
+    squash: Option<RawSquash>,
+    anon_uid: Option<u32>,
+    anon_gid: Option<u32>,
+    /// Rejects AUTH_NONE calls instead of serving them as `anon_uid`/`anon_gid`.
+    secure: Option<bool>,
+    /// File system ids keyed by export path.
+    fsid: Option<HashMap<PathBuf, u64>>,
+    /// Modes of files created without one, keyed by export path.
+    file_mode: Option<HashMap<PathBuf, u32>>,
+    /// Modes of directories created without one, keyed by export path.
+    dir_mode: Option<HashMap<PathBuf, u32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RawSquash {
+    None,
+    Root,
+    All,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RawDurability {
+    Full,
+    Data,
+    None,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RawCookieVerifier {
+    Listing,
+    Disabled,
 }
 
 fn validate_exports(exports: &[ExportConfig]) -> std::io::Result<()> {
@@ -222,6 +572,22 @@ fn invalid_input(message: impl Into<String>) -> std::io::Error {
     std::io::Error::new(std::io::ErrorKind::InvalidInput, message.into())
 }
 
+/// Returns the values of `raw`, keyed by normalized export path.
+fn by_export<T>(raw: Option<HashMap<PathBuf, T>>) -> std::io::Result<HashMap<PathBuf, T>> {
+    let mut values = HashMap::new();
+    for (export_path, value) in raw.unwrap_or_default() {
+        values.insert(normalize_export_path(&export_path)?, value);
+    }
+    Ok(values)
+}
+
+fn positive_millis(ms: u64, field: &str) -> std::io::Result<Duration> {
+    match ms {
+        0 => Err(invalid_input(format!("{field} must be greater than zero"))),
+        ms => Ok(Duration::from_millis(ms)),
+    }
+}
+
 fn non_zero(value: usize, field: &str) -> std::io::Result<NonZeroUsize> {
     NonZeroUsize::new(value)
         .ok_or_else(|| invalid_input(format!("{field} must be greater than zero")))
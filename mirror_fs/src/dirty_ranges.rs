@@ -0,0 +1,91 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::sync::Mutex;
+
+use nfs_mamont::vfs::file;
+
+/// Tracks byte ranges written with [`nfs_mamont::vfs::write::StableHow::Unstable`]
+/// that have not been committed yet.
+///
+/// Ranges of a file are kept sorted and coalesced: overlapping or adjacent
+/// writes collapse into a single range.
+#[derive(Debug, Default)]
+pub struct DirtyRanges {
+    files: Mutex<HashMap<file::Handle, BTreeMap<u64, u64>>>,
+}
+
+impl DirtyRanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `len` bytes at `offset` of `file` as dirty.
+    pub fn record(&self, file: &file::Handle, offset: u64, len: u64) {
+        if len == 0 {
+            return;
+        }
+        let mut start = offset;
+        let mut end = offset.saturating_add(len);
+
+        let mut files = self.files.lock().unwrap();
+        let ranges = files.entry(file.clone()).or_default();
+        let touching: Vec<u64> = ranges
+            .range(..=end)
+            .filter(|(_, &range_end)| range_end >= start)
+            .map(|(&range_start, _)| range_start)
+            .collect();
+        for range_start in touching {
+            let range_end = ranges.remove(&range_start).unwrap();
+            start = start.min(range_start);
+            end = end.max(range_end);
+        }
+        ranges.insert(start, end);
+    }
+
+    /// Removes and returns the parts of dirty ranges of `file` which overlap
+    /// `count` bytes at `offset`. A `count` of `0` means up to the end of file.
+    ///
+    /// Parts of dirty ranges outside the requested window stay recorded.
+    pub fn take(&self, file: &file::Handle, offset: u64, count: u32) -> Vec<Range<u64>> {
+        let window_end = if count == 0 { u64::MAX } else { offset.saturating_add(count as u64) };
+
+        let mut files = self.files.lock().unwrap();
+        let Some(ranges) = files.get_mut(file) else {
+            return Vec::new();
+        };
+        let overlapping: Vec<(u64, u64)> = ranges
+            .range(..window_end)
+            .filter(|(_, &range_end)| range_end > offset)
+            .map(|(&range_start, &range_end)| (range_start, range_end))
+            .collect();
+
+        let mut taken = Vec::with_capacity(overlapping.len());
+        for (range_start, range_end) in overlapping {
+            ranges.remove(&range_start);
+            if range_start < offset {
+                ranges.insert(range_start, offset);
+            }
+            if range_end > window_end {
+                ranges.insert(window_end, range_end);
+            }
+            taken.push(range_start.max(offset)..range_end.min(window_end));
+        }
+        if ranges.is_empty() {
+            files.remove(file);
+        }
+        taken
+    }
+
+    /// Forgets all dirty ranges of `file`, e.g. after the whole file was synced.
+    pub fn clear(&self, file: &file::Handle) {
+        self.files.lock().unwrap().remove(file);
+    }
+
+    /// Returns the dirty ranges of `file` in ascending order.
+    #[cfg(test)]
+    pub fn ranges(&self, file: &file::Handle) -> Vec<Range<u64>> {
+        self.files.lock().unwrap().get(file).map_or_else(Vec::new, |ranges| {
+            ranges.iter().map(|(&start, &end)| start..end).collect()
+        })
+    }
+}
@@ -1,50 +1,80 @@
+use nfs_mamont::vfs;
 use nfs_mamont::vfs::access;
 use nfs_mamont::vfs::file;
 
 use super::MirrorFS;
 
 impl access::Access for MirrorFS {
-    async fn access(&self, args: access::Args) -> Result<access::Success, access::Fail> {
-        let path = match self.path_for_handle(&args.file).await {
-            Ok(path) => path,
-            Err(error) => return Err(access::Fail { error, object_attr: None }),
-        };
-        let meta = match Self::metadata(&path) {
-            Ok(meta) => meta,
-            Err(error) => return Err(access::Fail { error, object_attr: None }),
+    async fn access(
+        &self,
+        cred: &vfs::Credentials,
+        args: access::Args,
+    ) -> Result<access::Success, access::Fail> {
+        if let Some((attr, granted)) = self.access.get(&args.file, cred, args.mask) {
+            return Ok(access::Success { object_attr: Some(attr), access: granted });
+        }
+        let attr = match self.attrs.get(&args.file) {
+            Some(attr) => attr,
+            None => {
+                let path = match self.path_for_handle(&args.file).await {
+                    Ok(path) => path,
+                    Err(error) => return Err(access::Fail { error, object_attr: None }),
+                };
+                match self.cached_attr(&args.file, &path) {
+                    Ok(attr) => attr,
+                    Err(error) => return Err(access::Fail { error, object_attr: None }),
+                }
+            }
         };
-        let attr = Self::attr_from_metadata(&meta);
-        let granted = Self::compute_access_mask(&attr, args.mask);
+        let granted = Self::compute_access_mask(cred, &attr, args.mask);
+        self.access.insert(&args.file, cred, args.mask, &attr, granted);
         Ok(access::Success { object_attr: Some(attr), access: granted })
     }
 }
 
 impl MirrorFS {
-    /// Computes the access mask based on the file's mode bits (owner class).
-    fn compute_access_mask(attr: &file::Attr, requested: access::Mask) -> access::Mask {
-        let mode = attr.mode;
+    /// Computes the access mask granted to `cred` by the file's mode bits.
+    ///
+    /// The owner, group or other class is chosen by comparing the caller's ids with the
+    /// file's owner, like the kernel does for non-root callers. Root is granted everything,
+    /// except that execution still takes at least one execute bit.
+    fn compute_access_mask(
+        cred: &vfs::Credentials,
+        attr: &file::Attr,
+        requested: access::Mask,
+    ) -> access::Mask {
         let is_dir = matches!(attr.file_type, file::Type::Directory);
-        let owner_r = mode & 0o400 != 0;
-        let owner_w = mode & 0o200 != 0;
-        let owner_x = mode & 0o100 != 0;
+        let shift = if cred.uid == attr.uid {
+            6
+        } else if cred.in_group(attr.gid) {
+            3
+        } else {
+            0
+        };
+        let class = (attr.mode >> shift) & 0o7;
+        let root = cred.uid == 0;
+        let readable = root || class & 0o4 != 0;
+        let writable = root || class & 0o2 != 0;
+        let searchable = root || class & 0o1 != 0;
+        let executable = if root { attr.mode & 0o111 != 0 } else { class & 0o1 != 0 };
 
         let mut result = 0u32;
-        if requested.contains(access::Mask::READ) && owner_r {
+        if requested.contains(access::Mask::READ) && readable {
             result |= access::Mask::READ;
         }
-        if requested.contains(access::Mask::LOOKUP) && is_dir && owner_x {
+        if requested.contains(access::Mask::LOOKUP) && is_dir && searchable {
             result |= access::Mask::LOOKUP;
         }
-        if requested.contains(access::Mask::MODIFY) && owner_w {
+        if requested.contains(access::Mask::MODIFY) && writable {
             result |= access::Mask::MODIFY;
         }
-        if requested.contains(access::Mask::EXTEND) && owner_w {
+        if requested.contains(access::Mask::EXTEND) && writable {
             result |= access::Mask::EXTEND;
         }
-        if requested.contains(access::Mask::DELETE) && owner_w {
+        if requested.contains(access::Mask::DELETE) && writable {
             result |= access::Mask::DELETE;
         }
-        if requested.contains(access::Mask::EXECUTE) && owner_x {
+        if requested.contains(access::Mask::EXECUTE) && executable {
             result |= access::Mask::EXECUTE;
         }
         access::Mask::from_wire(result)
@@ -0,0 +1,206 @@
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use num_traits::FromPrimitive;
+
+use nfs_mamont::vfs::acl::{Entry, Mask, Tag};
+use nfs_mamont::vfs::{self, acl, file, get_acl, set_acl};
+
+use super::MirrorFS;
+use crate::io_error::retry_interrupted;
+
+/// Extended attribute holding the access ACL of a file.
+const ACCESS_XATTR: &str = "system.posix_acl_access";
+/// Extended attribute holding the default ACL of a directory.
+const DEFAULT_XATTR: &str = "system.posix_acl_default";
+/// Version in the header of the Linux `posix_acl_xattr` format.
+const XATTR_VERSION: u32 = 2;
+/// Size of one entry of the `posix_acl_xattr` format: tag, perm and id.
+const XATTR_ENTRY_LEN: usize = 8;
+
+impl acl::Acl for MirrorFS {
+    async fn get_acl(&self, args: get_acl::Args) -> Result<get_acl::Success, get_acl::Fail> {
+        let path = match self.path_for_handle(&args.file).await {
+            Ok(path) => path,
+            Err(error) => return Err(get_acl::Fail { error, file_attr: None }),
+        };
+        let attr = match Self::metadata(&path) {
+            Ok(meta) => self.attr_from_metadata(&path, &meta),
+            Err(error) => return Err(get_acl::Fail { error, file_attr: None }),
+        };
+        let lists = read_acl(&path, ACCESS_XATTR).and_then(|access| match attr.file_type {
+            file::Type::Directory => Ok((access, read_acl(&path, DEFAULT_XATTR)?)),
+            _ => Ok((access, None)),
+        });
+        let (access, default) = match lists {
+            Ok(lists) => lists,
+            Err(error) => {
+                return Err(get_acl::Fail {
+                    error: Self::io_error_to_vfs(&error),
+                    file_attr: Some(attr),
+                });
+            }
+        };
+        let access = access.unwrap_or_else(|| acl_from_mode(attr.mode));
+        Ok(get_acl::Success {
+            access: with_owner_ids(access, &attr),
+            default: with_owner_ids(default.unwrap_or_default(), &attr),
+            file_attr: Some(attr),
+            mask: args.mask,
+        })
+    }
+
+    async fn set_acl(
+        &self,
+        cred: &vfs::Credentials,
+        args: set_acl::Args,
+    ) -> Result<set_acl::Success, set_acl::Fail> {
+        let path = match self.path_for_handle(&args.file).await {
+            Ok(path) => path,
+            Err(error) => return Err(set_acl::Fail { error, file_attr: None }),
+        };
+        let attr = match Self::metadata(&path) {
+            Ok(meta) => self.attr_from_metadata(&path, &meta),
+            Err(error) => return Err(set_acl::Fail { error, file_attr: None }),
+        };
+        if cred.uid != 0 && cred.uid != attr.uid {
+            return Err(set_acl::Fail { error: vfs::Error::Permission, file_attr: Some(attr) });
+        }
+
+        let result = Self::apply_acl(&path, &attr, &args);
+        self.attrs.invalidate(&args.file);
+        self.access.invalidate(&args.file);
+        match result {
+            Ok(()) => Ok(set_acl::Success { file_attr: self.file_attr(&path) }),
+            Err(error) => Err(set_acl::Fail { error, file_attr: self.file_attr(&path) }),
+        }
+    }
+}
+
+impl MirrorFS {
+    /// Stores the lists selected by `args.mask`; an access ACL made of the three base
+    /// entries only is applied as mode bits, like `setfacl` does.
+    fn apply_acl(path: &Path, attr: &file::Attr, args: &set_acl::Args) -> Result<(), vfs::Error> {
+        let io_error = |error: io::Error| Self::io_error_to_vfs(&error);
+        if args.mask.contains(Mask::ACL) {
+            match mode_from_acl(&args.access) {
+                Some(perms) => {
+                    let mode = (attr.mode & !0o777) | perms;
+                    retry_interrupted(|| {
+                        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                    })
+                    .map_err(io_error)?;
+                    remove_acl(path, ACCESS_XATTR).map_err(io_error)?;
+                }
+                None => write_acl(path, ACCESS_XATTR, &args.access).map_err(io_error)?,
+            }
+        }
+        if args.mask.contains(Mask::DEFAULT_ACL) {
+            match (attr.file_type, args.default.is_empty()) {
+                (file::Type::Directory, true) => {
+                    remove_acl(path, DEFAULT_XATTR).map_err(io_error)?
+                }
+                (file::Type::Directory, false) => {
+                    write_acl(path, DEFAULT_XATTR, &args.default).map_err(io_error)?;
+                }
+                (_, true) => {}
+                (_, false) => return Err(vfs::Error::InvalidArgument),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the minimal access ACL equivalent to permission bits `mode`.
+fn acl_from_mode(mode: u32) -> Vec<Entry> {
+    vec![
+        Entry { tag: Tag::UserObj, id: 0, perm: (mode >> 6) & 0o7 },
+        Entry { tag: Tag::GroupObj, id: 0, perm: (mode >> 3) & 0o7 },
+        Entry { tag: Tag::Other, id: 0, perm: mode & 0o7 },
+    ]
+}
+
+/// Returns the permission bits of an ACL made of exactly the three base entries.
+fn mode_from_acl(entries: &[Entry]) -> Option<u32> {
+    if entries.len() != 3 {
+        return None;
+    }
+    let perm = |tag| entries.iter().find(|entry| entry.tag == tag).map(|entry| entry.perm & 0o7);
+    Some(perm(Tag::UserObj)? << 6 | perm(Tag::GroupObj)? << 3 | perm(Tag::Other)?)
+}
+
+/// Fills ids of the owner entries the way NFSACL reports them: the owner's uid and
+/// gid, and zero for the mask and others.
+fn with_owner_ids(mut entries: Vec<Entry>, attr: &file::Attr) -> Vec<Entry> {
+    for entry in &mut entries {
+        entry.id = match entry.tag {
+            Tag::UserObj => attr.uid,
+            Tag::GroupObj => attr.gid,
+            Tag::User | Tag::Group => entry.id,
+            Tag::Mask | Tag::Other => 0,
+        };
+    }
+    entries
+}
+
+fn decode_acl(bytes: &[u8]) -> io::Result<Vec<Entry>> {
+    let invalid = || io::Error::from_raw_os_error(libc::EIO);
+    if bytes.len() < 4 {
+        return Err(invalid());
+    }
+    let (header, body) = bytes.split_at(4);
+    if header != XATTR_VERSION.to_le_bytes() || body.len() % XATTR_ENTRY_LEN != 0 {
+        return Err(invalid());
+    }
+    body.chunks_exact(XATTR_ENTRY_LEN)
+        .map(|raw| {
+            let tag = u16::from_le_bytes([raw[0], raw[1]]);
+            Ok(Entry {
+                tag: Tag::from_u16(tag).ok_or_else(invalid)?,
+                perm: u32::from(u16::from_le_bytes([raw[2], raw[3]])),
+                id: u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]),
+            })
+        })
+        .collect()
+}
+
+fn encode_acl(entries: &[Entry]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(4 + entries.len() * XATTR_ENTRY_LEN);
+    bytes.extend_from_slice(&XATTR_VERSION.to_le_bytes());
+    for entry in entries {
+        let id = match entry.tag {
+            Tag::User | Tag::Group => entry.id,
+            // ACL_UNDEFINED_ID: the kernel ignores ids of the other entries.
+            _ => u32::MAX,
+        };
+        bytes.extend_from_slice(&(entry.tag as u16).to_le_bytes());
+        bytes.extend_from_slice(&((entry.perm & 0o7) as u16).to_le_bytes());
+        bytes.extend_from_slice(&id.to_le_bytes());
+    }
+    bytes
+}
+
+/// Reads the ACL stored in extended attribute `name`, or `None` if the file has none
+/// or the backing file system does not support ACLs.
+fn read_acl(path: &Path, name: &str) -> io::Result<Option<Vec<Entry>>> {
+    match xattr::get(path, name) {
+        Ok(bytes) => bytes.map(|bytes| decode_acl(&bytes)).transpose(),
+        Err(error) if error.raw_os_error() == Some(libc::EOPNOTSUPP) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn write_acl(path: &Path, name: &str, entries: &[Entry]) -> io::Result<()> {
+    xattr::set(path, name, &encode_acl(entries))
+}
+
+/// Removes the ACL stored in extended attribute `name`; a missing ACL is not an error.
+fn remove_acl(path: &Path, name: &str) -> io::Result<()> {
+    match xattr::remove(path, name) {
+        Err(error) if matches!(error.raw_os_error(), Some(libc::ENODATA | libc::EOPNOTSUPP)) => {
+            Ok(())
+        }
+        result => result,
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(target_os = "linux")]
+use std::fs::OpenOptions;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+
+use nfs_mamont::vfs::allocate::{Mode, VfsAllocate};
+use nfs_mamont::vfs::{self, file};
+
+use super::MirrorFS;
+use crate::io_error::retry_interrupted;
+
+impl VfsAllocate for MirrorFS {
+    async fn allocate_space(
+        &self,
+        cred: &vfs::Credentials,
+        file: &file::Handle,
+        offset: u64,
+        len: u64,
+        mode: Mode,
+    ) -> Result<(), vfs::Error> {
+        if len == 0 {
+            return Err(vfs::Error::InvalidArgument);
+        }
+        let path = self.path_for_handle(file).await?;
+        let attr = self.cached_attr(file, &path)?;
+        Self::validate_regular(&attr)?;
+        if !Self::can_write(cred, &attr) {
+            return Err(vfs::Error::Access);
+        }
+        let result = tokio::task::spawn_blocking(move || {
+            retry_interrupted(|| Self::fallocate(path.clone(), offset, len, mode))
+        })
+        .await
+        .unwrap_or_else(|error| Err(io::Error::other(error)));
+        self.attrs.invalidate(file);
+        self.access.invalidate(file);
+        result.map_err(|error| Self::io_error_to_vfs(&error))
+    }
+}
+
+impl MirrorFS {
+    /// Preallocates the range with `fallocate(2)`, which file systems without
+    /// preallocation refuse with `EOPNOTSUPP`.
+    #[cfg(target_os = "linux")]
+    fn fallocate(path: PathBuf, offset: u64, len: u64, mode: Mode) -> io::Result<()> {
+        let offset =
+            libc::off_t::try_from(offset).map_err(|_| io::Error::from_raw_os_error(libc::EFBIG))?;
+        let len =
+            libc::off_t::try_from(len).map_err(|_| io::Error::from_raw_os_error(libc::EFBIG))?;
+        let flags = match mode {
+            Mode::Extend => 0,
+            Mode::KeepSize => libc::FALLOC_FL_KEEP_SIZE,
+        };
+        let file = OpenOptions::new().write(true).open(path)?;
+        // SAFETY: `file` is an open descriptor for the duration of the call.
+        match unsafe { libc::fallocate(file.as_raw_fd(), flags, offset, len) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn fallocate(_path: PathBuf, _offset: u64, _len: u64, _mode: Mode) -> io::Result<()> {
+        Err(io::Error::from_raw_os_error(libc::EOPNOTSUPP))
+    }
+}
@@ -1,4 +1,7 @@
-use tokio::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
 
 use nfs_mamont::vfs::commit;
 
@@ -17,31 +20,96 @@ impl commit::Commit for MirrorFS {
         };
         let before_meta = std::fs::symlink_metadata(&path).ok();
         let before = before_meta.as_ref().map(Self::wcc_attr_from_metadata);
-        if let Some(attr) = before_meta.as_ref().map(Self::attr_from_metadata) {
+        if let Some(attr) = before_meta.as_ref().map(|meta| self.attr_from_metadata(&path, meta)) {
             if let Err(error) = Self::validate_regular(&attr) {
-                return Err(commit::Fail { error, file_wcc: Self::wcc_data(&path, before) });
+                return Err(commit::Fail { error, file_wcc: self.wcc_data(&path, before) });
             }
         }
 
-        let file = match OpenOptions::new().write(true).open(&path).await {
-            Ok(file) => file,
-            Err(error) => {
+        // A count of zero commits the whole file, whatever the offset; only a ranged
+        // commit has to start within the file.
+        let offset = if args.count == 0 { 0 } else { args.offset };
+        if before_meta.as_ref().is_some_and(|meta| offset > meta.len()) {
+            return Err(commit::Fail {
+                error: vfs::Error::InvalidArgument,
+                file_wcc: self.wcc_data(&path, before),
+            });
+        }
+
+        if let Err(error) = self.flush_buffered(&args.file).await {
+            return Err(commit::Fail {
+                error: Self::io_error_to_vfs(&error),
+                file_wcc: self.wcc_data(&path, before),
+            });
+        }
+        // No recorded ranges does not mean clean data: the writes may predate a restart.
+        let ranges = self.dirty.take(&args.file, offset, args.count);
+        if self.durability != Durability::None {
+            self.record_sync();
+            let result = self.sync_data(path.clone(), ranges).await;
+            if let Err(error) = result {
                 return Err(commit::Fail {
                     error: Self::io_error_to_vfs(&error),
-                    file_wcc: Self::wcc_data(&path, before),
+                    file_wcc: self.wcc_data(&path, before),
                 });
             }
-        };
-        if let Err(error) = file.sync_all().await {
-            return Err(commit::Fail {
-                error: Self::io_error_to_vfs(&error),
-                file_wcc: Self::wcc_data(&path, before),
-            });
         }
 
         Ok(commit::Success {
-            file_wcc: Self::wcc_data(&path, before),
+            file_wcc: self.wcc_data(&path, before),
             verifier: self.write_verifier(),
         })
     }
 }
+
+impl MirrorFS {
+    /// Flushes `ranges` through io_uring when enabled, on a blocking thread otherwise.
+    async fn sync_data(&self, path: PathBuf, ranges: Vec<Range<u64>>) -> io::Result<()> {
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        if let Some(uring) = &self.uring {
+            return Self::sync_with_uring(uring, &path).await;
+        }
+        tokio::task::spawn_blocking(move || {
+            retry_interrupted(|| Self::sync_ranges(path.clone(), &ranges))
+        })
+        .await
+        .unwrap_or_else(|error| Err(io::Error::other(error)))
+    }
+
+    /// Flushes the file, `ranges` of it first, to stable storage.
+    ///
+    /// `sync_file_range`, where available, writes back just the committed ranges, but
+    /// neither flushes the disk cache nor the metadata needed to read them back, such as
+    /// the size; `sync_data` of the whole file follows, and reports any write-back error.
+    fn sync_ranges(path: PathBuf, ranges: &[Range<u64>]) -> io::Result<()> {
+        let file = OpenOptions::new().write(true).open(path)?;
+        for range in ranges {
+            let _ = Self::sync_file_range(&file, range);
+        }
+        file.sync_data()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sync_file_range(file: &File, range: &Range<u64>) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let (Ok(offset), Ok(len)) =
+            (i64::try_from(range.start), i64::try_from(range.end - range.start))
+        else {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        };
+        let flags = libc::SYNC_FILE_RANGE_WAIT_BEFORE
+            | libc::SYNC_FILE_RANGE_WRITE
+            | libc::SYNC_FILE_RANGE_WAIT_AFTER;
+        // SAFETY: the descriptor is owned by `file` and stays open for the whole call.
+        if unsafe { libc::sync_file_range(file.as_raw_fd(), offset, len, flags) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sync_file_range(_file: &File, _range: &Range<u64>) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
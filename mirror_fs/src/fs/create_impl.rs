@@ -1,11 +1,13 @@
-use tokio::fs::OpenOptions;
-
-use nfs_mamont::vfs::{self, create};
+use nfs_mamont::vfs::{self, create, set_attr};
 
 use super::{MirrorFS, DEFAULT_SET_ATTR};
 
 impl create::Create for MirrorFS {
-    async fn create(&self, args: create::Args) -> Result<create::Success, create::Fail> {
+    async fn create(
+        &self,
+        cred: &vfs::Credentials,
+        args: create::Args,
+    ) -> Result<create::Success, create::Fail> {
         if let Err(error) = Self::ensure_name_allowed(&args.object.name) {
             return Err(create::Fail {
                 error,
@@ -32,28 +34,39 @@ impl create::Create for MirrorFS {
             }
         };
         let before = Some(Self::wcc_attr_from_metadata(&dir_meta));
-        let dir_attr = Self::attr_from_metadata(&dir_meta);
+        let dir_attr = self.attr_from_metadata(&dir_path, &dir_meta);
         if let Err(error) = Self::validate_directory(&dir_attr) {
-            return Err(create::Fail { error, wcc_data: Self::wcc_data(&dir_path, before) });
+            return Err(create::Fail { error, wcc_data: self.wcc_data(&dir_path, before) });
+        }
+        if let Err(error) = self.writable_dir(cred, &dir_path) {
+            return Err(create::Fail { error, wcc_data: self.wcc_data(&dir_path, before) });
+        }
+
+        if self.has_case_collision(&dir_path, &args.object.name) {
+            return Err(create::Fail {
+                error: vfs::Error::Exist,
+                wcc_data: self.wcc_data(&dir_path, before),
+            });
         }
 
         let mut child_path = dir_path.clone();
         child_path.push(args.object.name.as_str());
-        let existed = std::fs::symlink_metadata(&child_path).is_ok();
+        let mut existed = std::fs::symlink_metadata(&child_path).is_ok();
+        let default_mode = self.create_modes(&dir_path).file;
 
         let apply_attr = match &args.how {
             create::How::Unchecked(attr) => {
-                if !existed {
-                    if let Err(error) = OpenOptions::new()
-                        .write(true)
-                        .create(true)
-                        .truncate(false)
-                        .open(&child_path)
-                        .await
-                    {
+                // Creating exclusively decides atomically whether the file is new: one that
+                // another client created since the check is left as is, data and owner alike.
+                match Self::create_file(&child_path, attr.mode.or(default_mode)).await {
+                    Ok(()) => existed = false,
+                    Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                        existed = true;
+                    }
+                    Err(error) => {
                         return Err(create::Fail {
                             error: Self::io_error_to_vfs(&error),
-                            wcc_data: Self::wcc_data(&dir_path, before),
+                            wcc_data: self.wcc_data(&dir_path, before),
                         });
                     }
                 }
@@ -63,64 +76,86 @@ impl create::Create for MirrorFS {
                 if existed {
                     return Err(create::Fail {
                         error: vfs::Error::Exist,
-                        wcc_data: Self::wcc_data(&dir_path, before),
+                        wcc_data: self.wcc_data(&dir_path, before),
                     });
                 }
-                if let Err(error) =
-                    OpenOptions::new().write(true).create_new(true).open(&child_path).await
+                if let Err(error) = Self::create_file(&child_path, attr.mode.or(default_mode)).await
                 {
                     return Err(create::Fail {
                         error: Self::io_error_to_vfs(&error),
-                        wcc_data: Self::wcc_data(&dir_path, before),
+                        wcc_data: self.wcc_data(&dir_path, before),
                     });
                 }
                 attr
             }
             create::How::Exclusive(ref verifier) => {
-                match OpenOptions::new().write(true).create_new(true).open(&child_path).await {
-                    Ok(_) => {
+                match Self::create_file(&child_path, default_mode).await {
+                    Ok(()) => {
+                        existed = false;
                         Self::store_exclusive_verifier(&child_path, &verifier.0);
                     }
                     Err(ref e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
                         if !Self::check_exclusive_verifier(&child_path, &verifier.0) {
                             return Err(create::Fail {
                                 error: vfs::Error::Exist,
-                                wcc_data: Self::wcc_data(&dir_path, before),
+                                wcc_data: self.wcc_data(&dir_path, before),
                             });
                         }
                     }
                     Err(error) => {
                         return Err(create::Fail {
                             error: Self::io_error_to_vfs(&error),
-                            wcc_data: Self::wcc_data(&dir_path, before),
+                            wcc_data: self.wcc_data(&dir_path, before),
                         });
                     }
                 }
                 &DEFAULT_SET_ATTR
             }
         };
+        // A new file got its mode at creation.
+        let apply_attr = set_attr::NewAttr {
+            mode: apply_attr.mode.filter(|_| existed),
+            uid: apply_attr.uid,
+            gid: apply_attr.gid,
+            size: apply_attr.size,
+            atime: apply_attr.atime,
+            mtime: apply_attr.mtime,
+        };
+
+        self.negative.invalidate(&args.object.dir);
 
-        if let Err(error) = Self::apply_set_attr(&child_path, apply_attr) {
-            return Err(create::Fail { error, wcc_data: Self::wcc_data(&dir_path, before) });
+        if !existed {
+            if let Err(error) = Self::own_new_object(cred, &child_path) {
+                return Err(create::Fail { error, wcc_data: self.wcc_data(&dir_path, before) });
+            }
         }
 
-        let attr = match Self::metadata(&child_path) {
-            Ok(meta) => Self::attr_from_metadata(&meta),
+        if let Err(error) = self.apply_set_attr(&child_path, &apply_attr) {
+            return Err(create::Fail { error, wcc_data: self.wcc_data(&dir_path, before) });
+        }
+
+        self.attrs.invalidate(&args.object.dir);
+        self.access.invalidate(&args.object.dir);
+        let handle = match self.handle_for_path(&child_path).await {
+            Ok(handle) => handle,
             Err(error) => {
-                return Err(create::Fail { error, wcc_data: Self::wcc_data(&dir_path, before) });
+                return Err(create::Fail { error, wcc_data: self.wcc_data(&dir_path, before) });
             }
         };
-        let handle = match self.handle_for_path(&child_path).await {
-            Ok(handle) => handle,
+        // An existing file may just have been truncated or had its times set.
+        self.attrs.invalidate(&handle);
+        self.access.invalidate(&handle);
+        let attr = match Self::metadata(&child_path) {
+            Ok(meta) => self.attr_from_metadata(&child_path, &meta),
             Err(error) => {
-                return Err(create::Fail { error, wcc_data: Self::wcc_data(&dir_path, before) });
+                return Err(create::Fail { error, wcc_data: self.wcc_data(&dir_path, before) });
             }
         };
 
         Ok(create::Success {
             file: Some(handle),
             attr: Some(attr),
-            wcc_data: Self::wcc_data(&dir_path, before),
+            wcc_data: self.wcc_data(&dir_path, before),
         })
     }
 }
@@ -1,7 +1,6 @@
-use nfs_mamont::vfs::file;
 use nfs_mamont::vfs::fs_info;
 
-use super::{MirrorFS, READ_DIR_PREF, READ_WRITE_MAX};
+use super::{MirrorFS, READ_DIR_PREF};
 
 impl fs_info::FsInfo for MirrorFS {
     async fn fs_info(&self, args: fs_info::Args) -> Result<fs_info::Success, fs_info::Fail> {
@@ -10,16 +9,16 @@ impl fs_info::FsInfo for MirrorFS {
             Err(error) => return Err(fs_info::Fail { error, root_attr: None }),
         };
         Ok(fs_info::Success {
-            root_attr: Self::file_attr(&path),
-            read_max: READ_WRITE_MAX,
-            read_pref: READ_WRITE_MAX,
-            read_mult: 1,
-            write_max: READ_WRITE_MAX,
-            write_pref: READ_WRITE_MAX,
-            write_mult: 1,
+            root_attr: self.file_attr(&path),
+            read_max: self.max_transfer,
+            read_pref: self.max_transfer,
+            read_mult: self.transfer_multiple,
+            write_max: self.max_transfer,
+            write_pref: self.max_transfer,
+            write_mult: self.transfer_multiple,
             read_dir_pref: READ_DIR_PREF,
             max_file_size: u64::MAX,
-            time_delta: file::Time { seconds: 0, nanos: 1 },
+            time_delta: self.time_delta,
             properties: fs_info::Properties::from_wire(
                 fs_info::Properties::LINK
                     | fs_info::Properties::SYMLINK
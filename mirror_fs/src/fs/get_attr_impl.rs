@@ -4,14 +4,17 @@ use super::MirrorFS;
 
 impl get_attr::GetAttr for MirrorFS {
     async fn get_attr(&self, args: get_attr::Args) -> Result<get_attr::Success, get_attr::Fail> {
+        if let Some(object) = self.attrs.get(&args.file) {
+            return Ok(get_attr::Success { object });
+        }
         let path = match self.path_for_handle(&args.file).await {
             Ok(path) => path,
             Err(error) => {
                 return Err(get_attr::Fail { error });
             }
         };
-        match Self::metadata(&path) {
-            Ok(meta) => Ok(get_attr::Success { object: Self::attr_from_metadata(&meta) }),
+        match self.cached_attr(&args.file, &path) {
+            Ok(object) => Ok(get_attr::Success { object }),
             Err(error) => Err(get_attr::Fail { error }),
         }
     }
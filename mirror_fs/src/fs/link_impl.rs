@@ -1,3 +1,6 @@
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
 use tokio::fs;
 
 use nfs_mamont::vfs::{self, file, link};
@@ -5,7 +8,11 @@ use nfs_mamont::vfs::{self, file, link};
 use super::MirrorFS;
 
 impl link::Link for MirrorFS {
-    async fn link(&self, args: link::Args) -> Result<link::Success, link::Fail> {
+    async fn link(
+        &self,
+        cred: &vfs::Credentials,
+        args: link::Args,
+    ) -> Result<link::Success, link::Fail> {
         if let Err(error) = Self::ensure_name_allowed(&args.link.name) {
             return Err(link::Fail {
                 error,
@@ -24,10 +31,11 @@ impl link::Link for MirrorFS {
                 });
             }
         };
-        let file_attr = Self::file_attr(&file_path);
+        let file_attr = self.file_attr(&file_path);
+        // Hard links to directories would let the tree loop back onto itself.
         if matches!(file_attr.as_ref().map(|attr| attr.file_type), Some(file::Type::Directory)) {
             return Err(link::Fail {
-                error: vfs::Error::InvalidArgument,
+                error: vfs::Error::Permission,
                 file_attr,
                 dir_wcc: vfs::WccData { before: None, after: None },
             });
@@ -46,20 +54,52 @@ impl link::Link for MirrorFS {
         let before = std::fs::symlink_metadata(&dir_path)
             .ok()
             .map(|meta| Self::wcc_attr_from_metadata(&meta));
+        // The file must be reachable by the caller, and its new name is added to `dir_path`.
+        let permitted = file_path
+            .parent()
+            .map_or(Ok(()), |parent| self.searchable_dir(cred, parent).map(drop))
+            .and_then(|()| self.writable_dir(cred, &dir_path).map(drop));
+        if let Err(error) = permitted {
+            return Err(link::Fail { error, file_attr, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
+        if !Self::same_device(&file_path, &dir_path) {
+            return Err(link::Fail {
+                error: vfs::Error::XDev,
+                file_attr,
+                dir_wcc: self.wcc_data(&dir_path, before),
+            });
+        }
         let mut target_path = dir_path.clone();
         target_path.push(args.link.name.as_str());
-        if let Err(error) = fs::hard_link(&file_path, &target_path).await {
+        let linked = fs::hard_link(&file_path, &target_path).await;
+        // The link count of the file changes along with the directory.
+        self.invalidate_cached([&args.file, &args.link.dir]);
+        self.negative.invalidate(&args.link.dir);
+        if let Err(error) = linked {
             return Err(link::Fail {
                 error: Self::io_error_to_vfs(&error),
                 file_attr,
-                dir_wcc: Self::wcc_data(&dir_path, before),
+                dir_wcc: self.wcc_data(&dir_path, before),
             });
         }
         let _ = self.handle_for_path(&target_path).await;
 
         Ok(link::Success {
-            file_attr: Self::file_attr(&file_path),
-            dir_wcc: Self::wcc_data(&dir_path, before),
+            file_attr: self.file_attr(&file_path),
+            dir_wcc: self.wcc_data(&dir_path, before),
         })
     }
 }
+
+impl MirrorFS {
+    /// Returns `false` if `file` and `dir` are known to live on different devices,
+    /// which `link(2)` would refuse with `EXDEV`.
+    ///
+    /// Objects whose metadata cannot be read are left for the link itself to report.
+    fn same_device(file: &Path, dir: &Path) -> bool {
+        match (std::fs::symlink_metadata(file), std::fs::metadata(dir)) {
+            (Ok(file), Ok(dir)) => file.dev() == dir.dev(),
+            _ => true,
+        }
+    }
+}
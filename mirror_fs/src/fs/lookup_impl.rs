@@ -1,6 +1,7 @@
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 
-use nfs_mamont::vfs::lookup;
+use nfs_mamont::vfs::{self, lookup};
 
 use super::MirrorFS;
 
@@ -12,19 +13,32 @@ impl lookup::Lookup for MirrorFS {
                 return Err(lookup::Fail { error, dir_attr: None });
             }
         };
-        let parent_meta = match Self::metadata(&parent_path) {
-            Ok(meta) => meta,
+        let parent_attr = match self.cached_attr(&args.parent, &parent_path) {
+            Ok(attr) => attr,
             Err(error) => {
                 return Err(lookup::Fail { error, dir_attr: None });
             }
         };
-        let parent_attr = Self::attr_from_metadata(&parent_meta);
         if let Err(error) = Self::validate_directory(&parent_attr) {
             return Err(lookup::Fail { error, dir_attr: Some(parent_attr) });
         }
 
+        let named = !matches!(args.name.as_str(), "." | "..");
+        if named && self.negative.contains(&args.parent, args.name.as_str()) {
+            return Err(lookup::Fail { error: vfs::Error::NoEntry, dir_attr: Some(parent_attr) });
+        }
+        let epoch = self.negative.epoch();
+
         let child_path = match args.name.as_str() {
-            "." => parent_path.clone(),
+            // "." names the directory itself, so the handle is returned as is.
+            "." => {
+                return Ok(lookup::Success {
+                    file: args.parent,
+                    file_attr: Some(parent_attr.clone()),
+                    dir_attr: Some(parent_attr),
+                });
+            }
+            // ".." resolves to the real parent, but never escapes the export root.
             ".." => {
                 let export_root = match self.exported_root_path().await {
                     Ok(path) => path,
@@ -39,15 +53,18 @@ impl lookup::Lookup for MirrorFS {
                     parent_path.parent().map(PathBuf::from).unwrap_or(parent_path.clone())
                 }
             }
-            _ => {
-                let mut child_path = parent_path.clone();
-                child_path.push(args.name.as_str());
-                child_path
-            }
+            _ => self.resolve_name(&parent_path, &args.name),
         };
+        if let Err(error) = self.check_resolution(&child_path).await {
+            return Err(lookup::Fail { error, dir_attr: Some(parent_attr) });
+        }
+        self.lookup_calls.fetch_add(1, Ordering::Relaxed);
         let child_meta = match Self::metadata(&child_path) {
             Ok(meta) => meta,
             Err(error) => {
+                if named && error == vfs::Error::NoEntry {
+                    self.negative.insert(&args.parent, args.name.as_str(), epoch);
+                }
                 return Err(lookup::Fail { error, dir_attr: Some(parent_attr) });
             }
         };
@@ -59,9 +76,11 @@ impl lookup::Lookup for MirrorFS {
             }
         };
 
+        let child_attr = self.attr_from_metadata(&child_path, &child_meta);
+        self.attrs.insert(&child_handle, &child_attr);
         Ok(lookup::Success {
             file: child_handle,
-            file_attr: Some(Self::attr_from_metadata(&child_meta)),
+            file_attr: Some(child_attr),
             dir_attr: Some(parent_attr),
         })
     }
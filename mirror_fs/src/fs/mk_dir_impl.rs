@@ -1,11 +1,13 @@
-use tokio::fs;
-
-use nfs_mamont::vfs::{self, mk_dir};
+use nfs_mamont::vfs::{self, mk_dir, set_attr};
 
 use super::MirrorFS;
 
 impl mk_dir::MkDir for MirrorFS {
-    async fn mk_dir(&self, args: mk_dir::Args) -> Result<mk_dir::Success, mk_dir::Fail> {
+    async fn mk_dir(
+        &self,
+        cred: &vfs::Credentials,
+        args: mk_dir::Args,
+    ) -> Result<mk_dir::Success, mk_dir::Fail> {
         if let Err(error) = Self::ensure_name_allowed(&args.object.name) {
             return Err(mk_dir::Fail {
                 error,
@@ -24,34 +26,46 @@ impl mk_dir::MkDir for MirrorFS {
         let before = std::fs::symlink_metadata(&dir_path)
             .ok()
             .map(|meta| Self::wcc_attr_from_metadata(&meta));
+        if let Err(error) = self.writable_dir(cred, &dir_path) {
+            return Err(mk_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
         let mut child_path = dir_path.clone();
         child_path.push(args.object.name.as_str());
-        if let Err(error) = fs::create_dir(&child_path).await {
+        let mode = args.attr.mode.or(self.create_modes(&dir_path).directory);
+        if let Err(error) = Self::create_directory(&child_path, mode).await {
             return Err(mk_dir::Fail {
                 error: Self::io_error_to_vfs(&error),
-                dir_wcc: Self::wcc_data(&dir_path, before),
+                dir_wcc: self.wcc_data(&dir_path, before),
             });
         }
-        if let Err(error) = Self::apply_set_attr(&child_path, &args.attr) {
-            return Err(mk_dir::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) });
+        self.negative.invalidate(&args.object.dir);
+        if let Err(error) = Self::own_new_object(cred, &child_path) {
+            return Err(mk_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
+        // The mode was given at creation.
+        let attr = set_attr::NewAttr { mode: None, ..args.attr };
+        if let Err(error) = self.apply_set_attr(&child_path, &attr) {
+            return Err(mk_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
         }
+        self.attrs.invalidate(&args.object.dir);
+        self.access.invalidate(&args.object.dir);
         let attr = match Self::metadata(&child_path) {
-            Ok(meta) => Self::attr_from_metadata(&meta),
+            Ok(meta) => self.attr_from_metadata(&child_path, &meta),
             Err(error) => {
-                return Err(mk_dir::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) })
+                return Err(mk_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
             }
         };
         let handle = match self.handle_for_path(&child_path).await {
             Ok(handle) => handle,
             Err(error) => {
-                return Err(mk_dir::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) })
+                return Err(mk_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
             }
         };
 
         Ok(mk_dir::Success {
             file: Some(handle),
             attr: Some(attr),
-            wcc_data: Self::wcc_data(&dir_path, before),
+            wcc_data: self.wcc_data(&dir_path, before),
         })
     }
 }
@@ -5,10 +5,15 @@ use crate::fs::DEFAULT_SET_ATTR;
 use super::MirrorFS;
 
 impl mk_node::MkNode for MirrorFS {
-    async fn mk_node(&self, args: mk_node::Args) -> Result<mk_node::Success, mk_node::Fail> {
+    async fn mk_node(
+        &self,
+        cred: &vfs::Credentials,
+        args: mk_node::Args,
+    ) -> Result<mk_node::Success, mk_node::Fail> {
         match args.what {
             mk_node::What::Regular => match create::Create::create(
                 self,
+                cred,
                 create::Args { object: args.object, how: create::How::Unchecked(DEFAULT_SET_ATTR) },
             )
             .await
@@ -23,6 +28,7 @@ impl mk_node::MkNode for MirrorFS {
             mk_node::What::Directory => {
                 match mk_dir::MkDir::mk_dir(
                     self,
+                    cred,
                     mk_dir::Args { object: args.object, attr: DEFAULT_SET_ATTR },
                 )
                 .await
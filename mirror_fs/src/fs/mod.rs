@@ -1,10 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
 use std::fs::Metadata;
-use std::io::ErrorKind;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU32;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use tokio::sync::RwLock;
+use tracing::warn;
 
 use nfs_mamont::consts::nfsv3::{NFS3_COOKIEVERFSIZE, NFS3_CREATEVERFSIZE};
 use nfs_mamont::vfs;
@@ -14,9 +20,20 @@ use nfs_mamont::vfs::set_attr;
 use nfs_mamont::vfs::write;
 use nfs_mamont::Buffer;
 
+use crate::access_cache::AccessCache;
+use crate::attr_cache::AttrCache;
+use crate::clock::{Clock, SystemClock};
+use crate::dirty_ranges::DirtyRanges;
 use crate::fs_map::FsMap;
+use crate::io_error::{map_io_error, retry_interrupted};
+use crate::negative_cache::NegativeCache;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use crate::uring::UringBackend;
+use crate::write_buffer::{WriteBuffer, WriteBufferLimits};
 
 mod access_impl;
+mod acl_impl;
+mod allocate_impl;
 mod commit_impl;
 mod create_impl;
 mod fs_info_impl;
@@ -36,10 +53,16 @@ mod rename_impl;
 mod rm_dir_impl;
 mod set_attr_impl;
 mod symlink_impl;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+mod uring_impl;
 mod write_impl;
+mod xattr_impl;
 
+/// READ and WRITE size advertised unless [`MirrorFS::with_max_transfer`] sets another.
 const READ_WRITE_MAX: u32 = 64 * 1024;
 const READ_DIR_PREF: u32 = 8 * 1024;
+/// Symlinked directories followed while resolving a single path.
+const MAX_SYMLINK_FOLLOWS: usize = 16;
 const DEFAULT_SET_ATTR: set_attr::NewAttr = set_attr::NewAttr {
     mode: None,
     uid: None,
@@ -49,21 +72,482 @@ const DEFAULT_SET_ATTR: set_attr::NewAttr = set_attr::NewAttr {
     mtime: set_attr::SetTime::DontChange,
 };
 
+/// How much durability writes and commits of [`MirrorFS`] provide.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Durability {
+    /// Data and metadata are synced as requested by the client.
+    #[default]
+    Full,
+    /// Only data is synced: `FileSync` writes are served as `DataSync`.
+    DataOnly,
+    /// Nothing is synced; every write stays `Unstable` and COMMIT is a no-op.
+    /// Suitable for scratch space that need not survive a crash.
+    None,
+}
+
+impl Durability {
+    /// Returns the stability a write requested as `stable` achieves under this policy.
+    pub fn cap(self, stable: write::StableHow) -> write::StableHow {
+        match (self, stable) {
+            (Durability::None, _) => write::StableHow::Unstable,
+            (Durability::DataOnly, write::StableHow::FileSync) => write::StableHow::DataSync,
+            (_, stable) => stable,
+        }
+    }
+}
+
+/// How READDIR and READDIRPLUS cookie verifiers are derived and checked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CookieVerifierPolicy {
+    /// The verifier digests the directory's mtime, size and entry names, so any
+    /// change of the listing invalidates outstanding cookies with `BadCookie`.
+    #[default]
+    Listing,
+    /// The verifier is always zero and never checked; cookies of a modified
+    /// directory keep pointing at the same positions of the new listing.
+    Disabled,
+}
+
+/// Permission bits of objects created under an export when the client sets no mode,
+/// instead of the ones the process umask leaves.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CreateModes {
+    /// Mode of regular files created by CREATE.
+    pub file: Option<u32>,
+    /// Mode of directories created by MKDIR.
+    pub directory: Option<u32>,
+}
+
 /// A file system implementation that mirrors a local directory.
 #[derive(Debug)]
 pub struct MirrorFS {
     fsmap: RwLock<FsMap>,
+    dirty: DirtyRanges,
+    durability: Durability,
+    cookie_verifiers: CookieVerifierPolicy,
+    attrs: AttrCache,
+    access: AccessCache,
+    negative: NegativeCache,
+    clock: Arc<dyn Clock>,
+    writes: Option<WriteBuffer>,
+    /// Most entries, each carrying a handle, a single READDIRPLUS reply returns.
+    read_dir_plus_max_handles: Option<NonZeroU32>,
+    /// Largest READ and WRITE FSINFO advertises.
+    max_transfer: u32,
+    /// Multiple READ and WRITE sizes and offsets should be aligned to, per FSINFO.
+    transfer_multiple: u32,
+    /// Held exclusively while buffered writes move to disk, so reads never miss them.
+    flushing: RwLock<()>,
+    syncs: AtomicU64,
+    metadata_calls: AtomicU64,
+    lookup_calls: AtomicU64,
     generation: u64,
+    time_delta: file::Time,
+    case_insensitive: bool,
+    /// Export roots whose files report a configured file system id.
+    export_fsids: Vec<(PathBuf, u64)>,
+    /// Export roots whose created objects get configured modes.
+    export_modes: Vec<(PathBuf, CreateModes)>,
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    uring: Option<UringBackend>,
 }
 
 impl MirrorFS {
     /// Creates a new mirror file system with the given root path.
     pub fn new(root: PathBuf) -> Self {
         let root = std::fs::canonicalize(&root).unwrap_or(root);
-        let generation =
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_nanos()
-                as u64;
-        Self { fsmap: RwLock::new(FsMap::new(root)), generation }
+        let time_delta = Self::probe_time_delta(&root);
+        let generation = Self::next_generation();
+        Self {
+            fsmap: RwLock::new(FsMap::new(root)),
+            dirty: DirtyRanges::new(),
+            durability: Durability::Full,
+            cookie_verifiers: CookieVerifierPolicy::Listing,
+            attrs: AttrCache::default(),
+            access: AccessCache::default(),
+            negative: NegativeCache::default(),
+            clock: Arc::new(SystemClock),
+            writes: None,
+            read_dir_plus_max_handles: None,
+            max_transfer: READ_WRITE_MAX,
+            transfer_multiple: 1,
+            flushing: RwLock::new(()),
+            syncs: AtomicU64::new(0),
+            metadata_calls: AtomicU64::new(0),
+            lookup_calls: AtomicU64::new(0),
+            generation,
+            time_delta,
+            case_insensitive: false,
+            export_fsids: Vec::new(),
+            export_modes: Vec::new(),
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            uring: None,
+        }
+    }
+
+    /// Enables matching of names ignoring case, while preserving the stored case.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Reports `fsid` as the file system id of every file under export `root`, instead of
+    /// the id of the device holding it.
+    pub fn with_export_fsid(mut self, root: PathBuf, fsid: u64) -> Self {
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+        self.export_fsids.push((root, fsid));
+        self
+    }
+
+    /// Gives objects created under export `root` without a mode from the client the
+    /// modes of `modes`.
+    pub fn with_export_create_modes(mut self, root: PathBuf, modes: CreateModes) -> Self {
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+        self.export_modes.push((root, modes));
+        self
+    }
+
+    /// Keeps the handle registry in the journal at `path`, so handles issued before a
+    /// restart stay valid for files which are still in place.
+    ///
+    /// The journal must live outside the mirrored directory.
+    pub fn with_handle_registry(mut self, path: &Path) -> std::io::Result<Self> {
+        self.fsmap.get_mut().attach_journal(path)?;
+        Ok(self)
+    }
+
+    /// Sets how much durability writes and commits provide.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Names the write verifier after `epoch` instead of the instance.
+    ///
+    /// Instances sharing an epoch, e.g. the servers behind one address, report the same
+    /// verifier, so a client failing over between them does not resend its writes. The
+    /// epoch must be bumped whenever an instance may have lost writes it acknowledged as
+    /// unstable, such as on a restart with buffered writes.
+    pub fn with_write_epoch(mut self, epoch: u64) -> Self {
+        self.generation = epoch;
+        self
+    }
+
+    /// Sets the timestamp granularity reported by FSINFO, overriding the probed one.
+    pub fn with_time_delta(mut self, time_delta: file::Time) -> Self {
+        self.time_delta = time_delta;
+        self
+    }
+
+    /// Sets how directory cookie verifiers are derived and checked.
+    pub fn with_cookie_verifiers(mut self, policy: CookieVerifierPolicy) -> Self {
+        self.cookie_verifiers = policy;
+        self
+    }
+
+    /// Caches attributes served by GETATTR, LOOKUP and ACCESS for `ttl`.
+    ///
+    /// A zero `ttl` disables the cache. Changes made to the mirrored directory
+    /// behind the server's back may stay invisible for up to `ttl`.
+    pub fn with_attr_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.attrs = AttrCache::new(ttl).with_clock(Arc::clone(&self.clock));
+        self
+    }
+
+    /// Serves READ, WRITE and COMMIT through an io_uring with `entries` slots.
+    ///
+    /// Keeps the blocking I/O path if the kernel does not support io_uring.
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    pub fn with_io_uring(mut self, entries: u32) -> Self {
+        match UringBackend::new(entries) {
+            Ok(uring) => self.uring = Some(uring),
+            Err(error) => warn!(%error, "io_uring unavailable, using blocking file I/O"),
+        }
+        self
+    }
+
+    /// Returns whether file data goes through io_uring.
+    #[cfg(all(test, feature = "io_uring", target_os = "linux"))]
+    pub fn uses_io_uring(&self) -> bool {
+        self.uring.is_some()
+    }
+
+    /// Caches ACCESS results per handle, caller identity and mask for `ttl`.
+    ///
+    /// A zero `ttl` disables the cache. Results are dropped whenever the server changes
+    /// the object, but permission changes made behind the server's back may stay
+    /// invisible for up to `ttl`.
+    pub fn with_access_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.access = AccessCache::new(ttl).with_clock(Arc::clone(&self.clock));
+        self
+    }
+
+    /// Remembers names LOOKUP found missing for `ttl`, sparing repeated misses a syscall.
+    ///
+    /// A zero `ttl` disables the cache. Entries of a directory are dropped whenever the
+    /// server adds a name to it, but files created behind the server's back may stay
+    /// invisible for up to `ttl`.
+    pub fn with_negative_lookup_ttl(mut self, ttl: Duration) -> Self {
+        self.negative = NegativeCache::new(ttl).with_clock(Arc::clone(&self.clock));
+        self
+    }
+
+    /// Reads the time from `clock`: cache entries age by it and SETATTR stamps
+    /// its time on files whose times are set to the server time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.attrs = std::mem::take(&mut self.attrs).with_clock(Arc::clone(&clock));
+        self.access = std::mem::take(&mut self.access).with_clock(Arc::clone(&clock));
+        self.negative = std::mem::take(&mut self.negative).with_clock(Arc::clone(&clock));
+        self.clock = clock;
+        self
+    }
+
+    /// Buffers unstable writes in memory and writes them out in larger chunks.
+    ///
+    /// Buffered data reaches the file once a file or the whole buffer exceeds `limits`,
+    /// on COMMIT, and before any stable write or size change of the file.
+    pub fn with_write_buffer(mut self, limits: WriteBufferLimits) -> Self {
+        self.writes = Some(WriteBuffer::new(limits));
+        self
+    }
+
+    /// Advertises `max` bytes as the largest and preferred READ and WRITE in FSINFO.
+    ///
+    /// Should match the transfer size the server's allocators were sized for with
+    /// [`nfs_mamont::Impl::for_transfers`].
+    pub fn with_max_transfer(mut self, max: NonZeroU32) -> Self {
+        self.max_transfer = max.get();
+        self
+    }
+
+    /// Advertises `multiple` in FSINFO as the alignment READ and WRITE transfers
+    /// should follow, e.g. the block size of a device written with direct I/O.
+    ///
+    /// It is a hint: unaligned transfers are still served, only less efficiently.
+    pub fn with_transfer_multiple(mut self, multiple: NonZeroU32) -> Self {
+        self.transfer_multiple = multiple.get();
+        self
+    }
+
+    /// Returns at most `max` entries per READDIRPLUS reply, however large the client's
+    /// byte budgets are, bounding the handles and attributes a single call looks up.
+    pub fn with_read_dir_plus_max_handles(mut self, max: NonZeroU32) -> Self {
+        self.read_dir_plus_max_handles = Some(max);
+        self
+    }
+
+    /// Drops up to `budget` expired entries of the attribute, ACCESS and negative lookup
+    /// caches, oldest first within each cache, and returns how many were dropped.
+    ///
+    /// Each cache gets an even share of `budget` first, so a busy cache cannot starve
+    /// the others; what the shares leave unused goes to whichever cache still has
+    /// expired entries.
+    pub fn expire_cached(&self, budget: usize) -> usize {
+        let caches: [&dyn Fn(usize) -> usize; 3] = [
+            &|budget| self.attrs.expire(budget),
+            &|budget| self.access.expire(budget),
+            &|budget| self.negative.expire(budget),
+        ];
+        let mut dropped = 0;
+        for (index, expire) in caches.iter().enumerate() {
+            dropped += expire((budget - dropped) / (caches.len() - index));
+        }
+        for expire in caches {
+            dropped += expire(budget - dropped);
+        }
+        dropped
+    }
+
+    /// Returns the number of entries held by the attribute, ACCESS and negative lookup
+    /// caches, expired or not.
+    #[cfg(test)]
+    pub fn cached_entries(&self) -> usize {
+        self.attrs.entry_count() + self.access.entry_count() + self.negative.entry_count()
+    }
+
+    /// Returns the number of names held by the negative lookup cache, expired or not.
+    #[cfg(test)]
+    pub fn negative_entries(&self) -> usize {
+        self.negative.entry_count()
+    }
+
+    /// Returns the number of attribute lookups which missed the attribute cache.
+    #[cfg(test)]
+    pub fn metadata_calls(&self) -> u64 {
+        self.metadata_calls.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of names LOOKUP resolved against the mirrored directory.
+    #[cfg(test)]
+    pub fn lookup_calls(&self) -> u64 {
+        self.lookup_calls.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of syncs to stable storage issued so far.
+    #[cfg(test)]
+    pub fn sync_count(&self) -> u64 {
+        self.syncs.load(Ordering::Relaxed)
+    }
+
+    fn record_sync(&self) {
+        self.syncs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if `cred` holds every permission of `rwx` (`0o4` read, `0o2` write,
+    /// `0o1` execute or search) on an object with `attr` according to its mode bits.
+    fn permits(cred: &vfs::Credentials, attr: &file::Attr, rwx: u32) -> bool {
+        if cred.uid == 0 {
+            return true;
+        }
+        let shift = if cred.uid == attr.uid {
+            6
+        } else if cred.in_group(attr.gid) {
+            3
+        } else {
+            0
+        };
+        (attr.mode >> shift) & rwx == rwx
+    }
+
+    /// Returns `true` if `cred` may write to an object with `attr` according to its mode bits.
+    fn can_write(cred: &vfs::Credentials, attr: &file::Attr) -> bool {
+        Self::permits(cred, attr, 0o2)
+    }
+
+    /// Returns the attributes of the directory at `dir_path` if `cred` may search it.
+    ///
+    /// A symlink is followed, as a handle may name a directory through one.
+    fn searchable_dir(
+        &self,
+        cred: &vfs::Credentials,
+        dir_path: &Path,
+    ) -> Result<file::Attr, vfs::Error> {
+        let meta = std::fs::metadata(dir_path).map_err(|error| Self::io_error_to_vfs(&error))?;
+        let attr = self.attr_from_metadata(dir_path, &meta);
+        Self::validate_directory(&attr)?;
+        if Self::permits(cred, &attr, 0o1) {
+            Ok(attr)
+        } else {
+            Err(vfs::Error::Access)
+        }
+    }
+
+    /// Returns the attributes of the directory at `dir_path` if `cred` may add and remove
+    /// its entries, which takes write and search permission.
+    fn writable_dir(
+        &self,
+        cred: &vfs::Credentials,
+        dir_path: &Path,
+    ) -> Result<file::Attr, vfs::Error> {
+        let attr = self.searchable_dir(cred, dir_path)?;
+        if Self::permits(cred, &attr, 0o2) {
+            Ok(attr)
+        } else {
+            Err(vfs::Error::Access)
+        }
+    }
+
+    /// Checks that `cred` may remove or replace the entry at `path` of a directory with
+    /// `dir_attr`: in a sticky directory only root and the owners of either may.
+    fn check_sticky(
+        cred: &vfs::Credentials,
+        dir_attr: &file::Attr,
+        path: &Path,
+    ) -> Result<(), vfs::Error> {
+        if dir_attr.mode & 0o1000 == 0 || cred.uid == 0 || cred.uid == dir_attr.uid {
+            return Ok(());
+        }
+        if Self::metadata(path)?.uid() == cred.uid {
+            Ok(())
+        } else {
+            Err(vfs::Error::Permission)
+        }
+    }
+
+    /// Checks that `cred` may change owner and group of an object with `attr` as requested.
+    ///
+    /// Only root may change the owner; the owner may change the group to one of its own.
+    fn check_owner_change(
+        cred: &vfs::Credentials,
+        attr: &file::Attr,
+        new_attr: &set_attr::NewAttr,
+    ) -> Result<(), vfs::Error> {
+        if cred.uid == 0 {
+            return Ok(());
+        }
+        if new_attr.uid.is_some_and(|uid| uid != attr.uid) {
+            return Err(vfs::Error::Permission);
+        }
+        if let Some(gid) = new_attr.gid {
+            if gid != attr.gid && (cred.uid != attr.uid || !cred.in_group(gid)) {
+                return Err(vfs::Error::Permission);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `cred` may change mode, size and times of an object with `attr` as requested.
+    ///
+    /// Mode and explicit times take root or the owner, a size takes write permission, and
+    /// setting times to the server clock takes either.
+    fn check_attr_change(
+        cred: &vfs::Credentials,
+        attr: &file::Attr,
+        new_attr: &set_attr::NewAttr,
+    ) -> Result<(), vfs::Error> {
+        let owner = cred.uid == 0 || cred.uid == attr.uid;
+        let explicit_time = [&new_attr.atime, &new_attr.mtime]
+            .into_iter()
+            .any(|time| matches!(time, set_attr::SetTime::ToClient(_)));
+        if (new_attr.mode.is_some() || explicit_time) && !owner {
+            return Err(vfs::Error::Permission);
+        }
+        if new_attr.size.is_some() && !Self::can_write(cred, attr) {
+            return Err(vfs::Error::Access);
+        }
+        let server_time = [&new_attr.atime, &new_attr.mtime]
+            .into_iter()
+            .any(|time| matches!(time, set_attr::SetTime::ToServer));
+        if server_time && !owner && !Self::can_write(cred, attr) {
+            return Err(vfs::Error::Access);
+        }
+        Ok(())
+    }
+
+    fn apply_owner(path: &Path, uid: Option<u32>, gid: Option<u32>) -> Result<(), vfs::Error> {
+        if uid.is_none() && gid.is_none() {
+            return Ok(());
+        }
+        retry_interrupted(|| std::os::unix::fs::lchown(path, uid, gid))
+            .map_err(|error| Self::io_error_to_vfs(&error))
+    }
+
+    /// Gives the object just created at `path` to `cred`, removing it again if that fails.
+    ///
+    /// Only ids that differ from the object's are changed, so a server not running as root
+    /// can still create objects for callers with its own ids.
+    fn own_new_object(cred: &vfs::Credentials, path: &Path) -> Result<(), vfs::Error> {
+        let meta = Self::metadata(path)?;
+        let uid = Some(cred.uid).filter(|&uid| uid != meta.uid());
+        let gid = Some(cred.gid).filter(|&gid| gid != meta.gid());
+        let Err(error) = Self::apply_owner(path, uid, gid) else {
+            return Ok(());
+        };
+        let removed = if meta.is_dir() {
+            retry_interrupted(|| std::fs::remove_dir(path))
+        } else {
+            retry_interrupted(|| std::fs::remove_file(path))
+        };
+        if let Err(remove_error) = removed {
+            warn!(%remove_error, path = %path.display(), "failed to remove object left unowned");
+        }
+        Err(error)
+    }
+
+    /// Returns byte ranges of `file` written unstably and not committed yet.
+    #[cfg(test)]
+    pub fn uncommitted_ranges(&self, file: &file::Handle) -> Vec<std::ops::Range<u64>> {
+        self.dirty.ranges(file)
     }
 
     /// Returns the root handle.
@@ -71,15 +555,72 @@ impl MirrorFS {
         self.fsmap.read().await.root_handle()
     }
 
+    /// Guesses the timestamp granularity of the file system holding `root`.
+    ///
+    /// Any sub-second part in the root's timestamps means the file system keeps
+    /// nanoseconds; otherwise only whole seconds are assumed to be preserved.
+    fn probe_time_delta(root: &Path) -> file::Time {
+        let nanos = std::fs::metadata(root)
+            .map(|meta| meta.atime_nsec() | meta.mtime_nsec() | meta.ctime_nsec())
+            .unwrap_or(0);
+        if nanos != 0 {
+            file::Time { seconds: 0, nanos: 1 }
+        } else {
+            file::Time { seconds: 1, nanos: 0 }
+        }
+    }
+
+    /// Returns the generation of a new instance, which names its write verifier.
+    ///
+    /// Generations come from the wall clock, so they differ across server restarts, and
+    /// strictly increase within a process, so an instance created in the same clock tick
+    /// as a previous one never repeats its verifier.
+    fn next_generation() -> u64 {
+        static LAST: AtomicU64 = AtomicU64::new(0);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_nanos()
+            as u64;
+        let previous = LAST
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(now.max(last.wrapping_add(1)))
+            })
+            .unwrap_or_default();
+        now.max(previous.wrapping_add(1))
+    }
+
     fn write_verifier(&self) -> write::Verifier {
         write::Verifier(self.generation.to_be_bytes())
     }
 
-    fn cookie_verifier_for_attr(attr: &file::Attr) -> read_dir::CookieVerifier {
-        let mut raw = [0u8; NFS3_COOKIEVERFSIZE];
-        raw[..4].copy_from_slice(&attr.ctime.seconds.to_be_bytes());
-        raw[4..].copy_from_slice(&attr.ctime.nanos.to_be_bytes());
-        read_dir::CookieVerifier::new(raw)
+    /// Returns the cookie verifier of a directory with `attr` listing `entries`.
+    fn cookie_verifier(
+        &self,
+        attr: &file::Attr,
+        entries: &[(file::Name, PathBuf, Metadata)],
+    ) -> read_dir::CookieVerifier {
+        match self.cookie_verifiers {
+            CookieVerifierPolicy::Listing => {
+                let mut hasher = DefaultHasher::new();
+                (attr.mtime.seconds, attr.mtime.nanos, attr.size).hash(&mut hasher);
+                entries.iter().for_each(|(name, _, _)| name.as_str().hash(&mut hasher));
+                read_dir::CookieVerifier::new(hasher.finish().to_be_bytes())
+            }
+            CookieVerifierPolicy::Disabled => {
+                read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE])
+            }
+        }
+    }
+
+    /// Returns `true` if a READDIR continuing from `cookie` with `presented` verifier
+    /// may proceed, given the directory's current verifier.
+    fn cookie_is_valid(
+        &self,
+        cookie: read_dir::Cookie,
+        presented: read_dir::CookieVerifier,
+        current: read_dir::CookieVerifier,
+    ) -> bool {
+        cookie.is_zero()
+            || self.cookie_verifiers == CookieVerifierPolicy::Disabled
+            || presented == current
     }
 
     async fn path_for_handle(&self, handle: &file::Handle) -> Result<PathBuf, vfs::Error> {
@@ -91,12 +632,54 @@ impl MirrorFS {
         self.fsmap.write().await.ensure_handle_for_path(path)
     }
 
-    async fn remove_cached_path(&self, path: &Path) {
-        self.fsmap.write().await.remove_path(path);
+    /// Forgets `path`, removed from directory `dir`, and drops cached results of both the
+    /// directory and the object `meta` describes, whose link count changed.
+    async fn remove_cached_path(&self, dir: &file::Handle, path: &Path, meta: &Metadata) {
+        let removed = {
+            let mut fsmap = self.fsmap.write().await;
+            let removed = fsmap.known_handle(meta);
+            fsmap.remove_path(path);
+            removed
+        };
+        self.invalidate_cached([dir].into_iter().chain(removed.as_ref()));
+    }
+
+    /// Moves the handles under `from` in directory `from_dir` to `to` in `to_dir`, and drops
+    /// cached results of both directories and of the object `meta` describes.
+    async fn rename_cached_path(
+        &self,
+        from_dir: &file::Handle,
+        to_dir: &file::Handle,
+        from: &Path,
+        to: &Path,
+        meta: &Metadata,
+    ) -> Result<(), vfs::Error> {
+        let (moved, renamed) = {
+            let mut fsmap = self.fsmap.write().await;
+            (fsmap.known_handle(meta), fsmap.rename_path(from, to))
+        };
+        self.negative.invalidate(to_dir);
+        self.invalidate_cached([from_dir, to_dir].into_iter().chain(moved.as_ref()));
+        renamed
+    }
+
+    /// Drops cached attributes and access results of `files`.
+    fn invalidate_cached<'a>(&self, files: impl IntoIterator<Item = &'a file::Handle>) {
+        for file in files {
+            self.attrs.invalidate(file);
+            self.access.invalidate(file);
+        }
     }
 
-    async fn rename_cached_path(&self, from: &Path, to: &Path) -> Result<(), vfs::Error> {
-        self.fsmap.write().await.rename_path(from, to)
+    /// Returns attributes of `file` at `path`, served from the attribute cache while fresh.
+    fn cached_attr(&self, file: &file::Handle, path: &Path) -> Result<file::Attr, vfs::Error> {
+        if let Some(attr) = self.attrs.get(file) {
+            return Ok(attr);
+        }
+        self.metadata_calls.fetch_add(1, Ordering::Relaxed);
+        let attr = self.attr_from_metadata(path, &Self::metadata(path)?);
+        self.attrs.insert(file, &attr);
+        Ok(attr)
     }
 
     fn ensure_name_allowed(name: &file::Name) -> Result<(), vfs::Error> {
@@ -108,17 +691,7 @@ impl MirrorFS {
     }
 
     fn io_error_to_vfs(error: &std::io::Error) -> vfs::Error {
-        match error.kind() {
-            ErrorKind::NotFound => vfs::Error::NoEntry,
-            ErrorKind::PermissionDenied => vfs::Error::Access,
-            ErrorKind::AlreadyExists => vfs::Error::Exist,
-            ErrorKind::InvalidInput | ErrorKind::InvalidData => vfs::Error::InvalidArgument,
-            ErrorKind::DirectoryNotEmpty => vfs::Error::NotEmpty,
-            ErrorKind::IsADirectory => vfs::Error::IsDir,
-            ErrorKind::NotADirectory => vfs::Error::NotDir,
-            ErrorKind::WriteZero => vfs::Error::NoSpace,
-            _ => vfs::Error::IO,
-        }
+        map_io_error(error)
     }
 
     fn time_from_unix(seconds: i64, nanos: i64) -> file::Time {
@@ -128,15 +701,11 @@ impl MirrorFS {
         }
     }
 
-    fn system_time_from_file_time(time: file::Time) -> SystemTime {
-        UNIX_EPOCH + Duration::new(u64::from(time.seconds), time.nanos)
-    }
-
     fn same_time(left: file::Time, right: file::Time) -> bool {
         left.seconds == right.seconds && left.nanos == right.nanos
     }
 
-    fn attr_from_metadata(meta: &Metadata) -> file::Attr {
+    fn attr_from_metadata(&self, path: &Path, meta: &Metadata) -> file::Attr {
         let file_type = meta.file_type();
         let file_type = if file_type.is_dir() {
             file::Type::Directory
@@ -163,9 +732,10 @@ impl MirrorFS {
             uid: meta.uid(),
             gid: meta.gid(),
             size: meta.size(),
+            // `st_blocks` counts 512-byte units whatever the block size of the file system.
             used: meta.blocks().saturating_mul(512),
-            device: file::Device { major: 0, minor: 0 },
-            fs_id: meta.dev(),
+            device: Self::device_from_metadata(&file_type, meta),
+            fs_id: self.fs_id(path, meta),
             file_id: meta.ino(),
             atime: Self::time_from_unix(meta.atime(), meta.atime_nsec()),
             mtime: Self::time_from_unix(meta.mtime(), meta.mtime_nsec()),
@@ -173,6 +743,74 @@ impl MirrorFS {
         }
     }
 
+    /// Returns the device number of a block or character special file, zeros for others.
+    #[allow(clippy::unnecessary_cast)]
+    fn device_from_metadata(file_type: &file::Type, meta: &Metadata) -> file::Device {
+        match file_type {
+            file::Type::BlockDevice | file::Type::CharacterDevice => {
+                let rdev = meta.rdev() as libc::dev_t;
+                file::Device { major: libc::major(rdev) as u32, minor: libc::minor(rdev) as u32 }
+            }
+            _ => file::Device { major: 0, minor: 0 },
+        }
+    }
+
+    /// Returns the file system id of the file at `path`: the one configured for its
+    /// export, or the id of the device holding it.
+    fn fs_id(&self, path: &Path, meta: &Metadata) -> u64 {
+        self.export_fsids
+            .iter()
+            .find(|(root, _)| path.starts_with(root))
+            .map_or_else(|| meta.dev(), |&(_, fsid)| fsid)
+    }
+
+    /// Returns the modes of objects created at `path` without a mode from the client.
+    fn create_modes(&self, path: &Path) -> CreateModes {
+        self.export_modes
+            .iter()
+            .find(|(root, _)| path.starts_with(root))
+            .map_or_else(CreateModes::default, |&(_, modes)| modes)
+    }
+
+    /// Creates the regular file `path`, failing if it exists, with permission bits `mode`
+    /// or those the umask leaves if [`None`].
+    ///
+    /// The mode is passed to `open`, so the file never exists with other permissions; bits
+    /// the umask cleared are restored through the new descriptor.
+    async fn create_file(path: &Path, mode: Option<u32>) -> std::io::Result<()> {
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        if let Some(mode) = mode {
+            options.mode(mode);
+        }
+        let file = options.open(path).await?;
+        if let Some(mode) = mode {
+            if file.metadata().await?.mode() & 0o7777 != mode {
+                file.set_permissions(std::fs::Permissions::from_mode(mode)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the directory `path` with permission bits `mode`, or those the umask leaves
+    /// if [`None`].
+    ///
+    /// Like [`Self::create_file`], the mode is given at creation; bits the umask cleared
+    /// are restored afterwards.
+    async fn create_directory(path: &Path, mode: Option<u32>) -> std::io::Result<()> {
+        let mut builder = tokio::fs::DirBuilder::new();
+        if let Some(mode) = mode {
+            builder.mode(mode);
+        }
+        builder.create(path).await?;
+        if let Some(mode) = mode {
+            if std::fs::symlink_metadata(path)?.mode() & 0o7777 != mode {
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+            }
+        }
+        Ok(())
+    }
+
     fn wcc_attr_from_metadata(meta: &Metadata) -> file::WccAttr {
         file::WccAttr {
             size: meta.size(),
@@ -185,10 +823,12 @@ impl MirrorFS {
         std::fs::symlink_metadata(path).map_err(|error| Self::io_error_to_vfs(&error))
     }
 
-    fn wcc_data(path: &Path, before: Option<file::WccAttr>) -> vfs::WccData {
+    fn wcc_data(&self, path: &Path, before: Option<file::WccAttr>) -> vfs::WccData {
         vfs::WccData {
             before,
-            after: std::fs::symlink_metadata(path).ok().map(|meta| Self::attr_from_metadata(&meta)),
+            after: std::fs::symlink_metadata(path)
+                .ok()
+                .map(|meta| self.attr_from_metadata(path, &meta)),
         }
     }
 
@@ -220,8 +860,8 @@ impl MirrorFS {
         data
     }
 
-    fn file_attr(path: &Path) -> Option<file::Attr> {
-        std::fs::symlink_metadata(path).ok().map(|meta| Self::attr_from_metadata(&meta))
+    fn file_attr(&self, path: &Path) -> Option<file::Attr> {
+        std::fs::symlink_metadata(path).ok().map(|meta| self.attr_from_metadata(path, &meta))
     }
 
     /// Stores an exclusive create verifier in the file's mtime (per RFC 1813 §3.3.8).
@@ -245,14 +885,16 @@ impl MirrorFS {
         stored_sec == expected_sec && stored_nsec == expected_nsec
     }
 
-    fn apply_set_attr(path: &Path, new_attr: &set_attr::NewAttr) -> Result<(), vfs::Error> {
+    fn apply_set_attr(&self, path: &Path, new_attr: &set_attr::NewAttr) -> Result<(), vfs::Error> {
         if new_attr.uid.is_some() || new_attr.gid.is_some() {
             return Err(vfs::Error::InvalidArgument);
         }
 
         if let Some(mode) = new_attr.mode {
-            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
-                .map_err(|error| Self::io_error_to_vfs(&error))?;
+            retry_interrupted(|| {
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            })
+            .map_err(|error| Self::io_error_to_vfs(&error))?;
         }
 
         if let Some(size) = new_attr.size {
@@ -260,39 +902,69 @@ impl MirrorFS {
                 .write(true)
                 .open(path)
                 .map_err(|error| Self::io_error_to_vfs(&error))?;
-            file.set_len(size).map_err(|error| Self::io_error_to_vfs(&error))?;
+            retry_interrupted(|| file.set_len(size))
+                .map_err(|error| Self::io_error_to_vfs(&error))?;
         }
 
-        let needs_atime = !matches!(new_attr.atime, set_attr::SetTime::DontChange);
-        let needs_mtime = !matches!(new_attr.mtime, set_attr::SetTime::DontChange);
-        if needs_atime || needs_mtime {
-            let file = std::fs::OpenOptions::new()
-                .read(true)
-                .open(path)
+        if !matches!(
+            (&new_attr.atime, &new_attr.mtime),
+            (set_attr::SetTime::DontChange, set_attr::SetTime::DontChange)
+        ) {
+            self.set_times(path, &new_attr.atime, &new_attr.mtime)
                 .map_err(|error| Self::io_error_to_vfs(&error))?;
-            let meta = file.metadata().map_err(|error| Self::io_error_to_vfs(&error))?;
-            let current_attr = Self::attr_from_metadata(&meta);
-            let atime = match new_attr.atime {
-                set_attr::SetTime::DontChange => {
-                    Self::system_time_from_file_time(current_attr.atime)
-                }
-                set_attr::SetTime::ToServer => SystemTime::now(),
-                set_attr::SetTime::ToClient(time) => Self::system_time_from_file_time(time),
-            };
-            let mtime = match new_attr.mtime {
-                set_attr::SetTime::DontChange => {
-                    Self::system_time_from_file_time(current_attr.mtime)
-                }
-                set_attr::SetTime::ToServer => SystemTime::now(),
-                set_attr::SetTime::ToClient(time) => Self::system_time_from_file_time(time),
-            };
-            let times = std::fs::FileTimes::new().set_accessed(atime).set_modified(mtime);
-            file.set_times(times).map_err(|error| Self::io_error_to_vfs(&error))?;
         }
 
         Ok(())
     }
 
+    /// Updates access and modification times of `path` with a single `utimensat`
+    /// call, so either time can be left untouched, set to the time of [`Self::with_clock`] or set to
+    /// a client-provided value independently of the other. Symlinks are not followed.
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: &set_attr::SetTime,
+        mtime: &set_attr::SetTime,
+    ) -> std::io::Result<()> {
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        let times = [self.utime_spec(atime), self.utime_spec(mtime)];
+        // SAFETY: `c_path` is a valid NUL-terminated string and `times` holds two timespecs.
+        let result = unsafe {
+            libc::utimensat(
+                libc::AT_FDCWD,
+                c_path.as_ptr(),
+                times.as_ptr(),
+                libc::AT_SYMLINK_NOFOLLOW,
+            )
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    fn utime_spec(&self, time: &set_attr::SetTime) -> libc::timespec {
+        match time {
+            set_attr::SetTime::DontChange => {
+                libc::timespec { tv_sec: 0, tv_nsec: libc::UTIME_OMIT }
+            }
+            set_attr::SetTime::ToServer => {
+                let now =
+                    self.clock.system_time().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+                libc::timespec {
+                    tv_sec: now.as_secs() as libc::time_t,
+                    tv_nsec: now.subsec_nanos() as libc::c_long,
+                }
+            }
+            set_attr::SetTime::ToClient(time) => libc::timespec {
+                tv_sec: time.seconds as libc::time_t,
+                tv_nsec: time.nanos as libc::c_long,
+            },
+        }
+    }
+
     fn list_directory_entries(
         &self,
         dir_path: &Path,
@@ -320,9 +992,75 @@ impl MirrorFS {
         name: &file::Name,
     ) -> Result<PathBuf, vfs::Error> {
         let dir_path = self.path_for_handle(dir).await?;
-        let mut child = dir_path;
-        child.push(name.as_str());
-        Ok(child)
+        let path = self.resolve_name(&dir_path, name);
+        self.check_resolution(&path).await?;
+        Ok(path)
+    }
+
+    /// Checks that `path` can be resolved under the export root without building
+    /// an overly long path or following too many symlinks.
+    ///
+    /// Directories of `path` reached through symlinks are counted against
+    /// [`MAX_SYMLINK_FOLLOWS`]; the final component is never followed.
+    async fn check_resolution(&self, path: &Path) -> Result<(), vfs::Error> {
+        let root = self.exported_root_path().await?;
+        let Ok(relative) = path.strip_prefix(&root) else {
+            return Err(vfs::Error::BadFileHandle);
+        };
+        if relative.as_os_str().len() > vfs::MAX_PATH_LEN {
+            return Err(vfs::Error::NameTooLong);
+        }
+
+        let mut current = root;
+        let mut components = relative.components().peekable();
+        let mut follows = 0;
+        while let Some(component) = components.next() {
+            if components.peek().is_none() {
+                break;
+            }
+            current.push(component);
+            match std::fs::symlink_metadata(&current) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    follows += 1;
+                    if follows > MAX_SYMLINK_FOLLOWS {
+                        return Err(vfs::Error::TooManyLevelsOfRemote);
+                    }
+                    // The kernel reports cycles behind the link with `ELOOP`.
+                    std::fs::metadata(&current).map_err(|error| Self::io_error_to_vfs(&error))?;
+                }
+                Ok(_) => {}
+                Err(error) => return Err(Self::io_error_to_vfs(&error)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the path of `name` inside `dir_path`.
+    ///
+    /// In case-insensitive mode an existing entry whose name differs only by case
+    /// is returned with its stored case; otherwise `name` is used verbatim.
+    fn resolve_name(&self, dir_path: &Path, name: &file::Name) -> PathBuf {
+        let exact = dir_path.join(name.as_str());
+        if !self.case_insensitive || std::fs::symlink_metadata(&exact).is_ok() {
+            return exact;
+        }
+        Self::case_variant(dir_path, name).unwrap_or(exact)
+    }
+
+    /// Returns `true` if `name` does not exist in `dir_path`, but differs only by case
+    /// from an existing entry, so creating it would make lookups ambiguous.
+    fn has_case_collision(&self, dir_path: &Path, name: &file::Name) -> bool {
+        self.case_insensitive && self.resolve_name(dir_path, name) != dir_path.join(name.as_str())
+    }
+
+    fn case_variant(dir_path: &Path, name: &file::Name) -> Option<PathBuf> {
+        let wanted = name.as_str().to_lowercase();
+        std::fs::read_dir(dir_path)
+            .ok()?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().to_lowercase() == wanted)
+            .map(|entry| entry.path())
+            .min()
     }
 
     async fn exported_root_path(&self) -> Result<PathBuf, vfs::Error> {
@@ -1,7 +1,61 @@
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
 use nfs_mamont::vfs::{self, path_conf};
 
 use super::MirrorFS;
 
+/// Limits of the file system holding an object, as PATHCONF reports them.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    link_max: u32,
+    name_max: u32,
+    no_trunc: bool,
+    chown_restricted: bool,
+}
+
+/// Limits reported for whatever `pathconf(3)` cannot tell.
+const FALLBACK: Limits = Limits {
+    link_max: u32::MAX,
+    name_max: vfs::MAX_NAME_LEN as u32,
+    no_trunc: true,
+    chown_restricted: true,
+};
+
+impl Limits {
+    /// Queries the limits of the file system holding `path`.
+    ///
+    /// `pathconf` answers `-1` both on errors and for limits which do not exist, so
+    /// such answers keep the fallback. Names longer than the protocol allows are
+    /// never accepted, so `name_max` does not exceed [`vfs::MAX_NAME_LEN`].
+    fn query(path: &Path) -> Self {
+        let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+            return FALLBACK;
+        };
+        // SAFETY: `c_path` is a valid NUL-terminated string.
+        let query = |name| unsafe { libc::pathconf(c_path.as_ptr(), name) };
+        let limit = |name, fallback: u32| match query(name) {
+            -1 => fallback,
+            value => u32::try_from(value).unwrap_or(u32::MAX),
+        };
+        let flag = |name, fallback| match query(name) {
+            -1 => fallback,
+            _ => true,
+        };
+        Self {
+            link_max: limit(libc::_PC_LINK_MAX, FALLBACK.link_max),
+            name_max: limit(libc::_PC_NAME_MAX, FALLBACK.name_max).min(FALLBACK.name_max),
+            no_trunc: flag(libc::_PC_NO_TRUNC, FALLBACK.no_trunc),
+            chown_restricted: flag(libc::_PC_CHOWN_RESTRICTED, FALLBACK.chown_restricted),
+        }
+    }
+
+    async fn query_blocking(path: PathBuf) -> Self {
+        tokio::task::spawn_blocking(move || Self::query(&path)).await.unwrap_or(FALLBACK)
+    }
+}
+
 impl path_conf::PathConf for MirrorFS {
     async fn path_conf(
         &self,
@@ -11,13 +65,14 @@ impl path_conf::PathConf for MirrorFS {
             Ok(path) => path,
             Err(error) => return Err(path_conf::Fail { error, file_attr: None }),
         };
+        let limits = Limits::query_blocking(path.clone()).await;
         Ok(path_conf::Success {
-            file_attr: Self::file_attr(&path),
-            link_max: u32::MAX,
-            name_max: vfs::MAX_NAME_LEN as u32,
-            no_trunc: true,
-            chown_restricted: true,
-            case_insensitive: false,
+            file_attr: self.file_attr(&path),
+            link_max: limits.link_max,
+            name_max: limits.name_max,
+            no_trunc: limits.no_trunc,
+            chown_restricted: limits.chown_restricted,
+            case_insensitive: self.case_insensitive,
             case_preserving: true,
         })
     }
@@ -12,22 +12,24 @@ impl read_dir::ReadDir for MirrorFS {
             Ok(meta) => meta,
             Err(error) => return Err(read_dir::Fail { error, dir_attr: None }),
         };
-        let dir_attr = Self::attr_from_metadata(&dir_meta);
+        let dir_attr = self.attr_from_metadata(&dir_path, &dir_meta);
         if let Err(error) = Self::validate_directory(&dir_attr) {
             return Err(read_dir::Fail { error, dir_attr: Some(dir_attr) });
         }
 
-        let verifier = Self::cookie_verifier_for_attr(&dir_attr);
-        if !args.cookie.is_zero() && args.cookie_verifier != verifier {
-            return Err(read_dir::Fail { error: vfs::Error::BadCookie, dir_attr: Some(dir_attr) });
-        }
-
         let entries = match self.list_directory_entries(&dir_path) {
             Ok(entries) => entries,
             Err(error) => return Err(read_dir::Fail { error, dir_attr: Some(dir_attr) }),
         };
 
+        let verifier = self.cookie_verifier(&dir_attr, &entries);
+        if !self.cookie_is_valid(args.cookie, args.cookie_verifier, verifier) {
+            return Err(read_dir::Fail { error: vfs::Error::BadCookie, dir_attr: Some(dir_attr) });
+        }
+
         let total_entries = entries.len();
+        // Cookies are positions in the listing, which is sorted by name, so resuming
+        // does not depend on how file ids are ordered.
         let start = args.cookie.raw() as usize;
         let mut used = 0u32;
         let mut result = Vec::new();
@@ -36,7 +38,7 @@ impl read_dir::ReadDir for MirrorFS {
             if !result.is_empty() && used.saturating_add(estimated) > args.count {
                 break;
             }
-            let attr = Self::attr_from_metadata(&meta);
+            let attr = self.attr_from_metadata(&path, &meta);
             let _ = self.handle_for_path(&path).await;
             result.push(read_dir::Entry {
                 file_id: attr.file_id,
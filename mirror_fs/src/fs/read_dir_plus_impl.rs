@@ -1,8 +1,25 @@
-use nfs_mamont::consts::nfsv3::NFS3_WRITEVERFSIZE;
+use nfs_mamont::consts::nfsv3::{NFS3_COOKIEVERFSIZE, NFS3_FHSIZE};
 use nfs_mamont::vfs::{self, read_dir, read_dir_plus};
 
 use super::MirrorFS;
 
+/// XDR size of `post_op_attr` carrying `fattr3`.
+const POST_OP_ATTR_SIZE: u32 = 4 + 84;
+/// XDR size of `post_op_fh3` carrying a handle.
+const POST_OP_FH_SIZE: u32 = 4 + 4 + NFS3_FHSIZE as u32;
+/// XDR size of the reply parts outside of entries: status, directory attributes,
+/// cookie verifier, list terminator and `eof` flag.
+const REPLY_OVERHEAD: u32 = 4 + POST_OP_ATTR_SIZE + NFS3_COOKIEVERFSIZE as u32 + 4 + 4;
+
+/// Returns the size of the directory information of an entry (`dircount` budget)
+/// and its full XDR size (`maxcount` budget).
+fn entry_sizes(name: &str) -> (u32, u32) {
+    let padded_name = name.len().div_ceil(4) * 4;
+    // list discriminant, fileid, name length and data, cookie
+    let dir_size = (4 + 8 + 4 + padded_name + 8) as u32;
+    (dir_size, dir_size + POST_OP_ATTR_SIZE + POST_OP_FH_SIZE)
+}
+
 impl read_dir_plus::ReadDirPlus for MirrorFS {
     async fn read_dir_plus(
         &self,
@@ -16,37 +33,60 @@ impl read_dir_plus::ReadDirPlus for MirrorFS {
             Ok(meta) => meta,
             Err(error) => return Err(read_dir_plus::Fail { error, dir_attr: None }),
         };
-        let dir_attr = Self::attr_from_metadata(&dir_meta);
+        let dir_attr = self.attr_from_metadata(&dir_path, &dir_meta);
         if let Err(error) = Self::validate_directory(&dir_attr) {
             return Err(read_dir_plus::Fail { error, dir_attr: Some(dir_attr) });
         }
+        self.attrs.insert(&args.dir, &dir_attr);
 
-        let verifier = Self::cookie_verifier_for_attr(&dir_attr);
-        if !args.cookie.is_zero() && args.cookie_verifier != verifier {
+        let entries = match self.list_directory_entries(&dir_path) {
+            Ok(entries) => entries,
+            Err(error) => return Err(read_dir_plus::Fail { error, dir_attr: Some(dir_attr) }),
+        };
+
+        let verifier = self.cookie_verifier(&dir_attr, &entries);
+        if !self.cookie_is_valid(args.cookie, args.cookie_verifier, verifier) {
             return Err(read_dir_plus::Fail {
                 error: vfs::Error::BadCookie,
                 dir_attr: Some(dir_attr),
             });
         }
 
-        let entries = match self.list_directory_entries(&dir_path) {
-            Ok(entries) => entries,
-            Err(error) => return Err(read_dir_plus::Fail { error, dir_attr: Some(dir_attr) }),
-        };
-
         let start = args.cookie.raw() as usize;
-        let mut used = 0u32;
+        // The byte budgets and the handle count are tracked independently; the reply
+        // ends as soon as any of them is exhausted.
+        let max_handles =
+            self.read_dir_plus_max_handles.map_or(usize::MAX, |max| max.get() as usize);
+        let mut dir_used = 0u32;
+        let mut total_used = REPLY_OVERHEAD;
         let mut result = Vec::new();
         for (index, (name, path, meta)) in entries.iter().cloned().enumerate().skip(start) {
-            let estimated = (48 + name.as_str().len() + NFS3_WRITEVERFSIZE) as u32;
-            if !result.is_empty() && used.saturating_add(estimated) > args.max_count {
+            if result.len() >= max_handles {
+                break;
+            }
+            let (dir_size, full_size) = entry_sizes(name.as_str());
+            // `maxcount` bounds the whole reply, so it is never exceeded.
+            if total_used.saturating_add(full_size) > args.max_count {
+                if result.is_empty() {
+                    return Err(read_dir_plus::Fail {
+                        error: vfs::Error::TooSmall,
+                        dir_attr: Some(dir_attr),
+                    });
+                }
+                break;
+            }
+            // `dircount` only bounds directory information; always return at least one entry.
+            if !result.is_empty() && dir_used.saturating_add(dir_size) > args.dir_count {
                 break;
             }
-            let attr = Self::attr_from_metadata(&meta);
+            let attr = self.attr_from_metadata(&path, &meta);
             let handle = match self.handle_for_path(&path).await {
                 Ok(handle) => handle,
                 Err(error) => return Err(read_dir_plus::Fail { error, dir_attr: Some(dir_attr) }),
             };
+            // Clients usually follow a listing with GETATTR or LOOKUP of its entries.
+            self.attrs.insert(&handle, &attr);
+            self.negative.remove(&args.dir, name.as_str());
             result.push(read_dir_plus::Entry {
                 file_id: attr.file_id,
                 file_name: name,
@@ -54,7 +94,8 @@ impl read_dir_plus::ReadDirPlus for MirrorFS {
                 file_attr: Some(attr),
                 file_handle: Some(handle),
             });
-            used = used.saturating_add(estimated);
+            dir_used = dir_used.saturating_add(dir_size);
+            total_used = total_used.saturating_add(full_size);
         }
 
         Ok(read_dir_plus::Success {
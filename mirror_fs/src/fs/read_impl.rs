@@ -2,13 +2,47 @@ use std::io::SeekFrom;
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-use nfs_mamont::vfs::read;
+use nfs_mamont::vfs::{self, read};
 use nfs_mamont::Buffer;
 
 use super::MirrorFS;
 
 impl<B: Buffer> read::Read<B> for MirrorFS {
-    async fn read(&self, args: read::Args, mut data: B) -> Result<read::Success<B>, read::Fail> {
+    async fn read(
+        &self,
+        cred: &vfs::Credentials,
+        args: read::Args,
+        data: B,
+    ) -> Result<read::Success<B>, read::Fail> {
+        let Some(writes) = &self.writes else {
+            return self.read_file(cred, args, data).await;
+        };
+        let _flushing = self.flushing.read().await;
+        let file = args.file.clone();
+        let mut position = args.offset;
+        let mut success = self.read_file(cred, args, data).await?;
+        let mut remaining = success.head.count as usize;
+        for chunk in success.data.chunks_mut() {
+            if remaining == 0 {
+                break;
+            }
+            let len = chunk.len().min(remaining);
+            writes.overlay(&file, position, &mut chunk[..len]);
+            position += len as u64;
+            remaining -= len;
+        }
+        Ok(success)
+    }
+}
+
+impl MirrorFS {
+    /// Reads from the mirrored file, without data still held in the write buffer.
+    async fn read_file<B: Buffer>(
+        &self,
+        cred: &vfs::Credentials,
+        args: read::Args,
+        mut data: B,
+    ) -> Result<read::Success<B>, read::Fail> {
         let path = match self.path_for_handle(&args.file).await {
             Ok(path) => path,
             Err(error) => {
@@ -21,10 +55,24 @@ impl<B: Buffer> read::Read<B> for MirrorFS {
                 return Err(read::Fail { error, file_attr: None });
             }
         };
-        let attr = Self::attr_from_metadata(&meta);
+        let attr = self.attr_from_metadata(&path, &meta);
         if let Err(error) = Self::validate_regular(&attr) {
             return Err(read::Fail { error, file_attr: Some(attr) });
         }
+        // Clients read the files they execute, so execute permission is enough.
+        if !Self::permits(cred, &attr, 0o4) && !Self::permits(cred, &attr, 0o1) {
+            return Err(read::Fail { error: vfs::Error::Access, file_attr: Some(attr) });
+        }
+
+        let file_len = meta.len();
+        let start = args.offset.min(file_len);
+        let end = args.offset.saturating_add(args.count as u64).min(file_len);
+        let requested = end.saturating_sub(start) as usize;
+
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        if let Some(uring) = &self.uring {
+            return Self::read_with_uring(uring, &path, attr, start, requested, data).await;
+        }
 
         let mut file = match File::open(&path).await {
             Ok(file) => file,
@@ -36,10 +84,6 @@ impl<B: Buffer> read::Read<B> for MirrorFS {
             }
         };
 
-        let file_len = meta.len();
-        let start = args.offset.min(file_len);
-        let end = args.offset.saturating_add(args.count as u64).min(file_len);
-        let requested = end.saturating_sub(start) as usize;
         let mut remaining = requested;
         let mut read_count = 0usize;
         if let Err(error) = file.seek(SeekFrom::Start(start)).await {
@@ -1,3 +1,5 @@
+use std::os::unix::ffi::OsStringExt;
+
 use nfs_mamont::vfs::{self, file, read_link};
 
 use super::MirrorFS;
@@ -19,7 +21,7 @@ impl read_link::ReadLink for MirrorFS {
                 return Err(read_link::Fail { error, symlink_attr: None });
             }
         };
-        let attr = Self::attr_from_metadata(&meta);
+        let attr = self.attr_from_metadata(&path, &meta);
         if !matches!(attr.file_type, file::Type::Symlink) {
             return Err(read_link::Fail {
                 error: vfs::Error::InvalidArgument,
@@ -35,21 +37,14 @@ impl read_link::ReadLink for MirrorFS {
                 });
             }
         };
-        let target_str = match target.to_str() {
-            Some(s) => s.to_owned(),
-            None => {
-                return Err(read_link::Fail { error: vfs::Error::IO, symlink_attr: Some(attr) });
-            }
-        };
-        let data = match file::Path::new(target_str) {
-            Ok(path) => path,
-            Err(_) => {
-                return Err(read_link::Fail {
-                    error: vfs::Error::InvalidArgument,
-                    symlink_attr: Some(attr),
-                });
-            }
-        };
+        // Targets are passed on as stored, so ones that are not UTF-8 stay readable.
+        let data = target.into_os_string().into_vec();
+        if data.len() > vfs::MAX_PATH_LEN {
+            return Err(read_link::Fail {
+                error: vfs::Error::InvalidArgument,
+                symlink_attr: Some(attr),
+            });
+        }
 
         Ok(read_link::Success { symlink_attr: Some(attr), data })
     }
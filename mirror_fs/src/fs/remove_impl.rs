@@ -5,14 +5,11 @@ use nfs_mamont::vfs::{self, remove};
 use super::MirrorFS;
 
 impl remove::Remove for MirrorFS {
-    async fn remove(&self, args: remove::Args) -> Result<remove::Success, remove::Fail> {
-        if let Err(error) = Self::ensure_name_allowed(&args.object.name) {
-            return Err(remove::Fail {
-                error,
-                dir_wcc: vfs::WccData { before: None, after: None },
-            });
-        }
-
+    async fn remove(
+        &self,
+        cred: &vfs::Credentials,
+        args: remove::Args,
+    ) -> Result<remove::Success, remove::Fail> {
         let dir_path = match self.path_for_handle(&args.object.dir).await {
             Ok(path) => path,
             Err(error) => {
@@ -25,33 +22,45 @@ impl remove::Remove for MirrorFS {
         let before = std::fs::symlink_metadata(&dir_path)
             .ok()
             .map(|meta| Self::wcc_attr_from_metadata(&meta));
+        if let Err(error) = Self::ensure_name_allowed(&args.object.name) {
+            return Err(remove::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
+        let dir_attr = match self.writable_dir(cred, &dir_path) {
+            Ok(attr) => attr,
+            Err(error) => {
+                return Err(remove::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+            }
+        };
         let child_path = match self.child_path(&args.object.dir, &args.object.name).await {
             Ok(path) => path,
             Err(error) => {
-                return Err(remove::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) });
+                return Err(remove::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
             }
         };
         let child_meta = match Self::metadata(&child_path) {
             Ok(meta) => meta,
             Err(error) => {
-                return Err(remove::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) });
+                return Err(remove::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
             }
         };
         if child_meta.is_dir() {
             return Err(remove::Fail {
                 error: vfs::Error::IsDir,
-                dir_wcc: Self::wcc_data(&dir_path, before),
+                dir_wcc: self.wcc_data(&dir_path, before),
             });
         }
+        if let Err(error) = Self::check_sticky(cred, &dir_attr, &child_path) {
+            return Err(remove::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
 
         if let Err(error) = fs::remove_file(&child_path).await {
             return Err(remove::Fail {
                 error: Self::io_error_to_vfs(&error),
-                dir_wcc: Self::wcc_data(&dir_path, before),
+                dir_wcc: self.wcc_data(&dir_path, before),
             });
         }
-        self.remove_cached_path(&child_path).await;
+        self.remove_cached_path(&args.object.dir, &child_path, &child_meta).await;
 
-        Ok(remove::Success { wcc_data: Self::wcc_data(&dir_path, before) })
+        Ok(remove::Success { wcc_data: self.wcc_data(&dir_path, before) })
     }
 }
@@ -1,3 +1,5 @@
+use std::os::unix::fs::MetadataExt;
+
 use tokio::fs;
 
 use nfs_mamont::vfs::{self, rename};
@@ -5,7 +7,11 @@ use nfs_mamont::vfs::{self, rename};
 use super::MirrorFS;
 
 impl rename::Rename for MirrorFS {
-    async fn rename(&self, args: rename::Args) -> Result<rename::Success, rename::Fail> {
+    async fn rename(
+        &self,
+        cred: &vfs::Credentials,
+        args: rename::Args,
+    ) -> Result<rename::Success, rename::Fail> {
         if matches!(args.from.name.as_str(), "." | "..")
             || matches!(args.to.name.as_str(), "." | "..")
         {
@@ -40,19 +46,40 @@ impl rename::Rename for MirrorFS {
         let to_before_meta = std::fs::symlink_metadata(&to_dir_path).ok();
         let from_before = from_before_meta.as_ref().map(Self::wcc_attr_from_metadata);
         let to_before = to_before_meta.as_ref().map(Self::wcc_attr_from_metadata);
-        let from_before_after = from_before_meta.as_ref().map(Self::attr_from_metadata);
-        let to_before_after = to_before_meta.as_ref().map(Self::attr_from_metadata);
+        let from_before_after =
+            from_before_meta.as_ref().map(|meta| self.attr_from_metadata(&from_dir_path, meta));
+        let to_before_after =
+            to_before_meta.as_ref().map(|meta| self.attr_from_metadata(&to_dir_path, meta));
 
-        let mut from_path = from_dir_path.clone();
-        from_path.push(args.from.name.as_str());
-        let mut to_path = to_dir_path.clone();
-        to_path.push(args.to.name.as_str());
+        // Both directories change, so the caller needs write and search permission on each.
+        let dir_attrs = self
+            .writable_dir(cred, &from_dir_path)
+            .and_then(|from| Ok((from, self.writable_dir(cred, &to_dir_path)?)));
+        let (from_dir_attr, to_dir_attr) = match dir_attrs {
+            Ok(attrs) => attrs,
+            Err(error) => {
+                return Err(rename::Fail {
+                    error,
+                    from_dir_wcc: vfs::WccData { before: from_before, after: from_before_after },
+                    to_dir_wcc: vfs::WccData { before: to_before, after: to_before_after },
+                });
+            }
+        };
 
-        if from_path == to_path {
-            return Ok(rename::Success {
-                from_dir_wcc: vfs::WccData { before: from_before, after: from_before_after },
-                to_dir_wcc: vfs::WccData { before: to_before, after: to_before_after },
-            });
+        let from_path = self.resolve_name(&from_dir_path, &args.from.name);
+        let mut to_path = self.resolve_name(&to_dir_path, &args.to.name);
+        if to_path == from_path {
+            // Renaming onto itself may still change the case of the stored name.
+            to_path = to_dir_path.join(args.to.name.as_str());
+        }
+        for path in [&from_path, &to_path] {
+            if let Err(error) = self.check_resolution(path).await {
+                return Err(rename::Fail {
+                    error,
+                    from_dir_wcc: vfs::WccData { before: from_before, after: from_before_after },
+                    to_dir_wcc: vfs::WccData { before: to_before, after: to_before_after },
+                });
+            }
         }
 
         let from_meta = match Self::metadata(&from_path) {
@@ -66,7 +93,31 @@ impl rename::Rename for MirrorFS {
             }
         };
 
-        if let Ok(target_meta) = Self::metadata(&to_path) {
+        let target_meta = Self::metadata(&to_path).ok();
+        let sticky =
+            Self::check_sticky(cred, &from_dir_attr, &from_path).and_then(|()| match target_meta {
+                Some(_) => Self::check_sticky(cred, &to_dir_attr, &to_path),
+                None => Ok(()),
+            });
+        if let Err(error) = sticky {
+            return Err(rename::Fail {
+                error,
+                from_dir_wcc: vfs::WccData { before: from_before, after: from_before_after },
+                to_dir_wcc: vfs::WccData { before: to_before, after: to_before_after },
+            });
+        }
+        // Names already referring to the same file, the same name included, are left in
+        // place as POSIX requires: neither the file system nor the handle registry change.
+        let same_file = target_meta.as_ref().is_some_and(|target| {
+            (target.dev(), target.ino()) == (from_meta.dev(), from_meta.ino())
+        });
+        if from_path == to_path || same_file {
+            return Ok(rename::Success {
+                from_dir_wcc: vfs::WccData { before: from_before, after: from_before_after },
+                to_dir_wcc: vfs::WccData { before: to_before, after: to_before_after },
+            });
+        }
+        if let Some(target_meta) = &target_meta {
             let compatible = from_meta.is_dir() == target_meta.is_dir();
             if !compatible {
                 return Err(rename::Fail {
@@ -79,7 +130,7 @@ impl rename::Rename for MirrorFS {
                 if let Ok(mut iter) = std::fs::read_dir(&to_path) {
                     if iter.next().is_some() {
                         return Err(rename::Fail {
-                            error: vfs::Error::Exist,
+                            error: vfs::Error::NotEmpty,
                             from_dir_wcc: vfs::WccData {
                                 before: from_before,
                                 after: from_before_after,
@@ -89,28 +140,35 @@ impl rename::Rename for MirrorFS {
                     }
                 }
             }
-            self.remove_cached_path(&to_path).await;
         }
 
+        // A target that is busy or on another file system makes the rename fail, so its
+        // handle is only forgotten once the rename went through.
         if let Err(error) = fs::rename(&from_path, &to_path).await {
             return Err(rename::Fail {
                 error: Self::io_error_to_vfs(&error),
-                from_dir_wcc: Self::wcc_data(&from_dir_path, from_before),
-                to_dir_wcc: Self::wcc_data(&to_dir_path, to_before),
+                from_dir_wcc: self.wcc_data(&from_dir_path, from_before),
+                to_dir_wcc: self.wcc_data(&to_dir_path, to_before),
             });
         }
+        if let Some(target_meta) = &target_meta {
+            self.remove_cached_path(&args.to.dir, &to_path, target_meta).await;
+        }
 
-        if let Err(error) = self.rename_cached_path(&from_path, &to_path).await {
+        let renamed = self
+            .rename_cached_path(&args.from.dir, &args.to.dir, &from_path, &to_path, &from_meta)
+            .await;
+        if let Err(error) = renamed {
             return Err(rename::Fail {
                 error,
-                from_dir_wcc: Self::wcc_data(&from_dir_path, from_before),
-                to_dir_wcc: Self::wcc_data(&to_dir_path, to_before),
+                from_dir_wcc: self.wcc_data(&from_dir_path, from_before),
+                to_dir_wcc: self.wcc_data(&to_dir_path, to_before),
             });
         }
 
         Ok(rename::Success {
-            from_dir_wcc: Self::wcc_data(&from_dir_path, from_before),
-            to_dir_wcc: Self::wcc_data(&to_dir_path, to_before),
+            from_dir_wcc: self.wcc_data(&from_dir_path, from_before),
+            to_dir_wcc: self.wcc_data(&to_dir_path, to_before),
         })
     }
 }
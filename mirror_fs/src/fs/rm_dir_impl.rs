@@ -1,22 +1,14 @@
 use nfs_mamont::vfs::{self, rm_dir};
 
 use super::MirrorFS;
+use crate::io_error::retry_interrupted;
 
 impl rm_dir::RmDir for MirrorFS {
-    async fn rm_dir(&self, args: rm_dir::Args) -> Result<rm_dir::Success, rm_dir::Fail> {
-        if args.object.name.as_str() == "." {
-            return Err(rm_dir::Fail {
-                error: vfs::Error::InvalidArgument,
-                dir_wcc: vfs::WccData { before: None, after: None },
-            });
-        }
-        if args.object.name.as_str() == ".." {
-            return Err(rm_dir::Fail {
-                error: vfs::Error::InvalidArgument,
-                dir_wcc: vfs::WccData { before: None, after: None },
-            });
-        }
-
+    async fn rm_dir(
+        &self,
+        cred: &vfs::Credentials,
+        args: rm_dir::Args,
+    ) -> Result<rm_dir::Success, rm_dir::Fail> {
         let dir_path = match self.path_for_handle(&args.object.dir).await {
             Ok(path) => path,
             Err(error) => {
@@ -29,33 +21,48 @@ impl rm_dir::RmDir for MirrorFS {
         let before = std::fs::symlink_metadata(&dir_path)
             .ok()
             .map(|meta| Self::wcc_attr_from_metadata(&meta));
+        if matches!(args.object.name.as_str(), "." | "..") {
+            return Err(rm_dir::Fail {
+                error: vfs::Error::InvalidArgument,
+                dir_wcc: self.wcc_data(&dir_path, before),
+            });
+        }
+        let dir_attr = match self.writable_dir(cred, &dir_path) {
+            Ok(attr) => attr,
+            Err(error) => {
+                return Err(rm_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
+            }
+        };
         let child_path = match self.child_path(&args.object.dir, &args.object.name).await {
             Ok(path) => path,
             Err(error) => {
-                return Err(rm_dir::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) })
+                return Err(rm_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
             }
         };
         let child_meta = match Self::metadata(&child_path) {
             Ok(meta) => meta,
             Err(error) => {
-                return Err(rm_dir::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) })
+                return Err(rm_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
             }
         };
         if !child_meta.is_dir() {
             return Err(rm_dir::Fail {
                 error: vfs::Error::NotDir,
-                dir_wcc: Self::wcc_data(&dir_path, before),
+                dir_wcc: self.wcc_data(&dir_path, before),
             });
         }
+        if let Err(error) = Self::check_sticky(cred, &dir_attr, &child_path) {
+            return Err(rm_dir::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
 
-        match std::fs::remove_dir(&child_path) {
+        match retry_interrupted(|| std::fs::remove_dir(&child_path)) {
             Ok(()) => {
-                self.remove_cached_path(&child_path).await;
-                Ok(rm_dir::Success { wcc_data: Self::wcc_data(&dir_path, before) })
+                self.remove_cached_path(&args.object.dir, &child_path, &child_meta).await;
+                Ok(rm_dir::Success { wcc_data: self.wcc_data(&dir_path, before) })
             }
             Err(error) => Err(rm_dir::Fail {
                 error: Self::io_error_to_vfs(&error),
-                dir_wcc: Self::wcc_data(&dir_path, before),
+                dir_wcc: self.wcc_data(&dir_path, before),
             }),
         }
     }
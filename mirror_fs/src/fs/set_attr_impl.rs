@@ -3,7 +3,11 @@ use nfs_mamont::vfs::{self, set_attr};
 use super::MirrorFS;
 
 impl set_attr::SetAttr for MirrorFS {
-    async fn set_attr(&self, args: set_attr::Args) -> Result<set_attr::Success, set_attr::Fail> {
+    async fn set_attr(
+        &self,
+        cred: &vfs::Credentials,
+        args: set_attr::Args,
+    ) -> Result<set_attr::Success, set_attr::Fail> {
         let path = match self.path_for_handle(&args.file).await {
             Ok(path) => path,
             Err(error) => {
@@ -23,7 +27,7 @@ impl set_attr::SetAttr for MirrorFS {
             }
         };
         let before = Some(Self::wcc_attr_from_metadata(&meta));
-        let current_attr = Self::attr_from_metadata(&meta);
+        let current_attr = self.attr_from_metadata(&path, &meta);
 
         if let Some(guard) = args.guard {
             if !Self::same_time(current_attr.ctime, guard.ctime) {
@@ -34,10 +38,45 @@ impl set_attr::SetAttr for MirrorFS {
             }
         }
 
-        if let Err(error) = Self::apply_set_attr(&path, &args.new_attr) {
-            return Err(set_attr::Fail { error, wcc_data: Self::wcc_data(&path, before) });
+        // Opening anything but a regular file to truncate it would fail or, for a symlink,
+        // truncate its target, after the other changes were already applied.
+        if args.new_attr.size.is_some() && !meta.is_file() {
+            let error = if meta.is_dir() { vfs::Error::IsDir } else { vfs::Error::InvalidArgument };
+            return Err(set_attr::Fail {
+                error,
+                wcc_data: vfs::WccData { before, after: Some(current_attr) },
+            });
         }
 
-        Ok(set_attr::Success { wcc_data: Self::wcc_data(&path, before) })
+        let new_attr = args.new_attr;
+        let (uid, gid) = (new_attr.uid, new_attr.gid);
+        let checked = Self::check_owner_change(cred, &current_attr, &new_attr)
+            .and_then(|()| Self::check_attr_change(cred, &current_attr, &new_attr));
+        if let Err(error) = checked {
+            return Err(set_attr::Fail { error, wcc_data: self.wcc_data(&path, before) });
+        }
+        // Buffered writes past the new size must not extend the file again later.
+        if new_attr.size.is_some() {
+            if let Err(error) = self.flush_buffered(&args.file).await {
+                return Err(set_attr::Fail {
+                    error: Self::io_error_to_vfs(&error),
+                    wcc_data: self.wcc_data(&path, before),
+                });
+            }
+        }
+        let new_attr = set_attr::NewAttr { uid: None, gid: None, ..new_attr };
+        let applied =
+            Self::apply_owner(&path, uid, gid).and_then(|()| self.apply_set_attr(&path, &new_attr));
+        self.attrs.invalidate(&args.file);
+        self.access.invalidate(&args.file);
+        if let Err(error) = applied {
+            return Err(set_attr::Fail { error, wcc_data: self.wcc_data(&path, before) });
+        }
+
+        // The after image is stat'ed anew, so it shows what actually took effect.
+        Ok(set_attr::Success {
+            wcc_data: self.wcc_data(&path, before),
+            ignored: set_attr::Ignored::default(),
+        })
     }
 }
@@ -1,9 +1,14 @@
 use nfs_mamont::vfs::{self, symlink};
 
 use super::MirrorFS;
+use crate::io_error::retry_interrupted;
 
 impl symlink::Symlink for MirrorFS {
-    async fn symlink(&self, args: symlink::Args) -> Result<symlink::Success, symlink::Fail> {
+    async fn symlink(
+        &self,
+        cred: &vfs::Credentials,
+        args: symlink::Args,
+    ) -> Result<symlink::Success, symlink::Fail> {
         if let Err(error) = Self::ensure_name_allowed(&args.object.name) {
             return Err(symlink::Fail {
                 error,
@@ -23,36 +28,46 @@ impl symlink::Symlink for MirrorFS {
         let before = std::fs::symlink_metadata(&dir_path)
             .ok()
             .map(|meta| Self::wcc_attr_from_metadata(&meta));
+        if let Err(error) = self.writable_dir(cred, &dir_path) {
+            return Err(symlink::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
         let mut link_path = dir_path.clone();
         link_path.push(args.object.name.as_str());
 
-        match std::os::unix::fs::symlink(args.path.as_path(), &link_path) {
+        match retry_interrupted(|| std::os::unix::fs::symlink(args.path.as_path(), &link_path)) {
             Ok(()) => {}
             Err(error) => {
                 return Err(symlink::Fail {
                     error: Self::io_error_to_vfs(&error),
-                    dir_wcc: Self::wcc_data(&dir_path, before),
+                    dir_wcc: self.wcc_data(&dir_path, before),
                 });
             }
         }
 
+        self.negative.invalidate(&args.object.dir);
+        self.attrs.invalidate(&args.object.dir);
+        if let Err(error) = Self::own_new_object(cred, &link_path) {
+            return Err(symlink::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) });
+        }
+
+        self.access.invalidate(&args.object.dir);
         let attr = match Self::metadata(&link_path) {
-            Ok(meta) => Self::attr_from_metadata(&meta),
+            Ok(meta) => self.attr_from_metadata(&link_path, &meta),
             Err(error) => {
-                return Err(symlink::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) })
+                return Err(symlink::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
             }
         };
         let handle = match self.handle_for_path(&link_path).await {
             Ok(handle) => handle,
             Err(error) => {
-                return Err(symlink::Fail { error, dir_wcc: Self::wcc_data(&dir_path, before) })
+                return Err(symlink::Fail { error, dir_wcc: self.wcc_data(&dir_path, before) })
             }
         };
 
         Ok(symlink::Success {
             file: Some(handle),
             attr: Some(attr),
-            wcc_data: Self::wcc_data(&dir_path, before),
+            wcc_data: self.wcc_data(&dir_path, before),
         })
     }
 }
@@ -0,0 +1,81 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use nfs_mamont::vfs::{file, read, write};
+use nfs_mamont::Buffer;
+
+use super::MirrorFS;
+use crate::uring::UringBackend;
+
+impl MirrorFS {
+    /// Reads `len` bytes at `start` through io_uring into `data`.
+    pub(super) async fn read_with_uring<B: Buffer>(
+        uring: &UringBackend,
+        path: &Path,
+        attr: file::Attr,
+        start: u64,
+        len: usize,
+        mut data: B,
+    ) -> Result<read::Success<B>, read::Fail> {
+        let file_len = attr.size;
+        let result = match File::open(path) {
+            Ok(file) => uring.read_at(&Arc::new(file), len, start).await,
+            Err(error) => Err(error),
+        };
+        let bytes = match result {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return Err(read::Fail {
+                    error: Self::io_error_to_vfs(&error),
+                    file_attr: Some(attr),
+                });
+            }
+        };
+
+        let mut remaining = bytes.as_slice();
+        for chunk in data.chunks_mut() {
+            if remaining.is_empty() {
+                break;
+            }
+            let count = chunk.len().min(remaining.len());
+            chunk[..count].copy_from_slice(&remaining[..count]);
+            remaining = &remaining[count..];
+        }
+
+        Ok(read::Success {
+            head: read::SuccessPartial {
+                file_attr: Some(attr),
+                count: bytes.len() as u32,
+                eof: start.saturating_add(bytes.len() as u64) >= file_len,
+            },
+            data,
+        })
+    }
+
+    /// io_uring counterpart of [`MirrorFS::write_at_path`].
+    pub(super) async fn write_with_uring(
+        uring: &UringBackend,
+        path: &Path,
+        data: Vec<u8>,
+        offset: u64,
+        stable: write::StableHow,
+    ) -> io::Result<(usize, write::StableHow)> {
+        let file = Arc::new(OpenOptions::new().write(true).truncate(false).open(path)?);
+        let written = uring.write_all_at(&file, data, offset).await?;
+        match stable {
+            write::StableHow::Unstable => {}
+            write::StableHow::DataSync => uring.sync(&file, true).await?,
+            write::StableHow::FileSync => uring.sync(&file, false).await?,
+        }
+        Ok((written, stable))
+    }
+
+    /// Flushes the file's data; unlike [`MirrorFS::sync_ranges`] this is not narrowed
+    /// to the committed ranges.
+    pub(super) async fn sync_with_uring(uring: &UringBackend, path: &Path) -> io::Result<()> {
+        let file = Arc::new(OpenOptions::new().write(true).open(path)?);
+        uring.sync(&file, true).await
+    }
+}
@@ -1,14 +1,21 @@
-use std::io::SeekFrom;
-use tokio::fs::OpenOptions;
-use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
 
-use nfs_mamont::vfs::{self, write};
+use nfs_mamont::vfs::{self, file, write};
 use nfs_mamont::Buffer;
 
 use super::MirrorFS;
+use crate::io_error::retry_interrupted;
+use crate::write_buffer::{Flush, WriteBuffer};
 
 impl<B: Buffer> write::Write<B> for MirrorFS {
-    async fn write(&self, args: write::Args<B>) -> Result<write::Success, write::Fail> {
+    async fn write(
+        &self,
+        cred: &vfs::Credentials,
+        args: write::Args<B>,
+    ) -> Result<write::Success, write::Fail> {
         let path = match self.path_for_handle(&args.file).await {
             Ok(path) => path,
             Err(error) => {
@@ -21,52 +28,178 @@ impl<B: Buffer> write::Write<B> for MirrorFS {
 
         let before_meta = std::fs::symlink_metadata(&path).ok();
         let before = before_meta.as_ref().map(Self::wcc_attr_from_metadata);
-        if let Some(attr) = before_meta.as_ref().map(Self::attr_from_metadata) {
+        if let Some(attr) = before_meta.as_ref().map(|meta| self.attr_from_metadata(&path, meta)) {
             if let Err(error) = Self::validate_regular(&attr) {
-                return Err(write::Fail { error, wcc_data: Self::wcc_data(&path, before) });
+                return Err(write::Fail { error, wcc_data: self.wcc_data(&path, before) });
+            }
+            if !Self::can_write(cred, &attr) {
+                return Err(write::Fail {
+                    error: vfs::Error::Access,
+                    wcc_data: self.wcc_data(&path, before),
+                });
             }
         }
 
-        let mut file = match OpenOptions::new().write(true).truncate(false).open(&path).await {
-            Ok(file) => file,
+        let data = Self::collect_buffer_bytes(&args.data, args.size);
+        let stable = self.durability.cap(args.stable);
+        let offset = args.offset;
+        let result = match &self.writes {
+            Some(writes) if stable == write::StableHow::Unstable => {
+                self.buffer_write(writes, &args.file, &path, data, offset).await
+            }
+            _ => match self.flush_buffered(&args.file).await {
+                Ok(()) => self.write_data(path.clone(), data, offset, stable).await,
+                Err(error) => Err(error),
+            },
+        };
+        self.attrs.invalidate(&args.file);
+        self.access.invalidate(&args.file);
+        let (count, committed) = match result {
+            Ok(written) => written,
             Err(error) => {
                 return Err(write::Fail {
                     error: Self::io_error_to_vfs(&error),
-                    wcc_data: Self::wcc_data(&path, before),
+                    wcc_data: self.wcc_data(&path, before),
                 });
             }
         };
 
-        let data = Self::collect_buffer_bytes(&args.data, args.size);
-        if let Err(error) = file.seek(SeekFrom::Start(args.offset)).await {
-            return Err(write::Fail {
-                error: Self::io_error_to_vfs(&error),
-                wcc_data: Self::wcc_data(&path, before),
-            });
+        match committed {
+            write::StableHow::Unstable => self.dirty.record(&args.file, offset, count as u64),
+            write::StableHow::DataSync | write::StableHow::FileSync => {
+                self.record_sync();
+                self.dirty.clear(&args.file);
+            }
+        }
+
+        Ok(write::Success {
+            file_wcc: self.wcc_data(&path, before),
+            count: count as u32,
+            committed,
+            verifier: self.write_verifier(),
+        })
+    }
+}
+
+impl MirrorFS {
+    /// Keeps `data` in the write buffer, extending the file so its size already
+    /// accounts for the buffered bytes.
+    async fn buffer_write(
+        &self,
+        writes: &WriteBuffer,
+        file: &file::Handle,
+        path: &Path,
+        data: Vec<u8>,
+        offset: u64,
+    ) -> io::Result<(usize, write::StableHow)> {
+        let end = offset
+            .checked_add(data.len() as u64)
+            .ok_or_else(|| io::Error::from(ErrorKind::InvalidInput))?;
+        let handle = OpenOptions::new().write(true).truncate(false).open(path)?;
+        if handle.metadata()?.len() < end {
+            handle.set_len(end)?;
         }
-        if let Err(error) = file.write_all(&data).await {
-            return Err(write::Fail {
-                error: Self::io_error_to_vfs(&error),
-                wcc_data: Self::wcc_data(&path, before),
-            });
+        match writes.insert(file, offset, &data) {
+            Flush::None => {}
+            Flush::File => self.flush_buffered(file).await?,
+            Flush::All => {
+                for file in writes.files() {
+                    self.flush_buffered(&file).await?;
+                }
+            }
         }
-        let sync_result = match args.stable {
-            write::StableHow::Unstable => Ok(()),
-            write::StableHow::DataSync => file.sync_data().await,
-            write::StableHow::FileSync => file.sync_all().await,
+        Ok((data.len(), write::StableHow::Unstable))
+    }
+
+    /// Writes the buffered data of `file` to disk.
+    pub(super) async fn flush_buffered(&self, file: &file::Handle) -> io::Result<()> {
+        let Some(writes) = &self.writes else {
+            return Ok(());
         };
-        if let Err(error) = sync_result {
-            return Err(write::Fail {
-                error: Self::io_error_to_vfs(&error),
-                wcc_data: Self::wcc_data(&path, before),
-            });
+        let _flushing = self.flushing.write().await;
+        let extents = writes.take(file);
+        if extents.is_empty() {
+            return Ok(());
         }
+        // Data of a file removed in the meantime has nowhere to go.
+        let Ok(path) = self.path_for_handle(file).await else {
+            return Ok(());
+        };
+        let mut extents = extents.into_iter();
+        while let Some((offset, data)) = extents.next() {
+            // The data goes back into the buffer if it fails to reach the file, so a
+            // later flush can retry it instead of losing it.
+            let result = self
+                .write_data(path.clone(), data.clone(), offset, write::StableHow::Unstable)
+                .await;
+            if let Err(error) = result {
+                writes.restore(file, std::iter::once((offset, data)).chain(extents).collect());
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
 
-        Ok(write::Success {
-            file_wcc: Self::wcc_data(&path, before),
-            count: data.len() as u32,
-            committed: args.stable,
-            verifier: self.write_verifier(),
+    /// Writes `data` through io_uring when enabled, on a blocking thread otherwise.
+    async fn write_data(
+        &self,
+        path: PathBuf,
+        data: Vec<u8>,
+        offset: u64,
+        stable: write::StableHow,
+    ) -> io::Result<(usize, write::StableHow)> {
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        if let Some(uring) = &self.uring {
+            return Self::write_with_uring(uring, &path, data, offset, stable).await;
+        }
+        tokio::task::spawn_blocking(move || {
+            retry_interrupted(|| Self::write_at_path(path.clone(), &data, offset, stable))
         })
+        .await
+        .unwrap_or_else(|error| Err(io::Error::other(error)))
+    }
+
+    /// Writes `data` at `offset` with positioned writes, so that concurrent writers to
+    /// the same file never race on a shared file position.
+    ///
+    /// Short writes are retried until all bytes land. Returns the number of bytes
+    /// actually written together with the stability the data reached, which is what
+    /// the client must be told: data left in the page cache is only `Unstable`.
+    fn write_at_path(
+        path: PathBuf,
+        data: &[u8],
+        offset: u64,
+        stable: write::StableHow,
+    ) -> io::Result<(usize, write::StableHow)> {
+        let file = OpenOptions::new().write(true).truncate(false).open(path)?;
+        let written = Self::write_all_at(&file, data, offset)?;
+        let committed = match stable {
+            write::StableHow::Unstable => write::StableHow::Unstable,
+            write::StableHow::DataSync => {
+                file.sync_data()?;
+                write::StableHow::DataSync
+            }
+            write::StableHow::FileSync => {
+                file.sync_all()?;
+                write::StableHow::FileSync
+            }
+        };
+        Ok((written, committed))
+    }
+
+    fn write_all_at(file: &File, data: &[u8], offset: u64) -> io::Result<usize> {
+        let mut written = 0;
+        while written < data.len() {
+            let position = offset
+                .checked_add(written as u64)
+                .ok_or_else(|| io::Error::from(ErrorKind::InvalidInput))?;
+            match file.write_at(&data[written..], position) {
+                Ok(0) => return Err(io::Error::from(ErrorKind::WriteZero)),
+                Ok(count) => written += count,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(written)
     }
 }
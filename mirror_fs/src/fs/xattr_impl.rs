@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+use nfs_mamont::vfs::xattr::{self, VfsXattr};
+use nfs_mamont::vfs::{self, file};
+
+use super::MirrorFS;
+
+/// Namespace of attributes any caller may read and, with write access, change; the
+/// others, such as `trusted.` and `security.`, are reserved for the superuser.
+const USER_NAMESPACE: &str = "user.";
+
+impl VfsXattr for MirrorFS {
+    async fn get_xattr(
+        &self,
+        cred: &vfs::Credentials,
+        file: &file::Handle,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, vfs::Error> {
+        let path = self.xattr_path_for_access(cred, file, name, 0o4).await?;
+        ::xattr::get(&path, name).map_err(|error| Self::io_error_to_vfs(&error))
+    }
+
+    async fn set_xattr(
+        &self,
+        cred: &vfs::Credentials,
+        file: &file::Handle,
+        name: &str,
+        value: &[u8],
+    ) -> Result<(), vfs::Error> {
+        if value.len() > xattr::MAX_VALUE_LEN {
+            return Err(vfs::Error::InvalidArgument);
+        }
+        let path = self.xattr_path_for_access(cred, file, name, 0o2).await?;
+        let result = ::xattr::set(&path, name, value);
+        self.invalidate_xattr_change(file);
+        result.map_err(|error| Self::io_error_to_vfs(&error))
+    }
+
+    async fn list_xattr(
+        &self,
+        cred: &vfs::Credentials,
+        file: &file::Handle,
+    ) -> Result<Vec<String>, vfs::Error> {
+        let path = self.path_for_handle(file).await?;
+        let names = ::xattr::list(&path).map_err(|error| Self::io_error_to_vfs(&error))?;
+        Ok(names
+            .map(|name| name.to_string_lossy().into_owned())
+            .filter(|name| cred.uid == 0 || name.starts_with(USER_NAMESPACE))
+            .collect())
+    }
+
+    async fn remove_xattr(
+        &self,
+        cred: &vfs::Credentials,
+        file: &file::Handle,
+        name: &str,
+    ) -> Result<(), vfs::Error> {
+        let path = self.xattr_path_for_access(cred, file, name, 0o2).await?;
+        let result = ::xattr::remove(&path, name);
+        self.invalidate_xattr_change(file);
+        match result {
+            Ok(()) => Ok(()),
+            Err(error) if error.raw_os_error() == Some(libc::ENODATA) => Err(vfs::Error::NoEntry),
+            Err(error) => Err(Self::io_error_to_vfs(&error)),
+        }
+    }
+}
+
+impl MirrorFS {
+    fn check_xattr_name(name: &str) -> Result<(), vfs::Error> {
+        match name.len() {
+            0 => Err(vfs::Error::InvalidArgument),
+            len if len > xattr::MAX_NAME_LEN => Err(vfs::Error::NameTooLong),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolves `file` for reading (`rw` of `0o4`) or changing (`0o2`) its attribute
+    /// `name`, checking that `cred` may do so.
+    async fn xattr_path_for_access(
+        &self,
+        cred: &vfs::Credentials,
+        file: &file::Handle,
+        name: &str,
+        rw: u32,
+    ) -> Result<PathBuf, vfs::Error> {
+        Self::check_xattr_name(name)?;
+        let path = self.path_for_handle(file).await?;
+        if cred.uid == 0 {
+            return Ok(path);
+        }
+        if !name.starts_with(USER_NAMESPACE) {
+            return Err(vfs::Error::Permission);
+        }
+        if !Self::permits(cred, &self.cached_attr(file, &path)?, rw) {
+            return Err(vfs::Error::Access);
+        }
+        Ok(path)
+    }
+
+    /// Drops cached results an attribute change may invalidate: the ctime moves, and
+    /// `system.posix_acl_*` attributes change what ACCESS grants.
+    fn invalidate_xattr_change(&self, file: &file::Handle) {
+        self.attrs.invalidate(file);
+        self.access.invalidate(file);
+    }
+}
@@ -1,19 +1,45 @@
 use std::collections::{BTreeSet, HashMap};
+use std::fs::Metadata;
 use std::os::unix::fs::MetadataExt;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use nfs_mamont::vfs;
 use nfs_mamont::vfs::file;
+use tracing::warn;
+
+use crate::handle_journal::{Journal, Record};
+use crate::io_error::map_io_error;
+
+/// Id reserved for the export root.
+const ROOT_ID: u32 = 1;
+/// First id handed out to non-root objects.
+const FIRST_ID: u32 = ROOT_ID + 1;
+/// Generation of the export root, which is never removed.
+const ROOT_GENERATION: u32 = 0;
 
 /// Maps mirror paths to opaque VFS handles.
+///
+/// A handle encodes `(id, generation)`. Ids of removed objects are recycled,
+/// and every removal bumps the generation of the freed id, so handles issued
+/// before the removal resolve to [`vfs::Error::StaleFile`] instead of the new object.
+///
+/// Both directions are indexed: a handle resolves through `id_to_key` and
+/// `key_to_paths`, a path through `relative_to_key` and `key_to_id`, so neither lookup
+/// walks the registry. Only removals and renames scan it, for cached descendants.
+///
+/// With a journal attached the registry survives restarts, so handles stay valid for
+/// objects which are still in place.
 #[derive(Debug)]
 pub struct FsMap {
     root: PathBuf,
-    next_id: u64,
-    id_to_key: HashMap<u64, ObjectKey>,
-    key_to_id: HashMap<ObjectKey, u64>,
+    next_id: u32,
+    free_ids: Vec<u32>,
+    generations: HashMap<u32, u32>,
+    id_to_key: HashMap<u32, ObjectKey>,
+    key_to_id: HashMap<ObjectKey, u32>,
     key_to_paths: HashMap<ObjectKey, BTreeSet<PathBuf>>,
     relative_to_key: HashMap<PathBuf, ObjectKey>,
+    journal: Option<Journal>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -26,22 +52,59 @@ impl FsMap {
     pub fn new(root: PathBuf) -> Self {
         Self {
             root,
-            next_id: 2,
+            next_id: FIRST_ID,
+            free_ids: Vec::new(),
+            generations: HashMap::new(),
             id_to_key: HashMap::new(),
             key_to_id: HashMap::new(),
             key_to_paths: HashMap::new(),
             relative_to_key: HashMap::new(),
+            journal: None,
         }
     }
 
+    /// Restores the registry from the journal at `path` and records later changes there.
+    ///
+    /// Paths which no longer hold the object they were registered for, because it was
+    /// removed or replaced while the server was down, are forgotten, so their handles
+    /// resolve to [`vfs::Error::StaleFile`].
+    pub fn attach_journal(&mut self, path: &Path) -> std::io::Result<()> {
+        for record in Journal::load(path)? {
+            self.apply(record);
+        }
+
+        let replaced = self
+            .relative_to_key
+            .iter()
+            .filter(|(relative, key)| {
+                Self::object_key_for_path(&self.to_full_path(relative)).ok() != Some(**key)
+            })
+            .map(|(relative, _)| relative.clone())
+            .collect::<Vec<_>>();
+        for relative in replaced {
+            self.remove_relative(&relative);
+        }
+
+        self.journal = Some(Journal::create(path, &self.snapshot())?);
+        Ok(())
+    }
+
     pub fn root_handle(&self) -> file::Handle {
-        Self::encode_handle(1)
+        Self::encode_handle(ROOT_ID, ROOT_GENERATION)
     }
 
     pub fn path_for_handle(&self, handle: &file::Handle) -> Result<PathBuf, vfs::Error> {
-        let id = Self::decode_handle(handle)?;
-        if id == 1 {
-            return Ok(self.root.clone());
+        let (id, generation) = Self::decode_handle(handle)?;
+        if id == ROOT_ID {
+            return if generation == ROOT_GENERATION {
+                Ok(self.root.clone())
+            } else {
+                Err(vfs::Error::StaleFile)
+            };
+        }
+
+        if generation != self.generation(id) {
+            return Err(vfs::Error::StaleFile);
         }
 
         let key = self.id_to_key.get(&id).ok_or(vfs::Error::StaleFile)?;
@@ -51,6 +114,9 @@ impl FsMap {
         // During in-flight rename/unlink operations another task may still update
         // aliases; eager pruning here can invalidate a valid handle and cause EIO.
         for relative in paths {
+            if !Self::is_contained(relative) {
+                return Err(vfs::Error::Access);
+            }
             let full = self.to_full_path(relative);
             if std::fs::symlink_metadata(&full).is_ok() {
                 return Ok(full);
@@ -60,34 +126,39 @@ impl FsMap {
         Err(vfs::Error::StaleFile)
     }
 
+    /// Returns the handle of `path`, issuing a new one on first use.
+    ///
+    /// Paths outside the export root are rejected with [`vfs::Error::BadFileHandle`],
+    /// and paths under the root that climb out of it through `..` with [`vfs::Error::Access`].
     pub fn ensure_handle_for_path(&mut self, path: &Path) -> Result<file::Handle, vfs::Error> {
         let relative =
             path.strip_prefix(&self.root).map_err(|_| vfs::Error::BadFileHandle)?.to_path_buf();
+        if !Self::is_contained(&relative) {
+            return Err(vfs::Error::Access);
+        }
 
         if relative.as_os_str().is_empty() {
             return Ok(self.root_handle());
         }
 
         let key = Self::object_key_for_path(path)?;
-        if let Some(id) = self.key_to_id.get(&key).copied() {
-            self.key_to_paths.entry(key).or_default().insert(relative.clone());
-            self.relative_to_key.insert(relative, key);
-            return Ok(Self::encode_handle(id));
-        }
-
-        let id = self.next_id;
-        self.next_id = self.next_id.wrapping_add(1);
-        if self.next_id <= 1 {
-            self.next_id = 2;
+        if self.relative_to_key.get(&relative) == Some(&key) {
+            let id = self.key_to_id[&key];
+            return Ok(Self::encode_handle(id, self.generation(id)));
         }
-        let mut paths = BTreeSet::new();
-        paths.insert(relative.clone());
+        let id = match self.key_to_id.get(&key) {
+            Some(&id) => id,
+            None => self.allocate_id()?,
+        };
+        self.insert(id, key, relative.clone());
+        self.record(Record::Add { id, dev: key.dev, ino: key.ino, relative });
+        Ok(Self::encode_handle(id, self.generation(id)))
+    }
 
-        self.id_to_key.insert(id, key);
-        self.key_to_id.insert(key, id);
-        self.key_to_paths.insert(key, paths);
-        self.relative_to_key.insert(relative, key);
-        Ok(Self::encode_handle(id))
+    /// Returns the handle issued for the object `meta` describes, if any.
+    pub fn known_handle(&self, meta: &Metadata) -> Option<file::Handle> {
+        let id = *self.key_to_id.get(&ObjectKey { dev: meta.dev(), ino: meta.ino() })?;
+        Some(Self::encode_handle(id, self.generation(id)))
     }
 
     pub fn remove_path(&mut self, path: &Path) {
@@ -95,45 +166,72 @@ impl FsMap {
             return;
         };
         let relative = relative.to_path_buf();
+        self.remove_relative(&relative);
+        self.record(Record::Remove(relative));
+    }
+
+    pub fn rename_path(&mut self, from: &Path, to: &Path) -> Result<(), vfs::Error> {
+        let from_relative =
+            from.strip_prefix(&self.root).map_err(|_| vfs::Error::BadFileHandle)?.to_path_buf();
+        let to_relative =
+            to.strip_prefix(&self.root).map_err(|_| vfs::Error::BadFileHandle)?.to_path_buf();
+        self.rename_relative(&from_relative, &to_relative);
+        self.record(Record::Rename { from: from_relative, to: to_relative });
+        Ok(())
+    }
+
+    /// Registers `relative` as a path of object `key` with `id`.
+    fn insert(&mut self, id: u32, key: ObjectKey, relative: PathBuf) {
+        if let Some(old_key) = self.relative_to_key.insert(relative.clone(), key) {
+            self.unlink(old_key, &relative);
+        }
+        if !self.key_to_id.contains_key(&key) {
+            self.free_ids.retain(|&free| free != id);
+            self.next_id = self.next_id.max(id.saturating_add(1));
+            self.id_to_key.insert(id, key);
+            self.key_to_id.insert(key, id);
+        }
+        self.key_to_paths.entry(key).or_default().insert(relative);
+    }
 
+    /// Drops `relative` from the paths of `key`, releasing the id of its last path.
+    fn unlink(&mut self, key: ObjectKey, relative: &Path) {
+        let Some(paths) = self.key_to_paths.get_mut(&key) else {
+            return;
+        };
+        paths.remove(relative);
+        if paths.is_empty() {
+            self.key_to_paths.remove(&key);
+            if let Some(id) = self.key_to_id.remove(&key) {
+                self.id_to_key.remove(&id);
+                self.release_id(id);
+            }
+        }
+    }
+
+    fn remove_relative(&mut self, relative: &Path) {
         let to_remove = self
             .relative_to_key
             .keys()
-            .filter(|known_relative| {
-                *known_relative == &relative || known_relative.starts_with(&relative)
-            })
+            .filter(|known_relative| known_relative.starts_with(relative))
             .cloned()
             .collect::<Vec<_>>();
 
         for known_relative in to_remove {
-            let Some(key) = self.relative_to_key.remove(&known_relative) else {
-                continue;
-            };
-            if let Some(paths) = self.key_to_paths.get_mut(&key) {
-                paths.remove(&known_relative);
-                if paths.is_empty() {
-                    self.key_to_paths.remove(&key);
-                    if let Some(id) = self.key_to_id.remove(&key) {
-                        self.id_to_key.remove(&id);
-                    }
-                }
+            if let Some(key) = self.relative_to_key.remove(&known_relative) {
+                self.unlink(key, &known_relative);
             }
         }
     }
 
-    pub fn rename_path(&mut self, from: &Path, to: &Path) -> Result<(), vfs::Error> {
-        let from_relative =
-            from.strip_prefix(&self.root).map_err(|_| vfs::Error::BadFileHandle)?.to_path_buf();
-        let to_relative =
-            to.strip_prefix(&self.root).map_err(|_| vfs::Error::BadFileHandle)?.to_path_buf();
-
+    fn rename_relative(&mut self, from_relative: &Path, to_relative: &Path) {
         let updates = self
             .relative_to_key
             .iter()
             .filter_map(|(known_relative, key)| {
-                if known_relative == &from_relative || known_relative.starts_with(&from_relative) {
-                    let suffix = known_relative.strip_prefix(&from_relative).ok()?.to_path_buf();
-                    let mut replacement = to_relative.clone();
+                if known_relative.starts_with(from_relative) {
+                    let suffix = known_relative.strip_prefix(from_relative).ok()?.to_path_buf();
+                    let mut replacement = to_relative.to_path_buf();
                     if !suffix.as_os_str().is_empty() {
                         replacement.push(suffix);
                     }
@@ -153,8 +251,55 @@ impl FsMap {
                 paths.insert(new_relative);
             }
         }
+    }
 
-        Ok(())
+    /// Replays a journal record onto the registry.
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::NextId(id) => self.next_id = id,
+            Record::Generation { id, generation } => {
+                self.generations.insert(id, generation);
+            }
+            Record::Free(id) => self.free_ids.push(id),
+            Record::Add { id, dev, ino, relative } => {
+                self.insert(id, ObjectKey { dev, ino }, relative)
+            }
+            Record::Remove(relative) => self.remove_relative(&relative),
+            Record::Rename { from, to } => self.rename_relative(&from, &to),
+        }
+    }
+
+    /// Returns records rebuilding the current registry.
+    fn snapshot(&self) -> Vec<Record> {
+        let mut records = vec![Record::NextId(self.next_id)];
+        records.extend(
+            self.generations
+                .iter()
+                .filter(|(_, &generation)| generation != 0)
+                .map(|(&id, &generation)| Record::Generation { id, generation }),
+        );
+        records.extend(self.free_ids.iter().map(|&id| Record::Free(id)));
+        records.extend(self.relative_to_key.iter().map(|(relative, key)| Record::Add {
+            id: self.key_to_id[key],
+            dev: key.dev,
+            ino: key.ino,
+            relative: relative.clone(),
+        }));
+        records
+    }
+
+    fn record(&mut self, record: Record) {
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+        journal.append(&record);
+        if journal.needs_compaction() {
+            let snapshot = self.snapshot();
+            let journal = self.journal.as_mut().expect("journal is attached");
+            if let Err(error) = journal.compact(&snapshot) {
+                warn!(%error, "failed to compact handle journal");
+            }
+        }
     }
 
     fn to_full_path(&self, relative: &Path) -> PathBuf {
@@ -165,32 +310,58 @@ impl FsMap {
         }
     }
 
-    fn encode_handle(id: u64) -> file::Handle {
-        file::Handle(id.to_be_bytes())
+    /// Checks that `relative` names an object under the export root, i.e. consists
+    /// of plain names only: no `..`, `.`, root or prefix components.
+    fn is_contained(relative: &Path) -> bool {
+        relative.components().all(|component| matches!(component, Component::Normal(_)))
+    }
+
+    fn generation(&self, id: u32) -> u32 {
+        self.generations.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Returns a recycled id if one is available, otherwise a fresh one.
+    fn allocate_id(&mut self) -> Result<u32, vfs::Error> {
+        if let Some(id) = self.free_ids.pop() {
+            return Ok(id);
+        }
+
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).ok_or(vfs::Error::NoSpace)?;
+        Ok(id)
+    }
+
+    /// Invalidates all handles issued for `id` and makes the id available for reuse.
+    fn release_id(&mut self, id: u32) {
+        let generation = self.generations.entry(id).or_default();
+        *generation = generation.wrapping_add(1);
+        self.free_ids.push(id);
+    }
+
+    /// Converts an object id and its generation into handle bytes.
+    ///
+    /// Both halves are always stored in big-endian (XDR) byte order; this and
+    /// [`Self::decode_handle`] are the only places doing the conversion.
+    fn encode_handle(id: u32, generation: u32) -> file::Handle {
+        let mut raw = [0u8; 8];
+        raw[..4].copy_from_slice(&id.to_be_bytes());
+        raw[4..].copy_from_slice(&generation.to_be_bytes());
+        file::Handle(raw)
     }
 
     fn object_key_for_path(path: &Path) -> Result<ObjectKey, vfs::Error> {
-        let metadata = std::fs::symlink_metadata(path).map_err(Self::map_io_error)?;
+        let metadata = std::fs::symlink_metadata(path).map_err(|error| map_io_error(&error))?;
         Ok(ObjectKey { dev: metadata.dev(), ino: metadata.ino() })
     }
 
-    fn decode_handle(handle: &file::Handle) -> Result<u64, vfs::Error> {
-        let id = u64::from_be_bytes(handle.0);
+    /// Converts handle bytes produced by [`Self::encode_handle`] back into an object id.
+    fn decode_handle(handle: &file::Handle) -> Result<(u32, u32), vfs::Error> {
+        let id = u32::from_be_bytes([handle.0[0], handle.0[1], handle.0[2], handle.0[3]]);
+        let generation = u32::from_be_bytes([handle.0[4], handle.0[5], handle.0[6], handle.0[7]]);
         if id == 0 {
             Err(vfs::Error::BadFileHandle)
         } else {
-            Ok(id)
-        }
-    }
-
-    fn map_io_error(error: std::io::Error) -> vfs::Error {
-        match error.kind() {
-            std::io::ErrorKind::NotFound => vfs::Error::NoEntry,
-            std::io::ErrorKind::PermissionDenied => vfs::Error::Access,
-            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => {
-                vfs::Error::InvalidArgument
-            }
-            _ => vfs::Error::IO,
+            Ok((id, generation))
         }
     }
 }
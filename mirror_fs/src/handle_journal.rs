@@ -0,0 +1,187 @@
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+/// A change of the handle registry of [`crate::fs_map::FsMap`].
+///
+/// Paths are relative to the export root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    /// Next never used id.
+    NextId(u32),
+    /// Generation of an id, if not zero.
+    Generation { id: u32, generation: u32 },
+    /// Id freed for reuse, in the order of reuse.
+    Free(u32),
+    /// Path of the object `(dev, ino)` with id `id`.
+    Add { id: u32, dev: u64, ino: u64, relative: PathBuf },
+    /// Removal of a path together with everything below it.
+    Remove(PathBuf),
+    /// Move of a path together with everything below it.
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+/// Records appended beyond the snapshot, per snapshot record, before the journal is
+/// rewritten from the registry in memory.
+const COMPACT_RATIO: usize = 4;
+
+/// Records appended before the first rewrite, however small the snapshot.
+const MIN_COMPACT_RECORDS: usize = 4096;
+
+/// Append-only file of [`Record`]s, one per line.
+///
+/// The journal starts with a snapshot of the registry, written when it is opened, and
+/// continues with every change made since; replaying it rebuilds the registry.
+///
+/// Appends are not synced: they survive the server crashing, but an operating system
+/// crash may lose the last records, so handles issued just before it resolve to
+/// [`nfs_mamont::vfs::Error::StaleFile`] afterwards. A record torn by such a crash is
+/// dropped on load. Snapshots are synced, and the journal is rewritten as one once
+/// appends outgrow it, which bounds its size by the registry's.
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+    path: PathBuf,
+    snapshot_len: usize,
+    appended: usize,
+    failed: bool,
+}
+
+impl Journal {
+    /// Reads all records of the journal at `path`; a missing journal has none.
+    ///
+    /// An unterminated last line is a record torn by a crash and is ignored.
+    pub fn load(path: &Path) -> io::Result<Vec<Record>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+        let (complete, torn) = match contents.rfind('\n') {
+            Some(end) => contents.split_at(end + 1),
+            None => ("", contents.as_str()),
+        };
+        if !torn.is_empty() {
+            warn!(path = %path.display(), "ignoring torn last record of handle journal");
+        }
+        complete.lines().map(Record::parse).collect()
+    }
+
+    /// Replaces the journal at `path` with `snapshot` and opens it for appending.
+    pub fn create(path: &Path, snapshot: &[Record]) -> io::Result<Self> {
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+
+        let mut contents = String::new();
+        snapshot.iter().for_each(|record| record.format_into(&mut contents));
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(contents.as_bytes())?;
+        tmp.sync_all()?;
+        std::fs::rename(&tmp_path, path)?;
+
+        Ok(Self {
+            file: OpenOptions::new().append(true).open(path)?,
+            path: path.to_path_buf(),
+            snapshot_len: snapshot.len(),
+            appended: 0,
+            failed: false,
+        })
+    }
+
+    /// Appends `record`; a failed write is logged, as the registry in memory stays valid.
+    pub fn append(&mut self, record: &Record) {
+        let mut line = String::new();
+        record.format_into(&mut line);
+        self.appended += 1;
+        if let Err(error) = self.file.write_all(line.as_bytes()) {
+            warn!(%error, "failed to append to handle journal");
+            // The line may be partly written; only a rewrite makes the journal readable.
+            self.failed = true;
+        }
+    }
+
+    /// Returns whether the journal should be rewritten by [`Journal::compact`]: it grew
+    /// too long, or an append failed.
+    pub fn needs_compaction(&self) -> bool {
+        self.failed || self.appended > MIN_COMPACT_RECORDS.max(self.snapshot_len * COMPACT_RATIO)
+    }
+
+    /// Replaces the journal with `snapshot` of the current registry.
+    pub fn compact(&mut self, snapshot: &[Record]) -> io::Result<()> {
+        *self = Self::create(&self.path, snapshot)?;
+        Ok(())
+    }
+}
+
+impl Record {
+    fn format_into(&self, out: &mut String) {
+        let _ = match self {
+            Record::NextId(id) => writeln!(out, "N {id}"),
+            Record::Generation { id, generation } => writeln!(out, "G {id} {generation}"),
+            Record::Free(id) => writeln!(out, "F {id}"),
+            Record::Add { id, dev, ino, relative } => {
+                writeln!(out, "A {id} {dev} {ino} {}", encode_path(relative))
+            }
+            Record::Remove(relative) => writeln!(out, "R {}", encode_path(relative)),
+            Record::Rename { from, to } => {
+                writeln!(out, "M {} {}", encode_path(from), encode_path(to))
+            }
+        };
+    }
+
+    fn parse(line: &str) -> io::Result<Self> {
+        Self::parse_fields(line).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad handle journal line {line:?}"))
+        })
+    }
+
+    fn parse_fields(line: &str) -> Option<Self> {
+        let mut fields = line.split(' ');
+        let record = match fields.next()? {
+            "N" => Record::NextId(fields.next()?.parse().ok()?),
+            "G" => Record::Generation {
+                id: fields.next()?.parse().ok()?,
+                generation: fields.next()?.parse().ok()?,
+            },
+            "F" => Record::Free(fields.next()?.parse().ok()?),
+            "A" => Record::Add {
+                id: fields.next()?.parse().ok()?,
+                dev: fields.next()?.parse().ok()?,
+                ino: fields.next()?.parse().ok()?,
+                relative: decode_path(fields.next()?)?,
+            },
+            "R" => Record::Remove(decode_path(fields.next()?)?),
+            "M" => Record::Rename {
+                from: decode_path(fields.next()?)?,
+                to: decode_path(fields.next()?)?,
+            },
+            _ => return None,
+        };
+        fields.next().is_none().then_some(record)
+    }
+}
+
+/// Encodes the raw bytes of `path` as hex, so any name fits on one line.
+fn encode_path(path: &Path) -> String {
+    path.as_os_str().as_bytes().iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+fn decode_path(hex: &str) -> Option<PathBuf> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
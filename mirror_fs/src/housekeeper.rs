@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use crate::fs::MirrorFS;
+
+/// Default pause between two housekeeping rounds.
+pub const DEFAULT_HOUSEKEEPING_INTERVAL: Duration = Duration::from_secs(1);
+/// Default number of cache entries a housekeeping round may expire.
+pub const DEFAULT_HOUSEKEEPING_BUDGET: usize = 1024;
+
+/// Background task dropping expired entries of the caches of a [`MirrorFS`].
+///
+/// Lookups only skip expired entries they happen to hit, so entries nobody asks for
+/// again would stay forever. The housekeeper removes them in rounds of at most
+/// `budget` entries, so a round holds each cache lock only briefly and requests never
+/// pay for a sweep of a whole cache.
+#[derive(Debug, Clone, Copy)]
+pub struct Housekeeper {
+    interval: Duration,
+    budget: NonZeroUsize,
+}
+
+impl Default for Housekeeper {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_HOUSEKEEPING_INTERVAL,
+            NonZeroUsize::new(DEFAULT_HOUSEKEEPING_BUDGET).unwrap(),
+        )
+    }
+}
+
+impl Housekeeper {
+    pub fn new(interval: Duration, budget: NonZeroUsize) -> Self {
+        Self { interval, budget }
+    }
+
+    /// Spawns the housekeeping task of `fs`, which ends once `fs` is dropped.
+    pub fn spawn(self, fs: &Arc<MirrorFS>) -> JoinHandle<()> {
+        let fs = Arc::downgrade(fs);
+        tokio::spawn(self.run(fs))
+    }
+
+    async fn run(self, fs: Weak<MirrorFS>) {
+        let mut ticks = tokio::time::interval(self.interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            let Some(fs) = fs.upgrade() else {
+                return;
+            };
+            fs.expire_cached(self.budget.get());
+        }
+    }
+}
+
+/// Most entries a cache keeps, expired or not.
+pub const MAX_CACHE_ENTRIES: usize = 1 << 16;
+
+/// Keys of a cache in the order they were inserted, which with a fixed TTL is also
+/// the order they expire in.
+///
+/// A key refreshed or dropped after insertion stays queued until it expires, so
+/// callers tell whether the cached entry still carries the queued insertion time.
+/// The queue is bounded by [`MAX_CACHE_ENTRIES`], which bounds the cache as well.
+#[derive(Debug)]
+pub struct ExpiryQueue<K> {
+    queue: VecDeque<(Instant, K)>,
+}
+
+impl<K> Default for ExpiryQueue<K> {
+    fn default() -> Self {
+        Self { queue: VecDeque::new() }
+    }
+}
+
+impl<K> ExpiryQueue<K> {
+    /// Queues `key`, first making room if the queue is full; returns the keys evicted
+    /// for it, whose cached entries the caller drops.
+    ///
+    /// `current` tells whether a queued key still carries its cached entry's time.
+    /// Superseded keys go first; if that is not enough, the oldest entries are evicted
+    /// until a quarter of the room is free, so eviction is rare and cheap on average.
+    pub fn push(
+        &mut self,
+        inserted_at: Instant,
+        key: K,
+        mut current: impl FnMut(&Instant, &K) -> bool,
+    ) -> Vec<K> {
+        let mut evicted = Vec::new();
+        if self.queue.len() >= MAX_CACHE_ENTRIES {
+            self.queue.retain(|(queued_at, key)| current(queued_at, key));
+            let keep = MAX_CACHE_ENTRIES - MAX_CACHE_ENTRIES / 4;
+            while self.queue.len() > keep {
+                evicted.extend(self.queue.pop_front().map(|(_, key)| key));
+            }
+        }
+        self.queue.push_back((inserted_at, key));
+        evicted
+    }
+
+    /// Removes and returns up to `budget` current keys inserted at least `ttl` before
+    /// `now`; expired keys which are no longer `current` are dropped on the way without
+    /// counting against `budget`.
+    pub fn pop_expired(
+        &mut self,
+        now: Instant,
+        ttl: Duration,
+        budget: usize,
+        mut current: impl FnMut(&Instant, &K) -> bool,
+    ) -> Vec<K> {
+        let mut expired = Vec::new();
+        while expired.len() < budget {
+            match self.queue.front() {
+                Some((inserted_at, _)) if now.saturating_duration_since(*inserted_at) >= ttl => {
+                    let (inserted_at, key) = self.queue.pop_front().expect("front is queued");
+                    if current(&inserted_at, &key) {
+                        expired.push(key);
+                    }
+                }
+                _ => break,
+            }
+        }
+        expired
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
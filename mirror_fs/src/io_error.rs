@@ -0,0 +1,36 @@
+use std::io::{self, ErrorKind};
+
+use nfs_mamont::vfs;
+
+/// Attempts a blocking file system call gets before an interruption is reported.
+pub const MAX_INTERRUPTED_ATTEMPTS: usize = 8;
+
+/// Runs the blocking file system call `op`, repeating it while it fails with `EINTR`,
+/// at most [`MAX_INTERRUPTED_ATTEMPTS`] times.
+///
+/// An interrupted call has not taken effect, so repeating it is safe. No other error
+/// is retried: `EAGAIN` means the object is busy for longer than a tight loop on a
+/// worker thread should wait, so it is reported, like an interruption outlasting the
+/// attempts, as [`vfs::Error::Jukebox`] for the client to retry later. All remaining
+/// errors are final.
+pub fn retry_interrupted<T>(mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut attempts = 1;
+    loop {
+        match op() {
+            Err(error)
+                if error.kind() == ErrorKind::Interrupted
+                    && attempts < MAX_INTERRUPTED_ATTEMPTS =>
+            {
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Translates an I/O error of the mirrored file system into an NFS error.
+///
+/// See the [`vfs::Error`] conversion from [`io::Error`] for how errors are mapped.
+pub fn map_io_error(error: &io::Error) -> vfs::Error {
+    vfs::Error::from(error)
+}
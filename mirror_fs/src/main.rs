@@ -1,25 +1,41 @@
 use std::io::ErrorKind;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 use clap::Parser;
-use tokio::net::TcpListener;
 use tracing::info;
 
+use nfs_mamont::audit::TracingAuditSink;
 use nfs_mamont::mount::ExportEntry;
 use nfs_mamont::vfs::file::Path as VfsPath;
-use nfs_mamont::{handle_forever, service, Impl, ServerContext};
+use nfs_mamont::{handle_forever, service, Impl, ServerContext, TokioSpawner};
 
 #[cfg(debug_assertions)]
 use nfs_mamont::init_tracing;
 
+pub mod access_cache;
 pub mod args;
+pub mod attr_cache;
+pub mod clock;
 pub mod config;
+pub mod dirty_ranges;
 pub mod fs;
 pub mod fs_map;
+pub mod handle_journal;
+pub mod housekeeper;
+pub mod io_error;
+pub mod negative_cache;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+pub mod uring;
+pub mod write_buffer;
 
 #[cfg(test)]
 mod tests;
 
+/// Submission queue size of the io_uring serving file data.
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+const URING_ENTRIES: u32 = 256;
+
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     #[cfg(debug_assertions)]
@@ -28,21 +44,97 @@ async fn main() -> std::io::Result<()> {
     let args = args::Args::parse();
 
     let config = config::load_config(&args.config_path)?;
-    let fs = Arc::new(fs::MirrorFS::new(config.export_root.clone()));
+    let fs = fs::MirrorFS::new(config.export_root.clone())
+        .with_case_insensitive(config.case_insensitive)
+        .with_durability(config.durability)
+        .with_cookie_verifiers(config.cookie_verifiers)
+        .with_attr_cache_ttl(config.attr_cache_ttl)
+        .with_access_cache_ttl(config.access_cache_ttl)
+        .with_negative_lookup_ttl(config.negative_lookup_ttl);
+    let fs = match config.time_delta {
+        Some(time_delta) => fs.with_time_delta(time_delta),
+        None => fs,
+    };
+    let fs = match config.write_epoch {
+        Some(epoch) => fs.with_write_epoch(epoch),
+        None => fs,
+    };
+    let fs = match config.read_dir_plus_max_handles {
+        Some(max) => fs.with_read_dir_plus_max_handles(max),
+        None => fs,
+    };
+    let fs = match config.write_buffer {
+        Some(limits) => fs.with_write_buffer(limits),
+        None => fs,
+    };
+    let fs = match &config.handle_registry {
+        Some(path) => fs.with_handle_registry(path)?,
+        None => fs,
+    };
+    let fs = config.exports.iter().fold(fs, |fs, export| match export.fsid {
+        Some(fsid) => fs.with_export_fsid(export.local_path.clone(), fsid),
+        None => fs,
+    });
+    let fs = config.exports.iter().fold(fs, |fs, export| {
+        if export.create_modes == fs::CreateModes::default() {
+            fs
+        } else {
+            fs.with_export_create_modes(export.local_path.clone(), export.create_modes)
+        }
+    });
+    let fs = match config.allocator.transfer {
+        Some(transfer) => fs.with_max_transfer(transfer.max_transfer),
+        None => fs,
+    };
+    let fs = match config.transfer_multiple {
+        Some(multiple) => fs.with_transfer_multiple(multiple),
+        None => fs,
+    };
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    let fs = fs.with_io_uring(URING_ENTRIES);
+    let fs = Arc::new(fs);
+    config.housekeeping.spawn(&fs);
 
-    let context = ServerContext::new(
+    let (read_allocator, write_allocator) = match config.allocator.transfer {
+        Some(transfer) => {
+            let max_transfer = NonZeroUsize::try_from(transfer.max_transfer)
+                .expect("u32 transfer sizes fit usize");
+            (
+                Impl::for_transfers(max_transfer, transfer.max_concurrent_ops),
+                Impl::for_transfers(max_transfer, transfer.max_concurrent_ops),
+            )
+        }
+        None => (
+            Impl::new(config.allocator.read_buffer_size, config.allocator.read_buffer_count),
+            Impl::new(config.allocator.write_buffer_size, config.allocator.write_buffer_count),
+        ),
+    };
+    let context = ServerContext::with_queue_capacity(
         fs.clone(),
-        Arc::new(Impl::new(config.allocator.read_buffer_size, config.allocator.read_buffer_count)),
-        Arc::new(Impl::new(
-            config.allocator.write_buffer_size,
-            config.allocator.write_buffer_count,
-        )),
+        Arc::new(read_allocator),
+        Arc::new(write_allocator),
         config.vfs_pool_size,
-    );
+        Arc::new(TokioSpawner),
+        config.queue_capacity,
+    )
+    .with_rate_limit(config.rate_limit)
+    .with_socket_config(config.socket)
+    .with_anonymous_access(config.anonymous_access)
+    .with_id_map(config.id_map)
+    .with_strict_attrs(config.strict_attrs);
+    let context = match config.proc_timeout {
+        Some(timeout) => context.with_proc_timeout(timeout),
+        None => context,
+    };
+    let context = if config.audit_log {
+        context.with_audit_sink(Arc::new(TracingAuditSink))
+    } else {
+        context
+    };
 
     info!(export_root = %config.export_root.display(), bind = %args.addr, "mirrorfs startup");
 
-    let listener = TcpListener::bind(&args.addr).await?;
+    let listener = config.socket.bind(args.addr)?;
 
     let mut exports = Vec::with_capacity(config.exports.len());
     for export in &config.exports {
@@ -62,6 +154,7 @@ async fn main() -> std::io::Result<()> {
                 names: Vec::new(),
             },
             root_handle,
+            auth_flavors: service::mount::DEFAULT_AUTH_FLAVORS.to_vec(),
         });
     }
 
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nfs_mamont::vfs::file;
+
+use crate::clock::{Clock, SystemClock};
+use crate::housekeeper::ExpiryQueue;
+
+/// Short-lived cache of names LOOKUP found missing, keyed by parent handle and name.
+///
+/// Entries expire after the configured TTL; a TTL of zero disables caching.
+/// Callers must invalidate the entries of a directory once a name may have appeared in
+/// it. Each invalidation starts a new epoch, and a miss observed in an older epoch is
+/// not cached, so a lookup racing with a create never records the created name as
+/// missing.
+pub struct NegativeCache {
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+}
+
+/// Missing names by parent handle, with the time they were found missing.
+type Entries = HashMap<file::Handle, HashMap<String, Instant>>;
+
+#[derive(Default)]
+struct State {
+    entries: Entries,
+    expiry: ExpiryQueue<(file::Handle, String)>,
+    epoch: u64,
+}
+
+impl std::fmt::Debug for NegativeCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cached = self.state.lock().map_or(0, |state| state.entries.len());
+        f.debug_struct("NegativeCache").field("ttl", &self.ttl).field("cached", &cached).finish()
+    }
+}
+
+impl Default for NegativeCache {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl NegativeCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, clock: Arc::new(SystemClock), state: Mutex::new(State::default()) }
+    }
+
+    /// Ages entries by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns `true` if `name` was recently found missing in `parent`.
+    pub fn contains(&self, parent: &file::Handle, name: &str) -> bool {
+        if self.ttl.is_zero() {
+            return false;
+        }
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let Some(names) = state.entries.get_mut(parent) else {
+            return false;
+        };
+        match names.get(name) {
+            Some(cached_at) if now.saturating_duration_since(*cached_at) < self.ttl => true,
+            Some(_) => {
+                names.remove(name);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the current epoch, to be taken before looking a name up.
+    pub fn epoch(&self) -> u64 {
+        self.state.lock().unwrap().epoch
+    }
+
+    /// Caches `name` as missing in `parent`, unless the cache was invalidated since
+    /// `epoch` was taken.
+    pub fn insert(&self, parent: &file::Handle, name: &str, epoch: u64) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.epoch != epoch {
+            return;
+        }
+        let now = self.clock.now();
+        let State { entries, expiry, .. } = &mut *state;
+        entries.entry(parent.clone()).or_default().insert(name.to_owned(), now);
+        let evicted = expiry.push(now, (parent.clone(), name.to_owned()), |at, (parent, name)| {
+            Self::current(entries, at, parent, name)
+        });
+        for (parent, name) in &evicted {
+            Self::remove_entry(entries, parent, name);
+        }
+    }
+
+    /// Drops `name` of `parent`, which is known to exist.
+    pub fn remove(&self, parent: &file::Handle, name: &str) {
+        if let Some(names) = self.state.lock().unwrap().entries.get_mut(parent) {
+            names.remove(name);
+        }
+    }
+
+    /// Drops all cached names of `parent`.
+    pub fn invalidate(&self, parent: &file::Handle) {
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        state.entries.remove(parent);
+    }
+
+    /// Drops all cached names.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        state.entries.clear();
+        state.expiry.clear();
+    }
+
+    /// Drops up to `budget` expired names, returning how many were dropped.
+    pub fn expire(&self, budget: usize) -> usize {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let State { entries, expiry, .. } = &mut *state;
+        let expired = expiry.pop_expired(now, self.ttl, budget, |at, (parent, name)| {
+            Self::current(entries, at, parent, name)
+        });
+        for (parent, name) in &expired {
+            Self::remove_entry(entries, parent, name);
+        }
+        expired.len()
+    }
+
+    /// Returns the number of cached names, expired or not.
+    pub fn entry_count(&self) -> usize {
+        self.state.lock().unwrap().entries.values().map(HashMap::len).sum()
+    }
+
+    /// Returns whether `name` of `parent` was cached at `cached_at`.
+    fn current(entries: &Entries, cached_at: &Instant, parent: &file::Handle, name: &str) -> bool {
+        entries.get(parent).and_then(|names| names.get(name)) == Some(cached_at)
+    }
+
+    fn remove_entry(entries: &mut Entries, parent: &file::Handle, name: &str) {
+        if let Some(names) = entries.get_mut(parent) {
+            names.remove(name);
+            if names.is_empty() {
+                entries.remove(parent);
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs as stdfs;
+use std::os::unix::fs::MetadataExt;
+
+use nfs_mamont::vfs;
+use nfs_mamont::vfs::allocate::{Mode, VfsAllocate};
+
+use super::helpers::{create_dir, expect_err, root_cred, write_file, TestContext};
+
+const MIB: u64 = 1024 * 1024;
+
+#[tokio::test]
+async fn allocate_space_reserves_blocks_of_sparse_file() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "sparse.img", b"");
+    stdfs::File::options().write(true).open(&path).unwrap().set_len(MIB).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "sparse.img").await;
+    let blocks_before = stdfs::metadata(&path).unwrap().blocks();
+
+    match ctx.fs.allocate_space(&root_cred(), &handle, 0, MIB, Mode::Extend).await {
+        Ok(()) => {}
+        // The file system of the test directory cannot preallocate.
+        Err(vfs::Error::NotSupported) => return,
+        Err(error) => panic!("allocate_space failed: {error:?}"),
+    }
+
+    let meta = stdfs::metadata(&path).unwrap();
+    assert!(meta.blocks() > blocks_before, "{} <= {blocks_before}", meta.blocks());
+    assert!(meta.blocks() * 512 >= MIB);
+    assert_eq!(meta.len(), MIB);
+    assert!(stdfs::read(&path).unwrap().iter().all(|&byte| byte == 0));
+}
+
+#[tokio::test]
+async fn allocate_space_extends_or_keeps_size_by_mode() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "keep.img", b"data");
+    write_file(ctx.root_path(), "extend.img", b"data");
+    let root = ctx.root_handle().await;
+
+    for (name, mode, size) in [("keep.img", Mode::KeepSize, 4), ("extend.img", Mode::Extend, MIB)] {
+        let handle = ctx.lookup_handle(root.clone(), name).await;
+        match ctx.fs.allocate_space(&root_cred(), &handle, 0, MIB, mode).await {
+            Ok(()) => {}
+            Err(vfs::Error::NotSupported) => return,
+            Err(error) => panic!("allocate_space failed: {error:?}"),
+        }
+        let path = ctx.root_path().join(name);
+        assert_eq!(stdfs::metadata(&path).unwrap().len(), size, "{name}");
+        assert_eq!(&stdfs::read(&path).unwrap()[..4], b"data", "{name}");
+    }
+}
+
+#[tokio::test]
+async fn allocate_space_rejects_empty_ranges_and_directories() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.img", b"");
+    create_dir(ctx.root_path(), "dir");
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root.clone(), "file.img").await;
+    let dir = ctx.lookup_handle(root, "dir").await;
+
+    let error = expect_err(
+        ctx.fs.allocate_space(&root_cred(), &file, 0, 0, Mode::Extend).await,
+        "empty ranges should be rejected",
+    );
+    assert_eq!(error, vfs::Error::InvalidArgument);
+    let error = expect_err(
+        ctx.fs.allocate_space(&root_cred(), &dir, 0, MIB, Mode::Extend).await,
+        "directories should be rejected",
+    );
+    assert_eq!(error, vfs::Error::InvalidArgument);
+}
@@ -0,0 +1,92 @@
+use std::fs as stdfs;
+use std::path::Path;
+
+use nfs_mamont::vfs::file;
+use nfs_mamont::vfs::get_attr;
+use nfs_mamont::vfs::lookup;
+
+use crate::config::load_config;
+use crate::fs::{CreateModes, MirrorFS};
+
+use super::helpers::{create_dir, expect_ok, name};
+
+/// Writes a config exporting `paths` of `root` next to `root` and loads it.
+fn load(root: &Path, paths: &[&str]) -> std::io::Result<crate::config::Config> {
+    let config_path = root.with_extension("toml");
+    let paths = paths.iter().map(|path| format!("{path:?}")).collect::<Vec<_>>().join(", ");
+    stdfs::write(
+        &config_path,
+        format!("[exports]\nroot = {:?}\npaths = [{paths}]\n", root.display().to_string()),
+    )
+    .unwrap();
+    load_config(&config_path)
+}
+
+#[tokio::test]
+async fn symlinked_export_mounts_the_real_directory() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let root = tempdir.path().join("root");
+    create_dir(&root, "real");
+    std::os::unix::fs::symlink("real", root.join("alias")).unwrap();
+
+    let config = load(&root, &["alias"]).unwrap();
+    let export = &config.exports[0];
+    assert_eq!(export.mount_path, "/alias");
+    assert_eq!(export.local_path, stdfs::canonicalize(root.join("real")).unwrap());
+
+    let fs = MirrorFS::new(config.export_root.clone());
+    let export_handle = fs.handle_for_path(&export.local_path).await.unwrap();
+    let root_handle = fs.handle_for_path(&config.export_root).await.unwrap();
+    let lookup = |entry: &str| {
+        lookup::Lookup::lookup(&fs, lookup::Args { parent: root_handle.clone(), name: name(entry) })
+    };
+    let real = expect_ok(lookup("real").await, "lookup of the directory should succeed");
+    let alias = expect_ok(lookup("alias").await, "lookup of the symlink should succeed");
+    assert_eq!(export_handle, real.file);
+    assert_ne!(export_handle, alias.file);
+    let attr = expect_ok(
+        get_attr::GetAttr::get_attr(&fs, get_attr::Args { file: export_handle }).await,
+        "get_attr should succeed",
+    );
+    assert!(matches!(attr.object.file_type, file::Type::Directory));
+}
+
+#[test]
+fn export_resolving_outside_the_root_is_rejected() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let root = tempdir.path().join("root");
+    create_dir(&root, "inside");
+    create_dir(tempdir.path(), "outside");
+    std::os::unix::fs::symlink(tempdir.path().join("outside"), root.join("escape")).unwrap();
+
+    let error = load(&root, &["escape"]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(load(&root, &["inside"]).is_ok());
+}
+
+#[test]
+fn create_modes_are_read_per_export() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let root = tempdir.path().join("root");
+    create_dir(&root, "fs01");
+    create_dir(&root, "fs02");
+    let config_path = tempdir.path().join("config.toml");
+    let write_config = |modes: &str| {
+        let root = root.display().to_string();
+        let exports = format!("[exports]\nroot = {root:?}\npaths = [\"fs01\", \"fs02\"]\n");
+        stdfs::write(&config_path, exports + modes).unwrap();
+    };
+
+    write_config("file_mode = { fs01 = 0o640 }\ndir_mode = { fs01 = 0o750 }\n");
+    let config = load_config(&config_path).unwrap();
+    assert_eq!(
+        config.exports[0].create_modes,
+        CreateModes { file: Some(0o640), directory: Some(0o750) }
+    );
+    assert_eq!(config.exports[1].create_modes, CreateModes::default());
+
+    write_config("file_mode = { fs03 = 0o640 }\n");
+    assert_eq!(load_config(&config_path).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    write_config("dir_mode = { fs01 = 0o17777 }\n");
+    assert_eq!(load_config(&config_path).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -1,6 +1,8 @@
 use std::fs as stdfs;
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use nfs_mamont::consts::nfsv3::NFS3_CREATEVERFSIZE;
 use nfs_mamont::vfs;
@@ -17,9 +19,14 @@ use nfs_mamont::vfs::set_attr;
 use nfs_mamont::vfs::symlink;
 use nfs_mamont::vfs::write;
 
+use crate::clock::{Clock, MockClock};
+use crate::fs::{CreateModes, Durability};
+use crate::write_buffer::WriteBufferLimits;
+
 use super::helpers::{
-    alloc_slice, assert_wcc_present, create_dir, default_new_attr, dir_op, expect_err, expect_ok,
-    file_path, sized_attr, slice_from_bytes, slice_to_vec, write_file, TestContext,
+    alloc_slice, assert_wcc_present, create_dir, create_dir_with_mode, create_symlink,
+    default_new_attr, dir_op, expect_err, expect_ok, file_path, root_cred, sized_attr,
+    slice_from_bytes, slice_to_vec, user_cred, write_file, TestContext,
 };
 
 #[tokio::test]
@@ -30,6 +37,7 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     let unchecked = expect_ok(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root.clone(), "alpha.txt"),
                 how: create::How::Unchecked(sized_attr(Some(0o640), Some(5))),
@@ -48,6 +56,7 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     let unchecked_existing = expect_ok(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root.clone(), "alpha.txt"),
                 how: create::How::Unchecked(sized_attr(Some(0o600), Some(2))),
@@ -64,6 +73,7 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     let guarded_fail = expect_err(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root.clone(), "alpha.txt"),
                 how: create::How::Guarded(default_new_attr()),
@@ -78,6 +88,7 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     let exclusive = expect_ok(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root.clone(), "beta.txt"),
                 how: create::How::Exclusive(create::Verifier([7u8; NFS3_CREATEVERFSIZE])),
@@ -93,6 +104,7 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     let retry = expect_ok(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root.clone(), "beta.txt"),
                 how: create::How::Exclusive(create::Verifier([7u8; NFS3_CREATEVERFSIZE])),
@@ -107,6 +119,7 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     let different = expect_err(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root, "beta.txt"),
                 how: create::How::Exclusive(create::Verifier([99u8; NFS3_CREATEVERFSIZE])),
@@ -118,6 +131,154 @@ async fn create_supports_unchecked_guarded_and_exclusive() {
     assert_eq!(different.error, vfs::Error::Exist);
 }
 
+#[tokio::test]
+async fn create_rejects_case_variant_in_case_insensitive_mode() {
+    let ctx = TestContext::new().with(|fs| fs.with_case_insensitive(true));
+    write_file(ctx.root_path(), "Foo.txt", b"data");
+    let root = ctx.root_handle().await;
+
+    let fail = expect_err(
+        create::Create::create(
+            &ctx.fs,
+            &root_cred(),
+            create::Args {
+                object: dir_op(root, "foo.txt"),
+                how: create::How::Unchecked(default_new_attr()),
+            },
+        )
+        .await,
+        "create of a case variant should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::Exist);
+    assert_wcc_present(&fail.wcc_data);
+    assert!(!ctx.root_path().join("foo.txt").exists());
+}
+
+#[tokio::test]
+async fn unchecked_create_of_existing_file_keeps_its_data() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "shared.txt", b"written by another client");
+    let root = ctx.root_handle().await;
+    let existing = ctx.lookup_handle(root.clone(), "shared.txt").await;
+
+    let created = expect_ok(
+        create::Create::create(
+            &ctx.fs,
+            &root_cred(),
+            create::Args {
+                object: dir_op(root, "shared.txt"),
+                how: create::How::Unchecked(default_new_attr()),
+            },
+        )
+        .await,
+        "unchecked create of an existing file should succeed",
+    );
+
+    assert!(created.file == Some(existing));
+    assert_eq!(created.attr.unwrap().size, 25);
+    assert_eq!(
+        stdfs::read(ctx.root_path().join("shared.txt")).unwrap(),
+        b"written by another client"
+    );
+}
+
+#[tokio::test]
+async fn unchecked_create_truncating_existing_file_refreshes_its_cached_attributes() {
+    let ctx = TestContext::new().with(|fs| fs.with_attr_cache_ttl(Duration::from_secs(60)));
+    write_file(ctx.root_path(), "shared.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let existing = ctx.lookup_handle(root.clone(), "shared.txt").await;
+
+    expect_ok(
+        create::Create::create(
+            &ctx.fs,
+            &root_cred(),
+            create::Args {
+                object: dir_op(root, "shared.txt"),
+                how: create::How::Unchecked(sized_attr(None, Some(0))),
+            },
+        )
+        .await,
+        "unchecked create of an existing file should succeed",
+    );
+
+    let attr = expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: existing }).await,
+        "get_attr should succeed",
+    );
+    assert_eq!(attr.object.size, 0);
+}
+
+#[tokio::test]
+async fn objects_created_without_mode_get_export_defaults() {
+    // The defaults have bits a typical umask clears, which must be restored.
+    let modes = CreateModes { file: Some(0o666), directory: Some(0o777) };
+    let ctx = TestContext::new();
+    let export = create_dir(ctx.root_path(), "share");
+    let ctx = ctx.with(|fs| fs.with_export_create_modes(export, modes));
+    let root = ctx.root_handle().await;
+    let share = ctx.lookup_handle(root, "share").await;
+    let cred = root_cred();
+    let create = |entry: &str, attr: set_attr::NewAttr| {
+        create::Create::create(
+            &ctx.fs,
+            &cred,
+            create::Args { object: dir_op(share.clone(), entry), how: create::How::Guarded(attr) },
+        )
+    };
+    let mode_of = |entry: &str| {
+        stdfs::metadata(ctx.root_path().join("share").join(entry)).unwrap().permissions().mode()
+            & 0o7777
+    };
+
+    expect_ok(create("default.txt", default_new_attr()).await, "create should succeed");
+    assert_eq!(mode_of("default.txt"), 0o666);
+
+    let explicit = set_attr::NewAttr { mode: Some(0o600), ..default_new_attr() };
+    expect_ok(create("explicit.txt", explicit).await, "create should succeed");
+    assert_eq!(mode_of("explicit.txt"), 0o600);
+
+    expect_ok(
+        mk_dir::MkDir::mk_dir(
+            &ctx.fs,
+            &root_cred(),
+            mk_dir::Args { object: dir_op(share.clone(), "dir"), attr: default_new_attr() },
+        )
+        .await,
+        "mk_dir should succeed",
+    );
+    assert_eq!(mode_of("dir"), 0o777);
+}
+
+#[tokio::test]
+async fn created_file_handle_matches_lookup() {
+    let ctx = TestContext::new();
+    let root = ctx.root_handle().await;
+
+    let created = expect_ok(
+        create::Create::create(
+            &ctx.fs,
+            &root_cred(),
+            create::Args {
+                object: dir_op(root.clone(), "created.txt"),
+                how: create::How::Guarded(default_new_attr()),
+            },
+        )
+        .await,
+        "guarded create should succeed",
+    );
+    let created_handle = created.file.unwrap();
+
+    let looked_up = ctx.lookup_handle(root, "created.txt").await;
+    assert!(created_handle == looked_up);
+
+    let attr = expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: created_handle }).await,
+        "get_attr by created handle should succeed",
+    );
+    assert!(matches!(attr.object.file_type, file::Type::Regular));
+}
+
 #[tokio::test]
 async fn link_creates_hard_link_and_rejects_directory() {
     let ctx = TestContext::new();
@@ -130,6 +291,7 @@ async fn link_creates_hard_link_and_rejects_directory() {
     let success = expect_ok(
         link::Link::link(
             &ctx.fs,
+            &root_cred(),
             link::Args { file: original, link: dir_op(root.clone(), "alias.txt") },
         )
         .await,
@@ -144,10 +306,122 @@ async fn link_creates_hard_link_and_rejects_directory() {
     assert_eq!(original_meta.nlink(), 2);
 
     let fail = expect_err(
-        link::Link::link(&ctx.fs, link::Args { file: dir, link: dir_op(root, "dir-link") }).await,
+        link::Link::link(
+            &ctx.fs,
+            &root_cred(),
+            link::Args { file: dir, link: dir_op(root, "dir-link") },
+        )
+        .await,
         "linking directories should fail",
     );
-    assert_eq!(fail.error, vfs::Error::InvalidArgument);
+    assert_eq!(fail.error, vfs::Error::Permission);
+    assert!(!ctx.root_path().join("dir-link").exists());
+}
+
+#[tokio::test]
+async fn create_needs_write_permission_and_gives_the_caller_ownership() {
+    let ctx = TestContext::new();
+    let parent = create_dir_with_mode(ctx.root_path(), "parent", 0o755);
+    let handle = ctx.lookup_handle(ctx.root_handle().await, "parent").await;
+    let args = || create::Args {
+        object: dir_op(handle.clone(), "file.txt"),
+        how: create::How::Unchecked(default_new_attr()),
+    };
+
+    let fail = expect_err(
+        create::Create::create(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not create files in a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert_wcc_present(&fail.wcc_data);
+    assert!(!parent.join("file.txt").exists());
+
+    stdfs::set_permissions(&parent, stdfs::Permissions::from_mode(0o777)).unwrap();
+    expect_ok(
+        create::Create::create(&ctx.fs, &user_cred(), args()).await,
+        "anybody may create files in a 0777 directory",
+    );
+    let meta = stdfs::metadata(parent.join("file.txt")).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (user_cred().uid, user_cred().gid));
+}
+
+#[tokio::test]
+async fn link_needs_search_permission_on_source_and_write_permission_on_target() {
+    let ctx = TestContext::new();
+    let source = create_dir_with_mode(ctx.root_path(), "source", 0o700);
+    let target = create_dir_with_mode(ctx.root_path(), "target", 0o777);
+    write_file(ctx.root_path(), "source/file.txt", b"data");
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(ctx.lookup_handle(root.clone(), "source").await, "file.txt").await;
+    let target_handle = ctx.lookup_handle(root, "target").await;
+    let args = || link::Args { file: file.clone(), link: dir_op(target_handle.clone(), "alias") };
+
+    let fail = expect_err(
+        link::Link::link(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not link a file of a 0700 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+
+    stdfs::set_permissions(&source, stdfs::Permissions::from_mode(0o755)).unwrap();
+    stdfs::set_permissions(&target, stdfs::Permissions::from_mode(0o755)).unwrap();
+    let fail = expect_err(
+        link::Link::link(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not add entries to a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert!(!target.join("alias").exists());
+}
+
+#[tokio::test]
+async fn read_needs_read_or_execute_permission() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"data");
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(0o600)).unwrap();
+    let handle = ctx.lookup_handle(ctx.root_handle().await, "file.txt").await;
+    let args = || read::Args { file: handle.clone(), offset: 0, count: 4 };
+
+    let fail = expect_err(
+        read::Read::read(&ctx.fs, &user_cred(), args(), alloc_slice(4).await).await,
+        "non-owner should not read a 0600 file",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(0o601)).unwrap();
+    let success = expect_ok(
+        read::Read::read(&ctx.fs, &user_cred(), args(), alloc_slice(4).await).await,
+        "others may read a file they may execute",
+    );
+    assert_eq!(success.head.count, 4);
+}
+
+#[tokio::test]
+async fn link_across_file_systems_reports_xdev() {
+    let ctx = TestContext::new();
+    let Ok(other) = tempfile::tempdir_in("/dev/shm") else {
+        return;
+    };
+    let device = |path: &std::path::Path| stdfs::metadata(path).unwrap().dev();
+    if device(other.path()) == device(ctx.root_path()) {
+        return;
+    }
+    write_file(ctx.root_path(), "file.txt", b"data");
+    std::os::unix::fs::symlink(other.path(), ctx.root_path().join("elsewhere")).unwrap();
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root, "file.txt").await;
+    let elsewhere = ctx.fs.handle_for_path(&ctx.root_path().join("elsewhere")).await.unwrap();
+
+    let fail = expect_err(
+        link::Link::link(
+            &ctx.fs,
+            &root_cred(),
+            link::Args { file, link: dir_op(elsewhere, "alias.txt") },
+        )
+        .await,
+        "link to another file system should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::XDev);
+    assert!(fail.file_attr.is_some());
+    assert!(!other.path().join("alias.txt").exists());
 }
 
 #[tokio::test]
@@ -158,6 +432,7 @@ async fn mk_dir_creates_directory_and_applies_mode() {
     let success = expect_ok(
         mk_dir::MkDir::mk_dir(
             &ctx.fs,
+            &root_cred(),
             mk_dir::Args {
                 object: dir_op(root.clone(), "child"),
                 attr: sized_attr(Some(0o750), None),
@@ -182,6 +457,7 @@ async fn mk_node_handles_supported_and_unsupported_types() {
     let regular = expect_ok(
         mk_node::MkNode::mk_node(
             &ctx.fs,
+            &root_cred(),
             mk_node::Args {
                 object: dir_op(root.clone(), "node-file.txt"),
                 what: mk_node::What::Regular,
@@ -196,6 +472,7 @@ async fn mk_node_handles_supported_and_unsupported_types() {
     let directory = expect_ok(
         mk_node::MkNode::mk_node(
             &ctx.fs,
+            &root_cred(),
             mk_node::Args {
                 object: dir_op(root.clone(), "node-dir"),
                 what: mk_node::What::Directory,
@@ -210,6 +487,7 @@ async fn mk_node_handles_supported_and_unsupported_types() {
     let bad_type = expect_err(
         mk_node::MkNode::mk_node(
             &ctx.fs,
+            &root_cred(),
             mk_node::Args {
                 object: dir_op(root.clone(), "node-link"),
                 what: mk_node::What::SymbolicLink,
@@ -223,6 +501,7 @@ async fn mk_node_handles_supported_and_unsupported_types() {
     let not_supported = expect_err(
         mk_node::MkNode::mk_node(
             &ctx.fs,
+            &root_cred(),
             mk_node::Args {
                 object: dir_op(root, "node-socket"),
                 what: mk_node::What::Socket(default_new_attr()),
@@ -248,6 +527,7 @@ async fn set_attr_updates_size_and_honors_guard() {
     let success = expect_ok(
         set_attr::SetAttr::set_attr(
             &ctx.fs,
+            &root_cred(),
             set_attr::Args {
                 file: handle.clone(),
                 new_attr: sized_attr(Some(0o600), Some(2)),
@@ -269,6 +549,7 @@ async fn set_attr_updates_size_and_honors_guard() {
     let fail = expect_err(
         set_attr::SetAttr::set_attr(
             &ctx.fs,
+            &root_cred(),
             set_attr::Args {
                 file: handle,
                 new_attr: sized_attr(None, Some(1)),
@@ -282,6 +563,311 @@ async fn set_attr_updates_size_and_honors_guard() {
     assert_eq!(stdfs::metadata(ctx.root_path().join("file.txt")).unwrap().len(), 2);
 }
 
+#[tokio::test]
+async fn set_attr_size_is_rejected_on_non_regular_files() {
+    let ctx = TestContext::new();
+    create_dir(ctx.root_path(), "dir");
+    write_file(ctx.root_path(), "target.txt", b"hello");
+    create_symlink(ctx.root_path(), "target.txt", "link");
+    let root = ctx.root_handle().await;
+    let set_size = |file: file::Handle, size: u64| set_attr::Args {
+        file,
+        new_attr: sized_attr(Some(0o700), Some(size)),
+        guard: None,
+    };
+
+    let dir = ctx.lookup_handle(root.clone(), "dir").await;
+    let dir_mode = stdfs::metadata(ctx.root_path().join("dir")).unwrap().permissions().mode();
+    let fail = expect_err(
+        set_attr::SetAttr::set_attr(&ctx.fs, &root_cred(), set_size(dir, 0)).await,
+        "set_attr should reject a size on a directory",
+    );
+    assert_eq!(fail.error, vfs::Error::IsDir);
+    assert!(fail.wcc_data.after.is_some());
+    // The mode requested along with the size is not applied either.
+    let meta = stdfs::metadata(ctx.root_path().join("dir")).unwrap();
+    assert_eq!(meta.permissions().mode(), dir_mode);
+
+    let link = ctx.lookup_handle(root.clone(), "link").await;
+    let fail = expect_err(
+        set_attr::SetAttr::set_attr(&ctx.fs, &root_cred(), set_size(link, 0)).await,
+        "set_attr should reject a size on a symlink",
+    );
+    assert_eq!(fail.error, vfs::Error::InvalidArgument);
+    assert_eq!(stdfs::read(ctx.root_path().join("target.txt")).unwrap(), b"hello");
+
+    let file = ctx.lookup_handle(root, "target.txt").await;
+    expect_ok(
+        set_attr::SetAttr::set_attr(&ctx.fs, &root_cred(), set_size(file, 2)).await,
+        "set_attr should truncate a regular file",
+    );
+    assert_eq!(stdfs::read(ctx.root_path().join("target.txt")).unwrap(), b"he");
+}
+
+#[tokio::test]
+async fn set_attr_needs_ownership_or_write_permission() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(0o644)).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let client = set_attr::SetTime::ToClient(file::Time { seconds: 2_000_000_000, nanos: 0 });
+    let set = |new_attr: set_attr::NewAttr| set_attr::Args {
+        file: handle.clone(),
+        new_attr,
+        guard: None,
+    };
+
+    for (new_attr, expected) in [
+        (sized_attr(Some(0o666), None), vfs::Error::Permission),
+        (set_attr::NewAttr { mtime: client, ..default_new_attr() }, vfs::Error::Permission),
+        (sized_attr(None, Some(0)), vfs::Error::Access),
+        (
+            set_attr::NewAttr { mtime: set_attr::SetTime::ToServer, ..default_new_attr() },
+            vfs::Error::Access,
+        ),
+    ] {
+        let fail = expect_err(
+            set_attr::SetAttr::set_attr(&ctx.fs, &user_cred(), set(new_attr)).await,
+            "set_attr should refuse a user who neither owns nor may write the file",
+        );
+        assert_eq!(fail.error, expected);
+        assert_wcc_present(&fail.wcc_data);
+    }
+    assert_eq!(stdfs::read(&path).unwrap(), b"hello");
+    assert_eq!(stdfs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o644);
+
+    // Write permission is enough for the size and the server time, but not for the mode.
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(0o666)).unwrap();
+    expect_ok(
+        set_attr::SetAttr::set_attr(&ctx.fs, &user_cred(), set(sized_attr(None, Some(2)))).await,
+        "set_attr should let a writer truncate the file",
+    );
+    expect_ok(
+        set_attr::SetAttr::set_attr(
+            &ctx.fs,
+            &user_cred(),
+            set(set_attr::NewAttr { mtime: set_attr::SetTime::ToServer, ..default_new_attr() }),
+        )
+        .await,
+        "set_attr should let a writer set the server time",
+    );
+    let fail = expect_err(
+        set_attr::SetAttr::set_attr(&ctx.fs, &user_cred(), set(sized_attr(Some(0o600), None)))
+            .await,
+        "set_attr should refuse a mode change by a non-owner",
+    );
+    assert_eq!(fail.error, vfs::Error::Permission);
+    assert_eq!(stdfs::read(&path).unwrap(), b"he");
+}
+
+/// Creates `file.txt` with both atime and mtime set to [`BASELINE`] and returns its handle.
+async fn file_with_baseline_times(ctx: &TestContext) -> file::Handle {
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    let baseline = UNIX_EPOCH + Duration::from_secs(BASELINE);
+    let times = stdfs::FileTimes::new().set_accessed(baseline).set_modified(baseline);
+    stdfs::File::options().write(true).open(path).unwrap().set_times(times).unwrap();
+    let root = ctx.root_handle().await;
+    ctx.lookup_handle(root, "file.txt").await
+}
+
+const BASELINE: u64 = 1_000_000_000;
+
+async fn set_times(
+    ctx: &TestContext,
+    handle: file::Handle,
+    atime: set_attr::SetTime,
+    mtime: set_attr::SetTime,
+) -> stdfs::Metadata {
+    expect_ok(
+        set_attr::SetAttr::set_attr(
+            &ctx.fs,
+            &root_cred(),
+            set_attr::Args {
+                file: handle,
+                new_attr: set_attr::NewAttr { atime, mtime, ..default_new_attr() },
+                guard: None,
+            },
+        )
+        .await,
+        "set_attr should set times",
+    );
+    stdfs::symlink_metadata(ctx.root_path().join("file.txt")).unwrap()
+}
+
+#[tokio::test]
+async fn set_attr_sets_mtime_from_client_and_keeps_atime() {
+    let ctx = TestContext::new();
+    let handle = file_with_baseline_times(&ctx).await;
+
+    let client = file::Time { seconds: 2_000_000_000, nanos: 5 };
+    let meta =
+        set_times(&ctx, handle, set_attr::SetTime::DontChange, set_attr::SetTime::ToClient(client))
+            .await;
+    assert_eq!(meta.atime() as u64, BASELINE);
+    assert_eq!((meta.mtime(), meta.mtime_nsec()), (2_000_000_000, 5));
+}
+
+#[tokio::test]
+async fn set_attr_sets_both_times_to_server_clock() {
+    let ctx = TestContext::new();
+    let handle = file_with_baseline_times(&ctx).await;
+    let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    let meta =
+        set_times(&ctx, handle, set_attr::SetTime::ToServer, set_attr::SetTime::ToServer).await;
+    assert!(meta.atime() >= before);
+    assert!(meta.mtime() >= before);
+}
+
+#[tokio::test]
+async fn set_attr_sets_server_time_from_configured_clock() {
+    let clock = Arc::new(MockClock::new());
+    let ctx = TestContext::new()
+        .with(|fs| fs.with_clock(clock.clone()).with_attr_cache_ttl(Duration::ZERO));
+    let handle = file_with_baseline_times(&ctx).await;
+    clock.advance(Duration::from_secs(86_400));
+    let now = clock.system_time().duration_since(UNIX_EPOCH).unwrap();
+
+    let meta =
+        set_times(&ctx, handle, set_attr::SetTime::DontChange, set_attr::SetTime::ToServer).await;
+    assert_eq!(meta.atime() as u64, BASELINE);
+    assert_eq!(
+        (meta.mtime() as u64, meta.mtime_nsec() as u32),
+        (now.as_secs(), now.subsec_nanos())
+    );
+}
+
+#[tokio::test]
+async fn set_attr_leaves_unchanged_times_alone() {
+    let ctx = TestContext::new();
+    let handle = file_with_baseline_times(&ctx).await;
+
+    let meta =
+        set_times(&ctx, handle, set_attr::SetTime::DontChange, set_attr::SetTime::DontChange).await;
+    assert_eq!(meta.atime() as u64, BASELINE);
+    assert_eq!(meta.mtime() as u64, BASELINE);
+}
+
+const ANON: u32 = 4242;
+
+/// Identity a squashed client reaches the backend with.
+fn anon_cred() -> vfs::Credentials {
+    vfs::Credentials { uid: ANON, gid: ANON, gids: Vec::new() }
+}
+
+#[tokio::test]
+async fn anonymous_client_cannot_chown_foreign_file() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    let owner = stdfs::metadata(&path).unwrap().uid();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    let fail = expect_err(
+        set_attr::SetAttr::set_attr(
+            &ctx.fs,
+            &anon_cred(),
+            set_attr::Args {
+                file: handle,
+                new_attr: set_attr::NewAttr { uid: Some(ANON), ..default_new_attr() },
+                guard: None,
+            },
+        )
+        .await,
+        "anonymous user should not chown a file it does not own",
+    );
+    assert_eq!(fail.error, vfs::Error::Permission);
+    assert_eq!(stdfs::metadata(&path).unwrap().uid(), owner);
+}
+
+#[tokio::test]
+async fn anonymous_client_may_keep_owner_of_its_own_file() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    std::os::unix::fs::chown(&path, Some(ANON), Some(ANON)).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    let success = expect_ok(
+        set_attr::SetAttr::set_attr(
+            &ctx.fs,
+            &anon_cred(),
+            set_attr::Args {
+                file: handle,
+                new_attr: set_attr::NewAttr {
+                    uid: Some(ANON),
+                    gid: Some(ANON),
+                    ..default_new_attr()
+                },
+                guard: None,
+            },
+        )
+        .await,
+        "owner may set its own owner and group",
+    );
+    let after = success.wcc_data.after.expect("after image");
+    assert_eq!((after.uid, after.gid), (ANON, ANON));
+    let meta = stdfs::metadata(&path).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (ANON, ANON));
+}
+
+#[tokio::test]
+async fn anonymous_client_writes_only_where_others_may() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(0o644)).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let args = |data| write::Args {
+        file: handle.clone(),
+        offset: 0,
+        size: 5,
+        stable: write::StableHow::Unstable,
+        data,
+    };
+
+    let fail = expect_err(
+        write::Write::write(&ctx.fs, &anon_cred(), args(slice_from_bytes(b"world").await)).await,
+        "anon uid has no write permission on 0644 file",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert_eq!(stdfs::read(&path).unwrap(), b"hello");
+
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(0o646)).unwrap();
+    let success = expect_ok(
+        write::Write::write(&ctx.fs, &anon_cred(), args(slice_from_bytes(b"world").await)).await,
+        "anon uid may write to world-writable file",
+    );
+    assert_eq!(success.count, 5);
+    assert_eq!(stdfs::read(&path).unwrap(), b"world");
+}
+
+#[tokio::test]
+async fn create_assigns_caller_uid_and_gid() {
+    let ctx = TestContext::new();
+    stdfs::set_permissions(ctx.root_path(), stdfs::Permissions::from_mode(0o777)).unwrap();
+    let root = ctx.root_handle().await;
+    let cred = vfs::Credentials { uid: 4242, gid: 4343, gids: Vec::new() };
+
+    let success = expect_ok(
+        create::Create::create(
+            &ctx.fs,
+            &cred,
+            create::Args {
+                object: dir_op(root, "owned.txt"),
+                how: create::How::Guarded(sized_attr(None, None)),
+            },
+        )
+        .await,
+        "create should succeed",
+    );
+    let attr = success.attr.unwrap();
+    assert_eq!((attr.uid, attr.gid), (4242, 4343));
+    let meta = stdfs::metadata(ctx.root_path().join("owned.txt")).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (4242, 4343));
+}
+
 #[tokio::test]
 async fn symlink_creates_symbolic_link() {
     let ctx = TestContext::new();
@@ -290,6 +876,7 @@ async fn symlink_creates_symbolic_link() {
     let success = expect_ok(
         symlink::Symlink::symlink(
             &ctx.fs,
+            &root_cred(),
             symlink::Args {
                 object: dir_op(root, "link.txt"),
                 attr: sized_attr(Some(0o700), None),
@@ -318,6 +905,7 @@ async fn write_writes_data_with_offset_and_commit_matches_verifier() {
     let write_result = expect_ok(
         write::Write::write(
             &ctx.fs,
+            &root_cred(),
             write::Args {
                 file: handle.clone(),
                 offset: 2,
@@ -344,6 +932,288 @@ async fn write_writes_data_with_offset_and_commit_matches_verifier() {
     assert_eq!(commit_result.verifier.0, write_result.verifier.0);
 }
 
+#[tokio::test]
+async fn writes_and_reads_beyond_4_gib_keep_their_64_bit_offsets() {
+    // Truncated to 32 bits, the offset would point at the start of the file.
+    const OFFSET: u64 = (5 << 32) + 3;
+    let limits = WriteBufferLimits { flush_bytes: 1024, max_bytes: 4096 };
+
+    for ctx in [TestContext::new(), TestContext::new().with(|fs| fs.with_write_buffer(limits))] {
+        let path = write_file(ctx.root_path(), "sparse.bin", b"");
+        let root = ctx.root_handle().await;
+        let handle = ctx.lookup_handle(root, "sparse.bin").await;
+
+        let written = expect_ok(
+            write::Write::write(
+                &ctx.fs,
+                &root_cred(),
+                write::Args {
+                    file: handle.clone(),
+                    offset: OFFSET,
+                    size: 4,
+                    stable: write::StableHow::Unstable,
+                    data: slice_from_bytes(b"high").await,
+                },
+            )
+            .await,
+            "write beyond 4 GiB should succeed",
+        );
+        assert_eq!(written.count, 4);
+        assert_eq!(written.file_wcc.after.unwrap().size, OFFSET + 4);
+
+        for (offset, expected) in [(OFFSET - 1, b"\0hig"), (OFFSET, b"high"), (3, b"\0\0\0\0")] {
+            let read = expect_ok(
+                read::Read::read(
+                    &ctx.fs,
+                    &root_cred(),
+                    read::Args { file: handle.clone(), offset, count: 4 },
+                    alloc_slice(4).await,
+                )
+                .await,
+                "read should succeed",
+            );
+            assert_eq!(slice_to_vec(&read.data), expected, "offset {offset:#x}");
+        }
+
+        expect_ok(
+            commit::Commit::commit(&ctx.fs, commit::Args { file: handle, offset: 0, count: 0 })
+                .await,
+            "commit should succeed",
+        );
+        let file = stdfs::File::open(&path).unwrap();
+        assert_eq!(file.metadata().unwrap().len(), OFFSET + 4);
+        let mut on_disk = [0; 4];
+        std::os::unix::fs::FileExt::read_exact_at(&file, &mut on_disk, OFFSET).unwrap();
+        assert_eq!(&on_disk, b"high");
+    }
+}
+
+#[tokio::test]
+async fn zero_length_write_succeeds_without_touching_the_file() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    for stable in [write::StableHow::Unstable, write::StableHow::FileSync] {
+        let result = expect_ok(
+            write::Write::write(
+                &ctx.fs,
+                &root_cred(),
+                write::Args {
+                    file: handle.clone(),
+                    offset: 64,
+                    size: 0,
+                    stable,
+                    data: slice_from_bytes(b"").await,
+                },
+            )
+            .await,
+            "zero-length write should succeed",
+        );
+        assert_eq!(result.count, 0);
+        assert_eq!(result.file_wcc.after.unwrap().size, 5);
+        assert_eq!(result.verifier.0, commit_verifier(&ctx, &handle).await.0);
+    }
+    assert_eq!(stdfs::read(path).unwrap(), b"hello");
+}
+
+async fn unstable_write_verifier(ctx: &TestContext, handle: &file::Handle) -> write::Verifier {
+    expect_ok(
+        write::Write::write(
+            &ctx.fs,
+            &root_cred(),
+            write::Args {
+                file: handle.clone(),
+                offset: 0,
+                size: 4,
+                stable: write::StableHow::Unstable,
+                data: slice_from_bytes(b"data").await,
+            },
+        )
+        .await,
+        "write should succeed",
+    )
+    .verifier
+}
+
+async fn commit_verifier(ctx: &TestContext, handle: &file::Handle) -> write::Verifier {
+    expect_ok(
+        commit::Commit::commit(&ctx.fs, commit::Args { file: handle.clone(), offset: 0, count: 0 })
+            .await,
+        "commit should succeed",
+    )
+    .verifier
+}
+
+#[tokio::test]
+async fn commit_after_restart_reports_new_verifier() {
+    let mut ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    let written = unstable_write_verifier(&ctx, &handle).await;
+    assert_eq!(commit_verifier(&ctx, &handle).await.0, written.0);
+
+    ctx.restart();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    // A client seeing another verifier must resend its unstable writes before committing.
+    let committed = commit_verifier(&ctx, &handle).await;
+    assert_ne!(committed.0, written.0);
+    assert_eq!(unstable_write_verifier(&ctx, &handle).await.0, committed.0);
+}
+
+#[tokio::test]
+async fn backends_sharing_a_write_epoch_report_the_same_verifier() {
+    let mut verifiers = Vec::new();
+    for epoch in [7, 7, 8] {
+        let ctx = TestContext::new().with(|fs| fs.with_write_epoch(epoch));
+        write_file(ctx.root_path(), "file.txt", b"");
+        let root = ctx.root_handle().await;
+        let handle = ctx.lookup_handle(root, "file.txt").await;
+        let written = unstable_write_verifier(&ctx, &handle).await;
+        assert_eq!(commit_verifier(&ctx, &handle).await.0, written.0);
+        verifiers.push(written.0);
+    }
+
+    assert_eq!(verifiers[0], verifiers[1]);
+    // Bumping the epoch tells clients to resend their unstable writes.
+    assert_ne!(verifiers[1], verifiers[2]);
+}
+
+#[tokio::test]
+async fn write_reports_achieved_stability() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    for (offset, stable) in [(0, write::StableHow::Unstable), (4, write::StableHow::FileSync)] {
+        let result = expect_ok(
+            write::Write::write(
+                &ctx.fs,
+                &root_cred(),
+                write::Args {
+                    file: handle.clone(),
+                    offset,
+                    size: 4,
+                    stable,
+                    data: slice_from_bytes(b"data").await,
+                },
+            )
+            .await,
+            "write should succeed",
+        );
+        assert_eq!(result.committed, stable);
+    }
+    // The file-synced write made the whole file durable.
+    assert!(ctx.fs.uncommitted_ranges(&handle).is_empty());
+}
+
+async fn file_sync_write(ctx: &TestContext, handle: &file::Handle) -> write::StableHow {
+    let result = expect_ok(
+        write::Write::write(
+            &ctx.fs,
+            &root_cred(),
+            write::Args {
+                file: handle.clone(),
+                offset: 0,
+                size: 4,
+                stable: write::StableHow::FileSync,
+                data: slice_from_bytes(b"data").await,
+            },
+        )
+        .await,
+        "write should succeed",
+    );
+    result.committed
+}
+
+#[tokio::test]
+async fn durability_policy_caps_write_stability() {
+    for (durability, committed, syncs) in [
+        (Durability::Full, write::StableHow::FileSync, 1),
+        (Durability::DataOnly, write::StableHow::DataSync, 1),
+        (Durability::None, write::StableHow::Unstable, 0),
+    ] {
+        let ctx = TestContext::new().with(|fs| fs.with_durability(durability));
+        write_file(ctx.root_path(), "file.txt", b"");
+        let root = ctx.root_handle().await;
+        let handle = ctx.lookup_handle(root, "file.txt").await;
+
+        assert_eq!(file_sync_write(&ctx, &handle).await, committed, "{durability:?}");
+        assert_eq!(ctx.fs.sync_count(), syncs, "{durability:?}");
+    }
+}
+
+#[tokio::test]
+async fn no_durability_skips_syncs_but_keeps_data() {
+    let ctx = TestContext::new().with(|fs| fs.with_durability(Durability::None));
+    write_file(ctx.root_path(), "file.txt", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    assert_eq!(file_sync_write(&ctx, &handle).await, write::StableHow::Unstable);
+    expect_ok(
+        commit::Commit::commit(&ctx.fs, commit::Args { file: handle.clone(), offset: 0, count: 0 })
+            .await,
+        "commit should succeed",
+    );
+    assert_eq!(ctx.fs.sync_count(), 0);
+
+    let read = expect_ok(
+        read::Read::read(
+            &ctx.fs,
+            &root_cred(),
+            read::Args { file: handle, offset: 0, count: 4 },
+            alloc_slice(4).await,
+        )
+        .await,
+        "read should succeed",
+    );
+    assert_eq!(slice_to_vec(&read.data), b"data");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn concurrent_writes_to_disjoint_regions_do_not_interfere() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    static LOW: [u8; 4096] = [b'a'; 4096];
+    static HIGH: [u8; 4096] = [b'b'; 4096];
+    let write_at = |offset: u64, data: &'static [u8]| {
+        let file = handle.clone();
+        let fs = &ctx.fs;
+        async move {
+            write::Write::write(
+                fs,
+                &root_cred(),
+                write::Args {
+                    file,
+                    offset,
+                    size: data.len() as u32,
+                    stable: write::StableHow::Unstable,
+                    data: slice_from_bytes(data).await,
+                },
+            )
+            .await
+        }
+    };
+
+    let (first, second) = tokio::join!(write_at(0, &LOW), write_at(4096, &HIGH));
+    assert_eq!(expect_ok(first, "low write should succeed").count, 4096);
+    assert_eq!(expect_ok(second, "high write should succeed").count, 4096);
+
+    let contents = stdfs::read(ctx.root_path().join("file.txt")).unwrap();
+    assert_eq!(contents.len(), 8192);
+    assert!(contents[..4096].iter().all(|&byte| byte == b'a'));
+    assert!(contents[4096..].iter().all(|&byte| byte == b'b'));
+}
+
 #[tokio::test]
 async fn file_lifecycle_create_edit_read_and_remove() {
     let ctx = TestContext::new();
@@ -352,6 +1222,7 @@ async fn file_lifecycle_create_edit_read_and_remove() {
     let created = expect_ok(
         create::Create::create(
             &ctx.fs,
+            &root_cred(),
             create::Args {
                 object: dir_op(root.clone(), "lifecycle.txt"),
                 how: create::How::Guarded(default_new_attr()),
@@ -365,6 +1236,7 @@ async fn file_lifecycle_create_edit_read_and_remove() {
     let write_result = expect_ok(
         write::Write::write(
             &ctx.fs,
+            &root_cred(),
             write::Args {
                 file: handle.clone(),
                 offset: 0,
@@ -381,6 +1253,7 @@ async fn file_lifecycle_create_edit_read_and_remove() {
     let read_result = expect_ok(
         read::Read::read(
             &ctx.fs,
+            &root_cred(),
             read::Args { file: handle.clone(), offset: 0, count: 11 },
             alloc_slice(11).await,
         )
@@ -392,8 +1265,12 @@ async fn file_lifecycle_create_edit_read_and_remove() {
     assert_eq!(slice_to_vec(&read_result.data), b"hello world");
 
     let removed = expect_ok(
-        remove::Remove::remove(&ctx.fs, remove::Args { object: dir_op(root, "lifecycle.txt") })
-            .await,
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root, "lifecycle.txt") },
+        )
+        .await,
         "file delete should succeed",
     );
     assert_wcc_present(&removed.wcc_data);
@@ -1,4 +1,8 @@
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::time::{Duration, SystemTime};
+
 use nfs_mamont::vfs;
+use nfs_mamont::vfs::create;
 use nfs_mamont::vfs::file;
 use nfs_mamont::vfs::get_attr;
 use nfs_mamont::vfs::lookup;
@@ -9,9 +13,11 @@ use nfs_mamont::vfs::rename;
 use nfs_mamont::vfs::rm_dir;
 use nfs_mamont::vfs::symlink;
 
+use crate::fs::MirrorFS;
+
 use super::helpers::{
-    assert_wcc_present, create_dir, dir_op, expect_err, expect_ok, file_path, name, write_file,
-    TestContext,
+    assert_wcc_present, create_dir, create_dir_with_mode, create_symlink, default_new_attr, dir_op,
+    expect_err, expect_ok, file_path, name, root_cred, user_cred, write_file, TestContext,
 };
 
 #[tokio::test]
@@ -62,20 +68,22 @@ async fn lookup_resolves_dot_and_dotdot() {
         "get_attr for directory should succeed",
     );
     let dot_attr = expect_ok(
-        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: dot.file }).await,
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: dot.file.clone() }).await,
         "get_attr for '.' result should succeed",
     );
     assert_eq!(dot_attr.object.file_id, dir_attr.object.file_id);
+    assert!(dot.file == dir);
 
     let dotdot = expect_ok(
         lookup::Lookup::lookup(&ctx.fs, lookup::Args { parent: nested, name: name("..") }).await,
         "lookup '..' should resolve to the parent directory",
     );
     let dotdot_attr = expect_ok(
-        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: dotdot.file }).await,
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: dotdot.file.clone() }).await,
         "get_attr for '..' result should succeed",
     );
     assert_eq!(dotdot_attr.object.file_id, dir_attr.object.file_id);
+    assert!(dotdot.file == dir);
 
     let root_parent = expect_ok(
         lookup::Lookup::lookup(&ctx.fs, lookup::Args { parent: root.clone(), name: name("..") })
@@ -87,10 +95,129 @@ async fn lookup_resolves_dot_and_dotdot() {
         "get_attr for root should succeed",
     );
     let root_parent_attr = expect_ok(
-        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: root_parent.file }).await,
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: root_parent.file.clone() })
+            .await,
         "get_attr for root '..' should succeed",
     );
     assert_eq!(root_parent_attr.object.file_id, root_attr.object.file_id);
+    assert!(root_parent.file == root);
+}
+
+#[tokio::test]
+async fn lookup_through_symlink_cycle_terminates_with_error() {
+    let ctx = TestContext::new();
+    create_dir(ctx.root_path(), "dir");
+    create_symlink(ctx.root_path(), ".", "loop");
+    create_symlink(ctx.root_path(), "b", "a");
+    create_symlink(ctx.root_path(), "a", "b");
+
+    // Every `loop` component leads back to the root, so the path never gets anywhere.
+    let looping = (0..17).fold(ctx.root_path().to_path_buf(), |path, _| path.join("loop"));
+    let dir = ctx.fs.handle_for_path(&looping.join("dir")).await.unwrap();
+    let fail = expect_err(
+        lookup::Lookup::lookup(&ctx.fs, lookup::Args { parent: dir.clone(), name: name("x") })
+            .await,
+        "lookup through too many symlinks should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::TooManyLevelsOfRemote);
+
+    let fail = expect_err(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args { from: dir_op(dir, "x"), to: dir_op(ctx.root_handle().await, "y") },
+        )
+        .await,
+        "rename through too many symlinks should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::TooManyLevelsOfRemote);
+
+    let cycle = ctx.fs.handle_for_path(&ctx.root_path().join("a/x")).await;
+    assert_eq!(cycle.unwrap_err(), vfs::Error::TooManyLevelsOfRemote);
+}
+
+#[tokio::test]
+async fn lookup_rejects_paths_longer_than_max_path_len() {
+    let ctx = TestContext::new();
+    let component = "d".repeat(250);
+    let deep = [component.as_str(); 5].join("/");
+    create_dir(ctx.root_path(), &deep);
+    let dir = ctx.fs.handle_for_path(&ctx.root_path().join(&deep)).await.unwrap();
+
+    let fail = expect_err(
+        lookup::Lookup::lookup(&ctx.fs, lookup::Args { parent: dir, name: name("x") }).await,
+        "lookup beyond the path length limit should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::NameTooLong);
+}
+
+#[tokio::test]
+async fn lookup_dotdot_walks_up_nested_directories() {
+    let ctx = TestContext::new();
+    create_dir(ctx.root_path(), "a/b/c");
+    let root = ctx.root_handle().await;
+    let a = ctx.lookup_handle(root.clone(), "a").await;
+    let b = ctx.lookup_handle(a.clone(), "b").await;
+    let c = ctx.lookup_handle(b.clone(), "c").await;
+
+    let up = ctx.lookup_handle(c, "..").await;
+    assert!(up == b);
+    let up = ctx.lookup_handle(up, "..").await;
+    assert!(up == a);
+    let up = ctx.lookup_handle(up, "..").await;
+    assert!(up == root);
+    let up = ctx.lookup_handle(up, "..").await;
+    assert!(up == root);
+}
+
+#[tokio::test]
+async fn lookup_matches_case_only_in_case_insensitive_mode() {
+    let sensitive = TestContext::new();
+    write_file(sensitive.root_path(), "Foo.txt", b"data");
+    let root = sensitive.root_handle().await;
+    let fail = expect_err(
+        lookup::Lookup::lookup(&sensitive.fs, lookup::Args { parent: root, name: name("foo.txt") })
+            .await,
+        "case-sensitive lookup should not match a different case",
+    );
+    assert_eq!(fail.error, vfs::Error::NoEntry);
+
+    let insensitive = TestContext::new().with(|fs| fs.with_case_insensitive(true));
+    write_file(insensitive.root_path(), "Foo.txt", b"data");
+    let root = insensitive.root_handle().await;
+    let exact = insensitive.lookup_handle(root.clone(), "Foo.txt").await;
+    let folded = insensitive.lookup_handle(root.clone(), "foo.txt").await;
+    assert_eq!(exact, folded);
+
+    expect_ok(
+        remove::Remove::remove(
+            &insensitive.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root, "FOO.TXT") },
+        )
+        .await,
+        "case-insensitive remove should succeed",
+    );
+    assert!(!insensitive.root_path().join("Foo.txt").exists());
+}
+
+#[tokio::test]
+async fn rename_changes_case_in_case_insensitive_mode() {
+    let ctx = TestContext::new().with(|fs| fs.with_case_insensitive(true));
+    write_file(ctx.root_path(), "Foo.txt", b"data");
+    let root = ctx.root_handle().await;
+
+    expect_ok(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args { from: dir_op(root.clone(), "foo.txt"), to: dir_op(root, "FOO.txt") },
+        )
+        .await,
+        "case-only rename should succeed",
+    );
+    assert!(ctx.root_path().join("FOO.txt").exists());
+    assert!(!ctx.root_path().join("Foo.txt").exists());
 }
 
 #[tokio::test]
@@ -99,13 +226,19 @@ async fn remove_rejects_dot_and_dotdot() {
     let root = ctx.root_handle().await;
 
     let dot_fail = expect_err(
-        remove::Remove::remove(&ctx.fs, remove::Args { object: dir_op(root.clone(), ".") }).await,
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root.clone(), ".") },
+        )
+        .await,
         "remove '.' should be rejected",
     );
     assert_eq!(dot_fail.error, vfs::Error::InvalidArgument);
 
     let dotdot_fail = expect_err(
-        remove::Remove::remove(&ctx.fs, remove::Args { object: dir_op(root, "..") }).await,
+        remove::Remove::remove(&ctx.fs, &root_cred(), remove::Args { object: dir_op(root, "..") })
+            .await,
         "remove '..' should be rejected",
     );
     assert_eq!(dotdot_fail.error, vfs::Error::Exist);
@@ -119,7 +252,12 @@ async fn remove_deletes_file_and_invalidates_cached_handle() {
     let file_handle = ctx.lookup_handle(root.clone(), "file.txt").await;
 
     let success = expect_ok(
-        remove::Remove::remove(&ctx.fs, remove::Args { object: dir_op(root, "file.txt") }).await,
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root, "file.txt") },
+        )
+        .await,
         "remove should succeed",
     );
     assert_wcc_present(&success.wcc_data);
@@ -132,6 +270,81 @@ async fn remove_deletes_file_and_invalidates_cached_handle() {
     assert_eq!(fail.error, vfs::Error::StaleFile);
 }
 
+#[tokio::test]
+async fn removed_handle_stays_stale_after_recreate() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let old_handle = ctx.lookup_handle(root.clone(), "file.txt").await;
+
+    expect_ok(
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root.clone(), "file.txt") },
+        )
+        .await,
+        "remove should succeed",
+    );
+    write_file(ctx.root_path(), "file.txt", b"again");
+    let new_handle = ctx.lookup_handle(root, "file.txt").await;
+    assert!(old_handle != new_handle);
+
+    let fail = expect_err(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: old_handle }).await,
+        "handle from before remove should be stale",
+    );
+    assert_eq!(fail.error, vfs::Error::StaleFile);
+    expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: new_handle }).await,
+        "handle of recreated file should resolve",
+    );
+}
+
+#[tokio::test]
+async fn remove_and_rename_drop_cached_attributes_of_affected_objects_only() {
+    let ctx = TestContext::new().with(|fs| fs.with_attr_cache_ttl(Duration::from_secs(60)));
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    write_file(ctx.root_path(), "other.txt", b"other");
+    std::fs::hard_link(ctx.root_path().join("file.txt"), ctx.root_path().join("alias.txt"))
+        .unwrap();
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root.clone(), "file.txt").await;
+    let other = ctx.lookup_handle(root.clone(), "other.txt").await;
+    let get_attr = |file: &file::Handle| {
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: file.clone() })
+    };
+
+    // The removed name was never looked up, yet the link count of its file changed.
+    expect_ok(
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root.clone(), "alias.txt") },
+        )
+        .await,
+        "remove should succeed",
+    );
+    let calls = ctx.fs.metadata_calls();
+    assert_eq!(expect_ok(get_attr(&file).await, "get_attr should succeed").object.nlink, 1);
+    expect_ok(get_attr(&other).await, "get_attr should succeed");
+    assert_eq!(ctx.fs.metadata_calls(), calls + 1);
+
+    expect_ok(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args { from: dir_op(root.clone(), "file.txt"), to: dir_op(root, "moved.txt") },
+        )
+        .await,
+        "rename should succeed",
+    );
+    let calls = ctx.fs.metadata_calls();
+    expect_ok(get_attr(&file).await, "renamed handle should resolve");
+    expect_ok(get_attr(&other).await, "get_attr should succeed");
+    assert_eq!(ctx.fs.metadata_calls(), calls + 1);
+}
+
 #[tokio::test]
 async fn rename_moves_subtree_and_updates_cached_descendants() {
     let ctx = TestContext::new();
@@ -145,6 +358,7 @@ async fn rename_moves_subtree_and_updates_cached_descendants() {
     let success = expect_ok(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args { from: dir_op(root.clone(), "dir"), to: dir_op(root.clone(), "moved") },
         )
         .await,
@@ -191,7 +405,12 @@ async fn removing_one_hard_link_keeps_shared_handle_valid() {
     assert!(original == alias);
 
     let success = expect_ok(
-        remove::Remove::remove(&ctx.fs, remove::Args { object: dir_op(root, "alias.txt") }).await,
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root, "alias.txt") },
+        )
+        .await,
         "remove hard-link alias should succeed",
     );
     assert_wcc_present(&success.wcc_data);
@@ -213,6 +432,7 @@ async fn rename_replaces_existing_file_atomically() {
     let success = expect_ok(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args {
                 from: dir_op(root.clone(), "src.txt"),
                 to: dir_op(root.clone(), "dst.txt"),
@@ -237,6 +457,7 @@ async fn rename_replaces_empty_directory() {
     let success = expect_ok(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args {
                 from: dir_op(root.clone(), "src_dir"),
                 to: dir_op(root.clone(), "dst_dir"),
@@ -249,6 +470,64 @@ async fn rename_replaces_empty_directory() {
     assert!(ctx.root_path().join("dst_dir/file.txt").exists());
 }
 
+#[tokio::test]
+async fn rename_onto_non_empty_directory_reports_not_empty() {
+    let ctx = TestContext::new();
+    create_dir(ctx.root_path(), "src_dir");
+    write_file(ctx.root_path(), "dst_dir/keep.txt", b"data");
+    let root = ctx.root_handle().await;
+    let kept =
+        ctx.lookup_handle(ctx.lookup_handle(root.clone(), "dst_dir").await, "keep.txt").await;
+
+    let fail = expect_err(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args {
+                from: dir_op(root.clone(), "src_dir"),
+                to: dir_op(root.clone(), "dst_dir"),
+            },
+        )
+        .await,
+        "rename onto non-empty directory should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::NotEmpty);
+    assert!(ctx.root_path().join("src_dir").is_dir());
+    // The failed rename leaves handles of the target tree intact.
+    expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: kept }).await,
+        "handle below the target should stay valid",
+    );
+}
+
+#[tokio::test]
+async fn rename_across_file_systems_reports_xdev() {
+    let ctx = TestContext::new();
+    let Ok(other) = tempfile::tempdir_in("/dev/shm") else {
+        return;
+    };
+    let same_device = |path: &std::path::Path| std::fs::metadata(path).unwrap().dev();
+    if same_device(other.path()) == same_device(ctx.root_path()) {
+        return;
+    }
+    write_file(ctx.root_path(), "file.txt", b"data");
+    std::os::unix::fs::symlink(other.path(), ctx.root_path().join("elsewhere")).unwrap();
+    let root = ctx.root_handle().await;
+    let elsewhere = ctx.fs.handle_for_path(&ctx.root_path().join("elsewhere")).await.unwrap();
+
+    let fail = expect_err(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args { from: dir_op(root, "file.txt"), to: dir_op(elsewhere, "file.txt") },
+        )
+        .await,
+        "rename to another file system should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::XDev);
+    assert!(ctx.root_path().join("file.txt").exists());
+}
+
 #[tokio::test]
 async fn rename_rejects_type_mismatch() {
     let ctx = TestContext::new();
@@ -259,6 +538,7 @@ async fn rename_rejects_type_mismatch() {
     let file_to_dir = expect_err(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args {
                 from: dir_op(root.clone(), "file.txt"),
                 to: dir_op(root.clone(), "dir"),
@@ -272,6 +552,7 @@ async fn rename_rejects_type_mismatch() {
     let dir_to_file = expect_err(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args { from: dir_op(root.clone(), "dir"), to: dir_op(root, "file.txt") },
         )
         .await,
@@ -289,6 +570,7 @@ async fn rename_self_is_noop() {
     let success = expect_ok(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args { from: dir_op(root.clone(), "file.txt"), to: dir_op(root, "file.txt") },
         )
         .await,
@@ -298,6 +580,97 @@ async fn rename_self_is_noop() {
     assert_eq!(std::fs::read(ctx.root_path().join("file.txt")).unwrap(), b"data");
 }
 
+#[tokio::test]
+async fn rename_self_leaves_attributes_and_handle_unchanged() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"data");
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root.clone(), "file.txt").await;
+    let before = expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: file.clone() }).await,
+        "get_attr before rename should succeed",
+    )
+    .object;
+
+    expect_ok(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args {
+                from: dir_op(root.clone(), "file.txt"),
+                to: dir_op(root.clone(), "file.txt"),
+            },
+        )
+        .await,
+        "rename file onto itself should succeed",
+    );
+
+    let after = expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: file.clone() }).await,
+        "handle should stay valid after renaming onto itself",
+    )
+    .object;
+    assert_eq!(after.file_id, before.file_id);
+    assert_eq!(after.size, before.size);
+    assert_eq!(after.nlink, before.nlink);
+    assert_eq!(
+        (after.mtime.seconds, after.mtime.nanos),
+        (before.mtime.seconds, before.mtime.nanos)
+    );
+    assert_eq!(
+        (after.ctime.seconds, after.ctime.nanos),
+        (before.ctime.seconds, before.ctime.nanos)
+    );
+    assert!(ctx.lookup_handle(root, "file.txt").await == file);
+
+    let fail = expect_err(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args {
+                from: dir_op(ctx.root_handle().await, "missing.txt"),
+                to: dir_op(ctx.root_handle().await, "missing.txt"),
+            },
+        )
+        .await,
+        "renaming a missing file onto itself should fail",
+    );
+    assert!(matches!(fail.error, vfs::Error::NoEntry));
+}
+
+#[tokio::test]
+async fn rename_onto_hard_link_of_same_file_keeps_both_names() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"data");
+    std::fs::hard_link(ctx.root_path().join("file.txt"), ctx.root_path().join("link.txt")).unwrap();
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root.clone(), "file.txt").await;
+    let link = ctx.lookup_handle(root.clone(), "link.txt").await;
+
+    expect_ok(
+        rename::Rename::rename(
+            &ctx.fs,
+            &root_cred(),
+            rename::Args {
+                from: dir_op(root.clone(), "file.txt"),
+                to: dir_op(root.clone(), "link.txt"),
+            },
+        )
+        .await,
+        "rename onto a hard link of the same file should succeed",
+    );
+
+    assert!(ctx.root_path().join("file.txt").exists());
+    assert!(ctx.root_path().join("link.txt").exists());
+    for handle in [file, link] {
+        let attr = expect_ok(
+            get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: handle }).await,
+            "both handles should stay valid",
+        );
+        assert_eq!(attr.object.nlink, 2);
+    }
+}
+
 #[tokio::test]
 async fn rm_dir_removes_empty_directory_and_rejects_non_empty_one() {
     let ctx = TestContext::new();
@@ -307,15 +680,24 @@ async fn rm_dir_removes_empty_directory_and_rejects_non_empty_one() {
     let root = ctx.root_handle().await;
 
     let success = expect_ok(
-        rm_dir::RmDir::rm_dir(&ctx.fs, rm_dir::Args { object: dir_op(root.clone(), "empty") })
-            .await,
+        rm_dir::RmDir::rm_dir(
+            &ctx.fs,
+            &root_cred(),
+            rm_dir::Args { object: dir_op(root.clone(), "empty") },
+        )
+        .await,
         "rm_dir should remove empty directories",
     );
     assert_wcc_present(&success.wcc_data);
     assert!(!ctx.root_path().join("empty").exists());
 
     let fail = expect_err(
-        rm_dir::RmDir::rm_dir(&ctx.fs, rm_dir::Args { object: dir_op(root, "non-empty") }).await,
+        rm_dir::RmDir::rm_dir(
+            &ctx.fs,
+            &root_cred(),
+            rm_dir::Args { object: dir_op(root, "non-empty") },
+        )
+        .await,
         "rm_dir should fail for non-empty directories",
     );
     assert_eq!(fail.error, vfs::Error::NotEmpty);
@@ -329,6 +711,7 @@ async fn directory_lifecycle_create_symlink_rename_and_remove() {
     let created = expect_ok(
         mk_dir::MkDir::mk_dir(
             &ctx.fs,
+            &root_cred(),
             mk_dir::Args {
                 object: dir_op(root.clone(), "docs"),
                 attr: super::helpers::default_new_attr(),
@@ -343,6 +726,7 @@ async fn directory_lifecycle_create_symlink_rename_and_remove() {
     let link = expect_ok(
         symlink::Symlink::symlink(
             &ctx.fs,
+            &root_cred(),
             symlink::Args {
                 object: dir_op(root.clone(), "docs-link"),
                 attr: super::helpers::default_new_attr(),
@@ -358,11 +742,12 @@ async fn directory_lifecycle_create_symlink_rename_and_remove() {
         read_link::ReadLink::read_link(&ctx.fs, read_link::Args { file: link_handle }).await,
         "read_link for directory symlink should succeed",
     );
-    assert_eq!(target.data.as_path(), std::path::Path::new("docs"));
+    assert_eq!(target.data, b"docs");
 
     let renamed = expect_ok(
         rename::Rename::rename(
             &ctx.fs,
+            &root_cred(),
             rename::Args {
                 from: dir_op(root.clone(), "docs"),
                 to: dir_op(root.clone(), "docs-renamed"),
@@ -382,8 +767,12 @@ async fn directory_lifecycle_create_symlink_rename_and_remove() {
     assert!(matches!(attr.object.file_type, file::Type::Directory));
 
     let removed_link = expect_ok(
-        remove::Remove::remove(&ctx.fs, remove::Args { object: dir_op(root.clone(), "docs-link") })
-            .await,
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(root.clone(), "docs-link") },
+        )
+        .await,
         "symlink removal should succeed",
     );
     assert_wcc_present(&removed_link.wcc_data);
@@ -391,6 +780,7 @@ async fn directory_lifecycle_create_symlink_rename_and_remove() {
     let removed_file = expect_ok(
         remove::Remove::remove(
             &ctx.fs,
+            &root_cred(),
             remove::Args { object: dir_op(docs_handle.clone(), "note.txt") },
         )
         .await,
@@ -399,9 +789,275 @@ async fn directory_lifecycle_create_symlink_rename_and_remove() {
     assert_wcc_present(&removed_file.wcc_data);
 
     let removed_dir = expect_ok(
-        rm_dir::RmDir::rm_dir(&ctx.fs, rm_dir::Args { object: dir_op(root, "docs-renamed") }).await,
+        rm_dir::RmDir::rm_dir(
+            &ctx.fs,
+            &root_cred(),
+            rm_dir::Args { object: dir_op(root, "docs-renamed") },
+        )
+        .await,
         "empty renamed directory should be removable",
     );
     assert_wcc_present(&removed_dir.wcc_data);
     assert!(!ctx.root_path().join("docs-renamed").exists());
 }
+
+#[tokio::test]
+async fn repeated_lookup_of_missing_name_is_served_from_negative_cache() {
+    let ctx = TestContext::new().with(|fs| fs.with_negative_lookup_ttl(Duration::from_secs(60)));
+    let root = ctx.root_handle().await;
+    let missing = || lookup::Args { parent: root.clone(), name: name("missing.h") };
+
+    for _ in 0..3 {
+        let fail = expect_err(
+            lookup::Lookup::lookup(&ctx.fs, missing()).await,
+            "lookup of a missing name should fail",
+        );
+        assert_eq!(fail.error, vfs::Error::NoEntry);
+        assert!(fail.dir_attr.is_some());
+    }
+    assert_eq!(ctx.fs.lookup_calls(), 1);
+
+    expect_ok(
+        create::Create::create(
+            &ctx.fs,
+            &root_cred(),
+            create::Args {
+                object: dir_op(root.clone(), "missing.h"),
+                how: create::How::Guarded(default_new_attr()),
+            },
+        )
+        .await,
+        "create should succeed",
+    );
+    expect_ok(lookup::Lookup::lookup(&ctx.fs, missing()).await, "created name should be found");
+    assert_eq!(ctx.fs.lookup_calls(), 2);
+}
+
+#[tokio::test]
+async fn restarted_mirror_with_handle_registry_issues_same_handles() {
+    let ctx = TestContext::new();
+    let state = tempfile::tempdir().unwrap();
+    let journal = state.path().join("handles");
+    create_dir(ctx.root_path(), "dir");
+    write_file(ctx.root_path(), "dir/file.txt", b"data");
+
+    let lookup_both = |fs: MirrorFS| async move {
+        let root = fs.root_handle().await;
+        let dir = expect_ok(
+            lookup::Lookup::lookup(&fs, lookup::Args { parent: root, name: name("dir") }).await,
+            "lookup dir should succeed",
+        )
+        .file;
+        let file = expect_ok(
+            lookup::Lookup::lookup(
+                &fs,
+                lookup::Args { parent: dir.clone(), name: name("file.txt") },
+            )
+            .await,
+            "lookup file should succeed",
+        )
+        .file;
+        (dir, file)
+    };
+
+    let first =
+        MirrorFS::new(ctx.root_path().to_path_buf()).with_handle_registry(&journal).unwrap();
+    let (dir, file) = lookup_both(first).await;
+    let second =
+        MirrorFS::new(ctx.root_path().to_path_buf()).with_handle_registry(&journal).unwrap();
+    // Without the registry the first lookup after a restart would take the id of `dir`.
+    write_file(ctx.root_path(), "other.txt", b"other");
+    let root = second.root_handle().await;
+    expect_ok(
+        lookup::Lookup::lookup(&second, lookup::Args { parent: root, name: name("other.txt") })
+            .await,
+        "lookup other should succeed",
+    );
+    let (dir_again, file_again) = lookup_both(second).await;
+    assert!(dir == dir_again);
+    assert!(file == file_again);
+}
+
+#[tokio::test]
+async fn remove_and_rm_dir_report_directory_mtime_before_and_after() {
+    let ctx = TestContext::new();
+    let dir = create_dir(ctx.root_path(), "parent");
+    write_file(ctx.root_path(), "parent/file.txt", b"data");
+    create_dir(ctx.root_path(), "parent/child");
+    let dir_handle = ctx.lookup_handle(ctx.root_handle().await, "parent").await;
+    let age_dir = || {
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        std::fs::File::open(&dir).unwrap().set_modified(old).unwrap();
+    };
+    let mtime_advanced = |wcc: &vfs::WccData| {
+        let before = wcc.before.expect("directory pre-op attributes must be present").mtime;
+        let after = wcc.after.as_ref().expect("directory post-op attributes must be present").mtime;
+        (after.seconds, after.nanos) > (before.seconds, before.nanos)
+    };
+
+    age_dir();
+    let removed = expect_ok(
+        remove::Remove::remove(
+            &ctx.fs,
+            &root_cred(),
+            remove::Args { object: dir_op(dir_handle.clone(), "file.txt") },
+        )
+        .await,
+        "file removal should succeed",
+    );
+    assert!(mtime_advanced(&removed.wcc_data));
+
+    age_dir();
+    let removed = expect_ok(
+        rm_dir::RmDir::rm_dir(
+            &ctx.fs,
+            &root_cred(),
+            rm_dir::Args { object: dir_op(dir_handle.clone(), "child") },
+        )
+        .await,
+        "directory removal should succeed",
+    );
+    assert!(mtime_advanced(&removed.wcc_data));
+
+    let fail = expect_err(
+        rm_dir::RmDir::rm_dir(
+            &ctx.fs,
+            &root_cred(),
+            rm_dir::Args { object: dir_op(dir_handle, "..") },
+        )
+        .await,
+        "removing .. should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::InvalidArgument);
+    assert_wcc_present(&fail.dir_wcc);
+}
+
+#[tokio::test]
+async fn mk_dir_needs_write_permission_and_gives_the_caller_ownership() {
+    let ctx = TestContext::new();
+    let parent = create_dir_with_mode(ctx.root_path(), "parent", 0o755);
+    let handle = ctx.lookup_handle(ctx.root_handle().await, "parent").await;
+    let args =
+        || mk_dir::Args { object: dir_op(handle.clone(), "child"), attr: default_new_attr() };
+
+    let fail = expect_err(
+        mk_dir::MkDir::mk_dir(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not create entries in a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert!(!parent.join("child").exists());
+
+    std::fs::set_permissions(&parent, std::fs::Permissions::from_mode(0o777)).unwrap();
+    expect_ok(
+        mk_dir::MkDir::mk_dir(&ctx.fs, &user_cred(), args()).await,
+        "anybody may create entries in a 0777 directory",
+    );
+    let meta = std::fs::metadata(parent.join("child")).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (user_cred().uid, user_cred().gid));
+}
+
+#[tokio::test]
+async fn symlink_needs_write_permission_and_gives_the_caller_ownership() {
+    let ctx = TestContext::new();
+    let parent = create_dir_with_mode(ctx.root_path(), "parent", 0o755);
+    let handle = ctx.lookup_handle(ctx.root_handle().await, "parent").await;
+    let args = || symlink::Args {
+        object: dir_op(handle.clone(), "link"),
+        attr: default_new_attr(),
+        path: file_path("target"),
+    };
+
+    let fail = expect_err(
+        symlink::Symlink::symlink(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not create entries in a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert!(std::fs::symlink_metadata(parent.join("link")).is_err());
+
+    std::fs::set_permissions(&parent, std::fs::Permissions::from_mode(0o777)).unwrap();
+    expect_ok(
+        symlink::Symlink::symlink(&ctx.fs, &user_cred(), args()).await,
+        "anybody may create entries in a 0777 directory",
+    );
+    let meta = std::fs::symlink_metadata(parent.join("link")).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (user_cred().uid, user_cred().gid));
+}
+
+#[tokio::test]
+async fn remove_needs_write_permission_and_ownership_in_sticky_directory() {
+    let ctx = TestContext::new();
+    let parent = create_dir_with_mode(ctx.root_path(), "parent", 0o755);
+    let file = write_file(ctx.root_path(), "parent/file.txt", b"data");
+    let handle = ctx.lookup_handle(ctx.root_handle().await, "parent").await;
+    let args = || remove::Args { object: dir_op(handle.clone(), "file.txt") };
+
+    let fail = expect_err(
+        remove::Remove::remove(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not remove entries of a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+
+    std::fs::set_permissions(&parent, std::fs::Permissions::from_mode(0o1777)).unwrap();
+    let fail = expect_err(
+        remove::Remove::remove(&ctx.fs, &user_cred(), args()).await,
+        "only owners may remove entries of a sticky directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Permission);
+    assert!(file.exists());
+
+    std::os::unix::fs::chown(&file, Some(user_cred().uid), None).unwrap();
+    expect_ok(
+        remove::Remove::remove(&ctx.fs, &user_cred(), args()).await,
+        "the owner of the file may remove it from a sticky directory",
+    );
+    assert!(!file.exists());
+}
+
+#[tokio::test]
+async fn rm_dir_needs_write_permission_on_parent() {
+    let ctx = TestContext::new();
+    create_dir_with_mode(ctx.root_path(), "parent", 0o755);
+    let child = create_dir(ctx.root_path(), "parent/child");
+    let handle = ctx.lookup_handle(ctx.root_handle().await, "parent").await;
+
+    let fail = expect_err(
+        rm_dir::RmDir::rm_dir(
+            &ctx.fs,
+            &user_cred(),
+            rm_dir::Args { object: dir_op(handle, "child") },
+        )
+        .await,
+        "non-owner should not remove entries of a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert!(child.exists());
+}
+
+#[tokio::test]
+async fn rename_needs_write_permission_on_both_directories() {
+    let ctx = TestContext::new();
+    create_dir_with_mode(ctx.root_path(), "from", 0o777);
+    let to = create_dir_with_mode(ctx.root_path(), "to", 0o755);
+    let file = write_file(ctx.root_path(), "from/file.txt", b"data");
+    let root = ctx.root_handle().await;
+    let from_handle = ctx.lookup_handle(root.clone(), "from").await;
+    let to_handle = ctx.lookup_handle(root, "to").await;
+    let args = || rename::Args {
+        from: dir_op(from_handle.clone(), "file.txt"),
+        to: dir_op(to_handle.clone(), "file.txt"),
+    };
+
+    let fail = expect_err(
+        rename::Rename::rename(&ctx.fs, &user_cred(), args()).await,
+        "non-owner should not add entries to a 0755 directory",
+    );
+    assert_eq!(fail.error, vfs::Error::Access);
+    assert!(file.exists());
+
+    std::fs::set_permissions(&to, std::fs::Permissions::from_mode(0o777)).unwrap();
+    expect_ok(
+        rename::Rename::rename(&ctx.fs, &user_cred(), args()).await,
+        "anybody may move entries between 0777 directories",
+    );
+    assert!(to.join("file.txt").exists());
+}
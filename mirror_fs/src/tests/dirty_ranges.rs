@@ -0,0 +1,38 @@
+use nfs_mamont::vfs::file;
+
+use crate::dirty_ranges::DirtyRanges;
+
+fn handle(id: u8) -> file::Handle {
+    let mut raw = [0u8; 8];
+    raw[0] = id;
+    file::Handle(raw)
+}
+
+#[test]
+fn record_coalesces_overlapping_and_adjacent_ranges() {
+    let dirty = DirtyRanges::new();
+    let file = handle(1);
+
+    dirty.record(&file, 100, 50);
+    dirty.record(&file, 0, 10);
+    dirty.record(&file, 10, 10);
+    dirty.record(&file, 140, 20);
+    dirty.record(&file, 500, 0);
+    assert_eq!(dirty.ranges(&file), vec![0..20, 100..160]);
+    assert!(dirty.ranges(&handle(2)).is_empty());
+}
+
+#[test]
+fn take_splits_ranges_at_window_bounds() {
+    let dirty = DirtyRanges::new();
+    let file = handle(1);
+    dirty.record(&file, 0, 100);
+    dirty.record(&file, 200, 100);
+
+    assert_eq!(dirty.take(&file, 50, 200), vec![50..100, 200..250]);
+    assert_eq!(dirty.ranges(&file), vec![0..50, 250..300]);
+
+    assert!(dirty.take(&file, 100, 100).is_empty());
+    assert_eq!(dirty.take(&file, 0, 0), vec![0..50, 250..300]);
+    assert!(dirty.ranges(&file).is_empty());
+}
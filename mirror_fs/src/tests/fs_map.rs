@@ -1,5 +1,6 @@
 use crate::fs_map::FsMap;
 use std::fs;
+use std::io::Write;
 
 use nfs_mamont::vfs;
 use nfs_mamont::vfs::file;
@@ -27,6 +28,27 @@ fn root_handle_and_reused_handles() {
     assert_eq!(error, vfs::Error::BadFileHandle);
 }
 
+#[test]
+fn handles_never_resolve_outside_root() {
+    let parent = tempfile::tempdir().unwrap();
+    let root = parent.path().join("export");
+    std::fs::create_dir(&root).unwrap();
+    std::fs::write(parent.path().join("secret.txt"), b"secret").unwrap();
+    let mut fs_map = FsMap::new(root.clone());
+
+    let escaping = root.join("../secret.txt");
+    let error = expect_err(fs_map.ensure_handle_for_path(&escaping), "escaping path must fail");
+    assert_eq!(error, vfs::Error::Access);
+
+    // Guessed handles for ids which were never issued resolve to nothing.
+    for id in [2u32, 3, u32::MAX] {
+        let mut raw = [0u8; 8];
+        raw[..4].copy_from_slice(&id.to_be_bytes());
+        let error = expect_err(fs_map.path_for_handle(&file::Handle(raw)), "guessed handle");
+        assert_eq!(error, vfs::Error::StaleFile);
+    }
+}
+
 #[test]
 fn remove_path_invalidates_subtree_handles() {
     let tempdir = tempfile::tempdir().unwrap();
@@ -67,6 +89,30 @@ fn rename_path_moves_cached_descendants() {
     assert_eq!(fs_map.path_for_handle(&child_handle).unwrap(), child_to);
 }
 
+#[test]
+fn rename_path_updates_both_directions_and_forgets_old_path() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let mut fs_map = FsMap::new(tempdir.path().to_path_buf());
+
+    let from = tempdir.path().join("old.txt");
+    let to = tempdir.path().join("new.txt");
+    fs::write(&from, b"hello").unwrap();
+    let handle = fs_map.ensure_handle_for_path(&from).unwrap();
+
+    fs::rename(&from, &to).unwrap();
+    fs_map.rename_path(&from, &to).unwrap();
+
+    assert_eq!(fs_map.path_for_handle(&handle).unwrap(), to);
+    assert!(fs_map.ensure_handle_for_path(&to).unwrap() == handle);
+
+    // A new file at the old name is a new object, not the renamed one.
+    fs::write(&from, b"other").unwrap();
+    let recreated = fs_map.ensure_handle_for_path(&from).unwrap();
+    assert!(recreated != handle);
+    assert_eq!(fs_map.path_for_handle(&recreated).unwrap(), from);
+    assert_eq!(fs_map.path_for_handle(&handle).unwrap(), to);
+}
+
 #[test]
 fn decode_handle_zero_returns_bad_file_handle() {
     let tempdir = tempfile::tempdir().unwrap();
@@ -108,3 +154,159 @@ fn removing_one_hard_link_keeps_handle_alive_if_another_path_is_cached() {
 
     assert_eq!(fs_map.path_for_handle(&handle).unwrap(), original);
 }
+
+#[test]
+fn handles_encode_id_and_generation_big_endian() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let mut fs_map = FsMap::new(tempdir.path().to_path_buf());
+
+    assert!(fs_map.root_handle() == file::Handle([0, 0, 0, 1, 0, 0, 0, 0]));
+
+    let child = tempdir.path().join("file.txt");
+    fs::write(&child, b"hello").unwrap();
+    let handle = fs_map.ensure_handle_for_path(&child).unwrap();
+    assert!(handle == file::Handle([0, 0, 0, 2, 0, 0, 0, 0]));
+    assert_eq!(fs_map.path_for_handle(&file::Handle([0, 0, 0, 2, 0, 0, 0, 0])).unwrap(), child);
+}
+
+#[test]
+fn recreated_file_reuses_id_with_new_generation() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let mut fs_map = FsMap::new(tempdir.path().to_path_buf());
+
+    let path = tempdir.path().join("file.txt");
+    fs::write(&path, b"hello").unwrap();
+    let old_handle = fs_map.ensure_handle_for_path(&path).unwrap();
+
+    fs::remove_file(&path).unwrap();
+    fs_map.remove_path(&path);
+    fs::write(&path, b"again").unwrap();
+    let new_handle = fs_map.ensure_handle_for_path(&path).unwrap();
+
+    assert_eq!(old_handle.0[..4], new_handle.0[..4]);
+    assert!(old_handle != new_handle);
+    assert_eq!(fs_map.path_for_handle(&old_handle).unwrap_err(), vfs::Error::StaleFile);
+    assert_eq!(fs_map.path_for_handle(&new_handle).unwrap(), path);
+}
+
+#[test]
+fn journaled_handles_survive_restart() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let journal = state.path().join("handles");
+    let root = tempdir.path().to_path_buf();
+    fs::create_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("dir/a.txt"), b"a").unwrap();
+    fs::write(root.join("b.txt"), b"b").unwrap();
+    fs::write(root.join("gone.txt"), b"gone").unwrap();
+
+    let mut fs_map = FsMap::new(root.clone());
+    fs_map.attach_journal(&journal).unwrap();
+    let dir = fs_map.ensure_handle_for_path(&root.join("dir")).unwrap();
+    let a = fs_map.ensure_handle_for_path(&root.join("dir/a.txt")).unwrap();
+    let b = fs_map.ensure_handle_for_path(&root.join("b.txt")).unwrap();
+    let gone = fs_map.ensure_handle_for_path(&root.join("gone.txt")).unwrap();
+    fs::rename(root.join("dir"), root.join("moved")).unwrap();
+    fs_map.rename_path(&root.join("dir"), &root.join("moved")).unwrap();
+    fs::remove_file(root.join("gone.txt")).unwrap();
+    fs_map.remove_path(&root.join("gone.txt"));
+    drop(fs_map);
+
+    let mut restarted = FsMap::new(root.clone());
+    restarted.attach_journal(&journal).unwrap();
+    assert_eq!(restarted.path_for_handle(&dir).unwrap(), root.join("moved"));
+    assert_eq!(restarted.path_for_handle(&a).unwrap(), root.join("moved/a.txt"));
+    assert_eq!(restarted.path_for_handle(&b).unwrap(), root.join("b.txt"));
+    assert!(restarted.ensure_handle_for_path(&root.join("moved/a.txt")).unwrap() == a);
+    let error = expect_err(restarted.path_for_handle(&gone), "removed file handle");
+    assert_eq!(error, vfs::Error::StaleFile);
+
+    // Handles issued after a restart are journaled as well.
+    fs::write(root.join("c.txt"), b"c").unwrap();
+    let c = restarted.ensure_handle_for_path(&root.join("c.txt")).unwrap();
+    drop(restarted);
+    let mut again = FsMap::new(root.clone());
+    again.attach_journal(&journal).unwrap();
+    assert!(again.ensure_handle_for_path(&root.join("c.txt")).unwrap() == c);
+    assert!(again.ensure_handle_for_path(&root.join("b.txt")).unwrap() == b);
+}
+
+#[test]
+fn journaled_handle_of_file_replaced_while_down_is_stale() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let journal = state.path().join("handles");
+    let root = tempdir.path().to_path_buf();
+    fs::write(root.join("file.txt"), b"old").unwrap();
+
+    let mut fs_map = FsMap::new(root.clone());
+    fs_map.attach_journal(&journal).unwrap();
+    let old = fs_map.ensure_handle_for_path(&root.join("file.txt")).unwrap();
+    drop(fs_map);
+
+    // The new file exists before the old one goes away, so it gets another inode.
+    fs::write(root.join("file.new"), b"new").unwrap();
+    fs::rename(root.join("file.new"), root.join("file.txt")).unwrap();
+
+    let mut restarted = FsMap::new(root.clone());
+    restarted.attach_journal(&journal).unwrap();
+    let error = expect_err(restarted.path_for_handle(&old), "replaced file handle");
+    assert_eq!(error, vfs::Error::StaleFile);
+    let new = restarted.ensure_handle_for_path(&root.join("file.txt")).unwrap();
+    assert!(new != old);
+}
+
+#[test]
+fn journal_with_torn_last_record_still_loads() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let journal = state.path().join("handles");
+    let root = tempdir.path().to_path_buf();
+    fs::write(root.join("file.txt"), b"data").unwrap();
+
+    let mut fs_map = FsMap::new(root.clone());
+    fs_map.attach_journal(&journal).unwrap();
+    let handle = fs_map.ensure_handle_for_path(&root.join("file.txt")).unwrap();
+    drop(fs_map);
+    // A crash in the middle of an append leaves an unterminated line behind.
+    let mut file = fs::OpenOptions::new().append(true).open(&journal).unwrap();
+    file.write_all(b"A 9 1").unwrap();
+    drop(file);
+
+    let mut restarted = FsMap::new(root.clone());
+    restarted.attach_journal(&journal).unwrap();
+    assert_eq!(restarted.path_for_handle(&handle).unwrap(), root.join("file.txt"));
+
+    // Damage anywhere else is not a torn append and is reported.
+    drop(restarted);
+    let mut file = fs::OpenOptions::new().append(true).open(&journal).unwrap();
+    file.write_all(b"bogus\nN 9\n").unwrap();
+    drop(file);
+    let error = FsMap::new(root).attach_journal(&journal).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn journal_is_compacted_as_it_grows() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let state = tempfile::tempdir().unwrap();
+    let journal = state.path().join("handles");
+    let root = tempdir.path().to_path_buf();
+    fs::write(root.join("kept.txt"), b"kept").unwrap();
+    fs::write(root.join("churn.txt"), b"churn").unwrap();
+
+    let mut fs_map = FsMap::new(root.clone());
+    fs_map.attach_journal(&journal).unwrap();
+    let kept = fs_map.ensure_handle_for_path(&root.join("kept.txt")).unwrap();
+    for _ in 0..5000 {
+        fs_map.ensure_handle_for_path(&root.join("churn.txt")).unwrap();
+        fs_map.remove_path(&root.join("churn.txt"));
+    }
+    let lines = fs::read_to_string(&journal).unwrap().lines().count();
+    assert!(lines < 5000, "journal holds {lines} records");
+    drop(fs_map);
+
+    let mut restarted = FsMap::new(root.clone());
+    restarted.attach_journal(&journal).unwrap();
+    assert_eq!(restarted.path_for_handle(&kept).unwrap(), root.join("kept.txt"));
+}
@@ -1,4 +1,5 @@
 use std::fs as stdfs;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::sync::{LazyLock, Mutex};
 
@@ -41,6 +42,18 @@ impl TestContext {
         Self { tempdir, fs }
     }
 
+    /// Applies the `configure` builder calls to the mirror, e.g.
+    /// `TestContext::new().with(|fs| fs.with_case_insensitive(true))`.
+    pub fn with(self, configure: impl FnOnce(MirrorFS) -> MirrorFS) -> Self {
+        Self { fs: configure(self.fs), ..self }
+    }
+
+    /// Simulates a server restart: replaces the mirror with a new instance over the same
+    /// root, which forgets all handles and reports a new write verifier.
+    pub fn restart(&mut self) {
+        self.fs = MirrorFS::new(self.tempdir.path().to_path_buf());
+    }
+
     pub fn root_path(&self) -> &Path {
         self.tempdir.path()
     }
@@ -58,6 +71,15 @@ impl TestContext {
     }
 }
 
+pub fn root_cred() -> vfs::Credentials {
+    vfs::Credentials::root()
+}
+
+/// Returns credentials of an unprivileged user owning none of the test files.
+pub fn user_cred() -> vfs::Credentials {
+    vfs::Credentials { uid: 4242, gid: 4343, gids: Vec::new() }
+}
+
 pub fn name(value: &str) -> file::Name {
     file::Name::new(value.to_owned()).unwrap()
 }
@@ -128,6 +150,13 @@ pub fn create_dir(root: &Path, relative: &str) -> PathBuf {
     path
 }
 
+/// Creates directory `relative` under `root` with permission bits `mode`.
+pub fn create_dir_with_mode(root: &Path, relative: &str, mode: u32) -> PathBuf {
+    let path = create_dir(root, relative);
+    stdfs::set_permissions(&path, stdfs::Permissions::from_mode(mode)).unwrap();
+    path
+}
+
 pub fn write_file(root: &Path, relative: &str, data: &[u8]) -> PathBuf {
     let path = root.join(relative);
     if let Some(parent) = path.parent() {
@@ -0,0 +1,155 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use nfs_mamont::vfs::{file, get_attr, lookup};
+
+use crate::attr_cache::AttrCache;
+use crate::clock::MockClock;
+use crate::fs::MirrorFS;
+use crate::housekeeper::{Housekeeper, MAX_CACHE_ENTRIES};
+
+use super::helpers::{expect_ok, name, write_file};
+
+const TTL: Duration = Duration::from_secs(60);
+const FILES: usize = 100;
+
+/// Returns a file system over `root` caching attributes and missing names for [`TTL`].
+fn caching_fs(root: &std::path::Path, clock: &Arc<MockClock>) -> MirrorFS {
+    MirrorFS::new(root.to_path_buf())
+        .with_clock(clock.clone())
+        .with_attr_cache_ttl(TTL)
+        .with_negative_lookup_ttl(TTL)
+}
+
+/// Looks up [`FILES`] existing and as many missing names, caching an entry for each.
+async fn fill_caches(fs: &MirrorFS, root: &std::path::Path) {
+    let root = fs.handle_for_path(root).await.unwrap();
+    for i in 0..FILES {
+        for file in [format!("file{i}"), format!("missing{i}")] {
+            let _ = lookup::Lookup::lookup(
+                fs,
+                lookup::Args { parent: root.clone(), name: name(&file) },
+            )
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn expire_cached_drops_at_most_budget_expired_entries() {
+    let tempdir = tempfile::tempdir().unwrap();
+    for i in 0..FILES {
+        write_file(tempdir.path(), &format!("file{i}"), b"data");
+    }
+    let clock = Arc::new(MockClock::new());
+    let fs = caching_fs(tempdir.path(), &clock);
+    fill_caches(&fs, tempdir.path()).await;
+    let cached = fs.cached_entries();
+    assert!(cached >= 2 * FILES);
+
+    assert_eq!(fs.expire_cached(16), 0, "fresh entries must stay");
+    assert_eq!(fs.cached_entries(), cached);
+
+    clock.advance(TTL);
+    assert_eq!(fs.expire_cached(16), 16);
+    assert_eq!(fs.cached_entries(), cached - 16);
+
+    while fs.expire_cached(16) > 0 {}
+    assert_eq!(fs.cached_entries(), 0);
+}
+
+#[tokio::test]
+async fn expire_cached_shares_the_budget_between_caches() {
+    let tempdir = tempfile::tempdir().unwrap();
+    for i in 0..FILES {
+        write_file(tempdir.path(), &format!("file{i}"), b"data");
+    }
+    let clock = Arc::new(MockClock::new());
+    let fs = caching_fs(tempdir.path(), &clock);
+    fill_caches(&fs, tempdir.path()).await;
+    let missing = fs.negative_entries();
+    clock.advance(TTL);
+
+    // The attribute cache has plenty expired, yet the negative cache gets its share,
+    // and the share of the empty ACCESS cache goes to the others.
+    assert_eq!(fs.expire_cached(16), 16);
+    assert!(fs.negative_entries() < missing);
+}
+
+/// Returns the attributes of a file in `dir`, to fill a cache with.
+async fn some_attr(dir: &std::path::Path) -> file::Attr {
+    let path = write_file(dir, "attr.txt", b"data");
+    let fs = MirrorFS::new(dir.to_path_buf());
+    let file = fs.handle_for_path(&path).await.unwrap();
+    expect_ok(get_attr::GetAttr::get_attr(&fs, get_attr::Args { file }).await, "get_attr").object
+}
+
+fn handle(id: usize) -> file::Handle {
+    let mut handle = file::Handle([0; nfs_mamont::consts::nfsv3::NFS3_FHSIZE]);
+    handle.0.copy_from_slice(&(id as u64).to_le_bytes());
+    handle
+}
+
+#[tokio::test]
+async fn superseded_entries_do_not_use_up_the_budget() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let attr = some_attr(tempdir.path()).await;
+    let clock = Arc::new(MockClock::new());
+    let cache = AttrCache::new(TTL).with_clock(clock.clone());
+    for _ in 0..100 {
+        cache.insert(&handle(0), &attr);
+    }
+    clock.advance(TTL);
+
+    assert_eq!(cache.expire(1), 1);
+    assert_eq!(cache.entry_count(), 0);
+}
+
+#[tokio::test]
+async fn caches_never_grow_past_the_entry_limit() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let attr = some_attr(tempdir.path()).await;
+    let cache = AttrCache::new(TTL);
+    for id in 0..MAX_CACHE_ENTRIES + 1 {
+        cache.insert(&handle(id), &attr);
+    }
+
+    assert!(cache.entry_count() <= MAX_CACHE_ENTRIES);
+    // The newest entries stay.
+    assert!(cache.get(&handle(MAX_CACHE_ENTRIES)).is_some());
+    assert!(cache.get(&handle(0)).is_none());
+}
+
+#[tokio::test]
+async fn housekeeper_eventually_evicts_expired_entries() {
+    let tempdir = tempfile::tempdir().unwrap();
+    for i in 0..FILES {
+        write_file(tempdir.path(), &format!("file{i}"), b"data");
+    }
+    let clock = Arc::new(MockClock::new());
+    let fs = Arc::new(caching_fs(tempdir.path(), &clock));
+    fill_caches(&fs, tempdir.path()).await;
+    let file = fs.handle_for_path(&tempdir.path().join("file0")).await.unwrap();
+    clock.advance(TTL);
+
+    let task = Housekeeper::new(Duration::from_millis(1), NonZeroUsize::new(8).unwrap()).spawn(&fs);
+    tokio::time::timeout(Duration::from_secs(10), async {
+        // Requests keep being served while the housekeeper works through the caches.
+        while fs.cached_entries() > 1 {
+            expect_ok(
+                get_attr::GetAttr::get_attr(&*fs, get_attr::Args { file: file.clone() }).await,
+                "get_attr should succeed",
+            );
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await
+    .expect("housekeeper should evict expired entries");
+
+    drop(fs);
+    tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("housekeeper should stop once the file system is dropped")
+        .unwrap();
+}
@@ -1,23 +1,36 @@
-use std::os::unix::fs::PermissionsExt;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::{FileExt, MetadataExt, PermissionsExt};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 use nfs_mamont::consts::nfsv3::NFS3_COOKIEVERFSIZE;
 use nfs_mamont::vfs;
 use nfs_mamont::vfs::access;
+use nfs_mamont::vfs::acl;
 use nfs_mamont::vfs::commit;
 use nfs_mamont::vfs::file;
 use nfs_mamont::vfs::fs_info;
 use nfs_mamont::vfs::fs_stat;
+use nfs_mamont::vfs::get_acl;
 use nfs_mamont::vfs::get_attr;
 use nfs_mamont::vfs::path_conf;
 use nfs_mamont::vfs::read;
 use nfs_mamont::vfs::read_dir;
 use nfs_mamont::vfs::read_dir_plus;
 use nfs_mamont::vfs::read_link;
+use nfs_mamont::vfs::set_acl;
+use nfs_mamont::vfs::set_attr;
+use nfs_mamont::vfs::write;
+use nfs_mamont::{Allocator as _, Impl};
+
+use crate::clock::MockClock;
+use crate::fs::CookieVerifierPolicy;
 
 use super::helpers::{
-    alloc_slice, create_dir, create_symlink, expect_err, expect_ok, slice_to_vec, write_file,
-    TestContext,
+    alloc_slice, create_dir, create_dir_with_mode, create_symlink, default_new_attr, expect_err,
+    expect_ok, root_cred, slice_from_bytes, slice_to_vec, user_cred, write_file, TestContext,
 };
 
 #[tokio::test]
@@ -30,6 +43,7 @@ async fn access_returns_requested_mask() {
     let result = expect_ok(
         access::Access::access(
             &ctx.fs,
+            &root_cred(),
             access::Args {
                 file: handle,
                 mask: access::Mask::from_wire(access::Mask::READ | access::Mask::MODIFY),
@@ -54,6 +68,7 @@ async fn access_respects_file_permissions() {
     let result = expect_ok(
         access::Access::access(
             &ctx.fs,
+            &user_cred(),
             access::Args {
                 file: handle,
                 mask: access::Mask::from_wire(
@@ -70,6 +85,143 @@ async fn access_respects_file_permissions() {
     assert!(!result.access.contains(access::Mask::EXECUTE));
 }
 
+#[tokio::test]
+async fn access_grants_root_everything_but_execute_without_an_execute_bit() {
+    let ctx = TestContext::new();
+    let readonly = write_file(ctx.root_path(), "readonly.txt", b"data");
+    std::fs::set_permissions(&readonly, std::fs::Permissions::from_mode(0o444)).unwrap();
+    let script = write_file(ctx.root_path(), "script.sh", b"true");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o010)).unwrap();
+    create_dir_with_mode(ctx.root_path(), "locked", 0o000);
+    let root = ctx.root_handle().await;
+    let all = access::Mask::READ
+        | access::Mask::LOOKUP
+        | access::Mask::MODIFY
+        | access::Mask::EXTEND
+        | access::Mask::DELETE
+        | access::Mask::EXECUTE;
+    let granted = |name: &'static str| {
+        let root = root.clone();
+        let ctx = &ctx;
+        async move {
+            let file = ctx.lookup_handle(root, name).await;
+            let args = access::Args { file, mask: access::Mask::from_wire(all) };
+            expect_ok(access::Access::access(&ctx.fs, &root_cred(), args).await, "access")
+                .access
+                .bits()
+        }
+    };
+
+    let writes = access::Mask::MODIFY | access::Mask::EXTEND | access::Mask::DELETE;
+    assert_eq!(granted("readonly.txt").await, access::Mask::READ | writes);
+    assert_eq!(granted("script.sh").await, access::Mask::READ | writes | access::Mask::EXECUTE);
+    assert_eq!(granted("locked").await, access::Mask::READ | access::Mask::LOOKUP | writes);
+}
+
+#[tokio::test]
+async fn commit_flushes_only_requested_unstable_ranges() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "file.txt", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    for (offset, bytes) in [(0, b"aaaa"), (4, b"bbbb"), (100, b"cccc"), (200, b"dddd")] {
+        let args = write::Args {
+            file: handle.clone(),
+            offset,
+            size: 4,
+            stable: write::StableHow::Unstable,
+            data: slice_from_bytes(bytes).await,
+        };
+        let success = expect_ok(
+            write::Write::write(&ctx.fs, &root_cred(), args).await,
+            "unstable write should succeed",
+        );
+        assert_eq!(success.committed, write::StableHow::Unstable);
+    }
+    assert_eq!(ctx.fs.uncommitted_ranges(&handle), vec![0..8, 100..104, 200..204]);
+
+    let success = expect_ok(
+        commit::Commit::commit(
+            &ctx.fs,
+            commit::Args { file: handle.clone(), offset: 2, count: 100 },
+        )
+        .await,
+        "commit of a sub-range should succeed",
+    );
+    assert_eq!(success.verifier.0.len(), 8);
+    assert_eq!(ctx.fs.uncommitted_ranges(&handle), vec![0..2, 102..104, 200..204]);
+
+    expect_ok(
+        commit::Commit::commit(&ctx.fs, commit::Args { file: handle.clone(), offset: 0, count: 0 })
+            .await,
+        "commit to end of file should succeed",
+    );
+    assert!(ctx.fs.uncommitted_ranges(&handle).is_empty());
+    let contents = std::fs::read(&path).unwrap();
+    assert_eq!(&contents[..8], b"aaaabbbb");
+    assert_eq!(&contents[200..], b"dddd");
+}
+
+#[tokio::test]
+async fn commit_syncs_even_without_recorded_ranges() {
+    let ctx = TestContext::new();
+    // Written before the server started, so no dirty ranges are known for it.
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    expect_ok(
+        commit::Commit::commit(&ctx.fs, commit::Args { file: handle, offset: 0, count: 0 }).await,
+        "commit should succeed",
+    );
+    assert_eq!(ctx.fs.sync_count(), 1);
+}
+
+#[tokio::test]
+async fn whole_file_commit_ignores_offset_and_ranged_commit_past_eof_fails() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+
+    for offset in [0, 100] {
+        let args = write::Args {
+            file: handle.clone(),
+            offset,
+            size: 4,
+            stable: write::StableHow::Unstable,
+            data: slice_from_bytes(b"data").await,
+        };
+        expect_ok(
+            write::Write::write(&ctx.fs, &root_cred(), args).await,
+            "unstable write should succeed",
+        );
+    }
+
+    let fail = expect_err(
+        commit::Commit::commit(
+            &ctx.fs,
+            commit::Args { file: handle.clone(), offset: 1000, count: 10 },
+        )
+        .await,
+        "ranged commit past eof should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::InvalidArgument);
+    assert!(fail.file_wcc.after.is_some());
+    assert_eq!(ctx.fs.uncommitted_ranges(&handle), vec![0..4, 100..104]);
+
+    expect_ok(
+        commit::Commit::commit(
+            &ctx.fs,
+            commit::Args { file: handle.clone(), offset: 1000, count: 0 },
+        )
+        .await,
+        "whole file commit should ignore the offset",
+    );
+    assert!(ctx.fs.uncommitted_ranges(&handle).is_empty());
+}
+
 #[tokio::test]
 async fn commit_flushes_regular_file_and_rejects_directory() {
     let ctx = TestContext::new();
@@ -96,6 +248,60 @@ async fn commit_flushes_regular_file_and_rejects_directory() {
     super::helpers::assert_wcc_present(&fail.file_wcc);
 }
 
+#[tokio::test]
+async fn fs_info_advertises_transfer_size_the_allocator_serves() {
+    const MAX_TRANSFER: u32 = 1024 * 1024;
+    let ctx =
+        TestContext::new().with(|fs| fs.with_max_transfer(NonZeroU32::new(MAX_TRANSFER).unwrap()));
+    let root = ctx.root_handle().await;
+
+    let result = expect_ok(
+        fs_info::FsInfo::fs_info(&ctx.fs, fs_info::Args { root }).await,
+        "fs_info should succeed",
+    );
+    assert_eq!((result.read_max, result.write_max), (MAX_TRANSFER, MAX_TRANSFER));
+    assert_eq!((result.read_pref, result.write_pref), (MAX_TRANSFER, MAX_TRANSFER));
+
+    let write_max = NonZeroUsize::new(result.write_max as usize).unwrap();
+    let allocator = Impl::for_transfers(write_max, NonZeroUsize::new(4).unwrap());
+    assert!(allocator.allocate(write_max).await.is_some());
+    assert!(allocator.allocate(write_max.checked_add(1).unwrap()).await.is_none());
+}
+
+#[tokio::test]
+async fn fs_info_advertises_transfer_multiple_and_unaligned_write_succeeds() {
+    const MULTIPLE: u32 = 4096;
+    let ctx =
+        TestContext::new().with(|fs| fs.with_transfer_multiple(NonZeroU32::new(MULTIPLE).unwrap()));
+    let path = write_file(ctx.root_path(), "file.txt", &[b'a'; 8192]);
+    let root = ctx.root_handle().await;
+
+    let result = expect_ok(
+        fs_info::FsInfo::fs_info(&ctx.fs, fs_info::Args { root: root.clone() }).await,
+        "fs_info should succeed",
+    );
+    assert_eq!((result.read_mult, result.write_mult), (MULTIPLE, MULTIPLE));
+
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let data = vec![b'b'; 5000];
+    let args = write::Args {
+        file: handle,
+        offset: 4093,
+        size: data.len() as u32,
+        stable: write::StableHow::FileSync,
+        data: slice_from_bytes(&data).await,
+    };
+    let success = expect_ok(
+        write::Write::write(&ctx.fs, &root_cred(), args).await,
+        "unaligned write should succeed",
+    );
+    assert_eq!(success.count, 5000);
+
+    let mut expected = vec![b'a'; 4093];
+    expected.extend_from_slice(&data);
+    assert_eq!(std::fs::read(path).unwrap(), expected);
+}
+
 #[tokio::test]
 async fn fs_info_returns_server_limits() {
     let ctx = TestContext::new();
@@ -116,6 +322,19 @@ async fn fs_info_returns_server_limits() {
     assert!(properties & fs_info::Properties::CANSETTIME != 0);
 }
 
+#[tokio::test]
+async fn fs_info_reports_configured_time_delta() {
+    let ctx =
+        TestContext::new().with(|fs| fs.with_time_delta(file::Time { seconds: 0, nanos: 1000 }));
+    let root = ctx.root_handle().await;
+
+    let result = expect_ok(
+        fs_info::FsInfo::fs_info(&ctx.fs, fs_info::Args { root }).await,
+        "fs_info should succeed",
+    );
+    assert_eq!((result.time_delta.seconds, result.time_delta.nanos), (0, 1000));
+}
+
 #[tokio::test]
 async fn fs_stat_returns_zero_counters() {
     let ctx = TestContext::new();
@@ -149,6 +368,224 @@ async fn get_attr_returns_metadata() {
     assert_eq!(result.object.size, 5);
 }
 
+#[tokio::test]
+async fn get_attr_reports_disk_usage_of_sparse_file() {
+    const HOLE: u64 = 64 * 1024 * 1024;
+
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "sparse.bin", b"");
+    let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.write_all_at(b"x", HOLE).unwrap();
+
+    let attr = attr_of(&ctx, "sparse.bin").await;
+    assert_eq!(attr.size, HOLE + 1);
+    assert!(attr.used < attr.size, "used {} of a sparse file of {} bytes", attr.used, attr.size);
+    assert_eq!(attr.used, std::fs::metadata(&path).unwrap().blocks() * 512);
+}
+
+async fn size_of(ctx: &TestContext, file: &file::Handle) -> u64 {
+    let result = expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: file.clone() }).await,
+        "get_attr should succeed",
+    );
+    result.object.size
+}
+
+#[tokio::test]
+async fn get_attr_within_ttl_hits_attr_cache() {
+    let ctx = TestContext::new().with(|fs| fs.with_attr_cache_ttl(Duration::from_secs(60)));
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let calls = ctx.fs.metadata_calls();
+
+    for _ in 0..5 {
+        assert_eq!(size_of(&ctx, &handle).await, 5);
+    }
+    // LOOKUP already cached the file's attributes.
+    assert_eq!(ctx.fs.metadata_calls(), calls);
+
+    expect_ok(
+        write::Write::write(
+            &ctx.fs,
+            &root_cred(),
+            write::Args {
+                file: handle.clone(),
+                offset: 5,
+                size: 3,
+                stable: write::StableHow::Unstable,
+                data: slice_from_bytes(b"!!!").await,
+            },
+        )
+        .await,
+        "write should succeed",
+    );
+    assert_eq!(size_of(&ctx, &handle).await, 8);
+    assert_eq!(ctx.fs.metadata_calls(), calls + 1);
+}
+
+#[tokio::test]
+async fn get_attr_after_read_dir_plus_hits_attr_cache() {
+    let ctx = TestContext::new().with(|fs| fs.with_attr_cache_ttl(Duration::from_secs(60)));
+    write_file(ctx.root_path(), "a.txt", b"a");
+    write_file(ctx.root_path(), "b.txt", b"bb");
+    create_dir(ctx.root_path(), "sub");
+    let root = ctx.root_handle().await;
+
+    let listing = expect_ok(
+        read_dir_plus::ReadDirPlus::read_dir_plus(
+            &ctx.fs,
+            read_dir_plus::Args {
+                dir: root,
+                cookie: read_dir::Cookie::new(0),
+                cookie_verifier: read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]),
+                dir_count: 4096,
+                max_count: 4096,
+            },
+        )
+        .await,
+        "read_dir_plus should succeed",
+    );
+    assert_eq!(listing.entries.len(), 3);
+    let calls = ctx.fs.metadata_calls();
+
+    for entry in listing.entries {
+        let file = entry.file_handle.unwrap();
+        let result = expect_ok(
+            get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file }).await,
+            "get_attr should succeed",
+        );
+        assert_eq!(result.object.file_id, entry.file_attr.unwrap().file_id);
+    }
+    assert_eq!(ctx.fs.metadata_calls(), calls);
+}
+
+#[tokio::test]
+async fn attr_cache_entry_expires_once_clock_passes_ttl() {
+    let clock = Arc::new(MockClock::new());
+    let ctx = TestContext::new()
+        .with(|fs| fs.with_clock(clock.clone()).with_attr_cache_ttl(Duration::from_secs(60)));
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let calls = ctx.fs.metadata_calls();
+
+    clock.advance(Duration::from_secs(59));
+    assert_eq!(size_of(&ctx, &handle).await, 5);
+    assert_eq!(ctx.fs.metadata_calls(), calls);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(size_of(&ctx, &handle).await, 5);
+    assert_eq!(ctx.fs.metadata_calls(), calls + 1);
+}
+
+#[tokio::test]
+async fn files_under_export_report_configured_fsid() {
+    const FSID: u64 = 0x5EED;
+    let ctx = TestContext::new();
+    let export = create_dir(ctx.root_path(), "export");
+    let ctx = ctx.with(|fs| fs.with_export_fsid(export, FSID));
+    write_file(ctx.root_path(), "export/one.txt", b"1");
+    create_dir(ctx.root_path(), "export/sub");
+    write_file(ctx.root_path(), "export/sub/two.txt", b"2");
+    write_file(ctx.root_path(), "outside.txt", b"3");
+    let root = ctx.root_handle().await;
+    let export = ctx.lookup_handle(root.clone(), "export").await;
+    let sub = ctx.lookup_handle(export.clone(), "sub").await;
+
+    for (parent, name) in [(export, "one.txt"), (sub, "two.txt")] {
+        let file = ctx.lookup_handle(parent, name).await;
+        let attr = expect_ok(
+            get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file }).await,
+            "get_attr should succeed",
+        )
+        .object;
+        assert_eq!(attr.fs_id, FSID, "{name}");
+    }
+
+    let outside = ctx.lookup_handle(root, "outside.txt").await;
+    let attr = expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: outside }).await,
+        "get_attr should succeed",
+    )
+    .object;
+    assert_eq!(attr.fs_id, std::fs::metadata(ctx.root_path()).unwrap().dev());
+}
+
+#[tokio::test]
+async fn get_attr_without_ttl_always_reads_metadata() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let calls = ctx.fs.metadata_calls();
+
+    assert_eq!(size_of(&ctx, &handle).await, 5);
+    write_file(ctx.root_path(), "file.txt", b"hello world");
+    assert_eq!(size_of(&ctx, &handle).await, 11);
+    assert_eq!(ctx.fs.metadata_calls(), calls + 2);
+}
+
+async fn access_of(ctx: &TestContext, cred: &vfs::Credentials, file: &file::Handle) -> u32 {
+    let result = expect_ok(
+        access::Access::access(
+            &ctx.fs,
+            cred,
+            access::Args {
+                file: file.clone(),
+                mask: access::Mask::from_wire(access::Mask::READ | access::Mask::MODIFY),
+            },
+        )
+        .await,
+        "access should succeed",
+    );
+    result.access.bits()
+}
+
+#[tokio::test]
+async fn access_within_ttl_hits_access_cache() {
+    let ctx = TestContext::new().with(|fs| fs.with_access_cache_ttl(Duration::from_secs(60)));
+    let path = write_file(ctx.root_path(), "file.txt", b"hello");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    let user = vfs::Credentials { uid: 4242, gid: 4343, gids: Vec::new() };
+    let calls = ctx.fs.metadata_calls();
+
+    for _ in 0..5 {
+        assert_eq!(access_of(&ctx, &user, &handle).await, 0);
+    }
+    assert_eq!(ctx.fs.metadata_calls(), calls + 1);
+
+    // Handing the file over to the user must not leave the old verdict behind.
+    expect_ok(
+        set_attr::SetAttr::set_attr(
+            &ctx.fs,
+            &root_cred(),
+            set_attr::Args {
+                file: handle.clone(),
+                new_attr: set_attr::NewAttr { uid: Some(4242), ..default_new_attr() },
+                guard: None,
+            },
+        )
+        .await,
+        "set_attr should succeed",
+    );
+    for _ in 0..5 {
+        assert_eq!(
+            access_of(&ctx, &user, &handle).await,
+            access::Mask::READ | access::Mask::MODIFY
+        );
+    }
+    assert_eq!(ctx.fs.metadata_calls(), calls + 2);
+}
+
+fn pathconf(path: &Path, name: libc::c_int) -> libc::c_long {
+    let c_path = std::ffi::CString::new(path.as_os_str().as_encoded_bytes()).unwrap();
+    // SAFETY: `c_path` is a valid NUL-terminated string.
+    unsafe { libc::pathconf(c_path.as_ptr(), name) }
+}
+
 #[tokio::test]
 async fn path_conf_reports_limits() {
     let ctx = TestContext::new();
@@ -161,15 +598,30 @@ async fn path_conf_reports_limits() {
         "path_conf should succeed",
     );
 
+    let file = ctx.root_path().join("file.txt");
     assert!(result.file_attr.is_some());
-    assert_eq!(result.link_max, u32::MAX);
-    assert_eq!(result.name_max, vfs::MAX_NAME_LEN as u32);
+    assert_eq!(result.link_max as libc::c_long, pathconf(&file, libc::_PC_LINK_MAX));
+    assert_eq!(result.name_max as libc::c_long, pathconf(&file, libc::_PC_NAME_MAX));
     assert!(result.no_trunc);
     assert!(result.chown_restricted);
     assert!(!result.case_insensitive);
     assert!(result.case_preserving);
 }
 
+#[tokio::test]
+async fn path_conf_reports_case_insensitive_mode() {
+    let ctx = TestContext::new().with(|fs| fs.with_case_insensitive(true));
+    let root = ctx.root_handle().await;
+
+    let result = expect_ok(
+        path_conf::PathConf::path_conf(&ctx.fs, path_conf::Args { file: root }).await,
+        "path_conf should succeed",
+    );
+
+    assert!(result.case_insensitive);
+    assert!(result.case_preserving);
+}
+
 #[tokio::test]
 async fn read_reads_requested_window_and_rejects_directories() {
     let ctx = TestContext::new();
@@ -182,6 +634,7 @@ async fn read_reads_requested_window_and_rejects_directories() {
     let success = expect_ok(
         read::Read::read(
             &ctx.fs,
+            &root_cred(),
             read::Args { file: file_handle, offset: 2, count: 3 },
             alloc_slice(3).await,
         )
@@ -195,6 +648,7 @@ async fn read_reads_requested_window_and_rejects_directories() {
     let eof = expect_ok(
         read::Read::read(
             &ctx.fs,
+            &root_cred(),
             read::Args {
                 file: ctx.lookup_handle(ctx.root_handle().await, "file.txt").await,
                 offset: 99,
@@ -211,6 +665,7 @@ async fn read_reads_requested_window_and_rejects_directories() {
     let fail = expect_err(
         read::Read::read(
             &ctx.fs,
+            &root_cred(),
             read::Args { file: dir_handle, offset: 0, count: 1 },
             alloc_slice(1).await,
         )
@@ -220,6 +675,30 @@ async fn read_reads_requested_window_and_rejects_directories() {
     assert_eq!(fail.error, vfs::Error::InvalidArgument);
 }
 
+#[tokio::test]
+async fn read_at_or_past_end_of_file_returns_no_data_and_eof() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"abcdef");
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root, "file.txt").await;
+
+    for offset in [6, 7, u64::MAX] {
+        let success = expect_ok(
+            read::Read::read(
+                &ctx.fs,
+                &root_cred(),
+                read::Args { file: file.clone(), offset, count: 4 },
+                alloc_slice(4).await,
+            )
+            .await,
+            "read at or past eof should succeed",
+        );
+        assert_eq!(success.head.count, 0, "offset {offset}");
+        assert!(success.head.eof, "offset {offset}");
+        assert_eq!(success.head.file_attr.expect("post-op attributes").size, 6);
+    }
+}
+
 #[tokio::test]
 async fn read_dir_returns_sorted_entries_and_rejects_bad_cookie() {
     let ctx = TestContext::new();
@@ -261,6 +740,149 @@ async fn read_dir_returns_sorted_entries_and_rejects_bad_cookie() {
     assert_eq!(fail.error, vfs::Error::BadCookie);
 }
 
+async fn read_dir_page(
+    ctx: &TestContext,
+    dir: file::Handle,
+    cookie: read_dir::Cookie,
+    cookie_verifier: read_dir::CookieVerifier,
+) -> Result<read_dir::Success, read_dir::Fail> {
+    // Room for a single entry per page.
+    read_dir::ReadDir::read_dir(&ctx.fs, read_dir::Args { dir, cookie, cookie_verifier, count: 1 })
+        .await
+}
+
+#[tokio::test]
+async fn read_dir_cookies_are_never_zero_and_resume_after_their_entry() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "a.txt", b"a");
+    write_file(ctx.root_path(), "b.txt", b"b");
+    let root = ctx.root_handle().await;
+    let start = read_dir::Cookie::new(0);
+    let no_verifier = read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]);
+
+    let first = expect_ok(
+        read_dir_page(&ctx, root.clone(), start, no_verifier).await,
+        "first page should succeed",
+    );
+    let cookie = first.entries[0].cookie;
+    assert!(cookie.raw() > 0);
+    let next = expect_ok(
+        read_dir_page(&ctx, root.clone(), cookie, first.cookie_verifier).await,
+        "continuation should succeed",
+    );
+    assert_eq!(next.entries[0].file_name.as_str(), "b.txt");
+    assert!(next.entries[0].cookie.raw() > cookie.raw());
+
+    let plus = expect_ok(
+        read_dir_plus::ReadDirPlus::read_dir_plus(
+            &ctx.fs,
+            read_dir_plus::Args {
+                dir: root,
+                cookie: start,
+                cookie_verifier: no_verifier,
+                dir_count: 4096,
+                max_count: 4096,
+            },
+        )
+        .await,
+        "read_dir_plus should succeed",
+    );
+    assert!(plus.entries.iter().all(|entry| entry.cookie.raw() > 0));
+    assert_eq!(plus.entries[0].cookie, cookie);
+}
+
+#[tokio::test]
+async fn read_dir_rejects_old_verifier_after_directory_changes() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "a.txt", b"a");
+    write_file(ctx.root_path(), "b.txt", b"b");
+    let root = ctx.root_handle().await;
+
+    let first = expect_ok(
+        read_dir_page(
+            &ctx,
+            root.clone(),
+            read_dir::Cookie::new(0),
+            read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]),
+        )
+        .await,
+        "first page should succeed",
+    );
+    assert!(!first.eof);
+    let cookie = first.entries.last().unwrap().cookie;
+
+    write_file(ctx.root_path(), "0.txt", b"0");
+    let fail = expect_err(
+        read_dir_page(&ctx, root, cookie, first.cookie_verifier).await,
+        "continuation of a modified directory should fail",
+    );
+    assert_eq!(fail.error, vfs::Error::BadCookie);
+}
+
+#[tokio::test]
+async fn paginated_read_dir_lists_entries_with_ids_reverse_to_names_once() {
+    let ctx = TestContext::new();
+    let names = ["e.txt", "d.txt", "c.txt", "b.txt", "a.txt"];
+    let root = ctx.root_handle().await;
+    // Handle ids and inode numbers both grow in creation order, opposite to the listing.
+    for name in names {
+        write_file(ctx.root_path(), name, b"");
+        ctx.lookup_handle(root.clone(), name).await;
+    }
+
+    let mut listed = Vec::new();
+    let mut cookie = read_dir::Cookie::new(0);
+    let mut cookie_verifier = read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]);
+    loop {
+        let page = expect_ok(
+            read_dir_page(&ctx, root.clone(), cookie, cookie_verifier).await,
+            "page should succeed",
+        );
+        assert!(page.entries.len() <= 1);
+        if let Some(entry) = page.entries.last() {
+            cookie = entry.cookie;
+        }
+        listed.extend(page.entries.into_iter().map(|entry| entry.file_name.as_str().to_owned()));
+        cookie_verifier = page.cookie_verifier;
+        if page.eof {
+            break;
+        }
+    }
+
+    let mut expected = names.map(str::to_owned).to_vec();
+    expected.sort();
+    assert_eq!(listed, expected);
+}
+
+#[tokio::test]
+async fn disabled_cookie_verifiers_are_not_checked() {
+    let ctx =
+        TestContext::new().with(|fs| fs.with_cookie_verifiers(CookieVerifierPolicy::Disabled));
+    write_file(ctx.root_path(), "a.txt", b"a");
+    write_file(ctx.root_path(), "b.txt", b"b");
+    let root = ctx.root_handle().await;
+
+    let first = expect_ok(
+        read_dir_page(
+            &ctx,
+            root.clone(),
+            read_dir::Cookie::new(0),
+            read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]),
+        )
+        .await,
+        "first page should succeed",
+    );
+    assert_eq!(first.cookie_verifier, read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]));
+    let cookie = first.entries.last().unwrap().cookie;
+
+    write_file(ctx.root_path(), "c.txt", b"c");
+    let next = expect_ok(
+        read_dir_page(&ctx, root, cookie, first.cookie_verifier).await,
+        "continuation should succeed without verifier checks",
+    );
+    assert_eq!(next.entries[0].file_name.as_str(), "b.txt");
+}
+
 #[tokio::test]
 async fn read_dir_plus_returns_handles_and_supports_pagination() {
     let ctx = TestContext::new();
@@ -276,8 +898,8 @@ async fn read_dir_plus_returns_handles_and_supports_pagination() {
                 dir: root.clone(),
                 cookie: read_dir::Cookie::new(0),
                 cookie_verifier: read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]),
-                dir_count: 0,
-                max_count: 130,
+                dir_count: 4096,
+                max_count: 380,
             },
         )
         .await,
@@ -297,7 +919,7 @@ async fn read_dir_plus_returns_handles_and_supports_pagination() {
                 dir: root,
                 cookie: first.entries.last().unwrap().cookie,
                 cookie_verifier: first.cookie_verifier,
-                dir_count: 0,
+                dir_count: 4096,
                 max_count: 4096,
             },
         )
@@ -309,6 +931,67 @@ async fn read_dir_plus_returns_handles_and_supports_pagination() {
     assert_eq!(second.entries[0].file_name.as_str(), "c.txt");
 }
 
+async fn read_dir_plus_page(
+    ctx: &TestContext,
+    dir_count: u32,
+    max_count: u32,
+) -> Result<read_dir_plus::Success, read_dir_plus::Fail> {
+    read_dir_plus::ReadDirPlus::read_dir_plus(
+        &ctx.fs,
+        read_dir_plus::Args {
+            dir: ctx.root_handle().await,
+            cookie: read_dir::Cookie::new(0),
+            cookie_verifier: read_dir::CookieVerifier::new([0; NFS3_COOKIEVERFSIZE]),
+            dir_count,
+            max_count,
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn read_dir_plus_stops_at_max_count_before_dir_count() {
+    let ctx = TestContext::new();
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        write_file(ctx.root_path(), name, b"data");
+    }
+
+    // Directory information of each entry is 32 bytes, so `dir_count` admits all four,
+    // while attributes and handles bring each entry to 136 bytes on top of 108 bytes
+    // of reply overhead.
+    let page = expect_ok(read_dir_plus_page(&ctx, 4096, 400).await, "page should succeed");
+    assert_eq!(page.entries.len(), 2);
+    assert!(!page.eof);
+
+    let page = expect_ok(read_dir_plus_page(&ctx, 64, 4096).await, "page should succeed");
+    assert_eq!(page.entries.len(), 2);
+    assert!(!page.eof);
+}
+
+#[tokio::test]
+async fn read_dir_plus_stops_at_max_handles_within_byte_budgets() {
+    let ctx = TestContext::new()
+        .with(|fs| fs.with_read_dir_plus_max_handles(NonZeroU32::new(2).unwrap()));
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        write_file(ctx.root_path(), name, b"data");
+    }
+
+    let page = expect_ok(read_dir_plus_page(&ctx, 4096, 4096).await, "page should succeed");
+    assert_eq!(page.entries.len(), 2);
+    assert!(page.entries.iter().all(|entry| entry.file_handle.is_some()));
+    assert!(!page.eof);
+}
+
+#[tokio::test]
+async fn read_dir_plus_rejects_max_count_below_one_entry() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "a.txt", b"data");
+
+    let fail = expect_err(read_dir_plus_page(&ctx, 4096, 200).await, "page should not fit");
+    assert_eq!(fail.error, vfs::Error::TooSmall);
+    assert!(fail.dir_attr.is_some());
+}
+
 #[tokio::test]
 async fn read_link_returns_target_and_rejects_regular_files() {
     let ctx = TestContext::new();
@@ -323,7 +1006,7 @@ async fn read_link_returns_target_and_rejects_regular_files() {
         "read_link should succeed",
     );
     assert!(matches!(success.symlink_attr.unwrap().file_type, file::Type::Symlink));
-    assert_eq!(success.data.as_path(), Path::new("target.txt"));
+    assert_eq!(success.data, b"target.txt");
 
     let fail = expect_err(
         read_link::ReadLink::read_link(&ctx.fs, read_link::Args { file: file_handle }).await,
@@ -331,3 +1014,181 @@ async fn read_link_returns_target_and_rejects_regular_files() {
     );
     assert_eq!(fail.error, vfs::Error::InvalidArgument);
 }
+
+#[tokio::test]
+async fn read_link_returns_whole_target_of_maximal_length() {
+    let ctx = TestContext::new();
+    let target = "t/".repeat(vfs::MAX_PATH_LEN / 2);
+    create_symlink(ctx.root_path(), &target, "link");
+    let root = ctx.root_handle().await;
+    let link_handle = ctx.lookup_handle(root, "link").await;
+
+    let success = expect_ok(
+        read_link::ReadLink::read_link(&ctx.fs, read_link::Args { file: link_handle }).await,
+        "read_link should succeed",
+    );
+    assert_eq!(success.data.len(), vfs::MAX_PATH_LEN);
+    assert_eq!(success.data, target.as_bytes());
+}
+
+#[tokio::test]
+async fn get_acl_reports_mode_bits_without_extended_acl() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "plain.txt", b"data");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "plain.txt").await;
+
+    let result = expect_ok(
+        acl::Acl::get_acl(
+            &ctx.fs,
+            get_acl::Args { file: handle, mask: acl::Mask::from_wire(acl::Mask::ALL) },
+        )
+        .await,
+        "get_acl should succeed",
+    );
+
+    let attr = result.file_attr.unwrap();
+    let perms: Vec<_> =
+        result.access.iter().map(|entry| (entry.tag, entry.id, entry.perm)).collect();
+    assert_eq!(
+        perms,
+        [
+            (acl::Tag::UserObj, attr.uid, 6),
+            (acl::Tag::GroupObj, attr.gid, 4),
+            (acl::Tag::Other, 0, 0)
+        ]
+    );
+    assert!(result.default.is_empty());
+}
+
+#[tokio::test]
+async fn set_acl_round_trips_named_user_entry() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "shared.txt", b"data");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "shared.txt").await;
+    let entry = |tag, id, perm| acl::Entry { tag, id, perm };
+    let rw = acl::Entry::READ | acl::Entry::WRITE;
+    let access = vec![
+        entry(acl::Tag::UserObj, 0, rw),
+        entry(acl::Tag::User, 1000, rw),
+        entry(acl::Tag::GroupObj, 0, acl::Entry::READ),
+        entry(acl::Tag::Mask, 0, rw),
+        entry(acl::Tag::Other, 0, 0),
+    ];
+
+    let result = acl::Acl::set_acl(
+        &ctx.fs,
+        &root_cred(),
+        set_acl::Args {
+            file: handle.clone(),
+            mask: acl::Mask::from_wire(acl::Mask::ACL),
+            access: access.clone(),
+            default: Vec::new(),
+        },
+    )
+    .await;
+    if let Err(fail) = &result {
+        // The backing file system was mounted without ACL support.
+        assert!(matches!(fail.error, vfs::Error::NotSupported));
+        return;
+    }
+
+    let result = expect_ok(
+        acl::Acl::get_acl(
+            &ctx.fs,
+            get_acl::Args { file: handle, mask: acl::Mask::from_wire(acl::Mask::ACL) },
+        )
+        .await,
+        "get_acl should succeed",
+    );
+    let attr = result.file_attr.unwrap();
+    let with_owner = |mut entry: acl::Entry| {
+        match entry.tag {
+            acl::Tag::UserObj => entry.id = attr.uid,
+            acl::Tag::GroupObj => entry.id = attr.gid,
+            _ => {}
+        }
+        entry
+    };
+    assert_eq!(result.access, access.into_iter().map(with_owner).collect::<Vec<_>>());
+    // The mask entry is reflected in the group bits of the mode.
+    assert_eq!(attr.mode & 0o777, 0o660);
+}
+
+#[tokio::test]
+async fn get_attrs_reports_per_handle_results() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    create_dir(ctx.root_path(), "dir");
+    write_file(ctx.root_path(), "gone.txt", b"");
+    let root = ctx.root_handle().await;
+    let file = ctx.lookup_handle(root.clone(), "file.txt").await;
+    let dir = ctx.lookup_handle(root.clone(), "dir").await;
+    let gone = ctx.lookup_handle(root.clone(), "gone.txt").await;
+    std::fs::remove_file(ctx.root_path().join("gone.txt")).unwrap();
+    let unknown = file::Handle([0xff; nfs_mamont::consts::nfsv3::NFS3_FHSIZE]);
+
+    let results = get_attr::GetAttr::get_attrs(&ctx.fs, &[file, gone, dir, unknown]).await;
+
+    assert_eq!(results.len(), 4);
+    let file = expect_ok(results[0].as_ref(), "get_attrs should succeed for the file");
+    assert!(matches!(file.object.file_type, file::Type::Regular));
+    assert_eq!(file.object.size, 5);
+    let error = &expect_err(results[1].as_ref(), "get_attrs should fail for a removed file").error;
+    assert!(matches!(error, vfs::Error::StaleFile), "unexpected error {error:?}");
+    let dir = expect_ok(results[2].as_ref(), "get_attrs should succeed for the directory");
+    assert!(matches!(dir.object.file_type, file::Type::Directory));
+    let error =
+        &expect_err(results[3].as_ref(), "get_attrs should fail for an unknown handle").error;
+    assert!(matches!(error, vfs::Error::StaleFile), "unexpected error {error:?}");
+}
+
+/// Creates a special file at `relative` under `root` with `mknod(2)`.
+fn make_node(
+    root: &Path,
+    relative: &str,
+    mode: libc::mode_t,
+    dev: libc::dev_t,
+) -> std::io::Result<()> {
+    let path = std::ffi::CString::new(root.join(relative).into_os_string().into_vec()).unwrap();
+    // SAFETY: `path` is a valid NUL-terminated string.
+    match unsafe { libc::mknod(path.as_ptr(), mode, dev) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+async fn attr_of(ctx: &TestContext, entry: &str) -> file::Attr {
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, entry).await;
+    expect_ok(
+        get_attr::GetAttr::get_attr(&ctx.fs, get_attr::Args { file: handle }).await,
+        "get_attr should succeed",
+    )
+    .object
+}
+
+#[tokio::test]
+async fn get_attr_reports_fifo_type() {
+    let ctx = TestContext::new();
+    make_node(ctx.root_path(), "pipe", libc::S_IFIFO | 0o644, 0).unwrap();
+
+    let attr = attr_of(&ctx, "pipe").await;
+    assert!(matches!(attr.file_type, file::Type::Fifo));
+    assert_eq!((attr.device.major, attr.device.minor), (0, 0));
+}
+
+#[tokio::test]
+async fn get_attr_reports_character_device_numbers() {
+    let ctx = TestContext::new();
+    // Creating device nodes takes CAP_MKNOD.
+    if make_node(ctx.root_path(), "null", libc::S_IFCHR | 0o666, libc::makedev(1, 3)).is_err() {
+        return;
+    }
+
+    let attr = attr_of(&ctx, "null").await;
+    assert!(matches!(attr.file_type, file::Type::CharacterDevice));
+    assert_eq!((attr.device.major, attr.device.minor), (1, 3));
+}
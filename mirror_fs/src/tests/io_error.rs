@@ -0,0 +1,103 @@
+use std::io::{self, ErrorKind};
+
+use nfs_mamont::vfs;
+
+use crate::io_error::{map_io_error, retry_interrupted, MAX_INTERRUPTED_ATTEMPTS};
+
+#[test]
+fn errno_maps_to_matching_nfs_error() {
+    let table = [
+        (libc::EPERM, vfs::Error::Permission),
+        (libc::ENOENT, vfs::Error::NoEntry),
+        (libc::EIO, vfs::Error::IO),
+        (libc::ENXIO, vfs::Error::NXIO),
+        (libc::EACCES, vfs::Error::Access),
+        (libc::EBUSY, vfs::Error::Access),
+        (libc::EEXIST, vfs::Error::Exist),
+        (libc::EXDEV, vfs::Error::XDev),
+        (libc::ENODEV, vfs::Error::NoDev),
+        (libc::ENOTDIR, vfs::Error::NotDir),
+        (libc::EISDIR, vfs::Error::IsDir),
+        (libc::EINVAL, vfs::Error::InvalidArgument),
+        (libc::EFBIG, vfs::Error::FileTooLarge),
+        (libc::ENOSPC, vfs::Error::NoSpace),
+        (libc::EROFS, vfs::Error::ReadOnlyFs),
+        (libc::EMLINK, vfs::Error::TooManyLinks),
+        (libc::ENAMETOOLONG, vfs::Error::NameTooLong),
+        (libc::ENOTEMPTY, vfs::Error::NotEmpty),
+        (libc::EDQUOT, vfs::Error::QuotaExceeded),
+        (libc::ESTALE, vfs::Error::StaleFile),
+        (libc::EREMOTE, vfs::Error::TooManyLevelsOfRemote),
+        (libc::ELOOP, vfs::Error::TooManyLevelsOfRemote),
+        (libc::EOPNOTSUPP, vfs::Error::NotSupported),
+        (libc::ENOSYS, vfs::Error::NotSupported),
+        (libc::EAGAIN, vfs::Error::Jukebox),
+        (libc::EINTR, vfs::Error::Jukebox),
+        (libc::ENOMEM, vfs::Error::ServerFault),
+        (libc::EPIPE, vfs::Error::ServerFault),
+    ];
+    for (errno, expected) in table {
+        assert_eq!(map_io_error(&io::Error::from_raw_os_error(errno)), expected, "errno {errno}");
+    }
+}
+
+#[test]
+fn kind_without_errno_maps_to_matching_nfs_error() {
+    let table = [
+        (ErrorKind::NotFound, vfs::Error::NoEntry),
+        (ErrorKind::PermissionDenied, vfs::Error::Access),
+        (ErrorKind::AlreadyExists, vfs::Error::Exist),
+        (ErrorKind::InvalidInput, vfs::Error::InvalidArgument),
+        (ErrorKind::InvalidData, vfs::Error::InvalidArgument),
+        (ErrorKind::WriteZero, vfs::Error::NoSpace),
+        (ErrorKind::Unsupported, vfs::Error::NotSupported),
+        (ErrorKind::Interrupted, vfs::Error::Jukebox),
+        (ErrorKind::UnexpectedEof, vfs::Error::IO),
+        (ErrorKind::Other, vfs::Error::ServerFault),
+    ];
+    for (kind, expected) in table {
+        assert_eq!(map_io_error(&io::Error::from(kind)), expected, "{kind:?}");
+    }
+}
+
+/// Returns a call failing with `errno` for its first `failures` attempts, counting them.
+fn flaky(
+    errno: i32,
+    failures: usize,
+    attempts: &mut usize,
+) -> impl FnMut() -> io::Result<u32> + '_ {
+    move || {
+        *attempts += 1;
+        if *attempts <= failures {
+            Err(io::Error::from_raw_os_error(errno))
+        } else {
+            Ok(42)
+        }
+    }
+}
+
+#[test]
+fn interrupted_call_is_retried_until_it_succeeds() {
+    let mut attempts = 0;
+    let result = retry_interrupted(flaky(libc::EINTR, MAX_INTERRUPTED_ATTEMPTS - 1, &mut attempts));
+    assert_eq!(result.unwrap(), 42);
+    assert_eq!(attempts, MAX_INTERRUPTED_ATTEMPTS);
+}
+
+#[test]
+fn persistent_interruption_is_reported_as_jukebox() {
+    let mut attempts = 0;
+    let error = retry_interrupted(flaky(libc::EINTR, usize::MAX, &mut attempts)).unwrap_err();
+    assert_eq!(attempts, MAX_INTERRUPTED_ATTEMPTS);
+    assert_eq!(map_io_error(&error), vfs::Error::Jukebox);
+}
+
+#[test]
+fn non_interruption_errors_are_not_retried() {
+    for errno in [libc::EAGAIN, libc::ENOSPC] {
+        let mut attempts = 0;
+        let error = retry_interrupted(flaky(errno, 1, &mut attempts)).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(errno));
+        assert_eq!(attempts, 1, "errno {errno}");
+    }
+}
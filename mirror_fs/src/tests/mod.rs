@@ -1,5 +1,14 @@
+mod allocate_ops;
+mod config;
 mod create_ops;
 mod directory_ops;
+mod dirty_ranges;
 mod fs_map;
 mod helpers;
+mod housekeeper;
 mod info_ops;
+mod io_error;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+mod uring;
+mod write_buffer;
+mod xattr_ops;
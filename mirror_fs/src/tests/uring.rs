@@ -0,0 +1,93 @@
+use nfs_mamont::vfs::commit;
+use nfs_mamont::vfs::file;
+use nfs_mamont::vfs::read;
+use nfs_mamont::vfs::write;
+
+use super::helpers::{
+    alloc_slice, expect_ok, root_cred, slice_from_bytes, slice_to_vec, write_file, TestContext,
+};
+
+async fn write_at(
+    ctx: &TestContext,
+    handle: &file::Handle,
+    offset: u64,
+    bytes: &[u8],
+    stable: write::StableHow,
+) -> (u32, write::StableHow) {
+    let result = expect_ok(
+        write::Write::write(
+            &ctx.fs,
+            &root_cred(),
+            write::Args {
+                file: handle.clone(),
+                offset,
+                size: bytes.len() as u32,
+                stable,
+                data: slice_from_bytes(bytes).await,
+            },
+        )
+        .await,
+        "write should succeed",
+    );
+    (result.count, result.committed)
+}
+
+async fn read_at(
+    ctx: &TestContext,
+    handle: &file::Handle,
+    offset: u64,
+    count: u32,
+) -> (Vec<u8>, bool) {
+    let result = expect_ok(
+        read::Read::read(
+            &ctx.fs,
+            &root_cred(),
+            read::Args { file: handle.clone(), offset, count },
+            alloc_slice(count as usize).await,
+        )
+        .await,
+        "read should succeed",
+    );
+    let mut data = slice_to_vec(&result.data);
+    data.truncate(result.head.count as usize);
+    (data, result.head.eof)
+}
+
+/// Runs the same writes, commit and reads, returning everything observable by a client.
+async fn exercise(ctx: &TestContext) -> Vec<String> {
+    write_file(ctx.root_path(), "file.bin", b"0123456789");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.bin").await;
+    let large = (0..=255u8).cycle().take(200_000).collect::<Vec<_>>();
+
+    let mut observed = Vec::new();
+    let (first, second, third) = tokio::join!(
+        write_at(ctx, &handle, 4, b"uring", write::StableHow::Unstable),
+        write_at(ctx, &handle, 20, b"sparse", write::StableHow::DataSync),
+        write_at(ctx, &handle, 4096, &large, write::StableHow::FileSync),
+    );
+    observed.push(format!("{first:?} {second:?} {third:?}"));
+    expect_ok(
+        commit::Commit::commit(&ctx.fs, commit::Args { file: handle.clone(), offset: 0, count: 0 })
+            .await,
+        "commit should succeed",
+    );
+    for (offset, count) in [(0, 32), (2, 5), (4000, 150_000), (204_000, 1_000), (300_000, 16)] {
+        observed.push(format!("{:?}", read_at(ctx, &handle, offset, count).await));
+    }
+    observed.push(format!("{:?}", std::fs::read(ctx.root_path().join("file.bin")).unwrap()));
+    observed
+}
+
+#[tokio::test]
+async fn uring_backend_matches_blocking_backend() {
+    let uring = TestContext::new().with(|fs| fs.with_io_uring(8));
+    if !uring.fs.uses_io_uring() {
+        eprintln!("io_uring is not supported by this kernel, skipping");
+        return;
+    }
+    let blocking = TestContext::new();
+
+    assert_eq!(exercise(&uring).await, exercise(&blocking).await);
+    assert_eq!(uring.fs.sync_count(), blocking.fs.sync_count());
+}
@@ -0,0 +1,147 @@
+use nfs_mamont::vfs::commit;
+use nfs_mamont::vfs::file;
+use nfs_mamont::vfs::read;
+use nfs_mamont::vfs::write;
+
+use crate::write_buffer::{Flush, WriteBuffer, WriteBufferLimits};
+
+use super::helpers::{
+    alloc_slice, expect_ok, root_cred, slice_from_bytes, slice_to_vec, write_file, TestContext,
+};
+
+const LIMITS: WriteBufferLimits = WriteBufferLimits { flush_bytes: 100, max_bytes: 150 };
+
+fn handle(id: u8) -> file::Handle {
+    let mut raw = [0u8; 8];
+    raw[0] = id;
+    file::Handle(raw)
+}
+
+fn overlaid(buffer: &WriteBuffer, file: &file::Handle, offset: u64, len: usize) -> Vec<u8> {
+    let mut buf = vec![b'.'; len];
+    buffer.overlay(file, offset, &mut buf);
+    buf
+}
+
+#[test]
+fn insert_merges_overlapping_and_out_of_order_writes() {
+    let buffer = WriteBuffer::new(LIMITS);
+    let file = handle(1);
+
+    assert_eq!(buffer.insert(&file, 10, b"bbbb"), Flush::None);
+    assert_eq!(buffer.insert(&file, 4, b"aaaaaa"), Flush::None);
+    assert_eq!(buffer.insert(&file, 12, b"cccc"), Flush::None);
+    assert_eq!(buffer.insert(&file, 30, b"dd"), Flush::None);
+    assert_eq!(buffer.buffered_bytes(), 14);
+    assert_eq!(overlaid(&buffer, &file, 0, 34), b"....aaaaaabbcccc..............dd..");
+    assert_eq!(overlaid(&buffer, &file, 8, 6), b"aabbcc");
+    assert_eq!(overlaid(&buffer, &handle(2), 0, 4), b"....");
+
+    assert_eq!(buffer.take(&file), vec![(4, b"aaaaaabbcccc".to_vec()), (30, b"dd".to_vec())]);
+    assert_eq!(buffer.buffered_bytes(), 0);
+    assert!(buffer.files().is_empty());
+}
+
+#[test]
+fn sequential_appends_grow_a_single_extent() {
+    let buffer =
+        WriteBuffer::new(WriteBufferLimits { flush_bytes: usize::MAX, max_bytes: usize::MAX });
+    let file = handle(1);
+
+    for index in 0..10_000u64 {
+        buffer.insert(&file, index * 4, &(index as u32).to_le_bytes());
+    }
+    assert_eq!(buffer.buffered_bytes(), 40_000);
+    let extents = buffer.take(&file);
+    assert_eq!(extents.len(), 1);
+    assert_eq!(extents[0].1[4 * 9_999..], 9_999u32.to_le_bytes());
+}
+
+#[test]
+fn restore_puts_extents_back_under_newer_data() {
+    let buffer = WriteBuffer::new(LIMITS);
+    let file = handle(1);
+    buffer.insert(&file, 0, b"aaaa");
+    buffer.insert(&file, 10, b"bb");
+
+    let taken = buffer.take(&file);
+    buffer.insert(&file, 2, b"cccc");
+    buffer.restore(&file, taken);
+
+    assert_eq!(overlaid(&buffer, &file, 0, 12), b"aacccc....bb");
+    assert_eq!(buffer.buffered_bytes(), 8);
+    assert_eq!(buffer.files(), vec![file]);
+}
+
+#[test]
+fn insert_reports_exceeded_limits() {
+    let buffer = WriteBuffer::new(LIMITS);
+
+    assert_eq!(buffer.insert(&handle(1), 0, &[1; 60]), Flush::None);
+    assert_eq!(buffer.insert(&handle(1), 60, &[1; 40]), Flush::File);
+    assert_eq!(buffer.insert(&handle(2), 0, &[2; 60]), Flush::All);
+}
+
+#[tokio::test]
+async fn buffered_writes_are_readable_before_and_durable_after_commit() {
+    const BLOCK: usize = 4096;
+    const BLOCKS: usize = 256;
+
+    let ctx = TestContext::new().with(|fs| {
+        fs.with_write_buffer(WriteBufferLimits {
+            flush_bytes: 2 * BLOCK * BLOCKS,
+            max_bytes: 4 * BLOCK * BLOCKS,
+        })
+    });
+    let path = write_file(ctx.root_path(), "file.bin", b"");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.bin").await;
+
+    let expected =
+        (0..BLOCK * BLOCKS).map(|index| (index / BLOCK + index) as u8).collect::<Vec<_>>();
+    for (index, block) in expected.chunks(BLOCK).enumerate() {
+        let result = expect_ok(
+            write::Write::write(
+                &ctx.fs,
+                &root_cred(),
+                write::Args {
+                    file: handle.clone(),
+                    offset: (index * BLOCK) as u64,
+                    size: BLOCK as u32,
+                    stable: write::StableHow::Unstable,
+                    data: slice_from_bytes(block).await,
+                },
+            )
+            .await,
+            "write should succeed",
+        );
+        assert_eq!(result.count, BLOCK as u32);
+        assert_eq!(result.committed, write::StableHow::Unstable);
+    }
+
+    // Nothing reached the file yet, but its size and reads account for the buffer.
+    let on_disk = std::fs::read(&path).unwrap();
+    assert_eq!(on_disk.len(), expected.len());
+    assert!(on_disk.iter().all(|&byte| byte == 0));
+    let result = expect_ok(
+        read::Read::read(
+            &ctx.fs,
+            &root_cred(),
+            read::Args { file: handle.clone(), offset: 0, count: expected.len() as u32 },
+            alloc_slice(expected.len()).await,
+        )
+        .await,
+        "read should succeed",
+    );
+    assert_eq!(result.head.count as usize, expected.len());
+    assert!(result.head.eof);
+    assert_eq!(slice_to_vec(&result.data), expected);
+
+    expect_ok(
+        commit::Commit::commit(&ctx.fs, commit::Args { file: handle.clone(), offset: 0, count: 0 })
+            .await,
+        "commit should succeed",
+    );
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+    assert!(ctx.fs.uncommitted_ranges(&handle).is_empty());
+}
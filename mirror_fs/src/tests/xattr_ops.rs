@@ -0,0 +1,113 @@
+use std::os::unix::fs::PermissionsExt;
+use std::time::Duration;
+
+use nfs_mamont::vfs::xattr::VfsXattr;
+use nfs_mamont::vfs::{self, access, file};
+
+use super::helpers::{expect_err, expect_ok, root_cred, write_file, TestContext};
+
+#[tokio::test]
+async fn set_list_and_remove_user_xattr() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "tagged.txt", b"data");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "tagged.txt").await;
+
+    expect_ok(
+        ctx.fs.set_xattr(&root_cred(), &handle, "user.comment", b"hello").await,
+        "set_xattr should succeed",
+    );
+
+    let names =
+        expect_ok(ctx.fs.list_xattr(&root_cred(), &handle).await, "list_xattr should succeed");
+    assert!(names.iter().any(|name| name == "user.comment"), "listed {names:?}");
+    let value =
+        expect_ok(ctx.fs.get_xattr(&root_cred(), &handle, "user.comment").await, "get_xattr");
+    assert_eq!(value.as_deref(), Some(&b"hello"[..]));
+
+    expect_ok(
+        ctx.fs.remove_xattr(&root_cred(), &handle, "user.comment").await,
+        "remove_xattr should succeed",
+    );
+    assert_eq!(
+        expect_ok(ctx.fs.get_xattr(&root_cred(), &handle, "user.comment").await, "get_xattr"),
+        None
+    );
+    let error = expect_err(
+        ctx.fs.remove_xattr(&root_cred(), &handle, "user.comment").await,
+        "removing a missing attribute should fail",
+    );
+    assert_eq!(error, vfs::Error::NoEntry);
+}
+
+#[tokio::test]
+async fn non_root_callers_are_limited_to_user_namespace() {
+    let ctx = TestContext::new();
+    write_file(ctx.root_path(), "owned.txt", b"data");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "owned.txt").await;
+    let cred = vfs::Credentials { uid: 1000, gid: 1000, gids: Vec::new() };
+
+    let error = expect_err(
+        ctx.fs.set_xattr(&cred, &handle, "trusted.secret", b"x").await,
+        "trusted attributes are reserved for root",
+    );
+    assert_eq!(error, vfs::Error::Permission);
+    let error = expect_err(
+        ctx.fs.set_xattr(&cred, &handle, "user.comment", b"x").await,
+        "the caller cannot write the file",
+    );
+    assert_eq!(error, vfs::Error::Access);
+}
+
+#[tokio::test]
+async fn non_root_callers_only_see_user_namespace() {
+    let ctx = TestContext::new();
+    let path = write_file(ctx.root_path(), "tagged.txt", b"data");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "tagged.txt").await;
+    for name in ["user.comment", "trusted.secret"] {
+        expect_ok(ctx.fs.set_xattr(&root_cred(), &handle, name, b"x").await, "set_xattr");
+    }
+    let cred = vfs::Credentials { uid: 1000, gid: 1000, gids: Vec::new() };
+
+    let names = expect_ok(ctx.fs.list_xattr(&cred, &handle).await, "list_xattr");
+    assert_eq!(names, vec!["user.comment".to_owned()]);
+    let value = expect_ok(ctx.fs.get_xattr(&cred, &handle, "user.comment").await, "get_xattr");
+    assert_eq!(value.as_deref(), Some(&b"x"[..]));
+    let error = expect_err(
+        ctx.fs.get_xattr(&cred, &handle, "trusted.secret").await,
+        "trusted attributes are reserved for root",
+    );
+    assert_eq!(error, vfs::Error::Permission);
+}
+
+#[tokio::test]
+async fn changing_an_xattr_drops_cached_access_results() {
+    let ctx = TestContext::new().with(|fs| fs.with_access_cache_ttl(Duration::from_secs(60)));
+    write_file(ctx.root_path(), "file.txt", b"hello");
+    let root = ctx.root_handle().await;
+    let handle = ctx.lookup_handle(root, "file.txt").await;
+    check_read_access(&ctx, &handle).await;
+    let calls = ctx.fs.metadata_calls();
+    check_read_access(&ctx, &handle).await;
+    assert_eq!(ctx.fs.metadata_calls(), calls);
+
+    expect_ok(ctx.fs.set_xattr(&root_cred(), &handle, "user.comment", b"x").await, "set_xattr");
+    let calls = ctx.fs.metadata_calls();
+    check_read_access(&ctx, &handle).await;
+    assert_eq!(ctx.fs.metadata_calls(), calls + 1);
+}
+
+async fn check_read_access(ctx: &TestContext, file: &file::Handle) {
+    expect_ok(
+        access::Access::access(
+            &ctx.fs,
+            &root_cred(),
+            access::Args { file: file.clone(), mask: access::Mask::from_wire(access::Mask::READ) },
+        )
+        .await,
+        "access should succeed",
+    );
+}
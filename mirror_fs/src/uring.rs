@@ -0,0 +1,300 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, ErrorKind, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use io_uring::{opcode, squeue, types, IoUring};
+use tokio::sync::oneshot;
+use tracing::error;
+
+/// `user_data` of the read keeping the wake-up eventfd armed.
+const WAKE: u64 = u64::MAX;
+
+/// Positioned reads, writes and syncs issued as io_uring submissions.
+///
+/// Operations are handed to a dedicated thread owning the ring. Every operation
+/// queued while the ring waits for completions is submitted in the next batch, so
+/// concurrent NFS requests share `io_uring_enter` calls instead of each
+/// occupying a blocking thread.
+pub struct UringBackend {
+    requests: Option<mpsc::Sender<Request>>,
+    wake: File,
+    entries: u32,
+}
+
+impl std::fmt::Debug for UringBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UringBackend").field("entries", &self.entries).finish()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Read,
+    Write,
+    Fsync { data_only: bool },
+}
+
+/// A single submission; `buf[done..]` is the part still to be transferred.
+struct Op {
+    kind: Kind,
+    file: Arc<File>,
+    buf: Vec<u8>,
+    done: usize,
+    offset: u64,
+}
+
+struct Request {
+    op: Op,
+    reply: oneshot::Sender<io::Result<(u32, Vec<u8>)>>,
+}
+
+impl Op {
+    fn entry(&mut self) -> squeue::Entry {
+        let fd = types::Fd(self.file.as_raw_fd());
+        let offset = self.offset + self.done as u64;
+        let rest = &mut self.buf[self.done..];
+        let len = u32::try_from(rest.len()).unwrap_or(u32::MAX);
+        match self.kind {
+            Kind::Read => opcode::Read::new(fd, rest.as_mut_ptr(), len).offset(offset).build(),
+            Kind::Write => opcode::Write::new(fd, rest.as_ptr(), len).offset(offset).build(),
+            Kind::Fsync { data_only: true } => {
+                opcode::Fsync::new(fd).flags(types::FsyncFlags::DATASYNC).build()
+            }
+            Kind::Fsync { data_only: false } => opcode::Fsync::new(fd).build(),
+        }
+    }
+}
+
+impl UringBackend {
+    /// Sets up a ring with `entries` submission slots and starts its worker thread.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the kernel does not support io_uring or forbids its use.
+    pub fn new(entries: u32) -> io::Result<Self> {
+        let ring = IoUring::new(entries)?;
+        // SAFETY: `eventfd` has no preconditions; the result is checked before use.
+        let wake = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if wake < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `wake` is a freshly created descriptor nothing else owns.
+        let wake = File::from(unsafe { OwnedFd::from_raw_fd(wake) });
+        let worker_wake = wake.try_clone()?;
+        let (requests, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("mirrorfs-uring".to_owned())
+            .spawn(move || Worker::new(ring, receiver, worker_wake).run())?;
+        Ok(Self { requests: Some(requests), wake, entries })
+    }
+
+    /// Reads up to `len` bytes at `offset`, stopping early only at end of file.
+    pub async fn read_at(&self, file: &Arc<File>, len: usize, offset: u64) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        let mut done = 0;
+        while done < len {
+            let op = Op { kind: Kind::Read, file: file.clone(), buf, done, offset };
+            let count;
+            (count, buf) = self.submit(op).await?;
+            if count == 0 {
+                break;
+            }
+            done += count as usize;
+        }
+        buf.truncate(done);
+        Ok(buf)
+    }
+
+    /// Writes all of `data` at `offset`, retrying short writes.
+    pub async fn write_all_at(
+        &self,
+        file: &Arc<File>,
+        mut data: Vec<u8>,
+        offset: u64,
+    ) -> io::Result<usize> {
+        offset
+            .checked_add(data.len() as u64)
+            .ok_or_else(|| io::Error::from(ErrorKind::InvalidInput))?;
+        let len = data.len();
+        let mut done = 0;
+        while done < len {
+            let op = Op { kind: Kind::Write, file: file.clone(), buf: data, done, offset };
+            let count;
+            (count, data) = self.submit(op).await?;
+            if count == 0 {
+                return Err(io::Error::from(ErrorKind::WriteZero));
+            }
+            done += count as usize;
+        }
+        Ok(len)
+    }
+
+    /// Flushes the file to stable storage; `data_only` skips metadata not needed
+    /// to read the data back, like `fdatasync`.
+    pub async fn sync(&self, file: &Arc<File>, data_only: bool) -> io::Result<()> {
+        let op = Op {
+            kind: Kind::Fsync { data_only },
+            file: file.clone(),
+            buf: Vec::new(),
+            done: 0,
+            offset: 0,
+        };
+        self.submit(op).await.map(|_| ())
+    }
+
+    async fn submit(&self, op: Op) -> io::Result<(u32, Vec<u8>)> {
+        let stopped = || io::Error::other("io_uring worker stopped");
+        let (reply, completion) = oneshot::channel();
+        let requests = self.requests.as_ref().ok_or_else(stopped)?;
+        requests.send(Request { op, reply }).map_err(|_| stopped())?;
+        self.notify();
+        completion.await.map_err(|_| stopped())?
+    }
+
+    fn notify(&self) {
+        if let Err(error) = (&self.wake).write_all(&1u64.to_ne_bytes()) {
+            error!(%error, "failed to wake io_uring worker");
+        }
+    }
+}
+
+impl Drop for UringBackend {
+    fn drop(&mut self) {
+        // Closing the channel before the wake-up lets the worker observe the
+        // disconnect, finish what is in flight and exit.
+        drop(self.requests.take());
+        self.notify();
+    }
+}
+
+/// State of the thread owning the ring.
+struct Worker {
+    ring: IoUring,
+    requests: mpsc::Receiver<Request>,
+    wake: File,
+    wake_buf: Box<[u8; 8]>,
+    wake_armed: bool,
+    closed: bool,
+    pending: VecDeque<Request>,
+    in_flight: HashMap<u64, Request>,
+    next_id: u64,
+}
+
+impl Worker {
+    fn new(ring: IoUring, requests: mpsc::Receiver<Request>, wake: File) -> Self {
+        Self {
+            ring,
+            requests,
+            wake,
+            wake_buf: Box::new([0; 8]),
+            wake_armed: false,
+            closed: false,
+            pending: VecDeque::new(),
+            in_flight: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn run(mut self) {
+        loop {
+            self.receive();
+            if self.closed
+                && !self.wake_armed
+                && self.pending.is_empty()
+                && self.in_flight.is_empty()
+            {
+                return;
+            }
+            self.push();
+            match self.ring.submit_and_wait(1) {
+                Ok(_) => {}
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::Interrupted | ErrorKind::WouldBlock)
+                        || error.raw_os_error() == Some(libc::EBUSY) => {}
+                Err(error) => {
+                    error!(%error, "io_uring submission failed");
+                    return self.abort(&error);
+                }
+            }
+            self.reap();
+        }
+    }
+
+    fn receive(&mut self) {
+        loop {
+            match self.requests.try_recv() {
+                Ok(request) => self.pending.push_back(request),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Queues the wake-up read and as many pending operations as the ring has room for.
+    fn push(&mut self) {
+        if !self.closed && !self.wake_armed {
+            let entry =
+                opcode::Read::new(types::Fd(self.wake.as_raw_fd()), self.wake_buf.as_mut_ptr(), 8)
+                    .build()
+                    .user_data(WAKE);
+            // SAFETY: `wake_buf` is heap allocated and owned by the worker, which
+            // does not exit while the read is armed.
+            self.wake_armed = unsafe { self.ring.submission().push(&entry) }.is_ok();
+        }
+        let capacity = self.ring.submission().capacity();
+        while self.in_flight.len() < capacity {
+            let Some(mut request) = self.pending.pop_front() else {
+                return;
+            };
+            let entry = request.op.entry().user_data(self.next_id);
+            // SAFETY: the buffer and file are kept alive in `in_flight` until the
+            // completion is reaped; moving the `Vec` does not move its heap buffer.
+            if unsafe { self.ring.submission().push(&entry) }.is_err() {
+                self.pending.push_front(request);
+                return;
+            }
+            self.in_flight.insert(self.next_id, request);
+            self.next_id = (self.next_id + 1) % WAKE;
+        }
+    }
+
+    fn reap(&mut self) {
+        let completions =
+            self.ring.completion().map(|cqe| (cqe.user_data(), cqe.result())).collect::<Vec<_>>();
+        for (id, result) in completions {
+            if id == WAKE {
+                self.wake_armed = false;
+                continue;
+            }
+            let Some(request) = self.in_flight.remove(&id) else {
+                continue;
+            };
+            let result = if result < 0 {
+                Err(io::Error::from_raw_os_error(-result))
+            } else {
+                Ok((result as u32, request.op.buf))
+            };
+            // The caller may have given up on the operation; nothing to do then.
+            let _ = request.reply.send(result);
+        }
+    }
+
+    /// Fails every operation after the ring became unusable.
+    fn abort(mut self, error: &io::Error) {
+        for request in
+            self.pending.drain(..).chain(self.in_flight.drain().map(|(_, request)| request))
+        {
+            // Buffers of submitted operations may still be written by the kernel.
+            std::mem::forget(request.op.buf);
+            let _ = request.reply.send(Err(io::Error::new(error.kind(), error.to_string())));
+        }
+        std::mem::forget(self.wake_buf);
+    }
+}
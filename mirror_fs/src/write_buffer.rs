@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use nfs_mamont::vfs::file;
+
+/// Limits of a [`WriteBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteBufferLimits {
+    /// Buffered bytes of a single file which trigger a flush of that file.
+    pub flush_bytes: usize,
+    /// Buffered bytes of all files which trigger a flush of every file.
+    pub max_bytes: usize,
+}
+
+/// Files whose buffered data has to be written out after an insert.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Flush {
+    None,
+    File,
+    All,
+}
+
+/// In-memory buffer of unstable writes not yet written to the mirrored files.
+///
+/// Extents of a file are kept sorted and coalesced: overlapping or adjacent writes
+/// collapse into a single extent, later data replacing earlier data.
+#[derive(Debug)]
+pub struct WriteBuffer {
+    limits: WriteBufferLimits,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    files: HashMap<file::Handle, Extents>,
+    total: usize,
+}
+
+/// Buffered extents of one file by offset, and their total length.
+#[derive(Debug, Default)]
+struct Extents {
+    extents: BTreeMap<u64, Vec<u8>>,
+    bytes: usize,
+}
+
+impl WriteBuffer {
+    pub fn new(limits: WriteBufferLimits) -> Self {
+        Self { limits, state: Mutex::new(State::default()) }
+    }
+
+    /// Buffers `data` at `offset` of `file` and reports what must be flushed to stay
+    /// within the limits.
+    pub fn insert(&self, file: &file::Handle, offset: u64, data: &[u8]) -> Flush {
+        if data.is_empty() {
+            return Flush::None;
+        }
+        let mut state = self.state.lock().unwrap();
+        let State { files, total } = &mut *state;
+        let extents = files.entry(file.clone()).or_default();
+        *total -= extents.bytes;
+        extents.insert(offset, data);
+        *total += extents.bytes;
+
+        if *total > self.limits.max_bytes {
+            Flush::All
+        } else if extents.bytes >= self.limits.flush_bytes {
+            Flush::File
+        } else {
+            Flush::None
+        }
+    }
+
+    /// Puts back `extents` of `file` taken by [`Self::take`] which could not be written
+    /// out; data buffered for `file` since they were taken stays on top of them.
+    pub fn restore(&self, file: &file::Handle, extents: Vec<(u64, Vec<u8>)>) {
+        let mut state = self.state.lock().unwrap();
+        let State { files, total } = &mut *state;
+        let buffered = files.entry(file.clone()).or_default();
+        let newer = std::mem::take(buffered);
+        *total -= newer.bytes;
+        for (offset, data) in extents.into_iter().chain(newer.extents) {
+            buffered.insert(offset, &data);
+        }
+        *total += buffered.bytes;
+    }
+
+    /// Removes and returns the buffered extents of `file` in ascending order.
+    pub fn take(&self, file: &file::Handle) -> Vec<(u64, Vec<u8>)> {
+        let mut state = self.state.lock().unwrap();
+        let Some(extents) = state.files.remove(file) else {
+            return Vec::new();
+        };
+        state.total -= extents.bytes;
+        extents.extents.into_iter().collect()
+    }
+
+    /// Returns the files with buffered data.
+    pub fn files(&self) -> Vec<file::Handle> {
+        self.state.lock().unwrap().files.keys().cloned().collect()
+    }
+
+    /// Copies buffered bytes of `file` overlapping `buf.len()` bytes at `offset`
+    /// over the corresponding bytes of `buf`.
+    pub fn overlay(&self, file: &file::Handle, offset: u64, buf: &mut [u8]) {
+        let end = offset.saturating_add(buf.len() as u64);
+        let state = self.state.lock().unwrap();
+        let Some(extents) = state.files.get(file) else {
+            return;
+        };
+        for (&extent_start, extent) in extents.extents.range(..end) {
+            let extent_end = extent_start + extent.len() as u64;
+            if extent_end <= offset {
+                continue;
+            }
+            let from = extent_start.max(offset);
+            let to = extent_end.min(end);
+            buf[(from - offset) as usize..(to - offset) as usize].copy_from_slice(
+                &extent[(from - extent_start) as usize..(to - extent_start) as usize],
+            );
+        }
+    }
+
+    /// Returns the total number of buffered bytes.
+    #[cfg(test)]
+    pub fn buffered_bytes(&self) -> usize {
+        self.state.lock().unwrap().total
+    }
+}
+
+impl Extents {
+    /// Merges `data` at `offset` with the extents it overlaps or touches.
+    ///
+    /// An extent starting at or before `offset` grows in place, so a run of sequential
+    /// writes appends to one vector instead of copying it over and over.
+    fn insert(&mut self, offset: u64, data: &[u8]) {
+        let mut end = offset.saturating_add(data.len() as u64);
+        // Extents are disjoint and sorted, so those touching the write are the last ones
+        // starting before its end.
+        let mut touching: Vec<(u64, Vec<u8>)> = Vec::new();
+        while let Some((&extent_start, extent)) = self.extents.range(..=end).next_back() {
+            let extent_end = extent_start + extent.len() as u64;
+            if extent_end < offset {
+                break;
+            }
+            end = end.max(extent_end);
+            touching.push((extent_start, self.extents.remove(&extent_start).unwrap()));
+        }
+        self.bytes -= touching.iter().map(|(_, extent)| extent.len()).sum::<usize>();
+
+        let (start, mut merged) = match touching.last() {
+            Some(&(extent_start, _)) if extent_start <= offset => touching.pop().unwrap(),
+            _ => (offset, Vec::new()),
+        };
+        merged.resize((end - start) as usize, 0);
+        for (extent_start, extent) in &touching {
+            let at = (extent_start - start) as usize;
+            merged[at..at + extent.len()].copy_from_slice(extent);
+        }
+        let at = (offset - start) as usize;
+        merged[at..at + data.len()].copy_from_slice(data);
+        self.bytes += merged.len();
+        self.extents.insert(start, merged);
+    }
+}
@@ -0,0 +1,157 @@
+//! Throughput of back-to-back 64 KiB WRITEs and READs through the whole server.
+//!
+//! Calls go over a loopback TCP connection through the parser, the VFS workers over
+//! [`MemFs`] and the serializer, so per-call allocations or lock contention anywhere
+//! on the path show up as lost throughput. The run fails below [`MIN_MB_PER_SEC`].
+//!
+//! ```text
+//! cargo bench -p nfs-mamont --bench throughput
+//! ```
+
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::{TcpListener, TcpStream};
+
+use nfs_mamont::consts::nfsv3::{NFS_PROGRAM, NFS_VERSION, READ, WRITE};
+use nfs_mamont::service::mount::MountService;
+use nfs_mamont::service::nlm::NlmService;
+use nfs_mamont::vfs::file;
+use nfs_mamont::{handle_until_shutdown, Impl, ServerContext, ShutdownHandle};
+
+#[path = "../examples/memfs/mod.rs"]
+#[allow(dead_code)]
+mod memfs;
+
+use memfs::MemFs;
+
+/// Bytes moved by every call.
+const CHUNK: usize = 64 * 1024;
+/// Calls of each kind; the file they cover is `CALLS * CHUNK` bytes long.
+const CALLS: usize = 1024;
+/// Lowest acceptable throughput of either direction.
+const MIN_MB_PER_SEC: f64 = 50.0;
+/// Read and write buffers of the server, enough to keep every worker busy.
+const BUFFER_COUNT: usize = 64;
+/// `stable_how` of the WRITEs: FILE_SYNC.
+const FILE_SYNC: u32 = 2;
+
+#[tokio::main]
+async fn main() {
+    let fs = Arc::new(MemFs::new());
+    let file = fs.insert_file(&fs.root_handle(), "bench", &[]).unwrap();
+
+    let buffers = || {
+        Arc::new(Impl::new(
+            NonZeroUsize::new(2 * CHUNK).unwrap(),
+            NonZeroUsize::new(BUFFER_COUNT).unwrap(),
+        ))
+    };
+    let context = ServerContext::new(fs, buffers(), buffers(), NonZeroUsize::new(4).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = ShutdownHandle::new();
+    let server = tokio::spawn(handle_until_shutdown(
+        listener,
+        context,
+        Arc::new(MountService::with_exports(Vec::new())),
+        Arc::new(NlmService::new()),
+        shutdown.clone(),
+    ));
+
+    let data = (0..CHUNK).map(|index| index as u8).collect::<Vec<_>>();
+    let writes = (0..CALLS).map(|index| write_call(index as u32, &file, index, &data)).collect();
+    let write_time = run(addr, writes, 0).await;
+    report("WRITE", write_time);
+
+    let reads = (0..CALLS).map(|index| read_call(index as u32, &file, index)).collect();
+    let read_time = run(addr, reads, CHUNK).await;
+    report("READ", read_time);
+
+    shutdown.shutdown();
+    server.await.unwrap().unwrap();
+}
+
+/// Sends `calls` back to back on a new connection while reading their replies, each
+/// carrying at least `payload` bytes past the status, and returns the time taken.
+async fn run(addr: std::net::SocketAddr, calls: Vec<Vec<u8>>, payload: usize) -> Duration {
+    let (mut readhalf, mut writehalf) = TcpStream::connect(addr).await.unwrap().into_split();
+    let count = calls.len();
+    let started = Instant::now();
+    let sender = tokio::spawn(async move {
+        for call in calls {
+            writehalf.write_all(&call).await.unwrap();
+        }
+        writehalf
+    });
+    // Workers run calls concurrently, so replies may come in any order.
+    let mut answered = vec![false; count];
+    for _ in 0..count {
+        let reply = read_reply(&mut readhalf).await;
+        let mut words =
+            reply[..28].chunks(4).map(|word| u32::from_be_bytes(word.try_into().unwrap()));
+        let xid = words.next().unwrap();
+        // REPLY, MSG_ACCEPTED, AUTH_NONE verifier, SUCCESS and NFS3_OK.
+        assert!(words.eq([1, 0, 0, 0, 0, 0]), "call {xid} failed");
+        assert!(reply.len() >= 28 + payload, "reply {xid} is short");
+        assert!(!std::mem::replace(&mut answered[xid as usize], true), "call {xid} answered twice");
+    }
+    let elapsed = started.elapsed();
+    drop(sender.await.unwrap());
+    elapsed
+}
+
+fn report(procedure: &str, elapsed: Duration) {
+    let mb_per_sec = (CALLS * CHUNK) as f64 / 1e6 / elapsed.as_secs_f64();
+    println!("{procedure}: {CALLS} x {} KiB in {elapsed:.2?}, {mb_per_sec:.1} MB/s", CHUNK / 1024);
+    assert!(
+        mb_per_sec >= MIN_MB_PER_SEC,
+        "{procedure} throughput {mb_per_sec:.1} MB/s is below {MIN_MB_PER_SEC} MB/s"
+    );
+}
+
+/// Serializes a call with AUTH_NONE credentials and verifier and arguments `args`.
+fn call(xid: u32, procedure: u32, args: &[u8]) -> Vec<u8> {
+    let words = [xid, 0, 2, NFS_PROGRAM, NFS_VERSION, procedure, 0, 0, 0, 0];
+    let len = words.len() * 4 + args.len();
+    let mut frame = (0x8000_0000 | len as u32).to_be_bytes().to_vec();
+    words.iter().for_each(|word| frame.extend_from_slice(&word.to_be_bytes()));
+    frame.extend_from_slice(args);
+    frame
+}
+
+/// Serializes `nfs_fh3` holding `handle`.
+fn handle_bytes(handle: &file::Handle) -> Vec<u8> {
+    let mut bytes = (handle.0.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(&handle.0);
+    bytes
+}
+
+fn write_call(xid: u32, file: &file::Handle, index: usize, data: &[u8]) -> Vec<u8> {
+    let mut args = handle_bytes(file);
+    args.extend_from_slice(&((index * CHUNK) as u64).to_be_bytes());
+    args.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    args.extend_from_slice(&FILE_SYNC.to_be_bytes());
+    args.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    args.extend_from_slice(data);
+    call(xid, WRITE, &args)
+}
+
+fn read_call(xid: u32, file: &file::Handle, index: usize) -> Vec<u8> {
+    let mut args = handle_bytes(file);
+    args.extend_from_slice(&((index * CHUNK) as u64).to_be_bytes());
+    args.extend_from_slice(&(CHUNK as u32).to_be_bytes());
+    call(xid, READ, &args)
+}
+
+/// Reads one record-marked reply, without the record mark.
+async fn read_reply(readhalf: &mut OwnedReadHalf) -> Vec<u8> {
+    let mut mark = [0u8; 4];
+    readhalf.read_exact(&mut mark).await.unwrap();
+    let mut reply = vec![0; (u32::from_be_bytes(mark) & !0x8000_0000) as usize];
+    readhalf.read_exact(&mut reply).await.unwrap();
+    reply
+}
@@ -0,0 +1,992 @@
+//! In-memory [`Vfs`](nfs_mamont::vfs::Vfs) backend shared by the examples.
+//!
+//! Every object lives in a map from file id to node; a handle is the big-endian file id.
+//! Permissions are not enforced, so any caller may do anything, and nothing survives a
+//! restart: the backend is meant for trying the server out, not for storing data.
+
+use std::collections::{BTreeMap, HashMap};
+use std::os::unix::ffi::OsStrExt;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nfs_mamont::consts::nfsv3::NFS3_FHSIZE;
+use nfs_mamont::vfs::read_dir::{Cookie, CookieVerifier};
+use nfs_mamont::vfs::set_attr::{NewAttr, SetTime};
+use nfs_mamont::vfs::{self, file};
+use nfs_mamont::vfs::{
+    access, commit, create, fs_info, fs_stat, get_attr, link, lookup, mk_dir, mk_node, path_conf,
+    read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_attr, symlink, write,
+};
+use nfs_mamont::Buffer;
+
+/// File id of the root directory.
+const ROOT_ID: u64 = 1;
+/// File system id reported for every object.
+const FS_ID: u64 = 1;
+/// Largest file size, which keeps a single client from exhausting server memory.
+const MAX_FILE_SIZE: u64 = 1 << 30;
+/// Largest READ and WRITE transfer advertised by FSINFO.
+const MAX_TRANSFER: u32 = 1 << 20;
+/// Estimated wire size of a READDIR entry without its name.
+const DIR_ENTRY_OVERHEAD: usize = 24;
+/// Estimated wire size of a READDIRPLUS entry without its name.
+const DIR_ENTRY_PLUS_OVERHEAD: usize = DIR_ENTRY_OVERHEAD + 100;
+
+/// File system kept entirely in memory.
+pub struct MemFs {
+    state: Mutex<State>,
+    /// Reported by WRITE and COMMIT; changes on every start, as unstable data never
+    /// survives one.
+    write_verifier: [u8; 8],
+}
+
+struct State {
+    nodes: HashMap<u64, Node>,
+    next_id: u64,
+}
+
+struct Node {
+    content: Content,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    nlink: u32,
+    atime: file::Time,
+    mtime: file::Time,
+    ctime: file::Time,
+}
+
+/// Entries listed by READDIR as `(file id, name, cookie)`, and whether the listing ended.
+type Listing = (Vec<(u64, String, Cookie)>, bool);
+
+enum Content {
+    File(Vec<u8>),
+    /// Entries by name, with the file id of the parent directory for `..`.
+    Dir {
+        entries: BTreeMap<String, u64>,
+        parent: u64,
+    },
+    Symlink(file::Path),
+    Special(file::Type, file::Device),
+}
+
+impl Default for MemFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemFs {
+    /// Creates a file system holding an empty root directory.
+    pub fn new() -> Self {
+        let now = now();
+        let root =
+            Node::new(Content::Dir { entries: BTreeMap::new(), parent: ROOT_ID }, 0o755, 0, 0);
+        let verifier = u64::from(now.seconds) << 32 | u64::from(now.nanos);
+        Self {
+            state: Mutex::new(State {
+                nodes: HashMap::from([(ROOT_ID, root)]),
+                next_id: ROOT_ID + 1,
+            }),
+            write_verifier: verifier.to_be_bytes(),
+        }
+    }
+
+    /// Returns the handle of the root directory.
+    pub fn root_handle(&self) -> file::Handle {
+        encode_handle(ROOT_ID)
+    }
+
+    /// Creates directory `name` in `dir`, owned by root.
+    pub fn insert_dir(&self, dir: &file::Handle, name: &str) -> Result<file::Handle, vfs::Error> {
+        let content = Content::Dir { entries: BTreeMap::new(), parent: ROOT_ID };
+        let mut state = self.state.lock().unwrap();
+        state
+            .insert_child(dir, name, Node::new(content, 0o755, 0, 0))
+            .map(|(id, _)| encode_handle(id))
+    }
+
+    /// Creates regular file `name` in `dir` holding `data`, owned by root.
+    pub fn insert_file(
+        &self,
+        dir: &file::Handle,
+        name: &str,
+        data: &[u8],
+    ) -> Result<file::Handle, vfs::Error> {
+        let node = Node::new(Content::File(data.to_vec()), 0o644, 0, 0);
+        let mut state = self.state.lock().unwrap();
+        state.insert_child(dir, name, node).map(|(id, _)| encode_handle(id))
+    }
+}
+
+impl Node {
+    fn new(content: Content, mode: u32, uid: u32, gid: u32) -> Self {
+        let now = now();
+        Self { content, mode, uid, gid, nlink: 1, atime: now, mtime: now, ctime: now }
+    }
+
+    fn file_type(&self) -> file::Type {
+        match &self.content {
+            Content::File(_) => file::Type::Regular,
+            Content::Dir { .. } => file::Type::Directory,
+            Content::Symlink(_) => file::Type::Symlink,
+            Content::Special(file_type, _) => *file_type,
+        }
+    }
+
+    fn size(&self) -> u64 {
+        match &self.content {
+            Content::File(data) => data.len() as u64,
+            Content::Dir { entries, .. } => entries.len() as u64,
+            Content::Symlink(path) => path.as_path().as_os_str().len() as u64,
+            Content::Special(..) => 0,
+        }
+    }
+
+    fn wcc_attr(&self) -> file::WccAttr {
+        file::WccAttr { size: self.size(), mtime: self.mtime, ctime: self.ctime }
+    }
+
+    /// Marks the data as modified.
+    fn touch(&mut self) {
+        let now = now();
+        self.mtime = now;
+        self.ctime = now;
+    }
+
+    /// Applies the settable attributes of `new_attr`.
+    fn apply(&mut self, new_attr: &NewAttr) -> Result<(), vfs::Error> {
+        if let Some(size) = new_attr.size {
+            let Content::File(data) = &mut self.content else {
+                return Err(if matches!(self.content, Content::Dir { .. }) {
+                    vfs::Error::IsDir
+                } else {
+                    vfs::Error::InvalidArgument
+                });
+            };
+            if size > MAX_FILE_SIZE {
+                return Err(vfs::Error::FileTooLarge);
+            }
+            data.resize(size as usize, 0);
+            self.mtime = now();
+        }
+        if let Some(mode) = new_attr.mode {
+            self.mode = mode & 0o7777;
+        }
+        if let Some(uid) = new_attr.uid {
+            self.uid = uid;
+        }
+        if let Some(gid) = new_attr.gid {
+            self.gid = gid;
+        }
+        match new_attr.atime {
+            SetTime::DontChange => {}
+            SetTime::ToServer => self.atime = now(),
+            SetTime::ToClient(time) => self.atime = time,
+        }
+        match new_attr.mtime {
+            SetTime::DontChange => {}
+            SetTime::ToServer => self.mtime = now(),
+            SetTime::ToClient(time) => self.mtime = time,
+        }
+        self.ctime = now();
+        Ok(())
+    }
+}
+
+impl State {
+    fn node(&self, handle: &file::Handle) -> Result<(u64, &Node), vfs::Error> {
+        let id = decode_handle(handle)?;
+        self.nodes.get(&id).map(|node| (id, node)).ok_or(vfs::Error::StaleFile)
+    }
+
+    fn node_mut(&mut self, handle: &file::Handle) -> Result<&mut Node, vfs::Error> {
+        let id = decode_handle(handle)?;
+        self.nodes.get_mut(&id).ok_or(vfs::Error::StaleFile)
+    }
+
+    fn attr(&self, id: u64) -> Option<file::Attr> {
+        let node = self.nodes.get(&id)?;
+        let (nlink, device) = match &node.content {
+            Content::Dir { entries, .. } => {
+                let subdirs = entries
+                    .values()
+                    .filter(|id| {
+                        matches!(
+                            self.nodes.get(id).map(|node| &node.content),
+                            Some(Content::Dir { .. })
+                        )
+                    })
+                    .count();
+                (2 + subdirs as u32, file::Device { major: 0, minor: 0 })
+            }
+            Content::Special(_, device) => (node.nlink, *device),
+            _ => (node.nlink, file::Device { major: 0, minor: 0 }),
+        };
+        let size = node.size();
+        Some(file::Attr {
+            file_type: node.file_type(),
+            mode: node.mode,
+            nlink,
+            uid: node.uid,
+            gid: node.gid,
+            size,
+            used: size,
+            device,
+            fs_id: FS_ID,
+            file_id: id,
+            atime: node.atime,
+            mtime: node.mtime,
+            ctime: node.ctime,
+        })
+    }
+
+    fn handle_attr(&self, handle: &file::Handle) -> Option<file::Attr> {
+        decode_handle(handle).ok().and_then(|id| self.attr(id))
+    }
+
+    fn wcc_before(&self, handle: &file::Handle) -> Option<file::WccAttr> {
+        self.node(handle).ok().map(|(_, node)| node.wcc_attr())
+    }
+
+    fn wcc(&self, handle: &file::Handle, before: Option<file::WccAttr>) -> vfs::WccData {
+        vfs::WccData { before, after: self.handle_attr(handle) }
+    }
+
+    fn entries(&self, dir: &file::Handle) -> Result<(u64, &BTreeMap<String, u64>), vfs::Error> {
+        match self.node(dir)? {
+            (id, Node { content: Content::Dir { entries, .. }, .. }) => Ok((id, entries)),
+            _ => Err(vfs::Error::NotDir),
+        }
+    }
+
+    fn entries_mut(
+        &mut self,
+        dir: &file::Handle,
+    ) -> Result<&mut BTreeMap<String, u64>, vfs::Error> {
+        match self.node_mut(dir)? {
+            Node { content: Content::Dir { entries, .. }, .. } => Ok(entries),
+            _ => Err(vfs::Error::NotDir),
+        }
+    }
+
+    /// Returns the file id `name` stands for in `dir`, including `.` and `..`.
+    fn lookup(&self, dir: &file::Handle, name: &str) -> Result<u64, vfs::Error> {
+        let (id, node) = self.node(dir)?;
+        let Content::Dir { entries, parent } = &node.content else {
+            return Err(vfs::Error::NotDir);
+        };
+        match name {
+            "." => Ok(id),
+            ".." => Ok(*parent),
+            name => entries.get(name).copied().ok_or(vfs::Error::NoEntry),
+        }
+    }
+
+    /// Adds `node` to `dir` as `name` and returns its file id and attributes.
+    fn insert_child(
+        &mut self,
+        dir: &file::Handle,
+        name: &str,
+        node: Node,
+    ) -> Result<(u64, file::Attr), vfs::Error> {
+        let (dir_id, entries) = self.entries(dir)?;
+        if name == "." || name == ".." || entries.contains_key(name) {
+            return Err(vfs::Error::Exist);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let node = match node.content {
+            Content::Dir { entries, .. } => {
+                Node { content: Content::Dir { entries, parent: dir_id }, ..node }
+            }
+            _ => node,
+        };
+        self.nodes.insert(id, node);
+        self.entries_mut(dir)?.insert(name.to_owned(), id);
+        self.node_mut(dir)?.touch();
+        Ok((id, self.attr(id).expect("node was just inserted")))
+    }
+
+    /// Creates `node` as `object`, owned by the caller, with `new_attr` applied.
+    fn create(
+        &mut self,
+        cred: &vfs::Credentials,
+        object: &vfs::DirOpArgs,
+        mut node: Node,
+        new_attr: &NewAttr,
+    ) -> Result<(file::Handle, file::Attr), vfs::Error> {
+        node.uid = cred.uid;
+        node.gid = cred.gid;
+        node.apply(new_attr)?;
+        let (id, attr) = self.insert_child(&object.dir, object.name.as_str(), node)?;
+        Ok((encode_handle(id), attr))
+    }
+
+    /// Drops one link to `id`, freeing the node with its last link.
+    fn unlink(&mut self, id: u64) {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+        node.nlink = node.nlink.saturating_sub(1);
+        node.ctime = now();
+        if node.nlink == 0 || matches!(node.content, Content::Dir { .. }) {
+            self.nodes.remove(&id);
+        }
+    }
+
+    /// Removes `object`, which must be a directory if `dir` is set and must not otherwise.
+    fn remove(&mut self, object: &vfs::DirOpArgs, dir: bool) -> Result<(), vfs::Error> {
+        let id = self.lookup(&object.dir, object.name.as_str())?;
+        match (&self.nodes[&id].content, dir) {
+            (Content::Dir { entries, .. }, true) if !entries.is_empty() => {
+                return Err(vfs::Error::NotEmpty)
+            }
+            (Content::Dir { .. }, true) => {}
+            (Content::Dir { .. }, false) => return Err(vfs::Error::IsDir),
+            (_, true) => return Err(vfs::Error::NotDir),
+            (_, false) => {}
+        }
+        if matches!(object.name.as_str(), "." | "..") {
+            return Err(vfs::Error::InvalidArgument);
+        }
+        self.entries_mut(&object.dir)?.remove(object.name.as_str());
+        self.node_mut(&object.dir)?.touch();
+        self.unlink(id);
+        Ok(())
+    }
+
+    /// Adds `link` as another name of the non-directory `file`.
+    fn link(&mut self, file: &file::Handle, link: &vfs::DirOpArgs) -> Result<(), vfs::Error> {
+        let (id, node) = self.node(file)?;
+        if matches!(node.content, Content::Dir { .. }) {
+            return Err(vfs::Error::IsDir);
+        }
+        let name = link.name.as_str();
+        if name == "." || name == ".." || self.entries(&link.dir)?.1.contains_key(name) {
+            return Err(vfs::Error::Exist);
+        }
+        self.entries_mut(&link.dir)?.insert(name.to_owned(), id);
+        self.node_mut(&link.dir)?.touch();
+        let node = self.node_mut(file)?;
+        node.nlink += 1;
+        node.ctime = now();
+        Ok(())
+    }
+
+    /// Returns `true` if directory `ancestor` is `id` or contains it at any depth.
+    fn is_ancestor(&self, ancestor: u64, mut id: u64) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match self.nodes.get(&id).map(|node| &node.content) {
+                Some(Content::Dir { parent, .. }) if *parent != id => id = *parent,
+                _ => return false,
+            }
+        }
+    }
+
+    fn rename(&mut self, from: &vfs::DirOpArgs, to: &vfs::DirOpArgs) -> Result<(), vfs::Error> {
+        let id = self.lookup(&from.dir, from.name.as_str())?;
+        let to_dir = self.entries(&to.dir)?.0;
+        if matches!(from.name.as_str(), "." | "..") || matches!(to.name.as_str(), "." | "..") {
+            return Err(vfs::Error::InvalidArgument);
+        }
+        let moves_dir = matches!(self.nodes[&id].content, Content::Dir { .. });
+        if moves_dir && self.is_ancestor(id, to_dir) {
+            return Err(vfs::Error::InvalidArgument);
+        }
+        match self.lookup(&to.dir, to.name.as_str()) {
+            Ok(target) if target == id => return Ok(()),
+            Ok(target) => {
+                match (&self.nodes[&target].content, moves_dir) {
+                    (Content::Dir { entries, .. }, true) if !entries.is_empty() => {
+                        return Err(vfs::Error::NotEmpty)
+                    }
+                    (Content::Dir { .. }, false) => return Err(vfs::Error::IsDir),
+                    (Content::Dir { .. }, true) => {}
+                    (_, true) => return Err(vfs::Error::NotDir),
+                    (_, false) => {}
+                }
+                self.unlink(target);
+            }
+            Err(vfs::Error::NoEntry) => {}
+            Err(error) => return Err(error),
+        }
+
+        self.entries_mut(&from.dir)?.remove(from.name.as_str());
+        self.entries_mut(&to.dir)?.insert(to.name.as_str().to_owned(), id);
+        if let Some(Node { content: Content::Dir { parent, .. }, .. }) = self.nodes.get_mut(&id) {
+            *parent = to_dir;
+        }
+        self.node_mut(&from.dir)?.touch();
+        self.node_mut(&to.dir)?.touch();
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.ctime = now();
+        }
+        Ok(())
+    }
+
+    /// Returns the entries of `dir` following `cookie`, as many as fit in `count` bytes
+    /// with `overhead` bytes per entry besides its name, and whether the listing ended.
+    ///
+    /// The cookie of an entry is its position in name order plus one.
+    fn list(
+        &self,
+        dir: &file::Handle,
+        cookie: Cookie,
+        count: u32,
+        overhead: usize,
+    ) -> Result<Listing, vfs::Error> {
+        let (_, entries) = self.entries(dir)?;
+        let skip = usize::try_from(cookie.raw()).map_err(|_| vfs::Error::BadCookie)?;
+        if skip > entries.len() {
+            return Err(vfs::Error::BadCookie);
+        }
+        let mut budget = count as usize;
+        let mut listed = Vec::new();
+        for (position, (name, &id)) in entries.iter().enumerate().skip(skip) {
+            let size = overhead + name.len().next_multiple_of(4);
+            if size > budget {
+                if listed.is_empty() {
+                    return Err(vfs::Error::TooSmall);
+                }
+                return Ok((listed, false));
+            }
+            budget -= size;
+            listed.push((id, name.clone(), Cookie::new(position as u64 + 1)));
+        }
+        Ok((listed, true))
+    }
+}
+
+fn now() -> file::Time {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    file::Time { seconds: elapsed.as_secs() as u32, nanos: elapsed.subsec_nanos() }
+}
+
+fn encode_handle(id: u64) -> file::Handle {
+    file::Handle(id.to_be_bytes())
+}
+
+fn decode_handle(handle: &file::Handle) -> Result<u64, vfs::Error> {
+    let bytes: [u8; NFS3_FHSIZE] = handle.0;
+    match u64::from_be_bytes(bytes) {
+        0 => Err(vfs::Error::BadFileHandle),
+        id => Ok(id),
+    }
+}
+
+fn name(name: String) -> file::Name {
+    file::Name::new(name).expect("names in the tree were validated on creation")
+}
+
+impl get_attr::GetAttr for MemFs {
+    async fn get_attr(&self, args: get_attr::Args) -> Result<get_attr::Success, get_attr::Fail> {
+        let state = self.state.lock().unwrap();
+        match state.node(&args.file) {
+            Ok((id, _)) => Ok(get_attr::Success { object: state.attr(id).unwrap() }),
+            Err(error) => Err(get_attr::Fail { error }),
+        }
+    }
+}
+
+impl set_attr::SetAttr for MemFs {
+    async fn set_attr(
+        &self,
+        _cred: &vfs::Credentials,
+        args: set_attr::Args,
+    ) -> Result<set_attr::Success, set_attr::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.wcc_before(&args.file);
+        let result = state.node_mut(&args.file).and_then(|node| {
+            match args.guard {
+                Some(guard)
+                    if (guard.ctime.seconds, guard.ctime.nanos)
+                        != (node.ctime.seconds, node.ctime.nanos) =>
+                {
+                    return Err(vfs::Error::NotSync)
+                }
+                _ => {}
+            }
+            node.apply(&args.new_attr)
+        });
+        let wcc_data = state.wcc(&args.file, before);
+        match result {
+            Ok(()) => Ok(set_attr::Success { wcc_data, ignored: set_attr::Ignored::default() }),
+            Err(error) => Err(set_attr::Fail { error, wcc_data }),
+        }
+    }
+}
+
+impl lookup::Lookup for MemFs {
+    async fn lookup(&self, args: lookup::Args) -> Result<lookup::Success, lookup::Fail> {
+        let state = self.state.lock().unwrap();
+        let dir_attr = state.handle_attr(&args.parent);
+        match state.lookup(&args.parent, args.name.as_str()) {
+            Ok(id) => {
+                Ok(lookup::Success { file: encode_handle(id), file_attr: state.attr(id), dir_attr })
+            }
+            Err(error) => Err(lookup::Fail { error, dir_attr }),
+        }
+    }
+}
+
+impl access::Access for MemFs {
+    async fn access(
+        &self,
+        _cred: &vfs::Credentials,
+        args: access::Args,
+    ) -> Result<access::Success, access::Fail> {
+        let state = self.state.lock().unwrap();
+        match state.node(&args.file) {
+            Ok((id, _)) => Ok(access::Success { object_attr: state.attr(id), access: args.mask }),
+            Err(error) => Err(access::Fail { error, object_attr: None }),
+        }
+    }
+}
+
+impl read_link::ReadLink for MemFs {
+    async fn read_link(
+        &self,
+        args: read_link::Args,
+    ) -> Result<read_link::Success, read_link::Fail> {
+        let state = self.state.lock().unwrap();
+        let symlink_attr = state.handle_attr(&args.file);
+        match state.node(&args.file) {
+            Ok((_, Node { content: Content::Symlink(path), .. })) => {
+                let data = path.as_path().as_os_str().as_bytes().to_vec();
+                Ok(read_link::Success { symlink_attr, data })
+            }
+            Ok(_) => Err(read_link::Fail { symlink_attr, error: vfs::Error::InvalidArgument }),
+            Err(error) => Err(read_link::Fail { symlink_attr, error }),
+        }
+    }
+}
+
+impl<B: Buffer> read::Read<B> for MemFs {
+    async fn read(
+        &self,
+        _cred: &vfs::Credentials,
+        args: read::Args,
+        mut data: B,
+    ) -> Result<read::Success<B>, read::Fail> {
+        let state = self.state.lock().unwrap();
+        let file_attr = state.handle_attr(&args.file);
+        let contents = match state.node(&args.file) {
+            Ok((_, Node { content: Content::File(contents), .. })) => contents,
+            Ok((_, node)) => {
+                let error = match node.content {
+                    Content::Dir { .. } => vfs::Error::IsDir,
+                    _ => vfs::Error::InvalidArgument,
+                };
+                return Err(read::Fail { error, file_attr });
+            }
+            Err(error) => return Err(read::Fail { error, file_attr }),
+        };
+
+        let len = contents.len() as u64;
+        let start = args.offset.min(len) as usize;
+        let end = args.offset.saturating_add(u64::from(args.count)).min(len) as usize;
+        let mut remaining = &contents[start..end];
+        for chunk in data.chunks_mut() {
+            let copied = chunk.len().min(remaining.len());
+            chunk[..copied].copy_from_slice(&remaining[..copied]);
+            remaining = &remaining[copied..];
+        }
+        let count = (end - start - remaining.len()) as u32;
+        let eof = start as u64 + u64::from(count) >= len;
+        Ok(read::Success { head: read::SuccessPartial { file_attr, count, eof }, data })
+    }
+}
+
+impl<B: Buffer> write::Write<B> for MemFs {
+    async fn write(
+        &self,
+        _cred: &vfs::Credentials,
+        args: write::Args<B>,
+    ) -> Result<write::Success, write::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.wcc_before(&args.file);
+        let result = state.node_mut(&args.file).and_then(|node| {
+            let Content::File(contents) = &mut node.content else {
+                return Err(match node.content {
+                    Content::Dir { .. } => vfs::Error::IsDir,
+                    _ => vfs::Error::InvalidArgument,
+                });
+            };
+            let size = (args.size as usize).min(args.data.len());
+            let end = args.offset.checked_add(size as u64).ok_or(vfs::Error::FileTooLarge)?;
+            if end > MAX_FILE_SIZE {
+                return Err(vfs::Error::FileTooLarge);
+            }
+            if contents.len() < end as usize {
+                contents.resize(end as usize, 0);
+            }
+            let mut position = args.offset as usize;
+            for chunk in args.data.chunks() {
+                let copied = chunk.len().min(end as usize - position);
+                contents[position..position + copied].copy_from_slice(&chunk[..copied]);
+                position += copied;
+            }
+            node.touch();
+            Ok(size as u32)
+        });
+        let file_wcc = state.wcc(&args.file, before);
+        match result {
+            Ok(count) => Ok(write::Success {
+                file_wcc,
+                count,
+                committed: write::StableHow::FileSync,
+                verifier: write::Verifier(self.write_verifier),
+            }),
+            Err(error) => Err(write::Fail { error, wcc_data: file_wcc }),
+        }
+    }
+}
+
+impl create::Create for MemFs {
+    async fn create(
+        &self,
+        cred: &vfs::Credentials,
+        args: create::Args,
+    ) -> Result<create::Success, create::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let node = Node::new(Content::File(Vec::new()), 0o644, 0, 0);
+        let before = state.wcc_before(&args.object.dir);
+        let result = match &args.how {
+            create::How::Unchecked(new_attr) => {
+                match state.lookup(&args.object.dir, args.object.name.as_str()) {
+                    // UNCHECKED creation of an existing file only applies the attributes.
+                    Ok(id) if matches!(state.nodes[&id].content, Content::File(_)) => {
+                        let handle = encode_handle(id);
+                        state
+                            .node_mut(&handle)
+                            .and_then(|node| node.apply(new_attr))
+                            .map(|()| (handle, state.attr(id).unwrap()))
+                    }
+                    _ => state.create(cred, &args.object, node, new_attr),
+                }
+            }
+            create::How::Guarded(new_attr) => state.create(cred, &args.object, node, new_attr),
+            create::How::Exclusive(_) => {
+                let unchanged = NewAttr {
+                    mode: None,
+                    uid: None,
+                    gid: None,
+                    size: None,
+                    atime: SetTime::DontChange,
+                    mtime: SetTime::DontChange,
+                };
+                state.create(cred, &args.object, node, &unchanged)
+            }
+        };
+        let wcc_data = state.wcc(&args.object.dir, before);
+        match result {
+            Ok((file, attr)) => {
+                Ok(create::Success { file: Some(file), attr: Some(attr), wcc_data })
+            }
+            Err(error) => Err(create::Fail { error, wcc_data }),
+        }
+    }
+}
+
+impl mk_dir::MkDir for MemFs {
+    async fn mk_dir(
+        &self,
+        cred: &vfs::Credentials,
+        args: mk_dir::Args,
+    ) -> Result<mk_dir::Success, mk_dir::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let content = Content::Dir { entries: BTreeMap::new(), parent: ROOT_ID };
+        let node = Node::new(content, 0o755, 0, 0);
+        let before = state.wcc_before(&args.object.dir);
+        let result = state.create(cred, &args.object, node, &args.attr);
+        let wcc_data = state.wcc(&args.object.dir, before);
+        match result {
+            Ok((file, attr)) => {
+                Ok(mk_dir::Success { file: Some(file), attr: Some(attr), wcc_data })
+            }
+            Err(error) => Err(mk_dir::Fail { error, dir_wcc: wcc_data }),
+        }
+    }
+}
+
+impl symlink::Symlink for MemFs {
+    async fn symlink(
+        &self,
+        cred: &vfs::Credentials,
+        args: symlink::Args,
+    ) -> Result<symlink::Success, symlink::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let node = Node::new(Content::Symlink(args.path), 0o777, 0, 0);
+        let before = state.wcc_before(&args.object.dir);
+        let result = state.create(cred, &args.object, node, &args.attr);
+        let wcc_data = state.wcc(&args.object.dir, before);
+        match result {
+            Ok((file, attr)) => {
+                Ok(symlink::Success { file: Some(file), attr: Some(attr), wcc_data })
+            }
+            Err(error) => Err(symlink::Fail { error, dir_wcc: wcc_data }),
+        }
+    }
+}
+
+impl mk_node::MkNode for MemFs {
+    async fn mk_node(
+        &self,
+        cred: &vfs::Credentials,
+        args: mk_node::Args,
+    ) -> Result<mk_node::Success, mk_node::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let no_device = file::Device { major: 0, minor: 0 };
+        let (file_type, new_attr, device) = match args.what {
+            mk_node::What::Char(new_attr, device) => {
+                (file::Type::CharacterDevice, new_attr, device)
+            }
+            mk_node::What::Block(new_attr, device) => (file::Type::BlockDevice, new_attr, device),
+            mk_node::What::Socket(new_attr) => (file::Type::Socket, new_attr, no_device),
+            mk_node::What::Fifo(new_attr) => (file::Type::Fifo, new_attr, no_device),
+            mk_node::What::Regular | mk_node::What::Directory | mk_node::What::SymbolicLink => {
+                let dir_wcc = state.wcc(&args.object.dir, state.wcc_before(&args.object.dir));
+                return Err(mk_node::Fail { error: vfs::Error::BadType, dir_wcc });
+            }
+        };
+        let node = Node::new(Content::Special(file_type, device), 0o644, 0, 0);
+        let before = state.wcc_before(&args.object.dir);
+        let result = state.create(cred, &args.object, node, &new_attr);
+        let wcc_data = state.wcc(&args.object.dir, before);
+        match result {
+            Ok((file, attr)) => {
+                Ok(mk_node::Success { file: Some(file), attr: Some(attr), wcc_data })
+            }
+            Err(error) => Err(mk_node::Fail { error, dir_wcc: wcc_data }),
+        }
+    }
+}
+
+impl remove::Remove for MemFs {
+    async fn remove(
+        &self,
+        _cred: &vfs::Credentials,
+        args: remove::Args,
+    ) -> Result<remove::Success, remove::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.wcc_before(&args.object.dir);
+        let result = state.remove(&args.object, false);
+        let wcc_data = state.wcc(&args.object.dir, before);
+        match result {
+            Ok(()) => Ok(remove::Success { wcc_data }),
+            Err(error) => Err(remove::Fail { error, dir_wcc: wcc_data }),
+        }
+    }
+}
+
+impl rm_dir::RmDir for MemFs {
+    async fn rm_dir(
+        &self,
+        _cred: &vfs::Credentials,
+        args: rm_dir::Args,
+    ) -> Result<rm_dir::Success, rm_dir::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.wcc_before(&args.object.dir);
+        let result = state.remove(&args.object, true);
+        let wcc_data = state.wcc(&args.object.dir, before);
+        match result {
+            Ok(()) => Ok(rm_dir::Success { wcc_data }),
+            Err(error) => Err(rm_dir::Fail { error, dir_wcc: wcc_data }),
+        }
+    }
+}
+
+impl rename::Rename for MemFs {
+    async fn rename(
+        &self,
+        _cred: &vfs::Credentials,
+        args: rename::Args,
+    ) -> Result<rename::Success, rename::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let from_before = state.wcc_before(&args.from.dir);
+        let to_before = state.wcc_before(&args.to.dir);
+        let result = state.rename(&args.from, &args.to);
+        let from_dir_wcc = state.wcc(&args.from.dir, from_before);
+        let to_dir_wcc = state.wcc(&args.to.dir, to_before);
+        match result {
+            Ok(()) => Ok(rename::Success { from_dir_wcc, to_dir_wcc }),
+            Err(error) => Err(rename::Fail { error, from_dir_wcc, to_dir_wcc }),
+        }
+    }
+}
+
+impl link::Link for MemFs {
+    async fn link(
+        &self,
+        _cred: &vfs::Credentials,
+        args: link::Args,
+    ) -> Result<link::Success, link::Fail> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.wcc_before(&args.link.dir);
+        let result = state.link(&args.file, &args.link);
+        let file_attr = state.handle_attr(&args.file);
+        let dir_wcc = state.wcc(&args.link.dir, before);
+        match result {
+            Ok(()) => Ok(link::Success { file_attr, dir_wcc }),
+            Err(error) => Err(link::Fail { error, file_attr, dir_wcc }),
+        }
+    }
+}
+
+impl read_dir::ReadDir for MemFs {
+    async fn read_dir(&self, args: read_dir::Args) -> Result<read_dir::Success, read_dir::Fail> {
+        let state = self.state.lock().unwrap();
+        let dir_attr = state.handle_attr(&args.dir);
+        match state.list(&args.dir, args.cookie, args.count, DIR_ENTRY_OVERHEAD) {
+            Ok((listed, eof)) => Ok(read_dir::Success {
+                dir_attr,
+                cookie_verifier: CookieVerifier::new([0; 8]),
+                entries: listed
+                    .into_iter()
+                    .map(|(file_id, file_name, cookie)| read_dir::Entry {
+                        file_id,
+                        file_name: name(file_name),
+                        cookie,
+                    })
+                    .collect(),
+                eof,
+            }),
+            Err(error) => Err(read_dir::Fail { error, dir_attr }),
+        }
+    }
+}
+
+impl read_dir_plus::ReadDirPlus for MemFs {
+    async fn read_dir_plus(
+        &self,
+        args: read_dir_plus::Args,
+    ) -> Result<read_dir_plus::Success, read_dir_plus::Fail> {
+        let state = self.state.lock().unwrap();
+        let dir_attr = state.handle_attr(&args.dir);
+        match state.list(&args.dir, args.cookie, args.max_count, DIR_ENTRY_PLUS_OVERHEAD) {
+            Ok((listed, eof)) => Ok(read_dir_plus::Success {
+                dir_attr,
+                cookie_verifier: CookieVerifier::new([0; 8]),
+                entries: listed
+                    .into_iter()
+                    .map(|(file_id, file_name, cookie)| read_dir_plus::Entry {
+                        file_id,
+                        file_name: name(file_name),
+                        cookie,
+                        file_attr: state.attr(file_id),
+                        file_handle: Some(encode_handle(file_id)),
+                    })
+                    .collect(),
+                eof,
+            }),
+            Err(error) => Err(read_dir_plus::Fail { error, dir_attr }),
+        }
+    }
+}
+
+impl fs_stat::FsStat for MemFs {
+    async fn fs_stat(&self, args: fs_stat::Args) -> Result<fs_stat::Success, fs_stat::Fail> {
+        let state = self.state.lock().unwrap();
+        let root_attr = state.handle_attr(&args.root);
+        if root_attr.is_none() {
+            return Err(fs_stat::Fail { error: vfs::Error::StaleFile, root_attr });
+        }
+        let used = state.nodes.values().map(Node::size).sum::<u64>();
+        let total_files = u64::from(u32::MAX);
+        let free_files = total_files.saturating_sub(state.nodes.len() as u64);
+        let free_bytes = MAX_FILE_SIZE.saturating_sub(used);
+        Ok(fs_stat::Success {
+            root_attr,
+            total_bytes: MAX_FILE_SIZE,
+            free_bytes,
+            available_bytes: free_bytes,
+            total_files,
+            free_files,
+            available_files: free_files,
+            invarsec: 0,
+        })
+    }
+}
+
+impl fs_info::FsInfo for MemFs {
+    async fn fs_info(&self, args: fs_info::Args) -> Result<fs_info::Success, fs_info::Fail> {
+        let state = self.state.lock().unwrap();
+        let root_attr = state.handle_attr(&args.root);
+        if root_attr.is_none() {
+            return Err(fs_info::Fail { error: vfs::Error::StaleFile, root_attr });
+        }
+        Ok(fs_info::Success {
+            root_attr,
+            read_max: MAX_TRANSFER,
+            read_pref: MAX_TRANSFER,
+            read_mult: 4096,
+            write_max: MAX_TRANSFER,
+            write_pref: MAX_TRANSFER,
+            write_mult: 4096,
+            read_dir_pref: 8192,
+            max_file_size: MAX_FILE_SIZE,
+            time_delta: file::Time { seconds: 0, nanos: 1 },
+            properties: fs_info::Properties::from_wire(fs_info::Properties::ALL),
+        })
+    }
+}
+
+impl path_conf::PathConf for MemFs {
+    async fn path_conf(
+        &self,
+        args: path_conf::Args,
+    ) -> Result<path_conf::Success, path_conf::Fail> {
+        let state = self.state.lock().unwrap();
+        let file_attr = state.handle_attr(&args.file);
+        if file_attr.is_none() {
+            return Err(path_conf::Fail { error: vfs::Error::StaleFile, file_attr });
+        }
+        Ok(path_conf::Success {
+            file_attr,
+            link_max: u32::MAX,
+            name_max: vfs::MAX_NAME_LEN as u32,
+            no_trunc: true,
+            chown_restricted: false,
+            case_insensitive: false,
+            case_preserving: true,
+        })
+    }
+}
+
+impl commit::Commit for MemFs {
+    async fn commit(&self, args: commit::Args) -> Result<commit::Success, commit::Fail> {
+        let state = self.state.lock().unwrap();
+        let before = state.wcc_before(&args.file);
+        let file_wcc = state.wcc(&args.file, before);
+        match before {
+            Some(_) => {
+                Ok(commit::Success { file_wcc, verifier: write::Verifier(self.write_verifier) })
+            }
+            None => Err(commit::Fail { error: vfs::Error::StaleFile, file_wcc }),
+        }
+    }
+}
+
+impl vfs::acl::Acl for MemFs {}
+
+/// Creates a small tree to browse: a README, a directory with a few files, and a
+/// symbolic link to one of them.
+pub fn populate(fs: &MemFs) -> Result<(), vfs::Error> {
+    let root = fs.root_handle();
+    fs.insert_file(&root, "README", b"Served from memory by nfs-mamont.\n")?;
+    let docs = fs.insert_dir(&root, "docs")?;
+    fs.insert_file(&docs, "hello.txt", b"Hello, world!\n")?;
+    fs.insert_file(&docs, "zeros.bin", &[0; 64 * 1024])?;
+    fs.insert_dir(&root, "empty")?;
+    let mut state = fs.state.lock().unwrap();
+    {
+        let path =
+            file::Path::new("docs/hello.txt".to_owned()).map_err(|_| vfs::Error::NameTooLong)?;
+        state.insert_child(&root, "hello", Node::new(Content::Symlink(path), 0o777, 0, 0))
+    }?;
+    Ok(())
+}
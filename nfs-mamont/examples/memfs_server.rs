@@ -0,0 +1,88 @@
+//! NFSv3 server over an in-memory file system, for trying the server out.
+//!
+//! ```text
+//! cargo run --example memfs_server -- [PORT]
+//! mount -t nfs -o vers=3,proto=tcp,port=PORT,mountport=PORT,nolock 127.0.0.1:/ /mnt
+//! ```
+//!
+//! The export starts with a few files and directories and is lost on exit. Ctrl-C stops
+//! the server once calls already received are answered.
+
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use tokio::net::TcpListener;
+
+use nfs_mamont::mount::ExportEntry;
+use nfs_mamont::service::mount::{ExportEntryWrapper, MountService, DEFAULT_AUTH_FLAVORS};
+use nfs_mamont::service::nlm::NlmService;
+use nfs_mamont::vfs::file;
+use nfs_mamont::{handle_until_shutdown, Impl, ServerContext, ShutdownHandle};
+
+mod memfs;
+
+use memfs::MemFs;
+
+/// Port served when none is given.
+const DEFAULT_PORT: u16 = 11111;
+/// Size of every read and write buffer, matching the largest transfer of [`MemFs`].
+const BUFFER_SIZE: usize = 1 << 20;
+/// Number of read and write buffers.
+const BUFFER_COUNT: usize = 16;
+/// Number of VFS workers.
+const VFS_POOL_SIZE: usize = 4;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    nfs_mamont::init_tracing();
+
+    let port = match std::env::args().nth(1) {
+        Some(port) => port.parse().map_err(|error| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("bad port {port:?}: {error}"),
+            )
+        })?,
+        None => DEFAULT_PORT,
+    };
+
+    let fs = Arc::new(MemFs::new());
+    memfs::populate(&fs)
+        .map_err(|error| std::io::Error::other(format!("failed to populate export: {error:?}")))?;
+
+    let buffer_size = NonZeroUsize::new(BUFFER_SIZE).unwrap();
+    let buffer_count = NonZeroUsize::new(BUFFER_COUNT).unwrap();
+    let context = ServerContext::new(
+        Arc::clone(&fs),
+        Arc::new(Impl::new(buffer_size, buffer_count)),
+        Arc::new(Impl::new(buffer_size, buffer_count)),
+        NonZeroUsize::new(VFS_POOL_SIZE).unwrap(),
+    );
+    let mount_service = Arc::new(MountService::with_exports(vec![ExportEntryWrapper {
+        export: ExportEntry { directory: file::Path::new("/".to_owned())?, names: Vec::new() },
+        root_handle: fs.root_handle(),
+        auth_flavors: DEFAULT_AUTH_FLAVORS.to_vec(),
+    }]));
+    let nlm_service = Arc::new(NlmService::new());
+
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    println!("serving MemFs on port {port}, press Ctrl-C to stop");
+
+    let shutdown = ShutdownHandle::new();
+    let mut server = tokio::spawn(handle_until_shutdown(
+        listener,
+        context,
+        mount_service,
+        nlm_service,
+        shutdown.clone(),
+    ));
+
+    tokio::select! {
+        served = tokio::signal::ctrl_c() => served?,
+        // The server only returns on its own if accepting connections fails.
+        result = &mut server => return result?,
+    }
+    println!("shutting down");
+    shutdown.shutdown();
+    server.await?
+}
@@ -0,0 +1,121 @@
+//! Accepting connections without letting one failed connection stop the server.
+//!
+//! `accept(2)` reports errors of a single pending connection, e.g. one reset by the
+//! peer, next to errors of the listener itself. Only the latter end the accept loop;
+//! the others are logged and the next connection is accepted. Running out of file
+//! descriptors or memory is retried after [`EXHAUSTION_BACKOFF`], giving connections
+//! closing meanwhile the chance to free some instead of spinning on the error.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+use tracing::warn;
+
+/// Pause before accepting again after the process or system ran out of resources.
+pub const EXHAUSTION_BACKOFF: Duration = Duration::from_millis(100);
+
+/// What the accept loop does after `accept` failed.
+#[derive(Debug, PartialEq, Eq)]
+enum Recovery {
+    /// Accept the next connection right away.
+    Retry,
+    /// Accept the next connection after the given pause.
+    Backoff(Duration),
+    /// The listener is unusable.
+    Fatal,
+}
+
+fn recovery(error: &io::Error) -> Recovery {
+    match error.raw_os_error() {
+        Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM) => {
+            Recovery::Backoff(EXHAUSTION_BACKOFF)
+        }
+        Some(libc::EBADF | libc::EINVAL | libc::ENOTSOCK | libc::EFAULT) => Recovery::Fatal,
+        Some(_) => Recovery::Retry,
+        // Errors not coming from the OS say nothing about the pending connection.
+        None => Recovery::Fatal,
+    }
+}
+
+/// Returns the next connection `accept` yields, recovering from errors that affect a
+/// single connection or are transient; fails only if the listener is unusable.
+pub async fn next_connection<F, Fut>(mut accept: F) -> io::Result<TcpStream>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<(TcpStream, SocketAddr)>>,
+{
+    loop {
+        let error = match accept().await {
+            Ok((socket, _)) => return Ok(socket),
+            Err(error) => error,
+        };
+        match recovery(&error) {
+            Recovery::Retry => warn!(error=%error, "failed to accept connection"),
+            Recovery::Backoff(pause) => {
+                warn!(error=%error, backoff_ms=pause.as_millis(), "failed to accept connection");
+                tokio::time::sleep(pause).await;
+            }
+            Recovery::Fatal => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::{next_connection, recovery, Recovery, EXHAUSTION_BACKOFF};
+
+    #[test]
+    fn classifies_accept_errors() {
+        let os = io::Error::from_raw_os_error;
+        assert_eq!(recovery(&os(libc::EMFILE)), Recovery::Backoff(EXHAUSTION_BACKOFF));
+        assert_eq!(recovery(&os(libc::ENFILE)), Recovery::Backoff(EXHAUSTION_BACKOFF));
+        assert_eq!(recovery(&os(libc::ECONNABORTED)), Recovery::Retry);
+        assert_eq!(recovery(&os(libc::EINTR)), Recovery::Retry);
+        assert_eq!(recovery(&os(libc::EBADF)), Recovery::Fatal);
+        assert_eq!(recovery(&os(libc::EINVAL)), Recovery::Fatal);
+    }
+
+    /// Failures of single connections and fd exhaustion are skipped over, and the
+    /// connection pending behind them is still accepted.
+    #[tokio::test]
+    async fn recovers_from_transient_errors_and_accepts_next_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let errors = [libc::ECONNABORTED, libc::EMFILE];
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let started = tokio::time::Instant::now();
+        let socket = next_connection(|| {
+            let call = calls.fetch_add(1, Ordering::Relaxed);
+            let listener = &listener;
+            async move {
+                match errors.get(call) {
+                    Some(&errno) => Err(io::Error::from_raw_os_error(errno)),
+                    None => listener.accept().await,
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(socket.peer_addr().unwrap(), client.local_addr().unwrap());
+        assert!(started.elapsed() >= EXHAUSTION_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn fatal_listener_error_is_returned() {
+        let result =
+            next_connection(|| async { Err(io::Error::from_raw_os_error(libc::EBADF)) }).await;
+        assert_eq!(result.unwrap_err().raw_os_error(), Some(libc::EBADF));
+    }
+}
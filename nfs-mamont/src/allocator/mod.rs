@@ -20,6 +20,9 @@ use tokio::sync::Semaphore;
 pub use buffer::UnownedBuffer;
 pub use slice::Slice;
 
+/// Largest buffer [`Impl::for_transfers`] carves its pool into.
+pub const MAX_TRANSFER_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Shared state of the allocator to allow return of buffers and permit restoration.
 #[derive(Debug)]
 pub struct AllocatorState {
@@ -76,16 +79,18 @@ pub trait Allocator {
     ///
     /// - `size` --- minimum size of the returned buffer in bytes.
     ///
-    /// # Panic
+    /// # Returns
     ///
-    /// This method returns [`None`] if size is greater then allocator capacity.
+    /// [`None`] if `size` is greater than the allocator capacity. Since requested sizes
+    /// come from the wire, implementations must not panic on oversize requests.
     fn allocate(&self, size: NonZeroUsize) -> impl Future<Output = Option<Self::Buffer>> + Send;
 }
 
 pub struct Impl {
     state: Arc<AllocatorState>,
     buffer_size: NonZeroUsize,
-    buffer_count: NonZeroUsize,
+    /// Largest size a single [`Allocator::allocate`] call is served for.
+    max_allocation: usize,
 }
 
 impl Impl {
@@ -131,12 +136,39 @@ impl Impl {
         Self {
             state: Arc::new(AllocatorState { pool, semaphore, base_ptr, layout }),
             buffer_size: size,
-            buffer_count: count,
+            max_allocation: buffer_size * buffer_count,
         }
     }
 
-    fn capacity(&self) -> usize {
-        self.buffer_size.get() * self.buffer_count.get()
+    /// Returns an [`Allocator`] sized for transfers of up to `max_transfer` bytes, of
+    /// which up to `max_concurrent_ops` may be in flight at once.
+    ///
+    /// Buffers hold at most [`MAX_TRANSFER_BUFFER_SIZE`] bytes, so a transfer spans a
+    /// bounded number of them, and the pool holds enough buffers for every concurrent
+    /// operation to get a maximal transfer. Requests larger than `max_transfer` are
+    /// refused, so that a single oversize request cannot drain the pool.
+    ///
+    /// The server should advertise `max_transfer` as `rtmax`/`wtmax` in FSINFO.
+    pub fn for_transfers(max_transfer: NonZeroUsize, max_concurrent_ops: NonZeroUsize) -> Self {
+        let (size, count) = Self::transfer_layout(max_transfer, max_concurrent_ops);
+        Self { max_allocation: max_transfer.get(), ..Self::new(size, count) }
+    }
+
+    /// Returns the buffer size and count [`Impl::for_transfers`] allocates.
+    pub fn transfer_layout(
+        max_transfer: NonZeroUsize,
+        max_concurrent_ops: NonZeroUsize,
+    ) -> (NonZeroUsize, NonZeroUsize) {
+        let size = max_transfer.min(NonZeroUsize::new(MAX_TRANSFER_BUFFER_SIZE).unwrap());
+        let per_transfer = max_transfer.get().div_ceil(size.get());
+        let count = per_transfer.checked_mul(max_concurrent_ops.get()).expect("count overflow");
+        (size, NonZeroUsize::new(count).unwrap())
+    }
+
+    /// Returns the largest size [`Allocator::allocate`] serves.
+    #[inline]
+    pub fn max_allocation(&self) -> usize {
+        self.max_allocation
     }
 }
 
@@ -144,7 +176,7 @@ impl Allocator for Impl {
     type Buffer = slice::Slice;
 
     async fn allocate(&self, size: NonZeroUsize) -> Option<Self::Buffer> {
-        if size.get() > self.capacity() {
+        if size.get() > self.max_allocation {
             return None;
         }
 
@@ -119,3 +119,41 @@ async fn allocate_more_than_capacity_returns_none() {
 
     assert!(allocator.allocate(requested).await.is_none());
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_drops_return_every_buffer() {
+    const SIZE: NonZeroUsize = NonZeroUsize::new(13).unwrap();
+    const COUNT: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+    const TASKS: usize = 16;
+    const ROUNDS: usize = 500;
+
+    let allocator = std::sync::Arc::new(Impl::new(SIZE, COUNT));
+
+    let tasks: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let allocator = std::sync::Arc::clone(&allocator);
+            tokio::spawn(async move {
+                for round in 0..ROUNDS {
+                    let size = NonZeroUsize::new(1 + (task + round) % (SIZE.get() * 2)).unwrap();
+                    let slice = allocator.allocate(size).await.unwrap();
+                    if round % 7 == 0 {
+                        tokio::task::yield_now().await;
+                    }
+                    drop(slice);
+                }
+            })
+        })
+        .collect();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        for task in tasks {
+            task.await.unwrap();
+        }
+    })
+    .await
+    .expect("slices dropped concurrently must not deadlock the pool");
+
+    let capacity = NonZeroUsize::new(SIZE.get() * COUNT.get()).unwrap();
+    let slice = allocator.allocate(capacity).await.unwrap();
+    assert_eq!(slice.iter().count(), COUNT.get());
+}
@@ -1,3 +1,4 @@
 //! Defines tests for [`crate::allocator::Allocator`] interface.
 
 mod allocate;
+mod transfers;
@@ -0,0 +1,56 @@
+//! Defines tests for [`crate::allocator::Impl::for_transfers`].
+
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use crate::allocator::Allocator as _;
+use crate::allocator::{Impl, MAX_TRANSFER_BUFFER_SIZE};
+
+fn non_zero(value: usize) -> NonZeroUsize {
+    NonZeroUsize::new(value).unwrap()
+}
+
+#[test]
+fn transfer_layout_bounds_buffers_per_transfer() {
+    let cases = [
+        (4096, 8, 4096, 8),
+        (MAX_TRANSFER_BUFFER_SIZE, 4, MAX_TRANSFER_BUFFER_SIZE, 4),
+        (1024 * 1024, 2, MAX_TRANSFER_BUFFER_SIZE, 32),
+        (MAX_TRANSFER_BUFFER_SIZE + 1, 3, MAX_TRANSFER_BUFFER_SIZE, 6),
+    ];
+    for (max_transfer, ops, size, count) in cases {
+        let (actual_size, actual_count) =
+            Impl::transfer_layout(non_zero(max_transfer), non_zero(ops));
+        assert_eq!((actual_size.get(), actual_count.get()), (size, count), "{max_transfer}");
+    }
+}
+
+#[tokio::test]
+async fn largest_transfer_is_allocatable_for_every_concurrent_op() {
+    for (max_transfer, ops) in [(4096, 8), (1024 * 1024, 2), (MAX_TRANSFER_BUFFER_SIZE + 1, 3)] {
+        let allocator = Impl::for_transfers(non_zero(max_transfer), non_zero(ops));
+        assert_eq!(allocator.max_allocation(), max_transfer);
+
+        let mut held = Vec::with_capacity(ops);
+        for _ in 0..ops {
+            let slice =
+                tokio::time::timeout(Duration::ZERO, allocator.allocate(non_zero(max_transfer)))
+                    .await
+                    .expect("pool should hold a maximal transfer per op")
+                    .expect("maximal transfer should be served");
+            assert_eq!(slice.len(), max_transfer);
+            held.push(slice);
+        }
+    }
+}
+
+#[tokio::test]
+async fn oversize_transfer_is_refused_without_waiting() {
+    let allocator = Impl::for_transfers(non_zero(4096), non_zero(8));
+
+    let oversize = tokio::time::timeout(Duration::ZERO, allocator.allocate(non_zero(4097)))
+        .await
+        .expect("oversize requests should not wait for buffers");
+    assert!(oversize.is_none());
+    assert!(allocator.allocate(non_zero(4096)).await.is_some());
+}
@@ -0,0 +1,77 @@
+//! Audit trail of NFS calls refused for security reasons.
+//!
+//! The dispatcher reports every NFSv3 call failing with [`vfs::Error::Permission`],
+//! [`vfs::Error::Access`], [`vfs::Error::StaleFile`] or [`vfs::Error::BadFileHandle`]
+//! to the [`AuditSink`] of the server, so backends need no auditing of their own.
+
+use std::net::SocketAddr;
+
+use tracing::warn;
+
+use crate::vfs::{self, file};
+
+/// A refused NFS call.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    /// Address of the client connection the call came from.
+    pub client_addr: SocketAddr,
+    /// User id of an AUTH_SYS caller; `None` for other flavors.
+    pub uid: Option<u32>,
+    /// Procedure name, such as `"REMOVE"`.
+    pub procedure: &'static str,
+    /// Handle the call operated on: the file, or the directory of a name.
+    pub handle: file::Handle,
+    /// Name within the directory of [`Self::handle`], for calls naming an entry.
+    pub name: Option<String>,
+    /// Error the call failed with.
+    pub error: vfs::Error,
+}
+
+impl AuditEvent {
+    /// Returns `true` if calls failing with `error` are audited.
+    pub fn is_audited(error: vfs::Error) -> bool {
+        matches!(
+            error,
+            vfs::Error::Permission
+                | vfs::Error::Access
+                | vfs::Error::StaleFile
+                | vfs::Error::BadFileHandle
+        )
+    }
+}
+
+/// Receiver of [`AuditEvent`]s.
+///
+/// Called on the VFS workers right after a call fails, so implementations should
+/// hand slow work, such as writing to remote storage, off to another task.
+pub trait AuditSink: Send + Sync {
+    /// Records a refused call.
+    fn record(&self, event: &AuditEvent);
+}
+
+/// Sink dropping every event, like a server without a sink.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopAuditSink;
+
+impl AuditSink for NoopAuditSink {
+    fn record(&self, _event: &AuditEvent) {}
+}
+
+/// Sink logging every event as a `tracing` warning with target `nfs_mamont::audit`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TracingAuditSink;
+
+impl AuditSink for TracingAuditSink {
+    fn record(&self, event: &AuditEvent) {
+        warn!(
+            target: "nfs_mamont::audit",
+            client = %event.client_addr,
+            uid = ?event.uid,
+            proc = event.procedure,
+            handle = ?event.handle,
+            name = ?event.name,
+            error = ?event.error,
+            "nfs call refused",
+        );
+    }
+}
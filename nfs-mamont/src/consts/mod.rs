@@ -1,3 +1,4 @@
 pub mod mount;
+pub mod nfs_acl;
 pub mod nfsv3;
 pub mod nlm;
@@ -5,6 +5,10 @@ pub const MOUNT_HOST_NAME_LEN: usize = 255;
 
 pub const MOUNT_PROGRAM: u32 = 100005;
 pub const MOUNT_VERSION: u32 = 3;
+/// Lowest MOUNT version served; older versions are answered with `PROG_MISMATCH`.
+pub const MOUNT_VERSION_LOW: u32 = MOUNT_VERSION;
+/// Highest MOUNT version served.
+pub const MOUNT_VERSION_HIGH: u32 = MOUNT_VERSION;
 
 pub const MOUNT_NULL: u32 = 0;
 pub const MOUNT_MNT: u32 = 1;
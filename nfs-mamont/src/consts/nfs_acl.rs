@@ -0,0 +1,15 @@
+pub const NFS_ACL_PROGRAM: u32 = 100227;
+pub const NFS_ACL_VERSION: u32 = 3;
+/// Lowest NFSACL version served; other versions are answered with `PROG_MISMATCH`.
+pub const NFS_ACL_VERSION_LOW: u32 = NFS_ACL_VERSION;
+/// Highest NFSACL version served.
+pub const NFS_ACL_VERSION_HIGH: u32 = NFS_ACL_VERSION;
+
+pub const ACLPROC3_NULL: u32 = 0;
+pub const ACLPROC3_GETACL: u32 = 1;
+pub const ACLPROC3_SETACL: u32 = 2;
+
+/// Maximum number of entries in a single access control list.
+pub const NFS_ACL_MAX_ENTRIES: usize = 1024;
+/// Flag set in the type of every entry of a default ACL on the wire.
+pub const NFS_ACL_DEFAULT: u32 = 0x1000;
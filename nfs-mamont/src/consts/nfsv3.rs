@@ -1,5 +1,9 @@
 pub const NFS_PROGRAM: u32 = 100003;
 pub const NFS_VERSION: u32 = 3;
+/// Lowest NFS version served; other versions are answered with `PROG_MISMATCH`.
+pub const NFS_VERSION_LOW: u32 = NFS_VERSION;
+/// Highest NFS version served.
+pub const NFS_VERSION_HIGH: u32 = NFS_VERSION;
 
 pub const NULL: u32 = 0;
 pub const GETATTR: u32 = 1;
@@ -24,6 +28,98 @@ pub const FSINFO: u32 = 19;
 pub const PATHCONF: u32 = 20;
 pub const COMMIT: u32 = 21;
 
+/// NFSv3 procedures, numbered as in RFC 1813.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NfsProc3 {
+    Null = NULL as isize,
+    GetAttr = GETATTR as isize,
+    SetAttr = SETATTR as isize,
+    Lookup = LOOKUP as isize,
+    Access = ACCESS as isize,
+    ReadLink = READLINK as isize,
+    Read = READ as isize,
+    Write = WRITE as isize,
+    Create = CREATE as isize,
+    MkDir = MKDIR as isize,
+    Symlink = SYMLINK as isize,
+    MkNod = MKNOD as isize,
+    Remove = REMOVE as isize,
+    RmDir = RMDIR as isize,
+    Rename = RENAME as isize,
+    Link = LINK as isize,
+    ReadDir = READDIR as isize,
+    ReadDirPlus = READDIRPLUS as isize,
+    FsStat = FSSTAT as isize,
+    FsInfo = FSINFO as isize,
+    PathConf = PATHCONF as isize,
+    Commit = COMMIT as isize,
+}
+
+impl NfsProc3 {
+    /// Every procedure, in procedure number order.
+    pub const ALL: [NfsProc3; 22] = [
+        NfsProc3::Null,
+        NfsProc3::GetAttr,
+        NfsProc3::SetAttr,
+        NfsProc3::Lookup,
+        NfsProc3::Access,
+        NfsProc3::ReadLink,
+        NfsProc3::Read,
+        NfsProc3::Write,
+        NfsProc3::Create,
+        NfsProc3::MkDir,
+        NfsProc3::Symlink,
+        NfsProc3::MkNod,
+        NfsProc3::Remove,
+        NfsProc3::RmDir,
+        NfsProc3::Rename,
+        NfsProc3::Link,
+        NfsProc3::ReadDir,
+        NfsProc3::ReadDirPlus,
+        NfsProc3::FsStat,
+        NfsProc3::FsInfo,
+        NfsProc3::PathConf,
+        NfsProc3::Commit,
+    ];
+
+    /// Returns the procedure name as RFC 1813 spells it, e.g. `"GETATTR"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            NfsProc3::Null => "NULL",
+            NfsProc3::GetAttr => "GETATTR",
+            NfsProc3::SetAttr => "SETATTR",
+            NfsProc3::Lookup => "LOOKUP",
+            NfsProc3::Access => "ACCESS",
+            NfsProc3::ReadLink => "READLINK",
+            NfsProc3::Read => "READ",
+            NfsProc3::Write => "WRITE",
+            NfsProc3::Create => "CREATE",
+            NfsProc3::MkDir => "MKDIR",
+            NfsProc3::Symlink => "SYMLINK",
+            NfsProc3::MkNod => "MKNOD",
+            NfsProc3::Remove => "REMOVE",
+            NfsProc3::RmDir => "RMDIR",
+            NfsProc3::Rename => "RENAME",
+            NfsProc3::Link => "LINK",
+            NfsProc3::ReadDir => "READDIR",
+            NfsProc3::ReadDirPlus => "READDIRPLUS",
+            NfsProc3::FsStat => "FSSTAT",
+            NfsProc3::FsInfo => "FSINFO",
+            NfsProc3::PathConf => "PATHCONF",
+            NfsProc3::Commit => "COMMIT",
+        }
+    }
+}
+
+impl TryFrom<u32> for NfsProc3 {
+    /// The procedure number, which names no NFSv3 procedure.
+    type Error = u32;
+
+    fn try_from(procedure: u32) -> Result<Self, Self::Error> {
+        NfsProc3::ALL.get(procedure as usize).copied().ok_or(procedure)
+    }
+}
+
 pub const NFS3_FHSIZE: usize = 8;
 
 pub const NFS3_COOKIEVERFSIZE: usize = 8;
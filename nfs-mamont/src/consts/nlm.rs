@@ -1,5 +1,9 @@
 pub const NLM_PROGRAM: u32 = 100021;
 pub const NLM_VERSION: u32 = 4;
+/// Lowest NLM version served; other versions are answered with `PROG_MISMATCH`.
+pub const NLM_VERSION_LOW: u32 = NLM_VERSION;
+/// Highest NLM version served.
+pub const NLM_VERSION_HIGH: u32 = NLM_VERSION;
 
 pub const NLMPROC4_NULL: u32 = 0;
 pub const NLMPROC4_TEST: u32 = 1;
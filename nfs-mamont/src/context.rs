@@ -1,10 +1,76 @@
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::allocator::{Allocator, Buffer};
+use crate::audit::AuditSink;
+use crate::parser::primitive;
+use crate::serializer::server::serialize_struct::DEFAULT_MAX_REPLY_BYTES;
+use crate::socket::SocketConfig;
+use crate::spawner::{Spawner, TokioSpawner};
 use crate::task::global::vfs::VfsPool;
 use crate::vfs;
 
+/// Default number of NFS calls queued for the VFS workers across all connections.
+pub const DEFAULT_REQUEST_QUEUE_CAPACITY: usize = 1024;
+/// Default number of replies queued for the write task of each connection.
+pub const DEFAULT_REPLY_QUEUE_CAPACITY: usize = 256;
+
+/// Capacities of the channels between the connection tasks and the VFS workers.
+///
+/// When the VFS workers fall behind, the request queue fills up and read tasks stop
+/// pulling calls from their sockets, so clients are throttled by TCP flow control
+/// instead of growing server memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueCapacity {
+    /// Calls parsed but not yet picked up by a VFS worker, shared by all connections.
+    pub requests: NonZeroUsize,
+    /// Replies produced but not yet written to the socket, per connection.
+    pub replies: NonZeroUsize,
+}
+
+impl Default for QueueCapacity {
+    fn default() -> Self {
+        Self {
+            requests: NonZeroUsize::new(DEFAULT_REQUEST_QUEUE_CAPACITY).unwrap(),
+            replies: NonZeroUsize::new(DEFAULT_REPLY_QUEUE_CAPACITY).unwrap(),
+        }
+    }
+}
+
+/// Per-connection limits on the rate calls are read off the socket.
+///
+/// Every connection gets its own token buckets, refilled at the configured rate and
+/// holding up to one second worth of tokens. A connection out of tokens stops reading
+/// until they refill, so its client is throttled by TCP flow control while other
+/// connections are unaffected. Both limits are off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Calls read per second, NULL calls included.
+    pub requests_per_sec: Option<NonZeroU32>,
+    /// Bytes of calls read per second, record marks included.
+    pub bytes_per_sec: Option<NonZeroU64>,
+}
+
+/// Treatment of NFS calls made with AUTH_NONE credentials, which carry no identity.
+///
+/// NULL calls are always answered, so clients can probe the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnonymousAccess {
+    /// Calls are served as user `uid` of group `gid`.
+    Allow { uid: u32, gid: u32 },
+    /// Calls are rejected with `AUTH_TOOWEAK`.
+    Deny,
+}
+
+impl Default for AnonymousAccess {
+    /// Serves AUTH_NONE calls as [`vfs::credentials::ANON_UID`] and
+    /// [`vfs::credentials::ANON_GID`].
+    fn default() -> Self {
+        Self::Allow { uid: vfs::credentials::ANON_UID, gid: vfs::credentials::ANON_GID }
+    }
+}
+
 /// Shared server resources: VFS worker pool, buffer allocators, and backend.
 ///
 /// Construct once at startup and share across connection handlers.
@@ -22,6 +88,21 @@ where
     write_allocator: Arc<A>,
     /// Filesystem implementation backing all NFS operations.
     backend: Arc<V>,
+    /// Strategy used to launch server tasks.
+    spawner: Arc<dyn Spawner>,
+    /// Capacities of the request and reply queues.
+    queue_capacity: QueueCapacity,
+    /// Limits on the rate of calls of each connection.
+    rate_limit: RateLimit,
+    /// TCP options of accepted connections.
+    socket_config: SocketConfig,
+    /// Checks applied to the fields of incoming calls.
+    parse_limits: primitive::Limits,
+    /// Cap on the size of a single reply, excluding its record mark.
+    max_reply_bytes: usize,
+    /// zstd level READ data is compressed at, if at all.
+    #[cfg(feature = "compression")]
+    read_compression: Option<i32>,
 }
 
 impl<A, V, B> ServerContext<A, V, B>
@@ -31,16 +112,162 @@ where
     V: vfs::Vfs<B> + Send + Sync + 'static,
 {
     /// Creates a context with the given backend and buffer pool sizes.
+    ///
+    /// Tasks are spawned on the ambient Tokio runtime with [`TokioSpawner`].
     pub fn new(
         backend: Arc<V>,
         read_allocator: Arc<A>,
         write_allocator: Arc<A>,
         vfs_pool_size: NonZeroUsize,
     ) -> Self {
-        let vfs_pool =
-            VfsPool::new(vfs_pool_size, Arc::clone(&backend), Arc::clone(&read_allocator));
+        Self::with_spawner(
+            backend,
+            read_allocator,
+            write_allocator,
+            vfs_pool_size,
+            Arc::new(TokioSpawner),
+        )
+    }
 
-        Self { vfs_pool, read_allocator, write_allocator, backend }
+    /// Creates a context whose tasks, including the VFS workers, are launched by `spawner`.
+    pub fn with_spawner(
+        backend: Arc<V>,
+        read_allocator: Arc<A>,
+        write_allocator: Arc<A>,
+        vfs_pool_size: NonZeroUsize,
+        spawner: Arc<dyn Spawner>,
+    ) -> Self {
+        Self::with_queue_capacity(
+            backend,
+            read_allocator,
+            write_allocator,
+            vfs_pool_size,
+            spawner,
+            QueueCapacity::default(),
+        )
+    }
+
+    /// Creates a context like [`Self::with_spawner`], with explicit queue capacities.
+    pub fn with_queue_capacity(
+        backend: Arc<V>,
+        read_allocator: Arc<A>,
+        write_allocator: Arc<A>,
+        vfs_pool_size: NonZeroUsize,
+        spawner: Arc<dyn Spawner>,
+        queue_capacity: QueueCapacity,
+    ) -> Self {
+        let vfs_pool = VfsPool::new(
+            vfs_pool_size,
+            queue_capacity.requests,
+            Arc::clone(&backend),
+            Arc::clone(&read_allocator),
+            spawner.as_ref(),
+        );
+
+        Self {
+            vfs_pool,
+            read_allocator,
+            write_allocator,
+            backend,
+            spawner,
+            queue_capacity,
+            rate_limit: RateLimit::default(),
+            socket_config: SocketConfig::default(),
+            parse_limits: primitive::Limits::default(),
+            max_reply_bytes: DEFAULT_MAX_REPLY_BYTES,
+            #[cfg(feature = "compression")]
+            read_compression: None,
+        }
+    }
+
+    /// Reports NFS calls refused for security reasons to `sink`; see [`crate::audit`].
+    pub fn with_audit_sink(self, sink: Arc<dyn AuditSink>) -> Self {
+        self.vfs_pool.set_audit_sink(sink);
+        self
+    }
+
+    /// Serves or rejects NFS calls made with AUTH_NONE credentials according to `access`.
+    pub fn with_anonymous_access(self, access: AnonymousAccess) -> Self {
+        self.vfs_pool.set_anonymous_access(access);
+        self
+    }
+
+    /// Maps the identity of every NFS call through `id_map` before the backend sees it,
+    /// e.g. to squash root callers to the anonymous user.
+    pub fn with_id_map(self, id_map: vfs::IdMapPolicy) -> Self {
+        self.vfs_pool.set_id_map(id_map);
+        self
+    }
+
+    /// Fails replies whose attributes break `fattr3` invariants, such as a file without
+    /// links or a directory whose mode has other type bits, with `NFS3ERR_SERVERFAULT`
+    /// instead of sending them. Meant to catch backend bugs, the failure is logged.
+    pub fn with_strict_attrs(self, strict: bool) -> Self {
+        self.vfs_pool.set_strict_attrs(strict);
+        self
+    }
+
+    /// Cancels NFS procedures the backend takes longer than `timeout` to complete and
+    /// answers them with `NFS3ERR_JUKEBOX`, asking the client to retry later, so a hung
+    /// backend call cannot stall its connection forever.
+    pub fn with_proc_timeout(self, timeout: Duration) -> Self {
+        self.vfs_pool.set_proc_timeout(timeout);
+        self
+    }
+
+    /// Limits the rate at which each connection reads calls.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Applies `config` to every accepted connection.
+    ///
+    /// Listener options take effect only on listeners created by [`SocketConfig::bind`].
+    pub fn with_socket_config(mut self, config: SocketConfig) -> Self {
+        self.socket_config = config;
+        self
+    }
+
+    /// Selects whether XDR padding bytes of incoming calls must be zero, as RFC 4506
+    /// requires.
+    ///
+    /// Strict mode is the default; lenient mode only exists for interoperability with
+    /// clients that leave garbage in padding.
+    pub fn with_strict_padding(mut self, strict: bool) -> Self {
+        self.parse_limits.strict_padding = strict;
+        self
+    }
+
+    /// Caps the declared length of any single counted field of incoming calls (opaque
+    /// data, strings, WRITE payloads) at `len`, on top of per-field limits.
+    ///
+    /// Defaults to [`crate::DEFAULT_MAX_COUNTED_LEN`].
+    pub fn with_max_counted_len(mut self, len: usize) -> Self {
+        self.parse_limits.max_counted_len = len;
+        self
+    }
+
+    /// Caps the size of a single reply, excluding its record mark, at `len` bytes.
+    ///
+    /// READ counts and READDIR/READDIRPLUS budgets requested by clients are clamped so
+    /// that replies fit; a reply exceeding the cap anyway is answered with `SYSTEM_ERR`
+    /// instead of being assembled. Defaults to [`crate::DEFAULT_MAX_REPLY_BYTES`].
+    pub fn with_max_reply_bytes(mut self, len: usize) -> Self {
+        self.vfs_pool.set_max_reply_bytes(len);
+        self.max_reply_bytes = len;
+        self
+    }
+
+    /// Compresses the data of every READ reply with zstd at `level`.
+    ///
+    /// Experimental and not part of NFSv3: only clients built to decompress READ data
+    /// can mount the server, standard clients read garbage. The opaque data of
+    /// `READ3resok` holds a zstd frame, while its `count` keeps the decompressed size.
+    #[cfg(feature = "compression")]
+    pub fn with_read_compression(mut self, level: i32) -> Self {
+        self.read_compression = Some(level);
+        self
     }
 
     /// Returns the shared VFS worker pool used to dispatch NFS procedure work.
@@ -49,6 +276,49 @@ where
         &self.vfs_pool
     }
 
+    /// Returns the capacities of the request and reply queues.
+    #[inline]
+    pub fn get_queue_capacity(&self) -> QueueCapacity {
+        self.queue_capacity
+    }
+
+    /// Returns the limits on the rate of calls of each connection.
+    #[inline]
+    pub fn get_rate_limit(&self) -> RateLimit {
+        self.rate_limit
+    }
+
+    /// Returns the TCP options of accepted connections.
+    #[inline]
+    pub fn get_socket_config(&self) -> SocketConfig {
+        self.socket_config
+    }
+
+    /// Returns the checks applied to the fields of incoming calls.
+    #[inline]
+    pub(crate) fn get_parse_limits(&self) -> primitive::Limits {
+        self.parse_limits
+    }
+
+    /// Returns the cap on the size of a single reply.
+    #[inline]
+    pub fn get_max_reply_bytes(&self) -> usize {
+        self.max_reply_bytes
+    }
+
+    /// Returns the zstd level READ data is compressed at, if at all.
+    #[cfg(feature = "compression")]
+    #[inline]
+    pub fn get_read_compression(&self) -> Option<i32> {
+        self.read_compression
+    }
+
+    /// Returns the strategy used to launch server tasks.
+    #[inline]
+    pub fn get_spawner(&self) -> &dyn Spawner {
+        self.spawner.as_ref()
+    }
+
     /// Returns a clone of the [`vfs::Vfs`] backend.
     #[inline]
     pub fn get_backend(&self) -> Arc<V> {
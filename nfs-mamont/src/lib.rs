@@ -1,6 +1,8 @@
 //! NFS Mamont - A Network File System (NFS) server implementation in Rust.
 
+mod accept;
 mod allocator;
+pub mod audit;
 pub mod consts;
 mod context;
 pub mod mount;
@@ -10,12 +12,16 @@ mod parser;
 mod rpc;
 mod serializer;
 pub mod service;
+mod shutdown;
+mod socket;
+mod spawner;
 mod task;
 pub mod vfs;
 
 use std::sync::Arc;
 
 use tokio::net::TcpListener;
+use tokio::sync::mpsc;
 use tracing_subscriber::EnvFilter;
 
 use crate::task::global::mount::MountTask;
@@ -24,8 +30,19 @@ use crate::vfs::Vfs;
 use crate::{mount::Mount, task::connection};
 
 use crate::nlm::Nlm;
-pub use allocator::{Allocator, Buffer, Impl, Slice, UnownedBuffer};
-pub use context::ServerContext;
+pub use allocator::{Allocator, Buffer, Impl, Slice, UnownedBuffer, MAX_TRANSFER_BUFFER_SIZE};
+pub use context::{
+    AnonymousAccess, QueueCapacity, RateLimit, ServerContext, DEFAULT_REPLY_QUEUE_CAPACITY,
+    DEFAULT_REQUEST_QUEUE_CAPACITY,
+};
+pub use parser::parser_struct::parse_request;
+pub use parser::primitive::DEFAULT_MAX_COUNTED_LEN;
+pub use rpc::OpaqueAuth;
+pub use serializer::server::serialize_struct::{Serializer, DEFAULT_MAX_REPLY_BYTES};
+pub use shutdown::ShutdownHandle;
+pub use socket::{Keepalive, SocketConfig, LISTEN_BACKLOG};
+pub use spawner::{HandleSpawner, LocalSpawner, ServerTask, Spawner, TokioSpawner};
+pub use task::{ProcReply, ProcResult};
 
 /// Initializes tracing logs.
 ///
@@ -44,6 +61,28 @@ pub async fn handle_forever<A, B, M, N, V>(
     mount_service: Arc<M>,
     nlm_service: Arc<N>,
 ) -> std::io::Result<()>
+where
+    A: Allocator<Buffer = B> + Send + Sync + 'static,
+    B: Buffer + 'static,
+    M: Mount + Send + Sync + 'static,
+    N: Nlm + Send + Sync + 'static,
+    V: Vfs<B> + Send + Sync + 'static,
+{
+    handle_until_shutdown(listener, context, mount_service, nlm_service, ShutdownHandle::new())
+        .await
+}
+
+/// Starts the NFS server like [`handle_forever`] and serves until `shutdown` is requested.
+///
+/// Returns once every connection has drained: read tasks are aborted first, calls
+/// already queued are answered, and only then are `context` and its allocators dropped.
+pub async fn handle_until_shutdown<A, B, M, N, V>(
+    listener: TcpListener,
+    context: ServerContext<A, V, B>,
+    mount_service: Arc<M>,
+    nlm_service: Arc<N>,
+    shutdown: ShutdownHandle,
+) -> std::io::Result<()>
 where
     A: Allocator<Buffer = B> + Send + Sync + 'static,
     B: Buffer + 'static,
@@ -52,14 +91,24 @@ where
     V: Vfs<B> + Send + Sync + 'static,
 {
     let (mount_task, mount_sender) = MountTask::new(mount_service);
-    mount_task.spawn();
+    mount_task.spawn(context.get_spawner());
 
     let (nlm_task, nlm_sender) = NlmTask::new(nlm_service);
-    nlm_task.spawn();
+    nlm_task.spawn(context.get_spawner());
 
+    let (drained_sender, mut drained) = mpsc::channel(1);
+    let mut signal = shutdown.signal(drained_sender);
     loop {
-        let (socket, _) = listener.accept().await?;
+        let socket = tokio::select! {
+            accepted = accept::next_connection(|| listener.accept()) => accepted?,
+            () = signal.requested() => break,
+        };
 
-        connection::new(socket, mount_sender.clone(), nlm_sender.clone(), &context).await;
+        connection::new(socket, mount_sender.clone(), nlm_sender.clone(), &context, &signal).await;
     }
+
+    drop(signal);
+    // Every signal clone held by connection tasks is gone once this returns `None`.
+    let _ = drained.recv().await;
+    Ok(())
 }
@@ -8,7 +8,7 @@ use std::net::SocketAddr;
 use num_derive::{FromPrimitive, ToPrimitive};
 
 use crate::rpc::{AuthFlavor, OpaqueAuth};
-use crate::vfs::file;
+use crate::vfs::{self, file};
 
 #[derive(Debug, ToPrimitive, FromPrimitive)]
 /// Possible MOUNT errors
@@ -33,6 +33,24 @@ pub enum Fail {
     ServerFault = 10006,
 }
 
+impl From<Fail> for vfs::Error {
+    /// Every MOUNT status shares its code and meaning with an NFS one, so a backend
+    /// serving both protocols can report MOUNT failures through [`vfs::Error`] too.
+    fn from(fail: Fail) -> Self {
+        match fail {
+            Fail::Perm => vfs::Error::Permission,
+            Fail::NoEnt => vfs::Error::NoEntry,
+            Fail::Io => vfs::Error::IO,
+            Fail::Access => vfs::Error::Access,
+            Fail::NoDir => vfs::Error::NotDir,
+            Fail::Inval => vfs::Error::InvalidArgument,
+            Fail::NameTooLong => vfs::Error::NameTooLong,
+            Fail::NotSupp => vfs::Error::NotSupported,
+            Fail::ServerFault => vfs::Error::ServerFault,
+        }
+    }
+}
+
 /// Success result.
 pub struct Success {
     /// The file handle for the mounted directory.
@@ -66,3 +84,29 @@ pub trait Mnt {
         cred: OpaqueAuth,
     ) -> Result<Success, Fail>;
 }
+
+#[cfg(test)]
+mod tests {
+    use num_traits::ToPrimitive;
+
+    use super::Fail;
+    use crate::vfs;
+
+    #[test]
+    fn fail_converts_to_nfs_error_with_the_same_code() {
+        for fail in [
+            Fail::Perm,
+            Fail::NoEnt,
+            Fail::Io,
+            Fail::Access,
+            Fail::NoDir,
+            Fail::Inval,
+            Fail::NameTooLong,
+            Fail::NotSupp,
+            Fail::ServerFault,
+        ] {
+            let code = fail.to_u32();
+            assert_eq!(vfs::Error::from(fail).to_u32(), code);
+        }
+    }
+}
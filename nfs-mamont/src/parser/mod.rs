@@ -6,7 +6,11 @@ pub mod nlm;
 pub mod parser_struct;
 pub mod primitive;
 pub mod read_buffer;
-mod rpc;
+pub mod registry;
+#[allow(dead_code)]
+#[cfg(test)]
+pub mod reply;
+pub mod rpc;
 
 #[cfg(test)]
 mod tests;
@@ -14,14 +18,16 @@ mod tests;
 use std::future::Future;
 
 use crate::allocator::Buffer;
+use crate::consts::nfsv3::NfsProc3;
 use crate::mount::{mnt, umnt};
 use crate::nlm::procedures::{
     cancel::Nlm4CancelArgs, lock::Nlm4LockArgs, test::Nlm4TestArgs, unlock::Nlm4UnlockArgs,
 };
 use crate::rpc::{Error, OpaqueAuth};
 use crate::vfs::{
-    access, commit, create, fs_info, fs_stat, get_attr, link, lookup, mk_dir, mk_node, path_conf,
-    read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_attr, symlink, write,
+    access, commit, create, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir, mk_node,
+    path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl, set_attr,
+    symlink, write,
 };
 
 /// Result of parsing operations with errors type [`Error`].
@@ -93,6 +99,8 @@ pub enum ProcArguments<B: Buffer> {
 }
 
 /// Enumerates supported NFS protocol procedure arguments.
+///
+/// NFSACL calls are served by the same [`crate::vfs::Vfs`] and are carried here as well.
 pub enum NfsArguments<B: Buffer> {
     /// Null operation arguments.
     Null,
@@ -138,6 +146,44 @@ pub enum NfsArguments<B: Buffer> {
     PathConf(path_conf::Args),
     /// Arguments for the [`commit`] operation.
     Commit(commit::Args),
+    /// Arguments for the NFSACL [`get_acl`] operation.
+    GetAcl(get_acl::Args),
+    /// Arguments for the NFSACL [`set_acl`] operation.
+    SetAcl(set_acl::Args),
+}
+
+impl<B: Buffer> NfsArguments<B> {
+    /// Returns the NFSv3 procedure the arguments are for, or `None` for an NFSACL one.
+    ///
+    /// The arguments of the NULL procedure of either program are both [`NfsProc3::Null`].
+    pub fn nfs_proc(&self) -> Option<NfsProc3> {
+        let proc = match self {
+            NfsArguments::Null => NfsProc3::Null,
+            NfsArguments::GetAttr(_) => NfsProc3::GetAttr,
+            NfsArguments::SetAttr(_) => NfsProc3::SetAttr,
+            NfsArguments::LookUp(_) => NfsProc3::Lookup,
+            NfsArguments::Access(_) => NfsProc3::Access,
+            NfsArguments::ReadLink(_) => NfsProc3::ReadLink,
+            NfsArguments::Read(_) => NfsProc3::Read,
+            NfsArguments::Write(_) => NfsProc3::Write,
+            NfsArguments::Create(_) => NfsProc3::Create,
+            NfsArguments::MkDir(_) => NfsProc3::MkDir,
+            NfsArguments::SymLink(_) => NfsProc3::Symlink,
+            NfsArguments::MkNod(_) => NfsProc3::MkNod,
+            NfsArguments::Remove(_) => NfsProc3::Remove,
+            NfsArguments::RmDir(_) => NfsProc3::RmDir,
+            NfsArguments::Rename(_) => NfsProc3::Rename,
+            NfsArguments::Link(_) => NfsProc3::Link,
+            NfsArguments::ReadDir(_) => NfsProc3::ReadDir,
+            NfsArguments::ReadDirPlus(_) => NfsProc3::ReadDirPlus,
+            NfsArguments::FsStat(_) => NfsProc3::FsStat,
+            NfsArguments::FsInfo(_) => NfsProc3::FsInfo,
+            NfsArguments::PathConf(_) => NfsProc3::PathConf,
+            NfsArguments::Commit(_) => NfsProc3::Commit,
+            NfsArguments::GetAcl(_) | NfsArguments::SetAcl(_) => return None,
+        };
+        Some(proc)
+    }
 }
 
 /// Enumerates supported MOUNT protocol procedure arguments.
@@ -0,0 +1,63 @@
+//! Implements parsing for [`acl::Entry`] lists of the NFSACL protocol.
+
+use std::io::Read;
+
+use num_traits::FromPrimitive;
+
+use crate::consts::nfs_acl::{NFS_ACL_DEFAULT, NFS_ACL_MAX_ENTRIES};
+use crate::parser::primitive::{counted_len, u32};
+use crate::parser::{Error, Result};
+use crate::vfs::acl;
+
+/// Parses a single `aclent`, dropping the default ACL flag from its type.
+pub fn entry(src: &mut impl Read) -> Result<acl::Entry> {
+    let tag = acl::Tag::from_u32(u32(src)? & !NFS_ACL_DEFAULT).ok_or(Error::EnumDiscMismatch)?;
+    Ok(acl::Entry { tag, id: u32(src)?, perm: u32(src)? })
+}
+
+/// Parses an entry count followed by the array of entries.
+///
+/// The count is only meaningful when the entries themselves are omitted,
+/// so the entries of the array are returned.
+pub fn list(src: &mut impl Read) -> Result<Vec<acl::Entry>> {
+    if u32(src)? as usize > NFS_ACL_MAX_ENTRIES {
+        return Err(Error::MaxElemLimit);
+    }
+    let len = counted_len(src, NFS_ACL_MAX_ENTRIES)?;
+    (0..len).map(|_| entry(src)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::list;
+    use crate::parser::Error;
+    use crate::vfs::acl::{Entry, Tag};
+
+    #[test]
+    fn test_default_list() {
+        #[rustfmt::skip]
+        const DATA: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x10, 0x02, 0x00, 0x00, 0x03, 0xE8,
+            0x00, 0x00, 0x00, 0x06,
+        ];
+
+        let entries = list(&mut Cursor::new(DATA)).unwrap();
+
+        assert_eq!(entries, [Entry { tag: Tag::User, id: 1000, perm: 6 }]);
+    }
+
+    #[test]
+    fn test_unknown_tag() {
+        #[rustfmt::skip]
+        const DATA: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x06,
+        ];
+
+        assert!(matches!(list(&mut Cursor::new(DATA)), Err(Error::EnumDiscMismatch)));
+    }
+}
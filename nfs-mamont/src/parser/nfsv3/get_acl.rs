@@ -0,0 +1,13 @@
+//! Implements parsing for [`get_acl::Args`] structure.
+
+use std::io::Read;
+
+use crate::parser::nfsv3::file;
+use crate::parser::primitive::u32;
+use crate::parser::Result;
+use crate::vfs::{acl, get_acl};
+
+/// Parses the arguments for an NFSACL `GETACL` operation from the provided `Read` source.
+pub fn args(src: &mut impl Read) -> Result<get_acl::Args> {
+    Ok(get_acl::Args { file: file::handle(src)?, mask: acl::Mask::from_wire(u32(src)?) })
+}
@@ -1,11 +1,13 @@
 //! Implements [`crate::vfs`] interfaces arguments parsing.
 
 pub mod access;
+pub mod acl;
 pub mod commit;
 pub mod create;
 pub mod file;
 pub mod fs_info;
 pub mod fs_stat;
+pub mod get_acl;
 pub mod get_attr;
 pub mod link;
 pub mod lookup;
@@ -19,6 +21,7 @@ pub mod read_link;
 pub mod remove;
 pub mod rename;
 pub mod rm_dir;
+pub mod set_acl;
 pub mod set_attr;
 pub mod symlink;
 pub mod write;
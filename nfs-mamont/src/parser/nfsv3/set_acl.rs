@@ -0,0 +1,59 @@
+//! Implements parsing for [`set_acl::Args`] structure.
+
+use std::io::Read;
+
+use crate::parser::nfsv3::{acl, file};
+use crate::parser::primitive::u32;
+use crate::parser::Result;
+use crate::vfs::{self, set_acl};
+
+/// Parses the arguments for an NFSACL `SETACL` operation from the provided `Read` source.
+pub fn args(src: &mut impl Read) -> Result<set_acl::Args> {
+    Ok(set_acl::Args {
+        file: file::handle(src)?,
+        mask: vfs::acl::Mask::from_wire(u32(src)?),
+        access: acl::list(src)?,
+        default: acl::list(src)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::args;
+    use crate::serializer::client::arguments::nfsv3::set_acl::set_acl_args;
+    use crate::vfs::acl::{Entry, Mask, Tag};
+    use crate::vfs::{file, set_acl};
+
+    #[test]
+    fn test_set_acl_round_trip() {
+        let access = vec![
+            Entry { tag: Tag::UserObj, id: 0, perm: 0o6 },
+            Entry { tag: Tag::User, id: 1000, perm: 0o6 },
+            Entry { tag: Tag::GroupObj, id: 0, perm: 0o4 },
+            Entry { tag: Tag::Mask, id: 0, perm: 0o6 },
+            Entry { tag: Tag::Other, id: 0, perm: 0o4 },
+        ];
+        let mut data = Vec::new();
+        set_acl_args(
+            &mut data,
+            set_acl::Args {
+                file: file::Handle([1, 2, 3, 4, 5, 6, 7, 8]),
+                mask: Mask::from_wire(Mask::ACL | Mask::DEFAULT_ACL),
+                access: access.clone(),
+                default: access[..3].to_vec(),
+            },
+        )
+        .unwrap();
+
+        let mut src = Cursor::new(data.as_slice());
+        let args = args(&mut src).unwrap();
+
+        assert_eq!(src.position() as usize, data.len());
+        assert_eq!(args.file.0, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(args.mask.bits(), Mask::ACL | Mask::DEFAULT_ACL);
+        assert_eq!(args.access, access);
+        assert_eq!(args.default, access[..3]);
+    }
+}
@@ -4,9 +4,10 @@
 //! according to RFC 5531 (RPC) and RFC 1813 (NFSv3). It handles:
 //!
 //! - RPC message framing and headers
-//! - Authentication (currently only AUTH_NONE)
+//! - Authentication (AUTH_NONE and AUTH_SYS; RPCSEC_GSS is decoded and rejected)
 //! - NFSv3 procedure parsing (all 22 procedures)
 //! - MOUNT protocol procedure parsing
+//! - NFSACL procedure parsing
 //! - NLM procedure parsing
 //! - Error handling and message discarding on protocol errors
 //!
@@ -14,37 +15,37 @@
 //! supporting retry logic for parsing operations that may need additional data.
 
 use std::cmp::min;
+use std::future::Future;
 use std::io::{self, ErrorKind};
 use std::num::NonZeroUsize;
+use std::pin::pin;
 use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
 
 use tokio::io::AsyncRead;
 use tracing::{debug, error, warn};
 
-use crate::allocator::{Allocator, Buffer};
+use crate::allocator::{Allocator, Buffer, Impl, Slice};
 use crate::consts::mount::{
     MOUNT_DUMP, MOUNT_EXPORT, MOUNT_MNT, MOUNT_NULL, MOUNT_PROGRAM, MOUNT_UMNT, MOUNT_UMNTALL,
-    MOUNT_VERSION,
-};
-use crate::consts::nfsv3::{
-    ACCESS, COMMIT, CREATE, FSINFO, FSSTAT, GETATTR, LINK, LOOKUP, MKDIR, MKNOD, NFS_PROGRAM,
-    NFS_VERSION, NULL, PATHCONF, READ, READDIR, READDIRPLUS, READLINK, REMOVE, RENAME, RMDIR,
-    SETATTR, SYMLINK, WRITE,
 };
+use crate::consts::nfs_acl::{ACLPROC3_GETACL, ACLPROC3_NULL, ACLPROC3_SETACL, NFS_ACL_PROGRAM};
+use crate::consts::nfsv3::{NfsProc3, NFS_PROGRAM};
 use crate::consts::nlm::{
     NLMPROC4_CANCEL, NLMPROC4_LOCK, NLMPROC4_NULL, NLMPROC4_TEST, NLMPROC4_UNLOCK, NLM_PROGRAM,
-    NLM_VERSION,
 };
 use crate::parser::mount::mnt::mount;
 use crate::parser::mount::umnt::unmount;
 use crate::parser::nfsv3::{
-    access, commit, create, fs_info, fs_stat, get_attr, link, lookup, mk_dir, mk_node, path_conf,
-    read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_attr, symlink, write,
+    access, commit, create, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir, mk_node,
+    path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl, set_attr,
+    symlink, write,
 };
 use crate::parser::nlm::{cancel::cancel, lock::lock, test::test, unlock::unlock};
-use crate::parser::primitive::{u32, u32_as_usize, ALIGNMENT};
+use crate::parser::primitive::{self, u32, ALIGNMENT};
 use crate::parser::read_buffer::CountBuffer;
-use crate::parser::rpc::{auth, RpcMessage};
+use crate::parser::registry::PROGRAMS;
+use crate::parser::rpc::{auth, auth_sys, gss_cred, RpcMessage};
 use crate::parser::{
     proc_nested_errors, ArgWrapper, Error, ErrorWrapper, MountArgWrapper, MountArguments,
     NfsArgWrapper, NfsArguments, NlmArguments, ProcArguments, Result, RpcHeader,
@@ -77,6 +78,8 @@ pub struct RpcParser<A: Allocator, S: AsyncRead + Unpin> {
     buffer: CountBuffer<S>,
     last: bool,
     current_frame_size: usize,
+    /// Bytes of all messages finished so far, record marks included.
+    parsed_bytes: u64,
 }
 
 impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
@@ -96,6 +99,7 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
             buffer: CountBuffer::new(DEFAULT_SIZE, socket),
             last: false,
             current_frame_size: 0,
+            parsed_bytes: 0,
         }
     }
 
@@ -117,9 +121,22 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
             buffer: CountBuffer::new(size, socket),
             last: false,
             current_frame_size: 0,
+            parsed_bytes: 0,
         }
     }
 
+    /// Makes the parser check the fields of every message against `limits`.
+    pub fn with_limits(mut self, limits: primitive::Limits) -> Self {
+        self.buffer.set_limits(limits);
+        self
+    }
+
+    /// Returns the number of bytes of all messages parsed or discarded so far,
+    /// record marks included.
+    pub fn parsed_bytes(&self) -> u64 {
+        self.parsed_bytes
+    }
+
     /// Reads and parses the RPC message header.
     ///
     /// The message header contains:
@@ -208,6 +225,10 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
     /// NFS clients commonly send AUTH_SYS credentials, so both AUTH_NONE and
     /// AUTH_SYS are accepted for credentials. Verifier must remain AUTH_NONE.
     ///
+    /// The credential and the verifier are both read before either is checked, so a
+    /// rejected call leaves the parser at its procedure arguments, from where the rest
+    /// of the frame is discarded.
+    ///
     /// # Returns
     ///
     /// Returns a pair of [`OpaqueAuth`] if authentication succeeds, or an error
@@ -215,70 +236,172 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
     async fn parse_authentication(&mut self) -> Result<(OpaqueAuth, OpaqueAuth)> {
         let cred = self.buffer.parse_with_retry(auth).await?;
         let verf = self.buffer.parse_with_retry(auth).await?;
-        let cred_ok = match cred.flavor {
-            AuthFlavor::None => cred.body.is_empty(),
+        if matches!(cred.flavor(), AuthFlavor::RpcSecGss) {
+            return Err(Self::reject_gss_cred(&cred));
+        }
+        if matches!(cred.flavor(), AuthFlavor::Sys) {
+            match auth_sys(&mut cred.body()) {
+                Ok(params) => debug!(
+                    stamp=%params.stamp,
+                    machine_name=%params.machine_name,
+                    uid=%params.uid,
+                    gid=%params.gid,
+                    gids_len=%params.gids.len(),
+                    "rpc auth: AUTH_SYS credential",
+                ),
+                Err(err) => {
+                    error!(error=?err, "rpc auth reject: malformed AUTH_SYS credential");
+                    return Err(Error::Auth(AuthStat::BadCred));
+                }
+            }
+        }
+        let cred_ok = match cred.flavor() {
+            AuthFlavor::None => cred.body().is_empty(),
             AuthFlavor::Sys => true,
             _ => false,
         };
         if !cred_ok {
             error!(
-                cred_flavor=?cred.flavor,
-                cred_len=%cred.body.len(),
+                cred_flavor=?cred.flavor(),
+                cred_len=%cred.body().len(),
                 "rpc auth reject: unsupported credential flavor",
             );
             return Err(Error::Auth(AuthStat::BadCred));
         }
-        if !matches!(verf.flavor, AuthFlavor::None) || !verf.body.is_empty() {
+        if !Self::verifier_ok(cred.flavor(), &verf) {
             error!(
-                verf_flavor=?verf.flavor,
-                verf_len=%verf.body.len(),
+                verf_flavor=?verf.flavor(),
+                verf_len=%verf.body().len(),
                 "rpc auth reject: invalid verifier",
             );
             return Err(Error::Auth(AuthStat::BadVerf));
         }
         debug!(
-            cred_flavor=?cred.flavor,
-            cred_len=%cred.body.len(),
-            verf_flavor=?verf.flavor,
-            verf_len=%verf.body.len(),
+            cred_flavor=?cred.flavor(),
+            cred_len=%cred.body().len(),
+            verf_flavor=?verf.flavor(),
+            verf_len=%verf.body().len(),
             "rpc auth accepted",
         );
         Ok((cred, verf))
     }
 
+    /// Returns `true` if `verf` is the verifier a call with a `cred_flavor` credential
+    /// must carry: an empty AUTH_NONE one for both AUTH_NONE and AUTH_SYS (RFC 5531 §A).
+    fn verifier_ok(cred_flavor: &AuthFlavor, verf: &OpaqueAuth) -> bool {
+        match cred_flavor {
+            AuthFlavor::None | AuthFlavor::Sys => {
+                matches!(verf.flavor(), AuthFlavor::None) && verf.body().is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// Decodes an RPCSEC_GSS credential and returns the error to reject it with.
+    ///
+    /// GSS contexts are not supported, so well-formed credentials are rejected with
+    /// [`AuthStat::RejectedCred`], letting the client fall back to another flavor.
+    fn reject_gss_cred(cred: &OpaqueAuth) -> Error {
+        match gss_cred(&mut cred.body()) {
+            Ok(gss) => {
+                error!(
+                    gss_version=%gss.version,
+                    gss_proc=?gss.proc,
+                    gss_service=?gss.service,
+                    seq_num=%gss.seq_num,
+                    context_len=%gss.handle.len(),
+                    "rpc auth reject: RPCSEC_GSS is not supported",
+                );
+                Error::Auth(AuthStat::RejectedCred)
+            }
+            Err(err) => {
+                error!(error=?err, "rpc auth reject: malformed RPCSEC_GSS credential");
+                Error::Auth(AuthStat::BadCred)
+            }
+        }
+    }
+
     /// Parses NFSv3 procedure arguments from the current frame.
     async fn parse_nfs_proc(&mut self, procedure: u32) -> Result<NfsArguments<A::Buffer>> {
+        let procedure = NfsProc3::try_from(procedure).map_err(|_| Error::ProcedureMismatch)?;
         let args = match procedure {
-            NULL => NfsArguments::Null,
-            GETATTR => NfsArguments::GetAttr(self.buffer.parse_with_retry(get_attr::args).await?),
-            SETATTR => NfsArguments::SetAttr(self.buffer.parse_with_retry(set_attr::args).await?),
-            LOOKUP => NfsArguments::LookUp(self.buffer.parse_with_retry(lookup::args).await?),
-            ACCESS => NfsArguments::Access(self.buffer.parse_with_retry(access::args).await?),
-            READLINK => {
+            NfsProc3::Null => NfsArguments::Null,
+            NfsProc3::GetAttr => {
+                NfsArguments::GetAttr(self.buffer.parse_with_retry(get_attr::args).await?)
+            }
+            NfsProc3::SetAttr => {
+                NfsArguments::SetAttr(self.buffer.parse_with_retry(set_attr::args).await?)
+            }
+            NfsProc3::Lookup => {
+                NfsArguments::LookUp(self.buffer.parse_with_retry(lookup::args).await?)
+            }
+            NfsProc3::Access => {
+                NfsArguments::Access(self.buffer.parse_with_retry(access::args).await?)
+            }
+            NfsProc3::ReadLink => {
                 NfsArguments::ReadLink(self.buffer.parse_with_retry(read_link::args).await?)
             }
-            READ => NfsArguments::Read(self.buffer.parse_with_retry(read::args).await?),
-            WRITE => {
-                NfsArguments::Write(adapter_for_write(&self.allocator, &mut self.buffer).await?)
-            }
-            CREATE => NfsArguments::Create(self.buffer.parse_with_retry(create::args).await?),
-            MKDIR => NfsArguments::MkDir(self.buffer.parse_with_retry(mk_dir::args).await?),
-            SYMLINK => NfsArguments::SymLink(self.buffer.parse_with_retry(symlink::args).await?),
-            MKNOD => NfsArguments::MkNod(self.buffer.parse_with_retry(mk_node::args).await?),
-            REMOVE => NfsArguments::Remove(self.buffer.parse_with_retry(remove::args).await?),
-            RMDIR => NfsArguments::RmDir(self.buffer.parse_with_retry(rm_dir::args).await?),
-            RENAME => NfsArguments::Rename(self.buffer.parse_with_retry(rename::args).await?),
-            LINK => NfsArguments::Link(self.buffer.parse_with_retry(link::args).await?),
-            READDIR => NfsArguments::ReadDir(self.buffer.parse_with_retry(read_dir::args).await?),
-            READDIRPLUS => {
+            NfsProc3::Read => NfsArguments::Read(self.buffer.parse_with_retry(read::args).await?),
+            NfsProc3::Write => {
+                let frame_end = self.current_frame_size + RMS_HEADER_SIZE;
+                NfsArguments::Write(
+                    adapter_for_write(&self.allocator, &mut self.buffer, frame_end).await?,
+                )
+            }
+            NfsProc3::Create => {
+                NfsArguments::Create(self.buffer.parse_with_retry(create::args).await?)
+            }
+            NfsProc3::MkDir => {
+                NfsArguments::MkDir(self.buffer.parse_with_retry(mk_dir::args).await?)
+            }
+            NfsProc3::Symlink => {
+                NfsArguments::SymLink(self.buffer.parse_with_retry(symlink::args).await?)
+            }
+            NfsProc3::MkNod => {
+                NfsArguments::MkNod(self.buffer.parse_with_retry(mk_node::args).await?)
+            }
+            NfsProc3::Remove => {
+                NfsArguments::Remove(self.buffer.parse_with_retry(remove::args).await?)
+            }
+            NfsProc3::RmDir => {
+                NfsArguments::RmDir(self.buffer.parse_with_retry(rm_dir::args).await?)
+            }
+            NfsProc3::Rename => {
+                NfsArguments::Rename(self.buffer.parse_with_retry(rename::args).await?)
+            }
+            NfsProc3::Link => NfsArguments::Link(self.buffer.parse_with_retry(link::args).await?),
+            NfsProc3::ReadDir => {
+                NfsArguments::ReadDir(self.buffer.parse_with_retry(read_dir::args).await?)
+            }
+            NfsProc3::ReadDirPlus => {
                 NfsArguments::ReadDirPlus(self.buffer.parse_with_retry(read_dir_plus::args).await?)
             }
-            FSSTAT => NfsArguments::FsStat(self.buffer.parse_with_retry(fs_stat::args).await?),
-            FSINFO => NfsArguments::FsInfo(self.buffer.parse_with_retry(fs_info::args).await?),
-            PATHCONF => {
+            NfsProc3::FsStat => {
+                NfsArguments::FsStat(self.buffer.parse_with_retry(fs_stat::args).await?)
+            }
+            NfsProc3::FsInfo => {
+                NfsArguments::FsInfo(self.buffer.parse_with_retry(fs_info::args).await?)
+            }
+            NfsProc3::PathConf => {
                 NfsArguments::PathConf(self.buffer.parse_with_retry(path_conf::args).await?)
             }
-            COMMIT => NfsArguments::Commit(self.buffer.parse_with_retry(commit::args).await?),
+            NfsProc3::Commit => {
+                NfsArguments::Commit(self.buffer.parse_with_retry(commit::args).await?)
+            }
+        };
+        Ok(args)
+    }
+
+    /// Parses NFSACL procedure arguments from the current frame.
+    async fn parse_nfs_acl_proc(&mut self, procedure: u32) -> Result<NfsArguments<A::Buffer>> {
+        let args = match procedure {
+            ACLPROC3_NULL => NfsArguments::Null,
+            ACLPROC3_GETACL => {
+                NfsArguments::GetAcl(self.buffer.parse_with_retry(get_acl::args).await?)
+            }
+            ACLPROC3_SETACL => {
+                NfsArguments::SetAcl(self.buffer.parse_with_retry(set_acl::args).await?)
+            }
             _ => return Err(Error::ProcedureMismatch),
         };
         Ok(args)
@@ -356,18 +479,24 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
     ) -> core::result::Result<ArgWrapper<A::Buffer>, ErrorWrapper> {
         let xid = match self.read_message_header().await {
             Ok(xid) => xid,
-            Err(error) => return Err(ErrorWrapper { xid: None, error }),
+            Err(error) => {
+                // nothing of the next frame arrived: the peer closed between messages
+                let at_boundary = self.buffer.total_bytes() == 0;
+                return Err(ErrorWrapper { xid: None, error: eof_error(error, at_boundary) });
+            }
         };
         let rpc_header = match self.parse_rpc_header().await {
             Ok(arg) => arg,
             Err(err) => {
-                return Err(ErrorWrapper { xid: Some(xid), error: self.match_errors(err).await })
+                let error = eof_error(self.match_errors(err).await, false);
+                return Err(ErrorWrapper { xid: Some(xid), error });
             }
         };
         let proc = match self.parse_next_message_with_header(&rpc_header).await {
             Ok(arg) => arg,
             Err(err) => {
-                return Err(ErrorWrapper { xid: Some(xid), error: self.match_errors(err).await })
+                let error = eof_error(self.match_errors(err).await, false);
+                return Err(ErrorWrapper { xid: Some(xid), error });
             }
         };
 
@@ -411,6 +540,11 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
                 let args = self.parse_nfs_message_with_header(head).await?;
                 Ok(ProcArguments::Nfs3(Box::new(args)))
             }
+            NFS_ACL_PROGRAM => {
+                PROGRAMS.check(head.program, head.version, head.procedure)?;
+                let args = self.parse_nfs_acl_proc(head.procedure).await?;
+                Ok(ProcArguments::Nfs3(Box::new(args)))
+            }
             MOUNT_PROGRAM => {
                 let args = self.parse_mount_message_with_header(head).await?;
                 Ok(ProcArguments::Mount(Box::new(args)))
@@ -419,8 +553,9 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
                 let args = self.parse_nlm_message_with_header(head).await?;
                 Ok(ProcArguments::Nlm4(Box::new(args)))
             }
-            _ => {
-                warn!(program = head.program, "rpc parse reject: unknown program");
+            program => {
+                PROGRAMS.check(program, head.version, head.procedure)?;
+                warn!(program, "rpc parse reject: registered program has no parser");
                 Err(Error::ProgramMismatch)
             }
         }
@@ -438,17 +573,7 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
             );
             return Err(Error::ProgramMismatch);
         }
-        if head.version != NFS_VERSION {
-            error!(
-                got = head.version,
-                expected = NFS_VERSION,
-                "rpc parse reject: nfs version mismatch",
-            );
-            return Err(Error::ProgramVersionMismatch(VersionMismatch {
-                low: NFS_VERSION,
-                high: NFS_VERSION,
-            }));
-        }
+        PROGRAMS.check(head.program, head.version, head.procedure)?;
         self.parse_nfs_proc(head.procedure).await
     }
 
@@ -464,17 +589,7 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
             );
             return Err(Error::ProgramMismatch);
         }
-        if head.version != MOUNT_VERSION {
-            error!(
-                got = head.version,
-                expected = MOUNT_VERSION,
-                "rpc parse reject: mount version mismatch",
-            );
-            return Err(Error::ProgramVersionMismatch(VersionMismatch {
-                low: MOUNT_VERSION,
-                high: MOUNT_VERSION,
-            }));
-        }
+        PROGRAMS.check(head.program, head.version, head.procedure)?;
         self.parse_mount_proc(head.procedure).await
     }
 
@@ -487,17 +602,7 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
             );
             return Err(Error::ProgramMismatch);
         }
-        if head.version != NLM_VERSION {
-            error!(
-                got = head.version,
-                expected = NLM_VERSION,
-                "rpc parse reject: NLM version mismatch",
-            );
-            return Err(Error::ProgramVersionMismatch(VersionMismatch {
-                low: NLM_VERSION,
-                high: NLM_VERSION,
-            }));
-        }
+        PROGRAMS.check(head.program, head.version, head.procedure)?;
         self.parse_nlm_proc(head.procedure).await
     }
 
@@ -528,6 +633,7 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
             )));
         }
 
+        self.parsed_bytes += self.buffer.total_bytes() as u64;
         self.buffer.clean();
         self.current_frame_size = 0;
         self.last = false;
@@ -553,6 +659,8 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
         | Error::ProcedureMismatch
         | Error::Auth(_)
         | Error::MessageTypeMismatch
+        | Error::IncorrectPadding
+        | Error::MaxElemLimit
         | Error::ProgramVersionMismatch(_) = &error
         {
             proc_nested_errors(error, self.discard_current_message()).await
@@ -587,6 +695,39 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
     }
 }
 
+/// Replaces an end-of-stream I/O error with [`Error::ConnectionClosed`] if it hit
+/// `at_boundary` of two frames, or with [`Error::TruncatedMessage`] otherwise.
+fn eof_error(error: Error, at_boundary: bool) -> Error {
+    match error {
+        Error::IO(err) if err.kind() == ErrorKind::UnexpectedEof => {
+            if at_boundary {
+                Error::ConnectionClosed
+            } else {
+                Error::TruncatedMessage
+            }
+        }
+        error => error,
+    }
+}
+
+/// Parses one complete RPC call, record mark included, from `bytes`.
+///
+/// This is the socket-free entry point of the parser, meant for fuzzing and tests:
+/// any input produces either the arguments or an error, and never panics.
+pub fn parse_request(bytes: &[u8]) -> core::result::Result<ArgWrapper<Slice>, Error> {
+    // A single buffer as large as the input holds any WRITE payload the input can carry.
+    let capacity = NonZeroUsize::new(bytes.len()).unwrap_or(NonZeroUsize::MIN);
+    let allocator = Arc::new(Impl::new(capacity, NonZeroUsize::MIN));
+    let mut parser = RpcParser::new(bytes, allocator);
+
+    let message = pin!(parser.next_message());
+    match message.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(result) => result.map_err(|wrapper| wrapper.error),
+        // Neither a slice nor a dedicated allocator ever has to wait.
+        Poll::Pending => Err(Error::IO(io::Error::from(ErrorKind::WouldBlock))),
+    }
+}
+
 /// Special adapter for parsing WRITE procedure arguments.
 ///
 /// The WRITE procedure requires special handling because it includes variable-length
@@ -600,16 +741,23 @@ impl<A: Allocator, S: AsyncRead + Unpin> RpcParser<A, S> {
 ///
 /// * `alloc` - The allocator to use for allocating the write data buffer
 /// * `buffer` - The buffer to read from
+/// * `frame_end` - Number of bytes in the stream up to the end of the current frame
 ///
 /// # Returns
 ///
 /// Returns the parsed [`vfs::write::Args`] with allocated data, or an error if:
 /// - Parsing fails
-/// - Memory allocation fails
-/// - Reading the data fails
+/// - The declared payload length exceeds the rest of the frame or
+///   [`primitive::Limits::max_counted_len`]
+/// - Reading the data fails, or fills fewer than the declared bytes, in which case
+///   [`Error::TruncatedMessage`] is returned
+///
+/// A zero-length payload is passed on without allocating. If the payload does not fit into
+/// the allocator, it is discarded and the arguments are returned with an empty buffer.
 async fn adapter_for_write<A, S>(
     alloc: &Arc<A>,
     buffer: &mut CountBuffer<S>,
+    frame_end: usize,
 ) -> Result<vfs::write::Args<A::Buffer>>
 where
     A: Allocator,
@@ -617,26 +765,53 @@ where
 {
     // Parse arguments for WRITE procedure.
     let part_arg = buffer.parse_with_retry(write::args).await?;
-    let size = buffer.parse_with_retry(u32_as_usize).await?;
+    let size = buffer.parse_with_retry(|src| primitive::counted_len(src, usize::MAX)).await?;
+    // Reject lengths the frame cannot hold before allocating anything for them.
+    if size > frame_end.saturating_sub(buffer.total_bytes()) {
+        return Err(Error::MaxElemLimit);
+    }
 
-    // Attempt allocation with the given size, or fallback to NonZeroUsize::MIN.
-    let non_zero_size = NonZeroUsize::new(size).unwrap_or(NonZeroUsize::MIN);
-    let mut buffer_data = alloc.allocate(non_zero_size).await.ok_or_else(|| {
-        Error::IO(io::Error::new(ErrorKind::OutOfMemory, "cannot allocate memory"))
-    })?;
+    // A zero-length WRITE carries neither data nor padding, and is passed on without
+    // allocating: the backend still replies with the file attributes and its verifier.
+    let Some(non_zero_size) = NonZeroUsize::new(size) else {
+        return Ok(vfs::write::Args {
+            file: part_arg.file,
+            offset: part_arg.offset,
+            size: part_arg.size,
+            stable: part_arg.stable,
+            data: A::Buffer::empty(),
+        });
+    };
 
     // Calculate necessary padding to maintain ALIGNMENT
     let padding = (ALIGNMENT - (size % ALIGNMENT)) % ALIGNMENT;
 
+    let Some(mut buffer_data) = alloc.allocate(non_zero_size).await else {
+        // Payload exceeds allocator capacity: skip it to keep the stream aligned and
+        // pass an empty buffer on, so the call is rejected instead of the connection.
+        buffer.discard_bytes(size + padding).await.map_err(Error::IO)?;
+        return Ok(vfs::write::Args {
+            file: part_arg.file,
+            offset: part_arg.offset,
+            size: part_arg.size,
+            stable: part_arg.stable,
+            data: A::Buffer::empty(),
+        });
+    };
+
     // Read synchronously what is available, then finish asynchronously if needed.
-    let bytes_read_sync = read_in_slice_sync(buffer, &mut buffer_data, size)?;
-    if bytes_read_sync < size {
-        read_in_slice_async(buffer, &mut buffer_data, bytes_read_sync, size - bytes_read_sync)
-            .await?;
+    let mut bytes_read = read_in_slice_sync(buffer, &mut buffer_data, size)?;
+    if bytes_read < size {
+        bytes_read +=
+            read_in_slice_async(buffer, &mut buffer_data, bytes_read, size - bytes_read).await?;
+    }
+    // Never pass on a payload with a zero-filled tail: the backend would write it as data.
+    if bytes_read != size {
+        return Err(Error::TruncatedMessage);
     }
 
-    // Discard any trailing padding bytes after the data.
-    buffer.discard_bytes(padding).await.map_err(Error::IO)?;
+    // Skip trailing padding bytes after the data, validating them like any other field.
+    buffer.parse_with_retry(|src| primitive::padding(src, size)).await?;
     Ok(vfs::write::Args {
         file: part_arg.file,
         offset: part_arg.offset,
@@ -1,6 +1,7 @@
 //! Primitive XDR data type parsing utilities.
 
-use std::io::Read;
+use std::cell::Cell;
+use std::io::{self, ErrorKind, Read};
 
 use byteorder::{BigEndian, ReadBytesExt};
 use num_traits::{FromPrimitive, ToPrimitive};
@@ -10,12 +11,70 @@ use super::{Error, Result};
 /// The XDR alignment in bytes.
 pub const ALIGNMENT: usize = 4;
 
+/// Default cap on the declared length of a single counted field.
+pub const DEFAULT_MAX_COUNTED_LEN: usize = 8 * 1024 * 1024;
+
+/// Checks the decoders apply to the fields of incoming calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Rejects non-zero padding bytes, as RFC 4506 requires.
+    pub strict_padding: bool,
+    /// Hard cap on the declared length of any single counted field (opaque data,
+    /// strings, WRITE payloads), applied on top of per-field limits.
+    pub max_counted_len: usize,
+}
+
+impl Limits {
+    const DEFAULT: Self = Self { strict_padding: true, max_counted_len: DEFAULT_MAX_COUNTED_LEN };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+thread_local! {
+    /// Limits of the parse running on this thread, see [`with_limits`].
+    static LIMITS: Cell<Limits> = const { Cell::new(Limits::DEFAULT) };
+}
+
+/// Runs the synchronous parse `f` with [`padding`] and [`counted_len`] applying `limits`.
+///
+/// The limits only hold for the duration of `f`, so connections parsed on the same
+/// thread each keep their own; outside of it the defaults apply.
+pub fn with_limits<T>(limits: Limits, f: impl FnOnce() -> T) -> T {
+    /// Puts the previous limits back, even if `f` unwinds.
+    struct Restore(Limits);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LIMITS.set(self.0);
+        }
+    }
+
+    let _restore = Restore(LIMITS.replace(limits));
+    f()
+}
+
 /// Reads and discards padding bytes to ensure XDR alignment.
+///
+/// Under [`Limits::strict_padding`] non-zero padding bytes produce [`Error::IncorrectPadding`].
 #[inline]
 pub fn padding(src: &mut impl Read, n: usize) -> Result<()> {
+    padding_with_mode(src, n, LIMITS.get().strict_padding)
+}
+
+/// Reads and discards padding bytes, validating them only if `strict` is set.
+#[inline]
+pub fn padding_with_mode(src: &mut impl Read, n: usize, strict: bool) -> Result<()> {
     let mut buf = [0u8; ALIGNMENT];
     let padding = (ALIGNMENT - n % ALIGNMENT) % ALIGNMENT;
-    src.read_exact(&mut buf[..padding]).map_err(Error::IO)
+    src.read_exact(&mut buf[..padding]).map_err(Error::IO)?;
+    if strict && buf[..padding].iter().any(|&byte| byte != 0) {
+        return Err(Error::IncorrectPadding);
+    }
+    Ok(())
 }
 
 /// Parses a `u8` (byte) from the `Read` source.
@@ -74,26 +133,45 @@ pub fn array<const N: usize>(src: &mut impl Read) -> Result<[u8; N]> {
     Ok(buf)
 }
 
+/// Parses the `u32` length of a counted field.
+///
+/// Fails with [`Error::MaxElemLimit`] if the length exceeds `max_size` or
+/// [`Limits::max_counted_len`], before anything is allocated for the field.
+#[inline]
+pub fn counted_len(src: &mut impl Read, max_size: usize) -> Result<usize> {
+    let size = u32_as_usize(src)?;
+    if size > max_size.min(LIMITS.get().max_counted_len) {
+        return Err(Error::MaxElemLimit);
+    }
+    Ok(size)
+}
+
+/// Reads exactly `size` bytes.
+///
+/// The vector grows with the data actually read, so a length larger than the data
+/// in the source fails with [`ErrorKind::UnexpectedEof`] instead of allocating `size` bytes upfront.
+#[inline]
+fn bytes(src: &mut impl Read, size: usize) -> Result<Vec<u8>> {
+    let mut vec = Vec::new();
+    src.take(size as u64).read_to_end(&mut vec).map_err(Error::IO)?;
+    if vec.len() != size {
+        return Err(Error::IO(io::Error::from(ErrorKind::UnexpectedEof)));
+    }
+    Ok(vec)
+}
+
 /// Parses a variable-length vector of bytes (opaque data) from the `Read` source.
 /// The vector's length is encoded as a `u32` preceding the data.
 #[inline]
 pub fn vector(src: &mut impl Read) -> Result<Vec<u8>> {
-    let size = u32_as_usize(src)?;
-    let mut vec = vec![0u8; size];
-    src.read_exact(vec.as_mut_slice()).map_err(Error::IO)?;
-    padding(src, size)?;
-    Ok(vec)
+    vec_max_size(src, usize::MAX)
 }
 
 /// Parses a variable-length vector of bytes with a maximum allowed size.
 #[inline]
 pub fn vec_max_size(src: &mut impl Read, max_size: usize) -> Result<Vec<u8>> {
-    let size = u32_as_usize(src)?;
-    if size > max_size {
-        return Err(Error::MaxElemLimit);
-    }
-    let mut vec = vec![0u8; size];
-    src.read_exact(vec.as_mut_slice()).map_err(Error::IO)?;
+    let size = counted_len(src, max_size)?;
+    let vec = bytes(src, size)?;
     padding(src, size)?;
     Ok(vec)
 }
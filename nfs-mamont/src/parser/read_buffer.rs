@@ -8,6 +8,10 @@
 //! and provides a [`Read`] interface for synchronous parsing functions. It uses
 //! two internal buffers to allow reading new data while
 //! still being able to retry parsing from a previous position if needed.
+//!
+//! Frames are parsed in place: data is never moved inside a buffer. Pipelined frames
+//! already buffered are parsed one after another without touching the socket, and a
+//! buffer is only reset once parsing has moved past all of its data.
 
 use std::cmp::min;
 use std::io;
@@ -15,6 +19,7 @@ use std::io::{ErrorKind, Read};
 
 use tokio::io::{AsyncRead, AsyncReadExt};
 
+use crate::parser::primitive::{self, Limits};
 use crate::parser::{Error, Result};
 
 /// A buffered reader that wraps an async stream and provides synchronous reading
@@ -36,6 +41,7 @@ pub struct CountBuffer<S: AsyncRead + Unpin> {
     retry_mode: bool,
     socket: S,
     total_bytes: usize,
+    limits: Limits,
 }
 
 impl<S: AsyncRead + Unpin> CountBuffer<S> {
@@ -53,9 +59,15 @@ impl<S: AsyncRead + Unpin> CountBuffer<S> {
             retry_mode: false,
             socket,
             total_bytes: 0,
+            limits: Limits::default(),
         }
     }
 
+    /// Makes the parsing functions run by [`Self::parse_with_retry`] apply `limits`.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
     /// Fills the write buffer by reading data from the socket.
     ///
     /// This method reads available data from the async stream into the current
@@ -106,7 +118,7 @@ impl<S: AsyncRead + Unpin> CountBuffer<S> {
         // there is no need to check if we reach end of buffer while appending data to buffer since we have buffer, that would
         // definitely be enough to read what we are planning
         loop {
-            match caller(self) {
+            match primitive::with_limits(self.limits, || caller(self)) {
                 Err(Error::IO(err)) if err.kind() == ErrorKind::UnexpectedEof => {
                     self.retry_mode = true;
                     // called whenever we need to read more data
@@ -0,0 +1,107 @@
+//! Registry of RPC programs served by the parser.
+//!
+//! Program and version checks of incoming calls consult [`PROGRAMS`], so serving
+//! another program or version only takes a new [`ProgramInfo`] entry.
+
+use tracing::{error, warn};
+
+use crate::consts::mount::{MOUNT_PROGRAM, MOUNT_VERSION_HIGH, MOUNT_VERSION_LOW};
+use crate::consts::nfs_acl::{NFS_ACL_PROGRAM, NFS_ACL_VERSION_HIGH, NFS_ACL_VERSION_LOW};
+use crate::consts::nfsv3::{NFS_PROGRAM, NFS_VERSION_HIGH, NFS_VERSION_LOW};
+use crate::consts::nlm::{NLM_PROGRAM, NLM_VERSION_HIGH, NLM_VERSION_LOW};
+use crate::parser::Result;
+use crate::rpc::{Error, VersionMismatch};
+
+/// Versions and procedures of a registered RPC program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramInfo {
+    /// Program number.
+    pub program: u32,
+    /// Lowest supported version.
+    pub low_version: u32,
+    /// Highest supported version.
+    pub high_version: u32,
+    /// Number of procedures; procedures are numbered from `0` to `proc_count - 1`.
+    pub proc_count: u32,
+}
+
+impl ProgramInfo {
+    /// Returns the range of supported versions as reported in `PROG_MISMATCH` replies.
+    pub fn versions(&self) -> VersionMismatch {
+        VersionMismatch { low: self.low_version, high: self.high_version }
+    }
+}
+
+/// Table of RPC programs, looked up by program number.
+#[derive(Debug)]
+pub struct ProgramRegistry {
+    programs: &'static [ProgramInfo],
+}
+
+/// Programs served by this crate.
+pub const PROGRAMS: ProgramRegistry = ProgramRegistry::new(&[
+    ProgramInfo {
+        program: NFS_PROGRAM,
+        low_version: NFS_VERSION_LOW,
+        high_version: NFS_VERSION_HIGH,
+        proc_count: 22,
+    },
+    ProgramInfo {
+        program: MOUNT_PROGRAM,
+        low_version: MOUNT_VERSION_LOW,
+        high_version: MOUNT_VERSION_HIGH,
+        proc_count: 6,
+    },
+    ProgramInfo {
+        program: NLM_PROGRAM,
+        low_version: NLM_VERSION_LOW,
+        high_version: NLM_VERSION_HIGH,
+        proc_count: 5,
+    },
+    ProgramInfo {
+        program: NFS_ACL_PROGRAM,
+        low_version: NFS_ACL_VERSION_LOW,
+        high_version: NFS_ACL_VERSION_HIGH,
+        proc_count: 3,
+    },
+]);
+
+impl ProgramRegistry {
+    /// Creates a registry over `programs`.
+    pub const fn new(programs: &'static [ProgramInfo]) -> Self {
+        Self { programs }
+    }
+
+    /// Returns the entry of `program`, if it is registered.
+    pub fn get(&self, program: u32) -> Option<&ProgramInfo> {
+        self.programs.iter().find(|info| info.program == program)
+    }
+
+    /// Validates program, version and procedure of a call.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ProgramMismatch`] if `program` is not registered.
+    /// - [`Error::ProgramVersionMismatch`] with the registered range if `version` is outside it.
+    /// - [`Error::ProcedureMismatch`] if `procedure` is not below the procedure count.
+    pub fn check(&self, program: u32, version: u32, procedure: u32) -> Result<&ProgramInfo> {
+        let Some(info) = self.get(program) else {
+            warn!(program, "rpc parse reject: unknown program");
+            return Err(Error::ProgramMismatch);
+        };
+        if !(info.low_version..=info.high_version).contains(&version) {
+            error!(
+                program,
+                got = version,
+                low = info.low_version,
+                high = info.high_version,
+                "rpc parse reject: program version mismatch",
+            );
+            return Err(Error::ProgramVersionMismatch(info.versions()));
+        }
+        if procedure >= info.proc_count {
+            return Err(Error::ProcedureMismatch);
+        }
+        Ok(info)
+    }
+}
@@ -0,0 +1,85 @@
+//! Client-side decoding of RPC replies.
+//!
+//! This is the mirror image of [`crate::serializer::server`]: it turns bytes produced
+//! by the reply serializer back into typed values, so tests can assert on decoded
+//! replies instead of raw byte arrays.
+
+pub mod nfsv3;
+
+use std::io::Read;
+
+use crate::parser::primitive::{u32, variant};
+use crate::parser::rpc::auth;
+use crate::parser::{Error, Result};
+use crate::rpc::{
+    AcceptStat, AuthStat, OpaqueAuth, RejectedReply, ReplyBody, RpcBody, VersionMismatch,
+};
+
+/// Mask of the last fragment flag in the record marking header.
+const LAST_FRAGMENT: u32 = 0x8000_0000;
+
+/// Decoded RPC reply header, up to the start of the procedure result.
+pub struct ReplyHeader {
+    pub xid: u32,
+    pub status: ReplyStatus,
+}
+
+/// Outcome of an RPC call as reported by the server.
+pub enum ReplyStatus {
+    /// Call was accepted; for [`AcceptStat::Success`] the procedure result follows.
+    Accepted { verf: OpaqueAuth, stat: AcceptStat },
+    /// Call was accepted, but the requested program version is not supported.
+    ProgMismatch { verf: OpaqueAuth, versions: VersionMismatch },
+    /// Call was denied because of unsupported RPC version.
+    RpcMismatch(VersionMismatch),
+    /// Call was denied because of authentication failure.
+    AuthError(AuthStat),
+}
+
+/// Parses the record marking header and returns the size of the fragment.
+///
+/// Only single fragment replies are supported, as the server never emits others.
+pub fn record_mark(src: &mut impl Read) -> Result<usize> {
+    let header = u32(src)?;
+    if header & LAST_FRAGMENT == 0 {
+        return Err(Error::MessageTypeMismatch);
+    }
+    Ok((header & !LAST_FRAGMENT) as usize)
+}
+
+/// Parses [`VersionMismatch`] (`low`, `high`) pair.
+fn version_mismatch(src: &mut impl Read) -> Result<VersionMismatch> {
+    Ok(VersionMismatch { low: u32(src)?, high: u32(src)? })
+}
+
+/// Parses the RPC reply header following the record mark.
+pub fn header(src: &mut impl Read) -> Result<ReplyHeader> {
+    let xid = u32(src)?;
+    if u32(src)? != RpcBody::Reply as u32 {
+        return Err(Error::MessageTypeMismatch);
+    }
+
+    let status = match u32(src)? {
+        stat if stat == ReplyBody::MsgAccepted as u32 => {
+            let verf = auth(src)?;
+            match variant::<AcceptStat>(src)? {
+                AcceptStat::ProgMismatch => {
+                    ReplyStatus::ProgMismatch { verf, versions: version_mismatch(src)? }
+                }
+                stat => ReplyStatus::Accepted { verf, stat },
+            }
+        }
+        stat if stat == ReplyBody::MsgDenied as u32 => match u32(src)? {
+            rejected if rejected == RejectedReply::RpcMismatch as u32 => {
+                ReplyStatus::RpcMismatch(version_mismatch(src)?)
+            }
+            rejected if rejected == RejectedReply::AuthError as u32 => {
+                ReplyStatus::AuthError(variant::<AuthStat>(src)?)
+            }
+            _ => return Err(Error::EnumDiscMismatch),
+        },
+        _ => return Err(Error::EnumDiscMismatch),
+    };
+
+    Ok(ReplyHeader { xid, status })
+}
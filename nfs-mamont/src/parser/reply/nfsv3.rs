@@ -0,0 +1,383 @@
+//! Decoding of NFSv3 procedure results (`<PROC>3res` unions).
+//!
+//! Each function parses the status discriminant followed by either the
+//! `resok` or the `resfail` arm and returns it as `Result<Success, Fail>`
+//! of the matching [`crate::vfs`] procedure.
+
+use std::io::Read;
+
+use num_traits::FromPrimitive;
+
+use crate::parser::nfsv3::acl as nfs_acl;
+use crate::parser::nfsv3::file;
+use crate::parser::nfsv3::read_dir_plus::{cookie, cookie_verifier};
+use crate::parser::primitive::{array, bool, option, u32, u64, variant, vec_max_size, vector};
+use crate::parser::{Error, Result};
+use crate::vfs::{self, file::Attr, file::Handle, MAX_PATH_LEN, STATUS_OK};
+use crate::vfs::{
+    access, acl, commit, create, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir,
+    mk_node, path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl,
+    set_attr, symlink, write,
+};
+
+/// Decoded procedure result: outer [`Result`] reports malformed input,
+/// inner one is the NFS status of the call.
+pub type NfsResult<S, F> = Result<std::result::Result<S, F>>;
+
+/// Parses `nfsstat3` and dispatches into `ok` or `fail` arm of the result union.
+fn nfs_result<S: Read, T, F>(
+    src: &mut S,
+    ok: impl FnOnce(&mut S) -> Result<T>,
+    fail: impl FnOnce(&mut S, vfs::Error) -> Result<F>,
+) -> NfsResult<T, F> {
+    let status = u32(src)?;
+    if status as usize == STATUS_OK {
+        return Ok(Ok(ok(src)?));
+    }
+    let error = vfs::Error::from_u32(status).ok_or(Error::EnumDiscMismatch)?;
+    Ok(Err(fail(src, error)?))
+}
+
+/// Parses `post_op_attr`.
+fn post_op_attr(src: &mut impl Read) -> Result<Option<Attr>> {
+    option(src, |s| file::attr(s))
+}
+
+/// Parses `post_op_fh3`.
+fn post_op_fh(src: &mut impl Read) -> Result<Option<Handle>> {
+    option(src, |s| file::handle(s))
+}
+
+/// Parses `wcc_data`.
+fn wcc_data(src: &mut impl Read) -> Result<vfs::WccData> {
+    Ok(vfs::WccData { before: option(src, |s| file::wcc_attr(s))?, after: post_op_attr(src)? })
+}
+
+/// Parses `GETATTR3res`.
+pub fn get_attr(src: &mut impl Read) -> NfsResult<get_attr::Success, get_attr::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(get_attr::Success { object: file::attr(s)? }),
+        |_, error| Ok(get_attr::Fail { error }),
+    )
+}
+
+/// Parses `SETATTR3res`.
+pub fn set_attr(src: &mut impl Read) -> NfsResult<set_attr::Success, set_attr::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(set_attr::Success { wcc_data: wcc_data(s)?, ignored: set_attr::Ignored::default() }),
+        |s, error| Ok(set_attr::Fail { error, wcc_data: wcc_data(s)? }),
+    )
+}
+
+/// Parses `LOOKUP3res`.
+pub fn lookup(src: &mut impl Read) -> NfsResult<lookup::Success, lookup::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(lookup::Success {
+                file: file::handle(s)?,
+                file_attr: post_op_attr(s)?,
+                dir_attr: post_op_attr(s)?,
+            })
+        },
+        |s, error| Ok(lookup::Fail { error, dir_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `ACCESS3res`.
+pub fn access(src: &mut impl Read) -> NfsResult<access::Success, access::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(access::Success {
+                object_attr: post_op_attr(s)?,
+                access: access::Mask::from_wire(u32(s)?),
+            })
+        },
+        |s, error| Ok(access::Fail { error, object_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `READLINK3res`.
+pub fn read_link(src: &mut impl Read) -> NfsResult<read_link::Success, read_link::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(read_link::Success {
+                symlink_attr: post_op_attr(s)?,
+                data: vec_max_size(s, MAX_PATH_LEN)?,
+            })
+        },
+        |s, error| Ok(read_link::Fail { symlink_attr: post_op_attr(s)?, error }),
+    )
+}
+
+/// Parses `READ3res`, returning the opaque data as a separate byte vector.
+pub fn read(src: &mut impl Read) -> NfsResult<(read::SuccessPartial, Vec<u8>), read::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            let file_attr = post_op_attr(s)?;
+            let count = u32(s)?;
+            let eof = bool(s)?;
+            Ok((read::SuccessPartial { file_attr, count, eof }, vector(s)?))
+        },
+        |s, error| Ok(read::Fail { error, file_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `WRITE3res`.
+pub fn write(src: &mut impl Read) -> NfsResult<write::Success, write::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(write::Success {
+                file_wcc: wcc_data(s)?,
+                count: u32(s)?,
+                committed: variant::<write::StableHow>(s)?,
+                verifier: write::Verifier(array(s)?),
+            })
+        },
+        |s, error| Ok(write::Fail { error, wcc_data: wcc_data(s)? }),
+    )
+}
+
+/// Parses `CREATE3res`.
+pub fn create(src: &mut impl Read) -> NfsResult<create::Success, create::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(create::Success {
+                file: post_op_fh(s)?,
+                attr: post_op_attr(s)?,
+                wcc_data: wcc_data(s)?,
+            })
+        },
+        |s, error| Ok(create::Fail { error, wcc_data: wcc_data(s)? }),
+    )
+}
+
+/// Parses `MKDIR3res`.
+pub fn mk_dir(src: &mut impl Read) -> NfsResult<mk_dir::Success, mk_dir::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(mk_dir::Success {
+                file: post_op_fh(s)?,
+                attr: post_op_attr(s)?,
+                wcc_data: wcc_data(s)?,
+            })
+        },
+        |s, error| Ok(mk_dir::Fail { error, dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `SYMLINK3res`.
+pub fn symlink(src: &mut impl Read) -> NfsResult<symlink::Success, symlink::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(symlink::Success {
+                file: post_op_fh(s)?,
+                attr: post_op_attr(s)?,
+                wcc_data: wcc_data(s)?,
+            })
+        },
+        |s, error| Ok(symlink::Fail { error, dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `MKNOD3res`.
+pub fn mk_node(src: &mut impl Read) -> NfsResult<mk_node::Success, mk_node::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(mk_node::Success {
+                file: post_op_fh(s)?,
+                attr: post_op_attr(s)?,
+                wcc_data: wcc_data(s)?,
+            })
+        },
+        |s, error| Ok(mk_node::Fail { error, dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `REMOVE3res`.
+pub fn remove(src: &mut impl Read) -> NfsResult<remove::Success, remove::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(remove::Success { wcc_data: wcc_data(s)? }),
+        |s, error| Ok(remove::Fail { error, dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `RMDIR3res`.
+pub fn rm_dir(src: &mut impl Read) -> NfsResult<rm_dir::Success, rm_dir::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(rm_dir::Success { wcc_data: wcc_data(s)? }),
+        |s, error| Ok(rm_dir::Fail { error, dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `RENAME3res`.
+pub fn rename(src: &mut impl Read) -> NfsResult<rename::Success, rename::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(rename::Success { from_dir_wcc: wcc_data(s)?, to_dir_wcc: wcc_data(s)? }),
+        |s, error| Ok(rename::Fail { error, from_dir_wcc: wcc_data(s)?, to_dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `LINK3res`.
+pub fn link(src: &mut impl Read) -> NfsResult<link::Success, link::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(link::Success { file_attr: post_op_attr(s)?, dir_wcc: wcc_data(s)? }),
+        |s, error| Ok(link::Fail { error, file_attr: post_op_attr(s)?, dir_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `READDIR3res`.
+pub fn read_dir(src: &mut impl Read) -> NfsResult<read_dir::Success, read_dir::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            let dir_attr = post_op_attr(s)?;
+            let cookie_verifier = cookie_verifier(s)?;
+            let mut entries = Vec::new();
+            while bool(s)? {
+                entries.push(read_dir::Entry {
+                    file_id: u64(s)?,
+                    file_name: file::file_name(s)?,
+                    cookie: cookie(s)?,
+                });
+            }
+            Ok(read_dir::Success { dir_attr, cookie_verifier, entries, eof: bool(s)? })
+        },
+        |s, error| Ok(read_dir::Fail { error, dir_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `READDIRPLUS3res`.
+pub fn read_dir_plus(
+    src: &mut impl Read,
+) -> NfsResult<read_dir_plus::Success, read_dir_plus::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            let dir_attr = post_op_attr(s)?;
+            let cookie_verifier = cookie_verifier(s)?;
+            let mut entries = Vec::new();
+            while bool(s)? {
+                entries.push(read_dir_plus::Entry {
+                    file_id: u64(s)?,
+                    file_name: file::file_name(s)?,
+                    cookie: cookie(s)?,
+                    file_attr: post_op_attr(s)?,
+                    file_handle: post_op_fh(s)?,
+                });
+            }
+            Ok(read_dir_plus::Success { dir_attr, cookie_verifier, entries, eof: bool(s)? })
+        },
+        |s, error| Ok(read_dir_plus::Fail { error, dir_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `FSSTAT3res`.
+pub fn fs_stat(src: &mut impl Read) -> NfsResult<fs_stat::Success, fs_stat::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(fs_stat::Success {
+                root_attr: post_op_attr(s)?,
+                total_bytes: u64(s)?,
+                free_bytes: u64(s)?,
+                available_bytes: u64(s)?,
+                total_files: u64(s)?,
+                free_files: u64(s)?,
+                available_files: u64(s)?,
+                invarsec: u32(s)?,
+            })
+        },
+        |s, error| Ok(fs_stat::Fail { error, root_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `FSINFO3res`.
+pub fn fs_info(src: &mut impl Read) -> NfsResult<fs_info::Success, fs_info::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(fs_info::Success {
+                root_attr: post_op_attr(s)?,
+                read_max: u32(s)?,
+                read_pref: u32(s)?,
+                read_mult: u32(s)?,
+                write_max: u32(s)?,
+                write_pref: u32(s)?,
+                write_mult: u32(s)?,
+                read_dir_pref: u32(s)?,
+                max_file_size: u64(s)?,
+                time_delta: file::time(s)?,
+                properties: fs_info::Properties::from_wire(u32(s)?),
+            })
+        },
+        |s, error| Ok(fs_info::Fail { error, root_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `PATHCONF3res`.
+pub fn path_conf(src: &mut impl Read) -> NfsResult<path_conf::Success, path_conf::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(path_conf::Success {
+                file_attr: post_op_attr(s)?,
+                link_max: u32(s)?,
+                name_max: u32(s)?,
+                no_trunc: bool(s)?,
+                chown_restricted: bool(s)?,
+                case_insensitive: bool(s)?,
+                case_preserving: bool(s)?,
+            })
+        },
+        |s, error| Ok(path_conf::Fail { error, file_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `COMMIT3res`.
+pub fn commit(src: &mut impl Read) -> NfsResult<commit::Success, commit::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(commit::Success { file_wcc: wcc_data(s)?, verifier: write::Verifier(array(s)?) }),
+        |s, error| Ok(commit::Fail { error, file_wcc: wcc_data(s)? }),
+    )
+}
+
+/// Parses `GETACL3res`.
+pub fn get_acl(src: &mut impl Read) -> NfsResult<get_acl::Success, get_acl::Fail> {
+    nfs_result(
+        src,
+        |s| {
+            Ok(get_acl::Success {
+                file_attr: post_op_attr(s)?,
+                mask: acl::Mask::from_wire(u32(s)?),
+                access: nfs_acl::list(s)?,
+                default: nfs_acl::list(s)?,
+            })
+        },
+        |s, error| Ok(get_acl::Fail { error, file_attr: post_op_attr(s)? }),
+    )
+}
+
+/// Parses `SETACL3res`.
+pub fn set_acl(src: &mut impl Read) -> NfsResult<set_acl::Success, set_acl::Fail> {
+    nfs_result(
+        src,
+        |s| Ok(set_acl::Success { file_attr: post_op_attr(s)? }),
+        |s, error| Ok(set_acl::Fail { error, file_attr: post_op_attr(s)? }),
+    )
+}
@@ -1,8 +1,11 @@
 use std::io::Read;
 
-use crate::parser::primitive::{variant, vec_max_size};
+use crate::parser::primitive::{string_max_size, u32, u32_as_usize, variant, vec_max_size};
 use crate::parser::Result;
-use crate::rpc::{AuthFlavor, OpaqueAuth, MAX_AUTH_SIZE};
+use crate::rpc::{
+    AuthFlavor, AuthStat, AuthSysParams, Error, OpaqueAuth, RpcGssCred, RpcGssProc, RpcGssService,
+    AUTH_SYS_MACHINE_NAME_LEN, AUTH_SYS_MAX_GIDS, MAX_AUTH_SIZE, RPCSEC_GSS_VERSION,
+};
 
 #[derive(Debug)]
 pub struct RpcMessage {
@@ -14,5 +17,36 @@ pub struct RpcMessage {
 }
 
 pub fn auth(src: &mut impl Read) -> Result<OpaqueAuth> {
-    Ok(OpaqueAuth { flavor: variant::<AuthFlavor>(src)?, body: vec_max_size(src, MAX_AUTH_SIZE)? })
+    OpaqueAuth::new(variant::<AuthFlavor>(src)?, vec_max_size(src, MAX_AUTH_SIZE)?)
+}
+
+/// Parses the body of an AUTH_SYS credential.
+pub fn auth_sys(src: &mut impl Read) -> Result<AuthSysParams> {
+    let stamp = u32(src)?;
+    let machine_name = string_max_size(src, AUTH_SYS_MACHINE_NAME_LEN)?;
+    let uid = u32(src)?;
+    let gid = u32(src)?;
+    let count = u32_as_usize(src)?;
+    if count > AUTH_SYS_MAX_GIDS {
+        return Err(Error::MaxElemLimit);
+    }
+    let gids = (0..count).map(|_| u32(src)).collect::<Result<Vec<_>>>()?;
+    Ok(AuthSysParams { stamp, machine_name, uid, gid, gids })
+}
+
+/// Parses the body of an RPCSEC_GSS credential.
+///
+/// Fails with [`AuthStat::BadCred`] if the credential version is not [`RPCSEC_GSS_VERSION`].
+pub fn gss_cred(src: &mut impl Read) -> Result<RpcGssCred> {
+    let version = u32(src)?;
+    if version != RPCSEC_GSS_VERSION {
+        return Err(Error::Auth(AuthStat::BadCred));
+    }
+    Ok(RpcGssCred {
+        version,
+        proc: variant::<RpcGssProc>(src)?,
+        seq_num: u32(src)?,
+        service: variant::<RpcGssService>(src)?,
+        handle: vec_max_size(src, MAX_AUTH_SIZE)?,
+    })
 }
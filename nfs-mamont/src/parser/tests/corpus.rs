@@ -0,0 +1,161 @@
+//! Seed corpus for [`parse_request`]: one valid call per supported procedure.
+
+use crate::consts::mount::{
+    MOUNT_DUMP, MOUNT_EXPORT, MOUNT_MNT, MOUNT_NULL, MOUNT_PROGRAM, MOUNT_UMNT, MOUNT_UMNTALL,
+    MOUNT_VERSION,
+};
+use crate::consts::nfs_acl::{
+    ACLPROC3_GETACL, ACLPROC3_NULL, ACLPROC3_SETACL, NFS_ACL_PROGRAM, NFS_ACL_VERSION,
+};
+use crate::consts::nfsv3::{
+    ACCESS, COMMIT, CREATE, FSINFO, FSSTAT, GETATTR, LINK, LOOKUP, MKDIR, MKNOD, NFS_PROGRAM,
+    NFS_VERSION, NULL, PATHCONF, READ, READDIR, READDIRPLUS, READLINK, REMOVE, RENAME, RMDIR,
+    SETATTR, SYMLINK, WRITE,
+};
+use crate::consts::nlm::{
+    NLMPROC4_CANCEL, NLMPROC4_LOCK, NLMPROC4_NULL, NLMPROC4_TEST, NLMPROC4_UNLOCK, NLM_PROGRAM,
+    NLM_VERSION,
+};
+use crate::parser::nlm::xdr::{bool_val, handle, i32_val, opaque, string, u32_val, u64_val};
+use crate::parser::parser_struct::parse_request;
+use crate::parser::{MountArguments, NfsArguments, NlmArguments, ProcArguments};
+
+use super::{call, XID};
+
+fn fh() -> Vec<u8> {
+    handle(&[1, 2, 3, 4, 5, 6, 7, 8])
+}
+
+fn dir_op(name: &str) -> Vec<u8> {
+    [fh(), string(name)].concat()
+}
+
+/// `sattr3` setting only the mode; both times use `DONT_CHANGE`.
+fn sattr() -> Vec<u8> {
+    [bool_val(true), u32_val(0o644), bool_val(false), bool_val(false), bool_val(false)]
+        .into_iter()
+        .chain([u32_val(0), u32_val(0)])
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+fn nlm_lock() -> Vec<u8> {
+    [string("client"), fh(), opaque(&[0xAA; 4]), i32_val(7), u64_val(0), u64_val(4096)].concat()
+}
+
+fn nfs(procedure: u32, args: &[Vec<u8>]) -> Vec<u8> {
+    call(NFS_PROGRAM, NFS_VERSION, procedure, args)
+}
+
+fn mount(procedure: u32, args: &[Vec<u8>]) -> Vec<u8> {
+    call(MOUNT_PROGRAM, MOUNT_VERSION, procedure, args)
+}
+
+fn nlm(procedure: u32, args: &[Vec<u8>]) -> Vec<u8> {
+    call(NLM_PROGRAM, NLM_VERSION, procedure, args)
+}
+
+fn nfs_acl(procedure: u32, args: &[Vec<u8>]) -> Vec<u8> {
+    call(NFS_ACL_PROGRAM, NFS_ACL_VERSION, procedure, args)
+}
+
+/// Access ACL with an owner entry only, followed by an empty default ACL.
+fn acl_lists() -> Vec<u8> {
+    [u32_val(1), u32_val(1), u32_val(1), u32_val(0), u32_val(6), u32_val(0), u32_val(0)].concat()
+}
+
+/// Valid encodings of every procedure the parser supports.
+fn seeds() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("NULL", nfs(NULL, &[])),
+        ("GETATTR", nfs(GETATTR, &[fh()])),
+        ("SETATTR", nfs(SETATTR, &[fh(), sattr(), bool_val(false)])),
+        ("LOOKUP", nfs(LOOKUP, &[dir_op("file")])),
+        ("ACCESS", nfs(ACCESS, &[fh(), u32_val(0x3F)])),
+        ("READLINK", nfs(READLINK, &[fh()])),
+        ("READ", nfs(READ, &[fh(), u64_val(0), u32_val(4096)])),
+        ("WRITE", nfs(WRITE, &[fh(), u64_val(0), u32_val(3), u32_val(0), opaque(&[1, 2, 3])])),
+        ("CREATE", nfs(CREATE, &[dir_op("file"), u32_val(0), sattr()])),
+        ("MKDIR", nfs(MKDIR, &[dir_op("dir"), sattr()])),
+        ("SYMLINK", nfs(SYMLINK, &[dir_op("link"), sattr(), string("target")])),
+        ("MKNOD", nfs(MKNOD, &[dir_op("fifo"), u32_val(7), sattr()])),
+        ("REMOVE", nfs(REMOVE, &[dir_op("file")])),
+        ("RMDIR", nfs(RMDIR, &[dir_op("dir")])),
+        ("RENAME", nfs(RENAME, &[dir_op("from"), dir_op("to")])),
+        ("LINK", nfs(LINK, &[fh(), dir_op("link")])),
+        ("READDIR", nfs(READDIR, &[fh(), u64_val(0), u64_val(0), u32_val(4096)])),
+        (
+            "READDIRPLUS",
+            nfs(READDIRPLUS, &[fh(), u64_val(0), u64_val(0), u32_val(4096), u32_val(8192)]),
+        ),
+        ("FSSTAT", nfs(FSSTAT, &[fh()])),
+        ("FSINFO", nfs(FSINFO, &[fh()])),
+        ("PATHCONF", nfs(PATHCONF, &[fh()])),
+        ("COMMIT", nfs(COMMIT, &[fh(), u64_val(0), u32_val(0)])),
+        ("MOUNT NULL", mount(MOUNT_NULL, &[])),
+        ("MNT", mount(MOUNT_MNT, &[string("/export")])),
+        ("DUMP", mount(MOUNT_DUMP, &[])),
+        ("UMNT", mount(MOUNT_UMNT, &[string("/export")])),
+        ("UMNTALL", mount(MOUNT_UMNTALL, &[])),
+        ("EXPORT", mount(MOUNT_EXPORT, &[])),
+        ("NLM NULL", nlm(NLMPROC4_NULL, &[])),
+        ("NLM TEST", nlm(NLMPROC4_TEST, &[u64_val(1), bool_val(true), nlm_lock()])),
+        (
+            "NLM LOCK",
+            nlm(
+                NLMPROC4_LOCK,
+                &[
+                    u64_val(1),
+                    bool_val(false),
+                    bool_val(true),
+                    nlm_lock(),
+                    bool_val(false),
+                    u32_val(3),
+                ],
+            ),
+        ),
+        (
+            "NLM CANCEL",
+            nlm(NLMPROC4_CANCEL, &[u64_val(1), bool_val(false), bool_val(true), nlm_lock()]),
+        ),
+        ("NLM UNLOCK", nlm(NLMPROC4_UNLOCK, &[u64_val(1), nlm_lock()])),
+        ("ACL NULL", nfs_acl(ACLPROC3_NULL, &[])),
+        ("GETACL", nfs_acl(ACLPROC3_GETACL, &[fh(), u32_val(0xF)])),
+        ("SETACL", nfs_acl(ACLPROC3_SETACL, &[fh(), u32_val(0x5), acl_lists()])),
+    ]
+}
+
+#[test]
+fn every_seed_parses() {
+    for (name, bytes) in seeds() {
+        let parsed = parse_request(&bytes).unwrap_or_else(|error| panic!("{name}: {error:?}"));
+        assert_eq!(parsed.header.xid, XID, "{name}");
+        if let ProcArguments::Nfs3(args) = &parsed.proc {
+            if let NfsArguments::Write(args) = args.as_ref() {
+                assert_eq!(args.data.len(), 3);
+            }
+        }
+    }
+}
+
+#[test]
+fn seeds_cover_every_program() {
+    let programs = seeds()
+        .iter()
+        .map(|(_, bytes)| match parse_request(bytes).unwrap().proc {
+            ProcArguments::Nfs3(args) => matches!(*args, NfsArguments::Null) as u8,
+            ProcArguments::Mount(args) => 2 + matches!(*args, MountArguments::Null) as u8,
+            ProcArguments::Nlm4(args) => 4 + matches!(*args, NlmArguments::Null) as u8,
+        })
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(programs.into_iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn every_truncated_seed_errors() {
+    for (name, bytes) in seeds() {
+        for len in 0..bytes.len() {
+            assert!(parse_request(&bytes[..len]).is_err(), "{name} truncated to {len} bytes");
+        }
+    }
+}
@@ -1,5 +1,27 @@
 mod allocator;
+mod corpus;
 mod mount;
 mod parser_struct;
 mod primitive;
+mod registry;
+mod reply;
 mod socket;
+
+use crate::parser::nlm::xdr::u32_val;
+use crate::rpc::{RpcBody, RPC_VERSION};
+
+/// Transaction id of the calls [`call`] serializes.
+pub const XID: u32 = 9;
+
+/// Serializes a complete call frame with AUTH_NONE credentials and verifier.
+pub fn call(program: u32, version: u32, procedure: u32, args: &[Vec<u8>]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for word in [XID, RpcBody::Call as u32, RPC_VERSION, program, version, procedure, 0, 0, 0, 0] {
+        payload.extend(u32_val(word));
+    }
+    args.iter().for_each(|arg| payload.extend(arg));
+
+    let mut frame = u32_val(0x8000_0000 | payload.len() as u32);
+    frame.extend(payload);
+    frame
+}
@@ -1,16 +1,22 @@
 use num_traits::ToPrimitive;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use crate::allocator::Buffer;
+use crate::allocator::{Buffer, Slice};
 use crate::consts::mount::{MOUNT_PROGRAM, MOUNT_VERSION};
 use crate::consts::nfsv3::{FSSTAT, NFS_PROGRAM, NFS_VERSION, WRITE};
 use crate::parser::parser_struct::RpcParser;
+use crate::parser::rpc::{auth, auth_sys};
 use crate::parser::tests::allocator::MockAllocator;
-use crate::parser::tests::socket::MockSocket;
+use crate::parser::tests::socket::{CountingSocket, MockSocket};
 use crate::parser::{
     ArgWrapper, Error, ErrorWrapper, MountArguments, NfsArguments, ProcArguments, RpcHeader,
 };
-use crate::rpc::{AuthFlavor, AuthStat, OpaqueAuth, RpcBody, RPC_VERSION};
+use crate::rpc::{
+    AuthFlavor, AuthStat, AuthSysParams, OpaqueAuth, RpcBody, VersionMismatch, MAX_AUTH_SIZE,
+    RPC_VERSION,
+};
+use crate::serializer::server::rpc::auth as serialize_auth;
 use crate::vfs::file::Handle;
 use crate::vfs::write;
 use crate::vfs::write::StableHow;
@@ -94,12 +100,12 @@ fn nfs_call_frame(
     push_u32(&mut payload, procedure);
     // cred
     // Auth body length (0 for AUTH_NONE/SYS in tests)
-    push_u32(&mut payload, header.cred.flavor.to_u32().unwrap());
-    push_opaque(&mut payload, &header.cred.body);
+    push_u32(&mut payload, header.cred.flavor().to_u32().unwrap());
+    push_opaque(&mut payload, header.cred.body());
 
     // verf
-    push_u32(&mut payload, header.verf.flavor.to_u32().unwrap());
-    push_opaque(&mut payload, &header.verf.body);
+    push_u32(&mut payload, header.verf.flavor().to_u32().unwrap());
+    push_opaque(&mut payload, header.verf.body());
     // Append procedure-specific arguments
     args_builder(&mut payload);
 
@@ -121,6 +127,25 @@ fn mount_call_frame(
     header: &RpcHeader,
     procedure: u32,
     args_builder: impl FnOnce(&mut Vec<u8>),
+) -> Vec<u8> {
+    mount_versioned_call_frame(
+        msg_type,
+        rpc_version,
+        MOUNT_VERSION,
+        header,
+        procedure,
+        args_builder,
+    )
+}
+
+/// Like [`mount_call_frame`], but for an arbitrary MOUNT program version.
+fn mount_versioned_call_frame(
+    msg_type: u32,
+    rpc_version: u32,
+    mount_version: u32,
+    header: &RpcHeader,
+    procedure: u32,
+    args_builder: impl FnOnce(&mut Vec<u8>),
 ) -> Vec<u8> {
     let mut payload = Vec::new();
     // RPC call header fields
@@ -128,15 +153,15 @@ fn mount_call_frame(
     push_u32(&mut payload, msg_type);
     push_u32(&mut payload, rpc_version);
     push_u32(&mut payload, MOUNT_PROGRAM);
-    push_u32(&mut payload, MOUNT_VERSION);
+    push_u32(&mut payload, mount_version);
     push_u32(&mut payload, procedure);
     // cred
-    push_u32(&mut payload, header.cred.flavor.to_u32().unwrap());
-    push_opaque(&mut payload, &header.cred.body);
+    push_u32(&mut payload, header.cred.flavor().to_u32().unwrap());
+    push_opaque(&mut payload, header.cred.body());
 
     // verf
-    push_u32(&mut payload, header.verf.flavor.to_u32().unwrap());
-    push_opaque(&mut payload, &header.verf.body);
+    push_u32(&mut payload, header.verf.flavor().to_u32().unwrap());
+    push_opaque(&mut payload, header.verf.body());
 
     // Append procedure-specific arguments
     args_builder(&mut payload);
@@ -225,7 +250,7 @@ fn assert_arg_wrapper<B: Buffer, F, T: Fn(&ProcArguments<B>, F)>(
 /// Test: Parses a valid MOUNT call and returns mount-specific arguments.
 #[tokio::test]
 async fn parse_mount_call() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     let frame = mount_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, 1, |buf| {
@@ -243,10 +268,41 @@ async fn parse_mount_call() {
     assert!(matches!(mount_args.as_ref(), MountArguments::Mount(_)));
 }
 
+/// Test: A MOUNT v1 probe is rejected with the supported version range,
+/// and the parser stays usable for the next call.
+#[tokio::test]
+async fn parse_mount_v1_reports_supported_versions() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+
+    let mut buf =
+        mount_versioned_call_frame(RpcBody::Call as u32, RPC_VERSION, 1, &header, 1, |buf| {
+            push_opaque(buf, b"/mnt/vol");
+        });
+    buf.extend(mount_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, 0, |_| {}));
+    let socket = MockSocket::new(buf.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 0x40);
+
+    let result = parser.next_message().await;
+    assert!(matches!(
+        result,
+        Err(ErrorWrapper {
+            error: Error::ProgramVersionMismatch(VersionMismatch { low: 3, high: 3 }),
+            xid: Some(XID)
+        })
+    ));
+
+    let result = parser.next_message().await.unwrap();
+    assert!(
+        matches!(result.proc, ProcArguments::Mount(args) if matches!(*args, MountArguments::Null))
+    );
+}
+
 /// Test: After a MOUNT procedure mismatch, parser can parse the next valid MOUNT call.
 #[tokio::test]
 async fn parse_mount_after_error() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     let first = mount_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, 99, |_| {});
@@ -278,7 +334,7 @@ async fn parse_mount_after_error() {
 /// Test: Parses two correct NFS FSSTAT frames back-to-back.
 #[tokio::test]
 async fn parse_two_correct() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     let first = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
@@ -311,10 +367,91 @@ async fn parse_two_correct() {
     );
 }
 
+/// Test: Many small frames pipelined in one stream are parsed in order straight from
+/// the buffered data, refilling only when a buffer's worth has been consumed.
+#[tokio::test]
+async fn parse_many_pipelined_frames() {
+    const FRAMES: u32 = 512;
+    const CAPACITY: usize = 4096;
+
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let headers: Vec<RpcHeader> =
+        (0..FRAMES).map(|xid| RpcHeader { xid, cred: auth.clone(), verf: auth.clone() }).collect();
+    let mut buf = Vec::new();
+    for header in &headers {
+        buf.extend(nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, header, FSSTAT, |buf| {
+            buf.extend_from_slice(&fsstat_args(
+                header.xid.to_be_bytes().repeat(2).try_into().unwrap(),
+            ));
+        }));
+    }
+
+    let reads = Arc::new(AtomicUsize::new(0));
+    let socket = CountingSocket::new(buf.as_slice(), reads.clone());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, CAPACITY);
+
+    for header in &headers {
+        let result = parser.next_message().await.unwrap();
+        assert_arg_wrapper(
+            result,
+            header,
+            |proc, opaque| assert_fsstat_proc_result(proc, opaque),
+            &header.xid.to_be_bytes().repeat(2),
+        );
+    }
+    // one read per filled buffer, plus slack for frames straddling two buffers
+    let max_reads = 2 * buf.len().div_ceil(CAPACITY);
+    assert!(reads.load(Ordering::Relaxed) <= max_reads);
+}
+
+/// Test: EOF right after a complete frame is reported as a clean close.
+#[tokio::test]
+async fn parse_reports_clean_close_at_frame_boundary() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
+    });
+
+    let socket = MockSocket::new(frame.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 0x50);
+
+    assert!(parser.next_message().await.is_ok());
+    assert!(matches!(
+        parser.next_message().await,
+        Err(ErrorWrapper { xid: None, error: Error::ConnectionClosed })
+    ));
+}
+
+/// Test: EOF inside a frame is reported as a truncated message, with the XID once known.
+#[tokio::test]
+async fn parse_reports_truncated_message() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
+    });
+
+    for (len, expected_xid) in [(2, None), (6, None), (20, Some(XID)), (frame.len() - 1, Some(XID))]
+    {
+        let socket = MockSocket::new(&frame[..len]);
+        let alloc = Arc::new(MockAllocator::new(0));
+        let mut parser = RpcParser::with_capacity(socket, alloc, 0x50);
+
+        let Err(ErrorWrapper { xid, error }) = parser.next_message().await else {
+            panic!("truncated frame of {len} bytes parsed");
+        };
+        assert!(matches!(error, Error::TruncatedMessage), "{len} bytes: {error:?}");
+        assert_eq!(xid, expected_xid, "{len} bytes");
+    }
+}
+
 /// Test: After a version mismatch error, parses the next valid FSSTAT frame.
 #[tokio::test]
 async fn parse_after_error() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     let first = nfs_call_frame(RpcBody::Call as u32, 3, &header, FSSTAT, |buf| {
@@ -345,7 +482,7 @@ async fn parse_after_error() {
 /// Test: Parses two correct NFS WRITE frames with data.
 #[tokio::test]
 async fn parse_write() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     #[rustfmt::skip]
@@ -390,7 +527,7 @@ async fn parse_write() {
 /// Test: Parser recovers from an error on first WRITE frame and parses the next valid WRITE frame.
 #[tokio::test]
 async fn parse_write_after_error() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     #[rustfmt::skip]
@@ -515,10 +652,91 @@ async fn parse_rejects_frame_smaller_than_xid() {
     assert!(matches!(error, Error::IO(err) if err.kind() == std::io::ErrorKind::InvalidData));
 }
 
+/// Verifies an oversize WRITE payload is skipped without breaking the stream.
+#[tokio::test]
+async fn parse_write_exceeding_allocator_capacity() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+
+    let oversize = WriteWrapper {
+        part: write::ArgsPartial {
+            file: Handle([1, 2, 3, 4, 5, 6, 7, 8]),
+            offset: 0,
+            size: 18,
+            stable: StableHow::Unstable,
+        },
+        data: &[0xAA; 18],
+    };
+    let fitting = WriteWrapper {
+        part: write::ArgsPartial {
+            file: Handle([1, 2, 3, 4, 5, 6, 7, 8]),
+            offset: 0x8000,
+            size: 2,
+            stable: StableHow::FileSync,
+        },
+        data: &[0x01, 0x02],
+    };
+
+    let mut buf = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, WRITE, |buf| {
+        buf.extend_from_slice(&write_args(&oversize));
+    });
+    buf.extend_from_slice(&nfs_call_frame(
+        RpcBody::Call as u32,
+        RPC_VERSION,
+        &header,
+        WRITE,
+        |buf| buf.extend_from_slice(&write_args(&fitting)),
+    ));
+
+    let socket = MockSocket::new(buf.as_slice());
+    let alloc = Arc::new(MockAllocator::new(4));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 72);
+
+    let result = parser.next_message().await.unwrap();
+    let ProcArguments::Nfs3(proc) = result.proc else {
+        panic!("expected NFSv3 arguments");
+    };
+    let NfsArguments::Write(args) = *proc else {
+        panic!("expected WRITE arguments");
+    };
+    assert_eq!(args.size, 18);
+    assert!(args.data.is_empty());
+
+    let result = parser.next_message().await.unwrap();
+    assert_arg_wrapper(result, &header, |proc, arg| assert_write_proc_result(proc, arg), &fitting);
+}
+
+/// Verifies WRITE offsets beyond 4 GiB are parsed whole.
+#[tokio::test]
+async fn parse_write_keeps_64_bit_offset() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+
+    let write = WriteWrapper {
+        part: write::ArgsPartial {
+            file: Handle([1, 2, 3, 4, 5, 6, 7, 8]),
+            offset: (5 << 32) + 3,
+            size: 4,
+            stable: StableHow::Unstable,
+        },
+        data: b"high",
+    };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, WRITE, |buf| {
+        buf.extend_from_slice(&write_args(&write));
+    });
+
+    let socket = MockSocket::new(frame.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0x24));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 72);
+
+    let result = parser.next_message().await.unwrap();
+    assert_arg_wrapper(result, &header, |proc, arg| assert_write_proc_result(proc, arg), &write);
+}
+
 /// Verifies parser handles WRITE with zero opaque payload.
 #[tokio::test]
 async fn parse_write_with_empty_payload() {
-    let auth = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
 
     let write = WriteWrapper {
@@ -543,10 +761,122 @@ async fn parse_write_with_empty_payload() {
     assert_arg_wrapper(result, &header, |proc, arg| assert_write_proc_result(proc, arg), &write);
 }
 
+/// Verifies WRITE payload padding is validated, and the next frame still parses.
+#[tokio::test]
+async fn parse_write_rejects_non_zero_payload_padding() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+
+    let write = WriteWrapper {
+        part: write::ArgsPartial {
+            file: Handle([1, 2, 3, 4, 5, 6, 7, 8]),
+            offset: 0,
+            size: 3,
+            stable: StableHow::Unstable,
+        },
+        data: &[1, 2, 3],
+    };
+    let mut first = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, WRITE, |buf| {
+        buf.extend_from_slice(&write_args(&write));
+    });
+    *first.last_mut().unwrap() = 0xAA;
+    let second = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([8; 8]));
+    });
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&first);
+    buf.extend_from_slice(&second);
+
+    let socket = MockSocket::new(buf.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0x24));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 72);
+
+    let Err(ErrorWrapper { xid, error }) = parser.next_message().await else {
+        panic!("expected padding error");
+    };
+    assert_eq!(xid, Some(XID));
+    assert!(matches!(error, Error::IncorrectPadding));
+
+    let result = parser.next_message().await.unwrap();
+    assert_arg_wrapper(result, &header, |proc, arg| assert_fsstat_proc_result(proc, arg), &[8; 8]);
+}
+
+/// Verifies a WRITE declaring a `0xFFFFFFFF` byte payload is rejected without an
+/// allocation attempt, and the next frame still parses.
+#[tokio::test]
+async fn parse_write_rejects_length_beyond_frame() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+
+    let first = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, WRITE, |buf| {
+        push_opaque(buf, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        push_u64(buf, 0);
+        push_u32(buf, 4);
+        push_u32(buf, StableHow::Unstable.to_u32().unwrap());
+        push_u32(buf, u32::MAX);
+        push_bytes(buf, &[1, 2, 3, 4]);
+    });
+    let second = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([8; 8]));
+    });
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&first);
+    buf.extend_from_slice(&second);
+
+    let socket = MockSocket::new(buf.as_slice());
+    // Any allocation of the declared size would succeed and be visible as a WRITE.
+    let alloc = Arc::new(MockAllocator::new(usize::MAX));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 72);
+
+    let Err(ErrorWrapper { xid, error }) = parser.next_message().await else {
+        panic!("expected length error");
+    };
+    assert_eq!(xid, Some(XID));
+    assert!(matches!(error, Error::MaxElemLimit));
+
+    let result = parser.next_message().await.unwrap();
+    assert_arg_wrapper(result, &header, |proc, arg| assert_fsstat_proc_result(proc, arg), &[8; 8]);
+}
+
+/// Verifies a connection closing inside a WRITE payload fails the call as truncated
+/// instead of passing on a partially filled buffer.
+#[tokio::test]
+async fn parse_write_reports_truncated_payload() {
+    let auth = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: auth.clone(), verf: auth };
+
+    let data: Vec<u8> = (1..=32).collect();
+    let write = WriteWrapper {
+        part: write::ArgsPartial {
+            file: Handle([1, 2, 3, 4, 5, 6, 7, 8]),
+            offset: 0,
+            size: 32,
+            stable: StableHow::FileSync,
+        },
+        data: &data,
+    };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, WRITE, |buf| {
+        buf.extend_from_slice(&write_args(&write));
+    });
+    let payload_start = frame.len() - data.len();
+
+    for len in [payload_start, payload_start + 1, payload_start + 17, frame.len() - 1] {
+        let socket = MockSocket::new(&frame[..len]);
+        let alloc = Arc::new(MockAllocator::new(0x24));
+        let mut parser = RpcParser::with_capacity(socket, alloc, 72);
+
+        let Err(ErrorWrapper { xid, error }) = parser.next_message().await else {
+            panic!("WRITE truncated to {len} bytes parsed");
+        };
+        assert_eq!(xid, Some(XID), "{len} bytes");
+        assert!(matches!(error, Error::TruncatedMessage), "{len} bytes: {error:?}");
+    }
+}
+
 #[tokio::test]
 async fn parse_rejects_non_none_cred_auth() {
-    let verf = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
-    let cred = OpaqueAuth { flavor: AuthFlavor::Short, body: vec![] };
+    let verf = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let cred = OpaqueAuth::new(AuthFlavor::Short, vec![]).unwrap();
     let header = RpcHeader { xid: XID, cred, verf };
     let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
         buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
@@ -562,10 +892,149 @@ async fn parse_rejects_non_none_cred_auth() {
     ));
 }
 
+/// Builds an RPCSEC_GSS credential body as sent by a `sec=krb5` client.
+fn gss_cred_body(version: u32) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_u32(&mut body, version);
+    // RPCSEC_GSS_DATA
+    push_u32(&mut body, 0);
+    push_u32(&mut body, 7);
+    // rpc_gss_svc_none
+    push_u32(&mut body, 1);
+    push_opaque(&mut body, &[0xde, 0xad, 0xbe, 0xef, 0x01]);
+    body
+}
+
+#[tokio::test]
+async fn parse_rejects_rpcsec_gss_cred() {
+    let cred = OpaqueAuth::new(AuthFlavor::RpcSecGss, gss_cred_body(1)).unwrap();
+    let verf = OpaqueAuth::new(AuthFlavor::RpcSecGss, vec![1, 2, 3, 4]).unwrap();
+    let header = RpcHeader { xid: XID, cred, verf };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
+    });
+    let socket = MockSocket::new(frame.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 0x80);
+
+    let result = parser.next_message().await;
+    assert!(matches!(
+        result,
+        Err(ErrorWrapper { error: Error::Auth(AuthStat::RejectedCred), xid: Some(XID) })
+    ));
+}
+
+#[tokio::test]
+async fn parse_rejects_malformed_rpcsec_gss_cred() {
+    let cred = OpaqueAuth::new(AuthFlavor::RpcSecGss, gss_cred_body(2)).unwrap();
+    let verf = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred, verf };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
+    });
+    let socket = MockSocket::new(frame.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 0x80);
+
+    let result = parser.next_message().await;
+    assert!(matches!(
+        result,
+        Err(ErrorWrapper { error: Error::Auth(AuthStat::BadCred), xid: Some(XID) })
+    ));
+}
+
+/// Builds an AUTH_SYS credential body with `gids_count` supplementary groups.
+fn auth_sys_body(gids_count: u32) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_u32(&mut body, 0x5eed);
+    push_opaque(&mut body, b"client");
+    push_u32(&mut body, 1000);
+    push_u32(&mut body, 100);
+    push_u32(&mut body, gids_count);
+    for gid in 0..gids_count {
+        push_u32(&mut body, gid);
+    }
+    body
+}
+
+async fn parse_with_auth_sys(body: Vec<u8>) -> Result<ArgWrapper<Slice>, ErrorWrapper> {
+    let cred = OpaqueAuth::new(AuthFlavor::Sys, body).unwrap();
+    let verf = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred, verf };
+    let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
+    });
+    let socket = MockSocket::new(frame.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 0x100);
+    parser.next_message().await
+}
+
+#[tokio::test]
+async fn parse_accepts_auth_sys_cred() {
+    let body = auth_sys_body(2);
+    let result = parse_with_auth_sys(body.clone()).await.unwrap();
+    assert_eq!(result.header.cred, OpaqueAuth::new(AuthFlavor::Sys, body.clone()).unwrap());
+
+    let params = auth_sys(&mut body.as_slice()).unwrap();
+    assert_eq!(
+        params,
+        AuthSysParams {
+            stamp: 0x5eed,
+            machine_name: "client".to_string(),
+            uid: 1000,
+            gid: 100,
+            gids: vec![0, 1],
+        }
+    );
+}
+
+#[tokio::test]
+async fn parse_rejects_malformed_auth_sys_cred() {
+    let result = parse_with_auth_sys(auth_sys_body(17)).await;
+    assert!(matches!(
+        result,
+        Err(ErrorWrapper { error: Error::Auth(AuthStat::BadCred), xid: Some(XID) })
+    ));
+
+    let mut truncated = auth_sys_body(2);
+    truncated.truncate(truncated.len() - 4);
+    let result = parse_with_auth_sys(truncated).await;
+    assert!(matches!(
+        result,
+        Err(ErrorWrapper { error: Error::Auth(AuthStat::BadCred), xid: Some(XID) })
+    ));
+}
+
+#[test]
+fn opaque_auth_rejects_body_over_max_auth_size() {
+    assert!(OpaqueAuth::new(AuthFlavor::Sys, vec![0; MAX_AUTH_SIZE]).is_ok());
+    assert!(matches!(
+        OpaqueAuth::new(AuthFlavor::Sys, vec![0; MAX_AUTH_SIZE + 1]),
+        Err(Error::MaxElemLimit)
+    ));
+
+    let mut src = Vec::new();
+    push_u32(&mut src, AuthFlavor::Sys.to_u32().unwrap());
+    push_opaque(&mut src, &[0; MAX_AUTH_SIZE + 4]);
+    assert!(matches!(auth(&mut src.as_slice()), Err(Error::MaxElemLimit)));
+}
+
+#[test]
+fn auth_sys_cred_round_trips_through_opaque_auth() {
+    let cred = OpaqueAuth::new(AuthFlavor::Sys, auth_sys_body(3)).unwrap();
+    let mut encoded = Vec::new();
+    serialize_auth(&mut encoded, cred.clone()).unwrap();
+
+    let decoded = auth(&mut encoded.as_slice()).unwrap();
+    assert_eq!(decoded, cred);
+    assert_eq!(auth_sys(&mut decoded.body()).unwrap().gids, vec![0, 1, 2]);
+}
+
 #[tokio::test]
 async fn parse_rejects_non_none_verf_auth() {
-    let cred = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
-    let verf = OpaqueAuth { flavor: AuthFlavor::None, body: vec![0, 1, 3] };
+    let cred = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let verf = OpaqueAuth::new(AuthFlavor::None, vec![0, 1, 3]).unwrap();
     let header = RpcHeader { xid: XID, cred, verf };
     let frame = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
         buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
@@ -580,3 +1049,47 @@ async fn parse_rejects_non_none_verf_auth() {
         Err(ErrorWrapper { error: Error::Auth(AuthStat::BadVerf), xid: Some(XID) })
     ));
 }
+
+/// Test: An AUTH_SYS call carries an AUTH_NONE verifier after its credential; both are
+/// consumed, so the arguments are read at the right offset, and a call with another
+/// verifier is rejected without losing track of the next frame.
+#[tokio::test]
+async fn parse_auth_sys_call_consumes_verifier() {
+    let cred = OpaqueAuth::new(AuthFlavor::Sys, auth_sys_body(2)).unwrap();
+    let none = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred: cred.clone(), verf: none };
+    let sys_verf = RpcHeader { xid: XID + 1, cred: cred.clone(), verf: cred };
+
+    let mut buf = nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([1, 2, 3, 4, 5, 6, 7, 8]));
+    });
+    buf.extend(nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &sys_verf, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([8, 7, 6, 5, 4, 3, 2, 1]));
+    }));
+    buf.extend(nfs_call_frame(RpcBody::Call as u32, RPC_VERSION, &header, FSSTAT, |buf| {
+        buf.extend_from_slice(&fsstat_args([9, 10, 11, 12, 13, 14, 15, 16]));
+    }));
+    let socket = MockSocket::new(buf.as_slice());
+    let alloc = Arc::new(MockAllocator::new(0));
+    let mut parser = RpcParser::with_capacity(socket, alloc, 0x100);
+
+    let result = parser.next_message().await.unwrap();
+    assert_arg_wrapper(
+        result,
+        &header,
+        |proc, opaque| assert_fsstat_proc_result(proc, opaque),
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    );
+    let result = parser.next_message().await;
+    assert!(matches!(
+        result,
+        Err(ErrorWrapper { error: Error::Auth(AuthStat::BadVerf), xid: Some(xid) }) if xid == XID + 1
+    ));
+    let result = parser.next_message().await.unwrap();
+    assert_arg_wrapper(
+        result,
+        &header,
+        |proc, opaque| assert_fsstat_proc_result(proc, opaque),
+        &[9, 10, 11, 12, 13, 14, 15, 16],
+    );
+}
@@ -1,8 +1,10 @@
-use std::io::Cursor;
+use std::io::{Cursor, ErrorKind};
 
 use byteorder::{BigEndian, WriteBytesExt};
 
-use crate::parser::primitive::{array, string, string_max_size, vector};
+use crate::parser::primitive::{
+    array, padding_with_mode, string, string_max_size, vector, with_limits, Limits,
+};
 use crate::parser::Error;
 
 #[test]
@@ -106,3 +108,64 @@ fn test_read_error() {
     let result = vector(&mut Cursor::new(src));
     assert!(matches!(result, Err(Error::IO(_))));
 }
+
+#[test]
+fn test_zero_padding_accepted_in_both_modes() {
+    for strict in [true, false] {
+        let mut src = Cursor::new([0u8, 0, 0]);
+        padding_with_mode(&mut src, 1, strict).unwrap();
+        assert_eq!(src.position(), 3);
+    }
+}
+
+#[test]
+fn test_non_zero_padding_rejected_in_strict_mode() {
+    let result = padding_with_mode(&mut Cursor::new([0u8, 1, 0]), 1, true);
+    assert!(matches!(result, Err(Error::IncorrectPadding)));
+
+    let mut src = Vec::new();
+    src.write_u32::<BigEndian>(3).unwrap();
+    src.extend([1, 2, 3, 0xAA]);
+    assert!(matches!(vector(&mut Cursor::new(src)), Err(Error::IncorrectPadding)));
+}
+
+#[test]
+fn test_non_zero_padding_accepted_in_lenient_mode() {
+    let mut src = Cursor::new([0xFFu8, 0xFF, 0xFF]);
+    padding_with_mode(&mut src, 1, false).unwrap();
+    assert_eq!(src.position(), 3);
+}
+
+#[test]
+fn test_limits_apply_only_within_with_limits() {
+    let mut src = Vec::new();
+    src.write_u32::<BigEndian>(3).unwrap();
+    src.extend([1, 2, 3, 0xAA]);
+
+    let lenient = Limits { strict_padding: false, ..Limits::default() };
+    let result = with_limits(lenient, || vector(&mut Cursor::new(src.clone())));
+    assert_eq!(result.unwrap(), [1, 2, 3]);
+
+    let short = Limits { max_counted_len: 2, ..lenient };
+    let result = with_limits(short, || vector(&mut Cursor::new(src.clone())));
+    assert!(matches!(result, Err(Error::MaxElemLimit)));
+
+    assert!(matches!(vector(&mut Cursor::new(src)), Err(Error::IncorrectPadding)));
+}
+
+#[test]
+fn test_vec_u8_rejects_max_length_before_allocating() {
+    let mut src = Vec::new();
+    src.write_u32::<BigEndian>(u32::MAX).unwrap();
+    src.extend([1, 2, 3, 4]);
+    assert!(matches!(vector(&mut Cursor::new(src)), Err(Error::MaxElemLimit)));
+}
+
+#[test]
+fn test_vec_u8_length_beyond_data_is_eof() {
+    let mut src = Vec::new();
+    src.write_u32::<BigEndian>(1024).unwrap();
+    src.extend([1, 2, 3, 4]);
+    let result = vector(&mut Cursor::new(src));
+    assert!(matches!(result, Err(Error::IO(error)) if error.kind() == ErrorKind::UnexpectedEof));
+}
@@ -0,0 +1,106 @@
+use crate::consts::mount::MOUNT_PROGRAM;
+use crate::consts::nfs_acl::NFS_ACL_PROGRAM;
+use crate::consts::nfsv3::{
+    NfsProc3, ACCESS, COMMIT, CREATE, FSINFO, FSSTAT, GETATTR, LINK, LOOKUP, MKDIR, MKNOD,
+    NFS_PROGRAM, NFS_VERSION, NULL, PATHCONF, READ, READDIR, READDIRPLUS, READLINK, REMOVE, RENAME,
+    RMDIR, SETATTR, SYMLINK, WRITE,
+};
+use crate::consts::nlm::NLM_PROGRAM;
+use crate::parser::parser_struct::parse_request;
+use crate::parser::registry::{ProgramInfo, ProgramRegistry, PROGRAMS};
+use crate::rpc::{Error, VersionMismatch};
+
+use super::call;
+
+#[test]
+fn registry_knows_served_programs() {
+    for program in [NFS_PROGRAM, MOUNT_PROGRAM, NLM_PROGRAM, NFS_ACL_PROGRAM] {
+        assert_eq!(PROGRAMS.get(program).unwrap().program, program);
+    }
+    assert!(PROGRAMS.get(100_000).is_none());
+}
+
+#[test]
+fn unregistered_program_is_program_mismatch() {
+    assert!(matches!(PROGRAMS.check(100_000, 2, 0), Err(Error::ProgramMismatch)));
+    assert!(matches!(parse_request(&call(100_000, 2, 0, &[])), Err(Error::ProgramMismatch)));
+}
+
+#[test]
+fn out_of_range_version_reports_registered_range() {
+    assert!(matches!(
+        PROGRAMS.check(NLM_PROGRAM, 1, 0),
+        Err(Error::ProgramVersionMismatch(VersionMismatch { low: 4, high: 4 }))
+    ));
+    assert!(matches!(
+        parse_request(&call(NFS_PROGRAM, 4, 0, &[])),
+        Err(Error::ProgramVersionMismatch(VersionMismatch { low: 3, high: 3 }))
+    ));
+}
+
+#[test]
+fn procedure_beyond_count_is_procedure_mismatch() {
+    assert!(PROGRAMS.check(MOUNT_PROGRAM, 3, 5).is_ok());
+    assert!(matches!(PROGRAMS.check(MOUNT_PROGRAM, 3, 6), Err(Error::ProcedureMismatch)));
+}
+
+#[test]
+fn nfs_procedures_end_at_commit() {
+    assert!(PROGRAMS.check(NFS_PROGRAM, NFS_VERSION, COMMIT).is_ok());
+    assert!(matches!(
+        PROGRAMS.check(NFS_PROGRAM, NFS_VERSION, COMMIT + 1),
+        Err(Error::ProcedureMismatch)
+    ));
+    assert!(matches!(
+        parse_request(&call(NFS_PROGRAM, NFS_VERSION, COMMIT + 1, &[])),
+        Err(Error::ProcedureMismatch)
+    ));
+}
+
+#[test]
+fn nfs_procedure_numbers_convert_to_their_procedures() {
+    let procedures = [
+        (NULL, NfsProc3::Null),
+        (GETATTR, NfsProc3::GetAttr),
+        (SETATTR, NfsProc3::SetAttr),
+        (LOOKUP, NfsProc3::Lookup),
+        (ACCESS, NfsProc3::Access),
+        (READLINK, NfsProc3::ReadLink),
+        (READ, NfsProc3::Read),
+        (WRITE, NfsProc3::Write),
+        (CREATE, NfsProc3::Create),
+        (MKDIR, NfsProc3::MkDir),
+        (SYMLINK, NfsProc3::Symlink),
+        (MKNOD, NfsProc3::MkNod),
+        (REMOVE, NfsProc3::Remove),
+        (RMDIR, NfsProc3::RmDir),
+        (RENAME, NfsProc3::Rename),
+        (LINK, NfsProc3::Link),
+        (READDIR, NfsProc3::ReadDir),
+        (READDIRPLUS, NfsProc3::ReadDirPlus),
+        (FSSTAT, NfsProc3::FsStat),
+        (FSINFO, NfsProc3::FsInfo),
+        (PATHCONF, NfsProc3::PathConf),
+        (COMMIT, NfsProc3::Commit),
+    ];
+    for (number, (expected_number, proc)) in (0..=21).zip(procedures) {
+        assert_eq!(number, expected_number);
+        assert_eq!(NfsProc3::try_from(number), Ok(proc));
+        assert_eq!(proc as u32, number);
+    }
+    assert_eq!(NfsProc3::try_from(22), Err(22));
+}
+
+#[test]
+fn registered_range_spans_several_versions() {
+    static NFS_2_TO_4: [ProgramInfo; 1] =
+        [ProgramInfo { program: NFS_PROGRAM, low_version: 2, high_version: 4, proc_count: 22 }];
+    let registry = ProgramRegistry::new(&NFS_2_TO_4);
+
+    assert!(registry.check(NFS_PROGRAM, NFS_VERSION, 0).is_ok());
+    assert!(registry.check(NFS_PROGRAM, 4, 0).is_ok());
+    assert!(matches!(
+        registry.check(NFS_PROGRAM, 5, 0),
+        Err(Error::ProgramVersionMismatch(VersionMismatch { low: 2, high: 4 }))
+    ));
+}
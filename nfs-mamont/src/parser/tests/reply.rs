@@ -0,0 +1,300 @@
+use std::io::Cursor;
+
+use crate::allocator::Slice;
+use crate::parser::reply::{header, nfsv3, record_mark, ReplyStatus};
+use crate::rpc::{
+    AcceptStat, AuthFlavor, AuthStat, Error, OpaqueAuth, RejectedReply, ReplyBody, RpcBody,
+    VersionMismatch,
+};
+use crate::serializer::server::serialize_struct::Serializer;
+use crate::task::{ProcReply, ProcResult};
+use crate::vfs::read_dir::{Cookie, CookieVerifier};
+use crate::vfs::{
+    self, acl, file, fs_info, get_acl, read_dir_plus, read_link, NfsRes, MAX_PATH_LEN,
+};
+
+const XID: u32 = 42;
+
+fn attr(file_id: u64) -> file::Attr {
+    file::Attr {
+        file_type: file::Type::Regular,
+        mode: 0o600,
+        nlink: 1,
+        uid: 0,
+        gid: 0,
+        size: 10,
+        used: 512,
+        device: file::Device { major: 0, minor: 0 },
+        fs_id: 1,
+        file_id,
+        atime: file::Time { seconds: 10, nanos: 0 },
+        mtime: file::Time { seconds: 20, nanos: 0 },
+        ctime: file::Time { seconds: 30, nanos: 0 },
+    }
+}
+
+fn entry(file_id: u64, name: &str) -> read_dir_plus::Entry {
+    read_dir_plus::Entry {
+        file_id,
+        file_name: file::Name::new(name.to_string()).unwrap(),
+        cookie: Cookie::new(file_id),
+        file_attr: Some(attr(file_id)),
+        file_handle: Some(file::Handle(file_id.to_be_bytes())),
+    }
+}
+
+async fn serialize(proc_result: Result<ProcResult<Slice>, Error>) -> Vec<u8> {
+    serialize_with_verifier(proc_result, OpaqueAuth::none()).await
+}
+
+async fn serialize_with_verifier(
+    proc_result: Result<ProcResult<Slice>, Error>,
+    verifier: OpaqueAuth,
+) -> Vec<u8> {
+    let mut serializer = Serializer::<Slice, _>::new(Vec::new());
+    serializer.form_reply(ProcReply { xid: XID, proc_result, call: None }, verifier).await.unwrap();
+    serializer.into_inner()
+}
+
+/// Splits a serialized reply into big-endian words, skipping the record mark.
+fn words(bytes: &[u8]) -> Vec<u32> {
+    assert_eq!(bytes.len() % 4, 0);
+    bytes[4..].chunks(4).map(|word| u32::from_be_bytes(word.try_into().unwrap())).collect()
+}
+
+/// Verifier which would be visible on the wire if a denied reply carried one.
+fn short_verifier() -> OpaqueAuth {
+    OpaqueAuth::new(AuthFlavor::Short, vec![0xAA; 8]).unwrap()
+}
+
+#[tokio::test]
+async fn read_dir_plus_round_trip() {
+    let success = read_dir_plus::Success {
+        dir_attr: None,
+        cookie_verifier: CookieVerifier::new([1, 2, 3, 4, 5, 6, 7, 8]),
+        entries: vec![entry(3, "a.txt"), entry(4, "b")],
+        eof: true,
+    };
+    let bytes = serialize(Ok(ProcResult::Nfs3(Box::new(NfsRes::ReadDirPlus(Ok(success)))))).await;
+
+    let mut src = Cursor::new(bytes.as_slice());
+    assert_eq!(record_mark(&mut src).unwrap(), bytes.len() - 4);
+    let reply = header(&mut src).unwrap();
+    assert_eq!(reply.xid, XID);
+    assert!(matches!(reply.status, ReplyStatus::Accepted { stat: AcceptStat::Success, .. }));
+
+    let Ok(decoded) = nfsv3::read_dir_plus(&mut src).unwrap() else {
+        panic!("expected READDIRPLUS success");
+    };
+    assert_eq!(src.position() as usize, bytes.len());
+    assert!(decoded.dir_attr.is_none());
+    assert_eq!(decoded.cookie_verifier.raw(), [1, 2, 3, 4, 5, 6, 7, 8]);
+    assert!(decoded.eof);
+
+    let expected = [entry(3, "a.txt"), entry(4, "b")];
+    assert_eq!(decoded.entries.len(), expected.len());
+    for (decoded, expected) in decoded.entries.iter().zip(expected.iter()) {
+        assert_eq!(decoded.file_id, expected.file_id);
+        assert_eq!(decoded.file_name, expected.file_name);
+        assert_eq!(decoded.cookie, expected.cookie);
+        assert_eq!(decoded.file_handle, expected.file_handle);
+        assert_eq!(decoded.file_attr.as_ref().unwrap().file_id, expected.file_id);
+    }
+}
+
+#[tokio::test]
+async fn read_dir_plus_fail_round_trip() {
+    let fail = read_dir_plus::Fail { error: vfs::Error::BadCookie, dir_attr: Some(attr(1)) };
+    let bytes = serialize(Ok(ProcResult::Nfs3(Box::new(NfsRes::ReadDirPlus(Err(fail)))))).await;
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    header(&mut src).unwrap();
+
+    let Err(decoded) = nfsv3::read_dir_plus(&mut src).unwrap() else {
+        panic!("expected READDIRPLUS failure");
+    };
+    assert_eq!(decoded.error, vfs::Error::BadCookie);
+    assert_eq!(decoded.dir_attr.unwrap().file_id, 1);
+}
+
+#[tokio::test]
+async fn read_link_target_bytes_round_trip() {
+    // The longest target allowed, not valid UTF-8 and of a length needing no padding.
+    let mut target = vec![b'a'; MAX_PATH_LEN];
+    target[0] = 0xFF;
+    let success = read_link::Success { symlink_attr: None, data: target.clone() };
+    let bytes = serialize(Ok(ProcResult::Nfs3(Box::new(NfsRes::ReadLink(Ok(success)))))).await;
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    header(&mut src).unwrap();
+
+    let Ok(decoded) = nfsv3::read_link(&mut src).unwrap() else {
+        panic!("expected READLINK success");
+    };
+    assert_eq!(src.position() as usize, bytes.len());
+    assert_eq!(decoded.data, target);
+}
+
+#[tokio::test]
+async fn fs_info_time_delta_round_trip() {
+    let success = fs_info::Success {
+        root_attr: Some(attr(1)),
+        read_max: 1024,
+        read_pref: 1024,
+        read_mult: 1,
+        write_max: 1024,
+        write_pref: 1024,
+        write_mult: 1,
+        read_dir_pref: 512,
+        max_file_size: u64::MAX,
+        time_delta: file::Time { seconds: 2, nanos: 1000 },
+        properties: fs_info::Properties::from_wire(fs_info::Properties::CANSETTIME),
+    };
+    let bytes = serialize(Ok(ProcResult::Nfs3(Box::new(NfsRes::FsInfo(Ok(success)))))).await;
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    header(&mut src).unwrap();
+
+    let Ok(decoded) = nfsv3::fs_info(&mut src).unwrap() else {
+        panic!("expected FSINFO success");
+    };
+    assert_eq!(src.position() as usize, bytes.len());
+    assert_eq!(decoded.time_delta, file::Time { seconds: 2, nanos: 1000 });
+    assert_eq!(decoded.max_file_size, u64::MAX);
+    assert!(decoded.properties.contains(fs_info::Properties::CANSETTIME));
+}
+
+/// Owner, owning group and one named user, with the mask a named entry requires.
+fn simple_acl() -> Vec<acl::Entry> {
+    use acl::{Entry, Tag};
+
+    vec![
+        Entry { tag: Tag::UserObj, id: 0, perm: Entry::READ | Entry::WRITE },
+        Entry { tag: Tag::User, id: 1000, perm: Entry::READ | Entry::WRITE },
+        Entry { tag: Tag::GroupObj, id: 0, perm: Entry::READ },
+        Entry { tag: Tag::Mask, id: 0, perm: Entry::READ | Entry::WRITE },
+        Entry { tag: Tag::Other, id: 0, perm: 0 },
+    ]
+}
+
+#[tokio::test]
+async fn get_acl_round_trip() {
+    let success = get_acl::Success {
+        file_attr: Some(attr(1)),
+        mask: acl::Mask::from_wire(acl::Mask::ALL),
+        access: simple_acl(),
+        default: simple_acl(),
+    };
+    let bytes = serialize(Ok(ProcResult::Nfs3(Box::new(NfsRes::GetAcl(Ok(success)))))).await;
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    header(&mut src).unwrap();
+
+    let Ok(decoded) = nfsv3::get_acl(&mut src).unwrap() else {
+        panic!("expected GETACL success");
+    };
+    assert_eq!(src.position() as usize, bytes.len());
+    assert_eq!(decoded.file_attr.unwrap().file_id, 1);
+    assert_eq!(decoded.mask.bits(), acl::Mask::ALL);
+    assert_eq!(decoded.access, simple_acl());
+    assert_eq!(decoded.default, simple_acl());
+}
+
+#[tokio::test]
+async fn get_acl_count_only_omits_entries() {
+    let success = get_acl::Success {
+        file_attr: None,
+        mask: acl::Mask::from_wire(acl::Mask::ACL_COUNT),
+        access: simple_acl(),
+        default: Vec::new(),
+    };
+    let bytes = serialize(Ok(ProcResult::Nfs3(Box::new(NfsRes::GetAcl(Ok(success)))))).await;
+
+    // Status, no attributes, mask, then counts with empty arrays.
+    assert_eq!(words(&bytes)[6..], [0, 0, acl::Mask::ACL_COUNT, 5, 0, 0, 0]);
+}
+
+#[tokio::test]
+async fn accepted_replies_carry_empty_auth_none_verifier() {
+    for (proc_result, expected) in [
+        (Ok(ProcResult::Nfs3(Box::new(NfsRes::Null))), AcceptStat::Success),
+        (Err(Error::ProcedureMismatch), AcceptStat::ProcUnavail),
+        (Err(Error::EnumDiscMismatch), AcceptStat::GarbageArgs),
+    ] {
+        let bytes = serialize(proc_result).await;
+        // Verifier flavor and body length follow xid, message type and reply status.
+        assert_eq!(words(&bytes)[3..5], [AuthFlavor::None as u32, 0]);
+
+        let mut src = Cursor::new(bytes.as_slice());
+        record_mark(&mut src).unwrap();
+        let ReplyStatus::Accepted { verf, stat } = header(&mut src).unwrap().status else {
+            panic!("reply should be accepted");
+        };
+        assert_eq!(verf, OpaqueAuth::none());
+        assert!(verf.body().is_empty());
+        assert_eq!(stat as u32, expected as u32);
+    }
+}
+
+#[tokio::test]
+async fn auth_error_reply_header() {
+    let bytes = serialize(Err(Error::Auth(AuthStat::TooWeak))).await;
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    let reply = header(&mut src).unwrap();
+    assert_eq!(reply.xid, XID);
+    assert!(matches!(reply.status, ReplyStatus::AuthError(AuthStat::TooWeak)));
+}
+
+#[tokio::test]
+async fn rpc_mismatch_reply_has_no_verifier() {
+    let versions = VersionMismatch { low: 2, high: 2 };
+    let bytes =
+        serialize_with_verifier(Err(Error::RpcVersionMismatch(versions)), short_verifier()).await;
+
+    assert_eq!(
+        words(&bytes),
+        [
+            XID,
+            RpcBody::Reply as u32,
+            ReplyBody::MsgDenied as u32,
+            RejectedReply::RpcMismatch as u32,
+            2,
+            2
+        ]
+    );
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    let reply = header(&mut src).unwrap();
+    assert!(matches!(reply.status, ReplyStatus::RpcMismatch(VersionMismatch { low: 2, high: 2 })));
+    assert_eq!(src.position() as usize, bytes.len());
+}
+
+#[tokio::test]
+async fn auth_error_reply_has_no_verifier() {
+    let bytes =
+        serialize_with_verifier(Err(Error::Auth(AuthStat::BadCred)), short_verifier()).await;
+
+    assert_eq!(
+        words(&bytes),
+        [
+            XID,
+            RpcBody::Reply as u32,
+            ReplyBody::MsgDenied as u32,
+            RejectedReply::AuthError as u32,
+            AuthStat::BadCred as u32
+        ]
+    );
+
+    let mut src = Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    let reply = header(&mut src).unwrap();
+    assert!(matches!(reply.status, ReplyStatus::AuthError(AuthStat::BadCred)));
+    assert_eq!(src.position() as usize, bytes.len());
+}
@@ -1,5 +1,7 @@
 use std::cmp::min;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use tokio::io::{AsyncRead, ReadBuf};
@@ -42,3 +44,31 @@ impl AsyncRead for MockSocket {
         Poll::Ready(Ok(()))
     }
 }
+
+/// Socket that hands out as much data as fits in each read and counts the reads.
+pub struct CountingSocket {
+    data: Vec<u8>,
+    position: usize,
+    reads: Arc<AtomicUsize>,
+}
+
+impl CountingSocket {
+    pub fn new(buf: &[u8], reads: Arc<AtomicUsize>) -> Self {
+        CountingSocket { data: buf.to_vec(), position: 0, reads }
+    }
+}
+
+impl AsyncRead for CountingSocket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let inner = self.get_mut();
+        inner.reads.fetch_add(1, Ordering::Relaxed);
+        let to_read = min(buf.remaining(), inner.data.len() - inner.position);
+        buf.put_slice(&inner.data[inner.position..inner.position + to_read]);
+        inner.position += to_read;
+        Poll::Ready(Ok(()))
+    }
+}
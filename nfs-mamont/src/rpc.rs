@@ -5,8 +5,18 @@ use num_derive::{FromPrimitive, ToPrimitive};
 
 pub const RPC_VERSION: u32 = 2;
 
+/// Maximum length of the body of an [`OpaqueAuth`] (RFC 5531 §8.2).
 pub const MAX_AUTH_SIZE: usize = 400;
 
+/// Maximum length of the machine name in AUTH_SYS credentials (RFC 5531 §A.1).
+pub const AUTH_SYS_MACHINE_NAME_LEN: usize = 255;
+
+/// Maximum number of supplementary groups in AUTH_SYS credentials (RFC 5531 §A.1).
+pub const AUTH_SYS_MAX_GIDS: usize = 16;
+
+/// Version of the RPCSEC_GSS credential format (RFC 2203 §5).
+pub const RPCSEC_GSS_VERSION: u32 = 1;
+
 #[derive(ToPrimitive, FromPrimitive)]
 pub enum AcceptStat {
     Success = 0,
@@ -47,22 +57,102 @@ pub enum ReplyBody {
     MsgDenied = 1,
 }
 
-/// Authentication flavors.
-#[derive(Debug, Clone, ToPrimitive, FromPrimitive)]
-#[cfg_attr(test, derive(PartialEq))]
+/// Authentication flavors (RFC 5531 §8.2, RFC 2203 §5).
+///
+/// The single place defining flavor numbers; parsers, serializers and the MOUNT
+/// service refer to flavors through this enum only.
+#[derive(Debug, Clone, PartialEq, Eq, ToPrimitive, FromPrimitive)]
 pub enum AuthFlavor {
+    /// `AUTH_NONE`: no authentication.
     None = 0,
+    /// `AUTH_SYS`: Unix user and group ids, see [`AuthSysParams`].
     Sys = 1,
+    /// `AUTH_SHORT`: shorthand handed out by a server for an earlier credential.
     Short = 2,
+    /// `AUTH_DH`: Diffie-Hellman authentication.
     Dh = 3,
+    /// `RPCSEC_GSS`: GSS-API security, see [`RpcGssCred`].
     RpcSecGss = 6,
 }
 
+/// An authenticator (`opaque_auth`): a flavor with a body of at most
+/// `MAX_AUTH_SIZE` (400) bytes.
+///
+/// The bound is checked whenever one is built, so every authenticator can be
+/// serialized.
 #[derive(Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct OpaqueAuth {
-    pub flavor: AuthFlavor,
-    pub body: Vec<u8>,
+    flavor: AuthFlavor,
+    body: Vec<u8>,
+}
+
+impl OpaqueAuth {
+    /// Builds an authenticator, failing with `Error::MaxElemLimit` if `body` is
+    /// longer than `MAX_AUTH_SIZE`.
+    pub fn new(flavor: AuthFlavor, body: Vec<u8>) -> Result<Self, Error> {
+        if body.len() > MAX_AUTH_SIZE {
+            return Err(Error::MaxElemLimit);
+        }
+        Ok(Self { flavor, body })
+    }
+
+    /// Returns the AUTH_NONE authenticator: flavor 0 with an empty body.
+    ///
+    /// This is the verifier of every accepted reply to an AUTH_NONE or AUTH_SYS call;
+    /// only flavors authenticating the server, such as RPCSEC_GSS, need another one.
+    pub fn none() -> Self {
+        Self { flavor: AuthFlavor::None, body: Vec::new() }
+    }
+
+    pub fn flavor(&self) -> &AuthFlavor {
+        &self.flavor
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+/// Body of an AUTH_SYS credential (`authsys_parms`).
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AuthSysParams {
+    pub stamp: u32,
+    pub machine_name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub gids: Vec<u32>,
+}
+
+/// RPCSEC_GSS control procedures (RFC 2203 §5).
+#[derive(Debug, Clone, Copy, ToPrimitive, FromPrimitive)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum RpcGssProc {
+    Data = 0,
+    Init = 1,
+    ContinueInit = 2,
+    Destroy = 3,
+}
+
+/// RPCSEC_GSS protection services (RFC 2203 §5).
+#[derive(Debug, Clone, Copy, ToPrimitive, FromPrimitive)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum RpcGssService {
+    None = 1,
+    Integrity = 2,
+    Privacy = 3,
+}
+
+/// Body of an RPCSEC_GSS credential (`rpc_gss_cred_vers_1_t`).
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RpcGssCred {
+    pub version: u32,
+    pub proc: RpcGssProc,
+    pub seq_num: u32,
+    pub service: RpcGssService,
+    pub handle: Vec<u8>,
 }
 
 pub enum RejectedReply {
@@ -85,10 +175,16 @@ pub enum Error {
     MaxElemLimit,
     /// An I/O error occurred.
     IO(io::Error),
+    /// The peer closed the connection between two messages.
+    ConnectionClosed,
+    /// The connection ended in the middle of a message.
+    TruncatedMessage,
     /// An enum discriminant mismatch occurred.
     EnumDiscMismatch,
     /// An incorrect string was encountered during UTF-8 conversion.
     IncorrectString(FromUtf8Error),
+    /// Non-zero XDR padding bytes were encountered in strict padding mode.
+    IncorrectPadding,
     /// An impossible type cast was attempted.
     ImpossibleTypeCast,
     /// A bad file handle was encountered.
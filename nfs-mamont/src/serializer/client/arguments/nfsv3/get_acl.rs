@@ -0,0 +1,10 @@
+use std::io::{Result, Write};
+
+use crate::serializer::files::file_handle;
+use crate::serializer::u32;
+use crate::vfs::get_acl::Args;
+
+/// Serializes the arguments [`Args`] for an NFSACL `GETACL` operation to the provided `Write` destination.
+pub fn get_acl_args(dest: &mut impl Write, arg: Args) -> Result<()> {
+    file_handle(dest, arg.file).and_then(|_| u32(dest, arg.mask.bits()))
+}
@@ -0,0 +1,13 @@
+use std::io::{Result, Write};
+
+use crate::serializer::files::{acl_list, file_handle};
+use crate::serializer::u32;
+use crate::vfs::set_acl::Args;
+
+/// Serializes the arguments [`Args`] for an NFSACL `SETACL` operation to the provided `Write` destination.
+pub fn set_acl_args(dest: &mut impl Write, arg: Args) -> Result<()> {
+    file_handle(dest, arg.file)?;
+    u32(dest, arg.mask.bits())?;
+    acl_list(dest, &arg.access, true, false)?;
+    acl_list(dest, &arg.default, true, true)
+}
@@ -3,10 +3,11 @@
 use std::io;
 use std::io::{ErrorKind, Write};
 
+use crate::consts::nfs_acl::NFS_ACL_DEFAULT;
 use crate::consts::nfsv3::NFS3_FHSIZE;
-use crate::serializer::{array, option, string_max_size, u32, u64, usize_as_u32, variant};
+use crate::serializer::{array, option, string_max_size, u32, usize_as_u32, variant};
 use crate::vfs;
-use crate::vfs::{file, DirOpArgs, MAX_PATH_LEN};
+use crate::vfs::{acl, file, DirOpArgs, MAX_PATH_LEN};
 
 /// Serializes [`vfs::file::Time`] into XDR `nfstime3`.
 pub fn nfs_time(dest: &mut impl Write, arg: file::Time) -> io::Result<()> {
@@ -25,33 +26,81 @@ pub fn error(dest: &mut impl Write, stat: vfs::Error) -> io::Result<()> {
 }
 
 /// Serializes [file::Type] as the XDR `ftype3` enum discriminant.
+#[cfg(test)]
 pub fn file_type(dest: &mut impl Write, file_type: file::Type) -> io::Result<()> {
     variant::<file::Type>(dest, file_type)
 }
 
+/// Size of an XDR `fattr3`, which has no variable-length fields.
+pub const FATTR3_SIZE: usize = 84;
+
 /// Serializes [`file::Attr`] as XDR `fattr3` (file attributes).
+///
+/// Attributes are part of almost every reply, so they are encoded into a stack array
+/// by [`encode_fattr3`] and written at once instead of field by field.
 pub fn file_attr(dest: &mut impl Write, attr: &file::Attr) -> io::Result<()> {
-    file_type(dest, attr.file_type)?;
-    u32(dest, attr.mode)?;
-    u32(dest, attr.nlink)?;
-    u32(dest, attr.uid)?;
-    u32(dest, attr.gid)?;
-    u64(dest, attr.size)?;
-    u64(dest, attr.used)?;
-    u32(dest, attr.device.major)?;
-    u32(dest, attr.device.minor)?;
-    u64(dest, attr.fs_id)?;
-    u64(dest, attr.file_id)?;
-    nfs_time(dest, attr.atime)?;
-    nfs_time(dest, attr.mtime)?;
-    nfs_time(dest, attr.ctime)
+    let mut encoded = [0; FATTR3_SIZE];
+    encode_fattr3(&mut encoded, attr);
+    dest.write_all(&encoded)
+}
+
+/// Encodes [`file::Attr`] as XDR `fattr3` into `dst`.
+#[inline]
+pub fn encode_fattr3(dst: &mut [u8; FATTR3_SIZE], attr: &file::Attr) {
+    let words = [
+        attr.file_type as u32,
+        attr.mode,
+        attr.nlink,
+        attr.uid,
+        attr.gid,
+        (attr.size >> 32) as u32,
+        attr.size as u32,
+        (attr.used >> 32) as u32,
+        attr.used as u32,
+        attr.device.major,
+        attr.device.minor,
+        (attr.fs_id >> 32) as u32,
+        attr.fs_id as u32,
+        (attr.file_id >> 32) as u32,
+        attr.file_id as u32,
+        attr.atime.seconds,
+        attr.atime.nanos,
+        attr.mtime.seconds,
+        attr.mtime.nanos,
+        attr.ctime.seconds,
+        attr.ctime.nanos,
+    ];
+    for (chunk, word) in dst.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
 }
 
+/// Size of an XDR `wcc_attr`.
+pub const WCC_ATTR_SIZE: usize = 24;
+
 /// Serializes [`file::WccAttr`] as XDR `wcc_attr` (weak cache consistency).
+///
+/// Like [`file_attr`], encodes into a stack array written at once.
 pub fn wcc_attr(dest: &mut impl Write, wcc: file::WccAttr) -> io::Result<()> {
-    u64(dest, wcc.size)?;
-    nfs_time(dest, wcc.mtime)?;
-    nfs_time(dest, wcc.ctime)
+    let mut encoded = [0; WCC_ATTR_SIZE];
+    encode_wcc_attr(&mut encoded, &wcc);
+    dest.write_all(&encoded)
+}
+
+/// Encodes [`file::WccAttr`] as XDR `wcc_attr` into `dst`.
+#[inline]
+pub fn encode_wcc_attr(dst: &mut [u8; WCC_ATTR_SIZE], wcc: &file::WccAttr) {
+    let words = [
+        (wcc.size >> 32) as u32,
+        wcc.size as u32,
+        wcc.mtime.seconds,
+        wcc.mtime.nanos,
+        wcc.ctime.seconds,
+        wcc.ctime.nanos,
+    ];
+    for (chunk, word) in dst.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
 }
 
 /// Serializes [`vfs::WccData`] as XDR `wcc_data` (before/after attributes).
@@ -81,11 +130,34 @@ pub fn dir_op_arg(dest: &mut impl Write, arg: DirOpArgs) -> io::Result<()> {
     file_handle(dest, arg.dir).and_then(|_| file_name(dest, arg.name))
 }
 
+/// Serializes an NFSACL entry count followed by the array of `entries`.
+///
+/// The array is left empty unless `with_entries` is set; entries of a `default` list
+/// carry the default ACL flag in their type.
+pub fn acl_list(
+    dest: &mut impl Write,
+    entries: &[acl::Entry],
+    with_entries: bool,
+    default: bool,
+) -> io::Result<()> {
+    usize_as_u32(dest, entries.len())?;
+    let entries = if with_entries { entries } else { &[] };
+    usize_as_u32(dest, entries.len())?;
+    let flag = if default { NFS_ACL_DEFAULT } else { 0 };
+    for entry in entries {
+        u32(dest, entry.tag as u32 | flag)?;
+        u32(dest, entry.id)?;
+        u32(dest, entry.perm)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
     use super::*;
+    use crate::serializer::u64;
     use crate::vfs::file;
 
     #[test]
@@ -236,4 +308,53 @@ mod tests {
         wcc_attr(&mut buffer, attr).unwrap();
         assert_eq!(buffer.into_inner(), DATA);
     }
+
+    /// Field-by-field `fattr3` encoding through [`Write`], the reference for
+    /// [`encode_fattr3`].
+    fn file_attr_by_field(dest: &mut impl Write, attr: &file::Attr) -> io::Result<()> {
+        file_type(dest, attr.file_type)?;
+        u32(dest, attr.mode)?;
+        u32(dest, attr.nlink)?;
+        u32(dest, attr.uid)?;
+        u32(dest, attr.gid)?;
+        u64(dest, attr.size)?;
+        u64(dest, attr.used)?;
+        u32(dest, attr.device.major)?;
+        u32(dest, attr.device.minor)?;
+        u64(dest, attr.fs_id)?;
+        u64(dest, attr.file_id)?;
+        nfs_time(dest, attr.atime)?;
+        nfs_time(dest, attr.mtime)?;
+        nfs_time(dest, attr.ctime)
+    }
+
+    #[test]
+    fn test_encode_fattr3_matches_field_by_field() {
+        let attr = file::Attr {
+            file_type: file::Type::Symlink,
+            mode: 0o4755,
+            nlink: 3,
+            uid: 1000,
+            gid: u32::MAX,
+            size: 0x0102_0304_0506_0708,
+            used: u64::MAX,
+            device: file::Device { major: 8, minor: 0x0A0B_0C0D },
+            fs_id: 0x1122_3344_5566_7788,
+            file_id: 1 << 40,
+            atime: file::Time { seconds: 1, nanos: 999_999_999 },
+            mtime: file::Time { seconds: u32::MAX, nanos: 0 },
+            ctime: file::Time { seconds: 0x7FFF_FFFF, nanos: 42 },
+        };
+
+        let mut by_field = Vec::new();
+        file_attr_by_field(&mut by_field, &attr).unwrap();
+        let mut encoded = [0; FATTR3_SIZE];
+        encode_fattr3(&mut encoded, &attr);
+        assert_eq!(by_field.len(), FATTR3_SIZE);
+        assert_eq!(encoded.as_slice(), by_field.as_slice());
+
+        let mut written = Vec::new();
+        file_attr(&mut written, &attr).unwrap();
+        assert_eq!(written, by_field);
+    }
 }
@@ -0,0 +1,21 @@
+//! XDR serializers for the NFSACL `GETACL` procedure.
+
+use std::io;
+use std::io::Write;
+
+use crate::serializer::files::{acl_list, file_attr};
+use crate::serializer::{option, u32};
+use crate::vfs::{acl, get_acl};
+
+/// Serializes [`get_acl::Success`] (GETACL3resok body) into XDR.
+pub fn result_ok(dest: &mut impl Write, arg: get_acl::Success) -> io::Result<()> {
+    option(dest, arg.file_attr, |attr, dest| file_attr(dest, &attr))?;
+    u32(dest, arg.mask.bits())?;
+    acl_list(dest, &arg.access, arg.mask.contains(acl::Mask::ACL), false)?;
+    acl_list(dest, &arg.default, arg.mask.contains(acl::Mask::DEFAULT_ACL), true)
+}
+
+/// Serializes [`get_acl::Fail`] (GETACL3resfail body) into XDR.
+pub fn result_fail(dest: &mut impl Write, arg: get_acl::Fail) -> io::Result<()> {
+    option(dest, arg.file_attr, |attr, dest| file_attr(dest, &attr))
+}
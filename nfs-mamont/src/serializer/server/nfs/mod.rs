@@ -1,6 +1,6 @@
 //! NFSv3-specific XDR serializers.
 //!
-//! Each submodule corresponds to an NFSv3 procedure and provides helpers that
+//! Each submodule corresponds to an NFSv3 or NFSACL procedure and provides helpers that
 //! serialize the associated `crate::vfs::*` result types into XDR.
 
 pub mod access;
@@ -8,6 +8,7 @@ pub mod commit;
 pub mod create;
 pub mod fs_info;
 pub mod fs_stat;
+pub mod get_acl;
 pub mod get_attr;
 pub mod link;
 pub mod lookup;
@@ -21,6 +22,7 @@ pub mod read_link;
 pub mod remove;
 pub mod rename;
 pub mod rm_dir;
+pub mod set_acl;
 pub mod set_attr;
 pub mod symlink;
 pub mod write;
@@ -3,14 +3,14 @@
 use std::io;
 use std::io::Write;
 
-use crate::serializer::files::{file_attr, file_path};
-use crate::serializer::option;
-use crate::vfs::read_link;
+use crate::serializer::files::file_attr;
+use crate::serializer::{option, vec_max_size};
+use crate::vfs::{read_link, MAX_PATH_LEN};
 
 /// Serializes [`read_link::Success`] (READLINK3resok body) into XDR.
 pub fn result_ok(dest: &mut impl Write, arg: read_link::Success) -> io::Result<()> {
     option(dest, arg.symlink_attr, |attr, dest| file_attr(dest, &attr))?;
-    file_path(dest, arg.data)
+    vec_max_size(dest, &arg.data, MAX_PATH_LEN)
 }
 
 /// Serializes [`read_link::Fail`] (READLINK3resfail body) into XDR.
@@ -0,0 +1,18 @@
+//! XDR serializers for the NFSACL `SETACL` procedure.
+
+use std::io;
+use std::io::Write;
+
+use crate::serializer::files::file_attr;
+use crate::serializer::option;
+use crate::vfs::set_acl;
+
+/// Serializes [`set_acl::Success`] (SETACL3resok body) into XDR.
+pub fn result_ok(dest: &mut impl Write, arg: set_acl::Success) -> io::Result<()> {
+    option(dest, arg.file_attr, |attr, dest| file_attr(dest, &attr))
+}
+
+/// Serializes [`set_acl::Fail`] (SETACL3resfail body) into XDR.
+pub fn result_fail(dest: &mut impl Write, arg: set_acl::Fail) -> io::Result<()> {
+    option(dest, arg.file_attr, |attr, dest| file_attr(dest, &attr))
+}
@@ -11,6 +11,6 @@ use crate::serializer::{variant, vec_max_size};
 
 /// Serializes [`OpaqueAuth`] (flavor + body) into XDR.
 pub fn auth(dest: &mut impl Write, data: OpaqueAuth) -> io::Result<()> {
-    variant(dest, data.flavor)?;
-    vec_max_size(dest, &data.body, MAX_AUTH_SIZE)
+    variant(dest, data.flavor().clone())?;
+    vec_max_size(dest, data.body(), MAX_AUTH_SIZE)
 }
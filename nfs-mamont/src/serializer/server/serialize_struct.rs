@@ -7,8 +7,10 @@
 
 use std::io;
 use std::io::{ErrorKind, IoSlice, Write};
+use std::sync::Arc;
 
 use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tracing::warn;
 
 use crate::allocator::Buffer;
 use crate::mount::MountRes;
@@ -21,9 +23,9 @@ use crate::vfs::{NfsRes, STATUS_OK};
 
 use super::mount::mnt;
 use super::nfs::{
-    access, commit, create, error, fs_info, fs_stat, get_attr, link, lookup, mk_dir, mk_node,
-    path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_attr, symlink,
-    write,
+    access, commit, create, error, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir,
+    mk_node, path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl,
+    set_attr, symlink, write,
 };
 use super::nlm;
 use super::rpc::auth;
@@ -33,10 +35,21 @@ use super::rpc::auth;
 /// this size is enough to hold only arguments without opaque data ([`Buffer`] in [`crate::vfs::read::Success`])
 const DEFAULT_SIZE: usize = 4096;
 
+/// Most buffers passed to a single vectored write, the `IOV_MAX` of Linux.
+const MAX_IOVECS: usize = 1024;
+
 /// Max size of RMS fragment data
 /// (<https://datatracker.ietf.org/doc/html/rfc5531#autoid-19>)
 const MAX_FRAGMENT_SIZE: usize = 0x7FFF_FFFF;
 
+/// Default cap on the size of a single reply, see
+/// [`crate::ServerContext::with_max_reply_bytes`].
+pub const DEFAULT_MAX_REPLY_BYTES: usize = 8 * 1024 * 1024;
+
+/// Upper bound of the bytes a reply spends outside READ data and directory entries:
+/// the RPC header with the largest verifier, the status and the post-op attributes.
+pub const MAX_REPLY_OVERHEAD: usize = 1024;
+
 /// Header mask of RMS
 /// (<https://datatracker.ietf.org/doc/html/rfc5531#autoid-19>)
 const HEADER_MASK: usize = 0x8000_0000;
@@ -69,13 +82,39 @@ pub struct Serializer<B: Buffer, T: AsyncWrite + Unpin> {
 impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
     /// Creates a reply serializer writing XDR bytes to the provided async writer.
     pub fn new(writer: T) -> Self {
-        Self { buffer: WriteBuffer::new(writer, DEFAULT_SIZE) }
+        Self { buffer: WriteBuffer::new(writer, DEFAULT_SIZE, DEFAULT_MAX_REPLY_BYTES) }
     }
 
     /// Creates a reply serializer with an explicit internal buffer capacity.
-    #[allow(dead_code)]
-    fn with_capacity(writer: T, capacity: usize) -> Self {
-        Self { buffer: WriteBuffer::new(writer, capacity) }
+    pub fn with_capacity(writer: T, capacity: usize) -> Self {
+        Self { buffer: WriteBuffer::new(writer, capacity, DEFAULT_MAX_REPLY_BYTES) }
+    }
+
+    /// Caps the size of a single reply, excluding its record mark, at `len` bytes
+    /// instead of [`DEFAULT_MAX_REPLY_BYTES`].
+    ///
+    /// A reply exceeding the cap is answered with `SYSTEM_ERR` instead of being assembled.
+    pub fn with_max_reply_bytes(mut self, len: usize) -> Self {
+        self.buffer.max_reply_bytes = len;
+        self
+    }
+
+    /// Compresses the data of READ replies with zstd at `level`.
+    ///
+    /// This is not part of NFSv3 and only cooperating clients can read such replies:
+    /// the opaque data of `READ3resok` holds a zstd frame instead of the file data,
+    /// while its `count` keeps the number of bytes read, i.e. the decompressed size.
+    #[cfg(feature = "compression")]
+    pub fn with_read_compression(mut self, level: i32) -> Self {
+        self.buffer.read_compression = Some(level);
+        self
+    }
+
+    /// Consumes the serializer and returns the underlying writer.
+    ///
+    /// Every reply is flushed by [`Self::form_reply`], so no staged bytes are lost.
+    pub fn into_inner(self) -> T {
+        self.buffer.socket
     }
 
     /// Serializes a [`ProcResult`] into its XDR reply body and writes it to the underlying writer.
@@ -84,6 +123,9 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
             ProcResult::Nfs3(data) => self.process_nfs3(data).await,
             ProcResult::Mount(data) => self.process_mount(data).await,
             ProcResult::Nlm4(data) => self.process_nlm(data).await,
+            ProcResult::Replay(_) => {
+                Err(io::Error::new(ErrorKind::InvalidInput, "replayed record within a reply"))
+            }
         }
     }
 
@@ -164,6 +206,12 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
             NfsRes::Commit(res) => {
                 nfs_result!(self, res, commit::result_ok, commit::result_fail)
             }
+            NfsRes::GetAcl(res) => {
+                nfs_result!(self, res, get_acl::result_ok, get_acl::result_fail)
+            }
+            NfsRes::SetAcl(res) => {
+                nfs_result!(self, res, set_acl::result_ok, set_acl::result_fail)
+            }
         }
     }
 
@@ -226,20 +274,66 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
     /// *   `verifier` - an authentication verifier of [`OpaqueAuth`] type that the server generates in
     ///     order to validate itself to the client
     ///
+    /// A reply failing halfway is discarded, so the next one starts from an empty buffer.
+    ///
     /// TODO:(<https://github.com/RMamonts/nfs-mamont/issues/137>)
     pub async fn form_reply(
         &mut self,
         reply: ProcReply<B>,
         verifier: OpaqueAuth,
     ) -> io::Result<()> {
+        let result = self.write_reply(reply, verifier).await;
+        if result.is_err() {
+            self.buffer.clean();
+        }
+        result
+    }
+
+    /// Returns `true` once a socket write failed, leaving part of a record sent.
+    ///
+    /// The client can no longer find the boundaries of later replies, so the
+    /// connection should be closed.
+    pub fn is_broken(&self) -> bool {
+        self.buffer.broken
+    }
+
+    /// Like [`Self::form_reply`], but also returns the record sent, record mark included,
+    /// so it can answer a retransmission of the call.
+    ///
+    /// Replies streaming READ data are not recorded and yield `None`.
+    pub async fn form_recorded_reply(
+        &mut self,
+        reply: ProcReply<B>,
+        verifier: OpaqueAuth,
+    ) -> io::Result<Option<Arc<[u8]>>> {
+        self.buffer.recording = true;
+        let result = self.form_reply(reply, verifier).await;
+        self.buffer.recording = false;
+        let record = self.buffer.recorded.take();
+        result.map(|()| record)
+    }
+
+    async fn write_reply(&mut self, reply: ProcReply<B>, verifier: OpaqueAuth) -> io::Result<()> {
+        if let Ok(ProcResult::Replay(record)) = &reply.proc_result {
+            return self.buffer.send_record(record).await;
+        }
         u32(&mut self.buffer, reply.xid)?;
         u32(&mut self.buffer, RpcBody::Reply as u32)?;
         match reply.proc_result {
             Ok(proc) => {
                 u32(&mut self.buffer, ReplyBody::MsgAccepted as u32)?;
                 auth(&mut self.buffer, verifier)?;
+                let stat_at = self.buffer.buf.len();
                 u32(&mut self.buffer, AcceptStat::Success as u32)?;
-                self.process_result(proc).await
+                match self.process_result(proc).await {
+                    Err(error) if error.kind() == ErrorKind::OutOfMemory => {
+                        warn!(xid = reply.xid, %error, "reply replaced with SYSTEM_ERR");
+                        self.buffer.buf.truncate(stat_at);
+                        u32(&mut self.buffer, AcceptStat::SystemErr as u32)?;
+                        self.buffer.send_inner_buffer().await
+                    }
+                    result => result,
+                }
             }
             Err(err) => {
                 match err {
@@ -248,6 +342,7 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
                     | Error::MessageTypeMismatch
                     | Error::EnumDiscMismatch
                     | Error::MaxElemLimit
+                    | Error::IncorrectPadding
                     | Error::IncorrectString(_) => {
                         u32(&mut self.buffer, ReplyBody::MsgAccepted as u32)?;
                         auth(&mut self.buffer, verifier)?;
@@ -288,7 +383,7 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
                         u32(&mut self.buffer, info.high)?;
                         self.buffer.send_inner_buffer().await
                     }
-                    Error::IO(_) => {
+                    Error::IO(_) | Error::ConnectionClosed | Error::TruncatedMessage => {
                         u32(&mut self.buffer, ReplyBody::MsgAccepted as u32)?;
                         auth(&mut self.buffer, verifier)?;
                         u32(&mut self.buffer, AcceptStat::SystemErr as u32)?;
@@ -304,12 +399,25 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Serializer<B, T> {
 struct WriteBuffer<B: Buffer, T: AsyncWrite + Unpin> {
     socket: T,
     buf: Vec<u8>,
+    /// Largest reply, excluding the record mark, the buffer assembles.
+    max_reply_bytes: usize,
+    /// Set once a socket write fails, leaving an unknown part of a record sent.
+    broken: bool,
+    /// Whether the next record sent by [`Self::send_inner_buffer`] is kept in `recorded`.
+    recording: bool,
+    recorded: Option<Arc<[u8]>>,
+    /// zstd level READ data is compressed at, if at all.
+    #[cfg(feature = "compression")]
+    read_compression: Option<i32>,
     _phantom: std::marker::PhantomData<B>,
 }
 
 impl<B: Buffer, T: AsyncWrite + Unpin> Write for WriteBuffer<B, T> {
     /// Writes raw bytes into the internal staging buffer (not directly to the socket).
+    ///
+    /// Fails with [`ErrorKind::OutOfMemory`] instead of growing the reply past its cap.
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_reply_size(buf.len())?;
         self.buf.extend_from_slice(buf);
         Ok(buf.len())
     }
@@ -322,10 +430,16 @@ impl<B: Buffer, T: AsyncWrite + Unpin> Write for WriteBuffer<B, T> {
 
 impl<B: Buffer, T: AsyncWrite + Unpin> WriteBuffer<B, T> {
     /// Creates a new buffer around an async writer with a fixed preallocated capacity.
-    fn new(socket: T, capacity: usize) -> WriteBuffer<B, T> {
+    fn new(socket: T, capacity: usize, max_reply_bytes: usize) -> WriteBuffer<B, T> {
         let mut buffer = WriteBuffer {
             socket,
             buf: Vec::with_capacity(capacity),
+            max_reply_bytes,
+            broken: false,
+            recording: false,
+            recorded: None,
+            #[cfg(feature = "compression")]
+            read_compression: None,
             _phantom: std::marker::PhantomData,
         };
         buffer.clean();
@@ -340,6 +454,18 @@ impl<B: Buffer, T: AsyncWrite + Unpin> WriteBuffer<B, T> {
         self.buf.extend_from_slice(&[0, 0, 0, 0]);
     }
 
+    /// Checks that `additional` more bytes keep the reply within its cap.
+    fn check_reply_size(&self, additional: usize) -> io::Result<()> {
+        let size = self.buf.len().saturating_sub(HEADER_SIZE).saturating_add(additional);
+        if size > self.max_reply_bytes {
+            return Err(io::Error::new(
+                ErrorKind::OutOfMemory,
+                format!("reply of at least {size} bytes exceeds {}", self.max_reply_bytes),
+            ));
+        }
+        Ok(())
+    }
+
     fn append_fragment_size(&mut self, size: usize) -> io::Result<()> {
         // now only single RMS fragment is allowed
         // TODO(https://github.com/RMamonts/nfs-mamont/issues/103)
@@ -359,16 +485,45 @@ impl<B: Buffer, T: AsyncWrite + Unpin> WriteBuffer<B, T> {
     /// Flushes the staged XDR bytes to the underlying writer.
     async fn send_inner_buffer(&mut self) -> io::Result<()> {
         self.append_fragment_size(self.buf.len().saturating_sub(HEADER_SIZE))?;
-        self.socket.write_all(&self.buf).await?;
+        let result = self.socket.write_all(&self.buf).await;
+        self.broken |= result.is_err();
+        result?;
+        if self.recording {
+            self.recorded = Some(Arc::from(self.buf.as_slice()));
+        }
         self.clean();
         Ok(())
     }
 
+    /// Writes a complete record, record mark included, as it was sent before.
+    async fn send_record(&mut self, record: &[u8]) -> io::Result<()> {
+        let result = self.socket.write_all(record).await;
+        self.broken |= result.is_err();
+        result
+    }
+
     /// Flushes the staged XDR bytes followed by a streamed payload [`Buffer`] (used for READ data).
     ///
-    /// Uses vectored I/O to coalesce all data chunks and padding into a single
-    /// `writev`-style syscall, reducing kernel transitions.
+    /// Uses vectored I/O to coalesce the staged bytes, all data chunks and padding into a
+    /// single `writev`-style syscall, reducing kernel transitions.
     async fn send_inner_with_buffer(&mut self, buffer: B, count: usize) -> io::Result<()> {
+        #[cfg(feature = "compression")]
+        if let Some(level) = self.read_compression {
+            let compressed = compress(buffer.chunks(), level)?;
+            // The buffer goes back to the allocator before waiting on the socket.
+            drop(buffer);
+            let count = compressed.len();
+            return self.send_inner_with_data(std::iter::once(compressed.as_slice()), count).await;
+        }
+        self.send_inner_with_data(buffer.chunks(), count).await
+    }
+
+    /// Flushes the staged XDR bytes followed by `count` bytes of `data` as XDR opaque data.
+    async fn send_inner_with_data<'a>(
+        &mut self,
+        data: impl Iterator<Item = &'a [u8]>,
+        count: usize,
+    ) -> io::Result<()> {
         // this place is a bit paradox
         // In READ procedure (https://datatracker.ietf.org/doc/html/rfc1813#autoid-25) opaque data
         // (which is represented with Buffer in vfs::read::Success) from XDR
@@ -377,54 +532,61 @@ impl<B: Buffer, T: AsyncWrite + Unpin> WriteBuffer<B, T> {
         //    (an unsigned integer) followed by the encoding of each of the array's
         //    elements, starting with element 0 and progressing through element n-1.
         // so we need to pass size of buffer before actual opaque data
+        let padding = (ALIGNMENT - count % ALIGNMENT) % ALIGNMENT;
+        self.check_reply_size(ALIGNMENT + count + padding)?;
         u32(&mut self.buf, count as u32)?;
 
-        let padding = (ALIGNMENT - count % ALIGNMENT) % ALIGNMENT;
         self.append_fragment_size(self.buf.len().saturating_sub(HEADER_SIZE) + count + padding)?;
 
-        self.socket.write_all(&self.buf).await?;
-
         let padding_bytes = [0u8; ALIGNMENT];
-
-        let mut written: usize = 0;
-        let total_payload: usize = buffer.len() + padding;
-
-        let mut iov: Vec<IoSlice<'_>> = Vec::with_capacity(buffer.chunks().count() + 1);
-
-        while written < total_payload {
-            iov.clear();
-            let mut to_skip = written;
-
-            for chunk in buffer.chunks() {
-                if to_skip == 0 {
-                    iov.push(IoSlice::new(chunk));
-                } else if to_skip < chunk.len() {
-                    iov.push(IoSlice::new(&chunk[to_skip..]));
-                    to_skip = 0;
-                } else {
-                    to_skip -= chunk.len();
-                }
-            }
-
-            if to_skip < padding {
-                iov.push(IoSlice::new(&padding_bytes[to_skip..padding]));
-            }
-
-            if !iov.is_empty() {
-                let n = self.socket.write_vectored(&iov).await?;
-                if n == 0 {
-                    return Err(io::Error::new(
-                        ErrorKind::WriteZero,
-                        "failed to write data to socket",
-                    ));
-                }
-                written += n;
-            } else {
-                break;
-            }
+        let mut parts = vec![self.buf.as_slice()];
+        for chunk in data {
+            parts.push(chunk);
         }
+        parts.push(&padding_bytes[..padding]);
+        parts.retain(|part| !part.is_empty());
+        let result = write_all_vectored(&mut self.socket, &parts).await;
+        self.broken |= result.is_err();
+        result?;
 
         self.clean();
         Ok(())
     }
 }
+
+/// Compresses `chunks` into a single zstd frame.
+#[cfg(feature = "compression")]
+fn compress<'a>(chunks: impl Iterator<Item = &'a [u8]>, level: i32) -> io::Result<Vec<u8>> {
+    let mut encoder = zstd::stream::Encoder::new(Vec::new(), level)?;
+    for chunk in chunks {
+        encoder.write_all(chunk)?;
+    }
+    encoder.finish()
+}
+
+/// Writes all of `parts` in as few `writev` calls as the socket accepts, resuming
+/// after short writes in the middle of a part.
+async fn write_all_vectored(
+    socket: &mut (impl AsyncWrite + Unpin),
+    parts: &[&[u8]],
+) -> io::Result<()> {
+    let mut part = 0;
+    let mut offset = 0;
+    let mut iov = Vec::with_capacity(parts.len().min(MAX_IOVECS));
+    while part < parts.len() {
+        iov.clear();
+        iov.push(IoSlice::new(&parts[part][offset..]));
+        iov.extend(parts[part + 1..].iter().take(MAX_IOVECS - 1).map(|part| IoSlice::new(part)));
+        let mut written = socket.write_vectored(&iov).await?;
+        if written == 0 {
+            return Err(io::Error::new(ErrorKind::WriteZero, "failed to write data to socket"));
+        }
+        while part < parts.len() && written >= parts[part].len() - offset {
+            written -= parts[part].len() - offset;
+            part += 1;
+            offset = 0;
+        }
+        offset += written;
+    }
+    Ok(())
+}
@@ -1 +1,2 @@
 mod primitive;
+mod serialize_struct;
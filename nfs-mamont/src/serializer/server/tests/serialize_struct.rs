@@ -0,0 +1,396 @@
+use std::io::{self, Cursor, IoSlice};
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::AsyncWrite;
+
+use crate::allocator::{Allocator, Buffer, Impl, Slice};
+use crate::parser::nfsv3::file;
+use crate::parser::primitive::{u32, vector};
+use crate::rpc::{AcceptStat, AuthFlavor, Error, OpaqueAuth, ReplyBody, RpcBody};
+use crate::serializer::server::serialize_struct::Serializer;
+use crate::serializer::{bool, u32 as put_u32, u64 as put_u64};
+use crate::task::{ProcReply, ProcResult};
+use crate::vfs::{self, get_attr, read, read_dir, read_dir_plus, NfsRes, STATUS_OK};
+
+const XID: u32 = 0x1234;
+
+/// Record marking bit of the RPC fragment header
+const LAST_FRAGMENT: u32 = 0x8000_0000;
+
+fn attr() -> vfs::file::Attr {
+    vfs::file::Attr {
+        file_type: vfs::file::Type::Regular,
+        mode: 0o644,
+        nlink: 1,
+        uid: 1000,
+        gid: 1000,
+        size: 42,
+        used: 4096,
+        device: vfs::file::Device { major: 0, minor: 0 },
+        fs_id: 7,
+        file_id: 99,
+        atime: vfs::file::Time { seconds: 1, nanos: 2 },
+        mtime: vfs::file::Time { seconds: 3, nanos: 4 },
+        ctime: vfs::file::Time { seconds: 5, nanos: 6 },
+    }
+}
+
+#[tokio::test]
+async fn get_attr_reply_round_trip() {
+    let mut serializer = Serializer::<Slice, _>::new(Vec::new());
+    let reply = ProcReply {
+        xid: XID,
+        proc_result: Ok(ProcResult::Nfs3(Box::new(NfsRes::GetAttr(Ok(get_attr::Success {
+            object: attr(),
+        }))))),
+        call: None,
+    };
+    let verifier = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    serializer.form_reply(reply, verifier).await.unwrap();
+
+    let bytes = serializer.into_inner();
+    let mut src = Cursor::new(bytes.as_slice());
+
+    let header = u32(&mut src).unwrap();
+    assert_eq!(header & LAST_FRAGMENT, LAST_FRAGMENT);
+    assert_eq!((header & !LAST_FRAGMENT) as usize, bytes.len() - 4);
+
+    assert_eq!(u32(&mut src).unwrap(), XID);
+    assert_eq!(u32(&mut src).unwrap(), RpcBody::Reply as u32);
+    assert_eq!(u32(&mut src).unwrap(), ReplyBody::MsgAccepted as u32);
+    assert_eq!(u32(&mut src).unwrap(), AuthFlavor::None as u32);
+    assert!(vector(&mut src).unwrap().is_empty());
+    assert_eq!(u32(&mut src).unwrap(), AcceptStat::Success as u32);
+    assert_eq!(u32(&mut src).unwrap() as usize, STATUS_OK);
+
+    let decoded = file::attr(&mut src).unwrap();
+    let expected = attr();
+    assert_eq!(decoded.mode, expected.mode);
+    assert_eq!(decoded.size, expected.size);
+    assert_eq!(decoded.used, expected.used);
+    assert_eq!(decoded.file_id, expected.file_id);
+    assert_eq!(decoded.mtime, expected.mtime);
+    assert_eq!(src.position() as usize, bytes.len());
+}
+
+/// Replies built the way procedure tasks build them serialize to the exact RPC record.
+#[tokio::test]
+async fn proc_reply_serializes_to_exact_bytes() {
+    let null = ProcReply::<Slice> {
+        xid: XID,
+        proc_result: Ok(ProcResult::Nfs3(Box::new(NfsRes::Null))),
+        call: None,
+    };
+    let rejected =
+        ProcReply::<Slice> { xid: XID + 1, proc_result: Err(Error::ProcedureMismatch), call: None };
+
+    for (reply, xid, accept_stat) in
+        [(null, XID, AcceptStat::Success), (rejected, XID + 1, AcceptStat::ProcUnavail)]
+    {
+        let mut serializer = Serializer::<Slice, _>::new(Vec::new());
+        let verifier = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+        serializer.form_reply(reply, verifier).await.unwrap();
+
+        let words = [
+            LAST_FRAGMENT | 24,
+            xid,
+            RpcBody::Reply as u32,
+            ReplyBody::MsgAccepted as u32,
+            AuthFlavor::None as u32,
+            0,
+            accept_stat as u32,
+        ];
+        let expected = words.iter().flat_map(|word| word.to_be_bytes()).collect::<Vec<_>>();
+        assert_eq!(serializer.into_inner(), expected);
+    }
+}
+
+/// The serializer is usable through the crate root, and a buffer starting out smaller
+/// than the reply grows to hold it.
+#[tokio::test]
+async fn serializer_from_crate_root_grows_a_small_buffer() {
+    let mut serializer = crate::Serializer::<Slice, _>::with_capacity(Vec::new(), 8);
+    let reply = crate::ProcReply {
+        xid: XID,
+        proc_result: Ok(crate::ProcResult::Nfs3(Box::new(NfsRes::GetAttr(Ok(
+            get_attr::Success { object: attr() },
+        ))))),
+        call: None,
+    };
+    serializer.form_reply(reply, crate::OpaqueAuth::none()).await.unwrap();
+
+    let bytes = serializer.into_inner();
+    let mut src = Cursor::new(bytes.as_slice());
+    assert_eq!(u32(&mut src).unwrap(), LAST_FRAGMENT | (bytes.len() - 4) as u32);
+    assert_eq!(u32(&mut src).unwrap(), XID);
+    src.set_position(4 * 7);
+    assert_eq!(u32(&mut src).unwrap() as usize, STATUS_OK);
+    assert_eq!(file::attr(&mut src).unwrap().file_id, attr().file_id);
+    assert_eq!(src.position() as usize, bytes.len());
+}
+
+/// A reply exceeding the cap is not assembled; the call is answered with SYSTEM_ERR.
+#[tokio::test]
+async fn oversized_reply_is_replaced_with_system_err() {
+    let mut serializer = Serializer::<Slice, _>::new(Vec::new()).with_max_reply_bytes(256);
+    let entries = (1..=16)
+        .map(|id| read_dir::Entry {
+            file_id: id,
+            file_name: vfs::file::Name::new(format!("entry{id:07}")).unwrap(),
+            cookie: read_dir::Cookie::new(id),
+        })
+        .collect();
+    let success = read_dir::Success {
+        dir_attr: Some(attr()),
+        cookie_verifier: read_dir::CookieVerifier::new([0; 8]),
+        entries,
+        eof: true,
+    };
+    let reply = ProcReply {
+        xid: XID,
+        proc_result: Ok(ProcResult::Nfs3(Box::new(NfsRes::ReadDir(Ok(success))))),
+        call: None,
+    };
+    let verifier = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    serializer.form_reply(reply, verifier).await.unwrap();
+
+    let bytes = serializer.into_inner();
+    let mut src = Cursor::new(bytes.as_slice());
+    assert_eq!(u32(&mut src).unwrap(), LAST_FRAGMENT | (bytes.len() - 4) as u32);
+    assert_eq!(u32(&mut src).unwrap(), XID);
+    assert_eq!(u32(&mut src).unwrap(), RpcBody::Reply as u32);
+    assert_eq!(u32(&mut src).unwrap(), ReplyBody::MsgAccepted as u32);
+    assert_eq!(u32(&mut src).unwrap(), AuthFlavor::None as u32);
+    assert!(vector(&mut src).unwrap().is_empty());
+    assert_eq!(u32(&mut src).unwrap(), AcceptStat::SystemErr as u32);
+    assert_eq!(src.position() as usize, bytes.len());
+}
+
+/// Socket accepting at most `max_write` bytes per call, like a congested connection.
+struct ShortWriter {
+    out: Vec<u8>,
+    max_write: usize,
+    calls: usize,
+    /// Number of calls succeeding before every further call fails.
+    fail_after: usize,
+}
+
+impl ShortWriter {
+    fn new(max_write: usize) -> Self {
+        Self { out: Vec::new(), max_write, calls: 0, fail_after: usize::MAX }
+    }
+}
+
+impl AsyncWrite for ShortWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[IoSlice::new(buf)])
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        if self.calls == self.fail_after {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        self.calls += 1;
+        let mut budget = self.max_write;
+        for buf in bufs {
+            let take = buf.len().min(budget);
+            self.out.extend_from_slice(&buf[..take]);
+            budget -= take;
+        }
+        Poll::Ready(Ok(self.max_write - budget))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// READ reply carrying `data` in a slice chained from the buffers of `allocator`.
+async fn read_reply(allocator: &Impl, data: &[u8]) -> ProcReply<Slice> {
+    let mut slice = allocator.allocate(NonZeroUsize::new(data.len()).unwrap()).await.unwrap();
+    let mut rest = data;
+    for chunk in slice.chunks_mut() {
+        let (head, tail) = rest.split_at(chunk.len());
+        chunk.copy_from_slice(head);
+        rest = tail;
+    }
+    let head = read::SuccessPartial { file_attr: None, count: data.len() as u32, eof: true };
+    ProcReply {
+        xid: XID,
+        proc_result: Ok(ProcResult::Nfs3(Box::new(NfsRes::Read(Ok(read::Success {
+            head,
+            data: slice,
+        }))))),
+        call: None,
+    }
+}
+
+#[tokio::test]
+async fn read_reply_from_multi_buffer_slice_survives_short_writes() {
+    let allocator = Impl::new(NonZeroUsize::new(5).unwrap(), NonZeroUsize::new(8).unwrap());
+    let data = (1..=13).collect::<Vec<u8>>();
+
+    let mut whole = Serializer::<Slice, _>::new(ShortWriter::new(usize::MAX));
+    whole.form_reply(read_reply(&allocator, &data).await, OpaqueAuth::none()).await.unwrap();
+    let whole = whole.into_inner();
+    // The staged header, the three buffers of the slice and the padding go out at once.
+    assert_eq!(whole.calls, 1);
+
+    let mut short = Serializer::<Slice, _>::new(ShortWriter::new(7));
+    short.form_reply(read_reply(&allocator, &data).await, OpaqueAuth::none()).await.unwrap();
+    let short = short.into_inner();
+    assert_eq!(short.out, whole.out);
+
+    let bytes = short.out;
+    let mut src = Cursor::new(bytes.as_slice());
+    assert_eq!(u32(&mut src).unwrap(), LAST_FRAGMENT | (bytes.len() - 4) as u32);
+    assert_eq!(u32(&mut src).unwrap(), XID);
+    assert_eq!(u32(&mut src).unwrap(), RpcBody::Reply as u32);
+    assert_eq!(u32(&mut src).unwrap(), ReplyBody::MsgAccepted as u32);
+    assert_eq!(u32(&mut src).unwrap(), AuthFlavor::None as u32);
+    assert!(vector(&mut src).unwrap().is_empty());
+    assert_eq!(u32(&mut src).unwrap(), AcceptStat::Success as u32);
+    assert_eq!(u32(&mut src).unwrap() as usize, STATUS_OK);
+    assert_eq!(u32(&mut src).unwrap(), 0);
+    assert_eq!(u32(&mut src).unwrap(), 13);
+    assert_eq!(u32(&mut src).unwrap(), 1);
+    assert_eq!(vector(&mut src).unwrap(), data);
+    assert_eq!(src.position() as usize, bytes.len());
+    assert_eq!(&bytes[bytes.len() - 3..], &[0, 0, 0]);
+}
+
+#[tokio::test]
+async fn failed_socket_write_breaks_serializer() {
+    let allocator = Impl::new(NonZeroUsize::new(5).unwrap(), NonZeroUsize::new(8).unwrap());
+    let mut writer = ShortWriter::new(16);
+    writer.fail_after = 1;
+    let mut serializer = Serializer::<Slice, _>::new(writer);
+    assert!(!serializer.is_broken());
+
+    let reply = read_reply(&allocator, &[7; 13]).await;
+    assert!(serializer.form_reply(reply, OpaqueAuth::none()).await.is_err());
+    assert!(serializer.is_broken());
+    assert_eq!(serializer.into_inner().out.len(), 16);
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn compressed_read_reply_decompresses_to_file_data() {
+    let allocator = Impl::new(NonZeroUsize::new(4096).unwrap(), NonZeroUsize::new(16).unwrap());
+    let data = b"nfs-mamont ".iter().copied().cycle().take(64 * 1024).collect::<Vec<u8>>();
+
+    let mut serializer = Serializer::<Slice, _>::new(Vec::new()).with_read_compression(3);
+    serializer.form_reply(read_reply(&allocator, &data).await, OpaqueAuth::none()).await.unwrap();
+    let bytes = serializer.into_inner();
+
+    let mut src = Cursor::new(bytes.as_slice());
+    assert_eq!(u32(&mut src).unwrap(), LAST_FRAGMENT | (bytes.len() - 4) as u32);
+    for _ in 0..4 {
+        u32(&mut src).unwrap();
+    }
+    assert!(vector(&mut src).unwrap().is_empty());
+    assert_eq!(u32(&mut src).unwrap(), AcceptStat::Success as u32);
+    assert_eq!(u32(&mut src).unwrap() as usize, STATUS_OK);
+    assert_eq!(u32(&mut src).unwrap(), 0);
+    // `count` keeps the size of the file data, the opaque data holds the zstd frame.
+    assert_eq!(u32(&mut src).unwrap() as usize, data.len());
+    assert_eq!(u32(&mut src).unwrap(), 1);
+    let frame = vector(&mut src).unwrap();
+    assert!(frame.len() < data.len() / 10);
+    assert_eq!(src.position() as usize, bytes.len());
+    assert_eq!(zstd::decode_all(frame.as_slice()).unwrap(), data);
+}
+
+/// Encodes `fattr3` field by field, as attributes were encoded before they went
+/// through a fixed-size array.
+fn fattr3_field_by_field(dest: &mut Vec<u8>, attr: &vfs::file::Attr) {
+    for word in [attr.file_type as u32, attr.mode, attr.nlink, attr.uid, attr.gid] {
+        put_u32(dest, word).unwrap();
+    }
+    put_u64(dest, attr.size).unwrap();
+    put_u64(dest, attr.used).unwrap();
+    put_u32(dest, attr.device.major).unwrap();
+    put_u32(dest, attr.device.minor).unwrap();
+    put_u64(dest, attr.fs_id).unwrap();
+    put_u64(dest, attr.file_id).unwrap();
+    for time in [attr.atime, attr.mtime, attr.ctime] {
+        put_u32(dest, time.seconds).unwrap();
+        put_u32(dest, time.nanos).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn read_dir_plus_reply_of_100_entries_matches_field_by_field_encoding() {
+    let entry = |id: u64| read_dir_plus::Entry {
+        file_id: id,
+        file_name: vfs::file::Name::new(format!("entry{id:03}")).unwrap(),
+        cookie: read_dir::Cookie::new(id),
+        file_attr: Some(vfs::file::Attr { file_id: id, size: id << 33, ..attr() }),
+        file_handle: Some(vfs::file::Handle(id.to_be_bytes())),
+    };
+    let success = read_dir_plus::Success {
+        dir_attr: Some(attr()),
+        cookie_verifier: read_dir::CookieVerifier::new([9; 8]),
+        entries: (1..=100).map(entry).collect(),
+        eof: true,
+    };
+    let reply = ProcReply {
+        xid: XID,
+        proc_result: Ok(ProcResult::Nfs3(Box::new(NfsRes::ReadDirPlus(Ok(success))))),
+        call: None,
+    };
+    let mut serializer = Serializer::<Slice, _>::with_capacity(Vec::new(), 64 * 1024);
+    serializer.form_reply(reply, OpaqueAuth::none()).await.unwrap();
+    let bytes = serializer.into_inner();
+
+    let mut expected = Vec::new();
+    for word in [
+        XID,
+        RpcBody::Reply as u32,
+        ReplyBody::MsgAccepted as u32,
+        AuthFlavor::None as u32,
+        0,
+        AcceptStat::Success as u32,
+        STATUS_OK as u32,
+        1,
+    ] {
+        put_u32(&mut expected, word).unwrap();
+    }
+    fattr3_field_by_field(&mut expected, &attr());
+    expected.extend_from_slice(&[9; 8]);
+    for entry in (1..=100).map(entry) {
+        bool(&mut expected, true).unwrap();
+        put_u64(&mut expected, entry.file_id).unwrap();
+        put_u32(&mut expected, 8).unwrap();
+        expected.extend_from_slice(entry.file_name.as_str().as_bytes());
+        put_u64(&mut expected, entry.cookie.raw()).unwrap();
+        bool(&mut expected, true).unwrap();
+        fattr3_field_by_field(&mut expected, entry.file_attr.as_ref().unwrap());
+        bool(&mut expected, true).unwrap();
+        put_u32(&mut expected, 8).unwrap();
+        expected.extend_from_slice(&entry.file_handle.as_ref().unwrap().0);
+    }
+    bool(&mut expected, false).unwrap();
+    bool(&mut expected, true).unwrap();
+
+    assert_eq!(&bytes[..4], &(LAST_FRAGMENT | expected.len() as u32).to_be_bytes());
+    assert_eq!(&bytes[4..], expected.as_slice());
+}
@@ -9,14 +9,13 @@ use crate::mount::{HostName, MountEntry};
 use crate::rpc::OpaqueAuth;
 
 use super::MountService;
-use super::AUTH;
 
 impl Mnt for MountService {
     async fn mnt(
         &self,
         args: Args,
         client_addr: SocketAddr,
-        _cred: OpaqueAuth,
+        cred: OpaqueAuth,
     ) -> Result<Success, Fail> {
         let Some(export) = self.export_entry(&args.dirpath).await else {
             let configured = self
@@ -34,6 +33,17 @@ impl Mnt for MountService {
             return Err(Fail::Access);
         };
 
+        if !export.auth_flavors.contains(cred.flavor()) {
+            warn!(
+                requested=%args.dirpath.as_path().to_string_lossy(),
+                client=%client_addr,
+                flavor=?cred.flavor(),
+                offered=?export.auth_flavors,
+                "mount denied: authentication flavor not offered",
+            );
+            return Err(Fail::Access);
+        }
+
         let file_handle = export.root_handle.clone();
 
         let hostname = HostName::new(client_addr.ip().to_string()).map_err(|_| Fail::Inval)?;
@@ -42,6 +52,6 @@ impl Mnt for MountService {
 
         self.mounts.write().await.by_client.entry(client_addr).or_default().insert(mount_entry);
 
-        Ok(Success { file_handle, auth_flavors: AUTH.to_vec() })
+        Ok(Success { file_handle, auth_flavors: export.auth_flavors.clone() })
     }
 }
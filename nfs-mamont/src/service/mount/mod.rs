@@ -18,9 +18,15 @@
 //!   mount multiple directories and `UMNT`/`UMNTALL` are client-scoped;
 //! - each export keeps server policy metadata (file handle + auth flavors)
 //!   next to user-visible export data.
+//!
+//! Export paths are names, not file system paths: the backend resolves each export to
+//! its directory (following symlinks) once, when the root handle is issued. `MNT` only
+//! normalizes the requested path lexically, so `/data/`, `//data` and `/data/.` all
+//! name export `/data`, while paths with `..` never match an export.
 
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::path::{Component, PathBuf};
 use std::sync::Arc;
 
 use tokio::sync::RwLock;
@@ -35,13 +41,16 @@ mod mnt;
 mod umnt;
 mod umntall;
 
-// TODO: should be taken from config
-const AUTH: [AuthFlavor; 1] = [AuthFlavor::None];
+/// Flavors the server authenticates calls with, in order of preference.
+pub const DEFAULT_AUTH_FLAVORS: [AuthFlavor; 2] = [AuthFlavor::Sys, AuthFlavor::None];
 
 #[derive(Clone)]
 pub struct ExportEntryWrapper {
     pub export: ExportEntry,
     pub root_handle: file::Handle,
+    /// Flavors `MNT` offers for the export, in order of preference. Mount requests
+    /// authenticated with any other flavor are refused.
+    pub auth_flavors: Vec<AuthFlavor>,
 }
 
 /// Registry of exported directories advertised by the server
@@ -55,17 +64,15 @@ impl ExportRegistry {
     fn from_entries(entries: Vec<ExportEntryWrapper>) -> Self {
         let mut by_directory = HashMap::new();
         for entry in entries.into_iter() {
-            let file_handle = entry.root_handle.clone();
-            by_directory.insert(
-                entry.export.directory.clone(),
-                ExportEntryWrapper { export: entry.export, root_handle: file_handle },
-            );
+            let directory = normalize(&entry.export.directory)
+                .unwrap_or_else(|| entry.export.directory.clone());
+            by_directory.insert(directory, entry);
         }
         Self { by_directory }
     }
 
     fn by_path(&self, path: &file::Path) -> Option<&ExportEntryWrapper> {
-        self.by_directory.get(path)
+        self.by_directory.get(&normalize(path)?)
     }
 
     fn export_list(&self) -> Vec<ExportEntry> {
@@ -100,3 +107,108 @@ impl MountService {
         self.exports.by_path(path)
     }
 }
+
+/// Returns `path` as an absolute path without empty, `.` and trailing components,
+/// or `None` if it contains `..`.
+fn normalize(path: &file::Path) -> Option<file::Path> {
+    let mut normalized = PathBuf::from("/");
+    for component in path.as_path().components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+    file::Path::new(normalized.into_os_string().into_string().ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use crate::consts::nfsv3::NFS3_FHSIZE;
+    use crate::mount::mnt::{Args, Fail, Mnt};
+    use crate::mount::ExportEntry;
+    use crate::rpc::{AuthFlavor, OpaqueAuth};
+    use crate::vfs::file;
+
+    use super::{ExportEntryWrapper, MountService, DEFAULT_AUTH_FLAVORS};
+
+    fn path(value: &str) -> file::Path {
+        file::Path::new(value.to_owned()).unwrap()
+    }
+
+    fn service() -> MountService {
+        service_with_flavors(DEFAULT_AUTH_FLAVORS.to_vec())
+    }
+
+    fn service_with_flavors(auth_flavors: Vec<AuthFlavor>) -> MountService {
+        MountService::with_exports(vec![ExportEntryWrapper {
+            export: ExportEntry { directory: path("/data"), names: Vec::new() },
+            root_handle: file::Handle([7; NFS3_FHSIZE]),
+            auth_flavors,
+        }])
+    }
+
+    async fn mnt(service: &MountService, dirpath: &str) -> Result<file::Handle, Fail> {
+        let client: SocketAddr = "127.0.0.1:700".parse().unwrap();
+        Mnt::mnt(service, Args { dirpath: path(dirpath) }, client, OpaqueAuth::none())
+            .await
+            .map(|success| success.file_handle)
+    }
+
+    #[tokio::test]
+    async fn mnt_matches_export_through_equivalent_spellings() {
+        let service = service();
+        for dirpath in ["/data", "/data/", "//data", "/./data/.", "data"] {
+            let handle = mnt(&service, dirpath).await.unwrap_or_else(|_| panic!("{dirpath}"));
+            assert_eq!(handle, file::Handle([7; NFS3_FHSIZE]), "{dirpath}");
+        }
+    }
+
+    #[tokio::test]
+    async fn mnt_rejects_paths_leaving_the_export() {
+        let service = service();
+        for dirpath in ["/data/..", "/data/../data", "/other", "/data/sub"] {
+            assert!(matches!(mnt(&service, dirpath).await, Err(Fail::Access)), "{dirpath}");
+        }
+    }
+
+    /// Returns an `AUTH_SYS` credential; `MNT` looks at its flavor only.
+    fn sys_cred() -> OpaqueAuth {
+        OpaqueAuth::new(AuthFlavor::Sys, Vec::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn mnt_lists_the_export_flavors() {
+        let client: SocketAddr = "127.0.0.1:700".parse().unwrap();
+        let args = Args { dirpath: path("/data") };
+
+        let success = Mnt::mnt(&service(), args, client, sys_cred()).await.unwrap();
+
+        assert_eq!(success.auth_flavors, [AuthFlavor::Sys, AuthFlavor::None]);
+    }
+
+    #[tokio::test]
+    async fn mnt_refuses_flavors_the_export_does_not_offer() {
+        let service = service_with_flavors(vec![AuthFlavor::RpcSecGss]);
+        let client: SocketAddr = "127.0.0.1:700".parse().unwrap();
+
+        for cred in [sys_cred(), OpaqueAuth::none()] {
+            let args = Args { dirpath: path("/data") };
+            let result = Mnt::mnt(&service, args, client, cred).await;
+            assert!(matches!(result, Err(Fail::Access)));
+        }
+    }
+
+    #[tokio::test]
+    async fn mnt_of_kerberos_only_export_omits_auth_sys() {
+        let service = service_with_flavors(vec![AuthFlavor::RpcSecGss, AuthFlavor::None]);
+        let client: SocketAddr = "127.0.0.1:700".parse().unwrap();
+        let args = Args { dirpath: path("/data") };
+
+        let success = Mnt::mnt(&service, args, client, OpaqueAuth::none()).await.unwrap();
+
+        assert!(!success.auth_flavors.contains(&AuthFlavor::Sys));
+    }
+}
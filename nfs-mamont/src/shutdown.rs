@@ -0,0 +1,64 @@
+//! Graceful shutdown of a server started with [`crate::handle_until_shutdown`].
+
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, watch};
+
+/// Requests shutdown of a running server; every clone controls the same server.
+///
+/// On shutdown the server stops accepting connections and aborts the read tasks, so no
+/// further calls are parsed and buffers held by half-read calls go back to their
+/// allocator. Calls already queued are still executed and answered; the serving future
+/// completes once every connection has drained.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Creates a handle of a server that has not been asked to stop.
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender: Arc::new(sender) }
+    }
+
+    /// Asks the server to stop; returns without waiting for the server to drain.
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Returns `true` if shutdown was requested.
+    pub fn is_shutdown(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Returns a signal of this handle whose clones, while alive, hold off the
+    /// completion reported by `drained`.
+    pub(crate) fn signal(&self, drained: mpsc::Sender<()>) -> ShutdownSignal {
+        ShutdownSignal { stop: self.sender.subscribe(), _drained: drained }
+    }
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Receiving side of a [`ShutdownHandle`], held by the tasks of a connection.
+#[derive(Debug, Clone)]
+pub(crate) struct ShutdownSignal {
+    stop: watch::Receiver<bool>,
+    /// Closed once the last signal is dropped, which tells the server it has drained.
+    _drained: mpsc::Sender<()>,
+}
+
+impl ShutdownSignal {
+    /// Completes once shutdown is requested; never completes if every handle is dropped
+    /// without requesting it.
+    pub async fn requested(&mut self) {
+        if self.stop.wait_for(|stop| *stop).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! TCP options of the listening socket and of every accepted connection.
+
+use std::io;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+/// Connections the kernel queues for [`TcpListener::accept`].
+pub const LISTEN_BACKLOG: u32 = 1024;
+
+/// TCP keepalive probing of idle connections, reaping clients that vanished without
+/// closing their connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// Idle time before the first probe is sent.
+    pub idle: Duration,
+    /// Time between unanswered probes.
+    pub interval: Duration,
+    /// Unanswered probes after which the connection is dropped.
+    pub retries: u32,
+}
+
+/// Options applied to the listener and to each accepted connection.
+///
+/// The defaults disable Nagle's algorithm, since RPC replies are written whole, and
+/// leave everything else to the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketConfig {
+    /// Sets `TCP_NODELAY` on accepted connections.
+    pub nodelay: bool,
+    /// Enables keepalive probing of accepted connections, if set.
+    pub keepalive: Option<Keepalive>,
+    /// `SO_SNDBUF` of accepted connections, if set.
+    pub send_buffer_size: Option<NonZeroUsize>,
+    /// `SO_RCVBUF` of accepted connections, if set.
+    pub recv_buffer_size: Option<NonZeroUsize>,
+    /// Sets `SO_REUSEADDR` on the listener, so a restarted server can bind its address
+    /// while connections of the previous one linger in `TIME_WAIT`.
+    pub reuse_address: bool,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            reuse_address: true,
+        }
+    }
+}
+
+impl SocketConfig {
+    /// Creates a listener bound to `addr` with the listener options applied.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<TcpListener> {
+        let socket = match addr {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        socket.set_reuseaddr(self.reuse_address)?;
+        socket.bind(addr)?;
+        socket.listen(LISTEN_BACKLOG)
+    }
+
+    /// Applies the connection options to `socket`.
+    pub fn apply(&self, socket: &TcpStream) -> io::Result<()> {
+        socket.set_nodelay(self.nodelay)?;
+        let socket = SockRef::from(socket);
+        if let Some(keepalive) = self.keepalive {
+            socket.set_tcp_keepalive(
+                &TcpKeepalive::new()
+                    .with_time(keepalive.idle)
+                    .with_interval(keepalive.interval)
+                    .with_retries(keepalive.retries),
+            )?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size.get())?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size.get())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    use socket2::SockRef;
+    use tokio::net::TcpStream;
+
+    use super::{Keepalive, SocketConfig};
+
+    #[tokio::test]
+    async fn options_are_applied_to_listener_and_accepted_socket() {
+        let config = SocketConfig {
+            nodelay: true,
+            keepalive: Some(Keepalive {
+                idle: Duration::from_secs(30),
+                interval: Duration::from_secs(5),
+                retries: 4,
+            }),
+            send_buffer_size: Some(NonZeroUsize::new(64 * 1024).unwrap()),
+            recv_buffer_size: Some(NonZeroUsize::new(64 * 1024).unwrap()),
+            reuse_address: true,
+        };
+        let listener = config.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert!(SockRef::from(&listener).reuse_address().unwrap());
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+
+        config.apply(&socket).unwrap();
+
+        assert!(socket.nodelay().unwrap());
+        let socket = SockRef::from(&socket);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.tcp_keepalive_time().unwrap(), Duration::from_secs(30));
+        assert_eq!(socket.tcp_keepalive_interval().unwrap(), Duration::from_secs(5));
+        assert_eq!(socket.tcp_keepalive_retries().unwrap(), 4);
+        // Linux doubles the requested size to account for bookkeeping overhead.
+        assert!(socket.send_buffer_size().unwrap() >= 64 * 1024);
+        assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn default_leaves_keepalive_off() {
+        let config = SocketConfig::default();
+        let listener = config.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+
+        config.apply(&socket).unwrap();
+
+        assert!(socket.nodelay().unwrap());
+        assert!(!SockRef::from(&socket).keepalive().unwrap());
+    }
+}
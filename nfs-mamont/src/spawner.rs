@@ -0,0 +1,69 @@
+//! Launching of server tasks on a user-chosen runtime.
+//!
+//! Every long-running task of the server (connection read and write tasks, VFS workers,
+//! MOUNT and NLM tasks) is started through a [`Spawner`], so embedders can pin the server
+//! to a specific runtime or run it on a single thread.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use tokio::runtime::Handle;
+
+/// Boxed future of a server task.
+pub type ServerTask = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Strategy used to launch server tasks.
+pub trait Spawner: Send + Sync {
+    /// Launches `task` in the background; the task runs until it completes.
+    fn spawn(&self, task: ServerTask);
+}
+
+/// Spawns tasks on the ambient Tokio runtime with [`tokio::spawn`].
+///
+/// This is the default strategy.
+///
+/// # Panics
+///
+/// [`Spawner::spawn`] panics if called outside of tokio runtime context.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioSpawner;
+
+impl Spawner for TokioSpawner {
+    fn spawn(&self, task: ServerTask) {
+        tokio::spawn(task);
+    }
+}
+
+/// Spawns tasks on the runtime behind a [`Handle`], regardless of the caller's context.
+#[derive(Debug, Clone)]
+pub struct HandleSpawner {
+    handle: Handle,
+}
+
+impl HandleSpawner {
+    /// Creates a spawner launching tasks on the runtime of `handle`.
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+}
+
+impl Spawner for HandleSpawner {
+    fn spawn(&self, task: ServerTask) {
+        self.handle.spawn(task);
+    }
+}
+
+/// Spawns tasks on the current [`tokio::task::LocalSet`] with [`tokio::task::spawn_local`],
+/// so the whole server runs on the thread driving the set.
+///
+/// # Panics
+///
+/// [`Spawner::spawn`] panics if called outside of a [`tokio::task::LocalSet`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalSpawner;
+
+impl Spawner for LocalSpawner {
+    fn spawn(&self, task: ServerTask) {
+        tokio::task::spawn_local(task);
+    }
+}
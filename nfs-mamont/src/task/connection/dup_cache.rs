@@ -0,0 +1,123 @@
+//! Per-connection cache of replies to non-idempotent calls.
+//!
+//! A client that believes a reply was lost, e.g. after a stall, retransmits the call
+//! with the same xid, possibly on the same connection. Executing a CREATE, REMOVE or
+//! RENAME again would fail where the first execution succeeded, so the read task
+//! registers such calls here, the write task records the reply it sends, and a
+//! retransmission is answered with the recorded reply instead of being executed.
+//!
+//! Calls are told apart by xid, program, version and procedure, so a call reusing the
+//! xid of another is never answered with its reply. Replies carry the key of the call
+//! they answer, and only those of registered calls are recorded.
+
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use crate::allocator::Buffer;
+use crate::consts::nfsv3::{
+    CREATE, LINK, MKDIR, MKNOD, NFS_PROGRAM, NFS_VERSION, REMOVE, RENAME, RMDIR, SETATTR, SYMLINK,
+};
+use crate::parser::NfsArguments;
+use crate::task::CallKey;
+
+/// Number of calls a connection remembers by default.
+pub const DEFAULT_DUPLICATE_CACHE_CAPACITY: usize = 256;
+
+/// State of a call registered with a [`DuplicateCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lookup {
+    /// First transmission of the call: it should be executed.
+    New,
+    /// The call is still being executed; its reply will answer the retransmission too.
+    InProgress,
+    /// The call was answered with this record, record mark included.
+    Done(Arc<[u8]>),
+}
+
+/// Replies of the last non-idempotent calls of a connection, keyed by [`CallKey`].
+#[derive(Debug)]
+pub struct DuplicateCache {
+    capacity: NonZeroUsize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// `None` while the call is in progress.
+    entries: HashMap<CallKey, Option<Arc<[u8]>>>,
+    /// Calls in registration order, oldest first.
+    order: VecDeque<CallKey>,
+}
+
+impl Default for DuplicateCache {
+    fn default() -> Self {
+        Self::new(NonZeroUsize::new(DEFAULT_DUPLICATE_CACHE_CAPACITY).unwrap())
+    }
+}
+
+impl DuplicateCache {
+    /// Creates a cache remembering the last `capacity` calls.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self { capacity, state: Mutex::new(State::default()) }
+    }
+
+    /// Returns the key of NFS call `xid` with `args` if it must not be executed twice.
+    pub fn key<B: Buffer>(xid: u32, args: &NfsArguments<B>) -> Option<CallKey> {
+        let procedure = match args {
+            NfsArguments::SetAttr(_) => SETATTR,
+            NfsArguments::Create(_) => CREATE,
+            NfsArguments::MkDir(_) => MKDIR,
+            NfsArguments::SymLink(_) => SYMLINK,
+            NfsArguments::MkNod(_) => MKNOD,
+            NfsArguments::Remove(_) => REMOVE,
+            NfsArguments::RmDir(_) => RMDIR,
+            NfsArguments::Rename(_) => RENAME,
+            NfsArguments::Link(_) => LINK,
+            _ => return None,
+        };
+        Some(CallKey { xid, program: NFS_PROGRAM, version: NFS_VERSION, procedure })
+    }
+
+    /// Registers call `key`, returning what is known about an earlier transmission.
+    ///
+    /// The oldest call is forgotten once more than the capacity are registered.
+    pub fn begin(&self, key: CallKey) -> Lookup {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.entries.get(&key) {
+            return match entry {
+                Some(record) => Lookup::Done(Arc::clone(record)),
+                None => Lookup::InProgress,
+            };
+        }
+        if state.order.len() == self.capacity.get() {
+            if let Some(oldest) = state.order.pop_front() {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(key, None);
+        state.order.push_back(key);
+        Lookup::New
+    }
+
+    /// Returns `true` if call `key` was registered and is still in progress.
+    pub fn is_in_progress(&self, key: &CallKey) -> bool {
+        matches!(self.state.lock().unwrap().entries.get(key), Some(None))
+    }
+
+    /// Records the reply sent for call `key`, or forgets the call if there is none.
+    pub fn complete(&self, key: &CallKey, record: Option<Arc<[u8]>>) {
+        let mut state = self.state.lock().unwrap();
+        match record {
+            Some(record) => {
+                if let Some(entry) = state.entries.get_mut(key) {
+                    *entry = Some(record);
+                }
+            }
+            None => {
+                state.entries.remove(key);
+                state.order.retain(|queued| queued != key);
+            }
+        }
+    }
+}
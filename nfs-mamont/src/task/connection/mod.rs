@@ -6,27 +6,54 @@
 //! - [`read::ReadTask`] - Reads RPC commands from the network connection
 //! - [`write::WriteTask`] - Writes operation results back to the network connection
 //!
-//! These tasks communicate via unbounded channels to form an asynchronous processing pipeline.
+//! These tasks communicate via channels to form an asynchronous processing pipeline.
+//! The channels to and from the VFS pool are bounded: a full queue stops the read task
+//! from pulling further calls off the socket until the workers catch up.
+//!
+//! On shutdown the read task is aborted, dropping the buffers of calls it has not
+//! handed over yet; the write task keeps answering queued calls until the VFS workers
+//! drop their reply senders, so the allocators get every buffer back.
+//!
+//! With a [`crate::RateLimit`] configured, the read task of a connection over its
+//! limits pauses before reading the next call; the limits are per connection.
+//!
+//! Non-idempotent NFS calls are registered in a [`dup_cache::DuplicateCache`] shared by
+//! both tasks, so a call retransmitted on the same connection is answered with the reply
+//! recorded for it instead of being executed twice.
+//!
+//! Waits only ever point downstream, so they cannot form a cycle: the read task waits
+//! for write buffers, freed as VFS workers consume queued calls, and for queue space;
+//! workers wait for read buffers, freed as the write task sends replies, and for reply
+//! queue space; the write task waits on the socket alone.
+
+use std::sync::Arc;
 
 use tokio::net::TcpStream;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::allocator::{Allocator, Buffer};
 use crate::context::ServerContext;
+use crate::shutdown::ShutdownSignal;
 use crate::task::global::mount::MountCommand;
 use crate::task::global::nlm::NlmCommand;
 use crate::task::ProcReply;
 use crate::vfs::Vfs;
 
+mod dup_cache;
+mod rate_limit;
 mod read;
 mod write;
 
+#[cfg(test)]
+mod tests;
+
 // Creates all connection tasks with their inner connections
 pub async fn new<A, V, B>(
     socket: TcpStream,
     mount_sender: async_channel::Sender<MountCommand<B>>,
     nlm_sender: async_channel::Sender<NlmCommand<B>>,
     context: &ServerContext<A, V, B>,
+    shutdown: &ShutdownSignal,
 ) where
     A: Allocator<Buffer = B> + Send + Sync + 'static,
     B: Buffer + 'static,
@@ -39,10 +66,14 @@ pub async fn new<A, V, B>(
             return;
         }
     };
+    if let Err(err) = context.get_socket_config().apply(&socket) {
+        warn!(client=%peer_addr, error=%err, "failed to apply socket options");
+    }
     let (readhalf, writehalf) = socket.into_split();
     // channel for result
-    let (result_sender, result_receiver) = async_channel::unbounded::<ProcReply<B>>();
-    // channel for request
+    let (result_sender, result_receiver) =
+        async_channel::bounded::<ProcReply<B>>(context.get_queue_capacity().replies.get());
+    let duplicate_cache = Arc::new(dup_cache::DuplicateCache::default());
 
     read::ReadTask::<A, B>::new(
         readhalf,
@@ -53,7 +84,15 @@ pub async fn new<A, V, B>(
         context.get_write_allocator(),
         context.get_vfs_pool().sender(),
     )
-    .spawn();
+    .with_rate_limit(context.get_rate_limit())
+    .with_parse_limits(context.get_parse_limits())
+    .with_duplicate_cache(Arc::clone(&duplicate_cache))
+    .spawn(context.get_spawner(), shutdown.clone());
 
-    write::WriteTask::<B>::new(writehalf, result_receiver).spawn();
+    let write_task = write::WriteTask::<B>::new(writehalf, result_receiver)
+        .with_max_reply_bytes(context.get_max_reply_bytes())
+        .with_duplicate_cache(duplicate_cache);
+    #[cfg(feature = "compression")]
+    let write_task = write_task.with_read_compression(context.get_read_compression());
+    write_task.spawn(context.get_spawner(), shutdown.clone());
 }
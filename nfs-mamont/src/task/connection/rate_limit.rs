@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::context::RateLimit;
+
+/// Token bucket refilled at `rate` tokens per second, holding at most one second worth.
+///
+/// Taking more tokens than the bucket holds drives it into debt, which the caller
+/// waits out, so calls larger than the burst still pass at the configured rate.
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        Self { rate, tokens: rate, refilled_at: Instant::now() }
+    }
+
+    /// Takes `amount` tokens and returns how long to wait until the bucket is out of debt.
+    fn take(&mut self, amount: f64) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate) - amount;
+        self.refilled_at = now;
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Paces the calls read from one connection according to a [`RateLimit`].
+pub struct RateLimiter {
+    requests: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            requests: limit.requests_per_sec.map(|rate| TokenBucket::new(f64::from(rate.get()))),
+            bytes: limit.bytes_per_sec.map(|rate| TokenBucket::new(rate.get() as f64)),
+        }
+    }
+
+    /// Accounts for a call of `bytes` bytes and sleeps while the connection is over its limits.
+    pub async fn throttle(&mut self, bytes: u64) {
+        let requests = self.requests.as_mut().map_or(Duration::ZERO, |bucket| bucket.take(1.0));
+        let bytes = self.bytes.as_mut().map_or(Duration::ZERO, |bucket| bucket.take(bytes as f64));
+        let wait = requests.max(bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
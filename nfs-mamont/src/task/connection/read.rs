@@ -4,24 +4,32 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use tokio::net::tcp::OwnedReadHalf;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 use async_channel::Sender;
 
 use crate::allocator::{Allocator, Buffer};
+use crate::context::RateLimit;
 use crate::mount::MountRes;
 use crate::nlm::NlmRes;
 use crate::parser::parser_struct::RpcParser;
+use crate::parser::primitive;
 use crate::parser::{
     ArgWrapper, ErrorWrapper, MountArgWrapper, MountArguments, NfsArgWrapper, NfsArguments,
     NlmArgWrapper, NlmArguments, ProcArguments,
 };
 use crate::rpc::Error;
+use crate::shutdown::ShutdownSignal;
+use crate::spawner::Spawner;
 use crate::task::global::mount::MountCommand;
 use crate::task::global::nlm::NlmCommand;
+use crate::task::global::vfs::{VfsCommand, VfsCommandSender};
 use crate::task::{ProcReply, ProcResult};
 use crate::vfs::NfsRes;
 
+use super::dup_cache::{DuplicateCache, Lookup};
+use super::rate_limit::RateLimiter;
+
 /// Reads RPC commands from a network connection, parses them,
 /// and forwards to [`super::super::global::vfs::VfsPool`] or other global tasks.
 pub struct ReadTask<A: Allocator + Send + Sync + 'static, B: Buffer = <A as Allocator>::Buffer> {
@@ -38,7 +46,13 @@ pub struct ReadTask<A: Allocator + Send + Sync + 'static, B: Buffer = <A as Allo
     result_sender: Sender<ProcReply<B>>,
     allocator: Arc<A>,
     // to pass (nfs_3_cmd, tx) into vfs task, so vfs task can send result back to write task
-    pool_sender: Sender<(NfsArgWrapper<B>, Sender<ProcReply<B>>)>,
+    pool_sender: VfsCommandSender<B>,
+    // paces the calls read from the socket
+    rate_limiter: RateLimiter,
+    // checks applied to the fields of every call
+    parse_limits: primitive::Limits,
+    // answers retransmitted non-idempotent calls
+    duplicate_cache: Option<Arc<DuplicateCache>>,
     _phantom: PhantomData<B>,
 }
 
@@ -55,7 +69,7 @@ where
         nlm_sender: Sender<NlmCommand<B>>,
         result_sender: Sender<ProcReply<B>>,
         allocator: Arc<A>,
-        pool_sender: Sender<(NfsArgWrapper<B>, Sender<ProcReply<B>>)>,
+        pool_sender: VfsCommandSender<B>,
     ) -> Self {
         Self {
             readhalf,
@@ -65,79 +79,114 @@ where
             result_sender,
             allocator,
             pool_sender,
+            rate_limiter: RateLimiter::new(RateLimit::default()),
+            parse_limits: primitive::Limits::default(),
+            duplicate_cache: None,
             _phantom: PhantomData,
         }
     }
 
-    /// Spawns a [`ReadTask`]  that reads commands from a socket.
+    /// Paces the calls read from the socket according to `rate_limit`.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limiter = RateLimiter::new(rate_limit);
+        self
+    }
+
+    /// Checks the fields of the calls read from the socket against `limits`.
+    pub fn with_parse_limits(mut self, limits: primitive::Limits) -> Self {
+        self.parse_limits = limits;
+        self
+    }
+
+    /// Registers non-idempotent NFS calls in `cache`, answering their retransmissions
+    /// from it instead of executing them again.
+    pub fn with_duplicate_cache(mut self, cache: Arc<DuplicateCache>) -> Self {
+        self.duplicate_cache = Some(cache);
+        self
+    }
+
+    /// Spawns a [`ReadTask`] that reads commands from a socket until the connection
+    /// closes or `shutdown` is requested.
+    ///
+    /// Aborting on shutdown drops the parser and any call not yet handed over,
+    /// returning their buffers to the allocator.
     ///
     /// # Panics
     ///
-    /// If called outside of tokio runtime context.
-    pub fn spawn(self)
+    /// If `spawner` cannot launch tasks in the current context.
+    pub fn spawn(self, spawner: &dyn Spawner, mut shutdown: ShutdownSignal)
     where
         B: 'static,
     {
-        tokio::spawn(async move { self.run().await });
+        let client_addr = self.client_addr;
+        spawner.spawn(Box::pin(async move {
+            tokio::select! {
+                _ = self.run() => {}
+                () = shutdown.requested() => {
+                    debug!(client=%client_addr, "read task aborted by shutdown");
+                }
+            }
+        }));
     }
 
-    async fn run(self) -> io::Result<()> {
-        let mut parser = RpcParser::new(self.readhalf, self.allocator);
+    async fn run(mut self) -> io::Result<()> {
+        let mut parser =
+            RpcParser::new(self.readhalf, self.allocator).with_limits(self.parse_limits);
+        let mut parsed_bytes = 0;
 
         loop {
-            match parser.next_message().await {
-                Ok(ArgWrapper { proc: ProcArguments::Nfs3(proc), header })
-                    if matches!(*proc, NfsArguments::Null) =>
-                {
-                    debug!(client=%self.client_addr, xid=header.xid, program="NFS", proc="NULL", "rpc dispatch");
-                    let result = ProcReply {
-                        xid: header.xid,
-                        proc_result: Ok(ProcResult::Nfs3(Box::new(NfsRes::Null))),
-                    };
-
-                    if let Err(err) = self.result_sender.send(result).await {
-                        return send_broken_pipe(&self.result_sender, header.xid, err).await;
-                    }
-                }
-
-                Ok(ArgWrapper { proc: ProcArguments::Nlm4(proc), header })
-                    if matches!(*proc, NlmArguments::Null) =>
-                {
-                    debug!(client=%self.client_addr, xid=header.xid, program="NLM", proc="NULL", "rpc dispatch");
-                    let result = ProcReply {
-                        xid: header.xid,
-                        proc_result: Ok(ProcResult::Nlm4(Box::new(NlmRes::Null))),
-                    };
-
+            let message = parser.next_message().await;
+            // Over its limits the connection pauses here, before reading the next call.
+            let message_bytes = parser.parsed_bytes() - parsed_bytes;
+            parsed_bytes = parser.parsed_bytes();
+            self.rate_limiter.throttle(message_bytes).await;
+            // NULL is answered right away, without scheduling it on any global task.
+            if let Ok(ArgWrapper { proc, header }) = &message {
+                if let Some((program, result)) = null_reply(proc) {
+                    debug!(client=%self.client_addr, xid=header.xid, program, proc="NULL", "rpc dispatch");
+                    let result = ProcReply { xid: header.xid, proc_result: Ok(result), call: None };
                     if let Err(err) = self.result_sender.send(result).await {
                         return send_broken_pipe(&self.result_sender, header.xid, err).await;
                     }
+                    continue;
                 }
+            }
 
+            match message {
                 Ok(ArgWrapper { proc: ProcArguments::Nfs3(proc), header }) => {
                     let xid = header.xid;
-                    debug!(client=%self.client_addr, xid, program="NFS", proc="NON_NULL", "rpc dispatch");
-                    let command = NfsArgWrapper { header, proc };
-
-                    if let Err(err) =
-                        self.pool_sender.send((command, self.result_sender.clone())).await
-                    {
-                        return send_broken_pipe(&self.result_sender, xid, err).await;
+                    let call = self
+                        .duplicate_cache
+                        .as_ref()
+                        .and_then(|cache| Some((cache, DuplicateCache::key(xid, &proc)?)));
+                    match call.map_or(Lookup::New, |(cache, key)| cache.begin(key)) {
+                        Lookup::New => {}
+                        Lookup::InProgress => {
+                            debug!(client=%self.client_addr, xid, "retransmission of a call in progress dropped");
+                            continue;
+                        }
+                        Lookup::Done(record) => {
+                            debug!(client=%self.client_addr, xid, "retransmission answered from the duplicate cache");
+                            let result = ProcReply {
+                                xid,
+                                proc_result: Ok(ProcResult::Replay(record)),
+                                call: None,
+                            };
+                            if let Err(err) = self.result_sender.send(result).await {
+                                return send_broken_pipe(&self.result_sender, xid, err).await;
+                            }
+                            continue;
+                        }
                     }
-                }
-
-                Ok(ArgWrapper { proc: ProcArguments::Mount(proc), header })
-                    if matches!(*proc, MountArguments::Null) =>
-                {
-                    let xid = header.xid;
-                    debug!(client=%self.client_addr, xid, program="MOUNT", proc="NULL", "rpc dispatch");
-
-                    let result = ProcReply {
-                        xid: header.xid,
-                        proc_result: Ok(ProcResult::Mount(Box::new(MountRes::Null))),
+                    debug!(client=%self.client_addr, xid, program="NFS", proc="NON_NULL", "rpc dispatch");
+                    let command = VfsCommand {
+                        result_tx: self.result_sender.clone(),
+                        client_addr: self.client_addr,
+                        args: NfsArgWrapper { header, proc },
+                        call: call.map(|(_, key)| key),
                     };
 
-                    if let Err(err) = self.result_sender.send(result).await {
+                    if let Err(err) = self.pool_sender.send(command).await {
                         return send_broken_pipe(&self.result_sender, xid, err).await;
                     }
                 }
@@ -168,9 +217,20 @@ where
                     }
                 }
 
+                // the peer is gone, so there is nobody to reply to
+                Err(ErrorWrapper { error: Error::ConnectionClosed, .. }) => {
+                    debug!(client=%self.client_addr, "connection closed by client");
+                    return Ok(());
+                }
+
+                Err(ErrorWrapper { xid, error: Error::TruncatedMessage }) => {
+                    warn!(client=%self.client_addr, xid, "connection closed mid-request");
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                }
+
                 Err(ErrorWrapper { xid: Some(xid), error }) => {
                     error!(client=%self.client_addr, xid, error=?error, "rpc parse error");
-                    let result = ProcReply { xid, proc_result: Err(error) };
+                    let result = ProcReply { xid, proc_result: Err(error), call: None };
                     if let Err(err) = self.result_sender.send(result).await {
                         return send_broken_pipe(&self.result_sender, xid, err).await;
                     }
@@ -186,6 +246,22 @@ where
     }
 }
 
+/// Returns the program name and the empty result if `proc` is a NULL procedure.
+fn null_reply<B: Buffer>(proc: &ProcArguments<B>) -> Option<(&'static str, ProcResult<B>)> {
+    match proc {
+        ProcArguments::Nfs3(proc) if matches!(**proc, NfsArguments::Null) => {
+            Some(("NFS", ProcResult::Nfs3(Box::new(NfsRes::Null))))
+        }
+        ProcArguments::Mount(proc) if matches!(**proc, MountArguments::Null) => {
+            Some(("MOUNT", ProcResult::Mount(Box::new(MountRes::Null))))
+        }
+        ProcArguments::Nlm4(proc) if matches!(**proc, NlmArguments::Null) => {
+            Some(("NLM", ProcResult::Nlm4(Box::new(NlmRes::Null))))
+        }
+        _ => None,
+    }
+}
+
 async fn send_broken_pipe<B: Buffer + 'static>(
     sender: &Sender<ProcReply<B>>,
     xid: u32,
@@ -195,6 +271,7 @@ async fn send_broken_pipe<B: Buffer + 'static>(
         .send(ProcReply {
             xid,
             proc_result: Err(Error::IO(io::Error::new(io::ErrorKind::BrokenPipe, err.to_string()))),
+            call: None,
         })
         .await
         .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
@@ -0,0 +1,551 @@
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::sync::Arc;
+use std::time::Duration;
+
+use socket2::SockRef;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::allocator::{Allocator, Impl, Slice};
+use crate::consts::mount::{MOUNT_MNT, MOUNT_PROGRAM, MOUNT_VERSION};
+use crate::consts::nfsv3::{CREATE, NFS_PROGRAM, NFS_VERSION, READ, REMOVE, WRITE};
+use crate::context::{QueueCapacity, RateLimit, ServerContext};
+use crate::mount::{ExportEntry, MountRes};
+use crate::parser::reply::{header, nfsv3, record_mark, ReplyStatus};
+use crate::rpc::{AcceptStat, AuthFlavor, RpcBody, RPC_VERSION};
+use crate::service::mount::{MountService, DEFAULT_AUTH_FLAVORS};
+use crate::service::nlm::NlmService;
+use crate::shutdown::{ShutdownHandle, ShutdownSignal};
+use crate::socket::{Keepalive, SocketConfig};
+use crate::spawner::{LocalSpawner, TokioSpawner};
+use crate::task::connection::read::ReadTask;
+use crate::task::global::tests::MockVfs;
+use crate::task::{ProcReply, ProcResult};
+use crate::vfs::export_set::ExportSet;
+use crate::vfs::{create, file, Credentials, DirOpArgs, Error, NfsRes};
+
+/// Serializes a call with AUTH_NONE credentials and verifier, followed by `args` words.
+fn call(xid: u32, program: u32, version: u32, procedure: u32, args: &[u32]) -> Vec<u8> {
+    let words = [xid, RpcBody::Call as u32, RPC_VERSION, program, version, procedure, 0, 0, 0, 0];
+    let len = (words.len() + args.len()) * 4;
+    let mut frame = (0x8000_0000 | len as u32).to_be_bytes().to_vec();
+    words.iter().chain(args).for_each(|word| frame.extend_from_slice(&word.to_be_bytes()));
+    frame
+}
+
+fn null_call(xid: u32, program: u32, version: u32) -> Vec<u8> {
+    call(xid, program, version, 0, &[])
+}
+
+/// Reads one record-marked reply from `client`, including the record mark.
+async fn read_reply(client: &mut (impl AsyncRead + Unpin)) -> Vec<u8> {
+    let mut mark = [0u8; 4];
+    client.read_exact(&mut mark).await.unwrap();
+    let len = (u32::from_be_bytes(mark) & !0x8000_0000) as usize;
+    let mut reply = mark.to_vec();
+    reply.resize(4 + len, 0);
+    client.read_exact(&mut reply[4..]).await.unwrap();
+    reply
+}
+
+async fn connected_pair() -> (TcpStream, TcpStream, SocketAddr) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+    let (server, peer) = listener.accept().await.unwrap();
+    (client, server, peer)
+}
+
+/// Returns a signal of a shutdown that is never requested.
+fn no_shutdown() -> ShutdownSignal {
+    ShutdownHandle::new().signal(mpsc::channel(1).0)
+}
+
+async fn next_reply(receiver: &async_channel::Receiver<ProcReply<Slice>>) -> ProcReply<Slice> {
+    tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await.unwrap().unwrap()
+}
+
+/// NULL calls are answered by the read task itself: the VFS pool and the MOUNT task,
+/// spied on through their command channels, must never see them.
+#[tokio::test]
+async fn null_calls_bypass_vfs_pool_and_mount_task() {
+    let (mut client, server, peer) = connected_pair().await;
+    let (readhalf, _writehalf) = server.into_split();
+    let (mount_sender, mount_receiver) = async_channel::unbounded();
+    let (nlm_sender, nlm_receiver) = async_channel::unbounded();
+    let (result_sender, result_receiver) = async_channel::unbounded();
+    let (pool_sender, pool_receiver) = async_channel::unbounded();
+    let allocator = Arc::new(Impl::new(NonZeroUsize::new(64).unwrap(), NonZeroUsize::MIN));
+
+    ReadTask::new(readhalf, peer, mount_sender, nlm_sender, result_sender, allocator, pool_sender)
+        .spawn(&TokioSpawner, no_shutdown());
+
+    client.write_all(&null_call(1, NFS_PROGRAM, NFS_VERSION)).await.unwrap();
+    client.write_all(&null_call(2, MOUNT_PROGRAM, MOUNT_VERSION)).await.unwrap();
+
+    let reply = next_reply(&result_receiver).await;
+    assert_eq!(reply.xid, 1);
+    let Ok(ProcResult::Nfs3(res)) = reply.proc_result else {
+        panic!("expected NFS reply");
+    };
+    assert!(matches!(*res, NfsRes::Null));
+
+    let reply = next_reply(&result_receiver).await;
+    assert_eq!(reply.xid, 2);
+    let Ok(ProcResult::Mount(res)) = reply.proc_result else {
+        panic!("expected MOUNT reply");
+    };
+    assert!(matches!(*res, MountRes::Null));
+
+    assert!(pool_receiver.is_empty());
+    assert!(mount_receiver.is_empty());
+    assert!(nlm_receiver.is_empty());
+}
+
+/// Runs the whole server, from `handle_forever` down to the VFS workers, on a single
+/// thread: a `current_thread` runtime driving a `LocalSet`.
+#[test]
+fn pipeline_runs_on_current_thread_runtime() {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&runtime, async {
+        let allocator =
+            || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+        let context = ServerContext::with_spawner(
+            Arc::new(MockVfs::new(16, 1024, 1024)),
+            allocator(),
+            allocator(),
+            NonZeroUsize::MIN,
+            Arc::new(LocalSpawner),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::task::spawn_local(crate::handle_forever(
+            listener,
+            context,
+            Arc::new(MountService::with_exports(Vec::new())),
+            Arc::new(NlmService::new()),
+        ));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(&null_call(1, NFS_PROGRAM, NFS_VERSION)).await.unwrap();
+        let reply = read_reply(&mut client).await;
+        let mut src = std::io::Cursor::new(reply.as_slice());
+        record_mark(&mut src).unwrap();
+        let reply_header = header(&mut src).unwrap();
+        assert_eq!(reply_header.xid, 1);
+        assert!(matches!(
+            reply_header.status,
+            ReplyStatus::Accepted { stat: AcceptStat::Success, .. }
+        ));
+
+        // READ of 8 bytes at offset 0 of handle [1, 0, 0, 0, 0, 0, 0, 0].
+        let args = [8, 0x0100_0000, 0, 0, 0, 8];
+        client.write_all(&call(2, NFS_PROGRAM, NFS_VERSION, READ, &args)).await.unwrap();
+        let reply =
+            tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+        let mut src = std::io::Cursor::new(reply.as_slice());
+        record_mark(&mut src).unwrap();
+        assert_eq!(header(&mut src).unwrap().xid, 2);
+        let Ok((head, data)) = nfsv3::read(&mut src).unwrap() else {
+            panic!("expected READ success");
+        };
+        assert_eq!(head.count, 8);
+        assert_eq!(data.len(), 8);
+    });
+}
+
+/// A stalled backend fills the bounded request queue; the read task then stops pulling
+/// calls off the socket, so the client's writes stall instead of the server buffering them.
+#[tokio::test]
+async fn slow_backend_stops_read_task_from_draining_socket() {
+    const CALLS: u32 = 10_000;
+
+    let gate = Arc::new(Semaphore::new(0));
+    let allocator =
+        || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+    let capacity = QueueCapacity {
+        requests: NonZeroUsize::new(2).unwrap(),
+        replies: NonZeroUsize::new(2).unwrap(),
+    };
+    let context = ServerContext::with_queue_capacity(
+        Arc::new(MockVfs::new(16, 1024, 1024).with_read_gate(Arc::clone(&gate))),
+        allocator(),
+        allocator(),
+        NonZeroUsize::MIN,
+        Arc::new(TokioSpawner),
+        capacity,
+    );
+
+    // Small socket buffers keep the kernel from absorbing the backlog on its own.
+    let socket = TcpSocket::new_v4().unwrap();
+    socket.set_recv_buffer_size(4096).unwrap();
+    socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let listener = socket.listen(1).unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(crate::handle_forever(
+        listener,
+        context,
+        Arc::new(MountService::with_exports(Vec::new())),
+        Arc::new(NlmService::new()),
+    ));
+    let socket = TcpSocket::new_v4().unwrap();
+    socket.set_send_buffer_size(4096).unwrap();
+    let (mut reader, mut writer) = socket.connect(addr).await.unwrap().into_split();
+
+    // READ of 8 bytes at offset 0 of handle [1, 0, 0, 0, 0, 0, 0, 0].
+    let args = [8, 0x0100_0000, 0, 0, 0, 8];
+    let calls = (1..=CALLS)
+        .flat_map(|xid| call(xid, NFS_PROGRAM, NFS_VERSION, READ, &args))
+        .collect::<Vec<_>>();
+    let sending = tokio::spawn(async move { writer.write_all(&calls).await.unwrap() });
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!sending.is_finished(), "server accepted every call of a stalled backend");
+
+    // Once the backend catches up, every call is answered: the bounded queues and the
+    // allocator do not deadlock each other.
+    gate.add_permits(CALLS as usize);
+    for xid in 1..=CALLS {
+        let reply =
+            tokio::time::timeout(Duration::from_secs(5), read_reply(&mut reader)).await.unwrap();
+        let mut src = std::io::Cursor::new(reply.as_slice());
+        record_mark(&mut src).unwrap();
+        assert_eq!(header(&mut src).unwrap().xid, xid);
+    }
+    sending.await.unwrap();
+}
+
+/// Shutdown in the middle of a WRITE aborts the read task, waits for the backend to
+/// finish the call, answers it and hands every buffer back to the allocator.
+#[tokio::test]
+async fn shutdown_mid_write_reclaims_buffers() {
+    const BUFFERS: usize = 4;
+
+    let gate = Arc::new(Semaphore::new(0));
+    let backend = Arc::new(MockVfs::new(16, 1024, 1024).with_write_gate(Arc::clone(&gate)));
+    let allocator = || {
+        Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(BUFFERS).unwrap()))
+    };
+    let write_allocator = allocator();
+    let context = ServerContext::new(
+        Arc::clone(&backend),
+        allocator(),
+        Arc::clone(&write_allocator),
+        NonZeroUsize::MIN,
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = ShutdownHandle::new();
+    let server = tokio::spawn(crate::handle_until_shutdown(
+        listener,
+        context,
+        Arc::new(MountService::with_exports(Vec::new())),
+        Arc::new(NlmService::new()),
+        shutdown.clone(),
+    ));
+
+    // UNSTABLE WRITE of 8 bytes at offset 0 of handle [1, 0, 0, 0, 0, 0, 0, 0].
+    let args = [8, 0x0100_0000, 0, 0, 0, 8, 0, 8, 0x6461_7461, 0x6461_7461];
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client.write_all(&call(1, NFS_PROGRAM, NFS_VERSION, WRITE, &args)).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while backend.last_write_size.lock().unwrap().is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    shutdown.shutdown();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!server.is_finished(), "shutdown completed before the WRITE drained");
+
+    gate.add_permits(1);
+    tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap().unwrap();
+    let reply =
+        tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+    let mut src = std::io::Cursor::new(reply.as_slice());
+    record_mark(&mut src).unwrap();
+    assert_eq!(header(&mut src).unwrap().xid, 1);
+
+    let everything = NonZeroUsize::new(1024 * BUFFERS).unwrap();
+    let slice = tokio::time::timeout(Duration::from_secs(1), write_allocator.allocate(everything))
+        .await
+        .expect("allocator pool did not return to full")
+        .unwrap();
+    assert_eq!(slice.iter().count(), BUFFERS);
+}
+
+/// A client calling faster than the configured rate is paced, while another client of
+/// the same server, having its own bucket, is answered right away.
+#[tokio::test]
+async fn rate_limit_caps_one_connection_without_slowing_another() {
+    const RATE: u32 = 20;
+    const FLOOD: u32 = 100;
+
+    let allocator =
+        || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+    let context = ServerContext::new(
+        Arc::new(MockVfs::new(16, 1024, 1024)),
+        allocator(),
+        allocator(),
+        NonZeroUsize::MIN,
+    )
+    .with_rate_limit(RateLimit {
+        requests_per_sec: Some(std::num::NonZeroU32::new(RATE).unwrap()),
+        bytes_per_sec: None,
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(crate::handle_forever(
+        listener,
+        context,
+        Arc::new(MountService::with_exports(Vec::new())),
+        Arc::new(NlmService::new()),
+    ));
+
+    let mut flooding = TcpStream::connect(addr).await.unwrap();
+    let calls =
+        (1..=FLOOD).flat_map(|xid| null_call(xid, NFS_PROGRAM, NFS_VERSION)).collect::<Vec<_>>();
+    flooding.write_all(&calls).await.unwrap();
+    let started = tokio::time::Instant::now();
+
+    // A burst within the bucket size on a second connection is not held back by the first.
+    let mut polite = TcpStream::connect(addr).await.unwrap();
+    let calls =
+        (1..=RATE / 2).flat_map(|xid| null_call(xid, NFS_PROGRAM, NFS_VERSION)).collect::<Vec<_>>();
+    polite.write_all(&calls).await.unwrap();
+    for _ in 0..RATE / 2 {
+        tokio::time::timeout(Duration::from_millis(500), read_reply(&mut polite)).await.unwrap();
+    }
+
+    let deadline = started + Duration::from_secs(1);
+    let mut answered = 0;
+    while tokio::time::timeout_at(deadline, read_reply(&mut flooding)).await.is_ok() {
+        answered += 1;
+    }
+    // One bucket of calls right away, then RATE per second.
+    assert!(answered >= RATE, "only {answered} calls answered");
+    assert!(answered <= 2 * RATE + 1, "{answered} calls answered within a second");
+}
+
+/// A CREATE retransmitted on the same connection is answered with the reply sent for
+/// the first transmission, byte for byte, and reaches the backend once.
+#[tokio::test]
+async fn retransmitted_create_is_answered_from_duplicate_cache() {
+    let allocator =
+        || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+    let backend = Arc::new(MockVfs::new(16, 1024, 1024));
+    let context =
+        ServerContext::new(Arc::clone(&backend), allocator(), allocator(), NonZeroUsize::MIN);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(crate::handle_forever(
+        listener,
+        context,
+        Arc::new(MountService::with_exports(Vec::new())),
+        Arc::new(NlmService::new()),
+    ));
+
+    // UNCHECKED CREATE of "f" in directory [1, 0, 0, 0, 0, 0, 0, 0], no attributes set.
+    let args = [8, 0x0100_0000, 0, 1, 0x6600_0000, 0, 0, 0, 0, 0, 0, 0];
+    let create = call(1, NFS_PROGRAM, NFS_VERSION, CREATE, &args);
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client.write_all(&create).await.unwrap();
+    let first =
+        tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+    client.write_all(&create).await.unwrap();
+    let second =
+        tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(*backend.create_calls.lock().unwrap(), 1);
+}
+
+/// A call reusing the xid of a recorded one for another procedure is executed, not
+/// answered with the reply recorded for the first call.
+#[tokio::test]
+async fn call_reusing_an_xid_for_another_procedure_is_executed() {
+    let allocator =
+        || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+    let backend = Arc::new(MockVfs::new(16, 1024, 1024));
+    let context =
+        ServerContext::new(Arc::clone(&backend), allocator(), allocator(), NonZeroUsize::MIN);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(crate::handle_forever(
+        listener,
+        context,
+        Arc::new(MountService::with_exports(Vec::new())),
+        Arc::new(NlmService::new()),
+    ));
+
+    // CREATE, then REMOVE, of "f" in directory [1, 0, 0, 0, 0, 0, 0, 0], both as xid 1.
+    let dir_op = [8, 0x0100_0000, 0, 1, 0x6600_0000];
+    let mut create_args = dir_op.to_vec();
+    create_args.extend([0, 0, 0, 0, 0, 0, 0]);
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client.write_all(&call(1, NFS_PROGRAM, NFS_VERSION, CREATE, &create_args)).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+    client.write_all(&call(1, NFS_PROGRAM, NFS_VERSION, REMOVE, &dir_op)).await.unwrap();
+    let reply =
+        tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+
+    let mut src = std::io::Cursor::new(reply.as_slice());
+    record_mark(&mut src).unwrap();
+    assert_eq!(header(&mut src).unwrap().xid, 1);
+    let Err(fail) = nfsv3::remove(&mut src).unwrap() else {
+        panic!("expected the REMOVE failure of the backend");
+    };
+    assert_eq!(fail.error, Error::Access);
+    assert_eq!(*backend.create_calls.lock().unwrap(), 1);
+}
+
+/// Mounts `path` with MNT call `xid` and returns the root handle.
+async fn mount(client: &mut TcpStream, xid: u32, path: &str) -> [u8; 8] {
+    let mut args = vec![path.len() as u32];
+    args.extend(path.as_bytes().chunks(4).map(|chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u32::from_be_bytes(word)
+    }));
+    client.write_all(&call(xid, MOUNT_PROGRAM, MOUNT_VERSION, MOUNT_MNT, &args)).await.unwrap();
+    let reply = tokio::time::timeout(Duration::from_secs(5), read_reply(client)).await.unwrap();
+    let mut src = std::io::Cursor::new(reply.as_slice());
+    record_mark(&mut src).unwrap();
+    assert_eq!(header(&mut src).unwrap().xid, xid);
+    let mut words = [0u8; 8];
+    std::io::Read::read_exact(&mut src, &mut words).unwrap();
+    assert_eq!(words, [0, 0, 0, 0, 0, 0, 0, 8], "MNT of {path} should succeed");
+    let mut handle = [0u8; 8];
+    std::io::Read::read_exact(&mut src, &mut handle).unwrap();
+    handle
+}
+
+/// Reads 4 bytes at offset 0 of `handle` with READ call `xid`.
+async fn read_head(
+    client: &mut TcpStream,
+    xid: u32,
+    handle: [u8; 8],
+) -> Result<crate::vfs::read::SuccessPartial, Error> {
+    let word = |at: usize| u32::from_be_bytes(handle[at..at + 4].try_into().unwrap());
+    let args = [8, word(0), word(4), 0, 0, 4];
+    client.write_all(&call(xid, NFS_PROGRAM, NFS_VERSION, READ, &args)).await.unwrap();
+    let reply = tokio::time::timeout(Duration::from_secs(5), read_reply(client)).await.unwrap();
+    let mut src = std::io::Cursor::new(reply.as_slice());
+    record_mark(&mut src).unwrap();
+    assert_eq!(header(&mut src).unwrap().xid, xid);
+    nfsv3::read(&mut src).unwrap().map(|(head, _)| head).map_err(|fail| fail.error)
+}
+
+/// Marks the backend of the second export as a type of its own.
+struct SmallExport;
+
+/// Two exports served by backends of different types over one connection: each MNT
+/// hands out a handle routed to its own backend, READs are clamped to the limits of
+/// the export they target, and handles of no export are rejected.
+#[tokio::test]
+async fn export_set_routes_each_export_to_its_backend() {
+    let export = |path: &str| ExportEntry {
+        directory: file::Path::new(path.to_owned()).unwrap(),
+        names: Vec::new(),
+    };
+    let root = file::Handle([0, 0, 0, 0, 0, 0, 0, 1]);
+    let mut exports = ExportSet::new();
+    exports.add(export("/large"), Arc::new(MockVfs::new(16, 1024, 1024)), root.clone()).unwrap();
+    let small = MockVfs::new(16, 2, 1024).of_kind::<SmallExport>();
+    exports
+        .add_with_auth_flavors(export("/small"), Arc::new(small), root, vec![AuthFlavor::None])
+        .unwrap();
+    let entries = exports.mount_entries();
+    assert_eq!(entries[0].auth_flavors, DEFAULT_AUTH_FLAVORS.to_vec());
+    assert_eq!(entries[1].auth_flavors, vec![AuthFlavor::None]);
+    let mount_service = Arc::new(MountService::with_exports(entries));
+
+    let allocator =
+        || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+    let context =
+        ServerContext::new(Arc::new(exports), allocator(), allocator(), NonZeroUsize::MIN);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(crate::handle_forever(
+        listener,
+        context,
+        mount_service,
+        Arc::new(NlmService::new()),
+    ));
+    let mut client = TcpStream::connect(addr).await.unwrap();
+
+    let large = mount(&mut client, 1, "/large").await;
+    let small = mount(&mut client, 2, "/small").await;
+    assert_ne!(large, small);
+
+    // The single worker serves both exports, yet applies each export's own limits.
+    let head = read_head(&mut client, 3, large).await.unwrap();
+    assert_eq!((head.count, head.eof), (4, false));
+    let head = read_head(&mut client, 4, small).await.unwrap();
+    assert_eq!((head.count, head.eof), (2, false));
+
+    let mut unknown = large;
+    unknown[0] = 3;
+    assert!(matches!(read_head(&mut client, 5, unknown).await, Err(Error::BadFileHandle)));
+    let mut bare = large;
+    bare[0] = 0;
+    assert!(matches!(read_head(&mut client, 6, bare).await, Err(Error::BadFileHandle)));
+}
+
+/// A backend handing out a handle that already carries a namespace fails the call
+/// instead of the client getting a reply without the handle.
+#[tokio::test]
+async fn export_set_fails_calls_whose_new_handle_uses_the_namespace_byte() {
+    let mut exports = ExportSet::<Slice>::new();
+    let entry =
+        ExportEntry { directory: file::Path::new("/".to_owned()).unwrap(), names: Vec::new() };
+    let root = file::Handle([0, 0, 0, 0, 0, 0, 0, 1]);
+    let root = exports.add(entry, Arc::new(MockVfs::new(16, 1024, 1024)), root).unwrap();
+
+    // The mock creates files with handles whose first byte is set.
+    let args = create::Args {
+        object: DirOpArgs { dir: root, name: file::Name::new("new".to_owned()).unwrap() },
+        how: create::How::Exclusive(create::Verifier([0; 8])),
+    };
+    let result = create::Create::create(&exports, &Credentials::anonymous(), args).await;
+    assert!(matches!(result, Err(create::Fail { error: Error::ServerFault, .. })));
+}
+
+/// A server configured with keepalive probing applies it to the connections it accepts.
+#[tokio::test]
+async fn accepted_connection_gets_configured_keepalive() {
+    let allocator =
+        || Arc::new(Impl::new(NonZeroUsize::new(1024).unwrap(), NonZeroUsize::new(4).unwrap()));
+    let keepalive =
+        Keepalive { idle: Duration::from_secs(45), interval: Duration::from_secs(3), retries: 5 };
+    let context = ServerContext::new(
+        Arc::new(MockVfs::new(16, 1024, 1024)),
+        allocator(),
+        allocator(),
+        NonZeroUsize::MIN,
+    )
+    .with_socket_config(SocketConfig { keepalive: Some(keepalive), ..SocketConfig::default() });
+    let (mut client, server, _) = connected_pair().await;
+    let fd = server.as_raw_fd();
+    let (mount_sender, _mount_receiver) = async_channel::unbounded();
+    let (nlm_sender, _nlm_receiver) = async_channel::unbounded();
+
+    super::new(server, mount_sender, nlm_sender, &context, &no_shutdown()).await;
+
+    // The connection is answering calls, so its tasks still own the socket.
+    client.write_all(&null_call(1, NFS_PROGRAM, NFS_VERSION)).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), read_reply(&mut client)).await.unwrap();
+    // SAFETY: `fd` stays open while the connection tasks hold the accepted socket.
+    let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+    let socket = SockRef::from(&fd);
+    assert!(socket.keepalive().unwrap());
+    assert_eq!(socket.tcp_keepalive_time().unwrap(), keepalive.idle);
+    assert_eq!(socket.tcp_keepalive_interval().unwrap(), keepalive.interval);
+    assert_eq!(socket.tcp_keepalive_retries().unwrap(), keepalive.retries);
+    assert!(socket.tcp_nodelay().unwrap());
+}
@@ -1,17 +1,26 @@
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use tokio::net::tcp::OwnedWriteHalf;
 use tracing::error;
 
 use crate::allocator::Buffer;
-use crate::rpc::{AuthFlavor, OpaqueAuth};
-use crate::serializer;
+use crate::rpc::OpaqueAuth;
+use crate::serializer::server::serialize_struct::{Serializer, DEFAULT_MAX_REPLY_BYTES};
+use crate::shutdown::ShutdownSignal;
+use crate::spawner::Spawner;
 use crate::task::ProcReply;
 
+use super::dup_cache::DuplicateCache;
+
 /// Writes [`super::super::global::vfs::VfsPool`] responses to a network connection.
 pub struct WriteTask<B: Buffer> {
     writehalf: OwnedWriteHalf,
     result_receiver: async_channel::Receiver<ProcReply<B>>,
+    #[cfg(feature = "compression")]
+    read_compression: Option<i32>,
+    max_reply_bytes: usize,
+    duplicate_cache: Option<Arc<DuplicateCache>>,
     _phantom: PhantomData<B>,
 }
 
@@ -21,39 +30,84 @@ impl<B: Buffer> WriteTask<B> {
         writehalf: OwnedWriteHalf,
         result_receiver: async_channel::Receiver<ProcReply<B>>,
     ) -> Self {
-        Self { writehalf, result_receiver, _phantom: PhantomData }
+        Self {
+            writehalf,
+            result_receiver,
+            #[cfg(feature = "compression")]
+            read_compression: None,
+            max_reply_bytes: DEFAULT_MAX_REPLY_BYTES,
+            duplicate_cache: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Compresses the data of READ replies with zstd at `level`, if set.
+    #[cfg(feature = "compression")]
+    pub fn with_read_compression(mut self, level: Option<i32>) -> Self {
+        self.read_compression = level;
+        self
+    }
+
+    /// Caps the size of a single reply at `len` bytes, excluding its record mark.
+    pub fn with_max_reply_bytes(mut self, len: usize) -> Self {
+        self.max_reply_bytes = len;
+        self
+    }
+
+    /// Records in `cache` the replies to calls the read task registered there.
+    pub fn with_duplicate_cache(mut self, cache: Arc<DuplicateCache>) -> Self {
+        self.duplicate_cache = Some(cache);
+        self
     }
 
     /// Spawns a [`WriteTask`] that writes command results to a socket.
     ///
+    /// The task holds `shutdown` until every reply of the connection is written, so a
+    /// shutdown waits for the connection to drain.
+    ///
     /// # Panics
     ///
-    /// If called outside of tokio runtime context.
-    pub fn spawn(self)
+    /// If `spawner` cannot launch tasks in the current context.
+    pub fn spawn(self, spawner: &dyn Spawner, shutdown: ShutdownSignal)
     where
         B: 'static,
     {
-        tokio::spawn(async move { self.run().await });
+        spawner.spawn(Box::pin(async move {
+            self.run().await;
+            drop(shutdown);
+        }));
     }
 
     async fn run(self) {
         let result_receiver = self.result_receiver;
         let mut serializer =
-            serializer::server::serialize_struct::Serializer::<B, _>::new(self.writehalf);
+            Serializer::<B, _>::new(self.writehalf).with_max_reply_bytes(self.max_reply_bytes);
+        #[cfg(feature = "compression")]
+        if let Some(level) = self.read_compression {
+            serializer = serializer.with_read_compression(level);
+        }
 
         while let Ok(reply) = result_receiver.recv().await {
-            // TODO: <https://github.com/RMamonts/nfs-mamont/issues/143>
-            // Use proper authentication verifier instead of None
-            let verifier = OpaqueAuth { flavor: AuthFlavor::None, body: vec![] };
-
-            match serializer.form_reply(reply, verifier).await {
+            // Calls are only accepted with AUTH_NONE or AUTH_SYS credentials, both of which
+            // are answered with an AUTH_NONE verifier.
+            let result = match (&self.duplicate_cache, reply.call) {
+                (Some(cache), Some(key)) if cache.is_in_progress(&key) => {
+                    let result = serializer.form_recorded_reply(reply, OpaqueAuth::none()).await;
+                    cache.complete(&key, result.as_ref().ok().cloned().flatten());
+                    result.map(drop)
+                }
+                _ => serializer.form_reply(reply, OpaqueAuth::none()).await,
+            };
+            match result {
                 Ok(_) => {
                     // Reply successfully written to socket
                 }
+                Err(e) if serializer.is_broken() => {
+                    error!(error=%e, "write task: failed to send reply, closing connection");
+                    break;
+                }
                 Err(e) => {
-                    error!(error=%e, "write task: failed to serialize/send reply");
-                    // TODO: Consider closing connection or continuing based on error type
-                    // For now, continue processing other replies
+                    error!(error=%e, "write task: failed to serialize reply");
                 }
             };
         }
@@ -10,3 +10,8 @@
 pub mod mount;
 pub mod nlm;
 pub mod vfs;
+
+mod strict;
+
+#[cfg(test)]
+pub(crate) mod tests;
@@ -7,6 +7,7 @@ use tracing::debug;
 use crate::allocator::Buffer;
 use crate::mount::{Mount, MountRes};
 use crate::parser::{MountArgWrapper, MountArguments};
+use crate::spawner::Spawner;
 use crate::task::{ProcReply, ProcResult};
 
 /// Command sent to [`MountTask`] from connection read tasks.
@@ -49,9 +50,9 @@ where
     ///
     /// # Panics
     ///
-    /// If called outside of tokio runtime context.
-    pub fn spawn(self) {
-        tokio::spawn(async move { self.run().await });
+    /// If `spawner` cannot launch tasks in the current context.
+    pub fn spawn(self, spawner: &dyn Spawner) {
+        spawner.spawn(Box::pin(self.run()));
     }
 
     async fn run(self) {
@@ -118,6 +119,7 @@ where
                 .send(ProcReply {
                     xid: header.xid,
                     proc_result: Ok(ProcResult::Mount(Box::new(mount_result))),
+                    call: None,
                 })
                 .await;
             debug!(xid = header.xid, "mount task: reply queued");
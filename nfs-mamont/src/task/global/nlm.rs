@@ -10,6 +10,7 @@ use tracing::debug;
 
 use crate::allocator::Buffer;
 use crate::nlm::Nlm;
+use crate::spawner::Spawner;
 use crate::task::{ProcReply, ProcResult};
 use crate::{
     nlm::NlmRes,
@@ -49,16 +50,16 @@ where
         (task, sender)
     }
 
-    /// Spawns the [`NlmTask`] on the current Tokio runtime.
+    /// Spawns the [`NlmTask`] with `spawner`.
     ///
     /// The task processes NLM commands received from read tasks and
     /// returns results to write tasks.
     ///
     /// # Panics
     ///
-    /// If called outside a Tokio runtime context.
-    pub fn spawn(self) {
-        tokio::spawn(async move { self.run().await });
+    /// If `spawner` cannot launch tasks in the current context.
+    pub fn spawn(self, spawner: &dyn Spawner) {
+        spawner.spawn(Box::pin(self.run()));
     }
 
     /// Main event loop: waits for commands, dispatches to the NLM service,
@@ -104,6 +105,7 @@ where
                 .send(ProcReply {
                     xid: header.xid,
                     proc_result: Ok(ProcResult::Nlm4(Box::new(nlm_result))),
+                    call: None,
                 })
                 .await;
             debug!(xid = header.xid, "nlm task: reply queued");
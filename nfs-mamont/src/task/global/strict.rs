@@ -0,0 +1,166 @@
+//! Checks of the `fattr3` attributes a backend reports, run before replies are
+//! serialized when strict attributes are enabled with
+//! [`super::vfs::VfsPool::set_strict_attrs`].
+//!
+//! Clients cache attributes and act on them, so malformed ones are worse than an
+//! error: a reply carrying any is replaced by a `NFS3ERR_SERVERFAULT` failure.
+
+use crate::allocator::Buffer;
+use crate::vfs::{
+    access, commit, create, file, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir,
+    mk_node, path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl,
+    set_attr, symlink, write, Error, NfsRes, WccData,
+};
+
+/// File type bits of a mode.
+const S_IFMT: u32 = 0o170_000;
+
+/// Returns the first invariant broken by an attribute set in `response`, if any.
+pub(super) fn violation<B: Buffer>(response: &NfsRes<B>) -> Option<&'static str> {
+    attrs(response).into_iter().find_map(attr_violation)
+}
+
+/// Returns `response` turned into a `NFS3ERR_SERVERFAULT` failure without attributes.
+pub(super) fn server_fault<B: Buffer>(response: &NfsRes<B>) -> NfsRes<B> {
+    let error = Error::ServerFault;
+    let no_wcc = || WccData { before: None, after: None };
+    match response {
+        NfsRes::Null => NfsRes::Null,
+        NfsRes::GetAttr(_) => NfsRes::GetAttr(Err(get_attr::Fail { error })),
+        NfsRes::SetAttr(_) => NfsRes::SetAttr(Err(set_attr::Fail { error, wcc_data: no_wcc() })),
+        NfsRes::LookUp(_) => NfsRes::LookUp(Err(lookup::Fail { error, dir_attr: None })),
+        NfsRes::Access(_) => NfsRes::Access(Err(access::Fail { error, object_attr: None })),
+        NfsRes::ReadLink(_) => NfsRes::ReadLink(Err(read_link::Fail { error, symlink_attr: None })),
+        NfsRes::Read(_) => NfsRes::Read(Err(read::Fail { error, file_attr: None })),
+        NfsRes::Write(_) => NfsRes::Write(Err(write::Fail { error, wcc_data: no_wcc() })),
+        NfsRes::Create(_) => NfsRes::Create(Err(create::Fail { error, wcc_data: no_wcc() })),
+        NfsRes::MkDir(_) => NfsRes::MkDir(Err(mk_dir::Fail { error, dir_wcc: no_wcc() })),
+        NfsRes::SymLink(_) => NfsRes::SymLink(Err(symlink::Fail { error, dir_wcc: no_wcc() })),
+        NfsRes::MkNod(_) => NfsRes::MkNod(Err(mk_node::Fail { error, dir_wcc: no_wcc() })),
+        NfsRes::Remove(_) => NfsRes::Remove(Err(remove::Fail { error, dir_wcc: no_wcc() })),
+        NfsRes::RmDir(_) => NfsRes::RmDir(Err(rm_dir::Fail { error, dir_wcc: no_wcc() })),
+        NfsRes::Rename(_) => NfsRes::Rename(Err(rename::Fail {
+            error,
+            from_dir_wcc: no_wcc(),
+            to_dir_wcc: no_wcc(),
+        })),
+        NfsRes::Link(_) => {
+            NfsRes::Link(Err(link::Fail { error, file_attr: None, dir_wcc: no_wcc() }))
+        }
+        NfsRes::ReadDir(_) => NfsRes::ReadDir(Err(read_dir::Fail { error, dir_attr: None })),
+        NfsRes::ReadDirPlus(_) => {
+            NfsRes::ReadDirPlus(Err(read_dir_plus::Fail { error, dir_attr: None }))
+        }
+        NfsRes::FsStat(_) => NfsRes::FsStat(Err(fs_stat::Fail { error, root_attr: None })),
+        NfsRes::FsInfo(_) => NfsRes::FsInfo(Err(fs_info::Fail { error, root_attr: None })),
+        NfsRes::PathConf(_) => NfsRes::PathConf(Err(path_conf::Fail { error, file_attr: None })),
+        NfsRes::Commit(_) => NfsRes::Commit(Err(commit::Fail { error, file_wcc: no_wcc() })),
+        NfsRes::GetAcl(_) => NfsRes::GetAcl(Err(get_acl::Fail { error, file_attr: None })),
+        NfsRes::SetAcl(_) => NfsRes::SetAcl(Err(set_acl::Fail { error, file_attr: None })),
+    }
+}
+
+/// Returns the first invariant `attr` breaks, if any.
+fn attr_violation(attr: &file::Attr) -> Option<&'static str> {
+    if attr.nlink == 0 {
+        return Some("existing file reported with no links");
+    }
+    if matches!(attr.file_type, file::Type::Symlink) && attr.size == 0 {
+        return Some("symbolic link reported with an empty target");
+    }
+    // Backends may report the permission bits only; type bits, when present, must agree.
+    let type_bits = attr.mode & S_IFMT;
+    if type_bits != 0 && type_bits != mode_type_bits(attr.file_type) {
+        return Some("mode type bits disagree with the file type");
+    }
+    None
+}
+
+/// Returns the `S_IFMT` bits of a file of type `file_type`.
+fn mode_type_bits(file_type: file::Type) -> u32 {
+    match file_type {
+        file::Type::Regular => 0o100_000,
+        file::Type::Directory => 0o040_000,
+        file::Type::BlockDevice => 0o060_000,
+        file::Type::CharacterDevice => 0o020_000,
+        file::Type::Symlink => 0o120_000,
+        file::Type::Socket => 0o140_000,
+        file::Type::Fifo => 0o010_000,
+    }
+}
+
+/// Returns every attribute set `response` carries.
+fn attrs<B: Buffer>(response: &NfsRes<B>) -> Vec<&file::Attr> {
+    let mut attrs: Vec<Option<&file::Attr>> = Vec::new();
+    match response {
+        NfsRes::Null => {}
+        NfsRes::GetAttr(Ok(success)) => attrs.push(Some(&success.object)),
+        NfsRes::GetAttr(Err(_)) => {}
+        NfsRes::SetAttr(Ok(success)) => attrs.push(success.wcc_data.after.as_ref()),
+        NfsRes::SetAttr(Err(fail)) => attrs.push(fail.wcc_data.after.as_ref()),
+        NfsRes::LookUp(Ok(success)) => {
+            attrs.extend([success.file_attr.as_ref(), success.dir_attr.as_ref()]);
+        }
+        NfsRes::LookUp(Err(fail)) => attrs.push(fail.dir_attr.as_ref()),
+        NfsRes::Access(Ok(success)) => attrs.push(success.object_attr.as_ref()),
+        NfsRes::Access(Err(fail)) => attrs.push(fail.object_attr.as_ref()),
+        NfsRes::ReadLink(Ok(success)) => attrs.push(success.symlink_attr.as_ref()),
+        NfsRes::ReadLink(Err(fail)) => attrs.push(fail.symlink_attr.as_ref()),
+        NfsRes::Read(Ok(success)) => attrs.push(success.head.file_attr.as_ref()),
+        NfsRes::Read(Err(fail)) => attrs.push(fail.file_attr.as_ref()),
+        NfsRes::Write(Ok(success)) => attrs.push(success.file_wcc.after.as_ref()),
+        NfsRes::Write(Err(fail)) => attrs.push(fail.wcc_data.after.as_ref()),
+        NfsRes::Create(Ok(success)) => {
+            attrs.extend([success.attr.as_ref(), success.wcc_data.after.as_ref()]);
+        }
+        NfsRes::Create(Err(fail)) => attrs.push(fail.wcc_data.after.as_ref()),
+        NfsRes::MkDir(Ok(success)) => {
+            attrs.extend([success.attr.as_ref(), success.wcc_data.after.as_ref()]);
+        }
+        NfsRes::MkDir(Err(fail)) => attrs.push(fail.dir_wcc.after.as_ref()),
+        NfsRes::SymLink(Ok(success)) => {
+            attrs.extend([success.attr.as_ref(), success.wcc_data.after.as_ref()]);
+        }
+        NfsRes::SymLink(Err(fail)) => attrs.push(fail.dir_wcc.after.as_ref()),
+        NfsRes::MkNod(Ok(success)) => {
+            attrs.extend([success.attr.as_ref(), success.wcc_data.after.as_ref()]);
+        }
+        NfsRes::MkNod(Err(fail)) => attrs.push(fail.dir_wcc.after.as_ref()),
+        NfsRes::Remove(Ok(success)) => attrs.push(success.wcc_data.after.as_ref()),
+        NfsRes::Remove(Err(fail)) => attrs.push(fail.dir_wcc.after.as_ref()),
+        NfsRes::RmDir(Ok(success)) => attrs.push(success.wcc_data.after.as_ref()),
+        NfsRes::RmDir(Err(fail)) => attrs.push(fail.dir_wcc.after.as_ref()),
+        NfsRes::Rename(Ok(success)) => {
+            attrs.extend([success.from_dir_wcc.after.as_ref(), success.to_dir_wcc.after.as_ref()])
+        }
+        NfsRes::Rename(Err(fail)) => {
+            attrs.extend([fail.from_dir_wcc.after.as_ref(), fail.to_dir_wcc.after.as_ref()]);
+        }
+        NfsRes::Link(Ok(success)) => {
+            attrs.extend([success.file_attr.as_ref(), success.dir_wcc.after.as_ref()]);
+        }
+        NfsRes::Link(Err(fail)) => {
+            attrs.extend([fail.file_attr.as_ref(), fail.dir_wcc.after.as_ref()]);
+        }
+        NfsRes::ReadDir(Ok(success)) => attrs.push(success.dir_attr.as_ref()),
+        NfsRes::ReadDir(Err(fail)) => attrs.push(fail.dir_attr.as_ref()),
+        NfsRes::ReadDirPlus(Ok(success)) => {
+            attrs.push(success.dir_attr.as_ref());
+            attrs.extend(success.entries.iter().map(|entry| entry.file_attr.as_ref()));
+        }
+        NfsRes::ReadDirPlus(Err(fail)) => attrs.push(fail.dir_attr.as_ref()),
+        NfsRes::FsStat(Ok(success)) => attrs.push(success.root_attr.as_ref()),
+        NfsRes::FsStat(Err(fail)) => attrs.push(fail.root_attr.as_ref()),
+        NfsRes::FsInfo(Ok(success)) => attrs.push(success.root_attr.as_ref()),
+        NfsRes::FsInfo(Err(fail)) => attrs.push(fail.root_attr.as_ref()),
+        NfsRes::PathConf(Ok(success)) => attrs.push(success.file_attr.as_ref()),
+        NfsRes::PathConf(Err(fail)) => attrs.push(fail.file_attr.as_ref()),
+        NfsRes::Commit(Ok(success)) => attrs.push(success.file_wcc.after.as_ref()),
+        NfsRes::Commit(Err(fail)) => attrs.push(fail.file_wcc.after.as_ref()),
+        NfsRes::GetAcl(Ok(success)) => attrs.push(success.file_attr.as_ref()),
+        NfsRes::GetAcl(Err(fail)) => attrs.push(fail.file_attr.as_ref()),
+        NfsRes::SetAcl(Ok(success)) => attrs.push(success.file_attr.as_ref()),
+        NfsRes::SetAcl(Err(fail)) => attrs.push(fail.file_attr.as_ref()),
+    }
+    attrs.into_iter().flatten().collect()
+}
@@ -0,0 +1,271 @@
+//! Backend shim answering [`vfs::Error::Jukebox`] while a file is "fetched from cold storage".
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::allocator::Slice;
+use crate::parser::NfsArguments;
+use crate::rpc::{AuthFlavor, OpaqueAuth};
+use crate::serializer::server::serialize_struct::Serializer;
+use crate::task::{ProcReply, ProcResult};
+use crate::vfs::{
+    self, access, acl, commit, create, file, fs_info, fs_stat, get_attr, link, lookup, mk_dir,
+    mk_node, path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_attr,
+    symlink, write, Credentials, NfsRes, WccData,
+};
+
+use super::{dispatch, file_handle, pool, MockVfs, XID};
+
+/// Wraps a backend and answers the first `delay` READ and WRITE calls for each file
+/// with [`vfs::Error::Jukebox`], as a backend recalling migrated data would.
+/// All other procedures are forwarded to the inner backend unchanged.
+pub struct DelayedFs<V> {
+    inner: V,
+    delay: u32,
+    accesses: Mutex<HashMap<file::Handle, u32>>,
+}
+
+impl<V> DelayedFs<V> {
+    pub fn new(inner: V, delay: u32) -> Self {
+        Self { inner, delay, accesses: Mutex::new(HashMap::new()) }
+    }
+
+    /// Counts an access to `file` and returns `true` while it still has to be delayed.
+    fn still_fetching(&self, file: &file::Handle) -> bool {
+        let mut accesses = self.accesses.lock().unwrap();
+        let count = accesses.entry(file.clone()).or_default();
+        *count += 1;
+        *count <= self.delay
+    }
+}
+
+impl<V: read::Read<Slice> + Sync> read::Read<Slice> for DelayedFs<V> {
+    async fn read(
+        &self,
+        cred: &Credentials,
+        args: read::Args,
+        data: Slice,
+    ) -> Result<read::Success<Slice>, read::Fail> {
+        if self.still_fetching(&args.file) {
+            return Err(read::Fail { error: vfs::Error::Jukebox, file_attr: None });
+        }
+        self.inner.read(cred, args, data).await
+    }
+}
+
+impl<V: write::Write<Slice> + Sync> write::Write<Slice> for DelayedFs<V> {
+    async fn write(
+        &self,
+        cred: &Credentials,
+        args: write::Args<Slice>,
+    ) -> Result<write::Success, write::Fail> {
+        if self.still_fetching(&args.file) {
+            return Err(write::Fail {
+                error: vfs::Error::Jukebox,
+                wcc_data: WccData { before: None, after: None },
+            });
+        }
+        self.inner.write(cred, args).await
+    }
+}
+
+impl<V: get_attr::GetAttr + Sync> get_attr::GetAttr for DelayedFs<V> {
+    async fn get_attr(&self, args: get_attr::Args) -> Result<get_attr::Success, get_attr::Fail> {
+        self.inner.get_attr(args).await
+    }
+}
+
+impl<V: lookup::Lookup + Sync> lookup::Lookup for DelayedFs<V> {
+    async fn lookup(&self, args: lookup::Args) -> Result<lookup::Success, lookup::Fail> {
+        self.inner.lookup(args).await
+    }
+}
+
+impl<V: read_link::ReadLink + Sync> read_link::ReadLink for DelayedFs<V> {
+    async fn read_link(
+        &self,
+        args: read_link::Args,
+    ) -> Result<read_link::Success, read_link::Fail> {
+        self.inner.read_link(args).await
+    }
+}
+
+impl<V: read_dir::ReadDir + Sync> read_dir::ReadDir for DelayedFs<V> {
+    async fn read_dir(&self, args: read_dir::Args) -> Result<read_dir::Success, read_dir::Fail> {
+        self.inner.read_dir(args).await
+    }
+}
+
+impl<V: read_dir_plus::ReadDirPlus + Sync> read_dir_plus::ReadDirPlus for DelayedFs<V> {
+    async fn read_dir_plus(
+        &self,
+        args: read_dir_plus::Args,
+    ) -> Result<read_dir_plus::Success, read_dir_plus::Fail> {
+        self.inner.read_dir_plus(args).await
+    }
+}
+
+impl<V: fs_stat::FsStat + Sync> fs_stat::FsStat for DelayedFs<V> {
+    async fn fs_stat(&self, args: fs_stat::Args) -> Result<fs_stat::Success, fs_stat::Fail> {
+        self.inner.fs_stat(args).await
+    }
+}
+
+impl<V: fs_info::FsInfo + Sync> fs_info::FsInfo for DelayedFs<V> {
+    async fn fs_info(&self, args: fs_info::Args) -> Result<fs_info::Success, fs_info::Fail> {
+        self.inner.fs_info(args).await
+    }
+}
+
+impl<V: path_conf::PathConf + Sync> path_conf::PathConf for DelayedFs<V> {
+    async fn path_conf(
+        &self,
+        args: path_conf::Args,
+    ) -> Result<path_conf::Success, path_conf::Fail> {
+        self.inner.path_conf(args).await
+    }
+}
+
+impl<V: commit::Commit + Sync> commit::Commit for DelayedFs<V> {
+    async fn commit(&self, args: commit::Args) -> Result<commit::Success, commit::Fail> {
+        self.inner.commit(args).await
+    }
+}
+
+impl<V: Send + Sync> acl::Acl for DelayedFs<V> {}
+
+impl<V: set_attr::SetAttr + Sync> set_attr::SetAttr for DelayedFs<V> {
+    async fn set_attr(
+        &self,
+        cred: &Credentials,
+        args: set_attr::Args,
+    ) -> Result<set_attr::Success, set_attr::Fail> {
+        self.inner.set_attr(cred, args).await
+    }
+}
+
+impl<V: access::Access + Sync> access::Access for DelayedFs<V> {
+    async fn access(
+        &self,
+        cred: &Credentials,
+        args: access::Args,
+    ) -> Result<access::Success, access::Fail> {
+        self.inner.access(cred, args).await
+    }
+}
+
+impl<V: create::Create + Sync> create::Create for DelayedFs<V> {
+    async fn create(
+        &self,
+        cred: &Credentials,
+        args: create::Args,
+    ) -> Result<create::Success, create::Fail> {
+        self.inner.create(cred, args).await
+    }
+}
+
+impl<V: mk_dir::MkDir + Sync> mk_dir::MkDir for DelayedFs<V> {
+    async fn mk_dir(
+        &self,
+        cred: &Credentials,
+        args: mk_dir::Args,
+    ) -> Result<mk_dir::Success, mk_dir::Fail> {
+        self.inner.mk_dir(cred, args).await
+    }
+}
+
+impl<V: symlink::Symlink + Sync> symlink::Symlink for DelayedFs<V> {
+    async fn symlink(
+        &self,
+        cred: &Credentials,
+        args: symlink::Args,
+    ) -> Result<symlink::Success, symlink::Fail> {
+        self.inner.symlink(cred, args).await
+    }
+}
+
+impl<V: mk_node::MkNode + Sync> mk_node::MkNode for DelayedFs<V> {
+    async fn mk_node(
+        &self,
+        cred: &Credentials,
+        args: mk_node::Args,
+    ) -> Result<mk_node::Success, mk_node::Fail> {
+        self.inner.mk_node(cred, args).await
+    }
+}
+
+impl<V: remove::Remove + Sync> remove::Remove for DelayedFs<V> {
+    async fn remove(
+        &self,
+        cred: &Credentials,
+        args: remove::Args,
+    ) -> Result<remove::Success, remove::Fail> {
+        self.inner.remove(cred, args).await
+    }
+}
+
+impl<V: rm_dir::RmDir + Sync> rm_dir::RmDir for DelayedFs<V> {
+    async fn rm_dir(
+        &self,
+        cred: &Credentials,
+        args: rm_dir::Args,
+    ) -> Result<rm_dir::Success, rm_dir::Fail> {
+        self.inner.rm_dir(cred, args).await
+    }
+}
+
+impl<V: rename::Rename + Sync> rename::Rename for DelayedFs<V> {
+    async fn rename(
+        &self,
+        cred: &Credentials,
+        args: rename::Args,
+    ) -> Result<rename::Success, rename::Fail> {
+        self.inner.rename(cred, args).await
+    }
+}
+
+impl<V: link::Link + Sync> link::Link for DelayedFs<V> {
+    async fn link(
+        &self,
+        cred: &Credentials,
+        args: link::Args,
+    ) -> Result<link::Success, link::Fail> {
+        self.inner.link(cred, args).await
+    }
+}
+
+#[tokio::test]
+async fn read_is_retried_until_file_is_fetched() {
+    let backend = Arc::new(DelayedFs::new(MockVfs::new(16, 1024, 1024), 2));
+    let pool = pool(backend, 1024, 4);
+
+    for _ in 0..2 {
+        let args = read::Args { file: file_handle(), offset: 0, count: 16 };
+        let NfsRes::Read(Err(fail)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+            panic!("expected READ to ask for a retry");
+        };
+        assert_eq!(fail.error, vfs::Error::Jukebox);
+    }
+
+    let args = read::Args { file: file_handle(), offset: 0, count: 16 };
+    let NfsRes::Read(Ok(success)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ success once the file is fetched");
+    };
+    assert_eq!(success.head.count, 16);
+}
+
+#[tokio::test]
+async fn jukebox_is_serialized_as_nfs3err_jukebox() {
+    let fail = read::Fail { error: vfs::Error::Jukebox, file_attr: None };
+    let proc_result = Ok(ProcResult::Nfs3(Box::new(NfsRes::<Slice>::Read(Err(fail)))));
+    let mut serializer = Serializer::<Slice, _>::new(Vec::new());
+    let verifier = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    serializer.form_reply(ProcReply { xid: XID, proc_result, call: None }, verifier).await.unwrap();
+
+    let bytes = serializer.into_inner();
+    let words: Vec<u32> =
+        bytes[4..].chunks(4).map(|word| u32::from_be_bytes(word.try_into().unwrap())).collect();
+    // xid, REPLY, MSG_ACCEPTED, AUTH_NONE verifier, SUCCESS, then the NFS status
+    // and absent post-op attributes.
+    assert_eq!(words, [XID, 1, 0, 0, 0, 0, 10008, 0]);
+}
@@ -0,0 +1,465 @@
+mod delayed;
+mod vfs;
+
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Semaphore;
+
+use crate::allocator::{Impl, Slice};
+use crate::parser::{NfsArgWrapper, NfsArguments, RpcHeader};
+use crate::rpc::{AuthFlavor, OpaqueAuth};
+use crate::spawner::TokioSpawner;
+use crate::task::global::vfs::{VfsCommand, VfsPool};
+use crate::task::{ProcReply, ProcResult};
+use crate::vfs::{
+    access, acl, commit, create, file, fs_info, fs_stat, get_attr, link, lookup, mk_dir, mk_node,
+    path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_attr, symlink,
+    write,
+};
+use crate::vfs::{Credentials, NfsRes, Vfs, WccData};
+
+pub const XID: u32 = 7;
+
+/// Error of the procedures dispatcher tests do not exercise.
+const NOT_USED: crate::vfs::Error = crate::vfs::Error::NotSupported;
+
+fn no_wcc() -> WccData {
+    WccData { before: None, after: None }
+}
+
+/// In-memory backend with a single regular file of `size` bytes.
+///
+/// Only the procedures exercised by the dispatcher tests are implemented, the others
+/// fail with [`NOT_USED`]. `K` only tells backends apart as types, for tests serving
+/// backends of different types side by side.
+pub struct MockVfs<K = ()> {
+    pub size: u64,
+    pub read_max: u32,
+    pub write_max: u32,
+    /// Number of bytes the last WRITE was asked to store.
+    pub last_write_size: Mutex<Option<u32>>,
+    /// Caller identity of the last WRITE.
+    pub last_write_cred: Mutex<Option<Credentials>>,
+    /// Owner and group the last SETATTR asked for.
+    pub last_set_owner: Mutex<Option<(Option<u32>, Option<u32>)>>,
+    /// If set, every READ consumes a permit first, stalling while none are available.
+    pub read_gate: Option<Arc<Semaphore>>,
+    /// If set, every WRITE consumes a permit after recording its size, stalling while
+    /// none are available and holding on to the written data.
+    pub write_gate: Option<Arc<Semaphore>>,
+    /// Number of entries READDIRPLUS lists in the root directory.
+    pub dir_entries: u64,
+    /// Number of CREATE calls executed.
+    pub create_calls: Mutex<u32>,
+    /// Link count GETATTR reports for the file.
+    pub nlink: u32,
+    kind: PhantomData<fn() -> K>,
+}
+
+impl MockVfs {
+    pub fn new(size: u64, read_max: u32, write_max: u32) -> Self {
+        Self {
+            size,
+            read_max,
+            write_max,
+            last_write_size: Mutex::new(None),
+            last_write_cred: Mutex::new(None),
+            last_set_owner: Mutex::new(None),
+            read_gate: None,
+            write_gate: None,
+            dir_entries: 0,
+            create_calls: Mutex::new(0),
+            nlink: 1,
+            kind: PhantomData,
+        }
+    }
+}
+
+impl<K> MockVfs<K> {
+    /// Turns the backend into one of another type `L`, with the same behavior.
+    pub fn of_kind<L>(self) -> MockVfs<L> {
+        MockVfs {
+            size: self.size,
+            read_max: self.read_max,
+            write_max: self.write_max,
+            last_write_size: self.last_write_size,
+            last_write_cred: self.last_write_cred,
+            last_set_owner: self.last_set_owner,
+            read_gate: self.read_gate,
+            write_gate: self.write_gate,
+            dir_entries: self.dir_entries,
+            create_calls: self.create_calls,
+            nlink: self.nlink,
+            kind: PhantomData,
+        }
+    }
+
+    /// Makes READ wait for a permit of `gate`, emulating a slow backend.
+    pub fn with_read_gate(mut self, gate: Arc<Semaphore>) -> Self {
+        self.read_gate = Some(gate);
+        self
+    }
+
+    /// Makes WRITE wait for a permit of `gate`, emulating a slow backend.
+    pub fn with_write_gate(mut self, gate: Arc<Semaphore>) -> Self {
+        self.write_gate = Some(gate);
+        self
+    }
+
+    /// Makes GETATTR report `nlink` links, zero emulating a backend bug.
+    pub fn with_nlink(mut self, nlink: u32) -> Self {
+        self.nlink = nlink;
+        self
+    }
+
+    /// Makes READDIRPLUS list a directory of `count` entries.
+    pub fn with_dir_entries(mut self, count: u64) -> Self {
+        self.dir_entries = count;
+        self
+    }
+}
+
+impl<K> fs_info::FsInfo for MockVfs<K> {
+    async fn fs_info(&self, _: fs_info::Args) -> Result<fs_info::Success, fs_info::Fail> {
+        Ok(fs_info::Success {
+            root_attr: None,
+            read_max: self.read_max,
+            read_pref: self.read_max,
+            read_mult: 1,
+            write_max: self.write_max,
+            write_pref: self.write_max,
+            write_mult: 1,
+            read_dir_pref: 4096,
+            max_file_size: u64::MAX,
+            time_delta: file::Time { seconds: 0, nanos: 1 },
+            properties: fs_info::Properties::from_wire(fs_info::Properties::ALL),
+        })
+    }
+}
+
+impl<K> read::Read<Slice> for MockVfs<K> {
+    async fn read(
+        &self,
+        _: &Credentials,
+        args: read::Args,
+        data: Slice,
+    ) -> Result<read::Success<Slice>, read::Fail> {
+        if let Some(gate) = &self.read_gate {
+            gate.acquire().await.unwrap().forget();
+        }
+        let remaining = self.size.saturating_sub(args.offset);
+        let count = remaining.min(u64::from(args.count)).min(data.len() as u64) as u32;
+        Ok(read::Success {
+            head: read::SuccessPartial {
+                file_attr: None,
+                count,
+                eof: args.offset + u64::from(count) >= self.size,
+            },
+            data,
+        })
+    }
+}
+
+impl<K> write::Write<Slice> for MockVfs<K> {
+    async fn write(
+        &self,
+        cred: &Credentials,
+        args: write::Args<Slice>,
+    ) -> Result<write::Success, write::Fail> {
+        *self.last_write_size.lock().unwrap() = Some(args.size);
+        *self.last_write_cred.lock().unwrap() = Some(cred.clone());
+        if let Some(gate) = &self.write_gate {
+            gate.acquire().await.unwrap().forget();
+        }
+        Ok(write::Success {
+            file_wcc: WccData { before: None, after: None },
+            count: args.size,
+            committed: args.stable,
+            verifier: write::Verifier([0; 8]),
+        })
+    }
+}
+
+impl<K> get_attr::GetAttr for MockVfs<K> {
+    async fn get_attr(&self, _: get_attr::Args) -> Result<get_attr::Success, get_attr::Fail> {
+        Ok(get_attr::Success {
+            object: file::Attr {
+                file_type: file::Type::Regular,
+                mode: 0o100_644,
+                nlink: self.nlink,
+                uid: 0,
+                gid: 0,
+                size: self.size,
+                used: self.size,
+                device: file::Device { major: 0, minor: 0 },
+                fs_id: 1,
+                file_id: 1,
+                atime: file::Time { seconds: 0, nanos: 0 },
+                mtime: file::Time { seconds: 0, nanos: 0 },
+                ctime: file::Time { seconds: 0, nanos: 0 },
+            },
+        })
+    }
+}
+
+impl<K> set_attr::SetAttr for MockVfs<K> {
+    async fn set_attr(
+        &self,
+        _: &Credentials,
+        args: set_attr::Args,
+    ) -> Result<set_attr::Success, set_attr::Fail> {
+        *self.last_set_owner.lock().unwrap() = Some((args.new_attr.uid, args.new_attr.gid));
+        Ok(set_attr::Success { wcc_data: no_wcc(), ignored: set_attr::Ignored::default() })
+    }
+}
+
+impl<K> lookup::Lookup for MockVfs<K> {
+    async fn lookup(&self, _: lookup::Args) -> Result<lookup::Success, lookup::Fail> {
+        Err(lookup::Fail { error: NOT_USED, dir_attr: None })
+    }
+}
+
+impl<K> access::Access for MockVfs<K> {
+    async fn access(
+        &self,
+        _: &Credentials,
+        _: access::Args,
+    ) -> Result<access::Success, access::Fail> {
+        Err(access::Fail { error: NOT_USED, object_attr: None })
+    }
+}
+
+impl<K> read_link::ReadLink for MockVfs<K> {
+    async fn read_link(&self, _: read_link::Args) -> Result<read_link::Success, read_link::Fail> {
+        Err(read_link::Fail { error: NOT_USED, symlink_attr: None })
+    }
+}
+
+impl<K> create::Create for MockVfs<K> {
+    async fn create(
+        &self,
+        _: &Credentials,
+        _: create::Args,
+    ) -> Result<create::Success, create::Fail> {
+        // The directory holds room for a single created file.
+        let mut calls = self.create_calls.lock().unwrap();
+        *calls += 1;
+        let wcc_data = WccData { before: None, after: None };
+        if *calls > 1 {
+            return Err(create::Fail { error: crate::vfs::Error::Exist, wcc_data });
+        }
+        Ok(create::Success {
+            file: Some(file::Handle([2, 0, 0, 0, 0, 0, 0, 0])),
+            attr: None,
+            wcc_data,
+        })
+    }
+}
+
+impl<K> mk_dir::MkDir for MockVfs<K> {
+    async fn mk_dir(
+        &self,
+        _: &Credentials,
+        _: mk_dir::Args,
+    ) -> Result<mk_dir::Success, mk_dir::Fail> {
+        Err(mk_dir::Fail { error: NOT_USED, dir_wcc: no_wcc() })
+    }
+}
+
+impl<K> symlink::Symlink for MockVfs<K> {
+    async fn symlink(
+        &self,
+        _: &Credentials,
+        _: symlink::Args,
+    ) -> Result<symlink::Success, symlink::Fail> {
+        Err(symlink::Fail { error: NOT_USED, dir_wcc: no_wcc() })
+    }
+}
+
+impl<K> mk_node::MkNode for MockVfs<K> {
+    async fn mk_node(
+        &self,
+        _: &Credentials,
+        _: mk_node::Args,
+    ) -> Result<mk_node::Success, mk_node::Fail> {
+        Err(mk_node::Fail { error: NOT_USED, dir_wcc: no_wcc() })
+    }
+}
+
+impl<K> remove::Remove for MockVfs<K> {
+    async fn remove(
+        &self,
+        _: &Credentials,
+        _: remove::Args,
+    ) -> Result<remove::Success, remove::Fail> {
+        // The single file lives in a directory nobody may modify.
+        Err(remove::Fail {
+            error: crate::vfs::Error::Access,
+            dir_wcc: WccData { before: None, after: None },
+        })
+    }
+}
+
+impl<K> rm_dir::RmDir for MockVfs<K> {
+    async fn rm_dir(
+        &self,
+        _: &Credentials,
+        _: rm_dir::Args,
+    ) -> Result<rm_dir::Success, rm_dir::Fail> {
+        Err(rm_dir::Fail { error: NOT_USED, dir_wcc: no_wcc() })
+    }
+}
+
+impl<K> rename::Rename for MockVfs<K> {
+    async fn rename(
+        &self,
+        _: &Credentials,
+        _: rename::Args,
+    ) -> Result<rename::Success, rename::Fail> {
+        Err(rename::Fail { error: NOT_USED, from_dir_wcc: no_wcc(), to_dir_wcc: no_wcc() })
+    }
+}
+
+impl<K> link::Link for MockVfs<K> {
+    async fn link(&self, _: &Credentials, _: link::Args) -> Result<link::Success, link::Fail> {
+        Err(link::Fail { error: NOT_USED, file_attr: None, dir_wcc: no_wcc() })
+    }
+}
+
+impl<K> read_dir::ReadDir for MockVfs<K> {
+    async fn read_dir(&self, _: read_dir::Args) -> Result<read_dir::Success, read_dir::Fail> {
+        Err(read_dir::Fail { error: NOT_USED, dir_attr: None })
+    }
+}
+
+impl<K> read_dir_plus::ReadDirPlus for MockVfs<K> {
+    /// Lists entries named `entryNNNNNNN` with attributes and handles, as many as fit
+    /// in `max_count` bytes.
+    async fn read_dir_plus(
+        &self,
+        args: read_dir_plus::Args,
+    ) -> Result<read_dir_plus::Success, read_dir_plus::Fail> {
+        // post_op_attr, cookieverf, list end and eof of a reply without directory attributes.
+        const HEAD: u64 = 4 + 8 + 4 + 4;
+        // value_follows, fileid, 12 byte name, cookie, post_op_attr and post_op_fh.
+        const ENTRY: u64 = 4 + 8 + 16 + 8 + 88 + 16;
+
+        let start = args.cookie.raw();
+        let fit = u64::from(args.max_count).saturating_sub(HEAD) / ENTRY;
+        let end = self.dir_entries.min(start.saturating_add(fit));
+        let entries = (start..end)
+            .map(|index| read_dir_plus::Entry {
+                file_id: index + 1,
+                file_name: file::Name::new(format!("entry{index:07}")).unwrap(),
+                cookie: read_dir::Cookie::new(index + 1),
+                file_attr: Some(file::Attr {
+                    file_type: file::Type::Regular,
+                    mode: 0o644,
+                    nlink: 1,
+                    uid: 0,
+                    gid: 0,
+                    size: self.size,
+                    used: self.size,
+                    device: file::Device { major: 0, minor: 0 },
+                    fs_id: 1,
+                    file_id: index + 1,
+                    atime: file::Time { seconds: 0, nanos: 0 },
+                    mtime: file::Time { seconds: 0, nanos: 0 },
+                    ctime: file::Time { seconds: 0, nanos: 0 },
+                }),
+                file_handle: Some(file::Handle((index + 1).to_be_bytes())),
+            })
+            .collect();
+        Ok(read_dir_plus::Success {
+            dir_attr: None,
+            cookie_verifier: read_dir::CookieVerifier::new([0; 8]),
+            entries,
+            eof: end == self.dir_entries,
+        })
+    }
+}
+
+impl<K> fs_stat::FsStat for MockVfs<K> {
+    async fn fs_stat(&self, _: fs_stat::Args) -> Result<fs_stat::Success, fs_stat::Fail> {
+        Err(fs_stat::Fail { error: NOT_USED, root_attr: None })
+    }
+}
+
+impl<K> path_conf::PathConf for MockVfs<K> {
+    async fn path_conf(&self, _: path_conf::Args) -> Result<path_conf::Success, path_conf::Fail> {
+        Err(path_conf::Fail { error: NOT_USED, file_attr: None })
+    }
+}
+
+impl<K> commit::Commit for MockVfs<K> {
+    async fn commit(&self, _: commit::Args) -> Result<commit::Success, commit::Fail> {
+        Err(commit::Fail { error: NOT_USED, file_wcc: no_wcc() })
+    }
+}
+
+/// ACLs are left unsupported, as by any backend relying on the default methods.
+impl<K> acl::Acl for MockVfs<K> {}
+
+pub fn file_handle() -> file::Handle {
+    file::Handle([1, 0, 0, 0, 0, 0, 0, 0])
+}
+
+/// Spawns a single worker pool over `backend` with an allocator of `buffer_count` buffers
+/// of `buffer_size` bytes.
+pub fn pool<V>(backend: Arc<V>, buffer_size: usize, buffer_count: usize) -> VfsPool<Slice>
+where
+    V: Vfs<Slice> + Send + Sync + 'static,
+{
+    let allocator = Arc::new(Impl::new(
+        NonZeroUsize::new(buffer_size).unwrap(),
+        NonZeroUsize::new(buffer_count).unwrap(),
+    ));
+    VfsPool::new(NonZeroUsize::MIN, NonZeroUsize::MIN, backend, allocator, &TokioSpawner)
+}
+
+/// Address of the client every dispatched call comes from.
+pub fn client_addr() -> SocketAddr {
+    SocketAddr::from(([192, 0, 2, 1], 700))
+}
+
+/// Dispatches `proc` through `pool` and returns the NFS result.
+pub async fn dispatch(pool: &VfsPool<Slice>, proc: NfsArguments<Slice>) -> NfsRes<Slice> {
+    dispatch_as(pool, OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap(), proc).await
+}
+
+/// Dispatches `proc` with credential `cred` through `pool` and returns the NFS result.
+pub async fn dispatch_as(
+    pool: &VfsPool<Slice>,
+    cred: OpaqueAuth,
+    proc: NfsArguments<Slice>,
+) -> NfsRes<Slice> {
+    match send_as(pool, cred, proc).await.proc_result {
+        Ok(ProcResult::Nfs3(res)) => *res,
+        _ => panic!("expected NFSv3 result"),
+    }
+}
+
+/// Dispatches `proc` with credential `cred` through `pool` and returns the reply.
+pub async fn send_as(
+    pool: &VfsPool<Slice>,
+    cred: OpaqueAuth,
+    proc: NfsArguments<Slice>,
+) -> ProcReply<Slice> {
+    let verf = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let header = RpcHeader { xid: XID, cred, verf };
+    let (tx, rx) = async_channel::bounded::<ProcReply<Slice>>(1);
+    let command = VfsCommand {
+        result_tx: tx,
+        client_addr: client_addr(),
+        args: NfsArgWrapper { header, proc: Box::new(proc) },
+        call: None,
+    };
+    pool.sender().send(command).await.unwrap();
+
+    let reply = rx.recv().await.unwrap();
+    assert_eq!(reply.xid, XID);
+    reply
+}
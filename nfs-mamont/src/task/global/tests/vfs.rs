@@ -0,0 +1,375 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+
+use crate::allocator::{Allocator, Impl, Slice};
+use crate::audit::{AuditEvent, AuditSink};
+use crate::context::AnonymousAccess;
+use crate::parser::reply::{header, nfsv3, record_mark, ReplyStatus};
+use crate::parser::NfsArguments;
+use crate::rpc::{AcceptStat, AuthFlavor, AuthStat, Error, OpaqueAuth};
+use crate::serializer::server::serialize_struct::{
+    Serializer, DEFAULT_MAX_REPLY_BYTES, MAX_REPLY_OVERHEAD,
+};
+use crate::spawner::TokioSpawner;
+use crate::task::global::vfs::VfsPool;
+use crate::task::{ProcReply, ProcResult};
+use crate::vfs::read_dir::{Cookie, CookieVerifier};
+use crate::vfs::{
+    self, acl, file, get_acl, get_attr, read, read_dir_plus, remove, set_attr, write, NfsRes,
+};
+
+use super::{client_addr, dispatch, dispatch_as, file_handle, pool, send_as, MockVfs, XID};
+
+const MIB: u32 = 1024 * 1024;
+
+#[tokio::test]
+async fn read_count_is_clamped_to_read_max() {
+    let backend = Arc::new(MockVfs::new(3 * u64::from(MIB), MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64 * 1024, 32);
+
+    let args = read::Args { file: file_handle(), offset: 0, count: u32::MAX };
+    let NfsRes::Read(Ok(success)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ success");
+    };
+    assert_eq!(success.head.count, MIB);
+    assert_eq!(success.data.len(), MIB as usize);
+    assert!(!success.head.eof);
+
+    let args = read::Args { file: file_handle(), offset: 2 * u64::from(MIB), count: u32::MAX };
+    let NfsRes::Read(Ok(success)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ success");
+    };
+    assert_eq!(success.head.count, MIB);
+    assert!(success.head.eof);
+}
+
+#[tokio::test]
+async fn read_count_is_clamped_to_fit_max_reply_bytes() {
+    let backend = Arc::new(MockVfs::new(3 * u64::from(MIB), MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64 * 1024, 32);
+    pool.set_max_reply_bytes(MAX_REPLY_OVERHEAD + 4096);
+
+    let args = read::Args { file: file_handle(), offset: 0, count: u32::MAX };
+    let NfsRes::Read(Ok(success)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ success");
+    };
+    assert_eq!(success.head.count, 4096);
+}
+
+#[tokio::test]
+async fn write_size_is_clamped_to_write_max() {
+    let backend = Arc::new(MockVfs::new(0, MIB, 4));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    let data = Impl::new(NonZeroUsize::new(8).unwrap(), NonZeroUsize::MIN)
+        .allocate(NonZeroUsize::new(8).unwrap())
+        .await
+        .unwrap();
+
+    let args = write::Args {
+        file: file_handle(),
+        offset: 0,
+        size: 8,
+        stable: write::StableHow::Unstable,
+        data,
+    };
+    let NfsRes::Write(Ok(success)) = dispatch(&pool, NfsArguments::Write(args)).await else {
+        panic!("expected WRITE success");
+    };
+    assert_eq!(success.count, 4);
+    assert_eq!(*backend.last_write_size.lock().unwrap(), Some(4));
+}
+
+#[tokio::test]
+async fn acl_procedures_default_to_not_supported() {
+    let pool = pool(Arc::new(MockVfs::new(0, MIB, MIB)), 64, 1);
+
+    let args = get_acl::Args { file: file_handle(), mask: acl::Mask::from_wire(acl::Mask::ALL) };
+    let NfsRes::GetAcl(Err(fail)) = dispatch(&pool, NfsArguments::GetAcl(args)).await else {
+        panic!("expected GETACL failure");
+    };
+    assert_eq!(fail.error, vfs::Error::NotSupported);
+}
+
+/// A client asking for `u32::MAX` bytes of a huge directory gets as many entries as fit
+/// in [`DEFAULT_MAX_REPLY_BYTES`], not a reply sized by its own request.
+#[tokio::test]
+async fn read_dir_plus_reply_stays_under_max_reply_bytes() {
+    let pool = pool(Arc::new(MockVfs::new(0, MIB, MIB).with_dir_entries(1_000_000)), 64, 1);
+
+    let args = read_dir_plus::Args {
+        dir: file_handle(),
+        cookie: Cookie::new(0),
+        cookie_verifier: CookieVerifier::new([0; 8]),
+        dir_count: u32::MAX,
+        max_count: u32::MAX,
+    };
+    let res = dispatch(&pool, NfsArguments::ReadDirPlus(args)).await;
+    let mut serializer = Serializer::<Slice, _>::new(Vec::new());
+    let reply =
+        ProcReply { xid: XID, proc_result: Ok(ProcResult::Nfs3(Box::new(res))), call: None };
+    let verifier = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    serializer.form_reply(reply, verifier).await.unwrap();
+    let bytes = serializer.into_inner();
+
+    assert!(bytes.len() - 4 <= DEFAULT_MAX_REPLY_BYTES, "reply of {} bytes", bytes.len());
+    let mut src = std::io::Cursor::new(bytes.as_slice());
+    record_mark(&mut src).unwrap();
+    assert!(matches!(
+        header(&mut src).unwrap().status,
+        ReplyStatus::Accepted { stat: AcceptStat::Success, .. }
+    ));
+    let Ok(success) = nfsv3::read_dir_plus(&mut src).unwrap() else {
+        panic!("expected READDIRPLUS success");
+    };
+    assert!(!success.eof);
+    assert!(success.entries.len() > 10_000);
+}
+
+/// Audit sink remembering every event.
+#[derive(Default)]
+struct SpySink {
+    events: Mutex<Vec<AuditEvent>>,
+}
+
+impl AuditSink for SpySink {
+    fn record(&self, event: &AuditEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn denied_remove_is_reported_to_audit_sink_once() {
+    let pool = pool(Arc::new(MockVfs::new(16, MIB, MIB)), 64, 1);
+    let sink = Arc::new(SpySink::default());
+    pool.set_audit_sink(Arc::clone(&sink) as Arc<dyn AuditSink>);
+
+    // AUTH_SYS: stamp, machine name "client", uid 1000, gid 100, no groups.
+    let body =
+        [0x5eed, 6, u32::from_be_bytes(*b"clie"), u32::from_be_bytes(*b"nt\0\0"), 1000, 100, 0]
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect();
+    let cred = OpaqueAuth::new(AuthFlavor::Sys, body).unwrap();
+    let name = file::Name::new("locked.txt".to_owned()).unwrap();
+    let args = remove::Args { object: vfs::DirOpArgs { dir: file_handle(), name } };
+    let NfsRes::Remove(Err(fail)) = dispatch_as(&pool, cred, NfsArguments::Remove(args)).await
+    else {
+        panic!("expected REMOVE failure");
+    };
+    assert_eq!(fail.error, vfs::Error::Access);
+
+    // Successful calls are not audited.
+    let args = read::Args { file: file_handle(), offset: 0, count: 8 };
+    let NfsRes::Read(Ok(_)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ success");
+    };
+
+    let events = sink.events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.client_addr, client_addr());
+    assert_eq!(event.uid, Some(1000));
+    assert_eq!(event.procedure, "REMOVE");
+    assert_eq!(event.handle, file_handle());
+    assert_eq!(event.name.as_deref(), Some("locked.txt"));
+    assert_eq!(event.error, vfs::Error::Access);
+}
+
+/// Returns WRITE arguments storing one byte.
+async fn one_byte_write() -> NfsArguments<Slice> {
+    let data =
+        Impl::new(NonZeroUsize::MIN, NonZeroUsize::MIN).allocate(NonZeroUsize::MIN).await.unwrap();
+    NfsArguments::Write(write::Args {
+        file: file_handle(),
+        offset: 0,
+        size: 1,
+        stable: write::StableHow::Unstable,
+        data,
+    })
+}
+
+#[tokio::test]
+async fn auth_none_call_runs_as_configured_anonymous_identity() {
+    let backend = Arc::new(MockVfs::new(0, MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    pool.set_anonymous_access(AnonymousAccess::Allow { uid: 1234, gid: 5678 });
+
+    let NfsRes::Write(Ok(_)) = dispatch(&pool, one_byte_write().await).await else {
+        panic!("expected WRITE success");
+    };
+    let cred = backend.last_write_cred.lock().unwrap().clone().unwrap();
+    assert_eq!((cred.uid, cred.gid), (1234, 5678));
+    assert!(cred.gids.is_empty());
+}
+
+#[tokio::test]
+async fn auth_none_call_is_rejected_when_anonymous_access_is_denied() {
+    let backend = Arc::new(MockVfs::new(0, MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    pool.set_anonymous_access(AnonymousAccess::Deny);
+
+    let none = OpaqueAuth::new(AuthFlavor::None, vec![]).unwrap();
+    let reply = send_as(&pool, none, one_byte_write().await).await;
+    assert!(matches!(reply.proc_result, Err(Error::Auth(AuthStat::TooWeak))));
+    assert!(backend.last_write_cred.lock().unwrap().is_none());
+
+    // AUTH_SYS callers are still served under their own identity.
+    let body = [0, 0, 1000, 100, 0].iter().flat_map(|word: &u32| word.to_be_bytes()).collect();
+    let sys = OpaqueAuth::new(AuthFlavor::Sys, body).unwrap();
+    let NfsRes::Write(Ok(_)) = dispatch_as(&pool, sys, one_byte_write().await).await else {
+        panic!("expected WRITE success");
+    };
+    assert_eq!(backend.last_write_cred.lock().unwrap().as_ref().unwrap().uid, 1000);
+}
+
+#[tokio::test]
+async fn strict_attrs_turn_attributes_without_links_into_server_fault() {
+    let backend = Arc::new(MockVfs::new(0, MIB, MIB).with_nlink(0));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    let get_attr = || NfsArguments::GetAttr(get_attr::Args { file: file_handle() });
+
+    let NfsRes::GetAttr(Ok(success)) = dispatch(&pool, get_attr()).await else {
+        panic!("expected GETATTR success without strict attributes");
+    };
+    assert_eq!(success.object.nlink, 0);
+
+    pool.set_strict_attrs(true);
+    let NfsRes::GetAttr(Err(fail)) = dispatch(&pool, get_attr()).await else {
+        panic!("expected GETATTR to fail with strict attributes");
+    };
+    assert!(matches!(fail.error, vfs::Error::ServerFault));
+}
+
+#[tokio::test]
+async fn timed_out_calls_get_jukebox_and_release_their_buffers() {
+    let gate = Arc::new(Semaphore::new(0));
+    let backend = Arc::new(
+        MockVfs::new(64, MIB, MIB)
+            .with_read_gate(Arc::clone(&gate))
+            .with_write_gate(Arc::clone(&gate)),
+    );
+    let read_allocator = Arc::new(Impl::new(NonZeroUsize::new(64).unwrap(), NonZeroUsize::MIN));
+    let pool = VfsPool::new(
+        NonZeroUsize::MIN,
+        NonZeroUsize::MIN,
+        Arc::clone(&backend),
+        Arc::clone(&read_allocator),
+        &TokioSpawner,
+    );
+    pool.set_proc_timeout(Duration::from_millis(50));
+
+    let args = read::Args { file: file_handle(), offset: 0, count: 64 };
+    let NfsRes::Read(Err(fail)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ to time out");
+    };
+    assert!(matches!(fail.error, vfs::Error::Jukebox));
+    let everything = NonZeroUsize::new(64).unwrap();
+    tokio::time::timeout(Duration::from_secs(1), read_allocator.allocate(everything))
+        .await
+        .expect("READ buffer was not returned to the allocator")
+        .unwrap();
+
+    let write_allocator = Impl::new(NonZeroUsize::new(8).unwrap(), NonZeroUsize::MIN);
+    let data = write_allocator.allocate(NonZeroUsize::new(8).unwrap()).await.unwrap();
+    let args = write::Args {
+        file: file_handle(),
+        offset: 0,
+        size: 8,
+        stable: write::StableHow::Unstable,
+        data,
+    };
+    let NfsRes::Write(Err(fail)) = dispatch(&pool, NfsArguments::Write(args)).await else {
+        panic!("expected WRITE to time out");
+    };
+    assert!(matches!(fail.error, vfs::Error::Jukebox));
+    tokio::time::timeout(
+        Duration::from_secs(1),
+        write_allocator.allocate(NonZeroUsize::new(8).unwrap()),
+    )
+    .await
+    .expect("WRITE data was not returned to the allocator")
+    .unwrap();
+
+    // Calls completing in time are unaffected.
+    gate.add_permits(1);
+    let args = read::Args { file: file_handle(), offset: 0, count: 64 };
+    let NfsRes::Read(Ok(success)) = dispatch(&pool, NfsArguments::Read(args)).await else {
+        panic!("expected READ success");
+    };
+    assert_eq!(success.head.count, 64);
+}
+
+/// Returns an AUTH_SYS credential of user `uid` in group `gid`.
+fn sys_cred(uid: u32, gid: u32) -> OpaqueAuth {
+    let body = [0, 0, uid, gid, 0].iter().flat_map(|word| word.to_be_bytes()).collect();
+    OpaqueAuth::new(AuthFlavor::Sys, body).unwrap()
+}
+
+const ANON: u32 = 4242;
+
+#[tokio::test]
+async fn root_squash_hands_the_backend_anonymous_root_only() {
+    let backend = Arc::new(MockVfs::new(0, MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    pool.set_id_map(vfs::IdMapPolicy::RootSquash { anon_uid: ANON, anon_gid: ANON });
+
+    let NfsRes::Write(Ok(_)) = dispatch_as(&pool, sys_cred(0, 0), one_byte_write().await).await
+    else {
+        panic!("expected WRITE success");
+    };
+    let cred = backend.last_write_cred.lock().unwrap().clone().unwrap();
+    assert_eq!((cred.uid, cred.gid), (ANON, ANON));
+
+    let NfsRes::Write(Ok(_)) =
+        dispatch_as(&pool, sys_cred(1000, 100), one_byte_write().await).await
+    else {
+        panic!("expected WRITE success");
+    };
+    let cred = backend.last_write_cred.lock().unwrap().clone().unwrap();
+    assert_eq!((cred.uid, cred.gid), (1000, 100));
+}
+
+#[tokio::test]
+async fn all_squash_hands_the_backend_anonymous_for_every_caller() {
+    let backend = Arc::new(MockVfs::new(0, MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    pool.set_id_map(vfs::IdMapPolicy::AllSquash { anon_uid: ANON, anon_gid: ANON });
+
+    let NfsRes::Write(Ok(_)) =
+        dispatch_as(&pool, sys_cred(1000, 100), one_byte_write().await).await
+    else {
+        panic!("expected WRITE success");
+    };
+    let cred = backend.last_write_cred.lock().unwrap().clone().unwrap();
+    assert_eq!((cred.uid, cred.gid), (ANON, ANON));
+}
+
+#[tokio::test]
+async fn root_squashed_chown_to_root_is_squashed_and_reported_ignored() {
+    let backend = Arc::new(MockVfs::new(0, MIB, MIB));
+    let pool = pool(Arc::clone(&backend), 64, 1);
+    pool.set_id_map(vfs::IdMapPolicy::RootSquash { anon_uid: ANON, anon_gid: ANON });
+
+    let args = set_attr::Args {
+        file: file_handle(),
+        new_attr: set_attr::NewAttr {
+            mode: None,
+            uid: Some(0),
+            gid: Some(0),
+            size: None,
+            atime: set_attr::SetTime::DontChange,
+            mtime: set_attr::SetTime::DontChange,
+        },
+        guard: None,
+    };
+    let NfsRes::SetAttr(Ok(success)) =
+        dispatch_as(&pool, sys_cred(0, 0), NfsArguments::SetAttr(args)).await
+    else {
+        panic!("expected SETATTR success");
+    };
+    assert_eq!(*backend.last_set_owner.lock().unwrap(), Some((Some(ANON), Some(ANON))));
+    assert_eq!(success.ignored, set_attr::Ignored { mode: false, uid: true, gid: true });
+}
@@ -1,25 +1,94 @@
 use async_channel::{Receiver, Sender};
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::num::NonZeroUsize;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use tracing::{error, warn};
 
 use crate::allocator::{Allocator, Buffer};
+use crate::audit::{AuditEvent, AuditSink};
+use crate::context::AnonymousAccess;
+use crate::parser::rpc::auth_sys;
 use crate::parser::{NfsArgWrapper, NfsArguments};
-use crate::task::{ProcReply, ProcResult};
-use crate::vfs::{self, NfsRes, Vfs};
+use crate::rpc::{AuthFlavor, AuthStat, Error, OpaqueAuth};
+use crate::serializer::server::serialize_struct::{DEFAULT_MAX_REPLY_BYTES, MAX_REPLY_OVERHEAD};
+use crate::spawner::Spawner;
+use crate::task::{CallKey, ProcReply, ProcResult};
+use crate::vfs::{
+    self, access, commit, create, file, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir,
+    mk_node, path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl,
+    set_attr, symlink, write, NfsRes, Vfs,
+};
+
+use super::strict;
 
 /// One queued NFS procedure: parsed arguments and a channel to send the result.
-pub type VfsCommand<B> = (NfsArgWrapper<B>, Sender<ProcReply<B>>);
+pub struct VfsCommand<B: Buffer> {
+    /// Channel used to pass the result to write task.
+    pub result_tx: Sender<ProcReply<B>>,
+    /// Client socket address from connection task.
+    pub client_addr: SocketAddr,
+    /// Parsed call.
+    pub args: NfsArgWrapper<B>,
+    /// Key of the call in the connection's duplicate cache, if it was registered there.
+    pub call: Option<CallKey>,
+}
 /// Sender to enqueue work in the pool.
 pub type VfsCommandSender<B> = Sender<VfsCommand<B>>;
 /// Receiver from the pool, each worker competes for the same command stream.
 type VfsCommandReceiver<B> = Receiver<VfsCommand<B>>;
+/// Settings shared by the workers of a pool, which may change while they run.
+type SharedSettings = Arc<RwLock<Settings>>;
+
+/// Per-call policies of the workers.
+#[derive(Clone)]
+struct Settings {
+    /// Sink of refused calls, if one is set.
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    /// Treatment of AUTH_NONE calls.
+    anonymous_access: AnonymousAccess,
+    /// Mapping of caller identities onto the ones the backend sees.
+    id_map: vfs::IdMapPolicy,
+    /// Replaces replies carrying malformed attributes with `NFS3ERR_SERVERFAULT`.
+    strict_attrs: bool,
+    /// Time a procedure may take before it is cancelled, if limited.
+    proc_timeout: Option<Duration>,
+    /// Bytes of READ data or directory entries that fit in a reply; client-requested
+    /// counts are clamped to it.
+    reply_budget: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            audit_sink: None,
+            anonymous_access: AnonymousAccess::default(),
+            id_map: vfs::IdMapPolicy::default(),
+            strict_attrs: false,
+            proc_timeout: None,
+            reply_budget: reply_budget(DEFAULT_MAX_REPLY_BYTES),
+        }
+    }
+}
+
+/// Returns the bytes of READ data or directory entries that fit in a reply of
+/// `max_reply_bytes`.
+fn reply_budget(max_reply_bytes: usize) -> u32 {
+    let budget = max_reply_bytes.saturating_sub(MAX_REPLY_OVERHEAD);
+    u32::try_from(budget).unwrap_or(u32::MAX)
+}
 
-/// Fixed-size pool of [`VfsTask`] workers fed from a single unbounded command channel.
+/// Fixed-size pool of [`VfsTask`] workers fed from a single bounded command channel.
+///
+/// Senders wait while the channel is full, so callers are slowed down to the pace
+/// of the workers instead of queueing without limit.
 pub struct VfsPool<B: Buffer> {
     /// Sender to enqueue work in the pool for execution.
     sender: VfsCommandSender<B>,
+    /// Policies consulted by every worker.
+    settings: SharedSettings,
 }
 
 impl<B: Buffer + 'static> VfsPool<B> {
@@ -28,25 +97,75 @@ impl<B: Buffer + 'static> VfsPool<B> {
     /// # Parameters
     ///
     /// - `num` --- number of workers to create
+    /// - `capacity` --- number of commands queued before senders wait
     /// - `backend` --- shared filesystem implementation
     /// - `allocator` --- allocator used for read buffers
+    /// - `spawner` --- strategy used to launch the workers
     ///
     /// # Returns
     ///
     /// A new [`VfsPool`] with the given number of workers.
-    pub fn new<A, V>(num: NonZeroUsize, backend: Arc<V>, allocator: Arc<A>) -> Self
+    pub fn new<A, V>(
+        num: NonZeroUsize,
+        capacity: NonZeroUsize,
+        backend: Arc<V>,
+        allocator: Arc<A>,
+        spawner: &dyn Spawner,
+    ) -> Self
     where
         A: Allocator<Buffer = B> + Send + Sync + 'static,
         V: Vfs<B> + Send + Sync + 'static,
     {
-        let (tx, rx) = async_channel::unbounded::<VfsCommand<B>>();
+        let (tx, rx) = async_channel::bounded::<VfsCommand<B>>(capacity.get());
+        let settings = SharedSettings::default();
 
         (0..num.get()).for_each(|_| {
             let rx_clone = rx.clone();
-            VfsTask::new(Arc::clone(&backend), Arc::clone(&allocator), rx_clone).spawn();
+            VfsTask::new(Arc::clone(&backend), Arc::clone(&allocator), rx_clone)
+                .with_settings(Arc::clone(&settings))
+                .spawn(spawner);
         });
 
-        Self { sender: tx }
+        Self { sender: tx, settings }
+    }
+
+    /// Reports refused calls handled by the workers from now on to `sink`.
+    pub fn set_audit_sink(&self, sink: Arc<dyn AuditSink>) {
+        self.settings.write().unwrap().audit_sink = Some(sink);
+    }
+
+    /// Treats AUTH_NONE calls handled by the workers from now on according to `access`.
+    pub fn set_anonymous_access(&self, access: AnonymousAccess) {
+        self.settings.write().unwrap().anonymous_access = access;
+    }
+
+    /// Maps the callers of calls handled by the workers from now on through `id_map`
+    /// before the backend sees them.
+    pub fn set_id_map(&self, id_map: vfs::IdMapPolicy) {
+        self.settings.write().unwrap().id_map = id_map;
+    }
+
+    /// Checks the attributes in replies of calls handled by the workers from now on,
+    /// failing those carrying malformed ones with [`vfs::Error::ServerFault`].
+    ///
+    /// Meant to catch backend bugs; see [`super::strict`] for the checks.
+    pub fn set_strict_attrs(&self, strict: bool) {
+        self.settings.write().unwrap().strict_attrs = strict;
+    }
+
+    /// Cancels procedures handled by the workers from now on that take longer than
+    /// `timeout`, answering them with [`vfs::Error::Jukebox`] so the client retries.
+    ///
+    /// The cancelled backend call is dropped at its next suspension point, together with
+    /// the buffers it holds.
+    pub fn set_proc_timeout(&self, timeout: Duration) {
+        self.settings.write().unwrap().proc_timeout = Some(timeout);
+    }
+
+    /// Clamps the READ counts and directory budgets of calls handled by the workers from
+    /// now on, so their replies fit in `max_reply_bytes`.
+    pub fn set_max_reply_bytes(&self, max_reply_bytes: usize) {
+        self.settings.write().unwrap().reply_budget = reply_budget(max_reply_bytes);
     }
 
     /// Returns a clone of the command sender for enqueueing work in the pool.
@@ -75,6 +194,18 @@ where
     allocator: Arc<A>,
     /// Shared receiver from the pool, each worker competes for the same command stream.
     command_receiver: VfsCommandReceiver<B>,
+    /// Transfer limits advertised by the backend per [`fs_info::FsInfo::transfer_domain`],
+    /// fetched on the first READ or WRITE in each domain.
+    transfer_limits: Mutex<HashMap<u64, TransferLimits>>,
+    /// Policies shared with the pool.
+    settings: SharedSettings,
+}
+
+/// Maximum READ and WRITE sizes advertised by [`fs_info::FsInfo::fs_info`].
+#[derive(Clone, Copy)]
+struct TransferLimits {
+    read_max: u32,
+    write_max: u32,
 }
 
 impl<A, V, B> VfsTask<A, V, B>
@@ -99,80 +230,112 @@ where
         allocator: Arc<A>,
         command_receiver: VfsCommandReceiver<B>,
     ) -> Self {
-        Self { backend, allocator, command_receiver }
+        Self {
+            backend,
+            allocator,
+            command_receiver,
+            transfer_limits: Mutex::default(),
+            settings: SharedSettings::default(),
+        }
+    }
+
+    /// Makes the worker follow the policies shared through `settings`.
+    fn with_settings(mut self, settings: SharedSettings) -> Self {
+        self.settings = settings;
+        self
     }
 
     /// Spawns a [`VfsTask`].
     ///
     /// # Panics
     ///
-    /// If called outside of tokio runtime context.
-    pub fn spawn(self) {
-        tokio::spawn(async move { self.run().await });
+    /// If `spawner` cannot launch tasks in the current context.
+    pub fn spawn(self, spawner: &dyn Spawner) {
+        spawner.spawn(Box::pin(self.run()));
     }
 
     /// Consumes commands until the channel closes, dispatching each NFS op and sending replies.
     async fn run(self) {
-        let command_receiver = self.command_receiver;
-
-        while let Ok((command, tx)) = command_receiver.recv().await {
-            let NfsArgWrapper { header, proc } = command;
+        while let Ok(command) = self.command_receiver.recv().await {
+            let VfsCommand {
+                result_tx: tx,
+                client_addr,
+                args: NfsArgWrapper { header, proc },
+                call: call_key,
+            } = command;
             let proc_name = Self::proc_name(&proc);
-
-            let response = match *proc {
-                NfsArguments::Null => NfsRes::Null,
-                NfsArguments::GetAttr(args) => NfsRes::GetAttr(self.backend.get_attr(args).await),
-                NfsArguments::SetAttr(args) => NfsRes::SetAttr(self.backend.set_attr(args).await),
-                NfsArguments::LookUp(args) => NfsRes::LookUp(self.backend.lookup(args).await),
-                NfsArguments::Access(args) => NfsRes::Access(self.backend.access(args).await),
-                NfsArguments::ReadLink(args) => {
-                    NfsRes::ReadLink(self.backend.read_link(args).await)
-                }
-                NfsArguments::Read(args) => {
-                    let data_result = if args.count == 0 {
-                        Ok(B::empty())
-                    } else {
-                        let requested_size = NonZeroUsize::new(args.count as usize).unwrap();
-
-                        self.allocator
-                            .allocate(requested_size)
-                            .await
-                            .ok_or(vfs::read::Fail { error: vfs::Error::TooSmall, file_attr: None })
-                    };
-
-                    match data_result {
-                        Ok(data) => NfsRes::Read(self.backend.read(args, data).await),
-                        Err(err) => NfsRes::Read(Err(err)),
-                    }
+            let Settings {
+                audit_sink,
+                anonymous_access,
+                id_map,
+                strict_attrs,
+                proc_timeout,
+                reply_budget,
+            } = self.settings.read().unwrap().clone();
+            let Some(cred) = Self::credentials(&header.cred, anonymous_access) else {
+                warn!(client=%client_addr, xid=header.xid, proc=%proc_name, "AUTH_NONE call rejected");
+                let reply = ProcReply {
+                    xid: header.xid,
+                    proc_result: Err(Error::Auth(AuthStat::TooWeak)),
+                    call: call_key,
+                };
+                if tx.send(reply).await.is_err() {
+                    warn!("writer task closed, connection pipeline is done");
                 }
-                NfsArguments::Write(args) => NfsRes::Write(self.backend.write(args).await),
-                NfsArguments::Create(args) => NfsRes::Create(self.backend.create(args).await),
-                NfsArguments::MkDir(args) => NfsRes::MkDir(self.backend.mk_dir(args).await),
-                NfsArguments::SymLink(args) => NfsRes::SymLink(self.backend.symlink(args).await),
-                NfsArguments::MkNod(args) => NfsRes::MkNod(self.backend.mk_node(args).await),
-                NfsArguments::Remove(args) => NfsRes::Remove(self.backend.remove(args).await),
-                NfsArguments::RmDir(args) => NfsRes::RmDir(self.backend.rm_dir(args).await),
-                NfsArguments::Rename(args) => NfsRes::Rename(self.backend.rename(args).await),
-                NfsArguments::Link(args) => NfsRes::Link(self.backend.link(args).await),
-                NfsArguments::ReadDir(args) => NfsRes::ReadDir(self.backend.read_dir(args).await),
-                NfsArguments::ReadDirPlus(args) => {
-                    NfsRes::ReadDirPlus(self.backend.read_dir_plus(args).await)
-                }
-                NfsArguments::FsStat(args) => NfsRes::FsStat(self.backend.fs_stat(args).await),
-                NfsArguments::FsInfo(args) => NfsRes::FsInfo(self.backend.fs_info(args).await),
-                NfsArguments::PathConf(args) => {
-                    NfsRes::PathConf(self.backend.path_conf(args).await)
+                continue;
+            };
+            // The target is only kept around when somebody listens.
+            let audit_target = audit_sink.as_ref().and_then(|_| Self::audit_target(&proc));
+
+            // Built up front, as the arguments move into the call that may time out.
+            let deadline =
+                proc_timeout.map(|limit| (limit, Self::failure(&proc, vfs::Error::Jukebox)));
+            // The backend only ever sees identities mapped by the export's policy.
+            let mut proc = *proc;
+            let squashed_owner = Self::squash_owner(id_map, &cred, &mut proc);
+            let effective = id_map.apply(cred.clone());
+            let call = self.execute(&effective, proc, reply_budget);
+            let mut response = match deadline {
+                Some((limit, timed_out)) => {
+                    tokio::time::timeout(limit, call).await.unwrap_or_else(|_| {
+                        warn!(client=%client_addr, xid=header.xid, proc=%proc_name, "nfs op timed out");
+                        timed_out
+                    })
                 }
-                NfsArguments::Commit(args) => NfsRes::Commit(self.backend.commit(args).await),
+                None => call.await,
             };
+            if let NfsRes::SetAttr(Ok(success)) = &mut response {
+                success.ignored.uid |= squashed_owner.uid;
+                success.ignored.gid |= squashed_owner.gid;
+            }
+            if strict_attrs {
+                if let Some(violation) = strict::violation(&response) {
+                    error!(xid=header.xid, proc=%proc_name, violation, "backend reported malformed attributes");
+                    response = strict::server_fault(&response);
+                }
+            }
 
             if let Some(error) = Self::error_from_response(&response) {
                 error!(xid=header.xid, proc=%proc_name, error=?error, "nfs op failed");
+                if let (Some(sink), Some((handle, name))) = (&audit_sink, audit_target) {
+                    if AuditEvent::is_audited(error) {
+                        sink.record(&AuditEvent {
+                            client_addr,
+                            uid: matches!(header.cred.flavor(), AuthFlavor::Sys)
+                                .then_some(cred.uid),
+                            procedure: proc_name,
+                            handle,
+                            name,
+                            error,
+                        });
+                    }
+                }
             }
 
             let reply = ProcReply {
                 xid: header.xid,
                 proc_result: Ok(ProcResult::Nfs3(Box::new(response))),
+                call: call_key,
             };
 
             // Write task may already be closed; then this connection pipeline is done.
@@ -182,31 +345,254 @@ where
         }
     }
 
+    /// Executes `proc` against the backend on behalf of `cred`, clamping the READ count
+    /// and directory budgets to `reply_budget`.
+    async fn execute(
+        &self,
+        cred: &vfs::Credentials,
+        proc: NfsArguments<B>,
+        reply_budget: u32,
+    ) -> NfsRes<B> {
+        match proc {
+            NfsArguments::Null => NfsRes::Null,
+            NfsArguments::GetAttr(args) => NfsRes::GetAttr(self.backend.get_attr(args).await),
+            NfsArguments::SetAttr(args) => NfsRes::SetAttr(self.backend.set_attr(cred, args).await),
+            NfsArguments::LookUp(args) => NfsRes::LookUp(self.backend.lookup(args).await),
+            NfsArguments::Access(args) => NfsRes::Access(self.backend.access(cred, args).await),
+            NfsArguments::ReadLink(args) => NfsRes::ReadLink(self.backend.read_link(args).await),
+            NfsArguments::Read(mut args) => {
+                args.count = args.count.min(reply_budget);
+                if let Some(limits) = self.transfer_limits(&args.file).await {
+                    args.count = args.count.min(limits.read_max);
+                }
+                let data_result = if args.count == 0 {
+                    Ok(B::empty())
+                } else {
+                    let requested_size = NonZeroUsize::new(args.count as usize).unwrap();
+
+                    self.allocator
+                        .allocate(requested_size)
+                        .await
+                        .ok_or(vfs::read::Fail { error: vfs::Error::TooSmall, file_attr: None })
+                };
+
+                match data_result {
+                    Ok(data) => NfsRes::Read(self.backend.read(cred, args, data).await),
+                    Err(err) => NfsRes::Read(Err(err)),
+                }
+            }
+            NfsArguments::Write(args) if args.data.len() < args.size as usize => {
+                // The parser drops payloads that exceed allocator capacity.
+                NfsRes::Write(Err(vfs::write::Fail {
+                    error: vfs::Error::FileTooLarge,
+                    wcc_data: vfs::WccData { before: None, after: None },
+                }))
+            }
+            NfsArguments::Write(mut args) => {
+                if let Some(limits) = self.transfer_limits(&args.file).await {
+                    args.size = args.size.min(limits.write_max);
+                }
+                NfsRes::Write(self.backend.write(cred, args).await)
+            }
+            NfsArguments::Create(args) => NfsRes::Create(self.backend.create(cred, args).await),
+            NfsArguments::MkDir(args) => NfsRes::MkDir(self.backend.mk_dir(cred, args).await),
+            NfsArguments::SymLink(args) => NfsRes::SymLink(self.backend.symlink(cred, args).await),
+            NfsArguments::MkNod(args) => NfsRes::MkNod(self.backend.mk_node(cred, args).await),
+            NfsArguments::Remove(args) => NfsRes::Remove(self.backend.remove(cred, args).await),
+            NfsArguments::RmDir(args) => NfsRes::RmDir(self.backend.rm_dir(cred, args).await),
+            NfsArguments::Rename(args) => NfsRes::Rename(self.backend.rename(cred, args).await),
+            NfsArguments::Link(args) => NfsRes::Link(self.backend.link(cred, args).await),
+            NfsArguments::ReadDir(mut args) => {
+                args.count = args.count.min(reply_budget);
+                NfsRes::ReadDir(self.backend.read_dir(args).await)
+            }
+            NfsArguments::ReadDirPlus(mut args) => {
+                args.dir_count = args.dir_count.min(reply_budget);
+                args.max_count = args.max_count.min(reply_budget);
+                NfsRes::ReadDirPlus(self.backend.read_dir_plus(args).await)
+            }
+            NfsArguments::FsStat(args) => NfsRes::FsStat(self.backend.fs_stat(args).await),
+            NfsArguments::FsInfo(args) => NfsRes::FsInfo(self.backend.fs_info(args).await),
+            NfsArguments::PathConf(args) => NfsRes::PathConf(self.backend.path_conf(args).await),
+            NfsArguments::Commit(args) => NfsRes::Commit(self.backend.commit(args).await),
+            NfsArguments::GetAcl(args) => NfsRes::GetAcl(self.backend.get_acl(args).await),
+            NfsArguments::SetAcl(args) => NfsRes::SetAcl(self.backend.set_acl(cred, args).await),
+        }
+    }
+
+    /// Returns backend transfer limits for `file`, querying [`fs_info::FsInfo::fs_info`]
+    /// once per worker and transfer domain.
+    ///
+    /// Clients may request any `count` up to `u32::MAX`; READ and WRITE are clamped to these
+    /// limits, so the client sees a short read or write and issues the rest separately.
+    /// Returns [`None`] if the backend fails to report them, in which case no clamp applies.
+    async fn transfer_limits(&self, file: &file::Handle) -> Option<TransferLimits> {
+        let domain = self.backend.transfer_domain(file);
+        if let Some(limits) = self.transfer_limits.lock().unwrap().get(&domain) {
+            return Some(*limits);
+        }
+        let info = self.backend.fs_info(fs_info::Args { root: file.clone() }).await.ok()?;
+        let limits = TransferLimits { read_max: info.read_max, write_max: info.write_max };
+        self.transfer_limits.lock().unwrap().insert(domain, limits);
+        Some(limits)
+    }
+
+    /// Returns the caller identity carried by the RPC credential, or [`None`] if
+    /// `anonymous_access` refuses the call.
+    ///
+    /// AUTH_NONE callers act as the identity `anonymous_access` gives them.
+    /// The parser rejects malformed AUTH_SYS bodies, so the anonymous fallback
+    /// is never expected to be used.
+    fn credentials(
+        cred: &OpaqueAuth,
+        anonymous_access: AnonymousAccess,
+    ) -> Option<vfs::Credentials> {
+        match (cred.flavor(), anonymous_access) {
+            (AuthFlavor::Sys, _) => Some(match auth_sys(&mut cred.body()) {
+                Ok(params) => {
+                    vfs::Credentials { uid: params.uid, gid: params.gid, gids: params.gids }
+                }
+                Err(_) => vfs::Credentials::anonymous(),
+            }),
+            (_, AnonymousAccess::Allow { uid, gid }) => {
+                Some(vfs::Credentials { uid, gid, gids: Vec::new() })
+            }
+            (_, AnonymousAccess::Deny) => None,
+        }
+    }
+
+    /// Maps the owner and group a SETATTR of `cred` asks for like `id_map` maps `cred`,
+    /// so a squashed root cannot hand objects to root, and returns which of them changed.
+    fn squash_owner(
+        id_map: vfs::IdMapPolicy,
+        cred: &vfs::Credentials,
+        proc: &mut NfsArguments<B>,
+    ) -> set_attr::Ignored {
+        let NfsArguments::SetAttr(args) = proc else {
+            return set_attr::Ignored::default();
+        };
+        let (uid, gid) = id_map.apply_owner(cred, args.new_attr.uid, args.new_attr.gid);
+        let squashed = set_attr::Ignored {
+            mode: false,
+            uid: uid != args.new_attr.uid,
+            gid: gid != args.new_attr.gid,
+        };
+        args.new_attr.uid = uid;
+        args.new_attr.gid = gid;
+        squashed
+    }
+
+    /// Returns the handle `proc` operates on and the name of the entry it names, if any.
+    ///
+    /// Calls involving two objects report the first: the file of LINK and the source
+    /// of RENAME. NULL has no target.
+    fn audit_target(proc: &NfsArguments<B>) -> Option<(file::Handle, Option<String>)> {
+        let entry =
+            |object: &vfs::DirOpArgs| (object.dir.clone(), Some(object.name.as_str().to_owned()));
+        let target = match proc {
+            NfsArguments::Null => return None,
+            NfsArguments::GetAttr(args) => (args.file.clone(), None),
+            NfsArguments::SetAttr(args) => (args.file.clone(), None),
+            NfsArguments::LookUp(args) => {
+                (args.parent.clone(), Some(args.name.as_str().to_owned()))
+            }
+            NfsArguments::Access(args) => (args.file.clone(), None),
+            NfsArguments::ReadLink(args) => (args.file.clone(), None),
+            NfsArguments::Read(args) => (args.file.clone(), None),
+            NfsArguments::Write(args) => (args.file.clone(), None),
+            NfsArguments::Create(args) => entry(&args.object),
+            NfsArguments::MkDir(args) => entry(&args.object),
+            NfsArguments::SymLink(args) => entry(&args.object),
+            NfsArguments::MkNod(args) => entry(&args.object),
+            NfsArguments::Remove(args) => entry(&args.object),
+            NfsArguments::RmDir(args) => entry(&args.object),
+            NfsArguments::Rename(args) => entry(&args.from),
+            NfsArguments::Link(args) => (args.file.clone(), None),
+            NfsArguments::ReadDir(args) => (args.dir.clone(), None),
+            NfsArguments::ReadDirPlus(args) => (args.dir.clone(), None),
+            NfsArguments::FsStat(args) => (args.root.clone(), None),
+            NfsArguments::FsInfo(args) => (args.root.clone(), None),
+            NfsArguments::PathConf(args) => (args.file.clone(), None),
+            NfsArguments::Commit(args) => (args.file.clone(), None),
+            NfsArguments::GetAcl(args) => (args.file.clone(), None),
+            NfsArguments::SetAcl(args) => (args.file.clone(), None),
+        };
+        Some(target)
+    }
+
     /// Static label for logging/tracing for the given procedure variant.
     fn proc_name(proc: &NfsArguments<B>) -> &'static str {
+        match proc.nfs_proc() {
+            Some(nfs_proc) => nfs_proc.name(),
+            None if matches!(proc, NfsArguments::GetAcl(_)) => "GETACL",
+            None => "SETACL",
+        }
+    }
+
+    /// Returns the failure of `proc` with `error` and no attributes.
+    fn failure(proc: &NfsArguments<B>, error: vfs::Error) -> NfsRes<B> {
+        let no_wcc = || vfs::WccData { before: None, after: None };
         match proc {
-            NfsArguments::Null => "NULL",
-            NfsArguments::GetAttr(_) => "GETATTR",
-            NfsArguments::SetAttr(_) => "SETATTR",
-            NfsArguments::LookUp(_) => "LOOKUP",
-            NfsArguments::Access(_) => "ACCESS",
-            NfsArguments::ReadLink(_) => "READLINK",
-            NfsArguments::Read(_) => "READ",
-            NfsArguments::Write(_) => "WRITE",
-            NfsArguments::Create(_) => "CREATE",
-            NfsArguments::MkDir(_) => "MKDIR",
-            NfsArguments::SymLink(_) => "SYMLINK",
-            NfsArguments::MkNod(_) => "MKNOD",
-            NfsArguments::Remove(_) => "REMOVE",
-            NfsArguments::RmDir(_) => "RMDIR",
-            NfsArguments::Rename(_) => "RENAME",
-            NfsArguments::Link(_) => "LINK",
-            NfsArguments::ReadDir(_) => "READDIR",
-            NfsArguments::ReadDirPlus(_) => "READDIRPLUS",
-            NfsArguments::FsStat(_) => "FSSTAT",
-            NfsArguments::FsInfo(_) => "FSINFO",
-            NfsArguments::PathConf(_) => "PATHCONF",
-            NfsArguments::Commit(_) => "COMMIT",
+            NfsArguments::Null => NfsRes::Null,
+            NfsArguments::GetAttr(_) => NfsRes::GetAttr(Err(get_attr::Fail { error })),
+            NfsArguments::SetAttr(_) => {
+                NfsRes::SetAttr(Err(set_attr::Fail { error, wcc_data: no_wcc() }))
+            }
+            NfsArguments::LookUp(_) => NfsRes::LookUp(Err(lookup::Fail { error, dir_attr: None })),
+            NfsArguments::Access(_) => {
+                NfsRes::Access(Err(access::Fail { error, object_attr: None }))
+            }
+            NfsArguments::ReadLink(_) => {
+                NfsRes::ReadLink(Err(read_link::Fail { error, symlink_attr: None }))
+            }
+            NfsArguments::Read(_) => NfsRes::Read(Err(read::Fail { error, file_attr: None })),
+            NfsArguments::Write(_) => NfsRes::Write(Err(write::Fail { error, wcc_data: no_wcc() })),
+            NfsArguments::Create(_) => {
+                NfsRes::Create(Err(create::Fail { error, wcc_data: no_wcc() }))
+            }
+            NfsArguments::MkDir(_) => NfsRes::MkDir(Err(mk_dir::Fail { error, dir_wcc: no_wcc() })),
+            NfsArguments::SymLink(_) => {
+                NfsRes::SymLink(Err(symlink::Fail { error, dir_wcc: no_wcc() }))
+            }
+            NfsArguments::MkNod(_) => {
+                NfsRes::MkNod(Err(mk_node::Fail { error, dir_wcc: no_wcc() }))
+            }
+            NfsArguments::Remove(_) => {
+                NfsRes::Remove(Err(remove::Fail { error, dir_wcc: no_wcc() }))
+            }
+            NfsArguments::RmDir(_) => NfsRes::RmDir(Err(rm_dir::Fail { error, dir_wcc: no_wcc() })),
+            NfsArguments::Rename(_) => NfsRes::Rename(Err(rename::Fail {
+                error,
+                from_dir_wcc: no_wcc(),
+                to_dir_wcc: no_wcc(),
+            })),
+            NfsArguments::Link(_) => {
+                NfsRes::Link(Err(link::Fail { error, file_attr: None, dir_wcc: no_wcc() }))
+            }
+            NfsArguments::ReadDir(_) => {
+                NfsRes::ReadDir(Err(read_dir::Fail { error, dir_attr: None }))
+            }
+            NfsArguments::ReadDirPlus(_) => {
+                NfsRes::ReadDirPlus(Err(read_dir_plus::Fail { error, dir_attr: None }))
+            }
+            NfsArguments::FsStat(_) => {
+                NfsRes::FsStat(Err(fs_stat::Fail { error, root_attr: None }))
+            }
+            NfsArguments::FsInfo(_) => {
+                NfsRes::FsInfo(Err(fs_info::Fail { error, root_attr: None }))
+            }
+            NfsArguments::PathConf(_) => {
+                NfsRes::PathConf(Err(path_conf::Fail { error, file_attr: None }))
+            }
+            NfsArguments::Commit(_) => {
+                NfsRes::Commit(Err(commit::Fail { error, file_wcc: no_wcc() }))
+            }
+            NfsArguments::GetAcl(_) => {
+                NfsRes::GetAcl(Err(get_acl::Fail { error, file_attr: None }))
+            }
+            NfsArguments::SetAcl(_) => {
+                NfsRes::SetAcl(Err(set_acl::Fail { error, file_attr: None }))
+            }
         }
     }
 
@@ -235,6 +621,8 @@ where
             NfsRes::FsInfo(Err(err)) => Some(err.error),
             NfsRes::PathConf(Err(err)) => Some(err.error),
             NfsRes::Commit(Err(err)) => Some(err.error),
+            NfsRes::GetAcl(Err(err)) => Some(err.error),
+            NfsRes::SetAcl(Err(err)) => Some(err.error),
             _ => None,
         }
     }
@@ -3,6 +3,8 @@
 //! This module provides the task infrastructure for handling NFS server operations,
 //! including connection-specific tasks and global task coordination.
 
+use std::sync::Arc;
+
 use crate::allocator::Buffer;
 use crate::mount::MountRes;
 use crate::nlm::NlmRes;
@@ -17,10 +19,30 @@ pub enum ProcResult<B: Buffer> {
     Nfs3(Box<NfsRes<B>>),
     Mount(Box<MountRes>),
     Nlm4(Box<NlmRes>),
+    /// Record sent for an earlier transmission of the call, record mark included,
+    /// resent verbatim.
+    Replay(Arc<[u8]>),
+}
+
+/// Identity of an RPC call within a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallKey {
+    pub xid: u32,
+    pub program: u32,
+    pub version: u32,
+    pub procedure: u32,
 }
 
 /// RPC reply metadata plus a typed result to be serialized.
+///
+/// Procedure tasks build replies and hand them to the connection's write task, which
+/// serializes them with `Serializer::form_reply`, supplying the reply verifier.
 pub struct ProcReply<B: Buffer> {
+    /// Transaction id of the call answered.
     pub xid: u32,
+    /// Result of the procedure, or the RPC-level error rejecting the call.
     pub proc_result: Result<ProcResult<B>, Error>,
+    /// Key of the call in the connection's duplicate cache, if it was registered there
+    /// and its reply should be recorded.
+    pub call: Option<CallKey>,
 }
@@ -1,6 +1,6 @@
 //! Defines NFSv3 [`Access`] interface.
 
-use super::{file, Error};
+use super::{file, Credentials, Error};
 
 /// Success result.
 pub struct Success {
@@ -62,5 +62,7 @@ pub trait Access {
     /// such access will be allowed to the file system object in
     /// the future, as access rights can be revoked by the server
     /// at any time.
-    async fn access(&self, args: Args) -> Result<Success, Fail>;
+    ///
+    /// Access is evaluated for the caller identified by `cred`.
+    async fn access(&self, cred: &Credentials, args: Args) -> Result<Success, Fail>;
 }
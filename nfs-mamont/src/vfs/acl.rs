@@ -0,0 +1,95 @@
+//! Defines NFSACL [`Acl`] interface for POSIX access control lists.
+//!
+//! NFSACL is the side protocol NFSv3 clients use for `getfacl`/`setfacl`. Backends
+//! without ACL support may leave the default methods, which fail with
+//! [`Error::NotSupported`].
+
+use std::future::Future;
+
+use num_derive::{FromPrimitive, ToPrimitive};
+
+use super::{get_acl, set_acl, Credentials, Error};
+
+/// Kind of an ACL [`Entry`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToPrimitive, FromPrimitive)]
+pub enum Tag {
+    /// Permissions of the file owner.
+    UserObj = 0x01,
+    /// Permissions of the user named by [`Entry::id`].
+    User = 0x02,
+    /// Permissions of the owning group.
+    GroupObj = 0x04,
+    /// Permissions of the group named by [`Entry::id`].
+    Group = 0x08,
+    /// Upper bound of the permissions granted by named and group entries.
+    Mask = 0x10,
+    /// Permissions of everyone else.
+    Other = 0x20,
+}
+
+/// Single entry of an access control list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub tag: Tag,
+    /// User or group id for [`Tag::User`] and [`Tag::Group`] entries.
+    pub id: u32,
+    /// Combination of [`Entry::READ`], [`Entry::WRITE`] and [`Entry::EXECUTE`].
+    pub perm: u32,
+}
+
+impl Entry {
+    pub const READ: u32 = 0o4;
+    pub const WRITE: u32 = 0o2;
+    pub const EXECUTE: u32 = 0o1;
+}
+
+/// Mask selecting the lists transferred by [`Acl::get_acl`] and [`Acl::set_acl`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Mask(u32);
+
+impl Mask {
+    /// Entries of the access ACL.
+    pub const ACL: u32 = 0x0001;
+    /// Number of entries of the access ACL.
+    pub const ACL_COUNT: u32 = 0x0002;
+    /// Entries of the default ACL of a directory.
+    pub const DEFAULT_ACL: u32 = 0x0004;
+    /// Number of entries of the default ACL of a directory.
+    pub const DEFAULT_ACL_COUNT: u32 = 0x0008;
+
+    pub const ALL: u32 = Self::ACL | Self::ACL_COUNT | Self::DEFAULT_ACL | Self::DEFAULT_ACL_COUNT;
+
+    pub fn from_wire(raw: u32) -> Self {
+        Self(raw & Self::ALL)
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(self, flag: u32) -> bool {
+        self.0 & flag == flag
+    }
+}
+
+#[trait_variant::make(Send)]
+pub trait Acl {
+    /// Retrieves the access and default ACLs of a file system object.
+    fn get_acl(
+        &self,
+        args: get_acl::Args,
+    ) -> impl Future<Output = Result<get_acl::Success, get_acl::Fail>> {
+        let _ = args;
+        async { Err(get_acl::Fail { error: Error::NotSupported, file_attr: None }) }
+    }
+
+    /// Replaces the access and default ACLs of a file system object on behalf of `cred`.
+    fn set_acl(
+        &self,
+        cred: &Credentials,
+        args: set_acl::Args,
+    ) -> impl Future<Output = Result<set_acl::Success, set_acl::Fail>> {
+        let _ = (cred, args);
+        async { Err(set_acl::Fail { error: Error::NotSupported, file_attr: None }) }
+    }
+}
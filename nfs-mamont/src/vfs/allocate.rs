@@ -0,0 +1,35 @@
+//! Defines [`VfsAllocate`] extension interface for preallocating file space.
+//!
+//! Preallocation is not part of NFSv3 and is not served over the wire; the trait lets
+//! applications embedding the server reserve space for databases or VM images through
+//! the backend that serves NFS requests, and an NFSv4.2-style ALLOCATE later.
+
+use super::{file, Credentials, Error};
+
+/// How [`VfsAllocate::allocate_space`] treats the size of the file.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Extends the file to cover the allocated range if it ends past the end of file.
+    #[default]
+    Extend,
+    /// Keeps the file size; space past the end of file is reserved for later appends.
+    KeepSize,
+}
+
+#[trait_variant::make(Send)]
+pub trait VfsAllocate {
+    /// Reserves storage for `len` bytes of `file` starting at `offset` on behalf of
+    /// `cred`, so later writes to the range do not fail for lack of space.
+    ///
+    /// Data already in the range is preserved and unwritten parts read as zeros.
+    /// Fails with [`Error::InvalidArgument`] if `len` is zero and with
+    /// [`Error::NotSupported`] if the backend cannot preallocate space.
+    async fn allocate_space(
+        &self,
+        cred: &Credentials,
+        file: &file::Handle,
+        offset: u64,
+        len: u64,
+        mode: Mode,
+    ) -> Result<(), Error>;
+}
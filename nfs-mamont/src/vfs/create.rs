@@ -13,6 +13,9 @@ pub enum How {
     /// Means that the file should be created without checking
     /// for the existence of a duplicate file in the same
     /// directory with initial attributes for the file.
+    ///
+    /// An existing file is not truncated: its data is kept and only the given
+    /// attributes, a size among them, are applied to it.
     Unchecked(super::set_attr::NewAttr),
     /// Specifies that the server should check for the presence
     /// of a duplicate file before performing the create and
@@ -65,5 +68,7 @@ pub struct Args {
 #[trait_variant::make(Send)]
 pub trait Create {
     /// Creates a [`file::Type::Regular`] file.
-    async fn create(&self, args: Args) -> Result<Success, Fail>;
+    ///
+    /// A newly created file should be owned by the caller identified by `cred`.
+    async fn create(&self, cred: &vfs::Credentials, args: Args) -> Result<Success, Fail>;
 }
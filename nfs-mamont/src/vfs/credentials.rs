@@ -0,0 +1,89 @@
+//! Defines caller identity passed into [`super::Vfs`] methods and its mapping policy.
+
+/// Default uid of the anonymous user (`nobody`).
+pub const ANON_UID: u32 = 65534;
+
+/// Default gid of the anonymous group (`nogroup`).
+pub const ANON_GID: u32 = 65534;
+
+/// Identity of the caller, as seen by the [`super::Vfs`] implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    /// Effective user id.
+    pub uid: u32,
+    /// Effective group id.
+    pub gid: u32,
+    /// Supplementary group ids.
+    pub gids: Vec<u32>,
+}
+
+impl Credentials {
+    /// Returns credentials of the superuser.
+    pub fn root() -> Self {
+        Self { uid: 0, gid: 0, gids: Vec::new() }
+    }
+
+    /// Returns credentials of the anonymous user ([`ANON_UID`], [`ANON_GID`]).
+    pub fn anonymous() -> Self {
+        Self { uid: ANON_UID, gid: ANON_GID, gids: Vec::new() }
+    }
+
+    /// Returns `true` if the caller belongs to `gid`, either as primary or supplementary group.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.gid == gid || self.gids.contains(&gid)
+    }
+}
+
+/// Mapping of client identities onto server identities, applied to every NFS call
+/// before it reaches the [`super::Vfs`]; see [`crate::ServerContext::with_id_map`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdMapPolicy {
+    /// Identities are used as sent by the client.
+    #[default]
+    NoSquash,
+    /// Root (uid `0`) is mapped to the anonymous identity, other users are unchanged.
+    RootSquash { anon_uid: u32, anon_gid: u32 },
+    /// Every user is mapped to the anonymous identity.
+    AllSquash { anon_uid: u32, anon_gid: u32 },
+}
+
+impl IdMapPolicy {
+    /// Returns the effective credentials for `credentials` under this policy.
+    ///
+    /// Squashed callers lose their supplementary groups.
+    pub fn apply(&self, credentials: Credentials) -> Credentials {
+        match *self {
+            Self::RootSquash { anon_uid, anon_gid } if credentials.uid == 0 => {
+                Credentials { uid: anon_uid, gid: anon_gid, gids: Vec::new() }
+            }
+            Self::AllSquash { anon_uid, anon_gid } => {
+                Credentials { uid: anon_uid, gid: anon_gid, gids: Vec::new() }
+            }
+            _ => credentials,
+        }
+    }
+
+    /// Returns the owner `uid` and group `gid` a caller with `credentials` asks to give
+    /// an object, mapped like the caller itself: ids squashed for this caller turn into
+    /// the anonymous ones.
+    ///
+    /// A squashed root asking for root ownership thus gets the anonymous owner it
+    /// would have received by creating the object.
+    pub fn apply_owner(
+        &self,
+        credentials: &Credentials,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> (Option<u32>, Option<u32>) {
+        match *self {
+            Self::RootSquash { anon_uid, anon_gid } if credentials.uid == 0 => (
+                uid.map(|uid| if uid == 0 { anon_uid } else { uid }),
+                gid.map(|gid| if gid == 0 { anon_gid } else { gid }),
+            ),
+            Self::AllSquash { anon_uid, anon_gid } => {
+                (uid.map(|_| anon_uid), gid.map(|_| anon_gid))
+            }
+            _ => (uid, gid),
+        }
+    }
+}
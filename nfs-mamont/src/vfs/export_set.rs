@@ -0,0 +1,713 @@
+//! Serves several exports, each backed by its own [`Vfs`], from one listener.
+//!
+//! [`ExportSet`] is itself a [`Vfs`]: it routes every operation to the backend of the
+//! export the file handle belongs to. Backends may be of different types. The first
+//! byte of a handle names the export (its index plus one); backends must leave that
+//! byte zero in the handles they issue, and get it back zeroed. Handles naming no
+//! export, and operations combining handles of different exports, fail with
+//! [`Error::BadFileHandle`]; a backend handing out a handle with the byte set fails
+//! the operation with [`Error::ServerFault`].
+//!
+//! [`ExportSet::mount_entries`] lists the exports with their namespaced root handles
+//! for [`crate::service::mount::MountService`], so `MNT` hands out handles routed to
+//! the chosen export.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::allocator::Buffer;
+use crate::mount::ExportEntry;
+use crate::rpc::AuthFlavor;
+use crate::service::mount::{ExportEntryWrapper, DEFAULT_AUTH_FLAVORS};
+
+use super::{
+    access, acl, commit, create, file, fs_info, fs_stat, get_acl, get_attr, link, lookup, mk_dir,
+    mk_node, path_conf, read, read_dir, read_dir_plus, read_link, remove, rename, rm_dir, set_acl,
+    set_attr, symlink, write, Credentials, DirOpArgs, Error, Vfs, WccData,
+};
+
+/// Most exports an [`ExportSet`] holds: namespace `0` is left to backends.
+pub const MAX_EXPORTS: usize = u8::MAX as usize;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Object-safe form of [`Vfs`], so one set holds backends of different types.
+///
+/// Every [`Vfs`] implements it by boxing the futures of its operations.
+trait DynVfs<B: Buffer + 'static>: Send + Sync {
+    fn get_attr(
+        &self,
+        args: get_attr::Args,
+    ) -> BoxFuture<'_, Result<get_attr::Success, get_attr::Fail>>;
+    fn set_attr<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: set_attr::Args,
+    ) -> BoxFuture<'a, Result<set_attr::Success, set_attr::Fail>>;
+    fn lookup(&self, args: lookup::Args) -> BoxFuture<'_, Result<lookup::Success, lookup::Fail>>;
+    fn access<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: access::Args,
+    ) -> BoxFuture<'a, Result<access::Success, access::Fail>>;
+    fn read_link(
+        &self,
+        args: read_link::Args,
+    ) -> BoxFuture<'_, Result<read_link::Success, read_link::Fail>>;
+    fn read<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: read::Args,
+        data: B,
+    ) -> BoxFuture<'a, Result<read::Success<B>, read::Fail>>;
+    fn write<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: write::Args<B>,
+    ) -> BoxFuture<'a, Result<write::Success, write::Fail>>;
+    fn create<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: create::Args,
+    ) -> BoxFuture<'a, Result<create::Success, create::Fail>>;
+    fn mk_dir<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: mk_dir::Args,
+    ) -> BoxFuture<'a, Result<mk_dir::Success, mk_dir::Fail>>;
+    fn symlink<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: symlink::Args,
+    ) -> BoxFuture<'a, Result<symlink::Success, symlink::Fail>>;
+    fn mk_node<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: mk_node::Args,
+    ) -> BoxFuture<'a, Result<mk_node::Success, mk_node::Fail>>;
+    fn remove<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: remove::Args,
+    ) -> BoxFuture<'a, Result<remove::Success, remove::Fail>>;
+    fn rm_dir<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: rm_dir::Args,
+    ) -> BoxFuture<'a, Result<rm_dir::Success, rm_dir::Fail>>;
+    fn rename<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: rename::Args,
+    ) -> BoxFuture<'a, Result<rename::Success, rename::Fail>>;
+    fn link<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: link::Args,
+    ) -> BoxFuture<'a, Result<link::Success, link::Fail>>;
+    fn read_dir(
+        &self,
+        args: read_dir::Args,
+    ) -> BoxFuture<'_, Result<read_dir::Success, read_dir::Fail>>;
+    fn read_dir_plus(
+        &self,
+        args: read_dir_plus::Args,
+    ) -> BoxFuture<'_, Result<read_dir_plus::Success, read_dir_plus::Fail>>;
+    fn fs_stat(
+        &self,
+        args: fs_stat::Args,
+    ) -> BoxFuture<'_, Result<fs_stat::Success, fs_stat::Fail>>;
+    fn fs_info(
+        &self,
+        args: fs_info::Args,
+    ) -> BoxFuture<'_, Result<fs_info::Success, fs_info::Fail>>;
+    fn path_conf(
+        &self,
+        args: path_conf::Args,
+    ) -> BoxFuture<'_, Result<path_conf::Success, path_conf::Fail>>;
+    fn commit(&self, args: commit::Args) -> BoxFuture<'_, Result<commit::Success, commit::Fail>>;
+    fn get_acl(
+        &self,
+        args: get_acl::Args,
+    ) -> BoxFuture<'_, Result<get_acl::Success, get_acl::Fail>>;
+    fn set_acl<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: set_acl::Args,
+    ) -> BoxFuture<'a, Result<set_acl::Success, set_acl::Fail>>;
+}
+
+impl<B: Buffer + 'static, V: Vfs<B> + Send + Sync> DynVfs<B> for V {
+    fn get_attr(
+        &self,
+        args: get_attr::Args,
+    ) -> BoxFuture<'_, Result<get_attr::Success, get_attr::Fail>> {
+        Box::pin(get_attr::GetAttr::get_attr(self, args))
+    }
+    fn set_attr<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: set_attr::Args,
+    ) -> BoxFuture<'a, Result<set_attr::Success, set_attr::Fail>> {
+        Box::pin(set_attr::SetAttr::set_attr(self, cred, args))
+    }
+    fn lookup(&self, args: lookup::Args) -> BoxFuture<'_, Result<lookup::Success, lookup::Fail>> {
+        Box::pin(lookup::Lookup::lookup(self, args))
+    }
+    fn access<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: access::Args,
+    ) -> BoxFuture<'a, Result<access::Success, access::Fail>> {
+        Box::pin(access::Access::access(self, cred, args))
+    }
+    fn read_link(
+        &self,
+        args: read_link::Args,
+    ) -> BoxFuture<'_, Result<read_link::Success, read_link::Fail>> {
+        Box::pin(read_link::ReadLink::read_link(self, args))
+    }
+    fn read<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: read::Args,
+        data: B,
+    ) -> BoxFuture<'a, Result<read::Success<B>, read::Fail>> {
+        Box::pin(read::Read::read(self, cred, args, data))
+    }
+    fn write<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: write::Args<B>,
+    ) -> BoxFuture<'a, Result<write::Success, write::Fail>> {
+        Box::pin(write::Write::write(self, cred, args))
+    }
+    fn create<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: create::Args,
+    ) -> BoxFuture<'a, Result<create::Success, create::Fail>> {
+        Box::pin(create::Create::create(self, cred, args))
+    }
+    fn mk_dir<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: mk_dir::Args,
+    ) -> BoxFuture<'a, Result<mk_dir::Success, mk_dir::Fail>> {
+        Box::pin(mk_dir::MkDir::mk_dir(self, cred, args))
+    }
+    fn symlink<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: symlink::Args,
+    ) -> BoxFuture<'a, Result<symlink::Success, symlink::Fail>> {
+        Box::pin(symlink::Symlink::symlink(self, cred, args))
+    }
+    fn mk_node<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: mk_node::Args,
+    ) -> BoxFuture<'a, Result<mk_node::Success, mk_node::Fail>> {
+        Box::pin(mk_node::MkNode::mk_node(self, cred, args))
+    }
+    fn remove<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: remove::Args,
+    ) -> BoxFuture<'a, Result<remove::Success, remove::Fail>> {
+        Box::pin(remove::Remove::remove(self, cred, args))
+    }
+    fn rm_dir<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: rm_dir::Args,
+    ) -> BoxFuture<'a, Result<rm_dir::Success, rm_dir::Fail>> {
+        Box::pin(rm_dir::RmDir::rm_dir(self, cred, args))
+    }
+    fn rename<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: rename::Args,
+    ) -> BoxFuture<'a, Result<rename::Success, rename::Fail>> {
+        Box::pin(rename::Rename::rename(self, cred, args))
+    }
+    fn link<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: link::Args,
+    ) -> BoxFuture<'a, Result<link::Success, link::Fail>> {
+        Box::pin(link::Link::link(self, cred, args))
+    }
+    fn read_dir(
+        &self,
+        args: read_dir::Args,
+    ) -> BoxFuture<'_, Result<read_dir::Success, read_dir::Fail>> {
+        Box::pin(read_dir::ReadDir::read_dir(self, args))
+    }
+    fn read_dir_plus(
+        &self,
+        args: read_dir_plus::Args,
+    ) -> BoxFuture<'_, Result<read_dir_plus::Success, read_dir_plus::Fail>> {
+        Box::pin(read_dir_plus::ReadDirPlus::read_dir_plus(self, args))
+    }
+    fn fs_stat(
+        &self,
+        args: fs_stat::Args,
+    ) -> BoxFuture<'_, Result<fs_stat::Success, fs_stat::Fail>> {
+        Box::pin(fs_stat::FsStat::fs_stat(self, args))
+    }
+    fn fs_info(
+        &self,
+        args: fs_info::Args,
+    ) -> BoxFuture<'_, Result<fs_info::Success, fs_info::Fail>> {
+        Box::pin(fs_info::FsInfo::fs_info(self, args))
+    }
+    fn path_conf(
+        &self,
+        args: path_conf::Args,
+    ) -> BoxFuture<'_, Result<path_conf::Success, path_conf::Fail>> {
+        Box::pin(path_conf::PathConf::path_conf(self, args))
+    }
+    fn commit(&self, args: commit::Args) -> BoxFuture<'_, Result<commit::Success, commit::Fail>> {
+        Box::pin(commit::Commit::commit(self, args))
+    }
+    fn get_acl(
+        &self,
+        args: get_acl::Args,
+    ) -> BoxFuture<'_, Result<get_acl::Success, get_acl::Fail>> {
+        Box::pin(acl::Acl::get_acl(self, args))
+    }
+    fn set_acl<'a>(
+        &'a self,
+        cred: &'a Credentials,
+        args: set_acl::Args,
+    ) -> BoxFuture<'a, Result<set_acl::Success, set_acl::Fail>> {
+        Box::pin(acl::Acl::set_acl(self, cred, args))
+    }
+}
+
+struct Export<B> {
+    entry: ExportEntry,
+    vfs: Arc<dyn DynVfs<B>>,
+    /// Root handle of the export, namespaced.
+    root: file::Handle,
+    /// Flavors `MNT` offers for the export.
+    auth_flavors: Vec<AuthFlavor>,
+}
+
+/// Exports served by different backends, told apart by a namespace in file handles.
+pub struct ExportSet<B> {
+    exports: Vec<Export<B>>,
+}
+
+impl<B> Default for ExportSet<B> {
+    fn default() -> Self {
+        Self { exports: Vec::new() }
+    }
+}
+
+impl<B: Buffer + 'static> ExportSet<B> {
+    /// Creates a set without exports.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `entry`, served by `vfs` from the directory with handle `root`, and returns
+    /// the root handle clients mount. The export offers [`DEFAULT_AUTH_FLAVORS`].
+    ///
+    /// Fails with [`Error::BadFileHandle`] if `root` uses the namespace byte and with
+    /// [`Error::NoSpace`] once the set holds [`MAX_EXPORTS`] exports.
+    pub fn add<V>(
+        &mut self,
+        entry: ExportEntry,
+        vfs: Arc<V>,
+        root: file::Handle,
+    ) -> Result<file::Handle, Error>
+    where
+        V: Vfs<B> + Send + Sync + 'static,
+    {
+        self.add_with_auth_flavors(entry, vfs, root, DEFAULT_AUTH_FLAVORS.to_vec())
+    }
+
+    /// Adds an export like [`Self::add`], offering `auth_flavors` in order of preference.
+    pub fn add_with_auth_flavors<V>(
+        &mut self,
+        entry: ExportEntry,
+        vfs: Arc<V>,
+        root: file::Handle,
+        auth_flavors: Vec<AuthFlavor>,
+    ) -> Result<file::Handle, Error>
+    where
+        V: Vfs<B> + Send + Sync + 'static,
+    {
+        if self.exports.len() == MAX_EXPORTS {
+            return Err(Error::NoSpace);
+        }
+        let root = namespaced(self.exports.len(), root).ok_or(Error::BadFileHandle)?;
+        self.exports.push(Export { entry, vfs, root: root.clone(), auth_flavors });
+        Ok(root)
+    }
+
+    /// Returns the exports with their namespaced root handles and authentication
+    /// flavors, for the MOUNT service.
+    pub fn mount_entries(&self) -> Vec<ExportEntryWrapper> {
+        self.exports
+            .iter()
+            .map(|export| ExportEntryWrapper {
+                export: export.entry.clone(),
+                root_handle: export.root.clone(),
+                auth_flavors: export.auth_flavors.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the index of the export `handle` belongs to, its backend and the handle
+    /// as the backend issued it.
+    fn route(&self, handle: &file::Handle) -> Result<(usize, &dyn DynVfs<B>, file::Handle), Error> {
+        let index = usize::from(handle.0[0]).checked_sub(1).ok_or(Error::BadFileHandle)?;
+        let export = self.exports.get(index).ok_or(Error::BadFileHandle)?;
+        let mut inner = handle.clone();
+        inner.0[0] = 0;
+        Ok((index, export.vfs.as_ref(), inner))
+    }
+
+    /// Routes a pair of handles that must belong to the same export.
+    fn route_pair(
+        &self,
+        first: &file::Handle,
+        second: &file::Handle,
+    ) -> Result<(usize, &dyn DynVfs<B>, file::Handle, file::Handle), Error> {
+        let (index, vfs, first) = self.route(first)?;
+        match self.route(second)? {
+            (other, _, second) if other == index => Ok((index, vfs, first, second)),
+            _ => Err(Error::BadFileHandle),
+        }
+    }
+}
+
+/// Places `handle`, issued by the backend of export `index`, in the export's namespace.
+///
+/// Returns `None` if the backend used the namespace byte.
+fn namespaced(index: usize, mut handle: file::Handle) -> Option<file::Handle> {
+    if handle.0[0] != 0 {
+        return None;
+    }
+    handle.0[0] = u8::try_from(index + 1).ok()?;
+    Some(handle)
+}
+
+/// Places an optional `handle` of export `index` in its namespace, failing with
+/// [`Error::ServerFault`] if the backend used the namespace byte.
+fn namespaced_opt(
+    index: usize,
+    handle: Option<file::Handle>,
+) -> Result<Option<file::Handle>, Error> {
+    handle.map(|handle| namespaced(index, handle).ok_or(Error::ServerFault)).transpose()
+}
+
+fn no_wcc() -> WccData {
+    WccData { before: None, after: None }
+}
+
+fn dir_op(object: DirOpArgs, dir: file::Handle) -> DirOpArgs {
+    DirOpArgs { dir, name: object.name }
+}
+
+impl<B: Buffer + 'static> get_attr::GetAttr for ExportSet<B> {
+    async fn get_attr(&self, args: get_attr::Args) -> Result<get_attr::Success, get_attr::Fail> {
+        let (_, vfs, file) = self.route(&args.file).map_err(|error| get_attr::Fail { error })?;
+        vfs.get_attr(get_attr::Args { file }).await
+    }
+}
+
+impl<B: Buffer + 'static> set_attr::SetAttr for ExportSet<B> {
+    async fn set_attr(
+        &self,
+        cred: &Credentials,
+        args: set_attr::Args,
+    ) -> Result<set_attr::Success, set_attr::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| set_attr::Fail { error, wcc_data: no_wcc() })?;
+        vfs.set_attr(cred, set_attr::Args { file, ..args }).await
+    }
+}
+
+impl<B: Buffer + 'static> lookup::Lookup for ExportSet<B> {
+    async fn lookup(&self, args: lookup::Args) -> Result<lookup::Success, lookup::Fail> {
+        let (index, vfs, parent) =
+            self.route(&args.parent).map_err(|error| lookup::Fail { error, dir_attr: None })?;
+        let success = vfs.lookup(lookup::Args { parent, name: args.name }).await?;
+        match namespaced(index, success.file) {
+            Some(file) => Ok(lookup::Success { file, ..success }),
+            None => Err(lookup::Fail { error: Error::ServerFault, dir_attr: success.dir_attr }),
+        }
+    }
+}
+
+impl<B: Buffer + 'static> access::Access for ExportSet<B> {
+    async fn access(
+        &self,
+        cred: &Credentials,
+        args: access::Args,
+    ) -> Result<access::Success, access::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| access::Fail { error, object_attr: None })?;
+        vfs.access(cred, access::Args { file, mask: args.mask }).await
+    }
+}
+
+impl<B: Buffer + 'static> read_link::ReadLink for ExportSet<B> {
+    async fn read_link(
+        &self,
+        args: read_link::Args,
+    ) -> Result<read_link::Success, read_link::Fail> {
+        let (_, vfs, file) = self
+            .route(&args.file)
+            .map_err(|error| read_link::Fail { error, symlink_attr: None })?;
+        vfs.read_link(read_link::Args { file }).await
+    }
+}
+
+impl<B: Buffer + 'static> read::Read<B> for ExportSet<B> {
+    async fn read(
+        &self,
+        cred: &Credentials,
+        args: read::Args,
+        data: B,
+    ) -> Result<read::Success<B>, read::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| read::Fail { error, file_attr: None })?;
+        vfs.read(cred, read::Args { file, ..args }, data).await
+    }
+}
+
+impl<B: Buffer + 'static> write::Write<B> for ExportSet<B> {
+    async fn write(
+        &self,
+        cred: &Credentials,
+        args: write::Args<B>,
+    ) -> Result<write::Success, write::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| write::Fail { error, wcc_data: no_wcc() })?;
+        vfs.write(cred, write::Args { file, ..args }).await
+    }
+}
+
+impl<B: Buffer + 'static> create::Create for ExportSet<B> {
+    async fn create(
+        &self,
+        cred: &Credentials,
+        args: create::Args,
+    ) -> Result<create::Success, create::Fail> {
+        let (index, vfs, dir) = self
+            .route(&args.object.dir)
+            .map_err(|error| create::Fail { error, wcc_data: no_wcc() })?;
+        let success = vfs
+            .create(cred, create::Args { object: dir_op(args.object, dir), how: args.how })
+            .await?;
+        match namespaced_opt(index, success.file) {
+            Ok(file) => Ok(create::Success { file, ..success }),
+            Err(error) => Err(create::Fail { error, wcc_data: success.wcc_data }),
+        }
+    }
+}
+
+impl<B: Buffer + 'static> mk_dir::MkDir for ExportSet<B> {
+    async fn mk_dir(
+        &self,
+        cred: &Credentials,
+        args: mk_dir::Args,
+    ) -> Result<mk_dir::Success, mk_dir::Fail> {
+        let (index, vfs, dir) = self
+            .route(&args.object.dir)
+            .map_err(|error| mk_dir::Fail { error, dir_wcc: no_wcc() })?;
+        let success = vfs
+            .mk_dir(cred, mk_dir::Args { object: dir_op(args.object, dir), attr: args.attr })
+            .await?;
+        match namespaced_opt(index, success.file) {
+            Ok(file) => Ok(mk_dir::Success { file, ..success }),
+            Err(error) => Err(mk_dir::Fail { error, dir_wcc: success.wcc_data }),
+        }
+    }
+}
+
+impl<B: Buffer + 'static> symlink::Symlink for ExportSet<B> {
+    async fn symlink(
+        &self,
+        cred: &Credentials,
+        args: symlink::Args,
+    ) -> Result<symlink::Success, symlink::Fail> {
+        let (index, vfs, dir) = self
+            .route(&args.object.dir)
+            .map_err(|error| symlink::Fail { error, dir_wcc: no_wcc() })?;
+        let args =
+            symlink::Args { object: dir_op(args.object, dir), attr: args.attr, path: args.path };
+        let success = vfs.symlink(cred, args).await?;
+        match namespaced_opt(index, success.file) {
+            Ok(file) => Ok(symlink::Success { file, ..success }),
+            Err(error) => Err(symlink::Fail { error, dir_wcc: success.wcc_data }),
+        }
+    }
+}
+
+impl<B: Buffer + 'static> mk_node::MkNode for ExportSet<B> {
+    async fn mk_node(
+        &self,
+        cred: &Credentials,
+        args: mk_node::Args,
+    ) -> Result<mk_node::Success, mk_node::Fail> {
+        let (index, vfs, dir) = self
+            .route(&args.object.dir)
+            .map_err(|error| mk_node::Fail { error, dir_wcc: no_wcc() })?;
+        let success = vfs
+            .mk_node(cred, mk_node::Args { object: dir_op(args.object, dir), what: args.what })
+            .await?;
+        match namespaced_opt(index, success.file) {
+            Ok(file) => Ok(mk_node::Success { file, ..success }),
+            Err(error) => Err(mk_node::Fail { error, dir_wcc: success.wcc_data }),
+        }
+    }
+}
+
+impl<B: Buffer + 'static> remove::Remove for ExportSet<B> {
+    async fn remove(
+        &self,
+        cred: &Credentials,
+        args: remove::Args,
+    ) -> Result<remove::Success, remove::Fail> {
+        let (_, vfs, dir) = self
+            .route(&args.object.dir)
+            .map_err(|error| remove::Fail { error, dir_wcc: no_wcc() })?;
+        vfs.remove(cred, remove::Args { object: dir_op(args.object, dir) }).await
+    }
+}
+
+impl<B: Buffer + 'static> rm_dir::RmDir for ExportSet<B> {
+    async fn rm_dir(
+        &self,
+        cred: &Credentials,
+        args: rm_dir::Args,
+    ) -> Result<rm_dir::Success, rm_dir::Fail> {
+        let (_, vfs, dir) = self
+            .route(&args.object.dir)
+            .map_err(|error| rm_dir::Fail { error, dir_wcc: no_wcc() })?;
+        vfs.rm_dir(cred, rm_dir::Args { object: dir_op(args.object, dir) }).await
+    }
+}
+
+impl<B: Buffer + 'static> rename::Rename for ExportSet<B> {
+    async fn rename(
+        &self,
+        cred: &Credentials,
+        args: rename::Args,
+    ) -> Result<rename::Success, rename::Fail> {
+        let (_, vfs, from, to) =
+            self.route_pair(&args.from.dir, &args.to.dir).map_err(|error| rename::Fail {
+                error,
+                from_dir_wcc: no_wcc(),
+                to_dir_wcc: no_wcc(),
+            })?;
+        let args = rename::Args { from: dir_op(args.from, from), to: dir_op(args.to, to) };
+        vfs.rename(cred, args).await
+    }
+}
+
+impl<B: Buffer + 'static> link::Link for ExportSet<B> {
+    async fn link(
+        &self,
+        cred: &Credentials,
+        args: link::Args,
+    ) -> Result<link::Success, link::Fail> {
+        let (_, vfs, file, dir) = self
+            .route_pair(&args.file, &args.link.dir)
+            .map_err(|error| link::Fail { error, file_attr: None, dir_wcc: no_wcc() })?;
+        vfs.link(cred, link::Args { file, link: dir_op(args.link, dir) }).await
+    }
+}
+
+impl<B: Buffer + 'static> read_dir::ReadDir for ExportSet<B> {
+    async fn read_dir(&self, args: read_dir::Args) -> Result<read_dir::Success, read_dir::Fail> {
+        let (_, vfs, dir) =
+            self.route(&args.dir).map_err(|error| read_dir::Fail { error, dir_attr: None })?;
+        vfs.read_dir(read_dir::Args { dir, ..args }).await
+    }
+}
+
+impl<B: Buffer + 'static> read_dir_plus::ReadDirPlus for ExportSet<B> {
+    async fn read_dir_plus(
+        &self,
+        args: read_dir_plus::Args,
+    ) -> Result<read_dir_plus::Success, read_dir_plus::Fail> {
+        let (index, vfs, dir) =
+            self.route(&args.dir).map_err(|error| read_dir_plus::Fail { error, dir_attr: None })?;
+        let mut success = vfs.read_dir_plus(read_dir_plus::Args { dir, ..args }).await?;
+        for entry in &mut success.entries {
+            match namespaced_opt(index, entry.file_handle.take()) {
+                Ok(file) => entry.file_handle = file,
+                Err(error) => {
+                    return Err(read_dir_plus::Fail { error, dir_attr: success.dir_attr })
+                }
+            }
+        }
+        Ok(success)
+    }
+}
+
+impl<B: Buffer + 'static> fs_stat::FsStat for ExportSet<B> {
+    async fn fs_stat(&self, args: fs_stat::Args) -> Result<fs_stat::Success, fs_stat::Fail> {
+        let (_, vfs, root) =
+            self.route(&args.root).map_err(|error| fs_stat::Fail { error, root_attr: None })?;
+        vfs.fs_stat(fs_stat::Args { root }).await
+    }
+}
+
+impl<B: Buffer + 'static> fs_info::FsInfo for ExportSet<B> {
+    async fn fs_info(&self, args: fs_info::Args) -> Result<fs_info::Success, fs_info::Fail> {
+        let (_, vfs, root) =
+            self.route(&args.root).map_err(|error| fs_info::Fail { error, root_attr: None })?;
+        vfs.fs_info(fs_info::Args { root }).await
+    }
+
+    /// Exports may differ in their limits, so each is a domain of its own.
+    fn transfer_domain(&self, file: &file::Handle) -> u64 {
+        u64::from(file.0[0])
+    }
+}
+
+impl<B: Buffer + 'static> path_conf::PathConf for ExportSet<B> {
+    async fn path_conf(
+        &self,
+        args: path_conf::Args,
+    ) -> Result<path_conf::Success, path_conf::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| path_conf::Fail { error, file_attr: None })?;
+        vfs.path_conf(path_conf::Args { file }).await
+    }
+}
+
+impl<B: Buffer + 'static> commit::Commit for ExportSet<B> {
+    async fn commit(&self, args: commit::Args) -> Result<commit::Success, commit::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| commit::Fail { error, file_wcc: no_wcc() })?;
+        vfs.commit(commit::Args { file, ..args }).await
+    }
+}
+
+impl<B: Buffer + 'static> acl::Acl for ExportSet<B> {
+    async fn get_acl(&self, args: get_acl::Args) -> Result<get_acl::Success, get_acl::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| get_acl::Fail { error, file_attr: None })?;
+        vfs.get_acl(get_acl::Args { file, ..args }).await
+    }
+
+    async fn set_acl(
+        &self,
+        cred: &Credentials,
+        args: set_acl::Args,
+    ) -> Result<set_acl::Success, set_acl::Fail> {
+        let (_, vfs, file) =
+            self.route(&args.file).map_err(|error| set_acl::Fail { error, file_attr: None })?;
+        vfs.set_acl(cred, set_acl::Args { file, ..args }).await
+    }
+}
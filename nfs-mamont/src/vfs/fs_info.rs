@@ -81,4 +81,13 @@ pub struct Args {
 pub trait FsInfo {
     /// Retrieves nonvolatile file system state information and general information.
     async fn fs_info(&self, args: Args) -> Result<Success, Fail>;
+
+    /// Returns a key shared by all handles of one file system, whose READ and WRITE
+    /// limits reported by [`FsInfo::fs_info`] the server may reuse for each other.
+    ///
+    /// The default suits backends serving a single file system.
+    fn transfer_domain(&self, file: &file::Handle) -> u64 {
+        let _ = file;
+        0
+    }
 }
@@ -0,0 +1,32 @@
+//! Defines NFSACL `GETACL` arguments and results of [`super::acl::Acl::get_acl`].
+
+use crate::vfs;
+
+use super::acl::{Entry, Mask};
+use super::file;
+
+/// Success result.
+pub struct Success {
+    pub file_attr: Option<file::Attr>,
+    /// Lists present in the result.
+    pub mask: Mask,
+    /// Access ACL of the object.
+    pub access: Vec<Entry>,
+    /// Default ACL of the object; always empty for non-directories.
+    pub default: Vec<Entry>,
+}
+
+/// Fail result.
+pub struct Fail {
+    /// Error on failure.
+    pub error: vfs::Error,
+    pub file_attr: Option<file::Attr>,
+}
+
+/// [`super::acl::Acl::get_acl`] arguments.
+pub struct Args {
+    /// File handle of the object whose ACLs are to be retrieved.
+    pub file: file::Handle,
+    /// Lists requested by the client.
+    pub mask: Mask,
+}
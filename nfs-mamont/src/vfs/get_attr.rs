@@ -1,5 +1,7 @@
 //! Defines NFSv3 [`GetAttr`] interface.
 
+use std::future::Future;
+
 use crate::vfs;
 
 use super::file;
@@ -25,4 +27,25 @@ pub struct Args {
 pub trait GetAttr {
     /// Retrieves the attributes for a specified file system object.
     async fn get_attr(&self, args: Args) -> Result<Success, Fail>;
+
+    /// Retrieves the attributes of each of `files`, in order, with a result per handle.
+    ///
+    /// The default implementation calls [`GetAttr::get_attr`] for every handle in turn;
+    /// backends able to batch lookups, e.g. in a single burst of `statx` calls, should
+    /// override it.
+    fn get_attrs(
+        &self,
+        files: &[file::Handle],
+    ) -> impl Future<Output = Vec<Result<Success, Fail>>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut results = Vec::with_capacity(files.len());
+            for file in files {
+                results.push(self.get_attr(Args { file: file.clone() }).await);
+            }
+            results
+        }
+    }
 }
@@ -35,5 +35,5 @@ pub trait MkDir {
     /// Creates a new subdirectory.
     ///
     /// Returns [`vfs::Error::Exist`] for "." or ".." `name`.
-    async fn mk_dir(&self, args: Args) -> Result<Success, Fail>;
+    async fn mk_dir(&self, cred: &vfs::Credentials, args: Args) -> Result<Success, Fail>;
 }
@@ -5,11 +5,16 @@ use num_derive::{FromPrimitive, ToPrimitive};
 use crate::allocator::Buffer;
 
 pub mod access;
+pub mod acl;
+pub mod allocate;
 pub mod commit;
 pub mod create;
+pub mod credentials;
+pub mod export_set;
 pub mod file;
 pub mod fs_info;
 pub mod fs_stat;
+pub mod get_acl;
 pub mod get_attr;
 pub mod link;
 pub mod lookup;
@@ -23,9 +28,13 @@ pub mod read_link;
 pub mod remove;
 pub mod rename;
 pub mod rm_dir;
+pub mod set_acl;
 pub mod set_attr;
 pub mod symlink;
 pub mod write;
+pub mod xattr;
+
+pub use credentials::{Credentials, IdMapPolicy};
 
 /// Maximum length of name passed into [`Vfs`] methods.
 pub const MAX_NAME_LEN: usize = 255;
@@ -124,12 +133,83 @@ pub enum Error {
     /// supported by the [`crate::vfs`] implementation.
     BadType = 10007,
     /// The server initiated the request, but was not able to
-    /// complete it in a timely fashion. The client should wait
-    /// and then try the request with a new RPC transaction ID.
-    /// to process a file that has been migrated. In this case,
-    /// the server should start the immigration process and
-    /// respond to client with this error.
-    JUKEBOX = 10008,
+    /// complete it in a timely fashion, e.g. because the data is
+    /// being fetched from cold storage. Asks the client to retry:
+    /// it should wait and then try the request again with a new
+    /// RPC transaction ID. Any procedure may return this error.
+    Jukebox = 10008,
+}
+
+impl From<&std::io::Error> for Error {
+    /// Translates an I/O error of a local file system into an NFS error.
+    ///
+    /// Errors carrying an OS error code are mapped by errno, so no detail is lost to the
+    /// coarser [`std::io::ErrorKind`]. Errors synthesized from a kind alone fall back to
+    /// the kind. Anything without an NFS counterpart becomes [`Error::ServerFault`].
+    fn from(error: &std::io::Error) -> Self {
+        match error.raw_os_error() {
+            Some(errno) => Error::from_errno(errno),
+            None => Error::from_kind(error.kind()),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::from(&error)
+    }
+}
+
+impl Error {
+    fn from_errno(errno: i32) -> Self {
+        match errno {
+            libc::EPERM => Error::Permission,
+            libc::ENOENT => Error::NoEntry,
+            libc::EIO => Error::IO,
+            libc::ENXIO => Error::NXIO,
+            libc::EACCES => Error::Access,
+            // A busy target, e.g. a mount point, cannot be replaced or removed by the client.
+            libc::EBUSY => Error::Access,
+            libc::EEXIST => Error::Exist,
+            libc::EXDEV => Error::XDev,
+            libc::ENODEV => Error::NoDev,
+            libc::ENOTDIR => Error::NotDir,
+            libc::EISDIR => Error::IsDir,
+            libc::EINVAL => Error::InvalidArgument,
+            libc::EFBIG => Error::FileTooLarge,
+            libc::ENOSPC => Error::NoSpace,
+            libc::EROFS => Error::ReadOnlyFs,
+            libc::EMLINK => Error::TooManyLinks,
+            libc::ENAMETOOLONG => Error::NameTooLong,
+            libc::ENOTEMPTY => Error::NotEmpty,
+            libc::EDQUOT => Error::QuotaExceeded,
+            libc::ESTALE => Error::StaleFile,
+            // NFSv3 has no code for symlink loops; "too many levels" is the closest.
+            libc::EREMOTE | libc::ELOOP => Error::TooManyLevelsOfRemote,
+            libc::EOPNOTSUPP | libc::ENOSYS => Error::NotSupported,
+            libc::EAGAIN | libc::EINTR => Error::Jukebox,
+            _ => Error::ServerFault,
+        }
+    }
+
+    fn from_kind(kind: std::io::ErrorKind) -> Self {
+        use std::io::ErrorKind;
+
+        match kind {
+            ErrorKind::NotFound => Error::NoEntry,
+            ErrorKind::PermissionDenied => Error::Access,
+            ErrorKind::AlreadyExists => Error::Exist,
+            ErrorKind::InvalidInput | ErrorKind::InvalidData => Error::InvalidArgument,
+            ErrorKind::DirectoryNotEmpty => Error::NotEmpty,
+            ErrorKind::IsADirectory => Error::IsDir,
+            ErrorKind::NotADirectory => Error::NotDir,
+            ErrorKind::WriteZero => Error::NoSpace,
+            ErrorKind::Unsupported => Error::NotSupported,
+            ErrorKind::WouldBlock | ErrorKind::Interrupted | ErrorKind::TimedOut => Error::Jukebox,
+            ErrorKind::UnexpectedEof => Error::IO,
+            _ => Error::ServerFault,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -172,6 +252,7 @@ pub trait Vfs<B: Buffer>:
     + fs_info::FsInfo
     + path_conf::PathConf
     + commit::Commit
+    + acl::Acl
 {
 }
 
@@ -198,11 +279,12 @@ where
         + fs_stat::FsStat
         + fs_info::FsInfo
         + path_conf::PathConf
-        + commit::Commit,
+        + commit::Commit
+        + acl::Acl,
 {
 }
 
-/// Wrapper for all supported NFSv3 procedure result types coming from [`Vfs`].
+/// Wrapper for all supported NFSv3 and NFSACL procedure result types coming from [`Vfs`].
 pub enum NfsRes<B: Buffer> {
     Null,
     GetAttr(std::result::Result<get_attr::Success, get_attr::Fail>),
@@ -226,4 +308,21 @@ pub enum NfsRes<B: Buffer> {
     FsInfo(std::result::Result<fs_info::Success, fs_info::Fail>),
     PathConf(std::result::Result<path_conf::Success, path_conf::Fail>),
     Commit(std::result::Result<commit::Success, commit::Fail>),
+    GetAcl(std::result::Result<get_acl::Success, get_acl::Fail>),
+    SetAcl(std::result::Result<set_acl::Success, set_acl::Fail>),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::Error;
+
+    #[test]
+    fn io_error_converts_by_errno_or_kind() {
+        assert_eq!(Error::from(io::Error::from(io::ErrorKind::NotFound)), Error::NoEntry);
+        assert_eq!(Error::from(io::Error::from_raw_os_error(libc::ENOENT)), Error::NoEntry);
+        assert_eq!(Error::from(io::Error::from_raw_os_error(libc::EPERM)), Error::Permission);
+        assert_eq!(Error::from(io::Error::other("unmapped")), Error::ServerFault);
+    }
 }
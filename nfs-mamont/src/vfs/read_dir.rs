@@ -6,6 +6,9 @@ use crate::vfs;
 use super::file;
 
 /// Identifies a point in the directory.
+///
+/// Cookie `0` asks for a listing from the start, so no entry may carry it: resuming
+/// after such an entry would list the directory again.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Cookie(u64);
 
@@ -8,8 +8,9 @@ use super::file;
 pub struct Success {
     /// The post-operation attributes for the symbolic link.
     pub symlink_attr: Option<file::Attr>,
-    /// The data associated with the symbolic link.
-    pub data: file::Path,
+    /// The data associated with the symbolic link: its target as raw bytes, which need
+    /// not be UTF-8, of at most [`vfs::MAX_PATH_LEN`] bytes.
+    pub data: Vec<u8>,
 }
 
 /// Fail result.
@@ -60,5 +60,5 @@ pub trait Rename {
     ///
     /// If arguments pairs refer to the same file (they might be hard links of each other), then
     /// [`Rename::rename`] should perform no action and return [`Success`].
-    async fn rename(&self, args: Args) -> Result<Success, Fail>;
+    async fn rename(&self, cred: &vfs::Credentials, args: Args) -> Result<Success, Fail>;
 }
@@ -0,0 +1,30 @@
+//! Defines NFSACL `SETACL` arguments and results of [`super::acl::Acl::set_acl`].
+
+use crate::vfs;
+
+use super::acl::{Entry, Mask};
+use super::file;
+
+/// Success result.
+pub struct Success {
+    pub file_attr: Option<file::Attr>,
+}
+
+/// Fail result.
+pub struct Fail {
+    /// Error on failure.
+    pub error: vfs::Error,
+    pub file_attr: Option<file::Attr>,
+}
+
+/// [`super::acl::Acl::set_acl`] arguments.
+pub struct Args {
+    /// File handle of the object whose ACLs are to be replaced.
+    pub file: file::Handle,
+    /// Lists to replace; lists not selected are left untouched.
+    pub mask: Mask,
+    /// New access ACL.
+    pub access: Vec<Entry>,
+    /// New default ACL; an empty list removes it.
+    pub default: Vec<Entry>,
+}
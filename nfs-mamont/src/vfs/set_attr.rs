@@ -38,9 +38,25 @@ pub struct Args {
     pub guard: Option<Guard>,
 }
 
+/// Requested attributes left unchanged by a successful [`SetAttr::set_attr`].
+///
+/// A server may keep a requested value from taking effect without failing the call,
+/// for example by mapping the owner requested by a squashed client to the anonymous
+/// user. The flags are for the server's own logging and tests; NFSv3 has no way to
+/// tell the client, which sees the outcome in [`Success::wcc_data`] only.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ignored {
+    pub mode: bool,
+    pub uid: bool,
+    pub gid: bool,
+}
+
 /// Success result.
 pub struct Success {
+    /// Attributes before the call and, read back after it, as the object has them now.
     pub wcc_data: vfs::WccData,
+    /// Requested changes which did not take effect.
+    pub ignored: Ignored,
 }
 
 /// Fail result.
@@ -70,12 +86,17 @@ pub trait SetAttr {
     /// extending the file size.
     ///
     /// Changing the size of a file with [`SetAttr::set_attr`] indirectly
-    /// changes the `mtime`.
+    /// changes the `mtime`. Only regular files have a size to change: a request setting
+    /// it on a directory should fail with [`vfs::Error::IsDir`], and on any other
+    /// non-regular file with [`vfs::Error::InvalidArgument`], before anything is changed.
     ///
     /// [`vfs::Error::InvalidArgument`] may be returned
     /// - if implementation can not store a uid or gid in its own representation
     /// - if implementation can only support 32 bit offset and sizes,
     ///   and [`SetAttr::set_attr`] request to set the size of a file to larger than
     ///   can be represented in 32 bit.
-    async fn set_attr(&self, args: Args) -> Result<Success, Fail>;
+    ///
+    /// `cred` identifies the caller; implementation should return [`vfs::Error::Permission`]
+    /// if the caller is not allowed to change the owner or group of the object.
+    async fn set_attr(&self, cred: &vfs::Credentials, args: Args) -> Result<Success, Fail>;
 }
@@ -42,5 +42,5 @@ pub trait Symlink {
     /// created in a single atomic operation. That is, once the symbolic link is visible,
     /// there must not be a window where a [`super::read_link::ReadLink::read_link`] would fail or
     /// return incorrect data.
-    async fn symlink(&self, args: Args) -> Result<Success, Fail>;
+    async fn symlink(&self, cred: &vfs::Credentials, args: Args) -> Result<Success, Fail>;
 }
@@ -96,5 +96,8 @@ pub trait Write<B: Buffer> {
     ///
     /// If the `file` system object type was not a [`file::Type::Regular`] file,
     /// [`vfs::Error::InvalidArgument`] is returned.
-    async fn write(&self, args: Args<B>) -> Result<Success, Fail>;
+    ///
+    /// `cred` identifies the caller; implementation should return [`vfs::Error::Access`]
+    /// if the caller has no write permission on the file.
+    async fn write(&self, cred: &vfs::Credentials, args: Args<B>) -> Result<Success, Fail>;
 }
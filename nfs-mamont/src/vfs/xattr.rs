@@ -0,0 +1,50 @@
+//! Defines [`VfsXattr`] extension interface for extended attributes.
+//!
+//! Extended attributes are not part of NFSv3 and are not served over the wire; the
+//! trait lets applications embedding the server reach them through the same backend
+//! that serves NFS requests.
+
+use super::{file, Credentials, Error};
+
+/// Maximum length of an extended attribute name, including its namespace prefix.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Maximum size of an extended attribute value.
+pub const MAX_VALUE_LEN: usize = 64 * 1024;
+
+#[trait_variant::make(Send)]
+pub trait VfsXattr {
+    /// Returns the value of attribute `name` of `file` read on behalf of `cred`, or
+    /// `None` if it has no such attribute.
+    async fn get_xattr(
+        &self,
+        cred: &Credentials,
+        file: &file::Handle,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Creates or replaces attribute `name` of `file` on behalf of `cred`.
+    async fn set_xattr(
+        &self,
+        cred: &Credentials,
+        file: &file::Handle,
+        name: &str,
+        value: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Returns names of the attributes of `file` visible to `cred`.
+    async fn list_xattr(
+        &self,
+        cred: &Credentials,
+        file: &file::Handle,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Removes attribute `name` of `file` on behalf of `cred`; fails with
+    /// [`Error::NoEntry`] if there is no such attribute.
+    async fn remove_xattr(
+        &self,
+        cred: &Credentials,
+        file: &file::Handle,
+        name: &str,
+    ) -> Result<(), Error>;
+}